@@ -0,0 +1,65 @@
+//! `coil`, tools for working on games made with `coil_engine`.
+//!
+//! `coil view <file>` shows a palette, sprite or scene file as the engine
+//! draws it, reloading it as it is edited. `coil replay <file>` steps
+//! through the inputs of a replay file; to see the state of the game at each
+//! tick, run the game's own [`ReplayInspector`] built `with_game`.
+//!
+//! [`ReplayInspector`]: coil_engine::nodes::ReplayInspector
+use clap::{Arg, Command};
+use coil_engine::Game;
+use coil_engine::nodes::ReplayInspector;
+use coil_engine::replay::Replay;
+use std::path::PathBuf;
+
+mod view;
+
+fn command() -> Command {
+    Command::new("coil")
+        .about("Tools for games made with coil_engine")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("view")
+                .about("Preview a palette, sprite or scene file")
+                .arg(Arg::new("file").value_name("FILE").required(true))
+                .arg(
+                    Arg::new("palette")
+                        .long("palette")
+                        .value_name("FILE")
+                        .help("Draw the file with the palette in FILE"),
+                ),
+        )
+        .subcommand(
+            Command::new("replay")
+                .about("Step through the inputs of a replay file")
+                .arg(Arg::new("file").value_name("FILE").required(true)),
+        )
+}
+
+fn main() {
+    let matches = command().get_matches();
+    match matches.subcommand() {
+        Some(("view", matches)) => {
+            let file = matches.get_one::<String>("file").expect("required");
+            let palette = matches.get_one::<String>("palette").map(PathBuf::from);
+            match view::Viewer::open(file, palette) {
+                Ok(viewer) => Game::new(viewer).start(),
+                Err(e) => {
+                    eprintln!("error: {}: {}", file, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(("replay", matches)) => {
+            let file = matches.get_one::<String>("file").expect("required");
+            match Replay::load(file) {
+                Ok(replay) => Game::new(ReplayInspector::new(replay)).start(),
+                Err(e) => {
+                    eprintln!("error: {}: {}", file, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => unreachable!("subcommand_required"),
+    }
+}
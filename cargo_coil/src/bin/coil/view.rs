@@ -0,0 +1,251 @@
+use coil_engine::crossterm::event::{Event, KeyCode, KeyEventKind};
+use coil_engine::style::Style;
+use coil_engine::tilemap::{self, Tile, TileMap};
+use coil_engine::{Context, EngineError, Frame, Node, style};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Seconds between checks for changed files
+const RELOAD_INTERVAL: f32 = 0.5;
+const MAX_ZOOM: u16 = 8;
+/// Extension of palette files
+const PALETTE: &str = "palette";
+
+/// What the viewed file holds.
+enum Content {
+    Palette(Vec<Tile>),
+    Map(TileMap),
+}
+
+/// Shows a palette, or a sprite or scene drawn as text art, as the game
+/// would draw it, and reloads it whenever it changes on disk.
+///
+/// Art is drawn as a [`TileMap`] with the palette given, or else the
+/// `.palette` file next to it; glyphs the palette lacks are drawn unstyled.
+pub struct Viewer {
+    path: PathBuf,
+    palette_path: Option<PathBuf>,
+    content: Content,
+    /// Modification time and length of each file when last loaded
+    stamps: Vec<Option<(SystemTime, u64)>>,
+    since_check: f32,
+    /// Why the last reload failed, while the previous content is shown
+    error: Option<String>,
+    zoom: u16,
+    /// Tile shown in the top-left corner
+    pan: (u16, u16),
+}
+
+impl Viewer {
+    pub fn open(path: impl Into<PathBuf>, palette: Option<PathBuf>) -> Result<Self, EngineError> {
+        let path = path.into();
+        let palette_path = if is_palette(&path) {
+            None
+        } else {
+            palette.or_else(|| Some(path.with_extension(PALETTE)).filter(|p| p.exists()))
+        };
+        let mut viewer = Self {
+            content: Content::Palette(Vec::new()),
+            stamps: Vec::new(),
+            since_check: 0.0,
+            error: None,
+            zoom: 1,
+            pan: (0, 0),
+            path,
+            palette_path,
+        };
+        viewer.stamps = viewer.stamps();
+        viewer.content = viewer.load()?;
+        Ok(viewer)
+    }
+
+    /// Reloads the files if any changed since they were loaded, returning
+    /// whether they did. A file that fails to load keeps the previous
+    /// content on screen along with the error.
+    pub fn reload_if_changed(&mut self) -> bool {
+        let stamps = self.stamps();
+        if stamps == self.stamps {
+            return false;
+        }
+        self.stamps = stamps;
+        match self.load() {
+            Ok(content) => {
+                self.content = content;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+        true
+    }
+
+    fn files(&self) -> impl Iterator<Item = &Path> {
+        std::iter::once(self.path.as_path()).chain(self.palette_path.as_deref())
+    }
+
+    fn stamps(&self) -> Vec<Option<(SystemTime, u64)>> {
+        self.files()
+            .map(|path| {
+                let metadata = fs::metadata(path).ok()?;
+                Some((metadata.modified().ok()?, metadata.len()))
+            })
+            .collect()
+    }
+
+    fn load(&self) -> Result<Content, EngineError> {
+        if is_palette(&self.path) {
+            return tilemap::load_palette(&self.path).map(Content::Palette);
+        }
+        let art = fs::read_to_string(&self.path)?;
+        let mut palette = match &self.palette_path {
+            Some(path) => tilemap::load_palette(path)?,
+            None => Vec::new(),
+        };
+        for ch in art.chars().filter(|&ch| ch != '\n' && ch != '\r') {
+            if !palette.iter().any(|tile| tile.ch == ch) {
+                palette.push(Tile::new(ch, Style::new()));
+            }
+        }
+        if palette.is_empty() {
+            palette.push(Tile::new(' ', Style::new()));
+        }
+        TileMap::parse(&art, palette).map(Content::Map)
+    }
+
+    /// Returns the tiles to draw, as rows of (glyph, style).
+    fn rows(&self) -> Vec<Vec<Tile>> {
+        match &self.content {
+            Content::Map(map) => (0..map.height())
+                .map(|y| (0..map.width()).filter_map(|x| map.tile(x, y)).collect())
+                .collect(),
+            // Each tile of a palette is shown as a swatch
+            Content::Palette(palette) => palette.iter().map(|&tile| vec![tile; 4]).collect(),
+        }
+    }
+
+    fn status(&self) -> String {
+        let name = self.path.file_name().unwrap_or_default().to_string_lossy();
+        let size = match &self.content {
+            Content::Map(map) => format!("{}x{}", map.width(), map.height()),
+            Content::Palette(palette) => format!("{} tiles", palette.len()),
+        };
+        format!(
+            "{}  {}  zoom {}x  +/- zoom  arrows pan  q quit",
+            name, size, self.zoom
+        )
+    }
+}
+
+fn is_palette(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == PALETTE)
+}
+
+impl Node for Viewer {
+    fn update(&mut self, _ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        self.since_check += dt;
+        if self.since_check >= RELOAD_INTERVAL {
+            self.since_check = 0.0;
+            self.reload_if_changed();
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        let Event::Key(key) = ev else {
+            return Ok(false);
+        };
+        if key.kind == KeyEventKind::Release {
+            return Ok(false);
+        }
+        let (x, y) = self.pan;
+        match key.code {
+            KeyCode::Char('+') | KeyCode::Char('=') => self.zoom = (self.zoom + 1).min(MAX_ZOOM),
+            KeyCode::Char('-') => self.zoom = (self.zoom - 1).max(1),
+            KeyCode::Left | KeyCode::Char('h') => self.pan = (x.saturating_sub(1), y),
+            KeyCode::Right | KeyCode::Char('l') => self.pan = (x + 1, y),
+            KeyCode::Up | KeyCode::Char('k') => self.pan = (x, y.saturating_sub(1)),
+            KeyCode::Down | KeyCode::Char('j') => self.pan = (x, y + 1),
+            KeyCode::Char('0') => (self.zoom, self.pan) = (1, (0, 0)),
+            KeyCode::Char('q') | KeyCode::Esc => ctx.request_exit(0),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        let (width, height) = frame.size();
+        let area = height.saturating_sub(1);
+        let zoom = self.zoom as usize;
+        let mut glyph = [0; 4];
+        let rows = self.rows();
+        for (y, row) in rows.iter().skip(self.pan.1 as usize).enumerate() {
+            for (x, tile) in row.iter().skip(self.pan.0 as usize).enumerate() {
+                let text = tile.ch.encode_utf8(&mut glyph).repeat(zoom);
+                for dy in 0..zoom {
+                    let row = (y * zoom + dy) as u16;
+                    if row < area && ((x * zoom) as u16) < width {
+                        frame.draw_styled((x * zoom) as u16, row, &text, tile.style)?;
+                    }
+                }
+            }
+        }
+        let (status, style) = match &self.error {
+            Some(error) => (error.clone(), style!(white on dark_red)),
+            None => (self.status(), Style::new().reverse()),
+        };
+        let line = format!("{:1$}", status, width as usize);
+        frame.draw_styled(0, area, &line, style)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coil_engine::config::GameConfig;
+    use coil_engine::crossterm::event::{KeyEvent, KeyModifiers};
+    use coil_engine::renderer::TestRenderer;
+
+    #[test]
+    fn test_view_zoom_and_reload() {
+        let dir = std::env::temp_dir().join(format!("coil-view-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let art = dir.join("ship.txt");
+        fs::write(&art, "#.\n.#\n").unwrap();
+        fs::write(dir.join("ship.palette"), "# = red bold\n").unwrap();
+
+        let mut viewer = Viewer::open(&art, None).unwrap();
+        let mut ctx = Context::new(GameConfig::new());
+        let mut renderer = TestRenderer::new(40, 5);
+        renderer.render(&viewer).unwrap();
+        assert_eq!(renderer.rows()[..2], ["#.", ".#"]);
+        assert!(renderer.row(4).starts_with("ship.txt  2x2  zoom 1x"));
+
+        let press = |viewer: &mut Viewer, ctx: &mut Context, code| {
+            let ev = Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+            viewer.on_event(ctx, &ev).unwrap()
+        };
+        press(&mut viewer, &mut ctx, KeyCode::Char('+'));
+        press(&mut viewer, &mut ctx, KeyCode::Right);
+        renderer.render(&viewer).unwrap();
+        assert_eq!(renderer.rows()[..4], ["..", "..", "##", "##"]);
+
+        // A broken palette keeps the last good art on screen
+        assert!(!viewer.reload_if_changed());
+        fs::write(dir.join("ship.palette"), "# = shiny\n").unwrap();
+        assert!(viewer.reload_if_changed());
+        assert!(viewer.error.is_some());
+        renderer.render(&viewer).unwrap();
+        assert_eq!(renderer.row(2), "##");
+        fs::write(dir.join("ship.palette"), "# = blue\n").unwrap();
+        fs::write(&art, "..#\n").unwrap();
+        assert!(viewer.reload_if_changed());
+        assert_eq!(viewer.error, None);
+        renderer.render(&viewer).unwrap();
+        assert_eq!(renderer.rows()[..2], ["..##", "..##"]);
+
+        press(&mut viewer, &mut ctx, KeyCode::Char('q'));
+        assert_eq!(ctx.exit_requested(), Some(0));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
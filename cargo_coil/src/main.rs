@@ -0,0 +1,224 @@
+//! `cargo coil`, companion tooling for games made with `coil_engine`.
+//!
+//! `cargo coil new <name>` creates a game crate ready to run: a title screen
+//! leading to a starter scene, a config file, an assets directory and tests
+//! driving the scene without a terminal.
+use clap::{Arg, ArgMatches, Command};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The engine next to this tool, which new games depend on when it exists
+const ENGINE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../coil_engine");
+/// The published engine new games depend on otherwise
+const ENGINE_VERSION: &str = "0.1";
+
+/// Files of a new game, relative to its directory, and their templates
+const TEMPLATES: &[(&str, &str)] = &[
+    ("Cargo.toml", include_str!("../templates/Cargo.toml.in")),
+    (".gitignore", include_str!("../templates/gitignore")),
+    ("config.txt", include_str!("../templates/config.txt")),
+    ("assets/level.txt", include_str!("../templates/level.txt")),
+    ("src/main.rs", include_str!("../templates/main.rs.in")),
+    ("src/lib.rs", include_str!("../templates/lib.rs.in")),
+    ("src/play.rs", include_str!("../templates/play.rs.in")),
+    (
+        "tests/common/mod.rs",
+        include_str!("../templates/common.rs.in"),
+    ),
+    (
+        "tests/play.rs",
+        include_str!("../templates/play_test.rs.in"),
+    ),
+];
+
+#[derive(Debug)]
+enum Error {
+    InvalidName(String),
+    Exists(PathBuf),
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidName(name) => write!(
+                f,
+                "`{}` is not a valid crate name: use letters, digits, `-` and `_`, \
+                 starting with a letter",
+                name
+            ),
+            Error::Exists(path) => write!(f, "`{}` already exists", path.display()),
+            Error::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// What a new game is called and built against.
+#[derive(Debug, Clone, PartialEq)]
+struct Project {
+    /// Package name, e.g. `space-race`
+    name: String,
+    /// Dependency on the engine, as written in `Cargo.toml`
+    engine: String,
+}
+
+impl Project {
+    fn new(name: &str, engine_path: Option<&Path>) -> Result<Self, Error> {
+        let valid = name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(Error::InvalidName(name.to_string()));
+        }
+        let engine_path = match engine_path {
+            Some(path) => Some(path.to_path_buf()),
+            None => Path::new(ENGINE_DIR).canonicalize().ok(),
+        };
+        let engine = match engine_path {
+            Some(path) => format!(
+                "{{ path = {:?}, features = [\"cli\"] }}",
+                path.display().to_string()
+            ),
+            None => format!(
+                "{{ version = \"{}\", features = [\"cli\"] }}",
+                ENGINE_VERSION
+            ),
+        };
+        Ok(Self {
+            name: name.to_string(),
+            engine,
+        })
+    }
+
+    /// Returns the name used in Rust code, e.g. `space_race`.
+    fn crate_name(&self) -> String {
+        self.name.replace('-', "_")
+    }
+
+    /// Returns the name shown on the title screen, e.g. `Space Race`.
+    fn title(&self) -> String {
+        self.name
+            .split(['-', '_'])
+            .filter(|word| !word.is_empty())
+            .map(|word| {
+                let mut chars = word.chars();
+                let first = chars.next().map(|c| c.to_ascii_uppercase());
+                first.into_iter().chain(chars).collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn fill(&self, template: &str) -> String {
+        template
+            .replace("{{name}}", &self.name)
+            .replace("{{crate}}", &self.crate_name())
+            .replace("{{title}}", &self.title())
+            .replace("{{engine}}", &self.engine)
+    }
+
+    /// Writes the game's files into `dir`, which must not exist yet.
+    fn generate(&self, dir: &Path) -> Result<(), Error> {
+        if dir.exists() {
+            return Err(Error::Exists(dir.to_path_buf()));
+        }
+        for (path, template) in TEMPLATES {
+            let path = dir.join(path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, self.fill(template))?;
+        }
+        Ok(())
+    }
+}
+
+fn command() -> Command {
+    // Cargo runs `cargo-coil coil <args>` for `cargo coil <args>`
+    Command::new("cargo")
+        .bin_name("cargo")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("coil")
+                .about("Tooling for games made with coil_engine")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("new")
+                        .about("Create a new game")
+                        .arg(Arg::new("name").value_name("NAME").required(true))
+                        .arg(
+                            Arg::new("engine_path")
+                                .long("engine-path")
+                                .value_name("PATH")
+                                .help("Depend on the engine at PATH"),
+                        ),
+                ),
+        )
+}
+
+fn new(matches: &ArgMatches) -> Result<(), Error> {
+    let name = matches.get_one::<String>("name").expect("required");
+    let engine_path = matches.get_one::<String>("engine_path").map(Path::new);
+    let project = Project::new(name, engine_path)?;
+    project.generate(Path::new(name))?;
+    println!("Created game `{}`. Run it with:", name);
+    println!();
+    println!("    cd {} && cargo run", name);
+    Ok(())
+}
+
+fn main() {
+    let matches = command().get_matches();
+    let result = match matches.subcommand() {
+        Some(("coil", coil)) => match coil.subcommand() {
+            Some(("new", matches)) => new(matches),
+            _ => unreachable!("subcommand_required"),
+        },
+        _ => unreachable!("subcommand_required"),
+    };
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coil_engine::config::GameConfig;
+
+    #[test]
+    fn test_new_game_files() {
+        assert!(matches!(
+            Project::new("2048", None),
+            Err(Error::InvalidName(_))
+        ));
+        let project = Project::new("space-race", Some(Path::new("/engine"))).unwrap();
+        assert_eq!(project.crate_name(), "space_race");
+        assert_eq!(project.title(), "Space Race");
+
+        let dir = std::env::temp_dir().join(format!("coil-new-{}", std::process::id()));
+        project.generate(&dir).unwrap();
+        let read = |path: &str| fs::read_to_string(dir.join(path)).unwrap();
+        assert!(read("Cargo.toml").contains("name = \"space-race\""));
+        assert!(read("Cargo.toml").contains("coil_engine = { path = \"/engine\""));
+        assert!(read("src/main.rs").contains("use space_race::play::Play;"));
+        assert!(read("tests/play.rs").contains("use space_race::play::Play;"));
+        assert!(!read("src/main.rs").contains("{{"));
+        let config = GameConfig::new().merge_str(&read("config.txt")).unwrap();
+        assert_eq!(config.app_name.as_deref(), Some("space-race"));
+        assert_eq!(config.target_fps, 30);
+
+        assert!(matches!(project.generate(&dir), Err(Error::Exists(_))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,457 @@
+//! Derive macros for `coil_engine`.
+//!
+//! Enabled through the engine's `derive` feature and re-exported as
+//! `coil_engine::nodes::Node`.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Expr, Fields, Ident, LitInt, Member, parse_macro_input};
+
+/// Derives `Node` for a struct by delegating to its child nodes.
+///
+/// Fields marked `#[node(child)]` receive every call, in declaration order
+/// unless reordered:
+///
+/// - `event_priority = N`: children with a higher priority see input events
+///   first (default `0`). A child returning `true` stops the dispatch.
+/// - `render_order = N`: children with a higher order are drawn later, on top
+///   (default `0`).
+///
+/// Children are updated every tick unless one field, of type
+/// `Vec<UpdateSchedule>`, is marked `#[node(schedules)]`; the children are
+/// then updated at their `update_interval`, with the field keeping track of
+/// when each is due. It can start out empty.
+///
+/// The struct's own logic is supplied with optional container attributes
+/// naming methods with the same signatures as the `Node` methods:
+/// `#[node(update = Self::tick, on_event = Self::handle, render = Self::draw)]`,
+/// plus `on_engine_event`, `on_resize` and `on_save`. The own `update`,
+/// `on_event`, `on_engine_event`, `on_resize` and `on_save` run after the
+/// children; the own `render` runs first, so children are drawn over it.
+///
+/// The node is dirty while any child is, and `mark_clean` and
+/// `world_bounds` cover the children too. A struct with its own `render`
+/// is always dirty and unbounded unless it also names `is_dirty` and
+/// `world_bounds` methods for its own drawing, and a `mark_clean` one if
+/// it keeps track.
+///
+/// ```
+/// use coil_engine::nodes::{Node, PauseOverlay, Toasts};
+///
+/// #[derive(Node)]
+/// struct Game {
+///     #[node(child, render_order = 1)]
+///     pause: PauseOverlay,
+///     #[node(child, event_priority = 1)]
+///     toasts: Toasts,
+/// }
+/// ```
+///
+/// Only structs can derive `Node`, and unknown attributes are rejected:
+///
+/// ```compile_fail
+/// use coil_engine::nodes::Node;
+///
+/// #[derive(Node)]
+/// enum Screen {
+///     Title,
+///     Playing,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use coil_engine::nodes::{Node, Toasts};
+///
+/// #[derive(Node)]
+/// struct Game {
+///     #[node(child, priority = 1)]
+///     toasts: Toasts,
+/// }
+/// ```
+#[proc_macro_derive(Node, attributes(node))]
+pub fn derive_node(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct Child {
+    member: Member,
+    event_priority: i64,
+    render_order: i64,
+}
+
+#[derive(Default)]
+struct Hooks {
+    update: Option<Expr>,
+    on_event: Option<Expr>,
+    on_engine_event: Option<Expr>,
+    on_resize: Option<Expr>,
+    on_save: Option<Expr>,
+    is_dirty: Option<Expr>,
+    mark_clean: Option<Expr>,
+    world_bounds: Option<Expr>,
+    render: Option<Expr>,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let hooks = parse_hooks(&input)?;
+    let (children, schedules) = parse_children(&input)?;
+
+    let name = &input.ident;
+    let krate = quote! { ::coil_engine };
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let update_children = children.iter().map(|c| &c.member);
+    let update_children = match &schedules {
+        Some(schedules) => {
+            let count = children.len();
+            let index = (0..count).map(proc_macro2::Literal::usize_unsuffixed);
+            quote! {
+                let schedules = &mut self.#schedules;
+                schedules.resize_with(#count, ::std::default::Default::default);
+                #(
+                    #krate::nodes::UpdateSchedule::tick(
+                        &mut schedules[#index],
+                        &mut self.#update_children,
+                        ctx,
+                        dt,
+                    )?;
+                )*
+            }
+        }
+        None => quote! {
+            #( #krate::nodes::Node::update(&mut self.#update_children, ctx, dt)?; )*
+        },
+    };
+    let update_self = hooks.update.iter();
+
+    let mut by_priority: Vec<&Child> = children.iter().collect();
+    by_priority.sort_by_key(|c| std::cmp::Reverse(c.event_priority));
+    let event_children = by_priority.iter().map(|c| &c.member);
+    let event_self = match &hooks.on_event {
+        Some(hook) => quote! { #hook(self, ctx, ev) },
+        None => quote! { ::std::result::Result::Ok(false) },
+    };
+
+    let mut by_order: Vec<&Child> = children.iter().collect();
+    by_order.sort_by_key(|c| c.render_order);
+    let render_children = by_order.iter().map(|c| &c.member);
+    let render_self = hooks.render.iter();
+
+    let engine_children = children.iter().map(|c| &c.member);
+    let engine_self = hooks.on_engine_event.iter();
+    let error_children = children.iter().map(|c| &c.member);
+    let pause_children = children.iter().map(|c| &c.member);
+    let save_children = children.iter().map(|c| &c.member);
+    let save_self = hooks.on_save.iter();
+    let resume_children = children.iter().map(|c| &c.member);
+    let resize_children = children.iter().map(|c| &c.member);
+    let resize_self = hooks.on_resize.iter();
+    let dirty_children = children.iter().map(|c| &c.member);
+    let dirty_self = match (&hooks.is_dirty, &hooks.render) {
+        (Some(hook), _) => quote! { #hook(self) },
+        (None, Some(_)) => quote! { true },
+        (None, None) => quote! { false },
+    };
+    let clean_children = children.iter().map(|c| &c.member);
+    let clean_self = hooks.mark_clean.iter();
+    let bounds_children = children.iter().map(|c| &c.member);
+    let bounds_self = match (&hooks.world_bounds, &hooks.render) {
+        (Some(hook), _) => Some(quote! { #hook(self) }),
+        (None, Some(_)) => Some(quote! { ::std::option::Option::None }),
+        (None, None) => None,
+    };
+    let bounds_self = bounds_self.iter();
+
+    Ok(quote! {
+        impl #impl_generics #krate::nodes::Node for #name #ty_generics #where_clause {
+            fn update(
+                &mut self,
+                ctx: &mut #krate::context::Context,
+                dt: f32,
+            ) -> ::std::result::Result<(), #krate::errors::EngineError> {
+                #update_children
+                #( #update_self(self, ctx, dt)?; )*
+                ::std::result::Result::Ok(())
+            }
+
+            fn on_event(
+                &mut self,
+                ctx: &mut #krate::context::Context,
+                ev: &#krate::crossterm::event::Event,
+            ) -> ::std::result::Result<bool, #krate::errors::EngineError> {
+                #(
+                    if #krate::nodes::Node::on_event(&mut self.#event_children, ctx, ev)? {
+                        return ::std::result::Result::Ok(true);
+                    }
+                )*
+                #event_self
+            }
+
+            fn on_engine_event(
+                &mut self,
+                ctx: &mut #krate::context::Context,
+                ev: &#krate::context::EngineEvent,
+            ) {
+                #( #krate::nodes::Node::on_engine_event(&mut self.#engine_children, ctx, ev); )*
+                #( #engine_self(self, ctx, ev); )*
+            }
+
+            fn on_pause(&mut self, ctx: &mut #krate::context::Context) {
+                #( #krate::nodes::Node::on_pause(&mut self.#pause_children, ctx); )*
+            }
+
+            fn on_resume(&mut self, ctx: &mut #krate::context::Context) {
+                #( #krate::nodes::Node::on_resume(&mut self.#resume_children, ctx); )*
+            }
+
+            fn on_resize(&mut self, ctx: &mut #krate::context::Context, width: u16, height: u16) {
+                #( #krate::nodes::Node::on_resize(&mut self.#resize_children, ctx, width, height); )*
+                #( #resize_self(self, ctx, width, height); )*
+            }
+
+            fn on_save(
+                &self,
+                save: &mut #krate::autosave::SaveData,
+            ) -> ::std::result::Result<(), #krate::errors::EngineError> {
+                #( #krate::nodes::Node::on_save(&self.#save_children, save)?; )*
+                #( #save_self(self, save)?; )*
+                ::std::result::Result::Ok(())
+            }
+
+            fn on_error(
+                &mut self,
+                ctx: &mut #krate::context::Context,
+                err: &#krate::errors::EngineError,
+            ) -> ::std::option::Option<#krate::config::ErrorPolicy> {
+                #(
+                    if let ::std::option::Option::Some(policy) =
+                        #krate::nodes::Node::on_error(&mut self.#error_children, ctx, err)
+                    {
+                        return ::std::option::Option::Some(policy);
+                    }
+                )*
+                ::std::option::Option::None
+            }
+
+            fn is_dirty(&self) -> bool {
+                #dirty_self #( || #krate::nodes::Node::is_dirty(&self.#dirty_children) )*
+            }
+
+            fn mark_clean(&mut self) {
+                #( #krate::nodes::Node::mark_clean(&mut self.#clean_children); )*
+                #( #clean_self(self); )*
+            }
+
+            fn world_bounds(&self) -> ::std::option::Option<#krate::camera::Bounds> {
+                #krate::camera::Bounds::enclosing([
+                    #( #bounds_self, )*
+                    #( #krate::nodes::Node::world_bounds(&self.#bounds_children), )*
+                ])
+            }
+
+            fn render(
+                &self,
+                frame: &mut #krate::frame::Frame<'_>,
+            ) -> ::std::result::Result<(), #krate::errors::EngineError> {
+                #( #render_self(self, frame)?; )*
+                #( #krate::nodes::Node::render(&self.#render_children, frame)?; )*
+                ::std::result::Result::Ok(())
+            }
+        }
+    })
+}
+
+fn parse_hooks(input: &DeriveInput) -> syn::Result<Hooks> {
+    let mut hooks = Hooks::default();
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("node")) {
+        attr.parse_nested_meta(|meta| {
+            let slot = if meta.path.is_ident("update") {
+                &mut hooks.update
+            } else if meta.path.is_ident("on_event") {
+                &mut hooks.on_event
+            } else if meta.path.is_ident("on_engine_event") {
+                &mut hooks.on_engine_event
+            } else if meta.path.is_ident("on_resize") {
+                &mut hooks.on_resize
+            } else if meta.path.is_ident("on_save") {
+                &mut hooks.on_save
+            } else if meta.path.is_ident("is_dirty") {
+                &mut hooks.is_dirty
+            } else if meta.path.is_ident("mark_clean") {
+                &mut hooks.mark_clean
+            } else if meta.path.is_ident("world_bounds") {
+                &mut hooks.world_bounds
+            } else if meta.path.is_ident("render") {
+                &mut hooks.render
+            } else {
+                return Err(meta.error(
+                    "expected `update`, `on_event`, `on_engine_event`, `on_resize`, `on_save`, \
+                     `is_dirty`, `mark_clean`, `world_bounds` or `render`",
+                ));
+            };
+            *slot = Some(meta.value()?.parse()?);
+            Ok(())
+        })?;
+    }
+    Ok(hooks)
+}
+
+/// Returns the child fields and the field holding their schedules, if any.
+fn parse_children(input: &DeriveInput) -> syn::Result<(Vec<Child>, Option<Member>)> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "#[derive(Node)] only supports structs",
+            ));
+        }
+    };
+
+    let mut children = Vec::new();
+    let mut schedules = None;
+    for (index, field) in fields.iter().enumerate() {
+        let member = match (&field.ident, fields) {
+            (Some(ident), _) => Member::Named(Ident::clone(ident)),
+            (None, Fields::Unnamed(_)) => Member::Unnamed(index.into()),
+            (None, _) => unreachable!("unnamed field outside a tuple struct"),
+        };
+        let mut is_child = false;
+        let mut is_schedules = false;
+        let mut event_priority = 0;
+        let mut render_order = 0;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("node")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("child") {
+                    is_child = true;
+                } else if meta.path.is_ident("event_priority") {
+                    event_priority = parse_int(&meta)?;
+                } else if meta.path.is_ident("render_order") {
+                    render_order = parse_int(&meta)?;
+                } else if meta.path.is_ident("schedules") {
+                    if schedules.is_some() {
+                        return Err(meta.error("only one field can hold the schedules"));
+                    }
+                    is_schedules = true;
+                } else {
+                    return Err(meta.error(
+                        "expected `child`, `event_priority`, `render_order` or `schedules`",
+                    ));
+                }
+                Ok(())
+            })?;
+        }
+        if is_schedules {
+            if is_child {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "the schedules field cannot be a child",
+                ));
+            }
+            schedules = Some(member);
+            continue;
+        }
+        if !is_child {
+            continue;
+        }
+        children.push(Child {
+            member,
+            event_priority,
+            render_order,
+        });
+    }
+    Ok((children, schedules))
+}
+
+fn parse_int(meta: &syn::meta::ParseNestedMeta) -> syn::Result<i64> {
+    let value = meta.value()?;
+    let negative = value.peek(syn::Token![-]);
+    if negative {
+        value.parse::<syn::Token![-]>()?;
+    }
+    let number: i64 = value.parse::<LitInt>()?.base10_parse()?;
+    Ok(if negative { -number } else { number })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    /// Returns the positions of `needles` in the expansion of `input`.
+    fn positions(input: DeriveInput, method: &str, needles: &[&str]) -> Vec<usize> {
+        let expanded = expand(input).unwrap().to_string();
+        let start = expanded.find(&format!("fn {} (", method)).unwrap();
+        let body = &expanded[start..];
+        let end = body[3..].find("fn ").map_or(body.len(), |end| end + 3);
+        needles
+            .iter()
+            .map(|needle| body[..end].find(needle).unwrap())
+            .collect()
+    }
+
+    fn error(input: DeriveInput) -> String {
+        expand(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_event_priority_orders_dispatch() {
+        let input: DeriveInput = parse_quote! {
+            struct Game {
+                #[node(child)]
+                world: World,
+                #[node(child, event_priority = 2)]
+                menu: Menu,
+                #[node(child, event_priority = -1)]
+                debug: Debug,
+            }
+        };
+        let order = positions(
+            input,
+            "on_event",
+            &["self . menu", "self . world", "self . debug"],
+        );
+        assert!(order.is_sorted(), "{:?}", order);
+    }
+
+    #[test]
+    fn test_render_order_draws_higher_orders_last() {
+        let input: DeriveInput = parse_quote! {
+            #[node(render = Self::draw)]
+            struct Game(#[node(child, render_order = 1)] Hud, #[node(child)] World);
+        };
+        let order = positions(input, "render", &["Self :: draw", "self . 1", "self . 0"]);
+        assert!(order.is_sorted(), "{:?}", order);
+    }
+
+    #[test]
+    fn test_unknown_attributes_are_rejected() {
+        let input: DeriveInput = parse_quote! {
+            struct Game {
+                #[node(child, priority = 1)]
+                menu: Menu,
+            }
+        };
+        assert!(error(input).starts_with("expected `child`, `event_priority`"));
+
+        let input: DeriveInput = parse_quote! {
+            #[node(draw = Self::draw)]
+            struct Game;
+        };
+        assert!(error(input).starts_with("expected `update`, `on_event`"));
+    }
+
+    #[test]
+    fn test_only_structs_are_supported() {
+        let input: DeriveInput = parse_quote! {
+            enum Screen {
+                Title,
+                Playing,
+            }
+        };
+        assert_eq!(error(input), "#[derive(Node)] only supports structs");
+    }
+}
@@ -0,0 +1,123 @@
+//! Measures event dispatch: events passed by reference from a reused buffer,
+//! against collecting a new `Vec` every frame and cloning each event for
+//! every child.
+//!
+//! Run with `cargo bench -p coil_engine --bench dispatch`.
+use coil_engine::config::GameConfig;
+use coil_engine::context::Context;
+use coil_engine::crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use coil_engine::errors::EngineError;
+use coil_engine::frame::Frame;
+use coil_engine::nodes::{Container, Node};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::VecDeque;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+const FRAMES: usize = 20_000;
+const CHILDREN: usize = 16;
+
+/// Counts allocations, to report how many a frame makes.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[derive(Default)]
+struct Listener(usize);
+
+impl Node for Listener {
+    fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    fn on_event(&mut self, _ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        if let Event::Paste(text) = ev {
+            self.0 += text.len();
+        }
+        self.0 += 1;
+        Ok(false)
+    }
+
+    fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        Ok(())
+    }
+}
+
+/// A frame's worth of input: a few keys and a paste.
+fn input(queue: &mut VecDeque<Event>) {
+    for ch in ['w', 'a', 's', 'd'] {
+        queue.push_back(Event::Key(KeyEvent::new(
+            KeyCode::Char(ch),
+            KeyModifiers::NONE,
+        )));
+    }
+    queue.push_back(Event::Paste("hello, world".to_string()));
+}
+
+fn container() -> Container {
+    (0..CHILDREN).fold(Container::new(0, 0), |container, _| {
+        container.with_child(Listener::default())
+    })
+}
+
+/// Runs `frame` for every frame, returning the time and allocations per frame.
+fn measure(mut frame: impl FnMut(&mut VecDeque<Event>)) -> (Duration, f64) {
+    let mut queue = VecDeque::new();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let started = Instant::now();
+    for _ in 0..FRAMES {
+        input(&mut queue);
+        frame(&mut queue);
+    }
+    let elapsed = started.elapsed() / FRAMES as u32;
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    // Filling the queue allocates the pasted text every frame
+    (elapsed, allocations as f64 / FRAMES as f64 - 1.0)
+}
+
+fn main() {
+    let mut ctx = Context::new(GameConfig::new());
+
+    let mut root = container();
+    let mut events = Vec::new();
+    let borrowed = measure(|queue| {
+        events.extend(queue.drain(..));
+        for event in &events {
+            black_box(root.on_event(&mut ctx, event).unwrap());
+        }
+        events.clear();
+    });
+
+    let mut root = container();
+    let cloned = measure(|queue| {
+        let events: Vec<Event> = queue.drain(..).collect();
+        for event in &events {
+            for child in root.children_mut() {
+                black_box(child.on_event(&mut ctx, &event.clone()).unwrap());
+            }
+        }
+    });
+
+    println!("{} children, 5 events a frame", CHILDREN);
+    for (name, (time, allocations)) in [("by reference", borrowed), ("cloned", cloned)] {
+        println!(
+            "{:<13} {:>8?} a frame, {:>5.1} allocations a frame",
+            name, time, allocations
+        );
+    }
+}
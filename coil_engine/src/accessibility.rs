@@ -0,0 +1,219 @@
+//! Accessibility settings applied by the engine.
+//!
+//! With `high_contrast` enabled the renderer maps every cell to black and
+//! white before it reaches the terminal, so text stays readable whatever
+//! palette the game uses. `reduced_motion` is a hint for nodes: effects such
+//! as screen shake, particles and flashing should check
+//! [`GameConfig::reduced_motion`](crate::config::GameConfig::reduced_motion)
+//! and stay still when it is set.
+//!
+//! For developers, `color_blindness` simulates a color vision deficiency over
+//! the final screen, and [`OKABE_ITO`] is a palette that stays distinguishable
+//! under all of them.
+use crate::color::Rgb;
+use crate::errors::EngineError;
+use crate::renderer::Cell;
+use crossterm::style::Color;
+use std::fmt;
+
+/// Relative luminance above which a color is treated as light.
+const LIGHT_THRESHOLD: f32 = 0.5;
+
+/// The Okabe-Ito palette, which people with any common color vision
+/// deficiency can tell apart: black, orange, sky blue, bluish green,
+/// yellow, blue, vermillion and reddish purple.
+pub const OKABE_ITO: [Rgb; 8] = [
+    Rgb::new(0x00, 0x00, 0x00),
+    Rgb::new(0xe6, 0x9f, 0x00),
+    Rgb::new(0x56, 0xb4, 0xe9),
+    Rgb::new(0x00, 0x9e, 0x73),
+    Rgb::new(0xf0, 0xe4, 0x42),
+    Rgb::new(0x00, 0x72, 0xb2),
+    Rgb::new(0xd5, 0x5e, 0x00),
+    Rgb::new(0xcc, 0x79, 0xa7),
+];
+
+/// A color vision deficiency the renderer can simulate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorBlindness {
+    /// No red cones: reds look dark and close to greens.
+    Protanopia,
+    /// No green cones: reds and greens look alike.
+    Deuteranopia,
+    /// No blue cones: blues look green and yellows look pink.
+    Tritanopia,
+}
+
+impl ColorBlindness {
+    /// Linear RGB transform for full severity, from Machado et al. (2009).
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            ColorBlindness::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            ColorBlindness::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            ColorBlindness::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+
+    /// Returns how a color looks to someone with this deficiency.
+    pub fn simulate(self, rgb: Rgb) -> Rgb {
+        let linear = [to_linear(rgb.r), to_linear(rgb.g), to_linear(rgb.b)];
+        let [r, g, b] = self
+            .matrix()
+            .map(|row| from_linear(row.iter().zip(linear).map(|(m, c)| m * c).sum()));
+        Rgb::new(r, g, b)
+    }
+
+    /// Returns the renderer filter that applies [`ColorBlindness::simulate`] to every cell.
+    pub fn filter(self) -> fn(Cell) -> Cell {
+        fn apply(cell: Cell, deficiency: ColorBlindness) -> Cell {
+            let simulate = |color| match Rgb::from_color(color) {
+                Some(rgb) => deficiency.simulate(rgb).into(),
+                None => color,
+            };
+            Cell {
+                fg: simulate(cell.fg),
+                bg: simulate(cell.bg),
+                ..cell
+            }
+        }
+        match self {
+            ColorBlindness::Protanopia => |cell| apply(cell, ColorBlindness::Protanopia),
+            ColorBlindness::Deuteranopia => |cell| apply(cell, ColorBlindness::Deuteranopia),
+            ColorBlindness::Tritanopia => |cell| apply(cell, ColorBlindness::Tritanopia),
+        }
+    }
+}
+
+impl std::str::FromStr for ColorBlindness {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "protanopia" => Ok(ColorBlindness::Protanopia),
+            "deuteranopia" => Ok(ColorBlindness::Deuteranopia),
+            "tritanopia" => Ok(ColorBlindness::Tritanopia),
+            _ => Err(EngineError::Config(format!(
+                "Unknown color blindness: {:?}",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ColorBlindness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColorBlindness::Protanopia => "protanopia",
+            ColorBlindness::Deuteranopia => "deuteranopia",
+            ColorBlindness::Tritanopia => "tritanopia",
+        };
+        f.write_str(name)
+    }
+}
+
+fn to_linear(value: u8) -> f32 {
+    let value = value as f32 / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(value: f32) -> u8 {
+    let value = value.clamp(0.0, 1.0);
+    let value = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    (value * 255.0).round() as u8
+}
+
+/// Returns the relative luminance of a color in `0.0..=1.0`, or `None` for
+/// the terminal's default color.
+pub fn luminance(color: Color) -> Option<f32> {
+    let rgb = Rgb::from_color(color)?;
+    Some(0.2126 * to_linear(rgb.r) + 0.7152 * to_linear(rgb.g) + 0.0722 * to_linear(rgb.b))
+}
+
+/// Maps a cell to the high-contrast palette.
+///
+/// Backgrounds become black or white depending on their lightness and the
+/// foreground takes the opposite one. Cells on the terminal's default
+/// background keep the default foreground, which the terminal already
+/// renders in contrast to it.
+pub fn high_contrast(cell: Cell) -> Cell {
+    let (fg, bg) = match luminance(cell.bg) {
+        None => (Color::Reset, Color::Reset),
+        Some(l) if l > LIGHT_THRESHOLD => (Color::Black, Color::White),
+        Some(_) => (Color::White, Color::Black),
+    };
+    Cell { fg, bg, ..cell }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_high_contrast() {
+        let dim = Cell::styled('a', crate::style!(dark_grey on dark_blue));
+        assert_eq!(
+            high_contrast(dim),
+            Cell::styled('a', crate::style!(white on black))
+        );
+
+        let light = Cell::styled('b', crate::style!(yellow on white));
+        assert_eq!(
+            high_contrast(light),
+            Cell::styled('b', crate::style!(black on white))
+        );
+
+        let plain = Cell::styled('c', crate::style!(red));
+        assert_eq!(high_contrast(plain), Cell::new('c'));
+    }
+
+    #[test]
+    fn test_color_blindness_simulation() {
+        let white = Rgb::new(255, 255, 255);
+        for deficiency in [
+            ColorBlindness::Protanopia,
+            ColorBlindness::Deuteranopia,
+            ColorBlindness::Tritanopia,
+        ] {
+            let simulated = deficiency.simulate(white);
+            assert!(simulated.r >= 254 && simulated.g >= 254 && simulated.b >= 254);
+            assert_eq!(
+                deficiency.to_string().parse::<ColorBlindness>().unwrap(),
+                deficiency
+            );
+        }
+
+        // red and green both turn into similar yellowish browns
+        let red = ColorBlindness::Deuteranopia.simulate(Rgb::new(255, 0, 0));
+        let green = ColorBlindness::Deuteranopia.simulate(Rgb::new(0, 255, 0));
+        assert!(red.r > red.b && red.g > red.b);
+        assert!(green.r > green.b && green.g > green.b);
+
+        let filter = ColorBlindness::Protanopia.filter();
+        assert_eq!(filter(Cell::new('x')), Cell::new('x'));
+        assert!(matches!(
+            filter(Cell::styled('x', crate::style!(red))).fg,
+            Color::Rgb { .. }
+        ));
+    }
+}
@@ -0,0 +1,258 @@
+//! Achievements unlocked from play statistics.
+//!
+//! Games record statistics (`kills`, `distance`, ...) as they happen; each
+//! achievement has a condition over those statistics and is unlocked, with a
+//! toast, the first time it holds. Progress is persisted in a small text
+//! format:
+//!
+//! ```text
+//! stats.kills = 12
+//! unlocked = first_blood, marathon
+//! ```
+use crate::context::Context;
+use crate::errors::EngineError;
+use crate::notify::Level;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+const STAT_PREFIX: &str = "stats.";
+
+/// Named counters describing how the game has been played.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    values: BTreeMap<String, i64>,
+}
+
+impl Stats {
+    /// Returns the value of a statistic, zero if it was never recorded.
+    pub fn get(&self, name: &str) -> i64 {
+        self.values.get(name).copied().unwrap_or(0)
+    }
+
+    /// Adds to a statistic.
+    pub fn add(&mut self, name: &str, amount: i64) {
+        *self.values.entry(name.to_string()).or_default() += amount;
+    }
+
+    /// Raises a statistic to `value` if it is higher, e.g. for a best score.
+    pub fn record_max(&mut self, name: &str, value: i64) {
+        let entry = self.values.entry(name.to_string()).or_insert(value);
+        *entry = (*entry).max(value);
+    }
+
+    /// Iterates over all statistics in name order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, i64)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value))
+    }
+}
+
+/// A goal the player can reach, checked whenever statistics change.
+pub struct Achievement {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    condition: Box<dyn Fn(&Stats) -> bool>,
+}
+
+/// Achievement definitions together with statistics and unlock state.
+#[derive(Default)]
+pub struct Achievements {
+    definitions: Vec<Achievement>,
+    unlocked: BTreeSet<String>,
+    stats: Stats,
+}
+
+impl Achievements {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defines an achievement unlocked once `condition` holds.
+    pub fn define(
+        mut self,
+        id: &str,
+        name: &str,
+        description: &str,
+        condition: impl Fn(&Stats) -> bool + 'static,
+    ) -> Self {
+        self.definitions.push(Achievement {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            condition: Box::new(condition),
+        });
+        self
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    pub fn definitions(&self) -> &[Achievement] {
+        &self.definitions
+    }
+
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.unlocked.contains(id)
+    }
+
+    /// Adds to a statistic and unlocks any achievements it completes.
+    pub fn record(&mut self, ctx: &mut Context, stat: &str, amount: i64) {
+        self.stats.add(stat, amount);
+        self.check(ctx);
+    }
+
+    /// Raises a statistic and unlocks any achievements it completes.
+    pub fn record_max(&mut self, ctx: &mut Context, stat: &str, value: i64) {
+        self.stats.record_max(stat, value);
+        self.check(ctx);
+    }
+
+    /// Unlocks an achievement directly, for goals not expressed as statistics.
+    ///
+    /// Returns `false` if it was already unlocked.
+    pub fn unlock(&mut self, ctx: &mut Context, id: &str) -> bool {
+        if !self.unlocked.insert(id.to_string()) {
+            return false;
+        }
+        let name = self
+            .definitions
+            .iter()
+            .find(|a| a.id == id)
+            .map_or(id, |a| a.name.as_str());
+        ctx.notify(
+            format!("Achievement unlocked: {}", name),
+            Level::Success,
+            Duration::from_secs(3),
+        );
+        true
+    }
+
+    /// Unlocks every achievement whose condition now holds.
+    pub fn check(&mut self, ctx: &mut Context) {
+        let reached: Vec<String> = self
+            .definitions
+            .iter()
+            .filter(|a| !self.unlocked.contains(&a.id) && (a.condition)(&self.stats))
+            .map(|a| a.id.clone())
+            .collect();
+        for id in reached {
+            self.unlock(ctx, &id);
+        }
+    }
+
+    /// Restores statistics and unlocks saved in the text format.
+    ///
+    /// Unlocks are restored silently; call [`Achievements::check`] afterwards
+    /// to unlock (with toasts) achievements reached by the saved statistics.
+    pub fn merge_str(&mut self, contents: &str) -> Result<(), EngineError> {
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || {
+                EngineError::Config(format!(
+                    "Invalid achievements line {}: {:?}",
+                    number + 1,
+                    line
+                ))
+            };
+            let (key, value) = line.split_once('=').ok_or_else(invalid)?;
+            let (key, value) = (key.trim(), value.trim());
+            if key == "unlocked" {
+                self.unlocked.extend(
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|id| !id.is_empty())
+                        .map(str::to_string),
+                );
+            } else if let Some(stat) = key.strip_prefix(STAT_PREFIX) {
+                let value = value.parse().map_err(|_| invalid())?;
+                self.stats.values.insert(stat.to_string(), value);
+            } else {
+                return Err(invalid());
+            }
+        }
+        Ok(())
+    }
+
+    /// Restores progress from a file, keeping defaults if it does not exist yet.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<(), EngineError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => self.merge_str(&contents),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes statistics and unlocks to a file in the text format.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), EngineError> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Achievements {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in self.stats.iter() {
+            writeln!(f, "{}{} = {}", STAT_PREFIX, name, value)?;
+        }
+        let unlocked: Vec<&str> = self.unlocked.iter().map(String::as_str).collect();
+        writeln!(f, "unlocked = {}", unlocked.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+
+    fn achievements() -> Achievements {
+        Achievements::new()
+            .define("first_blood", "First Blood", "Defeat an enemy", |s| {
+                s.get("kills") >= 1
+            })
+            .define("veteran", "Veteran", "Defeat ten enemies", |s| {
+                s.get("kills") >= 10
+            })
+    }
+
+    #[test]
+    fn test_unlocks_once_with_toast() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut achievements = achievements();
+
+        achievements.record(&mut ctx, "kills", 1);
+        achievements.record(&mut ctx, "kills", 1);
+        assert!(achievements.is_unlocked("first_blood"));
+        assert!(!achievements.is_unlocked("veteran"));
+        let toasts: Vec<_> = ctx.notifications().map(|n| n.message.clone()).collect();
+        assert_eq!(toasts, ["Achievement unlocked: First Blood"]);
+
+        achievements.record_max(&mut ctx, "best", 5);
+        achievements.record_max(&mut ctx, "best", 3);
+        assert_eq!(achievements.stats().get("best"), 5);
+    }
+
+    #[test]
+    fn test_progress_round_trip() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut achievements = achievements();
+        achievements.record(&mut ctx, "kills", 3);
+        achievements.unlock(&mut ctx, "secret");
+
+        let mut restored = self::achievements();
+        restored.merge_str(&achievements.to_string()).unwrap();
+        assert_eq!(restored.stats(), achievements.stats());
+        assert!(restored.is_unlocked("first_blood"));
+        assert!(restored.is_unlocked("secret"));
+        assert!(restored.merge_str("kills = 3").is_err());
+    }
+}
@@ -0,0 +1,524 @@
+//! Named input actions and their key bindings.
+//!
+//! Bindings are stored in a small text format, one action per line:
+//!
+//! ```text
+//! # Movement
+//! move_left = ['a', 'Left']
+//! save = 'Ctrl+s'
+//! ```
+//!
+//! Keys are single characters or names such as `Left`, `Enter`, `Space` and
+//! `F1`, optionally prefixed with `Ctrl+`, `Alt+` or `Shift+` modifiers.
+//!
+//! Player-recorded macros are kept in the same file, as the key that plays
+//! them and the actions they perform in order; see [`crate::macros`]:
+//!
+//! ```text
+//! macro 'F1' = ['move_left', 'move_left', 'jump']
+//! ```
+use crate::errors::EngineError;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A key together with the modifiers that must be held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Creates a binding for a key with the given modifiers.
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    /// Returns whether the key event triggers this binding.
+    ///
+    /// Shift is ignored for character keys, since terminals report it
+    /// inconsistently alongside the already-shifted character.
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        if key.kind == KeyEventKind::Release {
+            return false;
+        }
+        let significant = |code: KeyCode, modifiers: KeyModifiers| match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        self.code == key.code
+            && significant(self.code, self.modifiers) == significant(key.code, key.modifiers)
+    }
+}
+
+impl From<KeyCode> for KeyBinding {
+    fn from(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+}
+
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Enter", KeyCode::Enter),
+    ("Esc", KeyCode::Esc),
+    ("Space", KeyCode::Char(' ')),
+    ("Tab", KeyCode::Tab),
+    ("BackTab", KeyCode::BackTab),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+];
+
+const MODIFIERS: &[(&str, KeyModifiers)] = &[
+    ("Ctrl", KeyModifiers::CONTROL),
+    ("Alt", KeyModifiers::ALT),
+    ("Shift", KeyModifiers::SHIFT),
+];
+
+fn parse_key_code(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(ch), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(ch));
+    }
+    if let Some(&(_, code)) = NAMED_KEYS
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+    {
+        return Some(code);
+    }
+    let number = name.strip_prefix(['F', 'f'])?.parse().ok()?;
+    (1..=24).contains(&number).then_some(KeyCode::F(number))
+}
+
+impl FromStr for KeyBinding {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || EngineError::Config(format!("Invalid key binding: {:?}", s));
+        // A trailing '+' is the plus key itself, as in "Ctrl++"
+        let (prefix, key) = match s.strip_suffix("++") {
+            Some(prefix) => (Some(prefix), "+"),
+            None => match s.rsplit_once('+') {
+                Some((prefix, key)) if !key.is_empty() => (Some(prefix), key),
+                _ => (None, s),
+            },
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for name in prefix.into_iter().flat_map(|p| p.split('+')) {
+            let &(_, modifier) = MODIFIERS
+                .iter()
+                .find(|(m, _)| m.eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(invalid)?;
+            modifiers |= modifier;
+        }
+        let code = parse_key_code(key).ok_or_else(invalid)?;
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, modifier) in MODIFIERS {
+            if self.modifiers.contains(*modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        if let Some((name, _)) = NAMED_KEYS.iter().find(|(_, code)| *code == self.code) {
+            return f.write_str(name);
+        }
+        match self.code {
+            KeyCode::Char(ch) => write!(f, "{}", ch),
+            KeyCode::F(number) => write!(f, "F{}", number),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// Maps named actions to the key bindings that trigger them.
+///
+/// Actions keep the order in which they were first bound, so a saved
+/// bindings file lists them the same way the game declared them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActionMap {
+    actions: Vec<(String, Vec<KeyBinding>)>,
+    /// Keys playing a macro and the actions it performs
+    macros: Vec<(KeyBinding, Vec<String>)>,
+}
+
+impl ActionMap {
+    /// Creates an empty action map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a binding for an action and returns `self`, so you can chain.
+    pub fn with_binding(mut self, action: &str, binding: impl Into<KeyBinding>) -> Self {
+        self.bind(action, binding);
+        self
+    }
+
+    /// Adds a binding for an action.
+    pub fn bind(&mut self, action: &str, binding: impl Into<KeyBinding>) {
+        let binding = binding.into();
+        let bindings = self.entry(action);
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Replaces all bindings of an action.
+    pub fn rebind(&mut self, action: &str, bindings: Vec<KeyBinding>) {
+        *self.entry(action) = bindings;
+    }
+
+    /// Returns the bindings of an action.
+    pub fn bindings(&self, action: &str) -> &[KeyBinding] {
+        self.actions
+            .iter()
+            .find(|(name, _)| name == action)
+            .map(|(_, bindings)| bindings.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Returns the names of all actions in declaration order.
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.actions.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the first action bound to the key event, if any.
+    pub fn action_for(&self, key: &KeyEvent) -> Option<&str> {
+        self.actions
+            .iter()
+            .find(|(_, bindings)| bindings.iter().any(|b| b.matches(key)))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the first action triggered by the event, if it is a key event.
+    pub fn action_for_event(&self, event: &Event) -> Option<&str> {
+        match event {
+            Event::Key(key) => self.action_for(key),
+            _ => None,
+        }
+    }
+
+    /// Binds `trigger` to play `actions` in order, replacing any macro it played.
+    pub fn bind_macro(&mut self, trigger: impl Into<KeyBinding>, actions: Vec<String>) {
+        let trigger = trigger.into();
+        match self.macros.iter_mut().find(|(key, _)| *key == trigger) {
+            Some((_, steps)) => *steps = actions,
+            None => self.macros.push((trigger, actions)),
+        }
+    }
+
+    /// Removes the macro played by `trigger`, if any.
+    pub fn unbind_macro(&mut self, trigger: KeyBinding) {
+        self.macros.retain(|(key, _)| *key != trigger);
+    }
+
+    /// Returns each macro's key and the actions it performs.
+    pub fn macros(&self) -> impl Iterator<Item = (KeyBinding, &[String])> {
+        self.macros
+            .iter()
+            .map(|(key, actions)| (*key, actions.as_slice()))
+    }
+
+    /// Returns the actions of the macro the key event plays, if any.
+    pub fn macro_for(&self, key: &KeyEvent) -> Option<&[String]> {
+        self.macros
+            .iter()
+            .find(|(trigger, _)| trigger.matches(key))
+            .map(|(_, actions)| actions.as_slice())
+    }
+
+    /// Returns whether the key event triggers the given action.
+    pub fn is(&self, action: &str, key: &KeyEvent) -> bool {
+        self.bindings(action).iter().any(|b| b.matches(key))
+    }
+
+    /// Parses bindings from the text format.
+    pub fn parse(contents: &str) -> Result<Self, EngineError> {
+        let mut map = Self::new();
+        map.merge_str(contents)?;
+        Ok(map)
+    }
+
+    /// Applies bindings from the text format, replacing those of any action it lists.
+    pub fn merge_str(&mut self, contents: &str) -> Result<(), EngineError> {
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: &str| {
+                EngineError::Config(format!("{} on line {}: {:?}", message, number + 1, line))
+            };
+            let (action, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("Expected `action = keys`"))?;
+            let keys = parse_key_list(value.trim()).ok_or_else(|| error("Invalid key list"))?;
+            if let Some(trigger) = action.strip_prefix("macro ") {
+                let trigger = match parse_key_list(trigger.trim()).as_deref() {
+                    Some([trigger]) => trigger.parse::<KeyBinding>()?,
+                    _ => return Err(error("Expected `macro 'key' = actions`")),
+                };
+                self.bind_macro(trigger, keys);
+                continue;
+            }
+            let bindings = keys
+                .iter()
+                .map(|key| key.parse())
+                .collect::<Result<Vec<KeyBinding>, _>>()?;
+            self.rebind(action.trim(), bindings);
+        }
+        Ok(())
+    }
+
+    /// Loads bindings from a file in the text format.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, EngineError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Applies bindings from a file on top of this map, keeping the
+    /// defaults for actions the file does not mention.
+    pub fn merge_file(&mut self, path: impl AsRef<Path>) -> Result<(), EngineError> {
+        self.merge_str(&fs::read_to_string(path)?)
+    }
+
+    /// Loads player bindings from `path` on top of these defaults, or writes
+    /// the defaults there if the file does not exist yet.
+    pub fn load_or_create(mut self, path: impl AsRef<Path>) -> Result<Self, EngineError> {
+        let path = path.as_ref();
+        if path.exists() {
+            self.merge_file(path)?;
+        } else {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            self.save(path)?;
+        }
+        Ok(self)
+    }
+
+    /// Writes the bindings to a file in the text format.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), EngineError> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    fn entry(&mut self, action: &str) -> &mut Vec<KeyBinding> {
+        let index = match self.actions.iter().position(|(name, _)| name == action) {
+            Some(index) => index,
+            None => {
+                self.actions.push((action.to_string(), Vec::new()));
+                self.actions.len() - 1
+            }
+        };
+        &mut self.actions[index].1
+    }
+}
+
+/// Splits `['a', "Left"]` or a single quoted key into its key names.
+fn parse_key_list(value: &str) -> Option<Vec<String>> {
+    let inner = match value.strip_prefix('[') {
+        Some(rest) => rest.strip_suffix(']')?,
+        None => value,
+    };
+    let mut keys = Vec::new();
+    let mut chars = inner.trim().chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            quote @ ('\'' | '"') => {
+                let mut key = String::new();
+                loop {
+                    match chars.next()? {
+                        c if c == quote && !key.is_empty() => break,
+                        c => key.push(c),
+                    }
+                }
+                keys.push(key);
+            }
+            ',' | ' ' | '\t' => {}
+            _ => return None,
+        }
+    }
+    Some(keys)
+}
+
+/// Quotes a key or action name so [`parse_key_list`] reads it back.
+fn quote(name: &str) -> String {
+    let quote = if name.ends_with('\'') { '"' } else { '\'' };
+    format!("{quote}{name}{quote}")
+}
+
+impl fmt::Display for ActionMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (action, bindings) in &self.actions {
+            let keys: Vec<String> = bindings
+                .iter()
+                .map(|binding| quote(&binding.to_string()))
+                .collect();
+            writeln!(f, "{} = [{}]", action, keys.join(", "))?;
+        }
+        for (trigger, actions) in &self.macros {
+            let actions: Vec<String> = actions.iter().map(|action| quote(action)).collect();
+            let trigger = quote(&trigger.to_string());
+            writeln!(f, "macro {} = [{}]", trigger, actions.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_key_binding_parse_and_display() {
+        let cases = [
+            ("a", KeyBinding::from(KeyCode::Char('a'))),
+            ("Left", KeyBinding::from(KeyCode::Left)),
+            ("space", KeyBinding::from(KeyCode::Char(' '))),
+            ("F5", KeyBinding::from(KeyCode::F(5))),
+            (
+                "Ctrl+s",
+                KeyBinding::new(KeyCode::Char('s'), KeyModifiers::CONTROL),
+            ),
+            (
+                "Ctrl+Alt+Delete",
+                KeyBinding::new(KeyCode::Delete, KeyModifiers::CONTROL | KeyModifiers::ALT),
+            ),
+            (
+                "Ctrl++",
+                KeyBinding::new(KeyCode::Char('+'), KeyModifiers::CONTROL),
+            ),
+            ("+", KeyBinding::from(KeyCode::Char('+'))),
+        ];
+        for (text, expected) in cases {
+            let binding: KeyBinding = text.parse().unwrap();
+            assert_eq!(binding, expected, "parsing {:?}", text);
+            assert_eq!(binding.to_string().parse::<KeyBinding>().unwrap(), expected);
+        }
+        assert!("Hyper+a".parse::<KeyBinding>().is_err());
+        assert!("F99".parse::<KeyBinding>().is_err());
+        assert!("Nope".parse::<KeyBinding>().is_err());
+    }
+
+    #[test]
+    fn test_action_lookup() {
+        let map = ActionMap::new()
+            .with_binding("move_left", KeyCode::Char('a'))
+            .with_binding("move_left", KeyCode::Left)
+            .with_binding(
+                "save",
+                KeyBinding::new(KeyCode::Char('s'), KeyModifiers::CONTROL),
+            );
+
+        let left = key(KeyCode::Left, KeyModifiers::NONE);
+        assert_eq!(map.action_for(&left), Some("move_left"));
+        assert!(map.is("move_left", &key(KeyCode::Char('a'), KeyModifiers::NONE)));
+        assert_eq!(
+            map.action_for(&key(KeyCode::Char('s'), KeyModifiers::CONTROL)),
+            Some("save")
+        );
+        assert_eq!(
+            map.action_for(&key(KeyCode::Char('s'), KeyModifiers::NONE)),
+            None
+        );
+        assert_eq!(
+            map.action_for(&key(KeyCode::Char('A'), KeyModifiers::SHIFT)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_and_serialize_round_trip() {
+        let text = "# defaults\nmove_left = ['a', 'Left']\nsave = \"Ctrl+s\"\nquote = [\"'\"]\n";
+        let map = ActionMap::parse(text).unwrap();
+        assert_eq!(
+            map.actions().collect::<Vec<_>>(),
+            ["move_left", "save", "quote"]
+        );
+        assert_eq!(map.bindings("move_left").len(), 2);
+
+        let written = map.to_string();
+        assert_eq!(
+            written,
+            "move_left = ['a', 'Left']\nsave = ['Ctrl+s']\nquote = [\"'\"]\n"
+        );
+        assert_eq!(ActionMap::parse(&written).unwrap(), map);
+    }
+
+    #[test]
+    fn test_macros_round_trip() {
+        let mut map = ActionMap::parse("jump = ['Space']\nmacro 'F1' = ['jump', 'jump']").unwrap();
+        let trigger = KeyBinding::new(KeyCode::Char('1'), KeyModifiers::CONTROL);
+        map.bind_macro(trigger, vec!["jump".to_string()]);
+        assert_eq!(
+            map.macro_for(&key(KeyCode::F(1), KeyModifiers::NONE)),
+            Some(["jump".to_string(), "jump".to_string()].as_slice())
+        );
+
+        let written = map.to_string();
+        assert_eq!(
+            written,
+            "jump = ['Space']\nmacro 'F1' = ['jump', 'jump']\nmacro 'Ctrl+1' = ['jump']\n"
+        );
+        assert_eq!(ActionMap::parse(&written).unwrap(), map);
+        map.unbind_macro(trigger);
+        assert_eq!(map.macros().count(), 1);
+        assert!(ActionMap::parse("macro = ['jump']").is_err());
+    }
+
+    #[test]
+    fn test_merge_keeps_unlisted_defaults() {
+        let mut map = ActionMap::new()
+            .with_binding("jump", KeyCode::Char(' '))
+            .with_binding("quit", KeyCode::Esc);
+        map.merge_str("jump = ['w']").unwrap();
+        assert_eq!(map.bindings("jump"), [KeyBinding::from(KeyCode::Char('w'))]);
+        assert_eq!(map.bindings("quit"), [KeyBinding::from(KeyCode::Esc)]);
+    }
+
+    #[test]
+    fn test_load_or_create() {
+        let path = std::env::temp_dir().join(format!("coil-bindings-{}.txt", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let defaults = ActionMap::new().with_binding("jump", KeyCode::Char(' '));
+
+        let created = defaults.clone().load_or_create(&path).unwrap();
+        assert_eq!(created, defaults);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "jump = ['Space']\n");
+
+        fs::write(&path, "jump = ['w']\n").unwrap();
+        let loaded = defaults.load_or_create(&path).unwrap();
+        assert_eq!(
+            loaded.bindings("jump"),
+            [KeyBinding::from(KeyCode::Char('w'))]
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(ActionMap::parse("jump").is_err());
+        assert!(ActionMap::parse("jump = [w]").is_err());
+        assert!(ActionMap::parse("jump = ['w'").is_err());
+        assert!(ActionMap::parse("jump = ['Hyper+w']").is_err());
+    }
+}
@@ -0,0 +1,366 @@
+//! Audio levels for music visualizers.
+//!
+//! An [`AudioInput`] node analyzes the latest audio each update and
+//! publishes the energy of a few frequency bands, lowest first, through
+//! [`Context::audio_bands`], for nodes to drive colors, particles or
+//! motion with. Audio comes from:
+//!
+//! * a WAV file, played in step with game time with [`AudioInput::open_wav`]
+//! * a live stream of 16-bit little-endian mono samples with
+//!   [`AudioInput::open_stream`], e.g. system audio recorded into a FIFO
+//!   with `parec --format=s16le --channels=1 --rate=44100 > audio.fifo`
+//!
+//! Each band is scaled by the loudest it has been lately, so values stay
+//! between 0 and 1 whatever the volume.
+use crate::context::Context;
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::nodes::Node;
+use crossterm::event::Event;
+use log::warn;
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Samples analyzed at a time; a power of two
+const WINDOW: usize = 1024;
+/// Lowest frequency of the first band, in Hz
+const LOWEST: f32 = 40.0;
+/// How much of its peak a band keeps per update when it gets quieter
+const PEAK_DECAY: f32 = 0.995;
+
+enum Source {
+    /// A decoded file and the playback position in samples
+    Clip {
+        samples: Vec<f32>,
+        position: f64,
+        looping: bool,
+    },
+    /// The latest samples read from a stream
+    Live(Arc<Mutex<VecDeque<f32>>>),
+}
+
+/// A node publishing the frequency bands of an audio source.
+pub struct AudioInput {
+    source: Source,
+    sample_rate: u32,
+    bands: usize,
+    /// Recent peak energy of each band
+    peaks: Vec<f32>,
+}
+
+impl AudioInput {
+    /// Plays a WAV file with 16-bit integer or 32-bit float samples; all
+    /// channels are mixed together.
+    pub fn open_wav(path: impl AsRef<Path>) -> Result<Self, EngineError> {
+        let (samples, sample_rate) = decode_wav(&fs::read(path)?)?;
+        Ok(Self::new(
+            Source::Clip {
+                samples,
+                position: 0.0,
+                looping: false,
+            },
+            sample_rate,
+        ))
+    }
+
+    /// Reads 16-bit little-endian mono samples at `sample_rate` from a file
+    /// or FIFO as they arrive.
+    pub fn open_stream(path: impl AsRef<Path>, sample_rate: u32) -> Result<Self, EngineError> {
+        let mut stream = File::open(path)?;
+        let latest = Arc::new(Mutex::new(VecDeque::with_capacity(WINDOW)));
+        let shared = Arc::clone(&latest);
+        thread::spawn(move || {
+            let mut bytes = [0; 4096];
+            loop {
+                let length = match stream.read(&mut bytes) {
+                    Ok(0) => return,
+                    Ok(length) => length & !1,
+                    Err(e) => {
+                        warn!("Audio stream failed: {}", e);
+                        return;
+                    }
+                };
+                let Ok(mut latest) = shared.lock() else {
+                    return;
+                };
+                for pair in bytes[..length].chunks_exact(2) {
+                    if latest.len() == WINDOW {
+                        latest.pop_front();
+                    }
+                    latest.push_back(i16::from_le_bytes([pair[0], pair[1]]) as f32 / 32768.0);
+                }
+            }
+        });
+        Ok(Self::new(Source::Live(latest), sample_rate))
+    }
+
+    fn new(source: Source, sample_rate: u32) -> Self {
+        Self {
+            source,
+            sample_rate: sample_rate.max(1),
+            bands: 8,
+            peaks: Vec::new(),
+        }
+    }
+
+    /// Sets the number of bands, spread evenly over octaves; 8 by default.
+    pub fn with_bands(mut self, bands: usize) -> Self {
+        self.bands = bands.max(1);
+        self
+    }
+
+    /// Starts a file over when it ends instead of going silent.
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        if let Source::Clip { looping: l, .. } = &mut self.source {
+            *l = looping;
+        }
+        self
+    }
+
+    /// Advances a file by `dt` seconds and returns the window of samples
+    /// that ends at the playback position.
+    fn window(&mut self, dt: f32) -> Vec<f32> {
+        match &mut self.source {
+            Source::Clip {
+                samples,
+                position,
+                looping,
+            } => {
+                *position += dt as f64 * self.sample_rate as f64;
+                if *looping && !samples.is_empty() {
+                    *position %= samples.len() as f64;
+                }
+                let end = (*position as usize).min(samples.len());
+                if end == samples.len() && !*looping {
+                    return Vec::new();
+                }
+                samples[end.saturating_sub(WINDOW)..end].to_vec()
+            }
+            Source::Live(latest) => latest
+                .lock()
+                .map(|latest| latest.iter().copied().collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Node for AudioInput {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        let energies = band_energies(&self.window(dt), self.sample_rate, self.bands);
+        self.peaks.resize(self.bands, 0.0);
+        let bands = energies
+            .iter()
+            .zip(&mut self.peaks)
+            .map(|(&energy, peak)| {
+                *peak = (*peak * PEAK_DECAY).max(energy);
+                if *peak > f32::EPSILON {
+                    energy / *peak
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        ctx.set_audio_bands(bands);
+        Ok(())
+    }
+
+    fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+        Ok(false)
+    }
+
+    fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        Ok(())
+    }
+}
+
+/// Returns the mean magnitude of each of `bands` octave-spaced bands
+/// between [`LOWEST`] and the Nyquist frequency.
+fn band_energies(samples: &[f32], sample_rate: u32, bands: usize) -> Vec<f32> {
+    if samples.is_empty() {
+        return vec![0.0; bands];
+    }
+    // Zero padding keeps short windows, e.g. at the start of a file, usable
+    let mut re = vec![0.0; WINDOW];
+    let mut im = vec![0.0; WINDOW];
+    let offset = WINDOW - samples.len().min(WINDOW);
+    for (i, &sample) in samples.iter().take(WINDOW).enumerate() {
+        let hann = 0.5 - 0.5 * (2.0 * PI * (offset + i) as f32 / WINDOW as f32).cos();
+        re[offset + i] = sample * hann;
+    }
+    fft(&mut re, &mut im);
+
+    let nyquist = sample_rate as f32 / 2.0;
+    let bin_width = sample_rate as f32 / WINDOW as f32;
+    let ratio = (nyquist / LOWEST).max(1.0).powf(1.0 / bands as f32);
+    (0..bands)
+        .map(|band| {
+            let low = LOWEST * ratio.powi(band as i32);
+            let bins = (low / bin_width) as usize..((low * ratio / bin_width) as usize).max(1);
+            let bins = bins.start.min(WINDOW / 2)..bins.end.clamp(bins.start + 1, WINDOW / 2);
+            let total: f32 = bins
+                .clone()
+                .map(|bin| (re[bin] * re[bin] + im[bin] * im[bin]).sqrt())
+                .sum();
+            total / bins.len().max(1) as f32
+        })
+        .collect()
+}
+
+/// Transforms `re` and `im` in place; their length must be a power of two.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut length = 2;
+    while length <= n {
+        let angle = -2.0 * PI / length as f32;
+        for start in (0..n).step_by(length) {
+            for k in 0..length / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + length / 2);
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        length <<= 1;
+    }
+}
+
+/// Decodes a WAV file into mono samples and its sample rate.
+fn decode_wav(bytes: &[u8]) -> Result<(Vec<f32>, u32), EngineError> {
+    let invalid = |reason: &str| EngineError::game(format!("Unsupported WAV file: {}", reason));
+    if bytes.get(..4) != Some(b"RIFF") || bytes.get(8..12) != Some(b"WAVE") {
+        return Err(invalid("not a RIFF/WAVE file"));
+    }
+    let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+    let u32_at =
+        |at: usize| u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+    let mut format = None;
+    let mut at = 12;
+    while at + 8 <= bytes.len() {
+        let size = u32_at(at + 4) as usize;
+        let body = at + 8;
+        let chunk = bytes
+            .get(body..body + size)
+            .ok_or_else(|| invalid("truncated chunk"))?;
+        match &bytes[at..at + 4] {
+            b"fmt " if size >= 16 => {
+                // Format, channels, sample rate and bits per sample
+                format = Some((
+                    u16_at(body),
+                    u16_at(body + 2),
+                    u32_at(body + 4),
+                    u16_at(body + 14),
+                ));
+            }
+            b"data" => {
+                let (kind, channels, rate, bits) = format.ok_or_else(|| invalid("no format"))?;
+                let channels = channels.max(1) as usize;
+                let frames: Vec<f32> = match (kind, bits) {
+                    (1, 16) => chunk
+                        .chunks_exact(2)
+                        .map(|s| i16::from_le_bytes([s[0], s[1]]) as f32 / 32768.0)
+                        .collect(),
+                    (3, 32) => chunk
+                        .chunks_exact(4)
+                        .map(|s| f32::from_le_bytes([s[0], s[1], s[2], s[3]]))
+                        .collect(),
+                    _ => return Err(invalid("only 16-bit PCM and 32-bit float are read")),
+                };
+                let mono = frames
+                    .chunks_exact(channels)
+                    .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+                    .collect();
+                return Ok((mono, rate));
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even size
+        at = body + size + (size & 1);
+    }
+    Err(invalid("no data"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+
+    /// Encodes a 16-bit mono WAV file.
+    fn wav(samples: &[f32], rate: u32) -> Vec<u8> {
+        let data: Vec<u8> = samples
+            .iter()
+            .flat_map(|s| ((s * 32767.0) as i16).to_le_bytes())
+            .collect();
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend((36 + data.len() as u32).to_le_bytes());
+        bytes.extend(b"WAVEfmt ");
+        bytes.extend(16u32.to_le_bytes());
+        bytes.extend(1u16.to_le_bytes());
+        bytes.extend(1u16.to_le_bytes());
+        bytes.extend(rate.to_le_bytes());
+        bytes.extend((rate * 2).to_le_bytes());
+        bytes.extend(2u16.to_le_bytes());
+        bytes.extend(16u16.to_le_bytes());
+        bytes.extend(b"data");
+        bytes.extend((data.len() as u32).to_le_bytes());
+        bytes.extend(data);
+        bytes
+    }
+
+    #[test]
+    fn test_tone_lands_in_its_band() {
+        let rate = 44_100;
+        let tone: Vec<f32> = (0..rate)
+            .map(|i| (2.0 * PI * 1000.0 * i as f32 / rate as f32).sin() * 0.5)
+            .collect();
+        let (samples, decoded_rate) = decode_wav(&wav(&tone, rate)).unwrap();
+        assert_eq!(decoded_rate, rate);
+        assert_eq!(samples.len(), tone.len());
+
+        // 1 kHz is in the band from 40 * ratio^4 to 40 * ratio^5
+        let energies = band_energies(&samples[..WINDOW], rate, 8);
+        let loudest = (0..8)
+            .max_by(|&a, &b| energies[a].total_cmp(&energies[b]))
+            .unwrap();
+        assert_eq!(loudest, 4);
+
+        let mut input = AudioInput::new(
+            Source::Clip {
+                samples,
+                position: 0.0,
+                looping: false,
+            },
+            rate,
+        );
+        let mut ctx = Context::new(GameConfig::new());
+        input.update(&mut ctx, 0.5).unwrap();
+        assert_eq!(ctx.audio_bands().len(), 8);
+        assert_eq!(ctx.audio_bands()[4], 1.0);
+
+        // Past the end of the file the bands go quiet
+        input.update(&mut ctx, 1.0).unwrap();
+        assert!(ctx.audio_bands().iter().all(|&band| band == 0.0));
+        assert!(decode_wav(b"RIFF\0\0\0\0WAVX").is_err());
+    }
+}
@@ -0,0 +1,261 @@
+//! Cellular automata: grids whose cells all change together by a rule.
+//!
+//! A [`CellularAutomaton`] keeps two buffers, so every cell of a generation
+//! is computed from the previous generation only, never from neighbors that
+//! were already updated in the same step. The rule is a closure that sees a
+//! cell's [`Neighborhood`]; the same type covers Game of Life, falling sand,
+//! heat diffusion or cave generation.
+//!
+//! ```
+//! use coil_engine::automaton::{CellularAutomaton, Edges};
+//!
+//! let mut life = CellularAutomaton::new(8, 8, false).with_edges(Edges::Wrap);
+//! life.set(1, 0, true);
+//! life.set(1, 1, true);
+//! life.set(1, 2, true);
+//! life.step(|cell| {
+//!     let alive = cell.moore().filter(|alive| **alive).count();
+//!     matches!((*cell.get(), alive), (true, 2) | (_, 3))
+//! });
+//! assert!(*life.get(0, 1) && *life.get(2, 1));
+//! ```
+//!
+//! With the `parallel` feature, [`CellularAutomaton::par_step`] computes the
+//! rows of large grids in parallel.
+
+/// What cells outside the grid read as.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edges<T> {
+    /// The grid wraps around, so the left edge neighbors the right edge.
+    Wrap,
+    /// Cells outside read as the nearest edge cell.
+    Clamp,
+    /// Cells outside read as a fixed value, e.g. dead cells.
+    Value(T),
+}
+
+/// A cell and the cells around it, as seen by a rule.
+pub struct Neighborhood<'a, T> {
+    automaton: &'a CellularAutomaton<T>,
+    x: u16,
+    y: u16,
+}
+
+impl<T> Neighborhood<'_, T> {
+    /// Returns the position of the cell.
+    pub fn position(&self) -> (u16, u16) {
+        (self.x, self.y)
+    }
+
+    /// Returns the cell itself.
+    pub fn get(&self) -> &T {
+        self.at(0, 0)
+    }
+
+    /// Returns the cell at an offset from this one, applying the edge handling.
+    pub fn at(&self, dx: i32, dy: i32) -> &T {
+        self.automaton.read(self.x as i32 + dx, self.y as i32 + dy)
+    }
+
+    /// Iterates over the eight surrounding cells.
+    pub fn moore(&self) -> impl Iterator<Item = &T> {
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+            .filter(|&offset| offset != (0, 0))
+            .map(|(dx, dy)| self.at(dx, dy))
+    }
+
+    /// Iterates over the four orthogonal neighbors: right, below, left and above.
+    pub fn von_neumann(&self) -> impl Iterator<Item = &T> {
+        [(1, 0), (0, 1), (-1, 0), (0, -1)]
+            .into_iter()
+            .map(|(dx, dy)| self.at(dx, dy))
+    }
+}
+
+/// A double-buffered grid of cells stepped by a rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellularAutomaton<T> {
+    width: u16,
+    height: u16,
+    cells: Vec<T>,
+    next: Vec<T>,
+    edges: Edges<T>,
+    generation: u64,
+}
+
+impl<T: Clone> CellularAutomaton<T> {
+    /// Creates a grid filled with `fill`, whose outside also reads as `fill`.
+    pub fn new(width: u16, height: u16, fill: T) -> Self {
+        Self::from_fn(width, height, |_, _| fill.clone()).with_edges(Edges::Value(fill))
+    }
+
+    /// Creates a grid from a function of each cell's position, clamped at the edges.
+    pub fn from_fn(width: u16, height: u16, mut cell: impl FnMut(u16, u16) -> T) -> Self {
+        let cells: Vec<T> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| cell(x, y))
+            .collect();
+        Self {
+            width,
+            height,
+            next: cells.clone(),
+            cells,
+            edges: Edges::Clamp,
+            generation: 0,
+        }
+    }
+
+    pub fn with_edges(mut self, edges: Edges<T>) -> Self {
+        self.edges = edges;
+        self
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Returns the number of steps taken so far.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns the cell at (x,y); positions outside read by the edge handling.
+    pub fn get(&self, x: u16, y: u16) -> &T {
+        self.read(x as i32, y as i32)
+    }
+
+    /// Sets the cell at (x,y), ignoring positions outside the grid.
+    pub fn set(&mut self, x: u16, y: u16, value: T) {
+        if x < self.width && y < self.height {
+            self.cells[y as usize * self.width as usize + x as usize] = value;
+        }
+    }
+
+    /// Returns the cells row by row.
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// Computes the next generation of every cell from the current one.
+    pub fn step(&mut self, rule: impl Fn(&Neighborhood<'_, T>) -> T) {
+        let mut next = std::mem::take(&mut self.next);
+        let width = self.width.max(1) as usize;
+        for (index, cell) in next.iter_mut().enumerate() {
+            let (x, y) = ((index % width) as u16, (index / width) as u16);
+            *cell = rule(&Neighborhood {
+                automaton: self,
+                x,
+                y,
+            });
+        }
+        self.next = std::mem::replace(&mut self.cells, next);
+        self.generation += 1;
+    }
+
+    /// Like [`CellularAutomaton::step`], computing rows in parallel.
+    #[cfg(feature = "parallel")]
+    pub fn par_step(&mut self, rule: impl Fn(&Neighborhood<'_, T>) -> T + Sync)
+    where
+        T: Send + Sync,
+    {
+        use rayon::prelude::*;
+        let mut next = std::mem::take(&mut self.next);
+        let width = self.width.max(1) as usize;
+        let automaton = &*self;
+        next.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+            for (x, cell) in row.iter_mut().enumerate() {
+                *cell = rule(&Neighborhood {
+                    automaton,
+                    x: x as u16,
+                    y: y as u16,
+                });
+            }
+        });
+        self.next = std::mem::replace(&mut self.cells, next);
+        self.generation += 1;
+    }
+}
+
+impl<T> CellularAutomaton<T> {
+    fn read(&self, x: i32, y: i32) -> &T {
+        let (width, height) = (self.width as i32, self.height as i32);
+        let (x, y) = if x >= 0 && y >= 0 && x < width && y < height {
+            (x, y)
+        } else {
+            match &self.edges {
+                Edges::Wrap => (x.rem_euclid(width), y.rem_euclid(height)),
+                Edges::Clamp => (x.clamp(0, width - 1), y.clamp(0, height - 1)),
+                Edges::Value(value) => return value,
+            }
+        };
+        &self.cells[y as usize * self.width as usize + x as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn life(cell: &Neighborhood<'_, bool>) -> bool {
+        let alive = cell.moore().filter(|alive| **alive).count();
+        matches!((*cell.get(), alive), (true, 2) | (_, 3))
+    }
+
+    fn rows(automaton: &CellularAutomaton<bool>) -> Vec<String> {
+        automaton
+            .cells()
+            .chunks(automaton.width() as usize)
+            .map(|row| {
+                row.iter()
+                    .map(|alive| if *alive { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_glider_moves_and_wraps() {
+        let mut grid = CellularAutomaton::new(5, 5, false).with_edges(Edges::Wrap);
+        for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            grid.set(x, y, true);
+        }
+        for _ in 0..4 {
+            grid.step(life);
+        }
+        // After four generations the glider has moved one cell down and right
+        assert_eq!(rows(&grid), [".....", "..#..", "...#.", ".###.", "....."]);
+        for _ in 0..16 {
+            grid.step(life);
+        }
+        assert_eq!(grid.generation(), 20);
+        assert_eq!(rows(&grid), [".#...", "..#..", "###..", ".....", "....."]);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_step_matches_sequential() {
+        let seed = |x: u16, y: u16| (x * 7 + y * 3) % 5 < 2;
+        let mut sequential = CellularAutomaton::from_fn(40, 30, seed).with_edges(Edges::Wrap);
+        let mut parallel = sequential.clone();
+        for _ in 0..10 {
+            sequential.step(life);
+            parallel.par_step(life);
+        }
+        assert_eq!(sequential, parallel);
+    }
+
+    #[test]
+    fn test_edges() {
+        let grid = CellularAutomaton::from_fn(3, 1, |x, _| x);
+        assert_eq!(*grid.get(5, 0), 2);
+        let wrapped = grid.clone().with_edges(Edges::Wrap);
+        assert_eq!(*wrapped.read(-1, 0), 2);
+        let fixed = grid.with_edges(Edges::Value(9));
+        assert_eq!(*fixed.read(0, -1), 9);
+    }
+}
@@ -0,0 +1,312 @@
+//! Engine-managed autosaves.
+//!
+//! When the `autosave` setting names a file, the engine saves the game every
+//! `autosave_interval`, on [`Context::request_save`](crate::context::Context::request_save)
+//! and when the game exits cleanly. Each save asks the node tree for its state
+//! through [`Node::on_save`](crate::nodes::Node::on_save), where nodes write
+//! their [`Saveable`] state into named sections, then rotates the previous
+//! saves into numbered backups (`save.txt.1`, `save.txt.2`, ...). The result
+//! is announced with [`EngineEvent::Saved`](crate::context::EngineEvent::Saved)
+//! or [`EngineEvent::SaveFailed`](crate::context::EngineEvent::SaveFailed).
+//!
+//! Save files hold one section per name:
+//!
+//! ```text
+//! [player]
+//! x = 4
+//! [level]
+//! ####
+//! ```
+//!
+//! Games restore their state at startup from [`SaveData::load`].
+//!
+//! With `autosave_format = db` and the `db` feature, the sections are
+//! written into a world database instead, updating only the sections that
+//! changed since the last save; see [`crate::db`]. The engine keeps that
+//! database open while the game runs. Such saves are read back with
+//! [`SaveData::load_database`] before the game starts, and keep no backups.
+use crate::errors::EngineError;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// State that can be written to a save file as text.
+pub trait Saveable {
+    fn save(&self) -> Result<String, EngineError>;
+}
+
+impl Saveable for crate::achievements::Achievements {
+    fn save(&self) -> Result<String, EngineError> {
+        Ok(self.to_string())
+    }
+}
+
+impl Saveable for crate::tilemap::TileMap {
+    fn save(&self) -> Result<String, EngineError> {
+        Ok(self.to_string())
+    }
+}
+
+/// How autosaves are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SaveFormat {
+    /// A text file with numbered backups.
+    #[default]
+    Text,
+    /// A world database; needs the `db` feature.
+    Database,
+}
+
+impl std::str::FromStr for SaveFormat {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(SaveFormat::Text),
+            "db" | "database" => Ok(SaveFormat::Database),
+            _ => Err(EngineError::Config(format!("Unknown save format: {:?}", s))),
+        }
+    }
+}
+
+impl fmt::Display for SaveFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SaveFormat::Text => "text",
+            SaveFormat::Database => "db",
+        })
+    }
+}
+
+/// The sections of a save file, by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SaveData {
+    sections: BTreeMap<String, String>,
+}
+
+impl SaveData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Saves `state` into the section `name`, replacing any previous contents.
+    pub fn write(&mut self, name: &str, state: &impl Saveable) -> Result<(), EngineError> {
+        self.insert(name, state.save()?);
+        Ok(())
+    }
+
+    /// Sets the text of the section `name`.
+    pub fn insert(&mut self, name: &str, text: impl Into<String>) {
+        self.sections.insert(name.to_string(), text.into());
+    }
+
+    /// Returns the text of the section `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.sections.get(name).map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// Returns the name and text of each section, ordered by name.
+    pub fn sections(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.sections
+            .iter()
+            .map(|(name, text)| (name.as_str(), text.as_str()))
+    }
+
+    /// Parses a save file; text before the first section is ignored.
+    pub fn parse(text: &str) -> Self {
+        let mut data = Self::new();
+        let mut current: Option<&mut String> = None;
+        for line in text.lines() {
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                current = Some(data.sections.entry(name.to_string()).or_default());
+            } else if let Some(section) = current.as_mut() {
+                // Lines that would read as a header are escaped with a backslash
+                let line = line.strip_prefix('\\').unwrap_or(line);
+                section.push_str(line);
+                section.push('\n');
+            }
+        }
+        data
+    }
+
+    /// Reads a save file, returning no sections if it does not exist.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, EngineError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Self::parse(&text)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Reads a save from the world database at `path`, returning no
+    /// sections if nothing was saved there yet.
+    pub fn load_database(path: impl AsRef<Path>) -> Result<Self, EngineError> {
+        #[cfg(feature = "db")]
+        return crate::db::WorldDb::open(path)?.load_save();
+        #[cfg(not(feature = "db"))]
+        return Err(no_database(path.as_ref()));
+    }
+}
+
+impl fmt::Display for SaveData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, text) in &self.sections {
+            writeln!(f, "[{}]", name)?;
+            for line in text.lines() {
+                if line.starts_with('[') || line.starts_with('\\') {
+                    f.write_str("\\")?;
+                }
+                writeln!(f, "{}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes `data` to `path` in `format`, keeping up to `backups` earlier
+/// text saves. The directory of `path` is created if needed.
+///
+/// A database is opened for this write only; keep a
+/// [`WorldDb`](crate::db::WorldDb) open and call its `write_save` to save
+/// to it repeatedly.
+pub fn write(
+    path: &Path,
+    format: SaveFormat,
+    backups: usize,
+    data: &SaveData,
+) -> Result<(), EngineError> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    match format {
+        SaveFormat::Text => write_rotated(path, backups, data),
+        #[cfg(feature = "db")]
+        SaveFormat::Database => crate::db::WorldDb::open(path)?.write_save(data),
+        #[cfg(not(feature = "db"))]
+        SaveFormat::Database => Err(no_database(path)),
+    }
+}
+
+#[cfg(not(feature = "db"))]
+fn no_database(path: &Path) -> EngineError {
+    EngineError::Config(format!(
+        "Cannot use the database {}: built without the `db` feature",
+        path.display()
+    ))
+}
+
+/// Writes `data` to `path`, first shifting earlier saves into up to
+/// `backups` numbered backups.
+///
+/// The new save is written to a temporary file and renamed into place, so
+/// a failed write leaves the previous save intact.
+pub fn write_rotated(path: &Path, backups: usize, data: &SaveData) -> Result<(), EngineError> {
+    let temporary = suffixed(path, "tmp");
+    fs::write(&temporary, data.to_string())?;
+    if backups > 0 && path.exists() {
+        // Renaming onto an existing file fails on some platforms
+        let oldest = suffixed(path, &backups.to_string());
+        if oldest.exists() {
+            fs::remove_file(oldest)?;
+        }
+        for index in (1..backups).rev() {
+            let older = suffixed(path, &index.to_string());
+            if older.exists() {
+                fs::rename(&older, suffixed(path, &(index + 1).to_string()))?;
+            }
+        }
+        fs::rename(path, suffixed(path, "1"))?;
+    }
+    fs::rename(temporary, path)?;
+    Ok(())
+}
+
+/// Returns `path` with `.suffix` appended to its file name.
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Decides when the next timed autosave is due.
+#[derive(Debug, Default)]
+pub(crate) struct AutosaveTimer {
+    elapsed: Duration,
+}
+
+impl AutosaveTimer {
+    /// Advances the timer, returning whether `interval` has elapsed since the last save.
+    pub fn due(&mut self, elapsed: Duration, interval: Option<Duration>) -> bool {
+        let Some(interval) = interval else {
+            return false;
+        };
+        self.elapsed += elapsed;
+        if self.elapsed < interval {
+            return false;
+        }
+        self.elapsed = Duration::ZERO;
+        true
+    }
+
+    /// Restarts the interval, e.g. after a save was requested.
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_data_round_trips() {
+        let mut data = SaveData::new();
+        data.insert("player", "x = 4\ny = 2\n");
+        data.insert("notes", "[not a header]\n\\n\n");
+        let parsed = SaveData::parse(&data.to_string());
+        assert_eq!(parsed, data);
+        assert_eq!(parsed.get("player"), Some("x = 4\ny = 2\n"));
+    }
+
+    #[test]
+    fn test_saves_rotate_into_backups() {
+        let dir = std::env::temp_dir().join(format!("coil-autosave-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("save.txt");
+        for turn in 1..=4 {
+            let mut data = SaveData::new();
+            data.insert("turn", turn.to_string());
+            write_rotated(&path, 2, &data).unwrap();
+        }
+        let turn = |path: &Path| {
+            SaveData::load(path)
+                .unwrap()
+                .get("turn")
+                .map(str::to_string)
+        };
+        assert_eq!(turn(&path).as_deref(), Some("4\n"));
+        assert_eq!(turn(&dir.join("save.txt.1")).as_deref(), Some("3\n"));
+        assert_eq!(turn(&dir.join("save.txt.2")).as_deref(), Some("2\n"));
+        assert!(!dir.join("save.txt.3").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_timer() {
+        let mut timer = AutosaveTimer::default();
+        let second = Duration::from_secs(1);
+        assert!(!timer.due(second * 10, None));
+        assert!(!timer.due(second, Some(second * 2)));
+        assert!(timer.due(second, Some(second * 2)));
+        assert!(!timer.due(second, Some(second * 2)));
+    }
+}
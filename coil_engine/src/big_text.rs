@@ -0,0 +1,106 @@
+//! Large block letters for titles.
+//!
+//! Each character is drawn from a 3×5 cell font with full blocks, which the
+//! `glyphs` setting turns into `#` on terminals without Unicode. Letters are
+//! upper-cased, and characters the font lacks are drawn as `?`.
+//!
+//! ```
+//! use coil_engine::big_text;
+//!
+//! let lines = big_text::lines("HI");
+//! assert_eq!(lines[0], "█ █ ███");
+//! assert_eq!(big_text::width("HI"), 7);
+//! ```
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::style::Style;
+
+/// Rows of each character
+pub const HEIGHT: u16 = 5;
+/// Columns of each character, without the column between characters
+const GLYPH_WIDTH: u16 = 3;
+
+/// Rows of each character, `#` for a filled cell.
+fn glyph(ch: char) -> [&'static str; 5] {
+    match ch.to_ascii_uppercase() {
+        'A' => ["###", "# #", "###", "# #", "# #"],
+        'B' => ["## ", "# #", "## ", "# #", "## "],
+        'C' => ["###", "#  ", "#  ", "#  ", "###"],
+        'D' => ["## ", "# #", "# #", "# #", "## "],
+        'E' => ["###", "#  ", "## ", "#  ", "###"],
+        'F' => ["###", "#  ", "## ", "#  ", "#  "],
+        'G' => ["###", "#  ", "# #", "# #", "###"],
+        'H' => ["# #", "# #", "###", "# #", "# #"],
+        'I' => ["###", " # ", " # ", " # ", "###"],
+        'J' => ["  #", "  #", "  #", "# #", "###"],
+        'K' => ["# #", "# #", "## ", "# #", "# #"],
+        'L' => ["#  ", "#  ", "#  ", "#  ", "###"],
+        'M' => ["# #", "###", "###", "# #", "# #"],
+        'N' => ["## ", "# #", "# #", "# #", "# #"],
+        'O' | '0' => ["###", "# #", "# #", "# #", "###"],
+        'P' => ["###", "# #", "###", "#  ", "#  "],
+        'Q' => ["###", "# #", "# #", "###", "  #"],
+        'R' => ["###", "# #", "## ", "# #", "# #"],
+        'S' | '5' => ["###", "#  ", "###", "  #", "###"],
+        'T' => ["###", " # ", " # ", " # ", " # "],
+        'U' => ["# #", "# #", "# #", "# #", "###"],
+        'V' => ["# #", "# #", "# #", "# #", " # "],
+        'W' => ["# #", "# #", "###", "###", "# #"],
+        'X' => ["# #", "# #", " # ", "# #", "# #"],
+        'Y' => ["# #", "# #", " # ", " # ", " # "],
+        'Z' => ["###", "  #", " # ", "#  ", "###"],
+        '1' => [" # ", "## ", " # ", " # ", "###"],
+        '2' => ["###", "  #", "###", "#  ", "###"],
+        '3' => ["###", "  #", "###", "  #", "###"],
+        '4' => ["# #", "# #", "###", "  #", "  #"],
+        '6' => ["###", "#  ", "###", "# #", "###"],
+        '7' => ["###", "  #", "  #", "  #", "  #"],
+        '8' => ["###", "# #", "###", "# #", "###"],
+        '9' => ["###", "# #", "###", "  #", "###"],
+        ' ' => ["   ", "   ", "   ", "   ", "   "],
+        '!' => [" # ", " # ", " # ", "   ", " # "],
+        '-' => ["   ", "   ", "###", "   ", "   "],
+        '.' => ["   ", "   ", "   ", "   ", " # "],
+        ':' => ["   ", " # ", "   ", " # ", "   "],
+        '\'' => [" # ", " # ", "   ", "   ", "   "],
+        _ => ["###", "  #", " ##", "   ", " # "],
+    }
+}
+
+/// Returns the width of `text` in block letters.
+pub fn width(text: &str) -> u16 {
+    let count = text.chars().count() as u16;
+    (count * (GLYPH_WIDTH + 1)).saturating_sub(1)
+}
+
+/// Returns the rows of `text` in block letters, top first.
+pub fn lines(text: &str) -> Vec<String> {
+    (0..HEIGHT as usize)
+        .map(|row| {
+            let line: Vec<String> = text
+                .chars()
+                .map(|ch| glyph(ch)[row].replace('#', "█"))
+                .collect();
+            line.join(" ").trim_end().to_string()
+        })
+        .collect()
+}
+
+/// Draws `text` in block letters with its top-left corner at (x,y).
+pub fn draw(
+    frame: &mut Frame<'_>,
+    x: u16,
+    y: u16,
+    text: &str,
+    style: Style,
+) -> Result<(), EngineError> {
+    for (row, line) in lines(text).iter().enumerate() {
+        // Draw only the blocks, leaving what is behind the gaps visible
+        for (column, ch) in line.chars().enumerate() {
+            if ch != ' ' {
+                frame.draw_styled(x + column as u16, y + row as u16, "█", style)?;
+            }
+        }
+    }
+    Ok(())
+}
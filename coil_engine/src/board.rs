@@ -0,0 +1,389 @@
+//! Helpers for grid board games.
+//!
+//! A [`Board`] holds the pieces of a square or hexagonal board, finds the
+//! cells a piece can reach and draws itself with highlighted moves. Hex
+//! boards are stored in "odd-r" offset coordinates, so `(x, y)` is always a
+//! column and a row; see [`hex`](crate::hex) for the axial math. Each cell is
+//! drawn two columns wide, which keeps squares roughly square and lets odd
+//! hex rows sit between the hexes of their neighbors. [`Turns`] keeps track
+//! of whose turn it is.
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::hex::Hex;
+use crate::style::Style;
+use crate::tilemap::Tile;
+use std::collections::{BTreeMap, VecDeque};
+
+/// The shape of a board's cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoardLayout {
+    /// Square cells with four orthogonal neighbors.
+    Square,
+    /// Square cells that also neighbor their diagonals, as for kings.
+    SquareDiagonal,
+    /// Pointy-top hexes with six neighbors.
+    Hex,
+}
+
+/// Something that stands on a board.
+pub trait Piece {
+    /// Returns the glyph and style the piece is drawn with.
+    fn tile(&self) -> Tile;
+}
+
+/// A grid of cells, each empty or holding a piece.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Board<P> {
+    width: u16,
+    height: u16,
+    layout: BoardLayout,
+    cells: Vec<Option<P>>,
+    empty: Tile,
+    highlights: BTreeMap<(u16, u16), Style>,
+}
+
+impl<P> Board<P> {
+    pub fn new(width: u16, height: u16, layout: BoardLayout) -> Self {
+        Self {
+            width,
+            height,
+            layout,
+            cells: (0..width as usize * height as usize)
+                .map(|_| None)
+                .collect(),
+            empty: Tile::new('.', Style::new()),
+            highlights: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the tile drawn for empty cells.
+    pub fn with_empty(mut self, empty: Tile) -> Self {
+        self.empty = empty;
+        self
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn layout(&self) -> BoardLayout {
+        self.layout
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32
+    }
+
+    /// Returns the piece at (x,y), if any.
+    pub fn get(&self, x: u16, y: u16) -> Option<&P> {
+        self.cells.get(self.index(x, y)?)?.as_ref()
+    }
+
+    /// Puts a piece at (x,y), returning the piece it replaced.
+    pub fn place(&mut self, x: u16, y: u16, piece: P) -> Option<P> {
+        let index = self.index(x, y)?;
+        self.cells[index].replace(piece)
+    }
+
+    /// Removes the piece at (x,y).
+    pub fn take(&mut self, x: u16, y: u16) -> Option<P> {
+        let index = self.index(x, y)?;
+        self.cells[index].take()
+    }
+
+    /// Moves the piece at `from` to `to`, returning the piece it captured.
+    pub fn move_piece(
+        &mut self,
+        from: (u16, u16),
+        to: (u16, u16),
+    ) -> Result<Option<P>, EngineError> {
+        if self.index(to.0, to.1).is_none() {
+            return Err(EngineError::game(format!("{:?} is off the board", to)));
+        }
+        let piece = self
+            .take(from.0, from.1)
+            .ok_or_else(|| EngineError::game(format!("no piece at {:?}", from)))?;
+        Ok(self.place(to.0, to.1, piece))
+    }
+
+    /// Iterates over the pieces on the board with their positions.
+    pub fn pieces(&self) -> impl Iterator<Item = ((u16, u16), &P)> {
+        let width = self.width.max(1) as usize;
+        self.cells
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, cell)| {
+                let position = ((index % width) as u16, (index / width) as u16);
+                cell.as_ref().map(|piece| (position, piece))
+            })
+    }
+
+    /// Returns the cells adjacent to (x,y) on the board.
+    pub fn neighbors(&self, x: u16, y: u16) -> Vec<(u16, u16)> {
+        let (x, y) = (x as i32, y as i32);
+        let candidates: Vec<(i32, i32)> = match self.layout {
+            BoardLayout::Square => vec![(x + 1, y), (x, y + 1), (x - 1, y), (x, y - 1)],
+            BoardLayout::SquareDiagonal => (-1..=1)
+                .flat_map(|dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+                .filter(|&cell| cell != (x, y))
+                .collect(),
+            BoardLayout::Hex => Hex::from_offset(x, y)
+                .neighbors()
+                .iter()
+                .map(Hex::to_offset)
+                .collect(),
+        };
+        candidates
+            .into_iter()
+            .filter(|&(x, y)| self.contains(x, y))
+            .map(|(x, y)| (x as u16, y as u16))
+            .collect()
+    }
+
+    /// Returns the number of steps between two cells.
+    pub fn distance(&self, a: (u16, u16), b: (u16, u16)) -> u32 {
+        let dx = (a.0 as i32 - b.0 as i32).unsigned_abs();
+        let dy = (a.1 as i32 - b.1 as i32).unsigned_abs();
+        match self.layout {
+            BoardLayout::Square => dx + dy,
+            BoardLayout::SquareDiagonal => dx.max(dy),
+            BoardLayout::Hex => Hex::from_offset(a.0 as i32, a.1 as i32)
+                .distance(Hex::from_offset(b.0 as i32, b.1 as i32)),
+        }
+    }
+
+    /// Returns the cells reachable from `from` in up to `steps` steps,
+    /// walking only through cells for which `passable` holds.
+    ///
+    /// The starting cell is not included. Cells that are not passable are
+    /// never entered, so games that allow capturing check the neighbors of
+    /// the result themselves.
+    pub fn reachable(
+        &self,
+        from: (u16, u16),
+        steps: u32,
+        passable: impl Fn((u16, u16), Option<&P>) -> bool,
+    ) -> Vec<(u16, u16)> {
+        let mut seen = BTreeMap::from([(from, 0)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(cell) = queue.pop_front() {
+            let distance = seen[&cell];
+            if distance == steps {
+                continue;
+            }
+            for next in self.neighbors(cell.0, cell.1) {
+                if !seen.contains_key(&next) && passable(next, self.get(next.0, next.1)) {
+                    seen.insert(next, distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        seen.remove(&from);
+        seen.into_keys().collect()
+    }
+
+    /// Highlights a cell with the background of `style`, e.g. a legal move.
+    pub fn highlight(&mut self, x: u16, y: u16, style: Style) {
+        if self.index(x, y).is_some() {
+            self.highlights.insert((x, y), style);
+        }
+    }
+
+    /// Highlights every cell in `cells`.
+    pub fn highlight_all(&mut self, cells: impl IntoIterator<Item = (u16, u16)>, style: Style) {
+        for (x, y) in cells {
+            self.highlight(x, y, style);
+        }
+    }
+
+    pub fn clear_highlights(&mut self) {
+        self.highlights.clear();
+    }
+
+    /// Returns the local position a cell is drawn at.
+    pub fn cell_position(&self, x: u16, y: u16) -> (u16, u16) {
+        match self.layout {
+            BoardLayout::Hex => (x * 2 + (y & 1), y),
+            _ => (x * 2, y),
+        }
+    }
+
+    /// Returns the cell drawn at local (x,y), e.g. under the mouse.
+    pub fn cell_at(&self, x: u16, y: u16) -> Option<(u16, u16)> {
+        let column = match self.layout {
+            BoardLayout::Hex => x.checked_sub(y & 1)? / 2,
+            _ => x / 2,
+        };
+        self.index(column, y).map(|_| (column, y))
+    }
+
+    /// Returns the area the board covers when drawn at local (0,0).
+    pub fn bounds(&self) -> Rect {
+        let shift = (self.layout == BoardLayout::Hex && self.height > 1) as u16;
+        Rect::new(0, 0, self.width * 2 + shift, self.height)
+    }
+
+    fn index(&self, x: u16, y: u16) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| y as usize * self.width as usize + x as usize)
+    }
+}
+
+impl<P: Piece> Board<P> {
+    /// Draws the board with its top-left cell at local (0,0).
+    pub fn draw(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        let mut glyph = [0; 4];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mut tile = self.get(x, y).map_or(self.empty, Piece::tile);
+                if let Some(highlight) = self.highlights.get(&(x, y)) {
+                    tile.style.bg = highlight.bg;
+                }
+                let (column, row) = self.cell_position(x, y);
+                frame.draw_styled(column, row, tile.ch.encode_utf8(&mut glyph), tile.style)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What changed when a turn ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TurnChange {
+    /// The player whose turn ended
+    pub ended: usize,
+    /// The player whose turn starts
+    pub started: usize,
+    /// Whether the turn order wrapped around into a new round
+    pub new_round: bool,
+}
+
+/// Turn order for players numbered `0..players`.
+///
+/// Players who are out of the game are skipped; games react to each turn
+/// by inspecting the [`TurnChange`] returned by [`Turns::end_turn`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Turns {
+    current: usize,
+    round: u32,
+    active: Vec<bool>,
+}
+
+impl Turns {
+    pub fn new(players: usize) -> Self {
+        Self {
+            current: 0,
+            round: 1,
+            active: vec![true; players.max(1)],
+        }
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Returns the round number, starting at 1.
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    /// Takes a player out of the turn order.
+    pub fn eliminate(&mut self, player: usize) {
+        if let Some(active) = self.active.get_mut(player) {
+            *active = false;
+        }
+    }
+
+    pub fn is_active(&self, player: usize) -> bool {
+        self.active.get(player).copied().unwrap_or(false)
+    }
+
+    /// Returns the players still in the game.
+    pub fn remaining(&self) -> usize {
+        self.active.iter().filter(|active| **active).count()
+    }
+
+    /// Passes the turn to the next active player.
+    pub fn end_turn(&mut self) -> TurnChange {
+        let ended = self.current;
+        let mut new_round = false;
+        for _ in 0..self.active.len() {
+            self.current = (self.current + 1) % self.active.len();
+            new_round |= self.current == 0;
+            if self.active[self.current] {
+                break;
+            }
+        }
+        if new_round {
+            self.round += 1;
+        }
+        TurnChange {
+            ended,
+            started: self.current,
+            new_round,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::TestRenderer;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Stone(char);
+
+    impl Piece for Stone {
+        fn tile(&self) -> Tile {
+            Tile::new(self.0, Style::new())
+        }
+    }
+
+    #[test]
+    fn test_board_moves_and_draws_hexes() {
+        let mut board = Board::new(3, 2, BoardLayout::Hex);
+        board.place(0, 0, Stone('a'));
+        board.place(1, 1, Stone('b'));
+        assert_eq!(board.neighbors(0, 0), [(1, 0), (0, 1)]);
+        assert_eq!(board.neighbors(1, 1).len(), 4);
+        assert_eq!(board.distance((0, 0), (2, 1)), 3);
+
+        let moves = board.reachable((0, 0), 2, |_, piece| piece.is_none());
+        assert_eq!(moves, [(0, 1), (1, 0), (2, 0)]);
+        assert_eq!(board.move_piece((1, 1), (0, 0)).unwrap(), Some(Stone('a')));
+        assert!(board.move_piece((1, 1), (0, 0)).is_err());
+        assert_eq!(board.pieces().collect::<Vec<_>>(), [((0, 0), &Stone('b'))]);
+
+        board.highlight_all(moves, crate::style!(white on blue));
+        let mut renderer = TestRenderer::new(7, 2);
+        board
+            .draw(&mut Frame::new(&mut renderer, board.bounds(), 1.0))
+            .unwrap();
+        assert_eq!(renderer.rows(), ["b . .", " . . ."]);
+        assert_eq!(
+            renderer.cell_at(2, 0).unwrap().bg,
+            crate::style!(white on blue).bg
+        );
+        assert_eq!(board.cell_at(3, 1), Some((1, 1)));
+        assert_eq!(board.cell_at(0, 1), None);
+    }
+
+    #[test]
+    fn test_turns_skip_eliminated_players() {
+        let mut turns = Turns::new(3);
+        turns.eliminate(1);
+        let change = turns.end_turn();
+        assert_eq!(
+            (change.ended, change.started, change.new_round),
+            (0, 2, false)
+        );
+        let change = turns.end_turn();
+        assert_eq!((change.started, change.new_round), (0, true));
+        assert_eq!(turns.round(), 2);
+        assert_eq!(turns.remaining(), 2);
+    }
+}
@@ -0,0 +1,122 @@
+//! Scrolling view onto the game world.
+//!
+//! The camera is held by the [`Context`](crate::context::Context) so any node
+//! can move it, and [`Layers`](crate::nodes::Layers) applies it to the world
+//! while leaving the HUD pinned to the screen. World nodes that report
+//! their [`Bounds`] are skipped while the camera does not show them.
+
+/// World position shown at the top-left corner of the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Camera {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Camera {
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    /// Moves the camera so the world position (x,y) is in the middle of a
+    /// view of the given size.
+    pub fn center_on(&mut self, x: i32, y: i32, view: (u16, u16)) {
+        self.x = x - view.0 as i32 / 2;
+        self.y = y - view.1 as i32 / 2;
+    }
+
+    /// Converts a world position to a position on screen.
+    pub fn world_to_screen(&self, x: i32, y: i32) -> (i32, i32) {
+        (x - self.x, y - self.y)
+    }
+
+    /// Converts a screen cell, such as a mouse position, to a world position.
+    pub fn screen_to_world(&self, x: u16, y: u16) -> (i32, i32) {
+        (x as i32 + self.x, y as i32 + self.y)
+    }
+
+    /// Returns whether any of `bounds` shows in a view of the given size.
+    pub fn is_visible(&self, bounds: Bounds, view: (u16, u16)) -> bool {
+        let (x, y) = self.world_to_screen(bounds.x, bounds.y);
+        x < view.0 as i32
+            && y < view.1 as i32
+            && x + bounds.width as i32 > 0
+            && y + bounds.height as i32 > 0
+    }
+}
+
+/// An area of the world, which unlike a [`Rect`](crate::geometry::Rect) can
+/// start at negative positions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bounds {
+    pub x: i32,
+    pub y: i32,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Bounds {
+    pub const fn new(x: i32, y: i32, width: u16, height: u16) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the smallest bounds containing both.
+    pub fn union(self, other: Bounds) -> Bounds {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x as i64 + self.width as i64).max(other.x as i64 + other.width as i64);
+        let bottom = (self.y as i64 + self.height as i64).max(other.y as i64 + other.height as i64);
+        let width = (right - x as i64).min(u16::MAX as i64) as u16;
+        let height = (bottom - y as i64).min(u16::MAX as i64) as u16;
+        Bounds::new(x, y, width, height)
+    }
+
+    /// Returns the smallest bounds containing all of `bounds`, or `None`,
+    /// meaning unbounded, if there are none or any of them is `None`.
+    pub fn enclosing(bounds: impl IntoIterator<Item = Option<Bounds>>) -> Option<Bounds> {
+        let mut bounds = bounds.into_iter();
+        let first = bounds.next()??;
+        bounds.try_fold(first, |all, next| Some(all.union(next?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_camera_conversions() {
+        let mut camera = Camera::default();
+        camera.center_on(50, 20, (80, 24));
+        assert_eq!(camera, Camera::new(10, 8));
+        assert_eq!(camera.world_to_screen(50, 20), (40, 12));
+        assert_eq!(camera.screen_to_world(40, 12), (50, 20));
+        assert_eq!(camera.world_to_screen(0, 0), (-10, -8));
+    }
+
+    #[test]
+    fn test_visibility() {
+        let camera = Camera::new(10, 8);
+        let view = (80, 24);
+        assert!(camera.is_visible(Bounds::new(0, 0, 11, 9), view));
+        assert!(!camera.is_visible(Bounds::new(0, 0, 10, 9), view));
+        assert!(camera.is_visible(Bounds::new(89, 31, 5, 5), view));
+        assert!(!camera.is_visible(Bounds::new(90, 0, 5, 40), view));
+    }
+
+    #[test]
+    fn test_enclosing_bounds() {
+        let a = Bounds::new(-2, 1, 3, 2);
+        let b = Bounds::new(4, -1, 1, 1);
+        assert_eq!(a.union(b), Bounds::new(-2, -1, 7, 4));
+        assert_eq!(Bounds::enclosing([Some(a), Some(b)]), Some(a.union(b)));
+        assert_eq!(Bounds::enclosing([Some(a), None]), None);
+        assert_eq!(Bounds::enclosing([]), None);
+    }
+}
@@ -0,0 +1,404 @@
+//! Small charts of numeric series: sparklines, bar charts and line plots.
+//!
+//! A [`Series`] keeps the latest values of a stream, such as frame times or
+//! the population of a simulation, in a fixed-size ring buffer, so pushing
+//! a value never allocates. The charts only hold their look and draw a
+//! series into an area of a frame, showing as many of the latest values as
+//! fit. Unless given a fixed range, they scale to the values shown.
+//!
+//! ```
+//! use coil_engine::charts::{Series, Sparkline};
+//!
+//! let mut frame_times = Series::new(120);
+//! for ms in [16.0, 17.0, 33.0, 16.0] {
+//!     frame_times.push(ms);
+//! }
+//! assert_eq!(frame_times.max(), Some(33.0));
+//! assert_eq!(Sparkline::new().line(&frame_times, 4), "▄▅█▄");
+//! ```
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::renderer::Cell;
+use crate::style::Style;
+use std::collections::VecDeque;
+
+/// Blocks filled from the bottom, by eighths.
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// The latest values of a numeric stream, oldest first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    values: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl Series {
+    /// Creates an empty series keeping the last `capacity` values.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            values: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Appends a value, dropping the oldest one if the series is full.
+    pub fn push(&mut self, value: f32) {
+        if self.values.len() == self.capacity {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Returns the newest value.
+    pub fn last(&self) -> Option<f32> {
+        self.values.back().copied()
+    }
+
+    pub fn min(&self) -> Option<f32> {
+        self.values.iter().copied().reduce(f32::min)
+    }
+
+    pub fn max(&self) -> Option<f32> {
+        self.values.iter().copied().reduce(f32::max)
+    }
+
+    /// Iterates over the values, oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = f32> + ExactSizeIterator + '_ {
+        self.values.iter().copied()
+    }
+
+    /// Iterates over the latest `count` values, oldest first.
+    pub fn latest(&self, count: usize) -> impl Iterator<Item = f32> + '_ {
+        self.iter().skip(self.len().saturating_sub(count))
+    }
+}
+
+/// Returns the range to scale `values` to: `fixed`, or from zero (or the
+/// lowest value, if negative) to the highest value.
+fn range(fixed: Option<(f32, f32)>, values: impl Iterator<Item = f32>) -> (f32, f32) {
+    if let Some(range) = fixed {
+        return range;
+    }
+    let (min, max) = values.fold((0.0f32, f32::MIN), |(min, max), v| (min.min(v), max.max(v)));
+    (min, max.max(min + f32::EPSILON))
+}
+
+/// Returns where `value` lies in `range`, from 0.0 to 1.0.
+fn scale(value: f32, (min, max): (f32, f32)) -> f32 {
+    ((value - min) / (max - min)).clamp(0.0, 1.0)
+}
+
+/// A one-row chart of the latest values, one cell each.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sparkline {
+    range: Option<(f32, f32)>,
+    style: Style,
+}
+
+impl Sparkline {
+    pub fn new() -> Self {
+        Self {
+            range: None,
+            style: crate::style!(green),
+        }
+    }
+
+    /// Scales values between `min` and `max` instead of the values shown.
+    pub fn with_range(mut self, min: f32, max: f32) -> Self {
+        self.range = Some((min, max.max(min + f32::EPSILON)));
+        self
+    }
+
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Returns the sparkline of the latest `width` values as text.
+    pub fn line(&self, series: &Series, width: u16) -> String {
+        let range = range(self.range, series.latest(width as usize));
+        series
+            .latest(width as usize)
+            .map(|value| BARS[(scale(value, range) * 7.0).round() as usize])
+            .collect()
+    }
+
+    /// Draws the sparkline at (x,y), right-aligned in `width` cells so the
+    /// newest value is always at the right edge.
+    pub fn draw(
+        &self,
+        frame: &mut Frame<'_>,
+        x: u16,
+        y: u16,
+        width: u16,
+        series: &Series,
+    ) -> Result<(), EngineError> {
+        let line = self.line(series, width);
+        let offset = width - line.chars().count() as u16;
+        frame.draw_styled(x + offset, y, &line, self.style)?;
+        Ok(())
+    }
+}
+
+impl Default for Sparkline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A chart of vertical bars, one per value, filled in eighths of a cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BarChart {
+    range: Option<(f32, f32)>,
+    bar_width: u16,
+    gap: u16,
+    style: Style,
+    /// Style of bars at or above the warning value, e.g. slow frames
+    warning: Option<(f32, Style)>,
+}
+
+impl BarChart {
+    pub fn new() -> Self {
+        Self {
+            range: None,
+            bar_width: 1,
+            gap: 0,
+            style: crate::style!(cyan),
+            warning: None,
+        }
+    }
+
+    /// Scales values between `min` and `max` instead of the values shown.
+    pub fn with_range(mut self, min: f32, max: f32) -> Self {
+        self.range = Some((min, max.max(min + f32::EPSILON)));
+        self
+    }
+
+    /// Sets how many cells wide each bar is and the cells between bars.
+    pub fn with_bars(mut self, width: u16, gap: u16) -> Self {
+        self.bar_width = width.max(1);
+        self.gap = gap;
+        self
+    }
+
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Draws bars of values at or above `value` in `style`.
+    pub fn with_warning(mut self, value: f32, style: Style) -> Self {
+        self.warning = Some((value, style));
+        self
+    }
+
+    /// Draws the latest values that fit in `area`, newest on the right.
+    pub fn draw(
+        &self,
+        frame: &mut Frame<'_>,
+        area: Rect,
+        series: &Series,
+    ) -> Result<(), EngineError> {
+        let step = self.bar_width + self.gap;
+        let count = ((area.width + self.gap) / step) as usize;
+        let range = range(self.range, series.latest(count));
+        let shown = series.len().min(count);
+        let left = area.right() - (shown as u16 * step).saturating_sub(self.gap);
+        for (index, value) in series.latest(count).enumerate() {
+            let style = match self.warning {
+                Some((threshold, style)) if value >= threshold => style,
+                _ => self.style,
+            };
+            let mut eighths = (scale(value, range) * area.height as f32 * 8.0).round() as u32;
+            for row in (area.y..area.bottom()).rev() {
+                let filled = eighths.min(8);
+                eighths -= filled;
+                if filled == 0 {
+                    break;
+                }
+                for column in 0..self.bar_width {
+                    let x = left + index as u16 * step + column;
+                    frame.draw_cell(x, row, Cell::styled(BARS[filled as usize - 1], style))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for BarChart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A line chart drawn with braille dots, two values per cell and four
+/// rows of dots per cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinePlot {
+    range: Option<(f32, f32)>,
+    style: Style,
+}
+
+impl LinePlot {
+    pub fn new() -> Self {
+        Self {
+            range: None,
+            style: crate::style!(yellow),
+        }
+    }
+
+    /// Scales values between `min` and `max` instead of the values shown.
+    pub fn with_range(mut self, min: f32, max: f32) -> Self {
+        self.range = Some((min, max.max(min + f32::EPSILON)));
+        self
+    }
+
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Returns the braille cells of the plot, row by row, `area` sized.
+    pub fn cells(&self, series: &Series, (width, height): (u16, u16)) -> Vec<Vec<char>> {
+        let (dots_x, dots_y) = (width as usize * 2, height as usize * 4);
+        let mut dots = vec![vec![0u8; width as usize]; height as usize];
+        if dots_y > 0 {
+            let range = range(self.range, series.latest(dots_x));
+            let rows: Vec<usize> = series
+                .latest(dots_x)
+                .map(|value| ((1.0 - scale(value, range)) * (dots_y - 1) as f32).round() as usize)
+                .collect();
+            let left = dots_x - rows.len();
+            for (index, &row) in rows.iter().enumerate() {
+                // Join each point to the previous one with a vertical run
+                let previous = if index > 0 { rows[index - 1] } else { row };
+                let (top, bottom) = if previous < row {
+                    (previous + 1, row)
+                } else {
+                    (row, previous.saturating_sub(1).max(row))
+                };
+                let x = left + index;
+                for y in top..=bottom {
+                    dots[y / 4][x / 2] |= braille_dot(x % 2, y % 4);
+                }
+            }
+        }
+        dots.into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|bits| char::from_u32(0x2800 + bits as u32).unwrap_or(' '))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Draws the latest values that fit in `area`, newest on the right;
+    /// empty cells are left as they are.
+    pub fn draw(
+        &self,
+        frame: &mut Frame<'_>,
+        area: Rect,
+        series: &Series,
+    ) -> Result<(), EngineError> {
+        for (y, row) in self.cells(series, area.size()).into_iter().enumerate() {
+            for (x, ch) in row.into_iter().enumerate() {
+                if ch != '\u{2800}' {
+                    let cell = Cell::styled(ch, self.style);
+                    frame.draw_cell(area.x + x as u16, area.y + y as u16, cell)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for LinePlot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the bit of the braille dot in `column` 0-1 and `row` 0-3 of a cell.
+fn braille_dot(column: usize, row: usize) -> u8 {
+    const DOTS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+    DOTS[column][row]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::TestRenderer;
+
+    fn series(values: &[f32], capacity: usize) -> Series {
+        let mut series = Series::new(capacity);
+        for &value in values {
+            series.push(value);
+        }
+        series
+    }
+
+    #[test]
+    fn test_series_keeps_latest_values() {
+        let series = series(&[1.0, 5.0, 3.0, 2.0], 3);
+        assert_eq!(series.iter().collect::<Vec<_>>(), [5.0, 3.0, 2.0]);
+        assert_eq!(series.latest(2).collect::<Vec<_>>(), [3.0, 2.0]);
+        assert_eq!(
+            (series.min(), series.max(), series.last()),
+            (Some(2.0), Some(5.0), Some(2.0))
+        );
+
+        let line = Sparkline::new().with_range(0.0, 8.0);
+        assert_eq!(line.line(&series, 10), "▅▄▃");
+        assert_eq!(line.line(&series, 1), "▃");
+    }
+
+    #[test]
+    fn test_bar_chart_fills_in_eighths() {
+        let mut renderer = TestRenderer::new(6, 2);
+        let chart = BarChart::new()
+            .with_range(0.0, 16.0)
+            .with_bars(1, 1)
+            .with_warning(16.0, crate::style!(red));
+        chart
+            .draw(
+                &mut Frame::new(&mut renderer, Rect::new(0, 0, 6, 2), 0.0),
+                Rect::new(0, 0, 6, 2),
+                &series(&[99.0, 4.0, 12.0, 16.0], 8),
+            )
+            .unwrap();
+        // The three latest values fit, newest at the right edge
+        assert_eq!(renderer.rows(), ["   ▄ █", " ▄ █ █"]);
+        assert_eq!(renderer.cell_at(5, 0).unwrap().fg, crate::style!(red).fg);
+        assert_eq!(renderer.cell_at(3, 0).unwrap().fg, crate::style!(cyan).fg);
+    }
+
+    #[test]
+    fn test_line_plot_joins_points() {
+        let plot = LinePlot::new().with_range(0.0, 7.0);
+        let cells = plot.cells(&series(&[0.0, 7.0, 7.0], 8), (2, 2));
+        // Rising from the bottom dot row to the top one, then level
+        assert_eq!(cells[0].iter().collect::<String>(), "⠀⡏");
+        assert_eq!(cells[1].iter().collect::<String>(), "⢀⠇");
+        let flat = plot.cells(&series(&[7.0, 7.0, 7.0, 7.0], 8), (2, 1));
+        assert_eq!(flat[0].iter().collect::<String>(), "⠉⠉");
+    }
+}
@@ -0,0 +1,248 @@
+//! Input from an IRC or Twitch chat channel, for "chat plays" games.
+//!
+//! [`ChatInput`] joins a channel and turns chat commands such as `!left`
+//! into key presses sent with [`Context::send_event`], so the game handles
+//! them like keys pressed by the player. Commands are named after the
+//! actions of an [`ActionMap`] and press the action's first key, or bound
+//! to keys one by one. Add the node anywhere in the tree:
+//!
+//! ```no_run
+//! use coil_engine::actions::ActionMap;
+//! use coil_engine::chat::{ChatInput, ChatServer};
+//! use crossterm::event::KeyCode;
+//!
+//! let actions = ActionMap::new()
+//!     .with_binding("left", KeyCode::Left)
+//!     .with_binding("right", KeyCode::Right);
+//! let chat = ChatInput::connect(&ChatServer::twitch("mychannel"))
+//!     .unwrap()
+//!     .with_actions(&actions);
+//! ```
+//!
+//! Twitch accepts anonymous read-only logins over plain IRC, which is all
+//! this needs; other servers take a nick and an optional password.
+use crate::actions::{ActionMap, KeyBinding};
+use crate::context::Context;
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::nodes::Node;
+use crate::replay;
+use crossterm::event::Event;
+use log::{debug, warn};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/// Messages kept for [`ChatInput::take_messages`] before the oldest are dropped
+const MAX_MESSAGES: usize = 100;
+
+/// How to reach a chat channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatServer {
+    /// `host:port` of the IRC server
+    pub address: String,
+    pub nick: String,
+    /// Sent as `PASS` before logging in, e.g. a Twitch `oauth:` token
+    pub password: Option<String>,
+    /// Channel to join, without the leading `#`
+    pub channel: String,
+}
+
+impl ChatServer {
+    /// Joins a Twitch channel anonymously.
+    pub fn twitch(channel: impl Into<String>) -> Self {
+        Self {
+            address: "irc.chat.twitch.tv:6667".to_string(),
+            nick: "justinfan31415".to_string(),
+            password: None,
+            channel: channel.into(),
+        }
+    }
+}
+
+/// A message posted in the channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatMessage {
+    pub user: String,
+    pub text: String,
+}
+
+/// A node turning chat commands into key presses.
+///
+/// Messages are read on a background thread and handled at the next
+/// update. A message whose first word is a command, after the prefix (`!`
+/// by default), presses its key; other messages are only kept to show,
+/// e.g. in a chat box.
+pub struct ChatInput {
+    messages: Receiver<ChatMessage>,
+    /// Recent messages, oldest first
+    recent: Vec<ChatMessage>,
+    commands: Vec<(String, KeyBinding)>,
+    prefix: String,
+}
+
+impl ChatInput {
+    /// Connects to `server` and joins its channel.
+    pub fn connect(server: &ChatServer) -> Result<Self, EngineError> {
+        let mut stream = TcpStream::connect(&server.address)?;
+        if let Some(password) = &server.password {
+            write!(stream, "PASS {}\r\n", password)?;
+        }
+        write!(stream, "NICK {}\r\n", server.nick)?;
+        write!(stream, "JOIN #{}\r\n", server.channel.to_lowercase())?;
+        let reader = BufReader::new(stream.try_clone()?);
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => {
+                        warn!("Chat connection lost: {}", e);
+                        return;
+                    }
+                };
+                if let Some(token) = line.strip_prefix("PING ") {
+                    if write!(stream, "PONG {}\r\n", token).is_err() {
+                        return;
+                    }
+                } else if let Some(message) = parse_message(&line) {
+                    if sender.send(message).is_err() {
+                        return;
+                    }
+                } else {
+                    debug!("Chat: {}", line);
+                }
+            }
+        });
+        Ok(Self::from_receiver(messages))
+    }
+
+    /// Creates an input reading messages from `messages`, e.g. a bridge to
+    /// another chat service.
+    pub fn from_receiver(messages: Receiver<ChatMessage>) -> Self {
+        Self {
+            messages,
+            recent: Vec::new(),
+            commands: Vec::new(),
+            prefix: "!".to_string(),
+        }
+    }
+
+    /// Makes each action of `actions` a command pressing its first key.
+    pub fn with_actions(mut self, actions: &ActionMap) -> Self {
+        for action in actions.actions() {
+            if let Some(&key) = actions.bindings(action).first() {
+                self.commands.push((action.to_lowercase(), key));
+            }
+        }
+        self
+    }
+
+    /// Makes `command` press `key`; commands are matched ignoring case.
+    pub fn with_command(mut self, command: &str, key: impl Into<KeyBinding>) -> Self {
+        self.commands.push((command.to_lowercase(), key.into()));
+        self
+    }
+
+    /// Sets the text commands start with; an empty prefix makes any message
+    /// starting with a command's name count.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Returns the messages received since the last call, oldest first.
+    pub fn take_messages(&mut self) -> Vec<ChatMessage> {
+        std::mem::take(&mut self.recent)
+    }
+
+    /// Returns the key a message's command presses, if it is one.
+    fn command(&self, text: &str) -> Option<KeyBinding> {
+        let word = text.split_whitespace().next()?.strip_prefix(&self.prefix)?;
+        self.commands
+            .iter()
+            .find(|(command, _)| command.eq_ignore_ascii_case(word))
+            .map(|&(_, key)| key)
+    }
+}
+
+impl Node for ChatInput {
+    fn update(&mut self, ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+        while let Ok(message) = self.messages.try_recv() {
+            if let Some(key) = self.command(&message.text) {
+                ctx.send_event(replay::key_event(key));
+            }
+            if self.recent.len() == MAX_MESSAGES {
+                self.recent.remove(0);
+            }
+            self.recent.push(message);
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+        Ok(false)
+    }
+
+    fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        Ok(())
+    }
+}
+
+/// Parses a `PRIVMSG` line, ignoring IRCv3 tags.
+fn parse_message(line: &str) -> Option<ChatMessage> {
+    let line = match line.strip_prefix('@') {
+        Some(tagged) => tagged.split_once(' ')?.1,
+        None => line,
+    };
+    let (prefix, rest) = line.strip_prefix(':')?.split_once(' ')?;
+    let (_channel, text) = rest.strip_prefix("PRIVMSG ")?.split_once(" :")?;
+    let user = prefix.split('!').next()?;
+    Some(ChatMessage {
+        user: user.to_string(),
+        text: text.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    #[test]
+    fn test_parse_message() {
+        let line = "@badges=;color= :viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #game :!Left now";
+        assert_eq!(
+            parse_message(line),
+            Some(ChatMessage {
+                user: "viewer".to_string(),
+                text: "!Left now".to_string()
+            })
+        );
+        assert_eq!(parse_message(":tmi.twitch.tv 001 justinfan :Welcome"), None);
+    }
+
+    #[test]
+    fn test_commands_press_keys() {
+        let (sender, receiver) = mpsc::channel();
+        let actions = ActionMap::new().with_binding("jump", KeyCode::Char(' '));
+        let mut chat = ChatInput::from_receiver(receiver)
+            .with_actions(&actions)
+            .with_command("left", KeyCode::Left);
+        for text in ["!LEFT", "left", "!jump high", "hello"] {
+            let user = "viewer".to_string();
+            let text = text.to_string();
+            sender.send(ChatMessage { user, text }).unwrap();
+        }
+        let mut ctx = Context::new(GameConfig::new());
+        chat.update(&mut ctx, 0.1).unwrap();
+        let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+        assert_eq!(
+            ctx.take_sent_events(),
+            [key(KeyCode::Left), key(KeyCode::Char(' '))]
+        );
+        assert_eq!(chat.take_messages().len(), 4);
+    }
+}
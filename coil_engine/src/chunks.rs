@@ -0,0 +1,423 @@
+//! Worlds streamed in chunks around the camera.
+//!
+//! A [`ChunkManager`] splits an unbounded world into equally sized chunks,
+//! e.g. [`TileMap`](crate::tilemap::TileMap)s, and keeps only those near
+//! the camera in memory. Call [`ChunkManager::update`] every tick from the
+//! node owning the world: chunks coming into range are generated, or read
+//! back if they were saved, on the [job](crate::jobs) workers, and chunks
+//! far out of range are dropped. Chunks that were changed through
+//! [`ChunkManager::get_mut`] are written to the storage directory first,
+//! one file per chunk named after its position (`-1_3.chunk`), so the
+//! player's changes survive even though the world is generated again.
+//! With the `db` feature they can go to a table of a
+//! [`WorldDb`](crate::db::WorldDb) instead.
+//!
+//! ```no_run
+//! use coil_engine::chunks::ChunkManager;
+//! use coil_engine::style::Style;
+//! use coil_engine::tilemap::{Tile, TileMap};
+//!
+//! let palette = vec![Tile::new('.', Style::new()), Tile::new('#', Style::new())];
+//! let load_palette = palette.clone();
+//! let world = ChunkManager::new((32, 16), move |_position| {
+//!     TileMap::new(32, 16, palette.clone())
+//! })
+//! .with_storage("world", move |text| TileMap::parse(text, load_palette.clone()));
+//! ```
+use crate::autosave::Saveable;
+use crate::camera::{Bounds, Camera};
+use crate::context::Context;
+use crate::errors::EngineError;
+use crate::jobs::JobId;
+use log::warn;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Position of a chunk in the grid of chunks; chunk (0,0) starts at world (0,0).
+pub type ChunkPos = (i32, i32);
+
+type Generate<C> = Arc<dyn Fn(ChunkPos) -> C + Send + Sync>;
+type Load<C> = Arc<dyn Fn(&str) -> Result<C, EngineError> + Send + Sync>;
+
+/// Where modified chunks are kept.
+#[derive(Clone)]
+enum Storage {
+    /// One file per chunk in a directory
+    Directory(PathBuf),
+    /// One record per chunk in a database table, keeping the database open
+    #[cfg(feature = "db")]
+    Database(crate::db::WorldDb, sled::Tree),
+}
+
+impl Storage {
+    /// Returns the stored text of a chunk, if it was stored.
+    fn read(&self, position: ChunkPos) -> Result<Option<String>, EngineError> {
+        match self {
+            Storage::Directory(directory) => {
+                let path = chunk_path(directory, position);
+                match fs::read_to_string(&path) {
+                    Ok(text) => Ok(Some(text)),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                    Err(e) => Err(e.into()),
+                }
+            }
+            #[cfg(feature = "db")]
+            Storage::Database(_, table) => {
+                let value = table
+                    .get(chunk_key(position))
+                    .map_err(std::io::Error::from)?;
+                value
+                    .map(|value| String::from_utf8(value.to_vec()).map_err(EngineError::game))
+                    .transpose()
+            }
+        }
+    }
+
+    fn write(&self, position: ChunkPos, text: String) -> Result<(), EngineError> {
+        match self {
+            Storage::Directory(directory) => {
+                fs::create_dir_all(directory)?;
+                fs::write(chunk_path(directory, position), text)?;
+            }
+            #[cfg(feature = "db")]
+            Storage::Database(_, table) => {
+                table
+                    .insert(chunk_key(position), text.into_bytes())
+                    .map_err(std::io::Error::from)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Waits until the chunks written so far are on disk.
+    fn flush(&self) -> Result<(), EngineError> {
+        match self {
+            // Each file is complete once written
+            Storage::Directory(_) => Ok(()),
+            #[cfg(feature = "db")]
+            Storage::Database(db, _) => db.flush(),
+        }
+    }
+}
+
+struct Loaded<C> {
+    chunk: C,
+    /// Whether the chunk changed since it was generated or loaded
+    modified: bool,
+}
+
+/// Loads, generates and unloads the chunks of a world around the camera.
+pub struct ChunkManager<C> {
+    size: (u16, u16),
+    generate: Generate<C>,
+    /// Where modified chunks are saved and how to read them back
+    storage: Option<(Storage, Load<C>)>,
+    /// Chunks kept loaded around the view, on each side
+    radius: u16,
+    loaded: HashMap<ChunkPos, Loaded<C>>,
+    pending: HashMap<ChunkPos, JobId>,
+}
+
+impl<C: Saveable + Send + 'static> ChunkManager<C> {
+    /// Creates a world of chunks of `size` cells, made by `generate` when
+    /// they are first needed.
+    ///
+    /// `generate` runs on worker threads; it should be deterministic, e.g.
+    /// seeded with the chunk position, so unloaded chunks come back the same.
+    pub fn new(size: (u16, u16), generate: impl Fn(ChunkPos) -> C + Send + Sync + 'static) -> Self {
+        Self {
+            size: (size.0.max(1), size.1.max(1)),
+            generate: Arc::new(generate),
+            storage: None,
+            radius: 1,
+            loaded: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Saves modified chunks in `directory` when they are unloaded, and
+    /// reads them back with `load` instead of generating them again.
+    pub fn with_storage(
+        mut self,
+        directory: impl Into<PathBuf>,
+        load: impl Fn(&str) -> Result<C, EngineError> + Send + Sync + 'static,
+    ) -> Self {
+        self.storage = Some((Storage::Directory(directory.into()), Arc::new(load)));
+        self
+    }
+
+    /// Saves modified chunks in the table `table` of `db` when they are
+    /// unloaded, and reads them back with `load` instead of generating them
+    /// again.
+    #[cfg(feature = "db")]
+    pub fn with_database(
+        mut self,
+        db: &crate::db::WorldDb,
+        table: &str,
+        load: impl Fn(&str) -> Result<C, EngineError> + Send + Sync + 'static,
+    ) -> Result<Self, EngineError> {
+        let storage = Storage::Database(db.clone(), db.table(table)?);
+        self.storage = Some((storage, Arc::new(load)));
+        Ok(self)
+    }
+
+    /// Sets how many chunks beyond the edges of the view are loaded ahead,
+    /// 1 by default; chunks are unloaded one further out, so walking back
+    /// and forth over an edge does not reload them.
+    pub fn with_radius(mut self, chunks: u16) -> Self {
+        self.radius = chunks;
+        self
+    }
+
+    /// Returns the size of a chunk in cells.
+    pub fn chunk_size(&self) -> (u16, u16) {
+        self.size
+    }
+
+    /// Returns the chunk holding the world position (x,y) and the position
+    /// within that chunk.
+    pub fn chunk_at(&self, x: i32, y: i32) -> (ChunkPos, (u16, u16)) {
+        let (width, height) = (self.size.0 as i32, self.size.1 as i32);
+        (
+            (x.div_euclid(width), y.div_euclid(height)),
+            (x.rem_euclid(width) as u16, y.rem_euclid(height) as u16),
+        )
+    }
+
+    /// Returns the area of the world a chunk covers.
+    pub fn chunk_bounds(&self, position: ChunkPos) -> Bounds {
+        let (width, height) = self.size;
+        Bounds::new(
+            position.0 * width as i32,
+            position.1 * height as i32,
+            width,
+            height,
+        )
+    }
+
+    /// Returns a loaded chunk.
+    pub fn get(&self, position: ChunkPos) -> Option<&C> {
+        self.loaded.get(&position).map(|loaded| &loaded.chunk)
+    }
+
+    /// Returns a loaded chunk to change, marking it to be saved when unloaded.
+    pub fn get_mut(&mut self, position: ChunkPos) -> Option<&mut C> {
+        let loaded = self.loaded.get_mut(&position)?;
+        loaded.modified = true;
+        Some(&mut loaded.chunk)
+    }
+
+    /// Returns the loaded chunks with their positions, in no particular order.
+    pub fn loaded(&self) -> impl Iterator<Item = (ChunkPos, &C)> {
+        self.loaded
+            .iter()
+            .map(|(position, loaded)| (*position, &loaded.chunk))
+    }
+
+    pub fn is_loaded(&self, position: ChunkPos) -> bool {
+        self.loaded.contains_key(&position)
+    }
+
+    /// Returns whether chunks are still being generated or read.
+    pub fn is_loading(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Returns the chunks from `(left, top)` to `(right, bottom)` inclusive
+    /// that a view of `view` cells needs, grown by `margin` chunks.
+    fn range(&self, camera: Camera, view: (u16, u16), margin: i32) -> (ChunkPos, ChunkPos) {
+        let (left, top) = self.chunk_at(camera.x, camera.y).0;
+        let right = camera.x + view.0.max(1) as i32 - 1;
+        let bottom = camera.y + view.1.max(1) as i32 - 1;
+        let (right, bottom) = self.chunk_at(right, bottom).0;
+        (
+            (left - margin, top - margin),
+            (right + margin, bottom + margin),
+        )
+    }
+
+    /// Starts loading the chunks coming into range of the camera, takes in
+    /// those that are ready and unloads those far out of range.
+    ///
+    /// Fails if a chunk could not be read or saved; the chunk is left as it
+    /// was, and tried again on the next update.
+    pub fn update(&mut self, ctx: &mut Context) -> Result<(), EngineError> {
+        let (camera, view) = (ctx.camera(), ctx.screen_size());
+        let mut result = Ok(());
+        let finished: Vec<(ChunkPos, JobId)> =
+            self.pending.iter().map(|(&pos, &id)| (pos, id)).collect();
+        for (position, id) in finished {
+            let Some(chunk) = ctx.take_job::<Result<C, EngineError>>(id) else {
+                continue;
+            };
+            self.pending.remove(&position);
+            match chunk.and_then(|chunk| chunk) {
+                Ok(chunk) => {
+                    let modified = false;
+                    self.loaded.insert(position, Loaded { chunk, modified });
+                }
+                Err(e) => result = Err(e),
+            }
+        }
+
+        let margin = self.radius as i32;
+        let ((left, top), (right, bottom)) = self.range(camera, view, margin);
+        for y in top..=bottom {
+            for x in left..=right {
+                if !self.loaded.contains_key(&(x, y)) && !self.pending.contains_key(&(x, y)) {
+                    let id = self.spawn((x, y), ctx);
+                    self.pending.insert((x, y), id);
+                }
+            }
+        }
+
+        let ((left, top), (right, bottom)) = self.range(camera, view, margin + 1);
+        let distant: Vec<ChunkPos> = self
+            .loaded
+            .keys()
+            .filter(|(x, y)| !(left..=right).contains(x) || !(top..=bottom).contains(y))
+            .copied()
+            .collect();
+        let unloading = !distant.is_empty();
+        for position in distant {
+            match self.save(position) {
+                Ok(()) => {
+                    self.loaded.remove(&position);
+                }
+                Err(e) => result = Err(e),
+            }
+        }
+        if unloading && let Some((storage, _)) = &self.storage {
+            result = result.and(storage.flush());
+        }
+        result
+    }
+
+    /// Writes every modified chunk still loaded, e.g. when the game exits.
+    pub fn save_all(&mut self) -> Result<(), EngineError> {
+        let positions: Vec<ChunkPos> = self.loaded.keys().copied().collect();
+        for position in positions {
+            self.save(position)?;
+        }
+        match &self.storage {
+            Some((storage, _)) => storage.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes a chunk if it was modified, after which it no longer is.
+    fn save(&mut self, position: ChunkPos) -> Result<(), EngineError> {
+        let (Some((storage, _)), Some(loaded)) = (&self.storage, self.loaded.get_mut(&position))
+        else {
+            return Ok(());
+        };
+        if loaded.modified {
+            storage.write(position, loaded.chunk.save()?)?;
+            loaded.modified = false;
+        }
+        Ok(())
+    }
+
+    /// Reads the chunk at `position` back from storage, or generates it,
+    /// on a worker thread.
+    fn spawn(&self, position: ChunkPos, ctx: &mut Context) -> JobId {
+        let generate = Arc::clone(&self.generate);
+        let storage = self
+            .storage
+            .as_ref()
+            .map(|(storage, load)| (storage.clone(), Arc::clone(load)));
+        ctx.spawn_job(move || -> Result<C, EngineError> {
+            if let Some((storage, load)) = storage
+                && let Some(text) = storage.read(position)?
+            {
+                return load(&text)
+                    .inspect_err(|e| warn!("Failed to read chunk {:?}: {}", position, e));
+            }
+            Ok(generate(position))
+        })
+    }
+}
+
+/// Returns the file a chunk is stored in.
+fn chunk_path(directory: &Path, (x, y): ChunkPos) -> PathBuf {
+    directory.join(format!("{}_{}.chunk", x, y))
+}
+
+/// Returns the key of a chunk's database record.
+#[cfg(feature = "db")]
+fn chunk_key((x, y): ChunkPos) -> String {
+    format!("{}_{}", x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::geometry::Rect;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// A chunk holding its position and a number the player can change.
+    struct Counter(ChunkPos, u32);
+
+    impl Saveable for Counter {
+        fn save(&self) -> Result<String, EngineError> {
+            Ok(format!("{} {} {}", self.0.0, self.0.1, self.1))
+        }
+    }
+
+    fn parse(text: &str) -> Result<Counter, EngineError> {
+        let numbers: Vec<i32> = text.split(' ').filter_map(|n| n.parse().ok()).collect();
+        match numbers[..] {
+            [x, y, count] => Ok(Counter((x, y), count as u32)),
+            _ => Err(EngineError::game("bad chunk")),
+        }
+    }
+
+    /// Updates until no chunk is loading.
+    fn settle(world: &mut ChunkManager<Counter>, ctx: &mut Context) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        world.update(ctx).unwrap();
+        while world.is_loading() {
+            assert!(Instant::now() < deadline, "chunks never loaded");
+            thread::sleep(Duration::from_millis(2));
+            ctx.poll_jobs();
+            world.update(ctx).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_chunk_at_negative_positions() {
+        let world = ChunkManager::new((10, 5), |position| Counter(position, 0));
+        assert_eq!(world.chunk_at(0, 0), ((0, 0), (0, 0)));
+        assert_eq!(world.chunk_at(-1, 12), ((-1, 2), (9, 2)));
+        assert_eq!(world.chunk_bounds((-1, 2)), Bounds::new(-10, 10, 10, 5));
+    }
+
+    #[test]
+    fn test_modified_chunks_survive_unloading() {
+        let dir = std::env::temp_dir().join(format!("coil-chunks-{}", std::process::id()));
+        let mut world = ChunkManager::new((10, 5), |position| Counter(position, 0))
+            .with_radius(0)
+            .with_storage(&dir, parse);
+        let mut ctx = Context::new(GameConfig::new());
+        ctx.set_viewport(Rect::new(0, 0, 10, 5));
+
+        settle(&mut world, &mut ctx);
+        assert_eq!(world.loaded().count(), 1);
+        world.get_mut((0, 0)).unwrap().1 = 7;
+
+        // Far away the chunk is saved and dropped, then read back on return
+        ctx.set_camera(Camera::new(100, 0));
+        settle(&mut world, &mut ctx);
+        assert!(!world.is_loaded((0, 0)));
+        assert!(world.is_loaded((10, 0)));
+        ctx.set_camera(Camera::new(0, 0));
+        settle(&mut world, &mut ctx);
+        assert_eq!(world.get((0, 0)).unwrap().1, 7);
+        assert_eq!(world.get((0, 0)).unwrap().0, (0, 0));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
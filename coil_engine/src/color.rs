@@ -0,0 +1,562 @@
+//! Color construction, parsing and blending helpers.
+//!
+//! Colors are manipulated as [`Rgb`] triples and converted to crossterm's
+//! [`Color`] for drawing. Named and 256-color palette entries are mapped to
+//! their usual xterm RGB values so they can be blended too.
+use crate::errors::EngineError;
+use crate::renderer::Cell;
+use crossterm::style::Color;
+use std::fmt;
+use std::str::FromStr;
+
+/// A 24-bit RGB color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+const NAMED_COLORS: &[(&str, Color)] = &[
+    ("black", Color::Black),
+    ("dark_grey", Color::DarkGrey),
+    ("red", Color::Red),
+    ("dark_red", Color::DarkRed),
+    ("green", Color::Green),
+    ("dark_green", Color::DarkGreen),
+    ("yellow", Color::Yellow),
+    ("dark_yellow", Color::DarkYellow),
+    ("blue", Color::Blue),
+    ("dark_blue", Color::DarkBlue),
+    ("magenta", Color::Magenta),
+    ("dark_magenta", Color::DarkMagenta),
+    ("cyan", Color::Cyan),
+    ("dark_cyan", Color::DarkCyan),
+    ("white", Color::White),
+    ("grey", Color::Grey),
+];
+
+/// RGB values of the 16 ANSI colors, indexed by their palette number.
+const ANSI_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (128, 0, 0),
+    (0, 128, 0),
+    (128, 128, 0),
+    (0, 0, 128),
+    (128, 0, 128),
+    (0, 128, 128),
+    (192, 192, 192),
+    (128, 128, 128),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (0, 0, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+impl Rgb {
+    /// Creates a color from its components.
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Parses `#rrggbb` or `#rgb` (the `#` is optional).
+    pub fn from_hex(hex: &str) -> Result<Self, EngineError> {
+        let invalid = || EngineError::Config(format!("Invalid hex color: {:?}", hex));
+        let digits = hex.trim().trim_start_matches('#');
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let component = |range: std::ops::Range<usize>| {
+            u8::from_str_radix(&digits[range], 16).map_err(|_| invalid())
+        };
+        match digits.len() {
+            6 => Ok(Self::new(
+                component(0..2)?,
+                component(2..4)?,
+                component(4..6)?,
+            )),
+            3 => Ok(Self::new(
+                component(0..1)? * 17,
+                component(1..2)? * 17,
+                component(2..3)? * 17,
+            )),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Creates a color from hue (degrees), saturation and lightness (0.0 to 1.0).
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let s = saturation.clamp(0.0, 1.0);
+        let l = lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = l - chroma / 2.0;
+        let to_u8 = |v: f32| ((v + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Self::new(to_u8(r), to_u8(g), to_u8(b))
+    }
+
+    /// Returns the color as hue (degrees), saturation and lightness (0.0 to 1.0).
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let r = self.r as f32 / 255.0;
+        let g = self.g as f32 / 255.0;
+        let b = self.b as f32 / 255.0;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let l = (max + min) / 2.0;
+        let delta = max - min;
+        if delta == 0.0 {
+            return (0.0, 0.0, l);
+        }
+        let s = delta / (1.0 - (2.0 * l - 1.0).abs());
+        let h = if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (h, s, l)
+    }
+
+    /// Blends towards `other`; `t = 0.0` keeps this color, `t = 1.0` gives `other`.
+    pub fn mix(&self, other: Rgb, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Self::new(
+            lerp(self.r, other.r),
+            lerp(self.g, other.g),
+            lerp(self.b, other.b),
+        )
+    }
+
+    /// Filters the color through a light: white keeps it, black turns it black.
+    pub fn multiply(&self, light: Rgb) -> Self {
+        let channel = |a: u8, b: u8| ((a as u16 * b as u16 + 127) / 255) as u8;
+        Self::new(
+            channel(self.r, light.r),
+            channel(self.g, light.g),
+            channel(self.b, light.b),
+        )
+    }
+
+    /// Returns the gray of the same perceived brightness.
+    pub fn grayscale(&self) -> Self {
+        let luma = 0.299 * self.r as f32 + 0.587 * self.g as f32 + 0.114 * self.b as f32;
+        let luma = luma.round() as u8;
+        Self::new(luma, luma, luma)
+    }
+
+    /// Raises the HSL lightness by `amount` (0.0 to 1.0).
+    pub fn lighten(&self, amount: f32) -> Self {
+        let (h, s, l) = self.to_hsl();
+        Self::from_hsl(h, s, l + amount)
+    }
+
+    /// Lowers the HSL lightness by `amount` (0.0 to 1.0).
+    pub fn darken(&self, amount: f32) -> Self {
+        self.lighten(-amount)
+    }
+
+    /// Returns the RGB value a terminal color usually displays as, or `None`
+    /// for [`Color::Reset`] whose value depends on the terminal theme.
+    pub fn from_color(color: Color) -> Option<Self> {
+        let ansi = |index: usize| {
+            let (r, g, b) = ANSI_RGB[index];
+            Some(Self::new(r, g, b))
+        };
+        match color {
+            Color::Reset => None,
+            Color::Rgb { r, g, b } => Some(Self::new(r, g, b)),
+            Color::AnsiValue(value) => Some(Self::from_ansi(value)),
+            Color::Black => ansi(0),
+            Color::DarkRed => ansi(1),
+            Color::DarkGreen => ansi(2),
+            Color::DarkYellow => ansi(3),
+            Color::DarkBlue => ansi(4),
+            Color::DarkMagenta => ansi(5),
+            Color::DarkCyan => ansi(6),
+            Color::Grey => ansi(7),
+            Color::DarkGrey => ansi(8),
+            Color::Red => ansi(9),
+            Color::Green => ansi(10),
+            Color::Yellow => ansi(11),
+            Color::Blue => ansi(12),
+            Color::Magenta => ansi(13),
+            Color::Cyan => ansi(14),
+            Color::White => ansi(15),
+        }
+    }
+
+    /// Returns the RGB value of an xterm 256-color palette entry.
+    pub fn from_ansi(value: u8) -> Self {
+        match value {
+            0..=15 => {
+                let (r, g, b) = ANSI_RGB[value as usize];
+                Self::new(r, g, b)
+            }
+            16..=231 => {
+                let index = value - 16;
+                let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+                Self::new(level(index / 36), level(index / 6 % 6), level(index % 6))
+            }
+            232..=255 => {
+                let gray = 8 + (value - 232) * 10;
+                Self::new(gray, gray, gray)
+            }
+        }
+    }
+}
+
+impl From<Rgb> for Color {
+    fn from(rgb: Rgb) -> Self {
+        Color::Rgb {
+            r: rgb.r,
+            g: rgb.g,
+            b: rgb.b,
+        }
+    }
+}
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl FromStr for Rgb {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rgb::from_color(parse(s)?)
+            .ok_or_else(|| EngineError::Config(format!("{:?} has no RGB value", s)))
+    }
+}
+
+/// Parses a color from text, as found in themes and config files.
+///
+/// Accepts names (`dark_blue`, `DarkBlue`, `reset`), hex (`#1e90ff`, `#fff`),
+/// `rgb(30, 144, 255)`, `hsl(210, 100%, 56%)` and palette indices (`ansi(208)`).
+pub fn parse(text: &str) -> Result<Color, EngineError> {
+    let invalid = || EngineError::Config(format!("Invalid color: {:?}", text));
+    let text = text.trim();
+    if text.starts_with('#') {
+        return Rgb::from_hex(text).map(Color::from);
+    }
+    if let Some((function, args)) = text.strip_suffix(')').and_then(|rest| rest.split_once('(')) {
+        let args: Vec<&str> = args.split(',').map(str::trim).collect();
+        return match (
+            function.trim().to_ascii_lowercase().as_str(),
+            args.as_slice(),
+        ) {
+            ("rgb", [r, g, b]) => {
+                let component = |v: &str| v.parse::<u8>().map_err(|_| invalid());
+                Ok(Rgb::new(component(r)?, component(g)?, component(b)?).into())
+            }
+            ("hsl", [h, s, l]) => {
+                let number = |v: &str| v.parse::<f32>().map_err(|_| invalid());
+                let percent = |v: &str| number(v.trim_end_matches('%')).map(|p| p / 100.0);
+                Ok(Rgb::from_hsl(number(h)?, percent(s)?, percent(l)?).into())
+            }
+            ("ansi", [value]) => value.parse().map(Color::AnsiValue).map_err(|_| invalid()),
+            _ => Err(invalid()),
+        };
+    }
+    let normalized: String = text
+        .chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .collect::<String>()
+        .to_ascii_lowercase()
+        .replace("gray", "grey");
+    if normalized == "reset" {
+        return Ok(Color::Reset);
+    }
+    NAMED_COLORS
+        .iter()
+        .find(|(name, _)| name.replace('_', "") == normalized)
+        .map(|&(_, color)| color)
+        .ok_or_else(invalid)
+}
+
+/// Blends two terminal colors; [`Color::Reset`] on either side returns the other unchanged.
+pub fn mix(a: Color, b: Color, t: f32) -> Color {
+    match (Rgb::from_color(a), Rgb::from_color(b)) {
+        (Some(a), Some(b)) => a.mix(b, t).into(),
+        (Some(_), None) => a,
+        (None, _) => b,
+    }
+}
+
+/// Filters a terminal color through a light; [`Color::Reset`] is returned unchanged.
+pub fn multiply(color: Color, light: Rgb) -> Color {
+    Rgb::from_color(color).map_or(color, |rgb| rgb.multiply(light).into())
+}
+
+/// Lightens a terminal color; [`Color::Reset`] is returned unchanged.
+pub fn lighten(color: Color, amount: f32) -> Color {
+    Rgb::from_color(color).map_or(color, |rgb| rgb.lighten(amount).into())
+}
+
+/// Darkens a terminal color; [`Color::Reset`] is returned unchanged.
+pub fn darken(color: Color, amount: f32) -> Color {
+    Rgb::from_color(color).map_or(color, |rgb| rgb.darken(amount).into())
+}
+
+/// Returns the closest of the 16 standard terminal colors, for consoles that
+/// cannot show 256 or RGB colors; [`Color::Reset`] is returned unchanged.
+pub fn to_ansi16(color: Color) -> Color {
+    const ANSI16: [Color; 16] = [
+        Color::Black,
+        Color::DarkRed,
+        Color::DarkGreen,
+        Color::DarkYellow,
+        Color::DarkBlue,
+        Color::DarkMagenta,
+        Color::DarkCyan,
+        Color::Grey,
+        Color::DarkGrey,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::White,
+    ];
+    let rgb = match color {
+        Color::Rgb { r, g, b } => Rgb::new(r, g, b),
+        Color::AnsiValue(value) => Rgb::from_ansi(value),
+        _ => return color,
+    };
+    let distance = |&(r, g, b): &(u8, u8, u8)| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(rgb.r, r) + d(rgb.g, g) + d(rgb.b, b)
+    };
+    let nearest = (0..ANSI16.len())
+        .min_by_key(|&index| distance(&ANSI_RGB[index]))
+        .unwrap_or(0);
+    ANSI16[nearest]
+}
+
+/// Returns the closest entry of the 256-color palette for an RGB color;
+/// other colors are already in the palette and are returned unchanged.
+pub fn to_ansi256(color: Color) -> Color {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let Color::Rgb { r, g, b } = color else {
+        return color;
+    };
+    let level = |v: u8| match v {
+        0..48 => 0,
+        48..115 => 1,
+        _ => (v as usize - 35) / 40,
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = Rgb::new(LEVELS[ri], LEVELS[gi], LEVELS[bi]);
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_index = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray = 8 + 10 * gray_index;
+    let distance = |c: Rgb| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(r, c.r) + d(g, c.g) + d(b, c.b)
+    };
+    if distance(Rgb::new(gray, gray, gray)) < distance(cube) {
+        Color::AnsiValue(232 + gray_index)
+    } else {
+        Color::AnsiValue(16 + 36 * ri as u8 + 6 * gi as u8 + bi as u8)
+    }
+}
+
+/// How many colors the terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorDepth {
+    /// Probe `COLORTERM` for 24-bit support.
+    #[default]
+    Auto,
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorDepth {
+    /// Resolves [`ColorDepth::Auto`] from the environment.
+    ///
+    /// Terminals announce 24-bit support through `COLORTERM`; without it 256
+    /// colors are assumed, except on Windows where modern consoles all
+    /// support RGB.
+    pub fn resolve(self) -> Self {
+        match self {
+            ColorDepth::Auto => match std::env::var("COLORTERM") {
+                Ok(value) if value == "truecolor" || value == "24bit" => ColorDepth::TrueColor,
+                _ if cfg!(windows) => ColorDepth::TrueColor,
+                _ => ColorDepth::Ansi256,
+            },
+            depth => depth,
+        }
+    }
+
+    /// Returns the renderer filter reducing colors to this depth, if any.
+    pub fn filter(self) -> Option<fn(Cell) -> Cell> {
+        match self.resolve() {
+            ColorDepth::Ansi256 => Some(quantize_256),
+            ColorDepth::Ansi16 => Some(quantize_16),
+            ColorDepth::Auto | ColorDepth::TrueColor => None,
+        }
+    }
+}
+
+impl FromStr for ColorDepth {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(ColorDepth::Auto),
+            "truecolor" | "24bit" => Ok(ColorDepth::TrueColor),
+            "256" => Ok(ColorDepth::Ansi256),
+            "16" => Ok(ColorDepth::Ansi16),
+            _ => Err(EngineError::Config(format!("Unknown color depth: {:?}", s))),
+        }
+    }
+}
+
+impl fmt::Display for ColorDepth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColorDepth::Auto => "auto",
+            ColorDepth::TrueColor => "truecolor",
+            ColorDepth::Ansi256 => "256",
+            ColorDepth::Ansi16 => "16",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Renderer filter mapping RGB colors to the 256-color palette.
+pub fn quantize_256(cell: Cell) -> Cell {
+    Cell {
+        fg: to_ansi256(cell.fg),
+        bg: to_ansi256(cell.bg),
+        ..cell
+    }
+}
+
+/// Renderer filter mapping every color to the 16 standard ones.
+pub fn quantize_16(cell: Cell) -> Cell {
+    Cell {
+        fg: to_ansi16(cell.fg),
+        bg: to_ansi16(cell.bg),
+        ..cell
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_ansi256() {
+        let rgb = |r, g, b| Color::Rgb { r, g, b };
+        assert_eq!(to_ansi256(rgb(255, 0, 0)), Color::AnsiValue(196));
+        assert_eq!(to_ansi256(rgb(0, 95, 135)), Color::AnsiValue(24));
+        assert_eq!(to_ansi256(rgb(128, 128, 128)), Color::AnsiValue(244));
+        assert_eq!(to_ansi256(rgb(0, 0, 0)), Color::AnsiValue(16));
+        assert_eq!(to_ansi256(Color::Blue), Color::Blue);
+        assert_eq!("256".parse::<ColorDepth>().unwrap(), ColorDepth::Ansi256);
+        assert_eq!(ColorDepth::Ansi16.to_string(), "16");
+    }
+
+    #[test]
+    fn test_to_ansi16() {
+        assert_eq!(
+            to_ansi16(Color::Rgb {
+                r: 250,
+                g: 10,
+                b: 5
+            }),
+            Color::Red
+        );
+        assert_eq!(to_ansi16(Color::AnsiValue(17)), Color::DarkBlue);
+        assert_eq!(to_ansi16(Color::Cyan), Color::Cyan);
+        assert_eq!(to_ansi16(Color::Reset), Color::Reset);
+    }
+
+    #[test]
+    fn test_from_hex() {
+        assert_eq!(Rgb::from_hex("#1e90ff").unwrap(), Rgb::new(30, 144, 255));
+        assert_eq!(Rgb::from_hex("fff").unwrap(), Rgb::new(255, 255, 255));
+        assert!(Rgb::from_hex("#12345").is_err());
+        assert!(Rgb::from_hex("#gg0000").is_err());
+        assert_eq!(Rgb::new(30, 144, 255).to_string(), "#1e90ff");
+    }
+
+    #[test]
+    fn test_hsl_round_trip() {
+        assert_eq!(Rgb::from_hsl(0.0, 1.0, 0.5), Rgb::new(255, 0, 0));
+        assert_eq!(Rgb::from_hsl(120.0, 1.0, 0.25), Rgb::new(0, 128, 0));
+        assert_eq!(Rgb::from_hsl(42.0, 0.0, 1.0), Rgb::new(255, 255, 255));
+
+        let color = Rgb::new(30, 144, 255);
+        let (h, s, l) = color.to_hsl();
+        assert_eq!(Rgb::from_hsl(h, s, l), color);
+    }
+
+    #[test]
+    fn test_blending() {
+        let black = Rgb::new(0, 0, 0);
+        let white = Rgb::new(255, 255, 255);
+        assert_eq!(black.mix(white, 0.5), Rgb::new(128, 128, 128));
+        assert_eq!(black.mix(white, 2.0), white);
+        assert_eq!(Rgb::new(128, 128, 128).lighten(0.5), white);
+        assert_eq!(Rgb::new(128, 128, 128).darken(0.6), black);
+
+        assert_eq!(mix(Color::Reset, Color::Red, 0.3), Color::Red);
+        assert_eq!(lighten(Color::Reset, 0.3), Color::Reset);
+        assert_eq!(
+            mix(Color::Black, Color::White, 1.0),
+            Color::Rgb {
+                r: 255,
+                g: 255,
+                b: 255
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("dark_blue").unwrap(), Color::DarkBlue);
+        assert_eq!(parse("DarkBlue").unwrap(), Color::DarkBlue);
+        assert_eq!(parse("dark gray").unwrap(), Color::DarkGrey);
+        assert_eq!(parse("reset").unwrap(), Color::Reset);
+        assert_eq!(parse("#1e90ff").unwrap(), Rgb::new(30, 144, 255).into());
+        assert_eq!(parse("rgb(1, 2, 3)").unwrap(), Rgb::new(1, 2, 3).into());
+        assert_eq!(
+            parse("hsl(0, 100%, 50%)").unwrap(),
+            Rgb::new(255, 0, 0).into()
+        );
+        assert_eq!(parse("ansi(208)").unwrap(), Color::AnsiValue(208));
+        assert!(parse("rgb(1, 2)").is_err());
+        assert!(parse("chartreuse").is_err());
+        assert!("reset".parse::<Rgb>().is_err());
+        assert_eq!("white".parse::<Rgb>().unwrap(), Rgb::new(255, 255, 255));
+    }
+
+    #[test]
+    fn test_palette_mapping() {
+        assert_eq!(Rgb::from_ansi(16), Rgb::new(0, 0, 0));
+        assert_eq!(Rgb::from_ansi(196), Rgb::new(255, 0, 0));
+        assert_eq!(Rgb::from_ansi(232), Rgb::new(8, 8, 8));
+        assert_eq!(Rgb::from_color(Color::Grey), Some(Rgb::new(192, 192, 192)));
+    }
+}
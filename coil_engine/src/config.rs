@@ -1,21 +1,386 @@
+use crate::accessibility::ColorBlindness;
+use crate::actions::KeyBinding;
+use crate::autosave::SaveFormat;
+use crate::color::ColorDepth;
 use crate::errors::EngineError;
+use crate::geometry::Rect;
+use crate::glyphs::GlyphSupport;
 use crate::input::InputStrategy;
+use crate::spectate::SpectateTarget;
+use crate::style::Theme;
+use crate::terminal::{ConsoleMode, OutputMode};
 use crossterm::terminal;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+/// Environment variable prefix used by [`GameConfig::with_overrides`].
+pub const ENV_PREFIX: &str = "COIL";
+
+/// How the game's drawing area is sized relative to the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScreenSize {
+    /// A fixed-size area anchored at the top-left corner of the terminal.
+    Fixed(u16, u16),
+    /// The whole terminal, following it as it is resized.
+    #[default]
+    Fullscreen,
+    /// A fixed-size area centered in the terminal.
+    Centered(u16, u16),
+}
+
+impl ScreenSize {
+    /// Resolves the drawing area for a terminal of the given size.
+    ///
+    /// Returns an error if the terminal is too small to fit a fixed-size area.
+    pub fn resolve(&self, terminal: (u16, u16)) -> Result<Rect, EngineError> {
+        let (term_width, term_height) = terminal;
+        let (width, height) = match *self {
+            ScreenSize::Fullscreen => return Ok(Rect::new(0, 0, term_width, term_height)),
+            ScreenSize::Fixed(width, height) | ScreenSize::Centered(width, height) => {
+                (width, height)
+            }
+        };
+        if width > term_width || height > term_height {
+            return Err(EngineError::Config(format!(
+                "Terminal too small: need {}x{}, have {}x{}",
+                width, height, term_width, term_height
+            )));
+        }
+        match self {
+            ScreenSize::Centered(..) => Ok(Rect::new(
+                (term_width - width) / 2,
+                (term_height - height) / 2,
+                width,
+                height,
+            )),
+            _ => Ok(Rect::new(0, 0, width, height)),
+        }
+    }
+
+    /// Returns the smallest terminal size this mode can be resolved against.
+    pub fn min_size(&self) -> (u16, u16) {
+        match *self {
+            ScreenSize::Fullscreen => (0, 0),
+            ScreenSize::Fixed(width, height) | ScreenSize::Centered(width, height) => {
+                (width, height)
+            }
+        }
+    }
+
+    /// Resolves the drawing area for the current terminal.
+    pub fn detect(&self) -> Result<Rect, EngineError> {
+        self.resolve(terminal::size()?)
+    }
+}
+
+/// Named presets that set several options at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Profile {
+    /// Development: debug mode on and a reduced FPS cap.
+    Dev,
+    /// Release: the engine defaults.
+    Release,
+    /// Continuous integration: headless, dumping frames of a fixed 80x24
+    /// screen, with no mouse capture or vsync.
+    Ci,
+}
+
+impl Profile {
+    /// Returns the settings this profile applies, in order.
+    pub fn configs(&self) -> Vec<Config> {
+        match self {
+            Profile::Dev => vec![Config::DebugMode(true), Config::TargetFps(30)],
+            Profile::Release => vec![Config::DebugMode(false), Config::TargetFps(60)],
+            Profile::Ci => vec![
+                Config::ScreenSize(ScreenSize::Fixed(80, 24)),
+                Config::MouseCapture(false),
+                Config::Vsync(false),
+                Config::InputStrategy(InputStrategy::NonBlocking),
+                Config::Output(OutputMode::FrameDump),
+            ],
+        }
+    }
+}
+
+impl std::str::FromStr for Profile {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "dev" => Ok(Profile::Dev),
+            "release" => Ok(Profile::Release),
+            "ci" => Ok(Profile::Ci),
+            _ => Err(EngineError::Config(format!("Unknown profile: {:?}", s))),
+        }
+    }
+}
+
+/// What the engine does with recoverable errors, such as draws clipped
+/// during a resize, that the node tree does not handle itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorPolicy {
+    /// Ignore the error silently.
+    Continue,
+    /// Log the error as a warning and keep running.
+    #[default]
+    LogAndContinue,
+    /// Stop the event loop and return the error.
+    Abort,
+}
+
+impl std::str::FromStr for ErrorPolicy {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "continue" => Ok(ErrorPolicy::Continue),
+            "log_and_continue" | "log" => Ok(ErrorPolicy::LogAndContinue),
+            "abort" => Ok(ErrorPolicy::Abort),
+            _ => Err(EngineError::Config(format!(
+                "Unknown error policy: {:?}",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Config {
+    Profile(Profile),
     TargetFps(u32),
     InputStrategy(InputStrategy),
     MaxFrameTime(Duration),
+    CpuBudget(Option<Duration>),
+    MaxFrameSkip(u32),
     DebugMode(bool),
+    ShowRedraws(bool),
     Vsync(bool),
-    ScreenSize((u16, u16)),
+    ScreenSize(ScreenSize),
+    MouseCapture(bool),
+    TouchControls(bool),
+    MinScreenSize((u16, u16)),
+    ErrorPolicy(ErrorPolicy),
+    QuitKeys(Vec<KeyBinding>),
+    PauseKey(Option<KeyBinding>),
+    IdleTimeout(Option<Duration>),
+    HighContrast(bool),
+    ReducedMotion(bool),
+    SpeechOutput(Option<PathBuf>),
+    ColorBlindness(Option<ColorBlindness>),
+    Glyphs(GlyphSupport),
+    Console(ConsoleMode),
+    Output(OutputMode),
+    Spectate(Option<SpectateTarget>),
+    ColorDepth(ColorDepth),
+    Autosave(Option<PathBuf>),
+    AutosaveInterval(Option<Duration>),
+    AutosaveBackups(usize),
+    AutosaveFormat(SaveFormat),
+    AppName(Option<String>),
+    /// Set in code only; config files and the command line keep the theme as is.
+    Theme(Theme),
+}
+
+impl Config {
+    /// Keys understood by [`Config::parse`], as used in config files and
+    /// (upper-cased, prefixed) environment variables.
+    ///
+    /// `profile` comes first so that individual settings can refine it.
+    pub const KEYS: &'static [&'static str] = &[
+        "profile",
+        "target_fps",
+        "input_strategy",
+        "max_frame_time",
+        "cpu_budget",
+        "max_frame_skip",
+        "debug_mode",
+        "show_redraws",
+        "vsync",
+        "screen_size",
+        "mouse_capture",
+        "touch_controls",
+        "min_screen_size",
+        "error_policy",
+        "quit_keys",
+        "pause_key",
+        "idle_timeout",
+        "high_contrast",
+        "reduced_motion",
+        "speech_output",
+        "color_blindness",
+        "glyphs",
+        "console",
+        "output",
+        "spectate",
+        "color_depth",
+        "autosave",
+        "autosave_interval",
+        "autosave_backups",
+        "autosave_format",
+        "app_name",
+    ];
+
+    /// Parses a single `key = value` setting.
+    ///
+    /// Durations are given in milliseconds, booleans accept `true/false`,
+    /// `1/0`, `yes/no` and `on/off`, screen sizes are written as
+    /// `fullscreen`, `80x24` or `centered:80x24`, and key lists as
+    /// comma-separated bindings such as `Esc, Ctrl+c`.
+    pub fn parse(key: &str, value: &str) -> Result<Self, EngineError> {
+        let value = value.trim();
+        let config = match key.trim() {
+            "profile" => Config::Profile(value.parse()?),
+            "target_fps" => Config::TargetFps(parse_number(key, value)?),
+            "input_strategy" => Config::InputStrategy(parse_input_strategy(value)?),
+            "max_frame_time" => {
+                Config::MaxFrameTime(Duration::from_millis(parse_number(key, value)?))
+            }
+            "cpu_budget" => Config::CpuBudget(match value.to_ascii_lowercase().as_str() {
+                "" | "none" | "0" => None,
+                millis => Some(Duration::from_millis(parse_number(key, millis)?)),
+            }),
+            "max_frame_skip" => Config::MaxFrameSkip(parse_number(key, value)?),
+            "debug_mode" => Config::DebugMode(parse_bool(key, value)?),
+            "show_redraws" => Config::ShowRedraws(parse_bool(key, value)?),
+            "vsync" => Config::Vsync(parse_bool(key, value)?),
+            "screen_size" => Config::ScreenSize(parse_size(value)?),
+            "mouse_capture" => Config::MouseCapture(parse_bool(key, value)?),
+            "touch_controls" => Config::TouchControls(parse_bool(key, value)?),
+            "min_screen_size" => Config::MinScreenSize(parse_dimensions(value)?),
+            "error_policy" => Config::ErrorPolicy(value.parse()?),
+            "quit_keys" => Config::QuitKeys(parse_keys(value)?),
+            "idle_timeout" => Config::IdleTimeout(match value.to_ascii_lowercase().as_str() {
+                "" | "none" | "0" => None,
+                millis => Some(Duration::from_millis(parse_number(key, millis)?)),
+            }),
+            "high_contrast" => Config::HighContrast(parse_bool(key, value)?),
+            "reduced_motion" => Config::ReducedMotion(parse_bool(key, value)?),
+            "glyphs" => Config::Glyphs(value.parse()?),
+            "console" => Config::Console(value.parse()?),
+            "output" => Config::Output(value.parse()?),
+            "spectate" => Config::Spectate(match value.to_ascii_lowercase().as_str() {
+                "" | "none" => None,
+                _ => Some(value.parse()?),
+            }),
+            "color_depth" => Config::ColorDepth(value.parse()?),
+            "color_blindness" => {
+                Config::ColorBlindness(match value.to_ascii_lowercase().as_str() {
+                    "" | "none" => None,
+                    _ => Some(value.parse()?),
+                })
+            }
+            "speech_output" => Config::SpeechOutput(match value.to_ascii_lowercase().as_str() {
+                "" | "none" => None,
+                _ => Some(PathBuf::from(value)),
+            }),
+            "autosave" => Config::Autosave(match value.to_ascii_lowercase().as_str() {
+                "" | "none" => None,
+                _ => Some(PathBuf::from(value)),
+            }),
+            "app_name" => Config::AppName(match value.to_ascii_lowercase().as_str() {
+                "" | "none" => None,
+                _ => Some(value.to_string()),
+            }),
+            "autosave_interval" => {
+                Config::AutosaveInterval(match value.to_ascii_lowercase().as_str() {
+                    "" | "none" | "0" => None,
+                    millis => Some(Duration::from_millis(parse_number(key, millis)?)),
+                })
+            }
+            "autosave_backups" => Config::AutosaveBackups(parse_number(key, value)?),
+            "autosave_format" => Config::AutosaveFormat(value.parse()?),
+            "pause_key" => Config::PauseKey(match value.to_ascii_lowercase().as_str() {
+                "" | "none" => None,
+                _ => Some(value.parse()?),
+            }),
+            other => {
+                return Err(EngineError::Config(format!(
+                    "Unknown configuration key: {}",
+                    other
+                )));
+            }
+        };
+        Ok(config)
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, EngineError> {
+    value
+        .parse()
+        .map_err(|_| EngineError::Config(format!("Invalid number for {}: {:?}", key, value)))
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, EngineError> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Ok(true),
+        "false" | "0" | "no" | "off" => Ok(false),
+        _ => Err(EngineError::Config(format!(
+            "Invalid boolean for {}: {:?}",
+            key, value
+        ))),
+    }
+}
+
+fn parse_input_strategy(value: &str) -> Result<InputStrategy, EngineError> {
+    match value.to_ascii_lowercase().replace('-', "_").as_str() {
+        "non_blocking" => Ok(InputStrategy::NonBlocking),
+        "frame_budgeted" => Ok(InputStrategy::FrameBudgeted),
+        millis => Ok(InputStrategy::Timeout(Duration::from_millis(parse_number(
+            "input_strategy",
+            millis,
+        )?))),
+    }
+}
+
+fn parse_keys(value: &str) -> Result<Vec<KeyBinding>, EngineError> {
+    if value.is_empty() || value.eq_ignore_ascii_case("none") {
+        return Ok(Vec::new());
+    }
+    value.split(',').map(|key| key.trim().parse()).collect()
 }
+
+fn parse_dimensions(value: &str) -> Result<(u16, u16), EngineError> {
+    let invalid = || EngineError::Config(format!("Invalid screen size: {:?}", value));
+    let (width, height) = value.split_once(['x', 'X']).ok_or_else(invalid)?;
+    let width = width.trim().parse().map_err(|_| invalid())?;
+    let height = height.trim().parse().map_err(|_| invalid())?;
+    Ok((width, height))
+}
+
+fn parse_size(value: &str) -> Result<ScreenSize, EngineError> {
+    let invalid = || EngineError::Config(format!("Invalid screen size: {:?}", value));
+    if value.eq_ignore_ascii_case("fullscreen") {
+        return Ok(ScreenSize::Fullscreen);
+    }
+    let (centered, dimensions) = match value.split_once(':') {
+        Some((mode, dimensions)) if mode.trim().eq_ignore_ascii_case("centered") => {
+            (true, dimensions)
+        }
+        Some(_) => return Err(invalid()),
+        None => (false, value),
+    };
+    let (width, height) = parse_dimensions(dimensions)?;
+    if centered {
+        Ok(ScreenSize::Centered(width, height))
+    } else {
+        Ok(ScreenSize::Fixed(width, height))
+    }
+}
+
 /// Configuration for the game engine.
 ///
 /// This struct contains all the settings needed to configure the engine's behavior,
 /// including frame rate, input handling, and other engine parameters.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct GameConfig {
     /// Target frames per second for the game loop
     pub target_fps: u32,
@@ -23,12 +388,66 @@ pub struct GameConfig {
     pub input_strategy: InputStrategy,
     /// Maximum frame time to prevent spiral of death (prevents spiral of death in complex scenes)
     pub max_frame_time: Duration,
+    /// Time each frame may spend updating and drawing before the engine
+    /// degrades to save CPU, if any; see [`crate::stats`]
+    pub cpu_budget: Option<Duration>,
+    /// Most frames in a row left undrawn while updates catch up after falling behind
+    pub max_frame_skip: u32,
     /// Whether to enable debug logging
     pub debug_mode: bool,
+    /// Whether cells that changed since the previous frame are flashed in
+    /// reverse video, to spot nodes redrawing what did not change
+    pub show_redraws: bool,
     /// Whether to enable vsync-like behavior
     pub vsync: bool,
-    /// Screen size for the game window (width, height)
-    pub screen_size: (u16, u16),
+    /// Size and placement of the game's drawing area within the terminal
+    pub screen_size: ScreenSize,
+    /// Whether mouse events are captured and delivered to nodes
+    pub mouse_capture: bool,
+    /// Whether on-screen buttons are shown for pointer-only players; see
+    /// [`TouchControls`](crate::nodes::TouchControls)
+    pub touch_controls: bool,
+    /// Smallest playable terminal size (width, height); the game is paused below it
+    pub min_screen_size: Option<(u16, u16)>,
+    /// How recoverable errors are handled when no node overrides it
+    pub error_policy: ErrorPolicy,
+    /// Keys that exit the game before the node tree sees them
+    pub quit_keys: Vec<KeyBinding>,
+    /// Key that pauses and resumes the game, if any
+    pub pause_key: Option<KeyBinding>,
+    /// Time without input after which the game is considered idle, if any
+    pub idle_timeout: Option<Duration>,
+    /// Whether the screen is drawn in a black and white high-contrast palette
+    pub high_contrast: bool,
+    /// Whether effects such as screen shake, particles and flashing should be disabled
+    pub reduced_motion: bool,
+    /// File that announcements for screen readers are appended to, if any
+    pub speech_output: Option<PathBuf>,
+    /// Color vision deficiency simulated over the final screen, for testing readability
+    pub color_blindness: Option<ColorBlindness>,
+    /// Whether box-drawing, block and braille characters are drawn or replaced with ASCII
+    pub glyphs: GlyphSupport,
+    /// Whether to use the code paths for legacy consoles without ANSI support
+    pub console: ConsoleMode,
+    /// Whether frames are drawn to the terminal or dumped as text; read at startup
+    pub output: OutputMode,
+    /// Address or FIFO that spectators watch the game through, if any; read at startup
+    pub spectate: Option<SpectateTarget>,
+    /// How many colors the terminal shows; RGB colors are reduced to fit when drawing
+    pub color_depth: ColorDepth,
+    /// File the game is autosaved to, if any
+    pub autosave: Option<PathBuf>,
+    /// Time between autosaves; without one the game is only saved on exit and on request
+    pub autosave_interval: Option<Duration>,
+    /// Number of earlier saves kept as numbered backups next to the autosave file
+    pub autosave_backups: usize,
+    /// Whether autosaves are written as a text file or into a world database
+    pub autosave_format: SaveFormat,
+    /// Name of the game's directories in the platform's config, data and cache
+    /// locations; without one, relative paths are relative to the current directory
+    pub app_name: Option<String>,
+    /// Styles of the engine's menus, overlays and notifications
+    pub theme: Theme,
 }
 
 impl GameConfig {
@@ -38,24 +457,181 @@ impl GameConfig {
             target_fps: 60,
             input_strategy: InputStrategy::default(),
             max_frame_time: Duration::from_millis(50), // Cap at 20 FPS minimum
+            cpu_budget: None,
+            max_frame_skip: 0,
             debug_mode: false,
+            show_redraws: false,
             vsync: true,
-            screen_size: terminal::size().unwrap_or((80, 24)),
+            screen_size: ScreenSize::default(),
+            mouse_capture: true,
+            touch_controls: false,
+            min_screen_size: None,
+            error_policy: ErrorPolicy::default(),
+            quit_keys: Vec::new(),
+            pause_key: None,
+            idle_timeout: None,
+            high_contrast: false,
+            reduced_motion: false,
+            speech_output: None,
+            color_blindness: None,
+            glyphs: GlyphSupport::default(),
+            console: ConsoleMode::default(),
+            output: OutputMode::default(),
+            spectate: None,
+            color_depth: ColorDepth::default(),
+            autosave: None,
+            autosave_interval: None,
+            autosave_backups: 3,
+            autosave_format: SaveFormat::default(),
+            app_name: None,
+            theme: Theme::default(),
         }
     }
 
     pub fn add_config(mut self, config: Config) -> Self {
         match config {
+            Config::Profile(profile) => return self.with_profile(profile),
             Config::TargetFps(fps) => self.target_fps = fps,
             Config::InputStrategy(strategy) => self.input_strategy = strategy,
             Config::MaxFrameTime(max_time) => self.max_frame_time = max_time,
+            Config::CpuBudget(budget) => self.cpu_budget = budget,
+            Config::MaxFrameSkip(skips) => self.max_frame_skip = skips,
             Config::DebugMode(debug) => self.debug_mode = debug,
+            Config::ShowRedraws(enabled) => self.show_redraws = enabled,
             Config::Vsync(vsync) => self.vsync = vsync,
             Config::ScreenSize(size) => self.screen_size = size,
+            Config::MouseCapture(capture) => self.mouse_capture = capture,
+            Config::TouchControls(enabled) => self.touch_controls = enabled,
+            Config::MinScreenSize(size) => self.min_screen_size = Some(size),
+            Config::ErrorPolicy(policy) => self.error_policy = policy,
+            Config::QuitKeys(keys) => self.quit_keys = keys,
+            Config::PauseKey(key) => self.pause_key = key,
+            Config::IdleTimeout(timeout) => self.idle_timeout = timeout,
+            Config::HighContrast(enabled) => self.high_contrast = enabled,
+            Config::ReducedMotion(enabled) => self.reduced_motion = enabled,
+            Config::SpeechOutput(path) => self.speech_output = path,
+            Config::ColorBlindness(deficiency) => self.color_blindness = deficiency,
+            Config::Glyphs(support) => self.glyphs = support,
+            Config::Console(mode) => self.console = mode,
+            Config::Output(mode) => self.output = mode,
+            Config::Spectate(target) => self.spectate = target,
+            Config::ColorDepth(depth) => self.color_depth = depth,
+            Config::Autosave(path) => self.autosave = path,
+            Config::AutosaveInterval(interval) => self.autosave_interval = interval,
+            Config::AutosaveBackups(backups) => self.autosave_backups = backups,
+            Config::AutosaveFormat(format) => self.autosave_format = format,
+            Config::AppName(name) => self.app_name = name,
+            Config::Theme(theme) => self.theme = theme,
         }
         self
     }
 
+    /// Applies every setting of the given profile.
+    pub fn with_profile(self, profile: Profile) -> Self {
+        profile
+            .configs()
+            .into_iter()
+            .fold(self, |config, setting| config.add_config(setting))
+    }
+
+    /// Creates a configuration from the defaults, overridden by any
+    /// `<PREFIX>_<KEY>` environment variables (e.g. `COIL_TARGET_FPS=30`).
+    pub fn from_env(prefix: &str) -> Result<Self, EngineError> {
+        Self::new().merge_env(prefix)
+    }
+
+    /// Applies `<PREFIX>_<KEY>` environment variables on top of this configuration.
+    pub fn merge_env(self, prefix: &str) -> Result<Self, EngineError> {
+        let vars = std::env::vars_os().filter_map(|(name, value)| {
+            Some((name.into_string().ok()?, value.into_string().ok()?))
+        });
+        self.merge_vars(prefix, vars)
+    }
+
+    /// Applies `<PREFIX>_<KEY>` variables from `vars`, given as `(name, value)`
+    /// pairs, like [`GameConfig::merge_env`] does with the environment.
+    pub fn merge_vars(
+        mut self,
+        prefix: &str,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, EngineError> {
+        let vars: HashMap<String, String> = vars.into_iter().collect();
+        for &key in Config::KEYS {
+            let name = format!("{}_{}", prefix, key.to_ascii_uppercase());
+            if let Some(value) = vars.get(&name) {
+                self = self.add_config(Config::parse(key, value)?);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Applies settings from a `key = value` file on top of this configuration.
+    ///
+    /// Blank lines and lines starting with `#` are ignored.
+    pub fn merge_file(self, path: impl AsRef<Path>) -> Result<Self, EngineError> {
+        let contents = fs::read_to_string(path)?;
+        self.merge_str(&contents)
+    }
+
+    /// Writes every setting to a file in the config file format.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), EngineError> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// Applies settings in the config file format on top of this configuration.
+    pub fn merge_str(mut self, contents: &str) -> Result<Self, EngineError> {
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once('=').ok_or_else(|| {
+                EngineError::Config(format!("Expected `key = value` on line {}", number + 1))
+            })?;
+            let value = value.trim().trim_matches('"');
+            self = self.add_config(Config::parse(key, value)?);
+        }
+        Ok(self)
+    }
+
+    /// Applies the standard overrides on top of this configuration, layered as
+    /// config file (`--config`) < environment (`COIL_*`) < command line flags.
+    ///
+    /// Without the `cli` feature only the environment is consulted.
+    pub fn with_overrides(self) -> Result<Self, EngineError> {
+        #[cfg(feature = "cli")]
+        {
+            let matches = cli::command().get_matches();
+            cli::merge_matches(self, &matches)
+        }
+        #[cfg(not(feature = "cli"))]
+        {
+            self.merge_env(ENV_PREFIX)
+        }
+    }
+
+    /// Creates a configuration from the defaults and the standard overrides,
+    /// exiting with a usage message if the command line is invalid.
+    #[cfg(feature = "cli")]
+    pub fn from_args() -> Result<Self, EngineError> {
+        Self::new().with_overrides()
+    }
+
+    /// Applies the standard overrides using the given command line arguments
+    /// (including the program name) instead of the process arguments.
+    #[cfg(feature = "cli")]
+    pub fn merge_args<I, T>(self, args: I) -> Result<Self, EngineError>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let matches = cli::command()
+            .try_get_matches_from(args)
+            .map_err(|e| EngineError::Config(e.to_string()))?;
+        cli::merge_matches(self, &matches)
+    }
+
     /// Validates the configuration.
     pub fn validate(&self) -> Result<(), EngineError> {
         if self.target_fps == 0 {
@@ -68,21 +644,276 @@ impl GameConfig {
                 "Max frame time must be greater than zero".to_string(),
             ));
         }
+        if let ScreenSize::Fixed(width, height) | ScreenSize::Centered(width, height) =
+            self.screen_size
+            && (width == 0 || height == 0)
+        {
+            return Err(EngineError::Config(format!(
+                "Screen size must be at least 1x1, got {}x{}",
+                width, height
+            )));
+        }
+        if cfg!(not(feature = "db")) && self.autosave_format == SaveFormat::Database {
+            return Err(EngineError::Config(
+                "autosave_format = db needs the `db` feature".to_string(),
+            ));
+        }
         Ok(())
     }
 
+    /// Returns the smallest terminal size the game can run in, combining
+    /// `min_screen_size` with the needs of the screen size mode.
+    pub fn required_size(&self) -> (u16, u16) {
+        let (width, height) = self.screen_size.min_size();
+        let (min_width, min_height) = self.min_screen_size.unwrap_or((0, 0));
+        (width.max(min_width), height.max(min_height))
+    }
+
+    /// Resolves the drawing area against the current terminal size.
+    pub fn viewport(&self) -> Result<Rect, EngineError> {
+        self.screen_size.detect()
+    }
+
     /// Gets the frame duration for the target FPS.
     pub fn frame_duration(&self) -> Duration {
         Duration::from_secs_f32(1.0 / self.target_fps as f32)
     }
 }
 
+/// Formats the configuration as a config file that [`GameConfig::merge_str`] reads back.
+impl fmt::Display for GameConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys = |keys: &[KeyBinding]| match keys {
+            [] => "none".to_string(),
+            keys => keys
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        writeln!(f, "target_fps = {}", self.target_fps)?;
+        match self.input_strategy {
+            InputStrategy::NonBlocking => writeln!(f, "input_strategy = non_blocking")?,
+            InputStrategy::FrameBudgeted => writeln!(f, "input_strategy = frame_budgeted")?,
+            InputStrategy::Timeout(timeout) => {
+                writeln!(f, "input_strategy = {}", timeout.as_millis())?
+            }
+        }
+        writeln!(f, "max_frame_time = {}", self.max_frame_time.as_millis())?;
+        match self.cpu_budget {
+            Some(budget) => writeln!(f, "cpu_budget = {}", budget.as_millis())?,
+            None => writeln!(f, "cpu_budget = none")?,
+        }
+        writeln!(f, "max_frame_skip = {}", self.max_frame_skip)?;
+        writeln!(f, "debug_mode = {}", self.debug_mode)?;
+        writeln!(f, "show_redraws = {}", self.show_redraws)?;
+        writeln!(f, "vsync = {}", self.vsync)?;
+        match self.screen_size {
+            ScreenSize::Fullscreen => writeln!(f, "screen_size = fullscreen")?,
+            ScreenSize::Fixed(width, height) => writeln!(f, "screen_size = {}x{}", width, height)?,
+            ScreenSize::Centered(width, height) => {
+                writeln!(f, "screen_size = centered:{}x{}", width, height)?
+            }
+        }
+        writeln!(f, "mouse_capture = {}", self.mouse_capture)?;
+        writeln!(f, "touch_controls = {}", self.touch_controls)?;
+        if let Some((width, height)) = self.min_screen_size {
+            writeln!(f, "min_screen_size = {}x{}", width, height)?;
+        }
+        let policy = match self.error_policy {
+            ErrorPolicy::Continue => "continue",
+            ErrorPolicy::LogAndContinue => "log_and_continue",
+            ErrorPolicy::Abort => "abort",
+        };
+        writeln!(f, "error_policy = {}", policy)?;
+        writeln!(f, "quit_keys = {}", keys(&self.quit_keys))?;
+        writeln!(f, "pause_key = {}", keys(self.pause_key.as_slice()))?;
+        match self.idle_timeout {
+            Some(timeout) => writeln!(f, "idle_timeout = {}", timeout.as_millis())?,
+            None => writeln!(f, "idle_timeout = none")?,
+        }
+        writeln!(f, "high_contrast = {}", self.high_contrast)?;
+        writeln!(f, "reduced_motion = {}", self.reduced_motion)?;
+        match &self.speech_output {
+            Some(path) => writeln!(f, "speech_output = {}", path.display())?,
+            None => writeln!(f, "speech_output = none")?,
+        }
+        match self.color_blindness {
+            Some(deficiency) => writeln!(f, "color_blindness = {}", deficiency)?,
+            None => writeln!(f, "color_blindness = none")?,
+        }
+        writeln!(f, "glyphs = {}", self.glyphs)?;
+        writeln!(f, "console = {}", self.console)?;
+        writeln!(f, "output = {}", self.output)?;
+        match &self.spectate {
+            Some(target) => writeln!(f, "spectate = {}", target)?,
+            None => writeln!(f, "spectate = none")?,
+        }
+        writeln!(f, "color_depth = {}", self.color_depth)?;
+        match &self.autosave {
+            Some(path) => writeln!(f, "autosave = {}", path.display())?,
+            None => writeln!(f, "autosave = none")?,
+        }
+        match self.autosave_interval {
+            Some(interval) => writeln!(f, "autosave_interval = {}", interval.as_millis())?,
+            None => writeln!(f, "autosave_interval = none")?,
+        }
+        writeln!(f, "autosave_backups = {}", self.autosave_backups)?;
+        writeln!(f, "autosave_format = {}", self.autosave_format)?;
+        match &self.app_name {
+            Some(name) => writeln!(f, "app_name = {}", name),
+            None => writeln!(f, "app_name = none"),
+        }
+    }
+}
+
 impl Default for GameConfig {
     fn default() -> Self {
         Self::new()
     }
 }
 
+#[cfg(feature = "cli")]
+mod cli {
+    use super::{Config, ENV_PREFIX, GameConfig};
+    use crate::errors::EngineError;
+    use clap::{Arg, ArgAction, ArgMatches, Command};
+
+    pub(super) fn command() -> Command {
+        Command::new("coil")
+            .arg(Arg::new("profile").long("profile").value_name("PROFILE"))
+            .arg(Arg::new("config").long("config").value_name("FILE"))
+            .arg(Arg::new("target_fps").long("target-fps").value_name("FPS"))
+            .arg(
+                Arg::new("input_strategy")
+                    .long("input-strategy")
+                    .value_name("STRATEGY"),
+            )
+            .arg(
+                Arg::new("max_frame_time")
+                    .long("max-frame-time")
+                    .value_name("MS"),
+            )
+            .arg(Arg::new("cpu_budget").long("cpu-budget").value_name("MS"))
+            .arg(
+                Arg::new("max_frame_skip")
+                    .long("max-frame-skip")
+                    .value_name("FRAMES"),
+            )
+            .arg(
+                Arg::new("debug_mode")
+                    .long("debug")
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("show_redraws")
+                    .long("show-redraws")
+                    .value_name("BOOL"),
+            )
+            .arg(Arg::new("vsync").long("vsync").value_name("BOOL"))
+            .arg(
+                Arg::new("screen_size")
+                    .long("screen-size")
+                    .value_name("WxH"),
+            )
+            .arg(
+                Arg::new("min_screen_size")
+                    .long("min-screen-size")
+                    .value_name("WxH"),
+            )
+            .arg(
+                Arg::new("error_policy")
+                    .long("error-policy")
+                    .value_name("POLICY"),
+            )
+            .arg(
+                Arg::new("mouse_capture")
+                    .long("mouse-capture")
+                    .value_name("BOOL"),
+            )
+            .arg(
+                Arg::new("touch_controls")
+                    .long("touch-controls")
+                    .value_name("BOOL"),
+            )
+            .arg(Arg::new("quit_keys").long("quit-keys").value_name("KEYS"))
+            .arg(Arg::new("pause_key").long("pause-key").value_name("KEY"))
+            .arg(
+                Arg::new("idle_timeout")
+                    .long("idle-timeout")
+                    .value_name("MS"),
+            )
+            .arg(
+                Arg::new("high_contrast")
+                    .long("high-contrast")
+                    .value_name("BOOL"),
+            )
+            .arg(
+                Arg::new("reduced_motion")
+                    .long("reduced-motion")
+                    .value_name("BOOL"),
+            )
+            .arg(
+                Arg::new("speech_output")
+                    .long("speech-output")
+                    .value_name("FILE"),
+            )
+            .arg(Arg::new("glyphs").long("glyphs").value_name("SET"))
+            .arg(Arg::new("console").long("console").value_name("MODE"))
+            .arg(Arg::new("output").long("output").value_name("MODE"))
+            .arg(Arg::new("spectate").long("spectate").value_name("ADDRESS"))
+            .arg(
+                Arg::new("color_blindness")
+                    .long("color-blindness")
+                    .value_name("TYPE"),
+            )
+            .arg(
+                Arg::new("color_depth")
+                    .long("color-depth")
+                    .value_name("DEPTH"),
+            )
+            .arg(Arg::new("autosave").long("autosave").value_name("FILE"))
+            .arg(
+                Arg::new("autosave_interval")
+                    .long("autosave-interval")
+                    .value_name("MS"),
+            )
+            .arg(
+                Arg::new("autosave_backups")
+                    .long("autosave-backups")
+                    .value_name("COUNT"),
+            )
+            .arg(
+                Arg::new("autosave_format")
+                    .long("autosave-format")
+                    .value_name("FORMAT"),
+            )
+            .arg(Arg::new("app_name").long("app-name").value_name("NAME"))
+    }
+
+    pub(super) fn merge_matches(
+        mut config: GameConfig,
+        matches: &ArgMatches,
+    ) -> Result<GameConfig, EngineError> {
+        if let Some(path) = matches.get_one::<String>("config") {
+            config = config.merge_file(path)?;
+        }
+        config = config.merge_env(ENV_PREFIX)?;
+        if matches.get_flag("debug_mode") {
+            config = config.add_config(Config::DebugMode(true));
+        }
+        for &key in Config::KEYS {
+            if key == "debug_mode" {
+                continue;
+            }
+            if let Some(value) = matches.get_one::<String>(key) {
+                config = config.add_config(Config::parse(key, value)?);
+            }
+        }
+        Ok(config)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -95,4 +926,222 @@ mod tests {
         assert!(config.vsync);
         assert_eq!(config.max_frame_time, Duration::from_millis(50));
     }
+
+    #[test]
+    fn test_config_parse() {
+        let config = GameConfig::new()
+            .add_config(Config::parse("target_fps", "30").unwrap())
+            .add_config(Config::parse("input_strategy", "frame-budgeted").unwrap())
+            .add_config(Config::parse("max_frame_time", "100").unwrap())
+            .add_config(Config::parse("debug_mode", "yes").unwrap())
+            .add_config(Config::parse("vsync", "off").unwrap())
+            .add_config(Config::parse("screen_size", "100x40").unwrap())
+            .add_config(Config::parse("error_policy", "abort").unwrap())
+            .add_config(Config::parse("quit_keys", "Esc, Ctrl+c").unwrap())
+            .add_config(Config::parse("pause_key", "p").unwrap());
+        assert_eq!(config.target_fps, 30);
+        assert!(matches!(
+            config.input_strategy,
+            InputStrategy::FrameBudgeted
+        ));
+        assert_eq!(config.max_frame_time, Duration::from_millis(100));
+        assert!(config.debug_mode);
+        assert!(!config.vsync);
+        assert_eq!(config.screen_size, ScreenSize::Fixed(100, 40));
+        assert_eq!(config.error_policy, ErrorPolicy::Abort);
+        assert_eq!(
+            config.quit_keys,
+            ["Esc".parse().unwrap(), "Ctrl+c".parse().unwrap()]
+        );
+        assert_eq!(config.pause_key, Some("p".parse().unwrap()));
+        assert!(matches!(
+            Config::parse("pause_key", "none"),
+            Ok(Config::PauseKey(None))
+        ));
+    }
+
+    #[test]
+    fn test_config_parse_errors() {
+        assert!(matches!(
+            Config::parse("target_fps", "fast"),
+            Err(EngineError::Config(_))
+        ));
+        assert!(Config::parse("screen_size", "100").is_err());
+        assert!(Config::parse("screen_size", "middle:10x10").is_err());
+        assert!(Config::parse("vsync", "maybe").is_err());
+        assert!(Config::parse("unknown", "1").is_err());
+        assert!(Config::parse("quit_keys", "Esc, Hyper+q").is_err());
+        assert!(
+            GameConfig::new()
+                .add_config(Config::ScreenSize(ScreenSize::Centered(0, 24)))
+                .validate()
+                .is_err()
+        );
+        assert_eq!(
+            GameConfig::new()
+                .add_config(Config::AutosaveFormat(SaveFormat::Database))
+                .validate()
+                .is_ok(),
+            cfg!(feature = "db")
+        );
+    }
+
+    #[test]
+    fn test_screen_size_resolve() {
+        assert_eq!(
+            ScreenSize::Fullscreen.resolve((120, 40)).unwrap(),
+            Rect::new(0, 0, 120, 40)
+        );
+        assert_eq!(
+            ScreenSize::Fixed(80, 24).resolve((120, 40)).unwrap(),
+            Rect::new(0, 0, 80, 24)
+        );
+        assert_eq!(
+            ScreenSize::Centered(80, 24).resolve((120, 40)).unwrap(),
+            Rect::new(20, 8, 80, 24)
+        );
+        assert!(matches!(
+            ScreenSize::Centered(80, 24).resolve((60, 40)),
+            Err(EngineError::Config(_))
+        ));
+        assert!(matches!(
+            Config::parse("screen_size", "centered:80x24").unwrap(),
+            Config::ScreenSize(ScreenSize::Centered(80, 24))
+        ));
+        assert!(matches!(
+            Config::parse("screen_size", "Fullscreen").unwrap(),
+            Config::ScreenSize(ScreenSize::Fullscreen)
+        ));
+    }
+
+    #[test]
+    fn test_required_size() {
+        let config = GameConfig::new().add_config(Config::ScreenSize(ScreenSize::Fixed(80, 20)));
+        assert_eq!(config.required_size(), (80, 20));
+
+        let config = config.add_config(Config::parse("min_screen_size", "60x30").unwrap());
+        assert_eq!(config.required_size(), (80, 30));
+    }
+
+    #[test]
+    fn test_profiles() {
+        let dev = GameConfig::new().with_profile(Profile::Dev);
+        assert!(dev.debug_mode);
+        assert_eq!(dev.target_fps, 30);
+
+        let ci = GameConfig::new()
+            .merge_str("profile = ci\ntarget_fps = 20")
+            .unwrap();
+        assert_eq!(ci.screen_size, ScreenSize::Fixed(80, 24));
+        assert!(!ci.mouse_capture);
+        assert_eq!(ci.output, OutputMode::FrameDump);
+        assert_eq!(ci.target_fps, 20);
+
+        assert!("staging".parse::<Profile>().is_err());
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let config = GameConfig::new()
+            .with_profile(Profile::Ci)
+            .add_config(Config::InputStrategy(InputStrategy::Timeout(
+                Duration::from_millis(7),
+            )))
+            .add_config(Config::MinScreenSize((40, 12)))
+            .add_config(Config::ErrorPolicy(ErrorPolicy::Abort))
+            .add_config(Config::parse("quit_keys", "Esc, Ctrl+q").unwrap())
+            .add_config(Config::parse("pause_key", "p").unwrap())
+            .add_config(Config::parse("idle_timeout", "30000").unwrap())
+            .add_config(Config::parse("high_contrast", "on").unwrap())
+            .add_config(Config::parse("touch_controls", "yes").unwrap())
+            .add_config(Config::parse("speech_output", "speech.txt").unwrap())
+            .add_config(Config::parse("color_blindness", "tritanopia").unwrap())
+            .add_config(Config::parse("glyphs", "ascii").unwrap())
+            .add_config(Config::parse("console", "legacy").unwrap())
+            .add_config(Config::parse("output", "frame-dump").unwrap())
+            .add_config(Config::parse("spectate", "127.0.0.1:7878").unwrap())
+            .add_config(Config::parse("color_depth", "256").unwrap())
+            .add_config(Config::parse("autosave", "save.txt").unwrap())
+            .add_config(Config::parse("autosave_interval", "60000").unwrap())
+            .add_config(Config::parse("autosave_backups", "5").unwrap())
+            .add_config(Config::parse("autosave_format", "db").unwrap())
+            .add_config(Config::parse("app_name", "snake").unwrap())
+            .add_config(Config::parse("cpu_budget", "8").unwrap())
+            .add_config(Config::parse("max_frame_skip", "2").unwrap());
+        let parsed = GameConfig::new().merge_str(&config.to_string()).unwrap();
+        assert_eq!(parsed.to_string(), config.to_string());
+        assert_eq!(parsed.screen_size, config.screen_size);
+        assert_eq!(parsed.quit_keys, config.quit_keys);
+        assert_eq!(parsed.pause_key, config.pause_key);
+        assert_eq!(parsed.idle_timeout, Some(Duration::from_secs(30)));
+        assert!(parsed.high_contrast);
+        assert!(parsed.touch_controls);
+        assert!(!parsed.reduced_motion);
+        assert_eq!(parsed.speech_output, Some(PathBuf::from("speech.txt")));
+        assert_eq!(parsed.color_blindness, Some(ColorBlindness::Tritanopia));
+        assert_eq!(parsed.glyphs, GlyphSupport::Ascii);
+        assert_eq!(parsed.console, ConsoleMode::Legacy);
+        assert_eq!(parsed.output, OutputMode::FrameDump);
+        assert_eq!(parsed.spectate, config.spectate);
+        assert_eq!(parsed.color_depth, ColorDepth::Ansi256);
+        assert_eq!(parsed.autosave, Some(PathBuf::from("save.txt")));
+        assert_eq!(parsed.autosave_interval, Some(Duration::from_secs(60)));
+        assert_eq!(parsed.autosave_backups, 5);
+        assert_eq!(parsed.autosave_format, SaveFormat::Database);
+        assert_eq!(parsed.app_name.as_deref(), Some("snake"));
+        assert_eq!(parsed.cpu_budget, Some(Duration::from_millis(8)));
+        assert_eq!(parsed.max_frame_skip, 2);
+    }
+
+    #[test]
+    fn test_merge_str() {
+        let config = GameConfig::new()
+            .merge_str("# comment\n\ntarget_fps = 24\nscreen_size = \"40x20\"\n")
+            .unwrap();
+        assert_eq!(config.target_fps, 24);
+        assert_eq!(config.screen_size, ScreenSize::Fixed(40, 20));
+        assert!(GameConfig::new().merge_str("target_fps").is_err());
+    }
+
+    #[test]
+    fn test_merge_vars() {
+        let vars = [
+            ("COIL_TARGET_FPS", "15"),
+            ("OTHER_DEBUG_MODE", "true"),
+            ("COIL_NOT_A_KEY", "1"),
+        ];
+        let vars = vars.map(|(name, value)| (name.to_string(), value.to_string()));
+        let config = GameConfig::new().merge_vars("COIL", vars).unwrap();
+        assert_eq!(config.target_fps, 15);
+        assert!(!config.debug_mode);
+        assert_eq!(config.max_frame_time, Duration::from_millis(50));
+
+        let bad = [("COIL_TARGET_FPS".to_string(), "fast".to_string())];
+        assert!(GameConfig::new().merge_vars("COIL", bad).is_err());
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_merge_args() {
+        let config = GameConfig::new()
+            .merge_args(["game", "--debug", "--target-fps", "45"])
+            .unwrap();
+        assert!(config.debug_mode);
+        assert_eq!(config.target_fps, 45);
+        assert!(GameConfig::new().merge_args(["game", "--bogus"]).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let config = GameConfig::new().with_profile(Profile::Ci);
+        let json = serde_json::to_string(&config).unwrap();
+        let restored: GameConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.screen_size, config.screen_size);
+        assert_eq!(restored.max_frame_time, config.max_frame_time);
+
+        let partial: GameConfig = serde_json::from_str(r#"{"target_fps": 12}"#).unwrap();
+        assert_eq!(partial.target_fps, 12);
+        assert!(partial.vsync);
+    }
 }
@@ -0,0 +1,663 @@
+//! Engine context handed to nodes while the game is running.
+//!
+//! The context gives nodes read access to the active configuration and lets
+//! them queue changes that the event loop applies at the start of the next frame.
+use crate::camera::Camera;
+use crate::color::Rgb;
+use crate::config::{Config, GameConfig};
+use crate::drag::{Drag, DragEvent, DragState};
+use crate::errors::EngineError;
+use crate::geometry::Rect;
+use crate::hit::{Hit, HitRegions};
+use crate::input::InputStrategy;
+use crate::jobs::{JobId, Jobs};
+use crate::nodes::Popup;
+use crate::notify::{Level, MAX_VISIBLE, Notification};
+use crate::paths::Paths;
+use crate::scene::{Scene, SceneCommand};
+use crate::stats::FrameStats;
+use crate::style::{Style, Theme};
+use crate::terminal::TerminalCommand;
+use crate::tooltip::{Tooltip, TooltipState};
+use crossterm::cursor::SetCursorStyle;
+use crossterm::event::Event;
+use log::warn;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Notifications emitted by the engine itself, as opposed to terminal input.
+#[derive(Debug, Clone)]
+pub enum EngineEvent {
+    /// A configuration change requested through the [`Context`] has been applied.
+    ConfigChanged(Config),
+    /// The notifications on display changed; holds the ones now visible, oldest first.
+    Notifications(Vec<Notification>),
+    /// No input has arrived for the configured `idle_timeout`.
+    Idle,
+    /// Input arrived after an [`EngineEvent::Idle`]; sent before the input itself.
+    ActivityResumed,
+    /// A mouse event over a region registered with
+    /// [`Frame::hit_region`](crate::frame::Frame::hit_region); sent before the
+    /// event itself reaches `on_event`.
+    Hit(Hit),
+    /// A drag started with [`Context::start_drag`] entered or left a region,
+    /// was dropped or was cancelled.
+    Drag(DragEvent),
+    /// The game was autosaved to the given file.
+    Saved(PathBuf),
+    /// An autosave failed; holds the error message. The previous save is kept.
+    SaveFailed(String),
+    /// A job spawned with [`Context::spawn_job`] finished; take its result
+    /// with [`Context::take_job`].
+    JobFinished(JobId),
+}
+
+/// Runtime state shared between the engine and the node tree.
+#[derive(Debug)]
+pub struct Context {
+    config: GameConfig,
+    pending: Vec<Config>,
+    viewport: Rect,
+    exit_code: Option<i32>,
+    save_requested: bool,
+    paused: bool,
+    pause_pending: Option<bool>,
+    camera: Camera,
+    world_tint: Option<Rgb>,
+    popups: Vec<Popup>,
+    /// Popups already queued when the last update finished
+    stale_popups: usize,
+    /// Queued notifications; the first [`MAX_VISIBLE`] are on display
+    notifications: VecDeque<Notification>,
+    notifications_changed: bool,
+    idle: bool,
+    announcements: Vec<String>,
+    /// Input sent by the game itself, delivered with the next frame's events
+    sent_events: Vec<Event>,
+    terminal_commands: Vec<TerminalCommand>,
+    hit_regions: HitRegions,
+    drag: DragState,
+    tooltips: TooltipState,
+    jobs: Jobs,
+    frame_stats: FrameStats,
+    /// Band energies published by an `AudioInput`, lowest band first
+    audio_bands: Vec<f32>,
+    /// Scene changes for the `SceneStack`, applied after the current hook
+    scene_commands: Vec<SceneCommand>,
+    /// Scene changes already queued when the last update finished
+    stale_scene_commands: usize,
+}
+
+impl Context {
+    /// Creates a context for the given configuration.
+    pub fn new(config: GameConfig) -> Self {
+        Self {
+            config,
+            pending: Vec::new(),
+            viewport: Rect::default(),
+            exit_code: None,
+            save_requested: false,
+            paused: false,
+            pause_pending: None,
+            camera: Camera::default(),
+            world_tint: None,
+            popups: Vec::new(),
+            stale_popups: 0,
+            notifications: VecDeque::new(),
+            notifications_changed: false,
+            idle: false,
+            announcements: Vec::new(),
+            sent_events: Vec::new(),
+            terminal_commands: Vec::new(),
+            hit_regions: HitRegions::new(),
+            drag: DragState::default(),
+            tooltips: TooltipState::default(),
+            jobs: Jobs::default(),
+            frame_stats: FrameStats::new(),
+            audio_bands: Vec::new(),
+            scene_commands: Vec::new(),
+            stale_scene_commands: 0,
+        }
+    }
+
+    /// Returns the area of the terminal the game is drawn into.
+    pub fn viewport(&self) -> Rect {
+        self.viewport
+    }
+
+    /// Returns the size of the drawing area as `(width, height)`.
+    pub fn screen_size(&self) -> (u16, u16) {
+        self.viewport.size()
+    }
+
+    pub(crate) fn set_viewport(&mut self, viewport: Rect) {
+        self.viewport = viewport;
+    }
+
+    /// Returns whether the player has been idle for the configured `idle_timeout`.
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    pub(crate) fn set_idle(&mut self, idle: bool) {
+        self.idle = idle;
+    }
+
+    /// Returns the hit regions registered while rendering the last frame.
+    pub fn hit_regions(&self) -> &HitRegions {
+        &self.hit_regions
+    }
+
+    /// Returns statistics of the latest frames; see [`crate::stats`].
+    pub fn frame_stats(&self) -> &FrameStats {
+        &self.frame_stats
+    }
+
+    /// Returns whether the engine is over its CPU budget, in which case
+    /// nodes should cut back on effects; see [`crate::stats`].
+    pub fn is_degraded(&self) -> bool {
+        self.frame_stats.is_degraded()
+    }
+
+    pub(crate) fn frame_stats_mut(&mut self) -> &mut FrameStats {
+        &mut self.frame_stats
+    }
+
+    /// Returns the energy of each audio band between 0 and 1, lowest band
+    /// first, or nothing without an audio input; see [`crate::audio`].
+    pub fn audio_bands(&self) -> &[f32] {
+        &self.audio_bands
+    }
+
+    pub(crate) fn set_audio_bands(&mut self, bands: Vec<f32>) {
+        self.audio_bands = bands;
+    }
+
+    /// Exchanges the hit regions with those of a newly rendered frame.
+    pub(crate) fn swap_hit_regions(&mut self, regions: &mut HitRegions) {
+        std::mem::swap(&mut self.hit_regions, regions);
+    }
+
+    /// Starts dragging `drag`, cancelling any drag in progress.
+    ///
+    /// The drag follows the mouse until the button is released; call this
+    /// while handling the press, e.g. an [`EngineEvent::Hit`] for a
+    /// [`MouseEventKind::Down`](crossterm::event::MouseEventKind::Down).
+    pub fn start_drag(&mut self, drag: Drag) {
+        self.drag.start(drag);
+    }
+
+    /// Returns the drag in progress, if any.
+    pub fn drag(&self) -> Option<&Drag> {
+        self.drag.drag()
+    }
+
+    /// Cancels the drag in progress, sending [`DragEvent::Cancel`].
+    pub fn cancel_drag(&mut self) {
+        self.drag.cancel();
+    }
+
+    pub(crate) fn drag_state(&mut self) -> &mut DragState {
+        &mut self.drag
+    }
+
+    /// Runs `job` on a worker thread; see [`crate::jobs`].
+    pub fn spawn_job<T: Send + 'static>(
+        &mut self,
+        job: impl FnOnce() -> T + Send + 'static,
+    ) -> JobId {
+        self.jobs.spawn(job)
+    }
+
+    /// Takes the result of a job after its [`EngineEvent::JobFinished`];
+    /// `None` if it has not finished or was already taken.
+    pub fn take_job<T: std::any::Any>(&mut self, id: JobId) -> Option<Result<T, EngineError>> {
+        self.jobs.take(id)
+    }
+
+    /// Returns the number of jobs still running.
+    pub fn running_jobs(&self) -> usize {
+        self.jobs.running()
+    }
+
+    pub(crate) fn poll_jobs(&mut self) -> Vec<JobId> {
+        self.jobs.poll()
+    }
+
+    /// Sets how long content has to be hovered or focused before its
+    /// tooltip shows; see [`crate::tooltip`].
+    pub fn set_tooltip_delay(&mut self, delay: Duration) {
+        self.tooltips.set_delay(delay);
+    }
+
+    pub(crate) fn tooltip_state(&mut self) -> &mut TooltipState {
+        &mut self.tooltips
+    }
+
+    /// Advances the tooltip delay by `elapsed` and returns the tooltip to
+    /// show, if any, based on the regions of the last frame.
+    pub(crate) fn advance_tooltip(&mut self, elapsed: Duration) -> Option<Tooltip> {
+        self.tooltips.advance(elapsed, &self.hit_regions);
+        self.tooltips.visible(&self.hit_regions)
+    }
+
+    /// Returns the camera applied to the world layer.
+    pub fn camera(&self) -> Camera {
+        self.camera
+    }
+
+    /// Moves the camera; the world is drawn from the new position this frame.
+    pub fn set_camera(&mut self, camera: Camera) {
+        self.camera = camera;
+    }
+
+    /// Returns the camera for in-place changes such as [`Camera::center_on`].
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    /// Returns the light the world layer is tinted with, if any.
+    pub fn world_tint(&self) -> Option<Rgb> {
+        self.world_tint
+    }
+
+    /// Tints every cell of the world layer, e.g. with a [`DayCycle`](crate::day_cycle::DayCycle);
+    /// `None` draws the world in its own colors.
+    pub fn set_world_tint(&mut self, tint: Option<Rgb>) {
+        self.world_tint = tint;
+    }
+
+    /// Pops up a short text at a world position, shown by a
+    /// [`FloatingText`](crate::nodes::FloatingText) node.
+    ///
+    /// Popups no node took by the end of the following update are dropped.
+    pub fn popup(&mut self, x: f32, y: f32, text: impl Into<String>, style: Style) {
+        self.popups.push(Popup {
+            x,
+            y,
+            text: text.into(),
+            style,
+        });
+    }
+
+    pub(crate) fn take_popups(&mut self) -> Vec<Popup> {
+        self.stale_popups = 0;
+        std::mem::take(&mut self.popups)
+    }
+
+    /// Drops what was queued for nodes that a whole update went by without
+    /// taking, so it does not pile up when no node is listening.
+    pub(crate) fn finish_update(&mut self) {
+        self.popups.drain(..self.stale_popups);
+        self.stale_popups = self.popups.len();
+        if self.stale_scene_commands > 0 {
+            warn!(
+                "Dropping {} scene changes with no SceneStack to apply them",
+                self.stale_scene_commands
+            );
+            self.scene_commands.drain(..self.stale_scene_commands);
+        }
+        self.stale_scene_commands = self.scene_commands.len();
+    }
+
+    /// Posts a notification shown for `duration` once it reaches the display.
+    ///
+    /// The message is also announced to screen readers, see [`Context::announce`].
+    pub fn notify(&mut self, message: impl Into<String>, level: Level, duration: Duration) {
+        let message = message.into();
+        self.announce(message.as_str());
+        self.notifications.push_back(Notification {
+            message,
+            level,
+            remaining: duration,
+        });
+        self.notifications_changed |= self.notifications.len() <= MAX_VISIBLE;
+    }
+
+    /// Describes something for players using a screen reader, such as the
+    /// menu item that just got focus.
+    ///
+    /// Announcements are written to the configured `speech_output` file at
+    /// the end of the frame, and dropped when there is none.
+    pub fn announce(&mut self, text: impl Into<String>) {
+        if self.config.speech_output.is_some() {
+            self.announcements.push(text.into());
+        }
+    }
+
+    pub(crate) fn take_announcements(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.announcements)
+    }
+
+    /// Queues an input event as if it had been read from the terminal, e.g.
+    /// a key pressed through chat or an external controller.
+    ///
+    /// Queued events are delivered at the start of the next frame, after
+    /// the terminal's, and go through quit and pause keys like them.
+    pub fn send_event(&mut self, event: Event) {
+        self.sent_events.push(event);
+    }
+
+    pub(crate) fn take_sent_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.sent_events)
+    }
+
+    /// Pushes `scene` on top of the [`SceneStack`](crate::scene::SceneStack)
+    /// once the current hook returns.
+    ///
+    /// Without a `SceneStack` in the game, scene changes are logged and
+    /// dropped after the following update.
+    pub fn push_scene(&mut self, scene: impl Scene + 'static) {
+        self.scene_commands
+            .push(SceneCommand::Push(Box::new(scene)));
+    }
+
+    /// Pops the top scene once the current hook returns.
+    pub fn pop_scene(&mut self) {
+        self.scene_commands.push(SceneCommand::Pop);
+    }
+
+    /// Replaces the top scene with `scene` once the current hook returns.
+    pub fn replace_scene(&mut self, scene: impl Scene + 'static) {
+        self.scene_commands
+            .push(SceneCommand::Replace(Box::new(scene)));
+    }
+
+    pub(crate) fn take_scene_commands(&mut self) -> Vec<SceneCommand> {
+        self.stale_scene_commands = 0;
+        std::mem::take(&mut self.scene_commands)
+    }
+
+    /// Returns the notifications on display, oldest first.
+    pub fn notifications(&self) -> impl Iterator<Item = &Notification> {
+        self.notifications.iter().take(MAX_VISIBLE)
+    }
+
+    /// Counts down the visible notifications by real elapsed time, returning
+    /// the new visible set if it changed since the last call.
+    pub(crate) fn advance_notifications(&mut self, elapsed: Duration) -> Option<Vec<Notification>> {
+        for notification in self.notifications.iter_mut().take(MAX_VISIBLE) {
+            notification.remaining = notification.remaining.saturating_sub(elapsed);
+        }
+        let before = self.notifications.len();
+        let mut index = 0;
+        self.notifications.retain(|notification| {
+            let keep = index >= MAX_VISIBLE || !notification.remaining.is_zero();
+            index += 1;
+            keep
+        });
+        if before == self.notifications.len() && !std::mem::take(&mut self.notifications_changed) {
+            return None;
+        }
+        self.notifications_changed = false;
+        Some(self.notifications().cloned().collect())
+    }
+
+    /// Returns the configuration currently in effect.
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    /// Returns the directories for the game's files, if the `app_name`
+    /// setting names the game.
+    pub fn paths(&self) -> Option<Paths> {
+        self.config.app_name.as_deref().map(Paths::new)
+    }
+
+    /// Queues a configuration change to be applied at the start of the next frame.
+    pub fn set_config(&mut self, config: Config) {
+        self.pending.push(config);
+    }
+
+    /// Changes the target frames per second from the next frame on.
+    pub fn set_target_fps(&mut self, fps: u32) {
+        self.set_config(Config::TargetFps(fps));
+    }
+
+    /// Changes the input handling strategy from the next frame on.
+    pub fn set_input_strategy(&mut self, strategy: InputStrategy) {
+        self.set_config(Config::InputStrategy(strategy));
+    }
+
+    /// Enables or disables mouse capture from the next frame on.
+    pub fn set_mouse_capture(&mut self, enabled: bool) {
+        self.set_config(Config::MouseCapture(enabled));
+    }
+
+    /// Changes the styles of the engine's menus, overlays and notifications
+    /// from the next frame on.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.set_config(Config::Theme(theme));
+    }
+
+    /// Sets the terminal window title; the player's title is restored on exit.
+    pub fn set_title(&mut self, title: impl Into<String>) {
+        self.terminal_commands
+            .push(TerminalCommand::SetTitle(title.into()));
+    }
+
+    /// Rings the terminal bell.
+    pub fn bell(&mut self) {
+        self.terminal_commands.push(TerminalCommand::Bell);
+    }
+
+    /// Changes the cursor's shape, e.g. to a blinking bar while editing text.
+    pub fn set_cursor_style(&mut self, style: SetCursorStyle) {
+        self.terminal_commands
+            .push(TerminalCommand::CursorStyle(style));
+    }
+
+    /// Shows the cursor at a position within the viewport, or hides it with
+    /// `None` (the default).
+    pub fn set_cursor(&mut self, position: Option<(u16, u16)>) {
+        self.terminal_commands
+            .push(TerminalCommand::Cursor(position));
+    }
+
+    pub(crate) fn take_terminal_commands(&mut self) -> Vec<TerminalCommand> {
+        std::mem::take(&mut self.terminal_commands)
+    }
+
+    /// Asks the engine to end the game with the given process exit code.
+    ///
+    /// The event loop stops as soon as the current event or update returns;
+    /// the latest request wins if several are made.
+    pub fn request_exit(&mut self, code: i32) {
+        self.exit_code = Some(code);
+    }
+
+    /// Returns the exit code requested with [`Context::request_exit`], if any.
+    pub fn exit_requested(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Asks the engine to autosave at the end of the current frame, e.g. at a
+    /// checkpoint. Does nothing unless the `autosave` setting names a file.
+    pub fn request_save(&mut self) {
+        self.save_requested = true;
+    }
+
+    pub(crate) fn take_save_request(&mut self) -> bool {
+        std::mem::take(&mut self.save_requested)
+    }
+
+    /// Returns whether the game is paused, which halts fixed updates.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Pauses the game once the current events have been handled.
+    pub fn pause(&mut self) {
+        self.pause_pending = Some(true);
+    }
+
+    /// Resumes the game once the current events have been handled.
+    pub fn resume(&mut self) {
+        self.pause_pending = Some(false);
+    }
+
+    /// Pauses a running game or resumes a paused one.
+    pub fn toggle_pause(&mut self) {
+        self.pause_pending = Some(!self.pause_pending.unwrap_or(self.paused));
+    }
+
+    /// Applies a queued pause or resume, returning the new state if it changed.
+    pub(crate) fn apply_pause(&mut self) -> Option<bool> {
+        let paused = self.pause_pending.take()?;
+        if paused == self.paused {
+            return None;
+        }
+        self.paused = paused;
+        Some(paused)
+    }
+
+    /// Applies all queued changes, returning the ones that took effect.
+    ///
+    /// Changes that would leave the configuration invalid are logged and dropped.
+    pub(crate) fn apply_pending(&mut self) -> Vec<Config> {
+        let mut applied = Vec::new();
+        for change in std::mem::take(&mut self.pending) {
+            let candidate = self.config.clone().add_config(change.clone());
+            match candidate.validate() {
+                Ok(()) => {
+                    self.config = candidate;
+                    applied.push(change);
+                }
+                Err(e) => {
+                    warn!("Ignoring configuration change {:?}: {}", change, e);
+                    self.notify(e.to_string(), Level::Warning, Duration::from_secs(3));
+                }
+            }
+        }
+        applied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_apply_next_frame() {
+        let mut ctx = Context::new(GameConfig::new());
+        ctx.set_target_fps(30);
+        assert_eq!(ctx.config().target_fps, 60);
+
+        let applied = ctx.apply_pending();
+        assert_eq!(applied.len(), 1);
+        assert!(matches!(applied[0], Config::TargetFps(30)));
+        assert_eq!(ctx.config().target_fps, 30);
+        assert!(ctx.apply_pending().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_changes_are_dropped() {
+        let mut ctx = Context::new(GameConfig::new());
+        ctx.set_target_fps(0);
+        ctx.set_mouse_capture(false);
+
+        let applied = ctx.apply_pending();
+        assert_eq!(applied.len(), 1);
+        assert!(matches!(applied[0], Config::MouseCapture(false)));
+        assert_eq!(ctx.config().target_fps, 60);
+        assert!(!ctx.config().mouse_capture);
+    }
+
+    #[test]
+    fn test_pause_applies_once() {
+        let mut ctx = Context::new(GameConfig::new());
+        ctx.toggle_pause();
+        assert!(!ctx.is_paused());
+        assert_eq!(ctx.apply_pause(), Some(true));
+        assert!(ctx.is_paused());
+
+        ctx.pause();
+        assert_eq!(ctx.apply_pause(), None);
+        ctx.toggle_pause();
+        ctx.toggle_pause();
+        assert_eq!(ctx.apply_pause(), None);
+        ctx.resume();
+        assert_eq!(ctx.apply_pause(), Some(false));
+    }
+
+    #[test]
+    fn test_notifications_queue_and_expire() {
+        let mut ctx = Context::new(GameConfig::new());
+        assert!(ctx.advance_notifications(Duration::ZERO).is_none());
+        for i in 0..4 {
+            ctx.notify(format!("n{}", i), Level::Info, Duration::from_secs(2));
+        }
+        let visible = ctx.advance_notifications(Duration::from_secs(1)).unwrap();
+        assert_eq!(visible.len(), MAX_VISIBLE);
+        assert_eq!(visible[0].message, "n0");
+        assert!(ctx.advance_notifications(Duration::ZERO).is_none());
+
+        // the queued one starts counting down only once it is shown
+        let visible = ctx.advance_notifications(Duration::from_secs(1)).unwrap();
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].message, "n3");
+        assert_eq!(visible[0].remaining, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_request_exit() {
+        let mut ctx = Context::new(GameConfig::new());
+        assert_eq!(ctx.exit_requested(), None);
+        ctx.request_exit(3);
+        ctx.request_exit(0);
+        assert_eq!(ctx.exit_requested(), Some(0));
+    }
+
+    #[test]
+    fn test_terminal_commands_queue_in_order() {
+        let mut ctx = Context::new(GameConfig::new());
+        ctx.set_title("Coil");
+        ctx.bell();
+        ctx.set_cursor(Some((2, 3)));
+        assert_eq!(
+            ctx.take_terminal_commands(),
+            [
+                TerminalCommand::SetTitle("Coil".to_string()),
+                TerminalCommand::Bell,
+                TerminalCommand::Cursor(Some((2, 3))),
+            ]
+        );
+        assert!(ctx.take_terminal_commands().is_empty());
+    }
+
+    #[test]
+    fn test_unclaimed_popups_are_dropped_after_an_update() {
+        let mut ctx = Context::new(GameConfig::new());
+        ctx.popup(1.0, 2.0, "+1", Style::new());
+        ctx.finish_update();
+        ctx.popup(3.0, 4.0, "+2", Style::new());
+        let popups = ctx.take_popups();
+        assert_eq!(popups.len(), 2);
+
+        // Nobody takes them for a whole update
+        ctx.popup(1.0, 2.0, "+3", Style::new());
+        ctx.finish_update();
+        ctx.popup(1.0, 2.0, "+4", Style::new());
+        ctx.finish_update();
+        let popups = ctx.take_popups();
+        assert_eq!(popups.len(), 1);
+        assert_eq!(popups[0].text, "+4");
+    }
+
+    #[test]
+    fn test_unclaimed_scene_changes_are_dropped_after_an_update() {
+        let mut ctx = Context::new(GameConfig::new());
+        ctx.pop_scene();
+        ctx.finish_update();
+        assert_eq!(ctx.take_scene_commands().len(), 1);
+
+        ctx.pop_scene();
+        ctx.finish_update();
+        ctx.pop_scene();
+        ctx.finish_update();
+        assert_eq!(ctx.take_scene_commands().len(), 1);
+        ctx.finish_update();
+        assert!(ctx.take_scene_commands().is_empty());
+    }
+}
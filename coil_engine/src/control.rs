@@ -0,0 +1,423 @@
+//! Input from external controllers over OSC or MIDI.
+//!
+//! [`ControlInput`] receives OSC messages on a UDP port or reads raw MIDI
+//! from a device such as `/dev/snd/midiC1D0`, and maps them to the actions
+//! of an [`ActionMap`]:
+//!
+//! * OSC messages to `/<action>` are bound to every action automatically;
+//!   other addresses, MIDI notes and MIDI controllers with
+//!   [`ControlInput::with_osc`], [`ControlInput::with_note`] and
+//!   [`ControlInput::with_controller`]
+//! * each control has a value from 0 to 1: the first numeric argument of
+//!   an OSC message (1 without one), a note's velocity (0 once released)
+//!   or a controller's position
+//! * when an action's value rises to one half or more, its first key is
+//!   pressed through [`Context::send_event`], so buttons and pads act like
+//!   keys; faders and knobs are read with [`ControlInput::value`]
+//!
+//! Messages are read on a background thread and handled at the next update.
+use crate::actions::ActionMap;
+use crate::context::Context;
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::nodes::Node;
+use crate::replay;
+use crossterm::event::Event;
+use log::warn;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::net::UdpSocket;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+/// Values at or above this count as pressed
+const PRESSED: f32 = 0.5;
+
+/// A message from a controller.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlMessage {
+    /// An OSC message with its numeric arguments; others are skipped.
+    Osc { address: String, args: Vec<f32> },
+    /// A MIDI note was pressed, or released with a velocity of 0.
+    Note { channel: u8, note: u8, velocity: u8 },
+    /// A MIDI controller, such as a fader or knob, moved.
+    Controller {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+}
+
+/// A control that can be bound to an action.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Control {
+    Osc(String),
+    /// A MIDI note on any channel
+    Note(u8),
+    /// A MIDI controller on any channel
+    Controller(u8),
+}
+
+impl ControlMessage {
+    /// Returns the control the message is about and its value from 0 to 1.
+    fn control(&self) -> (Control, f32) {
+        match self {
+            ControlMessage::Osc { address, args } => (
+                Control::Osc(address.clone()),
+                args.first().copied().unwrap_or(1.0),
+            ),
+            ControlMessage::Note { note, velocity, .. } => {
+                (Control::Note(*note), *velocity as f32 / 127.0)
+            }
+            ControlMessage::Controller {
+                controller, value, ..
+            } => (Control::Controller(*controller), *value as f32 / 127.0),
+        }
+    }
+}
+
+/// A node turning controller messages into actions.
+pub struct ControlInput {
+    messages: Receiver<ControlMessage>,
+    actions: ActionMap,
+    bindings: HashMap<Control, String>,
+    /// Latest value of each action
+    values: HashMap<String, f32>,
+}
+
+impl ControlInput {
+    /// Receives OSC messages on a UDP address such as `0.0.0.0:9000`.
+    pub fn listen_osc(address: &str) -> Result<Self, EngineError> {
+        let socket = UdpSocket::bind(address)?;
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            let mut packet = [0; 65_536];
+            loop {
+                let length = match socket.recv(&mut packet) {
+                    Ok(length) => length,
+                    Err(e) => {
+                        warn!("OSC socket failed: {}", e);
+                        return;
+                    }
+                };
+                if !send_osc(&packet[..length], &sender) {
+                    return;
+                }
+            }
+        });
+        Ok(Self::from_receiver(messages))
+    }
+
+    /// Reads raw MIDI from a device or pipe, e.g. `/dev/snd/midiC1D0`.
+    pub fn open_midi(path: impl AsRef<Path>) -> Result<Self, EngineError> {
+        let mut device = File::open(path)?;
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            let mut parser = MidiParser::default();
+            let mut bytes = [0; 256];
+            loop {
+                let length = match device.read(&mut bytes) {
+                    Ok(0) => return,
+                    Ok(length) => length,
+                    Err(e) => {
+                        warn!("MIDI device failed: {}", e);
+                        return;
+                    }
+                };
+                for &byte in &bytes[..length] {
+                    if let Some(message) = parser.feed(byte)
+                        && sender.send(message).is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Self::from_receiver(messages))
+    }
+
+    /// Creates an input reading messages from `messages`, e.g. from another
+    /// MIDI backend.
+    pub fn from_receiver(messages: Receiver<ControlMessage>) -> Self {
+        Self {
+            messages,
+            actions: ActionMap::new(),
+            bindings: HashMap::new(),
+            values: HashMap::new(),
+        }
+    }
+
+    /// Uses the keys of `actions`, binding each action to the OSC address `/<action>`.
+    pub fn with_actions(mut self, actions: &ActionMap) -> Self {
+        for action in actions.actions() {
+            self.bindings
+                .insert(Control::Osc(format!("/{}", action)), action.to_string());
+        }
+        self.actions = actions.clone();
+        self
+    }
+
+    /// Binds an OSC address to `action`.
+    pub fn with_osc(self, address: impl Into<String>, action: &str) -> Self {
+        self.with_control(Control::Osc(address.into()), action)
+    }
+
+    /// Binds a MIDI note to `action`.
+    pub fn with_note(self, note: u8, action: &str) -> Self {
+        self.with_control(Control::Note(note), action)
+    }
+
+    /// Binds a MIDI controller to `action`.
+    pub fn with_controller(self, controller: u8, action: &str) -> Self {
+        self.with_control(Control::Controller(controller), action)
+    }
+
+    fn with_control(mut self, control: Control, action: &str) -> Self {
+        self.bindings.insert(control, action.to_string());
+        self
+    }
+
+    /// Returns the latest value from 0 to 1 of the controls bound to
+    /// `action`, or `None` before any arrived.
+    pub fn value(&self, action: &str) -> Option<f32> {
+        self.values.get(action).copied()
+    }
+}
+
+impl Node for ControlInput {
+    fn update(&mut self, ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+        while let Ok(message) = self.messages.try_recv() {
+            let (control, value) = message.control();
+            let Some(action) = self.bindings.get(&control) else {
+                continue;
+            };
+            let value = value.clamp(0.0, 1.0);
+            let previous = self.values.insert(action.clone(), value).unwrap_or(0.0);
+            if value >= PRESSED
+                && previous < PRESSED
+                && let Some(&key) = self.actions.bindings(action).first()
+            {
+                ctx.send_event(replay::key_event(key));
+            }
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+        Ok(false)
+    }
+
+    fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        Ok(())
+    }
+}
+
+/// Sends the messages of an OSC packet, returning `false` once nobody listens.
+fn send_osc(packet: &[u8], sender: &Sender<ControlMessage>) -> bool {
+    let mut messages = Vec::new();
+    if parse_osc(packet, &mut messages).is_none() {
+        warn!("Skipping a malformed OSC packet");
+    }
+    messages
+        .into_iter()
+        .all(|message| sender.send(message).is_ok())
+}
+
+/// Parses an OSC message or bundle into `messages`.
+fn parse_osc(packet: &[u8], messages: &mut Vec<ControlMessage>) -> Option<()> {
+    let (address, mut rest) = osc_string(packet)?;
+    if address == "#bundle" {
+        // Skip the time tag; elements are played as they arrive
+        rest = rest.get(8..)?;
+        while !rest.is_empty() {
+            let size = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+            parse_osc(rest.get(4..4 + size)?, messages)?;
+            rest = &rest[4 + size..];
+        }
+        return Some(());
+    }
+    let (tags, mut rest) = osc_string(rest).unwrap_or((",", &[]));
+    let word = |rest: &[u8]| -> Option<[u8; 4]> { rest.get(..4)?.try_into().ok() };
+    let wide = |rest: &[u8]| -> Option<[u8; 8]> { rest.get(..8)?.try_into().ok() };
+    let mut args = Vec::new();
+    for tag in tags.strip_prefix(',')?.chars() {
+        let (value, size) = match tag {
+            'i' => (Some(i32::from_be_bytes(word(rest)?) as f32), 4),
+            'f' => (Some(f32::from_be_bytes(word(rest)?)), 4),
+            'h' => (Some(i64::from_be_bytes(wide(rest)?) as f32), 8),
+            'd' => (Some(f64::from_be_bytes(wide(rest)?) as f32), 8),
+            'T' | 'I' => (Some(1.0), 0),
+            'F' | 'N' => (Some(0.0), 0),
+            't' => (None, 8),
+            's' | 'S' => (None, rest.len() - osc_string(rest)?.1.len()),
+            'b' => {
+                let size = u32::from_be_bytes(word(rest)?) as usize;
+                (None, (4 + size).next_multiple_of(4))
+            }
+            _ => return None,
+        };
+        args.extend(value);
+        rest = rest.get(size..)?;
+    }
+    messages.push(ControlMessage::Osc {
+        address: address.to_string(),
+        args,
+    });
+    Some(())
+}
+
+/// Reads a null-terminated string padded to four bytes.
+fn osc_string(bytes: &[u8]) -> Option<(&str, &[u8])> {
+    let end = bytes.iter().position(|&b| b == 0)?;
+    let text = std::str::from_utf8(&bytes[..end]).ok()?;
+    Some((text, bytes.get((end + 1).next_multiple_of(4)..)?))
+}
+
+/// Reassembles MIDI messages from a byte stream, including running status.
+#[derive(Debug, Default)]
+struct MidiParser {
+    status: Option<u8>,
+    data: Vec<u8>,
+}
+
+impl MidiParser {
+    fn feed(&mut self, byte: u8) -> Option<ControlMessage> {
+        match byte {
+            // Real-time messages may come between any two bytes
+            0xf8.. => return None,
+            0x80..=0xef => {
+                self.status = Some(byte);
+                self.data.clear();
+                return None;
+            }
+            // System messages cancel running status
+            0xf0..=0xf7 => {
+                self.status = None;
+                return None;
+            }
+            _ => {}
+        }
+        let status = self.status?;
+        self.data.push(byte);
+        let needed = match status & 0xf0 {
+            0xc0 | 0xd0 => 1,
+            _ => 2,
+        };
+        if self.data.len() < needed {
+            return None;
+        }
+        let data = std::mem::take(&mut self.data);
+        let channel = status & 0x0f;
+        match status & 0xf0 {
+            0x80 => Some(ControlMessage::Note {
+                channel,
+                note: data[0],
+                velocity: 0,
+            }),
+            0x90 => Some(ControlMessage::Note {
+                channel,
+                note: data[0],
+                velocity: data[1],
+            }),
+            0xb0 => Some(ControlMessage::Controller {
+                channel,
+                controller: data[0],
+                value: data[1],
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    #[test]
+    fn test_parse_osc_bundle() {
+        let message = b"/fader\0\0,fi\0\x3f\x00\x00\x00\x00\x00\x00\x02";
+        let mut bundle = b"#bundle\0\0\0\0\0\0\0\0\x01".to_vec();
+        bundle.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        bundle.extend_from_slice(message);
+        let mut messages = Vec::new();
+        parse_osc(&bundle, &mut messages).unwrap();
+        assert_eq!(
+            messages,
+            [ControlMessage::Osc {
+                address: "/fader".to_string(),
+                args: vec![0.5, 2.0]
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_midi_running_status() {
+        let mut parser = MidiParser::default();
+        let messages: Vec<_> = [0x91, 60, 100, 0xf8, 62, 0, 0xb0, 7, 127]
+            .into_iter()
+            .filter_map(|byte| parser.feed(byte))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                ControlMessage::Note {
+                    channel: 1,
+                    note: 60,
+                    velocity: 100
+                },
+                ControlMessage::Note {
+                    channel: 1,
+                    note: 62,
+                    velocity: 0
+                },
+                ControlMessage::Controller {
+                    channel: 0,
+                    controller: 7,
+                    value: 127
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_controls_press_action_keys() {
+        let (sender, receiver) = mpsc::channel();
+        let actions = ActionMap::new().with_binding("jump", KeyCode::Char(' '));
+        let mut input = ControlInput::from_receiver(receiver)
+            .with_actions(&actions)
+            .with_note(60, "jump")
+            .with_controller(7, "volume");
+        let note = |velocity| ControlMessage::Note {
+            channel: 0,
+            note: 60,
+            velocity,
+        };
+        // Held notes press once; the OSC message presses again after the release
+        for message in [note(127), note(100), note(0)] {
+            sender.send(message).unwrap();
+        }
+        sender
+            .send(ControlMessage::Osc {
+                address: "/jump".to_string(),
+                args: Vec::new(),
+            })
+            .unwrap();
+        sender
+            .send(ControlMessage::Controller {
+                channel: 3,
+                controller: 7,
+                value: 127,
+            })
+            .unwrap();
+        let mut ctx = Context::new(GameConfig::new());
+        input.update(&mut ctx, 0.1).unwrap();
+        let space = Event::Key(KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE));
+        assert_eq!(ctx.take_sent_events(), [space.clone(), space]);
+        assert_eq!(input.value("volume"), Some(1.0));
+        assert_eq!(input.value("jump"), Some(1.0));
+    }
+}
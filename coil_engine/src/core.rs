@@ -1,8 +1,17 @@
-use crate::config::{Config, GameConfig};
+use crate::accessibility::ColorBlindness;
+use crate::actions::KeyBinding;
+use crate::autosave::SaveFormat;
+use crate::color::ColorDepth;
+use crate::config::{Config, ErrorPolicy, GameConfig, Profile, ScreenSize};
 use crate::errors::EngineError;
 use crate::event_loop::EventLoop;
+use crate::glyphs::GlyphSupport;
+use crate::input::InputStrategy;
 use crate::nodes::Node;
+use crate::spectate::SpectateTarget;
+use crate::terminal::{ConsoleMode, OutputMode};
 use std::process;
+use std::time::Duration;
 
 pub struct Game<N> {
     pub node: N,
@@ -21,19 +30,278 @@ impl<N: Node> Game<N> {
         Self { node, config }
     }
 
+    /// Starts building a game with typed settings, validated by [`GameBuilder::build`].
+    pub fn builder() -> GameBuilder<N> {
+        GameBuilder::new()
+    }
+
     pub fn add_config(mut self, config: Config) -> Self {
         self.config = self.config.add_config(config);
         self
     }
 
+    /// Runs the game until it exits, ending the process with the code
+    /// passed to [`Context::request_exit`](crate::Context::request_exit) if it is not zero.
     pub fn start(&mut self) {
-        if let Err(e) = (|| -> Result<(), EngineError> {
-            let mut event_loop = EventLoop::new(&self.config)?;
-            event_loop.run::<N>(&mut self.node)?;
-            Ok(())
+        match (|| -> Result<i32, EngineError> {
+            let mut event_loop = EventLoop::new(self.config.clone())?;
+            event_loop.run(&mut self.node)
         })() {
-            eprintln!("Error running game: {}", e);
-            process::exit(1);
+            Ok(0) => {}
+            // The event loop has been dropped, so the terminal is already restored
+            Ok(code) => process::exit(code),
+            Err(e) => {
+                eprintln!("Error running game: {}", e);
+                process::exit(1);
+            }
         }
     }
 }
+
+/// Builder for a [`Game`], created with [`Game::builder`].
+///
+/// Settings are applied in call order on top of the defaults, or on top of
+/// a base set with [`GameBuilder::config`].
+pub struct GameBuilder<N> {
+    node: Option<N>,
+    config: GameConfig,
+}
+
+impl<N: Node> GameBuilder<N> {
+    pub fn new() -> Self {
+        Self {
+            node: None,
+            config: GameConfig::new(),
+        }
+    }
+
+    /// Sets the root node of the game tree.
+    pub fn node(mut self, node: N) -> Self {
+        self.node = Some(node);
+        self
+    }
+
+    /// Replaces all settings made so far, e.g. with [`GameConfig::with_overrides`].
+    pub fn config(mut self, config: GameConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn profile(self, profile: Profile) -> Self {
+        self.set(Config::Profile(profile))
+    }
+
+    pub fn target_fps(self, fps: u32) -> Self {
+        self.set(Config::TargetFps(fps))
+    }
+
+    pub fn input(self, strategy: InputStrategy) -> Self {
+        self.set(Config::InputStrategy(strategy))
+    }
+
+    pub fn max_frame_time(self, max_time: Duration) -> Self {
+        self.set(Config::MaxFrameTime(max_time))
+    }
+
+    /// Sets the time each frame may spend working before the engine
+    /// degrades to save CPU; see [`crate::stats`].
+    pub fn cpu_budget(self, budget: Duration) -> Self {
+        self.set(Config::CpuBudget(Some(budget)))
+    }
+
+    /// Lets up to `skips` frames in a row go undrawn while the game catches
+    /// up after falling behind.
+    pub fn max_frame_skip(self, skips: u32) -> Self {
+        self.set(Config::MaxFrameSkip(skips))
+    }
+
+    pub fn debug_mode(self, debug: bool) -> Self {
+        self.set(Config::DebugMode(debug))
+    }
+
+    /// Flashes the cells that changed since the previous frame, to find
+    /// what is redrawn needlessly.
+    pub fn show_redraws(self, enabled: bool) -> Self {
+        self.set(Config::ShowRedraws(enabled))
+    }
+
+    pub fn vsync(self, vsync: bool) -> Self {
+        self.set(Config::Vsync(vsync))
+    }
+
+    pub fn screen_size(self, size: ScreenSize) -> Self {
+        self.set(Config::ScreenSize(size))
+    }
+
+    pub fn min_screen_size(self, width: u16, height: u16) -> Self {
+        self.set(Config::MinScreenSize((width, height)))
+    }
+
+    pub fn mouse_capture(self, capture: bool) -> Self {
+        self.set(Config::MouseCapture(capture))
+    }
+
+    /// Shows on-screen buttons for players without a handy keyboard.
+    pub fn touch_controls(self, enabled: bool) -> Self {
+        self.set(Config::TouchControls(enabled))
+    }
+
+    pub fn high_contrast(self, enabled: bool) -> Self {
+        self.set(Config::HighContrast(enabled))
+    }
+
+    pub fn reduced_motion(self, enabled: bool) -> Self {
+        self.set(Config::ReducedMotion(enabled))
+    }
+
+    /// Sets the file announcements for screen readers are appended to.
+    pub fn speech_output(self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.set(Config::SpeechOutput(Some(path.into())))
+    }
+
+    /// Simulates a color vision deficiency over the final screen.
+    pub fn color_blindness(self, deficiency: ColorBlindness) -> Self {
+        self.set(Config::ColorBlindness(Some(deficiency)))
+    }
+
+    /// Overrides whether Unicode glyphs are drawn or replaced with ASCII.
+    pub fn glyphs(self, support: GlyphSupport) -> Self {
+        self.set(Config::Glyphs(support))
+    }
+
+    /// Overrides the detection of legacy consoles without ANSI support.
+    pub fn console(self, mode: ConsoleMode) -> Self {
+        self.set(Config::Console(mode))
+    }
+
+    /// Overrides whether frames are drawn to the terminal or dumped as text.
+    pub fn output(self, mode: OutputMode) -> Self {
+        self.set(Config::Output(mode))
+    }
+
+    /// Lets spectators watch the game read-only through `target`.
+    pub fn spectate(self, target: SpectateTarget) -> Self {
+        self.set(Config::Spectate(Some(target)))
+    }
+
+    /// Autosaves the game to `path` every `interval`, on request and on exit.
+    pub fn autosave(self, path: impl Into<std::path::PathBuf>, interval: Duration) -> Self {
+        self.set(Config::Autosave(Some(path.into())))
+            .set(Config::AutosaveInterval(Some(interval)))
+    }
+
+    /// Overrides whether autosaves are written as text or into a world database.
+    pub fn autosave_format(self, format: SaveFormat) -> Self {
+        self.set(Config::AutosaveFormat(format))
+    }
+
+    /// Keeps the game's files in the platform's directories for `name`; see
+    /// [`Paths`](crate::paths::Paths).
+    pub fn app_name(self, name: impl Into<String>) -> Self {
+        self.set(Config::AppName(Some(name.into())))
+    }
+
+    /// Overrides the detected number of colors the terminal can show.
+    pub fn color_depth(self, depth: ColorDepth) -> Self {
+        self.set(Config::ColorDepth(depth))
+    }
+
+    pub fn error_policy(self, policy: ErrorPolicy) -> Self {
+        self.set(Config::ErrorPolicy(policy))
+    }
+
+    /// Sets the keys that exit the game before the node tree sees them.
+    pub fn quit_keys<I>(self, keys: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<KeyBinding>,
+    {
+        self.set(Config::QuitKeys(keys.into_iter().map(Into::into).collect()))
+    }
+
+    /// Sets the key that pauses and resumes the game.
+    pub fn pause_key(self, key: impl Into<KeyBinding>) -> Self {
+        self.set(Config::PauseKey(Some(key.into())))
+    }
+
+    /// Sets the time without input after which [`EngineEvent::Idle`](crate::context::EngineEvent::Idle) is sent.
+    pub fn idle_timeout(self, timeout: Duration) -> Self {
+        self.set(Config::IdleTimeout(Some(timeout)))
+    }
+
+    fn set(mut self, config: Config) -> Self {
+        self.config = self.config.add_config(config);
+        self
+    }
+
+    /// Validates the settings and creates the game.
+    ///
+    /// # Returns
+    /// * `Err(EngineError::Config)` if no root node was given or a setting is invalid
+    pub fn build(self) -> Result<Game<N>, EngineError> {
+        let node = self.node.ok_or_else(|| {
+            EngineError::Config("Game has no root node; call `.node(...)` on the builder".into())
+        })?;
+        self.config.validate()?;
+        Ok(Game::with_config(node, self.config))
+    }
+}
+
+impl<N: Node> Default for GameBuilder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Context;
+    use crate::frame::Frame;
+    use crossterm::event::{Event, KeyCode, KeyModifiers};
+
+    struct Idle;
+
+    impl Node for Idle {
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+            Ok(())
+        }
+
+        fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+            Ok(false)
+        }
+
+        fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_builder_applies_settings() {
+        let game = Game::builder()
+            .node(Idle)
+            .target_fps(30)
+            .input(InputStrategy::FrameBudgeted)
+            .quit_keys([
+                KeyBinding::from(KeyCode::Esc),
+                KeyBinding::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(game.config.target_fps, 30);
+        assert!(matches!(
+            game.config.input_strategy,
+            InputStrategy::FrameBudgeted
+        ));
+        assert_eq!(game.config.quit_keys.len(), 2);
+    }
+
+    #[test]
+    fn test_builder_validates() {
+        let missing_node = GameBuilder::<Idle>::new().build();
+        assert!(matches!(missing_node, Err(EngineError::Config(_))));
+
+        let zero_fps = Game::builder().node(Idle).target_fps(0).build();
+        assert!(matches!(zero_fps, Err(EngineError::Config(_))));
+    }
+}
@@ -0,0 +1,153 @@
+//! A day and night cycle tinting the world.
+//!
+//! A [`DayCycle`] advances a time of day in the fixed update and turns it
+//! into an ambient light color by interpolating a curve of keyframes, from a
+//! dark blue night through a warm dawn to white noon by default. Games pass
+//! the color to [`Context::set_world_tint`](crate::context::Context::set_world_tint),
+//! which [`Layers`](crate::nodes::Layers) applies to every world cell while
+//! the HUD keeps its colors, or to [`Lighting::set_ambient`](crate::lighting::Lighting::set_ambient)
+//! so torches still shine at night.
+//!
+//! ```
+//! use coil_engine::day_cycle::DayCycle;
+//! use std::time::Duration;
+//!
+//! let mut day = DayCycle::new(Duration::from_secs(600)).with_time(0.5);
+//! day.update(1.0);
+//! assert!(!day.is_night(1.0));
+//! ```
+use crate::color::Rgb;
+use std::time::Duration;
+
+/// The time of day and the ambient light it gives.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DayCycle {
+    /// Real seconds one day takes at speed 1.0
+    day_length: f32,
+    /// Time of day before and after the last update, from 0.0 (midnight) to 1.0
+    previous: f32,
+    time: f32,
+    speed: f32,
+    /// Keyframes of (time of day, light), sorted by time
+    curve: Vec<(f32, Rgb)>,
+}
+
+impl DayCycle {
+    /// Creates a cycle starting at dawn, where a full day takes `day_length`.
+    pub fn new(day_length: Duration) -> Self {
+        Self {
+            day_length: day_length.as_secs_f32().max(f32::EPSILON),
+            previous: 0.25,
+            time: 0.25,
+            speed: 1.0,
+            curve: vec![
+                (0.0, Rgb::new(50, 60, 110)),
+                (0.2, Rgb::new(60, 70, 120)),
+                (0.27, Rgb::new(230, 170, 140)),
+                (0.35, Rgb::new(255, 255, 255)),
+                (0.65, Rgb::new(255, 255, 255)),
+                (0.74, Rgb::new(240, 150, 100)),
+                (0.82, Rgb::new(60, 70, 120)),
+            ],
+        }
+    }
+
+    /// Starts at a time of day, from 0.0 (midnight) through 0.5 (noon) to 1.0.
+    pub fn with_time(mut self, time: f32) -> Self {
+        self.time = time.rem_euclid(1.0);
+        self.previous = self.time;
+        self
+    }
+
+    /// Replaces the light curve with keyframes of (time of day, light); the
+    /// light is interpolated between them and wraps from the last to the first.
+    pub fn with_curve(mut self, mut curve: Vec<(f32, Rgb)>) -> Self {
+        for (time, _) in &mut curve {
+            *time = time.rem_euclid(1.0);
+        }
+        curve.sort_by(|a, b| a.0.total_cmp(&b.0));
+        if !curve.is_empty() {
+            self.curve = curve;
+        }
+        self
+    }
+
+    /// Sets how fast time passes relative to `day_length`; 0.0 stops it.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.max(0.0);
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Advances the time of day by `dt` seconds; call it from `update`.
+    pub fn update(&mut self, dt: f32) {
+        self.previous = self.time;
+        self.time += dt * self.speed / self.day_length;
+        // Keep both samples within one day, preserving their distance
+        let wraps = self.previous.floor();
+        self.previous -= wraps;
+        self.time -= wraps;
+    }
+
+    /// Returns the time of day at the frame's interpolation alpha, from 0.0 to 1.0.
+    pub fn time_of_day(&self, alpha: f32) -> f32 {
+        let time = self.previous + (self.time - self.previous) * alpha.clamp(0.0, 1.0);
+        time.rem_euclid(1.0)
+    }
+
+    /// Returns the hour of the day, from 0.0 to 24.0.
+    pub fn hour(&self, alpha: f32) -> f32 {
+        self.time_of_day(alpha) * 24.0
+    }
+
+    /// Returns whether the sun is down, between 19:00 and 6:00.
+    pub fn is_night(&self, alpha: f32) -> bool {
+        let hour = self.hour(alpha);
+        !(6.0..19.0).contains(&hour)
+    }
+
+    /// Returns the ambient light at the frame's interpolation alpha.
+    pub fn tint(&self, alpha: f32) -> Rgb {
+        let time = self.time_of_day(alpha);
+        let after = self.curve.iter().position(|(at, _)| *at > time);
+        let (from, to) = match after {
+            Some(0) | None => (self.curve[self.curve.len() - 1], self.curve[0]),
+            Some(index) => (self.curve[index - 1], self.curve[index]),
+        };
+        // Measure forwards from the earlier keyframe, across midnight if needed
+        let span = (to.0 - from.0).rem_euclid(1.0);
+        if span <= f32::EPSILON {
+            return from.1;
+        }
+        from.1.mix(to.1, (time - from.0).rem_euclid(1.0) / span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tint_follows_curve_across_midnight() {
+        let black = Rgb::new(0, 0, 0);
+        let white = Rgb::new(255, 255, 255);
+        let mut day = DayCycle::new(Duration::from_secs(100))
+            .with_curve(vec![(0.75, black), (0.25, white)])
+            .with_time(0.25);
+        assert_eq!(day.tint(0.0), white);
+        day.update(25.0);
+        assert_eq!(day.time_of_day(1.0), 0.5);
+        assert_eq!(day.tint(0.5), Rgb::new(191, 191, 191));
+        day.update(50.0);
+        // Half way from 0.75 to 0.25 the next morning is midnight
+        assert_eq!(day.time_of_day(1.0), 0.0);
+        assert_eq!(day.tint(1.0), Rgb::new(128, 128, 128));
+        assert!(day.is_night(1.0));
+
+        day.set_speed(0.0);
+        day.update(10.0);
+        assert_eq!(day.time_of_day(1.0), 0.0);
+    }
+}
@@ -0,0 +1,194 @@
+//! An embedded database for worlds too large to save in one piece.
+//!
+//! Simulation-heavy games change a little of a large world at a time.
+//! Rather than writing the whole state into one save file, a [`WorldDb`]
+//! keeps records of text in named tables, such as one record per entity or
+//! per chunk, and each change writes only its own record:
+//!
+//! ```no_run
+//! use coil_engine::db::WorldDb;
+//!
+//! let db = WorldDb::open("world.db").unwrap();
+//! db.insert("entities", "orc-17", "x = 4\ny = 9").unwrap();
+//! assert_eq!(db.get("entities", "orc-17").unwrap().as_deref(), Some("x = 4\ny = 9"));
+//! ```
+//!
+//! [`ChunkManager::with_database`](crate::chunks::ChunkManager::with_database)
+//! stores modified chunks in a database, and the engine's autosaves go to
+//! one with `autosave_format = db`. A database file can only be open once at
+//! a time, so clone the handle to share it, and keep autosaves in a file of
+//! their own.
+//!
+//! Records are durable once [`WorldDb::flush`] returns, which autosaves and
+//! chunk unloads do, and when the last handle to the database is dropped.
+//! [`std::process::exit`] skips that, so flush before ending the process
+//! with an exit code.
+use crate::autosave::{SaveData, Saveable};
+use crate::errors::EngineError;
+use std::fs::{File, TryLockError};
+use std::io;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Table autosaves are written to, one record per section
+const SAVE_TABLE: &str = "save";
+
+/// How long opening waits for a database that is being closed
+const LOCK_TIMEOUT: Duration = Duration::from_secs(1);
+
+fn db_error(error: sled::Error) -> EngineError {
+    EngineError::Io(io::Error::from(error))
+}
+
+/// A handle to a world database; clones share the same database.
+///
+/// Dropping a handle flushes the database.
+#[derive(Clone)]
+pub struct WorldDb {
+    db: sled::Db,
+}
+
+impl WorldDb {
+    /// Opens the database at `path`, creating it if needed.
+    ///
+    /// Fails if the database is already open, in this process or another.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, EngineError> {
+        wait_for_lock(path.as_ref());
+        let db = sled::open(path).map_err(db_error)?;
+        Ok(Self { db })
+    }
+
+    /// Sets the record `key` of `table`.
+    pub fn insert(&self, table: &str, key: &str, text: impl AsRef<str>) -> Result<(), EngineError> {
+        self.table(table)?
+            .insert(key, text.as_ref().as_bytes())
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    /// Saves `state` as the record `key` of `table`.
+    pub fn write(&self, table: &str, key: &str, state: &impl Saveable) -> Result<(), EngineError> {
+        self.insert(table, key, state.save()?)
+    }
+
+    /// Returns the record `key` of `table`.
+    pub fn get(&self, table: &str, key: &str) -> Result<Option<String>, EngineError> {
+        let value = self.table(table)?.get(key).map_err(db_error)?;
+        value.map(|value| text(&value)).transpose()
+    }
+
+    /// Removes the record `key` of `table`, returning whether it existed.
+    pub fn remove(&self, table: &str, key: &str) -> Result<bool, EngineError> {
+        Ok(self.table(table)?.remove(key).map_err(db_error)?.is_some())
+    }
+
+    /// Returns the keys of the records in `table`, in byte order.
+    pub fn keys(&self, table: &str) -> Result<Vec<String>, EngineError> {
+        self.table(table)?
+            .iter()
+            .keys()
+            .map(|key| text(&key.map_err(db_error)?))
+            .collect()
+    }
+
+    /// Waits until every change so far is written to disk.
+    pub fn flush(&self) -> Result<(), EngineError> {
+        self.db.flush().map_err(db_error)?;
+        Ok(())
+    }
+
+    /// Writes the sections of an autosave that changed since the last one,
+    /// removes those it no longer has and flushes.
+    pub fn write_save(&self, data: &SaveData) -> Result<(), EngineError> {
+        let table = self.table(SAVE_TABLE)?;
+        let mut batch = sled::Batch::default();
+        for (name, text) in data.sections() {
+            let stored = table.get(name).map_err(db_error)?;
+            if stored.as_deref() != Some(text.as_bytes()) {
+                batch.insert(name, text.as_bytes());
+            }
+        }
+        for name in self.keys(SAVE_TABLE)? {
+            if data.get(&name).is_none() {
+                batch.remove(name.as_bytes());
+            }
+        }
+        table.apply_batch(batch).map_err(db_error)?;
+        self.flush()
+    }
+
+    /// Reads the last autosave back.
+    pub fn load_save(&self) -> Result<SaveData, EngineError> {
+        let mut data = SaveData::new();
+        for name in self.keys(SAVE_TABLE)? {
+            if let Some(text) = self.get(SAVE_TABLE, &name)? {
+                data.insert(&name, text);
+            }
+        }
+        Ok(data)
+    }
+
+    pub(crate) fn table(&self, name: &str) -> Result<sled::Tree, EngineError> {
+        self.db.open_tree(name).map_err(db_error)
+    }
+}
+
+impl Drop for WorldDb {
+    fn drop(&mut self) {
+        if let Err(e) = self.db.flush() {
+            log::warn!("Could not flush the world database: {}", e);
+        }
+    }
+}
+
+/// Waits a little for the database at `path` to be closed.
+///
+/// sled releases the lock on its file from a background thread once the
+/// last handle is dropped, so reopening right away could fail.
+fn wait_for_lock(path: &Path) {
+    let Ok(file) = File::open(path.join("db")) else {
+        return;
+    };
+    let deadline = Instant::now() + LOCK_TIMEOUT;
+    while matches!(file.try_lock(), Err(TryLockError::WouldBlock)) && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Decodes a record, which is always written as UTF-8.
+fn text(bytes: &[u8]) -> Result<String, EngineError> {
+    String::from_utf8(bytes.to_vec()).map_err(EngineError::game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_and_saves() {
+        let path = std::env::temp_dir().join(format!("coil-world-{}.db", std::process::id()));
+        let db = WorldDb::open(&path).unwrap();
+        db.insert("entities", "orc", "hp = 3").unwrap();
+        db.insert("entities", "elf", "hp = 5").unwrap();
+        assert!(db.remove("entities", "orc").unwrap());
+        // Clones share the open database
+        let again = db.clone();
+        assert_eq!(again.keys("entities").unwrap(), ["elf"]);
+        assert_eq!(again.get("entities", "orc").unwrap(), None);
+
+        let mut save = SaveData::new();
+        save.insert("player", "x = 4\n");
+        save.insert("level", "####\n");
+        db.write_save(&save).unwrap();
+        let mut save = SaveData::new();
+        save.insert("player", "x = 5\n");
+        db.write_save(&save).unwrap();
+        assert_eq!(db.load_save().unwrap(), save);
+
+        // Dropping the last handle flushes and closes the database
+        drop((db, again));
+        assert_eq!(SaveData::load_database(&path).unwrap(), save);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+}
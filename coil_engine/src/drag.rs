@@ -0,0 +1,247 @@
+//! Drag-and-drop between nodes.
+//!
+//! A node starts a drag with [`Context::start_drag`](crate::context::Context::start_drag),
+//! usually when it receives an [`EngineEvent::Hit`](crate::context::EngineEvent::Hit)
+//! for a mouse press over one of its regions. While the button is held the
+//! engine draws the drag's ghost under the cursor and sends
+//! [`EngineEvent::Drag`](crate::context::EngineEvent::Drag) events as the cursor
+//! enters and leaves hit regions. Releasing the button drops the payload on
+//! the region under the cursor; releasing it elsewhere or pressing `Esc`
+//! cancels the drag.
+use crate::hit::{Hit, HitId};
+use crate::style::Style;
+use crossterm::event::{MouseEvent, MouseEventKind};
+use std::any::Any;
+use std::fmt;
+use std::rc::Rc;
+
+/// The value carried by a drag, shared by every event about it.
+#[derive(Clone)]
+pub struct DragPayload(Rc<dyn Any>);
+
+impl DragPayload {
+    pub fn new<T: Any>(value: T) -> Self {
+        Self(Rc::new(value))
+    }
+
+    /// Returns the value if it is a `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+
+    pub fn is<T: Any>(&self) -> bool {
+        self.0.is::<T>()
+    }
+}
+
+impl fmt::Debug for DragPayload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DragPayload(..)")
+    }
+}
+
+/// A drag in progress.
+#[derive(Debug, Clone)]
+pub struct Drag {
+    /// The region the drag started from
+    pub source: HitId,
+    pub payload: DragPayload,
+    /// Text drawn under the cursor while dragging
+    pub ghost: String,
+    pub style: Style,
+}
+
+impl Drag {
+    /// Creates a drag from `source` carrying `payload`, with a `*` ghost.
+    pub fn new<T: Any>(source: HitId, payload: T) -> Self {
+        Self {
+            source,
+            payload: DragPayload::new(payload),
+            ghost: "*".to_string(),
+            style: Style::new(),
+        }
+    }
+
+    /// Sets the text drawn under the cursor.
+    pub fn with_ghost(mut self, ghost: impl Into<String>, style: Style) -> Self {
+        self.ghost = ghost.into();
+        self.style = style;
+        self
+    }
+}
+
+/// Progress of a drag, sent to the node tree as [`EngineEvent::Drag`](crate::context::EngineEvent::Drag).
+#[derive(Debug, Clone)]
+pub enum DragEvent {
+    /// The cursor moved over `target` while dragging.
+    Enter { target: HitId, drag: Drag },
+    /// The cursor left `target` while dragging.
+    Leave { target: HitId, drag: Drag },
+    /// The drag was released over `target`, at (x,y) relative to it.
+    Drop {
+        target: HitId,
+        x: u16,
+        y: u16,
+        drag: Drag,
+    },
+    /// The drag ended without a drop.
+    Cancel { drag: Drag },
+}
+
+/// Tracks the drag in progress and the events it produces.
+#[derive(Debug, Default)]
+pub(crate) struct DragState {
+    drag: Option<Drag>,
+    target: Option<HitId>,
+    /// Cursor position in renderer coordinates, once known
+    cursor: Option<(u16, u16)>,
+    events: Vec<DragEvent>,
+}
+
+impl DragState {
+    /// Starts a drag, cancelling any drag already in progress.
+    pub fn start(&mut self, drag: Drag) {
+        self.cancel();
+        self.drag = Some(drag);
+    }
+
+    pub fn drag(&self) -> Option<&Drag> {
+        self.drag.as_ref()
+    }
+
+    /// Returns the drag and where to draw its ghost.
+    pub fn ghost(&self) -> Option<(&Drag, (u16, u16))> {
+        Some((self.drag.as_ref()?, self.cursor?))
+    }
+
+    pub fn cancel(&mut self) {
+        self.target = None;
+        self.cursor = None;
+        if let Some(drag) = self.drag.take() {
+            self.events.push(DragEvent::Cancel { drag });
+        }
+    }
+
+    /// Follows a mouse event at `position` in renderer coordinates, with the
+    /// region under it.
+    pub fn mouse(&mut self, event: &MouseEvent, position: (u16, u16), hit: Option<&Hit>) {
+        let Some(drag) = &self.drag else {
+            return;
+        };
+        self.cursor = Some(position);
+        let target = hit.map(|hit| hit.id);
+        if let MouseEventKind::Up(_) = event.kind {
+            let drag = self.drag.take().expect("drag checked above");
+            self.target = None;
+            self.cursor = None;
+            match hit {
+                Some(hit) => self.events.push(DragEvent::Drop {
+                    target: hit.id,
+                    x: hit.x,
+                    y: hit.y,
+                    drag,
+                }),
+                None => self.events.push(DragEvent::Cancel { drag }),
+            }
+            return;
+        }
+        if target == self.target {
+            return;
+        }
+        if let Some(previous) = self.target {
+            self.events.push(DragEvent::Leave {
+                target: previous,
+                drag: drag.clone(),
+            });
+        }
+        if let Some(target) = target {
+            self.events.push(DragEvent::Enter {
+                target,
+                drag: drag.clone(),
+            });
+        }
+        self.target = target;
+    }
+
+    pub fn take_events(&mut self) -> Vec<DragEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyModifiers, MouseButton};
+
+    fn mouse(kind: MouseEventKind) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column: 0,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    fn hit(id: HitId, x: u16, y: u16) -> Hit {
+        Hit {
+            id,
+            x,
+            y,
+            event: mouse(MouseEventKind::Moved),
+        }
+    }
+
+    fn names(events: Vec<DragEvent>) -> Vec<String> {
+        events
+            .into_iter()
+            .map(|event| match event {
+                DragEvent::Enter { target, .. } => format!("enter {:?}", target),
+                DragEvent::Leave { target, .. } => format!("leave {:?}", target),
+                DragEvent::Drop { target, x, y, drag } => format!(
+                    "drop {:?} {} {} {}",
+                    target,
+                    x,
+                    y,
+                    drag.payload.downcast_ref::<&str>().unwrap()
+                ),
+                DragEvent::Cancel { .. } => "cancel".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_drag_enters_leaves_and_drops() {
+        let (bag, chest) = (HitId::new(), HitId::new());
+        let drag = MouseEventKind::Drag(MouseButton::Left);
+        let mut state = DragState::default();
+        state.mouse(&mouse(drag), (1, 1), Some(&hit(bag, 1, 1)));
+        assert!(state.take_events().is_empty());
+
+        state.start(Drag::new(bag, "sword"));
+        state.mouse(&mouse(drag), (1, 1), Some(&hit(bag, 1, 1)));
+        state.mouse(&mouse(drag), (2, 1), Some(&hit(bag, 2, 1)));
+        state.mouse(&mouse(drag), (5, 1), None);
+        state.mouse(&mouse(drag), (9, 2), Some(&hit(chest, 0, 1)));
+        assert_eq!(state.ghost().map(|(_, cursor)| cursor), Some((9, 2)));
+        state.mouse(
+            &mouse(MouseEventKind::Up(MouseButton::Left)),
+            (10, 2),
+            Some(&hit(chest, 1, 1)),
+        );
+        assert_eq!(
+            names(state.take_events()),
+            [
+                format!("enter {:?}", bag),
+                format!("leave {:?}", bag),
+                format!("enter {:?}", chest),
+                format!("drop {:?} 1 1 sword", chest),
+            ]
+        );
+        assert!(state.ghost().is_none());
+
+        state.start(Drag::new(bag, "shield"));
+        state.start(Drag::new(chest, "potion"));
+        state.mouse(&mouse(MouseEventKind::Up(MouseButton::Left)), (5, 5), None);
+        assert_eq!(names(state.take_events()), ["cancel", "cancel"]);
+    }
+}
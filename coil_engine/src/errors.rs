@@ -15,13 +15,43 @@ pub enum EngineError {
     #[error("event loop error: {0}")]
     EventLoop(String),
 
-    /// Error rlated to rendering operations.
+    /// A draw that could not be carried out, such as one clipped at the
+    /// edge of the screen; failing to write to the terminal is an I/O error.
     #[error("rendering error: {0}")]
     Render(String),
 
     /// Configuration error, typically related to game settings.
     #[error("configuration error: {0}")]
     Config(String),
+
+    /// Error raised by game code, such as a missing asset or corrupt save,
+    /// which stops the game.
+    #[error("game error: {0}")]
+    Game(Box<dyn std::error::Error + Send + Sync>),
+
+    /// Error raised by game code that the game can carry on after, such as
+    /// a sound that failed to play; handled by the error policy.
+    #[error("game error: {0}")]
+    Recoverable(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl EngineError {
+    /// Wraps a game-defined error, e.g. `load(path).map_err(EngineError::game)?`.
+    pub fn game(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        EngineError::Game(error.into())
+    }
+
+    /// Wraps a game-defined error the game can carry on after, e.g.
+    /// `play(sound).map_err(EngineError::recoverable)?`.
+    pub fn recoverable(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        EngineError::Recoverable(error.into())
+    }
+
+    /// Returns whether the engine can keep running after this error,
+    /// subject to the configured [`ErrorPolicy`](crate::config::ErrorPolicy).
+    pub fn is_recoverable(&self) -> bool {
+        matches!(self, EngineError::Render(_) | EngineError::Recoverable(_))
+    }
 }
 
 #[cfg(test)]
@@ -33,8 +63,10 @@ mod tests {
     fn get_test_cases() -> Vec<EngineError> {
         vec![
             EngineError::Input("test input error".to_string()),
-            EngineError::Io(io::Error::new(io::ErrorKind::Other, "test io error")),
+            EngineError::Io(io::Error::other("test io error")),
             EngineError::EventLoop("test event loop error".to_string()),
+            EngineError::game(io::Error::other("missing asset")),
+            EngineError::recoverable("sound failed to play"),
         ]
     }
     fn get_expected_debug_message(error: &EngineError) -> String {
@@ -44,6 +76,7 @@ mod tests {
             EngineError::EventLoop(_) => "test event loop error".to_string(),
             EngineError::Config(_) => "configuration error".to_string(),
             EngineError::Render(_) => "rendering error".to_string(),
+            EngineError::Game(_) | EngineError::Recoverable(_) => "game error".to_string(),
         }
     }
 
@@ -70,11 +103,20 @@ mod tests {
         let error = EngineError::Input("test".to_string());
         let _source = error.source(); // Should not panic
 
-        let io_error = io::Error::new(io::ErrorKind::Other, "test");
+        let io_error = io::Error::other("test");
         let engine_error = EngineError::from(io_error);
         let _source = engine_error.source(); // Should not panic
     }
 
+    #[test]
+    fn test_is_recoverable() {
+        assert!(EngineError::Render("clipped".to_string()).is_recoverable());
+        assert!(!EngineError::game("save corrupt").is_recoverable());
+        assert!(EngineError::recoverable("sound failed to play").is_recoverable());
+        assert!(!EngineError::Input("no tty".to_string()).is_recoverable());
+        assert!(!EngineError::from(io::Error::other("broken pipe")).is_recoverable());
+    }
+
     #[test]
     fn test_different_input_error_messages() {
         let errors = vec![
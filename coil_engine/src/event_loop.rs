@@ -1,103 +1,615 @@
-use crate::config::GameConfig;
+use crate::accessibility;
+use crate::autosave::{self, AutosaveTimer, SaveData, SaveFormat};
+use crate::config::{Config, ErrorPolicy, GameConfig};
+use crate::context::{Context, EngineEvent};
+#[cfg(feature = "db")]
+use crate::db::WorldDb;
 use crate::errors::EngineError;
-use crate::input::InputHandler;
-use crate::nodes::Node;
-use crate::renderer::{BasicRenderer, Renderer};
-use log::{debug, warn};
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::glyphs::{self, GlyphSupport};
+use crate::hit::{Hit, HitRegions};
+use crate::input::{IdleDetector, InputHandler};
+use crate::nodes::{Node, UpdateSchedule};
+use crate::renderer::{BasicRenderer, Cell, Renderer};
+use crate::spectate::Spectators;
+use crate::speech::SpeechOutput;
+use crate::style::Style;
+use crate::terminal::{self as terminal_session, TerminalCommand, TerminalSession};
+use crossterm::event::{Event, KeyCode, MouseEvent};
+use crossterm::terminal;
+use log::{debug, info, warn};
+use std::path::Path;
+#[cfg(feature = "db")]
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
+/// Screen size used when frames are dumped and the game does not ask for one.
+const HEADLESS_SIZE: (u16, u16) = (80, 24);
+
 /// Main event loop that manages game timing and coordinates game state updates.
 ///
 /// The event loop uses a fixed timestep with lag compensation to ensure
 /// consistent game timing regardless of frame rate variations. It supports
 /// state machines and entity loops for complex game logic.
-pub struct EventLoop<'a> {
+pub struct EventLoop {
     input_handler: InputHandler,
+    /// Events read this frame, kept between frames to reuse the allocation
+    events: Vec<Event>,
     renderer: BasicRenderer,
-    config: &'a GameConfig,
+    ctx: Context,
+    /// Terminal size while it is too small to play in.
+    undersized: Option<(u16, u16)>,
+    idle: IdleDetector,
+    speech: SpeechOutput,
+    spectators: Spectators,
+    /// Regions being registered for the frame under construction
+    hits: HitRegions,
+    autosave: AutosaveTimer,
+    /// When the root node is next updated, for its update interval
+    schedule: UpdateSchedule,
+    /// Frames in a row not drawn, to catch up or stay within the CPU budget
+    skipped_renders: u32,
+    /// Database autosaves go to, kept open from the first one on
+    #[cfg(feature = "db")]
+    save_db: Option<(PathBuf, WorldDb)>,
+    // Dropped last so the terminal is restored after everything else is torn down
+    session: TerminalSession,
 }
 
-impl<'a> EventLoop<'a> {
+impl EventLoop {
     /// Creates a new event loop.
     ///
     /// # Returns
     /// * `Ok(EventLoop)` on success
     /// * `Err(EngineError)` if input handler initialization fails
-    pub fn new(config: &'a GameConfig) -> Result<Self, EngineError> {
+    pub fn new(config: GameConfig) -> Result<Self, EngineError> {
         debug!("Creating event loop");
         config.validate()?;
-        let (width, height) = config.screen_size;
-        Ok(Self {
-            input_handler: InputHandler::new()?,
-            renderer: BasicRenderer::new(width, height)?,
-            config,
-        })
+        let (session, input_handler, mut renderer) = if config.output.is_frame_dump() {
+            let (width, height) = match config.required_size() {
+                (0, _) | (_, 0) => HEADLESS_SIZE,
+                size => size,
+            };
+            debug!(
+                "Output is not a terminal, dumping {}x{} frames",
+                width, height
+            );
+            (
+                TerminalSession::headless(width, height),
+                InputHandler::disabled(),
+                BasicRenderer::frame_dump(width, height)?,
+            )
+        } else {
+            let (width, height) = terminal::size()?;
+            (
+                TerminalSession::new(config.mouse_capture)?,
+                InputHandler::new()?,
+                BasicRenderer::new(width, height)?,
+            )
+        };
+        let (width, height) = renderer.size();
+        renderer.set_filters(render_filters(&config));
+        renderer.set_show_redraws(config.show_redraws);
+        let speech = SpeechOutput::open(config.speech_output.as_deref())?;
+        let spectators = Spectators::open(config.spectate.as_ref())?;
+        renderer.set_mirrored(spectators.is_enabled());
+        let mut event_loop = Self {
+            input_handler,
+            events: Vec::new(),
+            renderer,
+            ctx: Context::new(config),
+            undersized: None,
+            idle: IdleDetector::new(Instant::now()),
+            speech,
+            spectators,
+            hits: HitRegions::new(),
+            autosave: AutosaveTimer::default(),
+            schedule: UpdateSchedule::default(),
+            skipped_renders: 0,
+            #[cfg(feature = "db")]
+            save_db: None,
+            session,
+        };
+        event_loop.sync_viewport((width, height))?;
+        Ok(event_loop)
     }
 
-    /// Runs the main game loop with the provided game state and configuration.
+    /// Runs the main game loop with the provided node tree.
     ///
     /// This method implements a fixed timestep loop with lag compensation.
-    /// It will continue running until the game state's `on_event` method
-    /// returns `true` for any input event.
-    ///
-    /// The loop supports state machines and entity updates through the GameState trait.
+    /// It will continue running until the root node's `on_event` method
+    /// returns `true` for any input event, a quit key is pressed, or a node
+    /// calls [`Context::request_exit`].
     ///
     /// # Arguments
-    /// * `node` - The initial game state implementing the Node trait
-    /// * `config` - Configuration for the game loop
+    /// * `node` - The root of the node tree
     ///
     /// # Returns
-    /// * `Ok(())` when the game exits normally
+    /// * `Ok(code)` with the requested exit code, or `0`, when the game exits normally
     /// * `Err(EngineError)` if an error occurs during execution
-    pub fn run<N: Node>(&mut self, node: &mut dyn Node) -> Result<(), EngineError> {
-        debug!("Starting event loop with config: {:?}", self.config);
+    ///
+    /// The game is autosaved when it exits normally.
+    pub fn run(&mut self, node: &mut dyn Node) -> Result<i32, EngineError> {
+        let code = self.run_frames(node)?;
+        self.save(node);
+        Ok(code)
+    }
+
+    fn run_frames(&mut self, node: &mut dyn Node) -> Result<i32, EngineError> {
+        debug!("Starting event loop with config: {:?}", self.ctx.config());
         let mut previous_time = Instant::now();
         let mut lag_time = Duration::ZERO;
-        let frame_duration = self.config.frame_duration();
 
         loop {
-            self.input_handler
-                .poll(self.config.input_strategy.timeout())?;
-
-            for event in self.input_handler.drain() {
-                if node.on_event(event) {
-                    return Ok(());
+            self.apply_config_changes(node)?;
+            let config = self.ctx.config();
+            let frame_duration = config.frame_duration();
+            let max_frame_time = config.max_frame_time;
+
+            self.input_handler.poll(config.input_strategy.timeout())?;
+            let work_started = Instant::now();
+
+            // Reuse the buffer from the last frame instead of allocating one
+            let mut events = std::mem::take(&mut self.events);
+            self.input_handler.drain_into(&mut events);
+            events.append(&mut self.ctx.take_sent_events());
+            for event in &events {
+                if IdleDetector::is_activity(event) && self.idle.activity(Instant::now()) {
+                    self.ctx.set_idle(false);
+                    node.on_engine_event(&mut self.ctx, &EngineEvent::ActivityResumed);
+                }
+                if let Event::Mouse(mouse) = event {
+                    let hit = self.hit(*mouse);
+                    if let Some(hit) = hit {
+                        node.on_engine_event(&mut self.ctx, &EngineEvent::Hit(hit));
+                    }
+                    let viewport = self.ctx.viewport();
+                    let position = (
+                        mouse.column.saturating_sub(viewport.x),
+                        mouse.row.saturating_sub(viewport.y),
+                    );
+                    self.ctx.drag_state().mouse(mouse, position, hit.as_ref());
+                    self.ctx
+                        .tooltip_state()
+                        .mouse(mouse.kind, position, hit.as_ref());
+                    self.dispatch_drag_events(node);
+                }
+                match event {
+                    Event::Resize(width, height) => {
+                        // Legacy consoles report the screen buffer, not the window
+                        let size = if self.ctx.config().console.is_legacy() {
+                            self.session.size()?
+                        } else {
+                            (*width, *height)
+                        };
+                        self.resize(node, size)?
+                    }
+                    Event::Key(key)
+                        if self.ctx.config().quit_keys.iter().any(|k| k.matches(key)) =>
+                    {
+                        debug!("Quit key pressed: {:?}", key);
+                        return Ok(0);
+                    }
+                    Event::Key(key)
+                        if self.ctx.config().pause_key.is_some_and(|k| k.matches(key)) =>
+                    {
+                        self.ctx.toggle_pause();
+                        continue;
+                    }
+                    Event::Key(key) if key.code == KeyCode::Esc && self.ctx.drag().is_some() => {
+                        self.ctx.cancel_drag();
+                        self.dispatch_drag_events(node);
+                        continue;
+                    }
+                    _ => {}
+                }
+                match node.on_event(&mut self.ctx, event) {
+                    Ok(true) => return Ok(self.ctx.exit_requested().unwrap_or(0)),
+                    Ok(false) => {}
+                    Err(error) => self.handle_error(node, error)?,
+                }
+                self.dispatch_drag_events(node);
+                if let Some(code) = self.ctx.exit_requested() {
+                    return Ok(code);
                 }
             }
+            events.clear();
+            self.events = events;
+
+            match self.ctx.apply_pause() {
+                Some(true) => node.on_pause(&mut self.ctx),
+                Some(false) => node.on_resume(&mut self.ctx),
+                None => {}
+            }
 
             let now = Instant::now();
+            if self.idle.check(now, self.ctx.config().idle_timeout) {
+                self.ctx.set_idle(true);
+                node.on_engine_event(&mut self.ctx, &EngineEvent::Idle);
+            }
             let mut elapsed = now.duration_since(previous_time);
             previous_time = now;
 
             // Prevent spiral of death by capping frame time
-            if elapsed > self.config.max_frame_time {
+            if elapsed > max_frame_time {
                 warn!(
                     "Frame time exceeded maximum: {:?}, capping to {:?}",
-                    elapsed, self.config.max_frame_time
+                    elapsed, max_frame_time
                 );
-                elapsed = self.config.max_frame_time;
+                elapsed = max_frame_time;
             }
 
             lag_time += elapsed;
 
+            for job in self.ctx.poll_jobs() {
+                node.on_engine_event(&mut self.ctx, &EngineEvent::JobFinished(job));
+            }
+
+            if let Some(visible) = self.ctx.advance_notifications(elapsed) {
+                node.on_engine_event(&mut self.ctx, &EngineEvent::Notifications(visible));
+            }
+
+            if self.ctx.is_paused() {
+                // Input and rendering carry on, but game time stands still
+                lag_time = Duration::ZERO;
+            }
+
+            if let Some(current) = self.undersized {
+                // The game stays paused until the terminal is large enough again
+                lag_time = Duration::ZERO;
+                self.renderer.clear()?;
+                render_size_prompt(
+                    &mut self.renderer,
+                    self.ctx.config().required_size(),
+                    current,
+                )?;
+                self.renderer.flush()?;
+                continue;
+            }
+
+            let mut updates = 0;
             while lag_time >= frame_duration {
-                node.update(frame_duration.as_secs_f32());
+                updates += 1;
+                let dt = frame_duration.as_secs_f32();
+                if let Err(error) = self.schedule.tick(node, &mut self.ctx, dt) {
+                    self.handle_error(node, error)?;
+                }
+                self.ctx.finish_update();
                 lag_time -= frame_duration;
+                if let Some(code) = self.ctx.exit_requested() {
+                    return Ok(code);
+                }
+            }
+            self.dispatch_drag_events(node);
+
+            if self.ctx.take_save_request() {
+                self.autosave.reset();
+                self.save(node);
+            } else if self
+                .autosave
+                .due(elapsed, self.ctx.config().autosave_interval)
+            {
+                self.save(node);
             }
 
+            let announcements = self.ctx.take_announcements();
+            if let Err(error) = self.speech.write(&announcements) {
+                self.handle_error(node, error)?;
+            }
+
+            // Behind, the time is better spent updating; over the CPU budget,
+            // only every other frame is drawn
+            let catching_up =
+                updates > 1 && self.skipped_renders < self.ctx.config().max_frame_skip;
+            if catching_up || self.ctx.frame_stats().is_degraded() && self.skipped_renders == 0 {
+                self.skipped_renders += 1;
+                let stats = self.ctx.frame_stats_mut();
+                if catching_up {
+                    stats.record_frame_skip();
+                } else {
+                    stats.record_skipped_render();
+                }
+                self.record_work(work_started);
+                continue;
+            }
+            self.skipped_renders = 0;
+
+            if self.spectators.accept() {
+                self.renderer.invalidate();
+            }
+            let alpha = lag_time.as_secs_f32() / frame_duration.as_secs_f32();
+            let (width, height) = self.renderer.size();
             self.renderer.clear()?;
-            node.render(&mut self.renderer);
-            self.renderer.flush()?;
+            self.hits.clear();
+            let ghost = self
+                .ctx
+                .drag_state()
+                .ghost()
+                .map(|(drag, cursor)| (drag.ghost.clone(), drag.style, cursor));
+            let tooltip = self.ctx.advance_tooltip(elapsed);
+            let mut frame = Frame::new(&mut self.renderer, Rect::new(0, 0, width, height), alpha)
+                .with_hit_regions(&mut self.hits);
+            let mut rendered = node.render(&mut frame);
+            if let Some(tooltip) = tooltip.filter(|_| ghost.is_none()) {
+                rendered = rendered.and(tooltip.draw(&mut frame, crate::style!(black on white)));
+            }
+            if let Some((text, style, (x, y))) = ghost {
+                // The ghost is drawn over everything but registers no hit region
+                rendered = rendered.and(frame.draw_styled(x, y, &text, style).map(|_| ()));
+            }
+            if self.ctx.config().debug_mode || self.ctx.config().show_redraws {
+                rendered = rendered.and(self.ctx.frame_stats().draw_overlay(&mut frame));
+            }
+            if let Err(error) = rendered {
+                self.handle_error(node, error)?;
+            }
+            self.ctx.swap_hit_regions(&mut self.hits);
+            for error in self.renderer.take_errors() {
+                self.handle_error(node, error)?;
+            }
+            let flush_started = Instant::now();
+            let flushed = self.renderer.flush();
+            let bytes = self.renderer.take_bytes_written();
+            let cells = self.renderer.take_changed_cells();
+            self.spectators.send(&self.renderer.take_mirrored());
+            self.ctx
+                .frame_stats_mut()
+                .record_flush(bytes, cells, flush_started.elapsed());
+            if let Err(error) = flushed {
+                self.handle_error(node, error)?;
+            }
+            self.apply_terminal_commands()?;
+            self.record_work(work_started);
+        }
+    }
+
+    /// Records the time the frame spent working since `started` and
+    /// degrades or recovers if that crossed the CPU budget.
+    fn record_work(&mut self, started: Instant) {
+        let budget = self.ctx.config().cpu_budget;
+        let stats = self.ctx.frame_stats_mut();
+        match stats.record_work(started.elapsed(), budget) {
+            Some(true) => warn!("Frames are over the CPU budget of {:?}, degrading", budget),
+            Some(false) => info!("Frames are within the CPU budget again"),
+            None => {}
+        }
+    }
+
+    /// Hit-tests a mouse event in terminal coordinates against the regions
+    /// of the last frame.
+    fn hit(&self, mut mouse: MouseEvent) -> Option<Hit> {
+        let viewport = self.ctx.viewport();
+        mouse.column = mouse.column.checked_sub(viewport.x)?;
+        mouse.row = mouse.row.checked_sub(viewport.y)?;
+        self.ctx.hit_regions().hit(mouse)
+    }
+
+    /// Autosaves the node tree's state, if an autosave file is configured,
+    /// and announces the result to the tree.
+    fn save(&mut self, node: &mut dyn Node) {
+        let Some(mut path) = self.ctx.config().autosave.clone() else {
+            return;
+        };
+        if let Some(paths) = self.ctx.paths() {
+            path = paths.save_file(path);
+        }
+        let mut data = SaveData::new();
+        let config = self.ctx.config();
+        let (format, backups) = (config.autosave_format, config.autosave_backups);
+        let result = node
+            .on_save(&mut data)
+            .and_then(|()| self.write_save(&path, format, backups, &data));
+        let event = match result {
+            Ok(()) => {
+                debug!("Autosaved to {}", path.display());
+                EngineEvent::Saved(path)
+            }
+            Err(error) => {
+                warn!("Autosave to {} failed: {}", path.display(), error);
+                EngineEvent::SaveFailed(error.to_string())
+            }
+        };
+        node.on_engine_event(&mut self.ctx, &event);
+    }
+
+    /// Writes an autosave, keeping a database open between saves.
+    fn write_save(
+        &mut self,
+        path: &Path,
+        format: SaveFormat,
+        backups: usize,
+        data: &SaveData,
+    ) -> Result<(), EngineError> {
+        #[cfg(feature = "db")]
+        if format == SaveFormat::Database {
+            if self.save_db.as_ref().is_none_or(|(open, _)| open != path) {
+                // Close the previous database before opening the new one
+                self.save_db = None;
+                self.save_db = Some((path.to_path_buf(), WorldDb::open(path)?));
+            }
+            if let Some((_, db)) = &self.save_db {
+                return db.write_save(data);
+            }
+        }
+        autosave::write(path, format, backups, data)
+    }
+
+    /// Sends the drag events produced since the last call to the node tree.
+    fn dispatch_drag_events(&mut self, node: &mut dyn Node) {
+        for event in self.ctx.drag_state().take_events() {
+            node.on_engine_event(&mut self.ctx, &EngineEvent::Drag(event));
+        }
+    }
+
+    /// Applies terminal operations requested through the context, then puts
+    /// the cursor back where the game shows it.
+    fn apply_terminal_commands(&mut self) -> Result<(), EngineError> {
+        let viewport = self.ctx.viewport();
+        for command in self.ctx.take_terminal_commands() {
+            let command = match command {
+                TerminalCommand::Cursor(Some((x, y))) => {
+                    TerminalCommand::Cursor(Some((viewport.x + x, viewport.y + y)))
+                }
+                command => command,
+            };
+            self.session.apply(command)?;
+        }
+        self.session.place_cursor()
+    }
+
+    /// Applies configuration changes queued through the context and notifies the node tree.
+    fn apply_config_changes(&mut self, node: &mut dyn Node) -> Result<(), EngineError> {
+        for change in self.ctx.apply_pending() {
+            debug!("Applied configuration change: {:?}", change);
+            match change {
+                Config::MouseCapture(enabled) => self.session.set_mouse_capture(enabled)?,
+                Config::SpeechOutput(ref path) => {
+                    self.speech = SpeechOutput::open(path.as_deref())?
+                }
+                Config::HighContrast(_)
+                | Config::ColorBlindness(_)
+                | Config::Glyphs(_)
+                | Config::Console(_)
+                | Config::ColorDepth(_) => {
+                    self.renderer.set_filters(render_filters(self.ctx.config()))
+                }
+                Config::ShowRedraws(enabled) => self.renderer.set_show_redraws(enabled),
+                Config::ScreenSize(_) | Config::MinScreenSize(_) => {
+                    self.resize(node, self.session.size()?)?
+                }
+                _ => {}
+            }
+            node.on_engine_event(&mut self.ctx, &EngineEvent::ConfigChanged(change));
         }
+        Ok(())
     }
+
+    /// Applies the error policy to an error raised during a frame.
+    ///
+    /// Unrecoverable errors are always returned; recoverable ones are offered
+    /// to the node tree first, falling back to the configured policy.
+    fn handle_error(&mut self, node: &mut dyn Node, error: EngineError) -> Result<(), EngineError> {
+        if !error.is_recoverable() {
+            return Err(error);
+        }
+        let policy = node
+            .on_error(&mut self.ctx, &error)
+            .unwrap_or(self.ctx.config().error_policy);
+        match policy {
+            ErrorPolicy::Continue => Ok(()),
+            ErrorPolicy::LogAndContinue => {
+                warn!("{}", error);
+                Ok(())
+            }
+            ErrorPolicy::Abort => Err(error),
+        }
+    }
+
+    /// Resizes the drawing area for a terminal of the given size, telling
+    /// the node tree if the size of the viewport changed.
+    fn resize(&mut self, node: &mut dyn Node, terminal: (u16, u16)) -> Result<(), EngineError> {
+        if self.sync_viewport(terminal)? {
+            let viewport = self.ctx.viewport();
+            node.on_resize(&mut self.ctx, viewport.width, viewport.height);
+        }
+        Ok(())
+    }
+
+    /// Re-resolves the drawing area for a terminal of the given size,
+    /// returning whether the size of the viewport changed.
+    ///
+    /// If the terminal is smaller than the configured minimum the whole
+    /// terminal is used to show a prompt asking the player to enlarge it.
+    fn sync_viewport(&mut self, terminal: (u16, u16)) -> Result<bool, EngineError> {
+        let (min_width, min_height) = self.ctx.config().required_size();
+        if terminal.0 < min_width || terminal.1 < min_height {
+            if self.undersized != Some(terminal) {
+                warn!(
+                    "Terminal {}x{} is smaller than the required {}x{}",
+                    terminal.0, terminal.1, min_width, min_height
+                );
+                self.renderer
+                    .set_viewport(Rect::new(0, 0, terminal.0, terminal.1))?;
+                self.undersized = Some(terminal);
+            }
+            return Ok(false);
+        }
+
+        let was_undersized = self.undersized.take().is_some();
+        let viewport = self.ctx.config().screen_size.resolve(terminal)?;
+        let previous = self.ctx.viewport();
+        if was_undersized || viewport != previous {
+            debug!("Viewport changed to {:?}", viewport);
+            self.renderer.set_viewport(viewport)?;
+            self.ctx.set_viewport(viewport);
+        }
+        Ok((viewport.width, viewport.height) != (previous.width, previous.height))
+    }
+}
+
+/// Returns the renderer filters for the console, glyph and accessibility settings.
+///
+/// High contrast removes all color, so it takes precedence over simulating
+/// color blindness. Legacy consoles get ASCII glyphs unless Unicode is
+/// requested explicitly, since their raster fonts lack most symbols.
+fn render_filters(config: &GameConfig) -> Vec<fn(Cell) -> Cell> {
+    let mut filters: Vec<fn(Cell) -> Cell> = Vec::new();
+    let legacy = config.console.is_legacy();
+    let unicode = match config.glyphs {
+        GlyphSupport::Auto if legacy => false,
+        glyphs => glyphs.unicode(),
+    };
+    if !unicode {
+        filters.push(glyphs::ascii);
+    }
+    if config.high_contrast {
+        filters.push(accessibility::high_contrast);
+    } else if let Some(deficiency) = config.color_blindness {
+        filters.push(deficiency.filter());
+    }
+    if legacy {
+        filters.push(terminal_session::legacy_colors);
+    } else if let Some(quantize) = config.color_depth.filter() {
+        filters.push(quantize);
+    }
+    filters
+}
+
+/// Draws the "please enlarge your terminal" screen, clipped to the renderer.
+fn render_size_prompt(
+    renderer: &mut BasicRenderer,
+    required: (u16, u16),
+    current: (u16, u16),
+) -> Result<(), EngineError> {
+    let lines = [
+        format!(
+            "Please enlarge your terminal to {}x{}",
+            required.0, required.1
+        ),
+        format!("(currently {}x{})", current.0, current.1),
+    ];
+    let (width, height) = renderer.size();
+    let top = (height / 2).saturating_sub(1);
+    for (row, line) in lines.iter().enumerate() {
+        let y = top + row as u16;
+        if y >= height {
+            break;
+        }
+        let text: String = line.chars().take(width as usize).collect();
+        let x = width.saturating_sub(text.chars().count() as u16) / 2;
+        renderer.draw_styled(x, y, &text, Style::new())?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{Config, GameConfig};
-    use crossterm::event::Event;
+    use crate::config::{Config, GameConfig, ScreenSize};
+    use crate::terminal::OutputMode;
     use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use crossterm::style::Color;
     use std::sync::{Arc, Mutex};
 
     struct MockState {
@@ -133,36 +645,38 @@ mod tests {
     }
 
     impl Node for MockState {
-        fn update(&mut self, _delta_time: f32) {
+        fn update(&mut self, _ctx: &mut Context, _delta_time: f32) -> Result<(), EngineError> {
             let mut count = self.update_count.lock().unwrap();
             *count += 1;
+            Ok(())
         }
 
-        fn on_event(&mut self, event: Event) -> bool {
-            if let Some(exit_after) = self.exit_after_n_events {
-                if self.get_render_count() >= exit_after {
-                    return true;
-                }
+        fn on_event(&mut self, _ctx: &mut Context, event: &Event) -> Result<bool, EngineError> {
+            if let Some(exit_after) = self.exit_after_n_events
+                && self.get_render_count() >= exit_after
+            {
+                return Ok(true);
             }
 
-            if let Event::Key(key_event) = event {
-                if key_event.code == KeyCode::Esc {
-                    return true;
-                }
+            if let Event::Key(key_event) = event
+                && key_event.code == KeyCode::Esc
+            {
+                return Ok(true);
             }
-            false
+            Ok(false)
         }
 
-        fn render(&self, _renderer: &mut dyn Renderer) {
+        fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
             let mut count = self.render_count.lock().unwrap();
             *count += 1;
+            Ok(())
         }
     }
 
     #[test]
     fn test_event_loop_creation() {
         let config = GameConfig::new();
-        match EventLoop::new(&config) {
+        match EventLoop::new(config) {
             Ok(_) => {
                 // Success case - terminal is available
             }
@@ -178,19 +692,22 @@ mod tests {
     #[test]
     fn test_game_state_trait_implementation() {
         let mut state = MockState::new();
+        let mut ctx = Context::new(GameConfig::new());
         let mut renderer = BasicRenderer::new(80, 24).unwrap();
 
-        state.update(1.0 / 60.0);
+        state.update(&mut ctx, 1.0 / 60.0).unwrap();
         assert_eq!(state.get_update_count(), 1);
 
-        state.render(&mut renderer);
+        state
+            .render(&mut Frame::new(&mut renderer, Rect::new(0, 0, 80, 24), 0.0))
+            .unwrap();
         assert_eq!(state.get_render_count(), 1);
 
         let key_event = Event::Key(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE));
-        assert!(!state.on_event(key_event));
+        assert!(!state.on_event(&mut ctx, &key_event).unwrap());
 
         let esc_event = Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
-        assert!(state.on_event(esc_event));
+        assert!(state.on_event(&mut ctx, &esc_event).unwrap());
     }
 
     #[test]
@@ -204,9 +721,10 @@ mod tests {
     #[test]
     fn test_game_state_update_counting() {
         let mut state = MockState::new();
+        let mut ctx = Context::new(GameConfig::new());
 
         for i in 1..=10 {
-            state.update(1.0 / 60.0);
+            state.update(&mut ctx, 1.0 / 60.0).unwrap();
             assert_eq!(state.get_update_count(), i);
         }
     }
@@ -217,7 +735,9 @@ mod tests {
         let mut renderer = BasicRenderer::new(80, 24).unwrap();
 
         for i in 1..=5 {
-            state.render(&mut renderer);
+            state
+                .render(&mut Frame::new(&mut renderer, Rect::new(0, 0, 80, 24), 0.0))
+                .unwrap();
             assert_eq!(state.get_render_count(), i);
         }
     }
@@ -225,22 +745,70 @@ mod tests {
     #[test]
     fn test_game_state_exit_conditions() {
         let mut state = MockState::new();
+        let mut ctx = Context::new(GameConfig::new());
 
         let char_event = Event::Key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE));
-        assert!(!state.on_event(char_event));
+        assert!(!state.on_event(&mut ctx, &char_event).unwrap());
 
         let enter_event = Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
-        assert!(!state.on_event(enter_event));
+        assert!(!state.on_event(&mut ctx, &enter_event).unwrap());
 
         let esc_event = Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
-        assert!(state.on_event(esc_event));
+        assert!(state.on_event(&mut ctx, &esc_event).unwrap());
     }
 
     #[test]
     fn test_exit_immediately_state() {
         let mut state = MockState::new_exit_after_n_events(0);
+        let mut ctx = Context::new(GameConfig::new());
 
         let any_event = Event::Key(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE));
-        assert!(state.on_event(any_event));
+        assert!(state.on_event(&mut ctx, &any_event).unwrap());
+    }
+
+    #[test]
+    fn test_clipped_draws_are_collected() {
+        let mut renderer = BasicRenderer::new(10, 2).unwrap();
+        let cell = crate::renderer::Cell {
+            ch: 'x',
+            fg: Color::Reset,
+            bg: Color::Reset,
+            attrs: crate::style::Attributes::empty(),
+            link: None,
+        };
+
+        assert!(renderer.draw_cell(10, 0, cell).is_ok());
+        assert!(renderer.draw_styled(8, 1, "abc", Style::new()).is_ok());
+        assert!(renderer.draw_styled(0, 1, "fits", Style::new()).is_ok());
+
+        let errors = renderer.take_errors();
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().all(EngineError::is_recoverable));
+        assert!(renderer.take_errors().is_empty());
+    }
+
+    /// Fails every write, like a closed stdout.
+    struct BrokenPipe;
+
+    impl std::io::Write for BrokenPipe {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    #[test]
+    fn test_failing_output_ends_the_loop() {
+        let config = GameConfig::new()
+            .add_config(Config::Output(OutputMode::FrameDump))
+            .add_config(Config::ScreenSize(ScreenSize::Fixed(4, 1)));
+        let mut event_loop = EventLoop::new(config).unwrap();
+        event_loop.renderer = BasicRenderer::with_output(4, 1, BrokenPipe).unwrap();
+        let mut node = MockState::new();
+        let result = event_loop.run(&mut node);
+        assert!(matches!(result, Err(EngineError::Io(_))));
+        assert_eq!(node.get_render_count(), 1);
     }
 }
@@ -0,0 +1,191 @@
+//! Color animations for cells.
+//!
+//! A [`ColorFade`] blends a style's foreground and background between two
+//! styles over time. Like [`SmoothPosition`](crate::motion::SmoothPosition)
+//! it advances in the fixed update and is sampled with the frame's alpha,
+//! so fades stay smooth at any frame rate. Colors are blended in RGB; the
+//! renderer reduces them to the terminal's `color_depth` when drawing.
+//!
+//! ```
+//! use coil_engine::fade::ColorFade;
+//! use coil_engine::style;
+//! use std::time::Duration;
+//!
+//! let mut glow = ColorFade::pulse(style!(dark_red), style!(red), Duration::from_secs(1));
+//! glow.update(0.25);
+//! let _style = glow.style(0.0);
+//! ```
+use crate::color;
+use crate::renderer::Cell;
+use crate::style::Style;
+use std::time::Duration;
+
+/// What a fade does once it reaches its end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repeat {
+    /// Stop at the end style.
+    Once,
+    /// Jump back to the start style and fade again.
+    Loop,
+    /// Fade back and forth between the two styles.
+    PingPong,
+}
+
+/// A blend between two styles over time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorFade {
+    from: Style,
+    to: Style,
+    /// Length of one fade in seconds
+    duration: f32,
+    repeat: Repeat,
+    previous: f32,
+    elapsed: f32,
+}
+
+impl ColorFade {
+    /// Fades once from `from` to `to`.
+    pub fn new(from: Style, to: Style, duration: Duration) -> Self {
+        Self {
+            from,
+            to,
+            duration: duration.as_secs_f32().max(f32::EPSILON),
+            repeat: Repeat::Once,
+            previous: 0.0,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Pulses between two styles, taking `period` to go from one to the other and back.
+    pub fn pulse(a: Style, b: Style, period: Duration) -> Self {
+        Self::new(a, b, period / 2).with_repeat(Repeat::PingPong)
+    }
+
+    /// Starts at `flash` and fades back to `base`, e.g. when a tile is hit.
+    pub fn flash(flash: Style, base: Style, duration: Duration) -> Self {
+        Self::new(flash, base, duration)
+    }
+
+    pub fn with_repeat(mut self, repeat: Repeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// Advances the fade by `dt` seconds; call it from `update`.
+    pub fn update(&mut self, dt: f32) {
+        self.previous = self.elapsed;
+        self.elapsed += dt;
+        if self.repeat != Repeat::Once {
+            // Keep both samples within one cycle, preserving their distance
+            let cycle = match self.repeat {
+                Repeat::PingPong => self.duration * 2.0,
+                _ => self.duration,
+            };
+            let wraps = (self.previous / cycle).floor() * cycle;
+            self.previous -= wraps;
+            self.elapsed -= wraps;
+        } else {
+            self.elapsed = self.elapsed.min(self.duration);
+        }
+    }
+
+    /// Starts the fade over from the `from` style.
+    pub fn restart(&mut self) {
+        self.previous = 0.0;
+        self.elapsed = 0.0;
+    }
+
+    /// Returns whether a [`Repeat::Once`] fade has reached its end style.
+    pub fn is_finished(&self) -> bool {
+        self.repeat == Repeat::Once && self.previous >= self.duration
+    }
+
+    /// Returns how far between `from` (0.0) and `to` (1.0) the fade is at
+    /// the frame's interpolation alpha.
+    pub fn progress(&self, alpha: f32) -> f32 {
+        let time = self.previous + (self.elapsed - self.previous) * alpha.clamp(0.0, 1.0);
+        let t = time / self.duration;
+        match self.repeat {
+            Repeat::Once => t.min(1.0),
+            Repeat::Loop => t.fract(),
+            Repeat::PingPong => {
+                let t = t % 2.0;
+                if t > 1.0 { 2.0 - t } else { t }
+            }
+        }
+    }
+
+    /// Returns the blended style at the frame's interpolation alpha.
+    pub fn style(&self, alpha: f32) -> Style {
+        let t = self.progress(alpha);
+        // Attributes cannot blend, so they switch halfway
+        let attrs = if t < 0.5 {
+            self.from.attrs
+        } else {
+            self.to.attrs
+        };
+        Style {
+            fg: color::mix(self.from.fg, self.to.fg, t),
+            bg: color::mix(self.from.bg, self.to.bg, t),
+            attrs,
+        }
+    }
+
+    /// Recolors a cell with the blended style.
+    pub fn apply(&self, cell: Cell, alpha: f32) -> Cell {
+        let style = self.style(alpha);
+        Cell {
+            fg: style.fg,
+            bg: style.bg,
+            attrs: style.attrs,
+            ..cell
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::style::Color;
+
+    #[test]
+    fn test_fades_interpolate_and_repeat() {
+        let black = crate::style!(black on black);
+        let white = Style::new()
+            .fg(Color::Rgb {
+                r: 255,
+                g: 255,
+                b: 255,
+            })
+            .bg(Color::Rgb {
+                r: 255,
+                g: 255,
+                b: 255,
+            });
+        let second = Duration::from_secs(1);
+
+        let mut once = ColorFade::new(black, white, second);
+        once.update(0.5);
+        assert_eq!(once.progress(1.0), 0.5);
+        assert_eq!(once.progress(0.5), 0.25);
+        assert_eq!(
+            once.style(1.0).fg,
+            Color::Rgb {
+                r: 128,
+                g: 128,
+                b: 128
+            }
+        );
+        once.update(1.0);
+        once.update(0.1);
+        assert!(once.is_finished());
+        assert_eq!(once.style(0.0), white);
+
+        let mut pulse = ColorFade::pulse(black, white, second * 2);
+        pulse.update(1.5);
+        assert_eq!(pulse.progress(1.0), 0.5);
+        pulse.update(1.0);
+        assert!((pulse.progress(1.0) - 0.5).abs() < 1e-5);
+        assert!(!pulse.is_finished());
+    }
+}
@@ -0,0 +1,668 @@
+//! Interactive fiction: passages, choices and variables.
+//!
+//! Stories are written in Twee, the text format of Twine, with a small
+//! subset of Harlowe markup:
+//!
+//! ```text
+//! :: Start
+//! (set: $gold to 3)
+//! You wake up in a cell. You have $gold coins.
+//! (if: $gold > 2)[A guard eyes your purse.](else:)[Nobody cares about you.]
+//! [[Call the guard->Guard]]
+//! [[Sleep]]
+//! ```
+//!
+//! Supported are `[[text]]`, `[[text->target]]`, `[[target<-text]]` and
+//! `[[text|target]]` links, `$variables`, the `(set:)`, `(print:)`, `(if:)`,
+//! `(unless:)`, `(else-if:)` and `(else:)` macros with hooks in brackets,
+//! and `''bold''`/`//italic//` emphasis, which is dropped since cells carry no
+//! attributes. Expressions support numbers, strings, `true`/`false`, `+ - * /`,
+//! `is`, `is not`, `<`, `<=`, `>`, `>=`, `contains`, `and`, `or` and `not`.
+//!
+//! The story starts at the passage named by `"start"` in a `StoryData`
+//! passage, at `Start`, or at the first passage. [`nodes::StoryPlayer`](crate::nodes::StoryPlayer)
+//! plays a story in the terminal.
+use crate::errors::EngineError;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// A variable's value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+}
+
+impl Value {
+    fn truthy(&self) -> bool {
+        match self {
+            Value::Number(n) => *n != 0.0,
+            Value::Text(s) => !s.is_empty(),
+            Value::Bool(b) => *b,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Text(s) => f.write_str(s),
+            Value::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+/// A passage of the story, with its unprocessed markup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Passage {
+    pub name: String,
+    pub tags: Vec<String>,
+    pub source: String,
+}
+
+/// A link out of a passage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Choice {
+    pub text: String,
+    pub target: String,
+}
+
+/// A passage as shown to the player: its text and the choices it offers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    pub passage: String,
+    pub text: String,
+    pub choices: Vec<Choice>,
+}
+
+/// Where the player is in the story and what they have done.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StoryState {
+    pub passage: String,
+    pub variables: BTreeMap<String, Value>,
+    pub visited: BTreeSet<String>,
+}
+
+impl StoryState {
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.variables.get(name)
+    }
+}
+
+/// A parsed story.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Story {
+    passages: BTreeMap<String, Passage>,
+    start: String,
+}
+
+impl Story {
+    /// Parses a story in Twee format.
+    pub fn parse(twee: &str) -> Result<Self, EngineError> {
+        let mut passages = BTreeMap::new();
+        let mut order = Vec::new();
+        let mut current: Option<Passage> = None;
+        for line in twee.lines() {
+            if let Some(header) = line.strip_prefix("::") {
+                if let Some(passage) = current.take() {
+                    order.push(passage.name.clone());
+                    passages.insert(passage.name.clone(), passage);
+                }
+                current = Some(parse_header(header));
+            } else if let Some(passage) = &mut current {
+                passage.source.push_str(line);
+                passage.source.push('\n');
+            }
+        }
+        if let Some(passage) = current {
+            order.push(passage.name.clone());
+            passages.insert(passage.name.clone(), passage);
+        }
+
+        let start = passages
+            .get("StoryData")
+            .and_then(|data| json_string(&data.source, "start"))
+            .or_else(|| passages.contains_key("Start").then(|| "Start".to_string()))
+            .or_else(|| order.iter().find(|name| !is_special(name)).cloned())
+            .ok_or_else(|| EngineError::game("story has no passages"))?;
+        if !passages.contains_key(&start) {
+            return Err(EngineError::game(format!(
+                "start passage {:?} does not exist",
+                start
+            )));
+        }
+        Ok(Self { passages, start })
+    }
+
+    /// Reads a `.twee` file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, EngineError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn passage(&self, name: &str) -> Option<&Passage> {
+        self.passages.get(name)
+    }
+
+    pub fn start(&self) -> &str {
+        &self.start
+    }
+
+    /// Starts a new playthrough, entering the start passage.
+    pub fn begin(&self, state: &mut StoryState) -> Result<Page, EngineError> {
+        *state = StoryState::default();
+        self.enter(state, &self.start.clone())
+    }
+
+    /// Moves to `passage`, running its macros against `state`.
+    pub fn enter(&self, state: &mut StoryState, passage: &str) -> Result<Page, EngineError> {
+        let source = &self
+            .passages
+            .get(passage)
+            .ok_or_else(|| EngineError::game(format!("no passage named {:?}", passage)))?
+            .source;
+        state.passage = passage.to_string();
+        let mut runner = Runner {
+            state,
+            text: String::new(),
+            choices: Vec::new(),
+        };
+        runner
+            .run(source)
+            .map_err(|e| EngineError::game(format!("in passage {:?}: {}", passage, e)))?;
+        let (text, choices) = (tidy(&runner.text), runner.choices);
+        state.visited.insert(passage.to_string());
+        Ok(Page {
+            passage: passage.to_string(),
+            text,
+            choices,
+        })
+    }
+
+    /// Follows the choice at `index` of `page`.
+    pub fn choose(
+        &self,
+        state: &mut StoryState,
+        page: &Page,
+        index: usize,
+    ) -> Result<Page, EngineError> {
+        let choice = page
+            .choices
+            .get(index)
+            .ok_or_else(|| EngineError::game(format!("no choice {}", index)))?;
+        self.enter(state, &choice.target)
+    }
+}
+
+/// Parses `Name [tag1 tag2] {"position":"0,0"}`.
+fn parse_header(header: &str) -> Passage {
+    let header = header.trim();
+    let (name_part, rest) = match header.find(['[', '{']) {
+        Some(index) => header.split_at(index),
+        None => (header, ""),
+    };
+    let tags = rest
+        .strip_prefix('[')
+        .and_then(|rest| rest.split(']').next())
+        .map(|tags| tags.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default();
+    Passage {
+        name: name_part.trim().to_string(),
+        tags,
+        source: String::new(),
+    }
+}
+
+fn is_special(name: &str) -> bool {
+    matches!(name, "StoryData" | "StoryTitle")
+}
+
+/// Extracts a string field from the small JSON object in `StoryData`.
+fn json_string(json: &str, key: &str) -> Option<String> {
+    let after_key = &json[json.find(&format!("\"{}\"", key))? + key.len() + 2..];
+    let value = after_key.trim_start().strip_prefix(':')?.trim_start();
+    let value = value.strip_prefix('"')?;
+    Some(value[..value.find('"')?].to_string())
+}
+
+/// Trims lines left blank by macros and collapses runs of blank lines.
+fn tidy(text: &str) -> String {
+    let mut tidied = String::new();
+    let mut blank_run = 0;
+    for line in text.lines().map(str::trim_end) {
+        if line.trim().is_empty() {
+            blank_run += 1;
+            continue;
+        }
+        if !tidied.is_empty() {
+            tidied.push_str(if blank_run > 0 { "\n\n" } else { "\n" });
+        }
+        blank_run = 0;
+        tidied.push_str(line);
+    }
+    tidied
+}
+
+/// Executes a passage's markup, collecting its text and links.
+struct Runner<'a> {
+    state: &'a mut StoryState,
+    text: String,
+    choices: Vec<Choice>,
+}
+
+impl Runner<'_> {
+    fn run(&mut self, source: &str) -> Result<(), String> {
+        // Result of the last (if:) chain, for a following (else:)
+        let mut last_condition: Option<bool> = None;
+        let mut rest = source;
+        while let Some(ch) = rest.chars().next() {
+            if let Some(after) = rest.strip_prefix("[[") {
+                let end = after.find("]]").ok_or("unclosed link")?;
+                self.choices.push(parse_link(&after[..end]));
+                rest = &after[end + 2..];
+                // Links are offered as choices, so a line holding only a link disappears
+                rest = rest.strip_prefix('\n').unwrap_or(rest);
+            } else if ch == '(' && macro_name(rest).is_some() {
+                let end = matching(rest, '(', ')').ok_or("unclosed macro")?;
+                let (name, args) = macro_name(&rest[..end]).unwrap_or_default();
+                rest = &rest[end + 1..];
+                match name {
+                    "set" => self.set(args)?,
+                    "print" => {
+                        let value = self.eval(args)?;
+                        self.text.push_str(&value.to_string());
+                    }
+                    "if" | "unless" | "else-if" | "elseif" | "else" => {
+                        let hook_start = rest.trim_start_matches([' ', '\t']);
+                        if !hook_start.starts_with('[') {
+                            return Err(format!("({}:) needs a [hook]", name));
+                        }
+                        let end = matching(hook_start, '[', ']').ok_or("unclosed hook")?;
+                        let hook = &hook_start[1..end];
+                        rest = &hook_start[end + 1..];
+                        let show = match name {
+                            "if" => self.eval(args)?.truthy(),
+                            "unless" => !self.eval(args)?.truthy(),
+                            "else" => last_condition == Some(false),
+                            _ => last_condition == Some(false) && self.eval(args)?.truthy(),
+                        };
+                        last_condition = match name {
+                            "if" | "unless" => Some(show),
+                            _ => last_condition.map(|shown_before| shown_before || show),
+                        };
+                        if show {
+                            self.run(hook)?;
+                        }
+                        continue;
+                    }
+                    other => return Err(format!("unknown macro ({}:)", other)),
+                }
+            } else if ch == '$' && variable_name(&rest[1..]).is_some() {
+                let name = variable_name(&rest[1..]).unwrap_or_default();
+                let value = self.state.get(name).map(Value::to_string);
+                self.text.push_str(&value.unwrap_or_default());
+                rest = &rest[1 + name.len()..];
+            } else if let Some(after) = rest.strip_prefix("''").or_else(|| rest.strip_prefix("//"))
+            {
+                rest = after;
+            } else {
+                self.text.push(ch);
+                rest = &rest[ch.len_utf8()..];
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs `$name to expression`.
+    fn set(&mut self, args: &str) -> Result<(), String> {
+        let args = args.trim();
+        let name = args
+            .strip_prefix('$')
+            .and_then(variable_name)
+            .ok_or("(set:) needs a $variable")?;
+        let expression = args[1 + name.len()..]
+            .trim_start()
+            .strip_prefix("to")
+            .ok_or("(set:) needs `to`")?;
+        let value = self.eval(expression)?;
+        self.state.variables.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    fn eval(&self, expression: &str) -> Result<Value, String> {
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+            state: self.state,
+        };
+        let value = parser.or()?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(value),
+            Some(token) => Err(format!("unexpected {:?} in {:?}", token, expression.trim())),
+        }
+    }
+}
+
+/// Splits `[[...]]` contents into the shown text and the target passage.
+fn parse_link(link: &str) -> Choice {
+    let (text, target) = if let Some((text, target)) = link.split_once("->") {
+        (text, target)
+    } else if let Some((target, text)) = link.split_once("<-") {
+        (text, target)
+    } else if let Some((text, target)) = link.split_once('|') {
+        (text, target)
+    } else {
+        (link, link)
+    };
+    Choice {
+        text: text.trim().to_string(),
+        target: target.trim().to_string(),
+    }
+}
+
+/// Returns the name and arguments of a macro call starting with `(name:`.
+fn macro_name(text: &str) -> Option<(&str, &str)> {
+    let inner = text.strip_prefix('(')?;
+    let colon = inner.find(':')?;
+    let name = &inner[..colon];
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphabetic() || c == '-') {
+        return None;
+    }
+    Some((name, &inner[colon + 1..]))
+}
+
+fn variable_name(text: &str) -> Option<&str> {
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    (end > 0).then(|| &text[..end])
+}
+
+/// Returns the index of the bracket closing the one `text` starts with,
+/// skipping nested brackets and quoted strings.
+fn matching(text: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    for (index, ch) in text.char_indices() {
+        match quote {
+            Some(q) if ch == q => quote = None,
+            Some(_) => {}
+            None if ch == '"' && open == '(' => quote = Some(ch),
+            None if ch == open => depth += 1,
+            None if ch == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            None => {}
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Value(Value),
+    Variable(String),
+    Symbol(&'static str),
+    Word(String),
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    const SYMBOLS: [&str; 10] = ["<=", ">=", "<", ">", "+", "-", "*", "/", "(", ")"];
+    let mut tokens = Vec::new();
+    let mut rest = expression.trim_start();
+    while let Some(ch) = rest.chars().next() {
+        if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else if ch == '"' || ch == '\'' {
+            let end = rest[1..].find(ch).ok_or("unclosed string")? + 1;
+            tokens.push(Token::Value(Value::Text(rest[1..end].to_string())));
+            rest = &rest[end + 1..];
+        } else if ch == '$' {
+            let name = variable_name(&rest[1..]).ok_or("expected a variable name after $")?;
+            tokens.push(Token::Variable(name.to_string()));
+            rest = &rest[1 + name.len()..];
+        } else if ch.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            let number = rest[..end].parse().map_err(|_| "invalid number")?;
+            tokens.push(Token::Value(Value::Number(number)));
+            rest = &rest[end..];
+        } else if ch.is_alphabetic() {
+            let word = variable_name(rest).unwrap_or_default();
+            tokens.push(match word {
+                "true" => Token::Value(Value::Bool(true)),
+                "false" => Token::Value(Value::Bool(false)),
+                word => Token::Word(word.to_string()),
+            });
+            rest = &rest[word.len()..];
+        } else {
+            return Err(format!("unexpected {:?}", ch));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over expression tokens, lowest precedence first.
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    state: &'a StoryState,
+}
+
+impl Parser<'_> {
+    fn peek_word(&self, word: &str) -> bool {
+        matches!(self.tokens.get(self.position), Some(Token::Word(w)) if w == word)
+    }
+
+    fn peek_symbol(&self) -> Option<&'static str> {
+        match self.tokens.get(self.position) {
+            Some(Token::Symbol(symbol)) => Some(symbol),
+            _ => None,
+        }
+    }
+
+    fn or(&mut self) -> Result<Value, String> {
+        let mut value = self.and()?;
+        while self.peek_word("or") {
+            self.position += 1;
+            let right = self.and()?;
+            value = Value::Bool(value.truthy() || right.truthy());
+        }
+        Ok(value)
+    }
+
+    fn and(&mut self) -> Result<Value, String> {
+        let mut value = self.not()?;
+        while self.peek_word("and") {
+            self.position += 1;
+            let right = self.not()?;
+            value = Value::Bool(value.truthy() && right.truthy());
+        }
+        Ok(value)
+    }
+
+    fn not(&mut self) -> Result<Value, String> {
+        if self.peek_word("not") {
+            self.position += 1;
+            return Ok(Value::Bool(!self.not()?.truthy()));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Value, String> {
+        let left = self.sum()?;
+        let operator = if self.peek_word("is") {
+            self.position += 1;
+            if self.peek_word("not") {
+                self.position += 1;
+                "is not"
+            } else {
+                "is"
+            }
+        } else if self.peek_word("contains") {
+            self.position += 1;
+            "contains"
+        } else if let Some(symbol @ ("<" | "<=" | ">" | ">=")) = self.peek_symbol() {
+            self.position += 1;
+            symbol
+        } else {
+            return Ok(left);
+        };
+        let right = self.sum()?;
+        let result = match operator {
+            "is" => left == right,
+            "is not" => left != right,
+            "contains" => left.to_string().contains(&right.to_string()),
+            _ => {
+                let (Value::Number(a), Value::Number(b)) = (&left, &right) else {
+                    return Err(format!("cannot compare {} and {}", left, right));
+                };
+                match operator {
+                    "<" => a < b,
+                    "<=" => a <= b,
+                    ">" => a > b,
+                    _ => a >= b,
+                }
+            }
+        };
+        Ok(Value::Bool(result))
+    }
+
+    fn sum(&mut self) -> Result<Value, String> {
+        let mut value = self.product()?;
+        while let Some(symbol @ ("+" | "-")) = self.peek_symbol() {
+            self.position += 1;
+            let right = self.product()?;
+            value = match (symbol, value, right) {
+                ("+", Value::Number(a), Value::Number(b)) => Value::Number(a + b),
+                ("-", Value::Number(a), Value::Number(b)) => Value::Number(a - b),
+                ("+", a, b) => Value::Text(format!("{}{}", a, b)),
+                (_, a, b) => return Err(format!("cannot subtract {} from {}", b, a)),
+            };
+        }
+        Ok(value)
+    }
+
+    fn product(&mut self) -> Result<Value, String> {
+        let mut value = self.atom()?;
+        while let Some(symbol @ ("*" | "/")) = self.peek_symbol() {
+            self.position += 1;
+            let right = self.atom()?;
+            let (Value::Number(a), Value::Number(b)) = (&value, &right) else {
+                return Err(format!("cannot use {} on {} and {}", symbol, value, right));
+            };
+            value = Value::Number(if symbol == "*" { a * b } else { a / b });
+        }
+        Ok(value)
+    }
+
+    fn atom(&mut self) -> Result<Value, String> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .ok_or("unexpected end of expression")?;
+        self.position += 1;
+        match token {
+            Token::Value(value) => Ok(value.clone()),
+            // Unset variables read as 0, as in Harlowe
+            Token::Variable(name) => {
+                Ok(self.state.get(name).cloned().unwrap_or(Value::Number(0.0)))
+            }
+            Token::Symbol("-") => match self.atom()? {
+                Value::Number(n) => Ok(Value::Number(-n)),
+                other => Err(format!("cannot negate {}", other)),
+            },
+            Token::Symbol("(") => {
+                let value = self.or()?;
+                if self.peek_symbol() != Some(")") {
+                    return Err("missing )".to_string());
+                }
+                self.position += 1;
+                Ok(value)
+            }
+            other => Err(format!("unexpected {:?}", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STORY: &str = r#":: StoryTitle
+The Cell
+
+:: StoryData
+{"ifid": "0A1B", "start": "Cell"}
+
+:: Cell [intro]
+(set: $gold to 3)
+You wake up in a ''cold'' cell. You have $gold coins.
+(if: $gold > 5)[You are rich.](else-if: $gold > 2)[A guard eyes your purse.](else:)[Nobody cares.]
+[[Bribe the guard->Guard]]
+[[Sleep]]
+
+:: Guard
+(set: $gold to $gold - 2)(set: $name to "Bram")
+(unless: $gold is 0)[$name takes two coins. You have (print: $gold) left.]
+[[Back to sleep|Sleep]]
+
+:: Sleep
+(if: $gold < 2 and not ($name is 0))[You dream of coins.]
+"#;
+
+    #[test]
+    fn test_story_runs_macros_and_links() {
+        let story = Story::parse(STORY).unwrap();
+        assert_eq!(story.start(), "Cell");
+        assert_eq!(story.passage("Cell").unwrap().tags, ["intro"]);
+
+        let mut state = StoryState::default();
+        let page = story.begin(&mut state).unwrap();
+        assert_eq!(
+            page.text,
+            "You wake up in a cold cell. You have 3 coins.\nA guard eyes your purse."
+        );
+        assert_eq!(
+            page.choices,
+            [
+                Choice {
+                    text: "Bribe the guard".into(),
+                    target: "Guard".into()
+                },
+                Choice {
+                    text: "Sleep".into(),
+                    target: "Sleep".into()
+                },
+            ]
+        );
+
+        let page = story.choose(&mut state, &page, 0).unwrap();
+        assert_eq!(page.text, "Bram takes two coins. You have 1 left.");
+        assert_eq!(page.choices[0].target, "Sleep");
+        let page = story.choose(&mut state, &page, 0).unwrap();
+        assert_eq!(page.text, "You dream of coins.");
+        assert!(page.choices.is_empty());
+        assert!(state.visited.contains("Guard"));
+        assert_eq!(state.get("gold"), Some(&Value::Number(1.0)));
+    }
+
+    #[test]
+    fn test_errors_name_the_passage() {
+        let story = Story::parse(":: Start\n(set: $x to 1 +)\n").unwrap();
+        let error = story.begin(&mut StoryState::default()).unwrap_err();
+        assert!(error.to_string().contains("\"Start\""), "{}", error);
+        assert!(Story::parse("no passages").is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! Fixed-point numbers for simulations that must give the same results on
+//! every machine.
+//!
+//! Lockstep multiplayer and replays only work if every peer computes the
+//! exact same state from the same inputs. Floating point functions such as
+//! `sin`, `cos` and `sqrt` may round differently between platforms, C
+//! libraries and compiler versions, so over many ticks floats drift apart.
+//! [`Fixed`] numbers use integer arithmetic only and come out bit for bit
+//! the same everywhere.
+//!
+//! With the `fixed` feature the [`steering`](crate::steering) module keeps
+//! its bodies' positions and velocities as `Fixed` and does all its math
+//! with them. Its API still takes and returns `f32`, converted at the edges,
+//! and the conversions are exact for values a game passes in, e.g. whole
+//! cells or speeds such as 2.5.
+//!
+//! ```
+//! use coil_engine::fixed::Fixed;
+//!
+//! let half = Fixed::from(0.5);
+//! assert_eq!(half * Fixed::from_int(3), Fixed::from(1.5));
+//! assert_eq!(f32::from(Fixed::from_int(16).sqrt()), 4.0);
+//! ```
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+const FRACTION_BITS: u32 = 16;
+const SCALE: f32 = (1 << FRACTION_BITS) as f32;
+
+/// A signed number with 16 fractional bits, i.e. steps of 1/65536.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fixed(i64);
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(1 << FRACTION_BITS);
+    /// The smallest positive number.
+    pub const EPSILON: Fixed = Fixed(1);
+    pub const MAX: Fixed = Fixed(i64::MAX);
+    pub const MIN: Fixed = Fixed(i64::MIN);
+    pub const PI: Fixed = Fixed(205_887);
+    pub const FRAC_PI_2: Fixed = Fixed(102_944);
+    pub const TAU: Fixed = Fixed(411_775);
+
+    /// Creates a number from its raw representation, `value / 65536`.
+    pub const fn from_bits(bits: i64) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the raw representation, e.g. for hashing game state.
+    pub const fn to_bits(self) -> i64 {
+        self.0
+    }
+
+    pub const fn from_int(value: i32) -> Self {
+        Self((value as i64) << FRACTION_BITS)
+    }
+
+    pub const fn abs(self) -> Self {
+        Self(self.0.saturating_abs())
+    }
+
+    pub fn max(self, other: Self) -> Self {
+        Ord::max(self, other)
+    }
+
+    pub fn min(self, other: Self) -> Self {
+        Ord::min(self, other)
+    }
+
+    /// Returns the square root, or zero for negative numbers.
+    pub fn sqrt(self) -> Self {
+        if self.0 <= 0 {
+            return Self::ZERO;
+        }
+        let root = ((self.0 as u128) << FRACTION_BITS).isqrt();
+        Self(root as i64)
+    }
+
+    /// Returns the sine of an angle in radians, to within about 1/65536.
+    pub fn sin(self) -> Self {
+        // Bring the angle into [-pi/2, pi/2], where the series converges fast
+        let mut x = Self(self.0.rem_euclid(Self::TAU.0));
+        if x > Self::PI {
+            x -= Self::TAU;
+        }
+        if x > Self::FRAC_PI_2 {
+            x = Self::PI - x;
+        } else if x < -Self::FRAC_PI_2 {
+            x = -Self::PI - x;
+        }
+        // x - x^3/3! + x^5/5! - x^7/7! + x^9/9!
+        let square = x * x;
+        let mut term = x;
+        let mut sum = x;
+        for divisor in [6, 20, 42, 72] {
+            term = -(term * square) / Self::from_int(divisor);
+            sum += term;
+        }
+        sum
+    }
+
+    /// Returns the cosine of an angle in radians, to within about 1/65536.
+    pub fn cos(self) -> Self {
+        (self + Self::FRAC_PI_2).sin()
+    }
+}
+
+/// Rounds to the nearest representable number; NaN becomes zero and
+/// numbers out of range saturate.
+impl From<f32> for Fixed {
+    fn from(value: f32) -> Self {
+        Self((value * SCALE).round() as i64)
+    }
+}
+
+impl From<Fixed> for f32 {
+    fn from(value: Fixed) -> Self {
+        value.0 as f32 / SCALE
+    }
+}
+
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&f32::from(*self), f)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let product = (self.0 as i128 * other.0 as i128) >> FRACTION_BITS;
+        Self(product.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+/// Division by zero saturates towards the sign of the dividend.
+impl Div for Fixed {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        if other.0 == 0 {
+            return match self.0 {
+                0 => Self::ZERO,
+                n if n > 0 => Self::MAX,
+                _ => Self::MIN,
+            };
+        }
+        let quotient = ((self.0 as i128) << FRACTION_BITS) / other.0 as i128;
+        Self(quotient.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(self.0.saturating_neg())
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Fixed {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl MulAssign for Fixed {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl DivAssign for Fixed {
+    fn div_assign(&mut self, other: Self) {
+        *self = *self / other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Fixed::from(2.25);
+        let b = Fixed::from(-1.5);
+        assert_eq!(f32::from(a + b), 0.75);
+        assert_eq!(f32::from(a * b), -3.375);
+        assert_eq!(f32::from(a / b), -1.5);
+        assert_eq!(Fixed::ONE / Fixed::ZERO, Fixed::MAX);
+        assert_eq!(Fixed::from(2.0).sqrt().to_bits(), 92_681);
+        assert_eq!(Fixed::from(-4.0).sqrt(), Fixed::ZERO);
+        assert_eq!(Fixed::from(f32::NAN), Fixed::ZERO);
+    }
+
+    #[test]
+    fn test_trigonometry() {
+        for step in -40..=40 {
+            let angle = step as f32 * 0.2;
+            let fixed = Fixed::from(angle);
+            let (sin, cos) = (f32::from(fixed.sin()), f32::from(fixed.cos()));
+            assert!((sin - angle.sin()).abs() < 1e-4, "sin {angle}: {sin}");
+            assert!((cos - angle.cos()).abs() < 1e-4, "cos {angle}: {cos}");
+        }
+    }
+}
@@ -0,0 +1,645 @@
+//! Scoped drawing surface handed to [`Node::render`](crate::nodes::Node::render).
+use crate::errors::EngineError;
+use crate::geometry::Rect;
+use crate::hit::{HitId, HitRegions};
+use crate::renderer::{Cell, Renderer};
+use crate::shapes::BorderSet;
+use crate::style::Style;
+use crate::text;
+use crossterm::style::Color;
+
+/// A view of the renderer limited to the area assigned to a node.
+///
+/// Coordinates are local to that area, so (0,0) is its top-left corner, and
+/// anything drawn outside of it is clipped instead of spilling over the
+/// neighbouring nodes.
+///
+/// A frame can also be [translated](Frame::translated), e.g. by a camera, in
+/// which case its local origin moves while the clipping area stays put.
+pub struct Frame<'a> {
+    renderer: &'a mut dyn Renderer,
+    /// Clipping area in renderer coordinates
+    area: Rect,
+    /// Renderer coordinates of local (0,0)
+    origin: (i32, i32),
+    alpha: f32,
+    /// Where [`Frame::hit_region`] records regions, if anywhere
+    hits: Option<&'a mut HitRegions>,
+}
+
+impl<'a> Frame<'a> {
+    /// Creates a frame drawing into `area` of the renderer.
+    ///
+    /// `alpha` is how far the game is between the last update and the next,
+    /// from 0.0 to 1.0, for interpolating positions while drawing.
+    pub fn new(renderer: &'a mut dyn Renderer, area: Rect, alpha: f32) -> Self {
+        Self {
+            renderer,
+            area,
+            origin: (area.x as i32, area.y as i32),
+            alpha: alpha.clamp(0.0, 1.0),
+            hits: None,
+        }
+    }
+
+    /// Records the regions registered with [`Frame::hit_region`] in `hits`.
+    pub fn with_hit_regions(mut self, hits: &'a mut HitRegions) -> Self {
+        self.hits = Some(hits);
+        self
+    }
+
+    /// Registers a local area as a mouse target, clipped like drawing.
+    ///
+    /// Mouse events over it are sent as [`EngineEvent::Hit`](crate::context::EngineEvent::Hit)
+    /// with positions relative to its top-left corner.
+    pub fn hit_region(&mut self, id: HitId, area: Rect) {
+        let child = self.child(area);
+        let (clip, origin) = (child.area, child.origin);
+        if let Some(hits) = self.hits.as_deref_mut() {
+            hits.push(id, clip, origin);
+        }
+    }
+
+    /// Attaches a tooltip to the hit region `id`, shown when the mouse rests
+    /// over it; see [`crate::tooltip`].
+    pub fn tooltip(&mut self, id: HitId, text: impl Into<String>) {
+        if let Some(hits) = self.hits.as_deref_mut() {
+            hits.push_tooltip(id, text.into());
+        }
+    }
+
+    /// Marks a local area as the widget with keyboard focus, with the
+    /// tooltip to show below it once it stays focused for a moment.
+    pub fn focus_tooltip(&mut self, area: Rect, text: impl Into<String>) {
+        let clip = self.child(area).area;
+        if let Some(hits) = self.hits.as_deref_mut() {
+            hits.set_focus_tooltip(clip, text.into());
+        }
+    }
+
+    /// Returns the assigned area in renderer coordinates.
+    pub fn area(&self) -> Rect {
+        self.area
+    }
+
+    /// Returns the size of the assigned area as `(width, height)`.
+    pub fn size(&self) -> (u16, u16) {
+        self.area.size()
+    }
+
+    /// Returns the interpolation alpha between the last two updates.
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    /// Returns a frame for a sub-area given in local coordinates.
+    ///
+    /// The sub-area is clipped to this frame, so children can never draw
+    /// outside their parent.
+    pub fn child(&mut self, area: Rect) -> Frame<'_> {
+        let x = self.origin.0 + area.x as i32;
+        let y = self.origin.1 + area.y as i32;
+        let clip = |start: i32, length: u16, min: u16, max: u16| {
+            let end = (start + length as i32).clamp(min as i32, max as i32);
+            let start = start.clamp(min as i32, max as i32);
+            (start as u16, (end - start) as u16)
+        };
+        let (clip_x, width) = clip(x, area.width, self.area.x, self.area.right());
+        let (clip_y, height) = clip(y, area.height, self.area.y, self.area.bottom());
+        Frame {
+            renderer: &mut *self.renderer,
+            area: Rect::new(clip_x, clip_y, width, height),
+            origin: (x, y),
+            alpha: self.alpha,
+            hits: self.hits.as_deref_mut(),
+        }
+    }
+
+    /// Returns a frame whose content is shifted by (dx,dy) within the same area.
+    ///
+    /// Drawing at local (0,0) lands at (dx,dy) of this frame, so a camera
+    /// looking at world position (cx,cy) translates by (-cx,-cy).
+    pub fn translated(&mut self, dx: i32, dy: i32) -> Frame<'_> {
+        Frame {
+            renderer: &mut *self.renderer,
+            area: self.area,
+            origin: (self.origin.0 + dx, self.origin.1 + dy),
+            alpha: self.alpha,
+            hits: self.hits.as_deref_mut(),
+        }
+    }
+
+    /// Runs `draw` with a frame whose cells pass through `filter` before
+    /// reaching the renderer, e.g. to tint the world layer.
+    pub fn filtered<R>(
+        &mut self,
+        filter: &dyn Fn(Cell) -> Cell,
+        draw: impl FnOnce(&mut Frame<'_>) -> R,
+    ) -> R {
+        let mut renderer = FilteredRenderer {
+            inner: &mut *self.renderer,
+            filter,
+        };
+        let mut frame = Frame {
+            renderer: &mut renderer,
+            area: self.area,
+            origin: self.origin,
+            alpha: self.alpha,
+            hits: self.hits.as_deref_mut(),
+        };
+        draw(&mut frame)
+    }
+
+    /// Runs `draw` and keeps what it drew, including hit regions and
+    /// tooltips, in `recording`, replacing what was recorded before.
+    pub fn record<R>(
+        &mut self,
+        recording: &mut Recording,
+        draw: impl FnOnce(&mut Frame<'_>) -> R,
+    ) -> R {
+        recording.ops.clear();
+        recording.hits.clear();
+        recording.placement = Some((self.area, self.origin));
+        let mut renderer = RecordingRenderer {
+            inner: &mut *self.renderer,
+            ops: &mut recording.ops,
+        };
+        let recorded_hits = self.hits.is_some().then_some(&mut recording.hits);
+        let mut frame = Frame {
+            renderer: &mut renderer,
+            area: self.area,
+            origin: self.origin,
+            alpha: self.alpha,
+            hits: recorded_hits,
+        };
+        let result = draw(&mut frame);
+        if let Some(hits) = self.hits.as_deref_mut() {
+            hits.extend(&recording.hits);
+        }
+        result
+    }
+
+    /// Draws a recording again, if it was made in a frame with the same
+    /// area and origin as this one.
+    ///
+    /// Returns `false`, without drawing anything, if it was not.
+    pub fn replay(&mut self, recording: &Recording) -> Result<bool, EngineError> {
+        if recording.placement != Some((self.area, self.origin)) {
+            return Ok(false);
+        }
+        for op in &recording.ops {
+            match op {
+                DrawOp::Clear => self.renderer.clear()?,
+                DrawOp::Cell(x, y, cell) => self.renderer.draw_cell(*x, *y, *cell)?,
+                DrawOp::Str(x, y, text, style) => {
+                    self.renderer.draw_styled(*x, *y, text, *style)?
+                }
+                DrawOp::Link(uri) => self.renderer.set_link(uri.as_deref()),
+            }
+        }
+        if let Some(hits) = self.hits.as_deref_mut() {
+            hits.extend(&recording.hits);
+        }
+        Ok(true)
+    }
+
+    /// Fills the assigned area with blank cells.
+    pub fn clear(&mut self) -> Result<(), EngineError> {
+        for y in self.area.y..self.area.bottom() {
+            for x in self.area.x..self.area.right() {
+                self.renderer.draw_cell(x, y, Cell::BLANK)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the renderer position of local (x,y) if it is inside the area.
+    fn to_renderer(&self, x: u16, y: u16) -> Option<(u16, u16)> {
+        let x = u16::try_from(self.origin.0 + x as i32).ok()?;
+        let y = u16::try_from(self.origin.1 + y as i32).ok()?;
+        self.area.contains(x, y).then_some((x, y))
+    }
+
+    /// Draw one cell at local (x,y); cells outside the area are skipped.
+    pub fn draw_cell(&mut self, x: u16, y: u16, cell: Cell) -> Result<(), EngineError> {
+        match self.to_renderer(x, y) {
+            Some((x, y)) => self.renderer.draw_cell(x, y, cell),
+            None => Ok(()),
+        }
+    }
+
+    /// Draw a string starting at local (x,y) in the given colors, like
+    /// [`Frame::draw_styled`].
+    #[deprecated(since = "0.1.0", note = "use `draw_styled` with a `Style`")]
+    pub fn draw_str(
+        &mut self,
+        x: u16,
+        y: u16,
+        text: &str,
+        fg: Color,
+        bg: Color,
+    ) -> Result<Rect, EngineError> {
+        self.draw_styled(x, y, text, Style::new().fg(fg).bg(bg))
+    }
+
+    /// Draw a string starting at local (x,y) with the given style, cut off
+    /// at the edges of the area.
+    ///
+    /// Returns the local rectangle the visible text occupies, which is empty
+    /// if nothing was drawn.
+    pub fn draw_styled(
+        &mut self,
+        x: u16,
+        y: u16,
+        text: &str,
+        style: Style,
+    ) -> Result<Rect, EngineError> {
+        let empty = Ok(Rect::new(x, y, 0, 0));
+        let row = self.origin.1 + y as i32;
+        if row < self.area.y as i32 || row >= self.area.bottom() as i32 {
+            return empty;
+        }
+        // Skip glyphs that start left of the area, then cut at its right edge
+        let mut column = self.origin.0 + x as i32;
+        let mut start = text.len();
+        for (index, ch) in text.char_indices() {
+            if column >= self.area.x as i32 {
+                start = index;
+                break;
+            }
+            column += text::char_width(ch) as i32;
+        }
+        if column >= self.area.right() as i32 {
+            return empty;
+        }
+        let visible = &text[start..];
+        let available = (self.area.right() as i32 - column) as u16;
+        let visible = &visible[..text::fit_width(visible, available)];
+        self.renderer
+            .draw_styled(column as u16, row as u16, visible, style)?;
+        let width = text::text_width(visible);
+        let local_x = (column - self.origin.0) as u16;
+        Ok(Rect::new(local_x, y, width, if width > 0 { 1 } else { 0 }))
+    }
+
+    /// Draw a string that links to `uri`, which supporting terminals make
+    /// clickable.
+    pub fn draw_link(
+        &mut self,
+        x: u16,
+        y: u16,
+        text: &str,
+        style: Style,
+        uri: &str,
+    ) -> Result<Rect, EngineError> {
+        self.renderer.set_link(Some(uri));
+        let drawn = self.draw_styled(x, y, text, style);
+        self.renderer.set_link(None);
+        drawn
+    }
+
+    /// Draw text in the local rectangle `area`, flowing through the layout's
+    /// columns from top to bottom and left to right.
+    ///
+    /// Returns `None` if all the text fit, or the byte offset in `text` where
+    /// the text that did not fit starts, so it can be shown on a next page
+    /// with `&text[offset..]`.
+    pub fn draw_text_layout(
+        &mut self,
+        area: Rect,
+        text: &str,
+        style: Style,
+        layout: text::TextLayout,
+    ) -> Result<Option<usize>, EngineError> {
+        let columns = layout.columns.max(1);
+        let gaps = layout.gap.saturating_mul(columns - 1);
+        let column_width = area.width.saturating_sub(gaps) / columns;
+        if column_width == 0 || area.height == 0 {
+            return Ok((!text.is_empty()).then_some(0));
+        }
+        let lines = text::wrap(text, column_width);
+        // Wrapped lines are slices of the text, so pointers give their offsets
+        let offset = |line: &str| line.as_ptr() as usize - text.as_ptr() as usize;
+        let capacity = columns as usize * area.height as usize;
+        for (index, line) in lines.iter().enumerate().take(capacity) {
+            let column = (index / area.height as usize) as u16;
+            let row = (index % area.height as usize) as u16;
+            let x = area.x + column * (column_width + layout.gap);
+            let ends_paragraph = lines
+                .get(index + 1)
+                .is_none_or(|next| text[offset(line) + line.len()..offset(next)].contains('\n'));
+            if layout.justify && !ends_paragraph {
+                self.draw_styled(x, area.y + row, &text::justify(line, column_width), style)?;
+            } else {
+                self.draw_styled(x, area.y + row, line, style)?;
+            }
+        }
+        Ok(lines.get(capacity).map(|line| offset(line)))
+    }
+
+    /// Draw text starting at local (x,y), wrapped to the right edge of the area.
+    ///
+    /// Lines past the bottom of the area are dropped. Returns the local
+    /// rectangle the drawn text occupies, as [`text::measure_text`] would.
+    pub fn draw_text(
+        &mut self,
+        x: u16,
+        y: u16,
+        text: &str,
+        style: Style,
+    ) -> Result<Rect, EngineError> {
+        let right = self.area.right() as i32 - self.origin.0;
+        let available = (right - x as i32).clamp(0, u16::MAX as i32) as u16;
+        let bottom = self.area.bottom() as i32 - self.origin.1;
+        let mut bounds = Rect::new(x, y, 0, 0);
+        for (row, line) in text::wrap(text, available).into_iter().enumerate() {
+            let Some(line_y) = y
+                .checked_add(row as u16)
+                .filter(|&line_y| (line_y as i32) < bottom)
+            else {
+                break;
+            };
+            let drawn = self.draw_styled(x, line_y, line, style)?;
+            bounds.width = bounds.width.max(drawn.width);
+            bounds.height = row as u16 + 1;
+        }
+        Ok(bounds)
+    }
+
+    /// Draw `width` copies of `cell` rightwards from local (x,y).
+    pub fn draw_hline(
+        &mut self,
+        x: u16,
+        y: u16,
+        width: u16,
+        cell: Cell,
+    ) -> Result<(), EngineError> {
+        for x in x..x.saturating_add(width) {
+            self.draw_cell(x, y, cell)?;
+        }
+        Ok(())
+    }
+
+    /// Draw `height` copies of `cell` downwards from local (x,y).
+    pub fn draw_vline(
+        &mut self,
+        x: u16,
+        y: u16,
+        height: u16,
+        cell: Cell,
+    ) -> Result<(), EngineError> {
+        for y in y..y.saturating_add(height) {
+            self.draw_cell(x, y, cell)?;
+        }
+        Ok(())
+    }
+
+    /// Draw a straight line of `cell` between two local points, both included.
+    pub fn draw_line(
+        &mut self,
+        from: (u16, u16),
+        to: (u16, u16),
+        cell: Cell,
+    ) -> Result<(), EngineError> {
+        // Bresenham's algorithm, stepping along whichever axis is longer
+        let (mut x, mut y) = (from.0 as i32, from.1 as i32);
+        let (dx, dy) = ((to.0 as i32 - x).abs(), -(to.1 as i32 - y).abs());
+        let (step_x, step_y) = ((to.0 as i32 - x).signum(), (to.1 as i32 - y).signum());
+        let mut error = dx + dy;
+        loop {
+            self.draw_cell(x as u16, y as u16, cell)?;
+            if (x, y) == (to.0 as i32, to.1 as i32) {
+                return Ok(());
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// Draw the outline of the local rectangle `area` with `cell`.
+    pub fn draw_rect(&mut self, area: Rect, cell: Cell) -> Result<(), EngineError> {
+        self.draw_border(area, BorderSet::uniform(cell.ch), cell.style())
+    }
+
+    /// Fill the local rectangle `area` with `cell`.
+    pub fn fill_rect(&mut self, area: Rect, cell: Cell) -> Result<(), EngineError> {
+        for y in area.y..area.bottom() {
+            self.draw_hline(area.x, y, area.width, cell)?;
+        }
+        Ok(())
+    }
+
+    /// Draw a border along the inside edges of the local rectangle `area`.
+    ///
+    /// What is inside the border is left as it is; [`Frame::child`] with the
+    /// area shrunk by one cell on each side draws there.
+    pub fn draw_border(
+        &mut self,
+        area: Rect,
+        border: BorderSet,
+        style: Style,
+    ) -> Result<(), EngineError> {
+        if area.is_empty() {
+            return Ok(());
+        }
+        let cell = |ch| Cell::styled(ch, style);
+        let (right, bottom) = (area.right() - 1, area.bottom() - 1);
+        let inner = (area.width.saturating_sub(2), area.height.saturating_sub(2));
+        self.draw_hline(area.x + 1, area.y, inner.0, cell(border.horizontal))?;
+        self.draw_hline(area.x + 1, bottom, inner.0, cell(border.horizontal))?;
+        self.draw_vline(area.x, area.y + 1, inner.1, cell(border.vertical))?;
+        self.draw_vline(right, area.y + 1, inner.1, cell(border.vertical))?;
+        self.draw_cell(area.x, area.y, cell(border.top_left))?;
+        self.draw_cell(right, area.y, cell(border.top_right))?;
+        self.draw_cell(area.x, bottom, cell(border.bottom_left))?;
+        self.draw_cell(right, bottom, cell(border.bottom_right))
+    }
+}
+
+/// What was drawn through [`Frame::record`], to draw again with [`Frame::replay`].
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    ops: Vec<DrawOp>,
+    hits: HitRegions,
+    /// Area and origin of the frame it was recorded in
+    placement: Option<(Rect, (i32, i32))>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets what was recorded, so it can no longer be replayed.
+    pub fn clear(&mut self) {
+        self.ops.clear();
+        self.hits.clear();
+        self.placement = None;
+    }
+}
+
+/// A renderer call, in renderer coordinates.
+#[derive(Debug, Clone)]
+enum DrawOp {
+    Clear,
+    Cell(u16, u16, Cell),
+    Str(u16, u16, String, Style),
+    Link(Option<String>),
+}
+
+/// Passes every call on to the renderer and keeps a copy of it.
+struct RecordingRenderer<'a> {
+    inner: &'a mut dyn Renderer,
+    ops: &'a mut Vec<DrawOp>,
+}
+
+impl Renderer for RecordingRenderer<'_> {
+    fn clear(&mut self) -> Result<(), EngineError> {
+        self.ops.push(DrawOp::Clear);
+        self.inner.clear()
+    }
+
+    fn draw_cell(&mut self, x: u16, y: u16, cell: Cell) -> Result<(), EngineError> {
+        self.ops.push(DrawOp::Cell(x, y, cell));
+        self.inner.draw_cell(x, y, cell)
+    }
+
+    fn draw_styled(&mut self, x: u16, y: u16, text: &str, style: Style) -> Result<(), EngineError> {
+        self.ops.push(DrawOp::Str(x, y, text.to_string(), style));
+        self.inner.draw_styled(x, y, text, style)
+    }
+
+    fn set_link(&mut self, uri: Option<&str>) {
+        self.ops.push(DrawOp::Link(uri.map(str::to_string)));
+        self.inner.set_link(uri);
+    }
+
+    fn flush(&mut self) -> Result<(), EngineError> {
+        self.inner.flush()
+    }
+}
+
+/// Passes every drawn cell through a filter on its way to the renderer.
+struct FilteredRenderer<'a> {
+    inner: &'a mut dyn Renderer,
+    filter: &'a dyn Fn(Cell) -> Cell,
+}
+
+impl Renderer for FilteredRenderer<'_> {
+    fn clear(&mut self) -> Result<(), EngineError> {
+        self.inner.clear()
+    }
+
+    fn draw_cell(&mut self, x: u16, y: u16, cell: Cell) -> Result<(), EngineError> {
+        self.inner.draw_cell(x, y, (self.filter)(cell))
+    }
+
+    fn draw_styled(&mut self, x: u16, y: u16, text: &str, style: Style) -> Result<(), EngineError> {
+        // Strings keep their glyph handling; only their style is filtered
+        let filtered = (self.filter)(Cell::styled(' ', style));
+        self.inner.draw_styled(x, y, text, filtered.style())
+    }
+
+    fn set_link(&mut self, uri: Option<&str>) {
+        self.inner.set_link(uri);
+    }
+
+    fn flush(&mut self) -> Result<(), EngineError> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::TestRenderer;
+
+    #[test]
+    fn test_frame_translates_and_clips() {
+        let mut renderer = TestRenderer::new(8, 3);
+        {
+            let mut frame = Frame::new(&mut renderer, Rect::new(2, 1, 4, 1), 0.5);
+            assert_eq!(frame.size(), (4, 1));
+            frame.draw_styled(1, 0, "abcdef", Style::new()).unwrap();
+            frame.draw_cell(0, 0, Cell::new('>')).unwrap();
+            frame.draw_cell(0, 1, Cell::new('!')).unwrap();
+            frame.draw_styled(9, 0, "far", Style::new()).unwrap();
+        }
+        assert_eq!(renderer.row(0), "");
+        assert_eq!(renderer.row(1), "  >abc");
+        assert_eq!(renderer.row(2), "");
+        assert!(renderer.take_errors().is_empty());
+    }
+
+    #[test]
+    fn test_text_bounds() {
+        let mut renderer = TestRenderer::new(8, 3);
+        {
+            let mut frame = Frame::new(&mut renderer, Rect::new(0, 0, 8, 3), 0.0);
+            let bounds = frame.draw_styled(6, 0, "日本", Style::new()).unwrap();
+            assert_eq!(bounds, Rect::new(6, 0, 2, 1));
+            let bounds = frame.draw_text(1, 1, "ab cdef ghij", Style::new()).unwrap();
+            assert_eq!(bounds, Rect::new(1, 1, 7, 2));
+        }
+        assert_eq!(renderer.row(0), "      日");
+        assert_eq!(renderer.cell_at(7, 0).unwrap().ch, Cell::WIDE_TAIL);
+        assert_eq!(renderer.row(1), " ab cdef");
+        assert_eq!(renderer.row(2), " ghij");
+        assert!(renderer.take_errors().is_empty());
+    }
+
+    #[test]
+    fn test_text_layout_flows_through_columns() {
+        let text = "aa bb cc dd ee ff gg\nhi jj kk ll";
+        let mut renderer = TestRenderer::new(15, 2);
+        let rest = Frame::new(&mut renderer, Rect::new(0, 0, 15, 2), 0.0)
+            .draw_text_layout(
+                Rect::new(0, 0, 15, 2),
+                text,
+                Style::new(),
+                text::TextLayout::new().with_columns(2, 1).justified(),
+            )
+            .unwrap();
+        assert_eq!(renderer.row(0), "aa   bb ee   ff");
+        assert_eq!(renderer.row(1), "cc   dd gg");
+        assert_eq!(rest.map(|offset| &text[offset..]), Some("hi jj kk ll"));
+    }
+
+    #[test]
+    fn test_translated_frames_clip_on_all_sides() {
+        let mut renderer = TestRenderer::new(8, 2);
+        {
+            let mut frame = Frame::new(&mut renderer, Rect::new(1, 0, 6, 2), 0.0);
+            let mut world = frame.translated(-3, 1);
+            let bounds = world.draw_styled(1, 0, "abcdefghij", Style::new()).unwrap();
+            assert_eq!(bounds, Rect::new(3, 0, 6, 1));
+            world.draw_cell(3, 0, Cell::new('!')).unwrap();
+            world.draw_cell(2, 1, Cell::new('?')).unwrap();
+            let mut child = world.child(Rect::new(2, 0, 3, 3));
+            assert_eq!(child.area(), Rect::new(1, 1, 2, 1));
+            child.draw_cell(2, 0, Cell::new('#')).unwrap();
+        }
+        assert_eq!(renderer.row(0), "");
+        assert_eq!(renderer.row(1), " !#efgh");
+    }
+
+    #[test]
+    fn test_child_frames_stay_inside_parent() {
+        let mut renderer = TestRenderer::new(8, 2);
+        {
+            let mut frame = Frame::new(&mut renderer, Rect::new(1, 0, 5, 2), 2.0);
+            assert_eq!(frame.alpha(), 1.0);
+            let mut child = frame.child(Rect::new(3, 1, 10, 10));
+            assert_eq!(child.area(), Rect::new(4, 1, 2, 1));
+            child.draw_styled(0, 0, "xyz", Style::new()).unwrap();
+        }
+        assert_eq!(renderer.row(1), "    xy");
+    }
+}
@@ -0,0 +1,86 @@
+//! Basic geometry types shared across the engine.
+
+/// An axis-aligned rectangle in terminal cell coordinates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Rect {
+    /// Creates a rectangle from its top-left corner and size.
+    pub const fn new(x: u16, y: u16, width: u16, height: u16) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the size as `(width, height)`.
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    /// Returns the column just past the right edge.
+    pub fn right(&self) -> u16 {
+        self.x.saturating_add(self.width)
+    }
+
+    /// Returns the row just past the bottom edge.
+    pub fn bottom(&self) -> u16 {
+        self.y.saturating_add(self.height)
+    }
+
+    /// Returns whether the rectangle covers no cells.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Returns whether the cell at (x,y) lies inside the rectangle.
+    pub fn contains(&self, x: u16, y: u16) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    /// Returns the area covered by both rectangles, empty if they do not overlap.
+    pub fn intersection(&self, other: Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        Rect::new(x, y, right.saturating_sub(x), bottom.saturating_sub(y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rect_edges_and_contains() {
+        let rect = Rect::new(2, 3, 4, 5);
+        assert_eq!(rect.right(), 6);
+        assert_eq!(rect.bottom(), 8);
+        assert!(rect.contains(2, 3));
+        assert!(rect.contains(5, 7));
+        assert!(!rect.contains(6, 7));
+        assert!(!rect.contains(1, 3));
+        assert!(!rect.is_empty());
+        assert!(Rect::new(0, 0, 0, 3).is_empty());
+    }
+
+    #[test]
+    fn test_rect_intersection() {
+        let rect = Rect::new(2, 3, 4, 5);
+        assert_eq!(
+            rect.intersection(Rect::new(4, 0, 10, 5)),
+            Rect::new(4, 3, 2, 2)
+        );
+        assert_eq!(rect.intersection(rect), rect);
+        assert!(rect.intersection(Rect::new(10, 10, 2, 2)).is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+//! Terminal glyph support and ASCII fallbacks.
+//!
+//! Box-drawing, block and braille characters render as tofu on terminals or
+//! fonts without Unicode support. The `glyphs` setting decides whether the
+//! renderer replaces them with ASCII approximations; by default the
+//! terminal's locale is probed for UTF-8.
+use crate::errors::EngineError;
+use crate::renderer::Cell;
+use std::fmt;
+
+/// Which characters the terminal can display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GlyphSupport {
+    /// Probe the terminal's locale.
+    #[default]
+    Auto,
+    /// Draw every character as is.
+    Unicode,
+    /// Replace box-drawing, block and braille characters with ASCII.
+    Ascii,
+}
+
+impl GlyphSupport {
+    /// Returns whether Unicode glyphs should be drawn, probing the locale for [`GlyphSupport::Auto`].
+    pub fn unicode(self) -> bool {
+        match self {
+            GlyphSupport::Auto => probe_unicode(|name| std::env::var(name).ok()),
+            GlyphSupport::Unicode => true,
+            GlyphSupport::Ascii => false,
+        }
+    }
+}
+
+impl std::str::FromStr for GlyphSupport {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(GlyphSupport::Auto),
+            "unicode" => Ok(GlyphSupport::Unicode),
+            "ascii" => Ok(GlyphSupport::Ascii),
+            _ => Err(EngineError::Config(format!(
+                "Unknown glyph support: {:?}",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for GlyphSupport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GlyphSupport::Auto => "auto",
+            GlyphSupport::Unicode => "unicode",
+            GlyphSupport::Ascii => "ascii",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Returns whether the locale in the environment uses UTF-8.
+///
+/// Follows the POSIX precedence of `LC_ALL`, `LC_CTYPE` and `LANG`. Windows
+/// terminals are assumed to support Unicode when no locale is set.
+fn probe_unicode(var: impl Fn(&str) -> Option<String>) -> bool {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .filter_map(var)
+        .find(|value| !value.is_empty());
+    match locale {
+        Some(locale) => {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        }
+        None => cfg!(windows),
+    }
+}
+
+/// Returns the ASCII approximation of a box-drawing, block, braille or
+/// common symbol character, or `None` if it has none.
+pub fn ascii_fallback(ch: char) -> Option<char> {
+    let fallback = match ch {
+        // Box drawing
+        '─' | '━' | '═' | '┄' | '┅' | '┈' | '┉' | '╌' | '╍' => '-',
+        '╴' | '╶' | '╸' | '╺' | '╼' | '╾' => '-',
+        '│' | '┃' | '║' | '┆' | '┇' | '┊' | '┋' | '╎' | '╏' => '|',
+        '╵' | '╷' | '╹' | '╻' | '╽' | '╿' => '|',
+        '╱' => '/',
+        '╲' => '\\',
+        '╳' => 'X',
+        '\u{2500}'..='\u{257f}' => '+',
+        // Block elements
+        '▀' => '"',
+        '▄' | '▁' | '▂' | '▃' => '_',
+        '░' => '.',
+        '▒' => ':',
+        '\u{2580}'..='\u{259f}' => '#',
+        // Braille, by how many of the eight dots are raised
+        '\u{2800}'..='\u{28ff}' => match (ch as u32 - 0x2800).count_ones() {
+            0 => ' ',
+            1..=2 => '.',
+            3..=5 => ':',
+            _ => '#',
+        },
+        // Common symbols
+        '←' => '<',
+        '→' => '>',
+        '↑' => '^',
+        '↓' => 'v',
+        '•' | '●' | '◆' | '■' => '*',
+        '·' | '…' => '.',
+        '○' | '◯' | '□' => 'o',
+        _ => return None,
+    };
+    Some(fallback)
+}
+
+/// Renderer filter replacing characters with their [`ascii_fallback`].
+pub fn ascii(cell: Cell) -> Cell {
+    match ascii_fallback(cell.ch) {
+        Some(ch) => Cell { ch, ..cell },
+        None => cell,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_fallback() {
+        let frame: String = "┌─┐│╰╯▐█▌⠀⠃⡇⣿←x"
+            .chars()
+            .map(|ch| ascii_fallback(ch).unwrap_or(ch))
+            .collect();
+        assert_eq!(frame, "+-+|++### .:#<x");
+        assert_eq!(
+            ascii(Cell::styled('━', crate::style!(red))),
+            Cell::styled('-', crate::style!(red))
+        );
+    }
+
+    #[test]
+    fn test_locale_probe() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert!(probe_unicode(env(&[("LANG", "en_US.UTF-8")])));
+        assert!(!probe_unicode(env(&[
+            ("LC_ALL", "C"),
+            ("LANG", "en_US.UTF-8")
+        ])));
+        assert!(probe_unicode(env(&[
+            ("LC_ALL", ""),
+            ("LC_CTYPE", "C.utf8")
+        ])));
+        assert_eq!(probe_unicode(env(&[])), cfg!(windows));
+        assert!(!GlyphSupport::Ascii.unicode());
+    }
+}
@@ -0,0 +1,217 @@
+//! Hexagonal grid coordinates.
+//!
+//! Hexes use axial coordinates `(q, r)`, where `q` grows to the east and `r`
+//! to the south-east. Pointy-top hex grids map onto terminal rows by shifting
+//! every odd row half a hex to the right ("odd-r" offset coordinates), with
+//! each hex two columns wide:
+//!
+//! ```text
+//! a b c d
+//!  e f g h
+//! i j k l
+//! ```
+//!
+//! Besides distances and neighbors, hexes support straight lines, rings and
+//! a field of view; [`TileMap::draw_hex`](crate::tilemap::TileMap::draw_hex)
+//! draws a tile map in this layout.
+use std::collections::BTreeSet;
+use std::ops::{Add, Sub};
+
+/// A hex in axial coordinates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hex {
+    pub q: i32,
+    pub r: i32,
+}
+
+impl Hex {
+    /// The six neighbor directions, clockwise from east.
+    pub const DIRECTIONS: [Hex; 6] = [
+        Hex::new(1, 0),
+        Hex::new(0, 1),
+        Hex::new(-1, 1),
+        Hex::new(-1, 0),
+        Hex::new(0, -1),
+        Hex::new(1, -1),
+    ];
+
+    pub const fn new(q: i32, r: i32) -> Self {
+        Self { q, r }
+    }
+
+    /// Returns the third cube coordinate, so that `q + r + s == 0`.
+    pub const fn s(&self) -> i32 {
+        -self.q - self.r
+    }
+
+    /// Returns the six adjacent hexes, clockwise from east.
+    pub fn neighbors(&self) -> [Hex; 6] {
+        Self::DIRECTIONS.map(|direction| *self + direction)
+    }
+
+    /// Returns the number of steps between two hexes.
+    pub fn distance(&self, other: Hex) -> u32 {
+        let d = *self - other;
+        (d.q.unsigned_abs() + d.r.unsigned_abs() + d.s().unsigned_abs()) / 2
+    }
+
+    /// Returns the hexes on a straight line from `self` to `other`, both included.
+    pub fn line(&self, other: Hex) -> Vec<Hex> {
+        self.nudged_line(other, 1e-6)
+    }
+
+    /// Draws a line shifted by `nudge`, which decides on which side of a
+    /// hex edge the line passes when it runs exactly along it.
+    fn nudged_line(&self, other: Hex, nudge: f32) -> Vec<Hex> {
+        let steps = self.distance(other);
+        if steps == 0 {
+            return vec![*self];
+        }
+        let (aq, ar) = (self.q as f32 + nudge, self.r as f32 + nudge);
+        let (bq, br) = (other.q as f32 + nudge, other.r as f32 + nudge);
+        (0..=steps)
+            .map(|step| {
+                let t = step as f32 / steps as f32;
+                Hex::round(aq + (bq - aq) * t, ar + (br - ar) * t)
+            })
+            .collect()
+    }
+
+    /// Returns the hex containing fractional axial coordinates.
+    pub fn round(q: f32, r: f32) -> Self {
+        let s = -q - r;
+        let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+        // Recompute the coordinate that rounded furthest from the others
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        }
+        Self::new(rq as i32, rr as i32)
+    }
+
+    /// Returns the hexes exactly `radius` steps away, clockwise.
+    pub fn ring(&self, radius: u32) -> Vec<Hex> {
+        if radius == 0 {
+            return vec![*self];
+        }
+        let mut hex = *self + Hex::new(0, -(radius as i32));
+        let mut ring = Vec::with_capacity(6 * radius as usize);
+        for direction in Self::DIRECTIONS {
+            for _ in 0..radius {
+                ring.push(hex);
+                hex = hex + direction;
+            }
+        }
+        ring
+    }
+
+    /// Returns every hex within `radius` steps, from the center outwards.
+    pub fn spiral(&self, radius: u32) -> Vec<Hex> {
+        (0..=radius).flat_map(|r| self.ring(r)).collect()
+    }
+
+    /// Returns the hexes visible from `self` within `radius` steps.
+    ///
+    /// A hex is visible when no hex on the line to it, other than the hex
+    /// itself, is opaque, so walls are seen but not seen through. Lines that
+    /// run along an edge between two hexes may pass on either side.
+    pub fn field_of_view(&self, radius: u32, opaque: impl Fn(Hex) -> bool) -> BTreeSet<Hex> {
+        self.spiral(radius)
+            .into_iter()
+            .filter(|target| {
+                [1e-6, -1e-6].iter().any(|nudge| {
+                    let line = self.nudged_line(*target, *nudge);
+                    let between = line.len().saturating_sub(2);
+                    line.iter().skip(1).take(between).all(|hex| !opaque(*hex))
+                })
+            })
+            .collect()
+    }
+
+    /// Converts "odd-r" offset coordinates (column, row) to axial.
+    pub fn from_offset(col: i32, row: i32) -> Self {
+        Self::new(col - (row - (row & 1)) / 2, row)
+    }
+
+    /// Converts to "odd-r" offset coordinates (column, row).
+    pub fn to_offset(&self) -> (i32, i32) {
+        (self.q + (self.r - (self.r & 1)) / 2, self.r)
+    }
+
+    /// Returns the terminal cell the hex is drawn at, two columns per hex
+    /// with odd rows shifted by one column.
+    pub fn to_cell(&self) -> (i32, i32) {
+        let (col, row) = self.to_offset();
+        (col * 2 + (row & 1), row)
+    }
+
+    /// Returns the hex drawn at a terminal cell, the inverse of [`Hex::to_cell`].
+    pub fn from_cell(x: i32, y: i32) -> Self {
+        Self::from_offset((x - (y & 1)).div_euclid(2), y)
+    }
+}
+
+impl Add for Hex {
+    type Output = Hex;
+
+    fn add(self, other: Hex) -> Hex {
+        Hex::new(self.q + other.q, self.r + other.r)
+    }
+}
+
+impl Sub for Hex {
+    type Output = Hex;
+
+    fn sub(self, other: Hex) -> Hex {
+        Hex::new(self.q - other.q, self.r - other.r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_and_cell_conversions() {
+        for row in -3..4 {
+            for col in -3..4 {
+                let hex = Hex::from_offset(col, row);
+                assert_eq!(hex.to_offset(), (col, row));
+                let (x, y) = hex.to_cell();
+                assert_eq!(Hex::from_cell(x, y), hex);
+                assert_eq!(Hex::from_cell(x + 1, y), hex);
+            }
+        }
+        // Odd rows are shifted right by one column
+        assert_eq!(Hex::from_offset(0, 1).to_cell(), (1, 1));
+
+        let origin = Hex::new(0, 0);
+        assert!(origin.neighbors().iter().all(|n| origin.distance(*n) == 1));
+        assert_eq!(origin.distance(Hex::new(3, -1)), 3);
+        assert_eq!(Hex::new(2, -5).s(), 3);
+    }
+
+    #[test]
+    fn test_lines_rings_and_field_of_view() {
+        let origin = Hex::new(0, 0);
+        let line = origin.line(Hex::new(3, -1));
+        assert_eq!(line.len(), 4);
+        assert_eq!(line.last(), Some(&Hex::new(3, -1)));
+        assert!(line.windows(2).all(|pair| pair[0].distance(pair[1]) == 1));
+
+        assert_eq!(origin.ring(2).len(), 12);
+        assert!(origin.ring(2).iter().all(|hex| origin.distance(*hex) == 2));
+        assert_eq!(origin.spiral(2).len(), 19);
+
+        // A wall east of the origin hides the hex behind it, but not itself
+        let wall = Hex::new(1, 0);
+        let visible = origin.field_of_view(2, |hex| hex == wall);
+        assert!(visible.contains(&wall));
+        assert!(!visible.contains(&Hex::new(2, 0)));
+        assert!(visible.contains(&Hex::new(0, 2)));
+        assert_eq!(visible.len(), 18);
+    }
+}
@@ -0,0 +1,159 @@
+//! Mouse hit-testing.
+//!
+//! While rendering, nodes register the areas they respond to with
+//! [`Frame::hit_region`](crate::frame::Frame::hit_region). The engine keeps
+//! the regions of the last frame and, for every mouse event, sends an
+//! [`EngineEvent::Hit`](crate::context::EngineEvent::Hit) naming the topmost
+//! region under the cursor with the cursor position local to that region.
+//! Regions registered later (drawn on top) win.
+use crate::geometry::Rect;
+use crossterm::event::MouseEvent;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Identifies a hit region; nodes usually create one in their constructor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HitId(u64);
+
+impl HitId {
+    /// Returns an id distinct from every other id created in this process.
+    pub fn new() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Default for HitId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A mouse event over a registered region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hit {
+    pub id: HitId,
+    /// Cursor column relative to the region's top-left corner
+    pub x: u16,
+    /// Cursor row relative to the region's top-left corner
+    pub y: u16,
+    /// The event, with `column`/`row` in renderer coordinates
+    pub event: MouseEvent,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Region {
+    id: HitId,
+    /// Visible part of the region in renderer coordinates
+    clip: Rect,
+    /// Renderer coordinates of the region's top-left corner, which may be off screen
+    origin: (i32, i32),
+}
+
+/// The hit regions registered while rendering a frame, bottom to top.
+#[derive(Debug, Clone, Default)]
+pub struct HitRegions {
+    regions: Vec<Region>,
+    /// Tooltip texts by region, see [`crate::tooltip`]
+    tooltips: Vec<(HitId, String)>,
+    /// Area and tooltip text of the focused widget
+    focus: Option<(Rect, String)>,
+}
+
+impl HitRegions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.regions.clear();
+        self.tooltips.clear();
+        self.focus = None;
+    }
+
+    pub(crate) fn push(&mut self, id: HitId, clip: Rect, origin: (i32, i32)) {
+        if clip.width > 0 && clip.height > 0 {
+            self.regions.push(Region { id, clip, origin });
+        }
+    }
+
+    pub(crate) fn push_tooltip(&mut self, id: HitId, text: String) {
+        match self.tooltips.iter_mut().find(|(region, _)| *region == id) {
+            Some((_, tooltip)) => *tooltip = text,
+            None => self.tooltips.push((id, text)),
+        }
+    }
+
+    pub(crate) fn set_focus_tooltip(&mut self, area: Rect, text: String) {
+        self.focus = Some((area, text));
+    }
+
+    /// Adds the regions and tooltips of `other` on top of these.
+    pub(crate) fn extend(&mut self, other: &HitRegions) {
+        self.regions.extend_from_slice(&other.regions);
+        for (id, text) in &other.tooltips {
+            self.push_tooltip(*id, text.clone());
+        }
+        if let Some(focus) = &other.focus {
+            self.focus = Some(focus.clone());
+        }
+    }
+
+    /// Returns the tooltip text attached to region `id`, if any.
+    pub fn tooltip(&self, id: HitId) -> Option<&str> {
+        self.tooltips
+            .iter()
+            .find(|(region, _)| *region == id)
+            .map(|(_, text)| text.as_str())
+    }
+
+    /// Returns the focused widget's area in renderer coordinates and its tooltip text.
+    pub fn focus_tooltip(&self) -> Option<(Rect, &str)> {
+        self.focus
+            .as_ref()
+            .map(|(area, text)| (*area, text.as_str()))
+    }
+
+    /// Returns the topmost region at renderer position (x,y) and the
+    /// position relative to that region.
+    pub fn hit_test(&self, x: u16, y: u16) -> Option<(HitId, u16, u16)> {
+        let region = self.regions.iter().rev().find(|r| r.clip.contains(x, y))?;
+        Some((
+            region.id,
+            (x as i32 - region.origin.0) as u16,
+            (y as i32 - region.origin.1) as u16,
+        ))
+    }
+
+    /// Hit-tests a mouse event given in renderer coordinates.
+    pub fn hit(&self, event: MouseEvent) -> Option<Hit> {
+        let (id, x, y) = self.hit_test(event.column, event.row)?;
+        Some(Hit { id, x, y, event })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::Frame;
+    use crate::renderer::BasicRenderer;
+
+    #[test]
+    fn test_topmost_region_wins() {
+        let (board, button) = (HitId::new(), HitId::new());
+        let mut renderer = BasicRenderer::new(20, 10).unwrap();
+        let mut regions = HitRegions::new();
+        {
+            let mut frame = Frame::new(&mut renderer, Rect::new(0, 0, 20, 10), 0.0)
+                .with_hit_regions(&mut regions);
+            let mut world = frame.translated(-5, 0);
+            world.hit_region(board, Rect::new(5, 2, 10, 5));
+            let mut hud = frame.child(Rect::new(2, 3, 4, 1));
+            hud.hit_region(button, Rect::new(0, 0, 4, 1));
+        }
+
+        assert_eq!(regions.hit_test(0, 2), Some((board, 0, 0)));
+        assert_eq!(regions.hit_test(4, 4), Some((board, 4, 2)));
+        assert_eq!(regions.hit_test(3, 3), Some((button, 1, 0)));
+        assert_eq!(regions.hit_test(12, 2), None);
+    }
+}
@@ -1,13 +1,11 @@
 use crate::errors::EngineError;
-use crossterm::{
-    event::{self, Event, poll},
-    terminal::{disable_raw_mode, enable_raw_mode},
-};
-use std::time::Duration;
+use crossterm::event::{self, Event, poll};
+use std::time::{Duration, Instant};
 
 use std::collections::VecDeque;
 
 #[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Defines how input events should be handled in the engine.
 pub enum InputStrategy {
     /// Non-blocking: grab every event currently queued, then sleep to cap FPS.
@@ -32,17 +30,32 @@ impl InputStrategy {
 
 pub(crate) struct InputHandler {
     queue: VecDeque<Event>,
+    enabled: bool,
 }
 
 impl InputHandler {
     pub fn new() -> Result<Self, EngineError> {
-        enable_raw_mode().map_err(|e| EngineError::Input(e.to_string()))?;
         Ok(Self {
             queue: VecDeque::new(),
+            enabled: true,
         })
     }
 
+    /// Creates a handler that never reads input, for headless sessions.
+    ///
+    /// Polling still waits for the timeout so frames keep their pacing.
+    pub fn disabled() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            enabled: false,
+        }
+    }
+
     pub fn poll(&mut self, timeout: Duration) -> Result<(), EngineError> {
+        if !self.enabled {
+            std::thread::sleep(timeout);
+            return Ok(());
+        }
         while poll(timeout)? {
             if let Ok(event) = event::read() {
                 self.queue.push_back(event);
@@ -51,16 +64,47 @@ impl InputHandler {
         Ok(())
     }
 
-    pub fn drain(&mut self) -> Vec<Event> {
-        self.queue.drain(..).collect()
+    /// Moves the queued events to the end of `events`.
+    pub fn drain_into(&mut self, events: &mut Vec<Event>) {
+        events.extend(self.queue.drain(..));
     }
 }
 
-impl Drop for InputHandler {
-    fn drop(&mut self) {
-        disable_raw_mode().unwrap_or_else(|e| {
-            eprintln!("Failed to disable raw mode: {}", e);
-        });
+/// Tracks time since the last player input to detect idleness.
+pub(crate) struct IdleDetector {
+    last_activity: Instant,
+    idle: bool,
+}
+
+impl IdleDetector {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            last_activity: now,
+            idle: false,
+        }
+    }
+
+    /// Records player input, returning `true` if it ended an idle period.
+    pub fn activity(&mut self, now: Instant) -> bool {
+        self.last_activity = now;
+        std::mem::take(&mut self.idle)
+    }
+
+    /// Returns `true` once, when no input has arrived for `timeout`.
+    pub fn check(&mut self, now: Instant, timeout: Option<Duration>) -> bool {
+        let Some(timeout) = timeout else {
+            return false;
+        };
+        if self.idle || now.duration_since(self.last_activity) < timeout {
+            return false;
+        }
+        self.idle = true;
+        true
+    }
+
+    /// Returns whether input counts as player activity.
+    pub fn is_activity(event: &Event) -> bool {
+        matches!(event, Event::Key(_) | Event::Mouse(_) | Event::Paste(_))
     }
 }
 
@@ -125,6 +169,20 @@ mod tests {
         assert_eq!(long_timeout.as_millis(), 100);
     }
 
+    #[test]
+    fn test_idle_detector() {
+        let start = Instant::now();
+        let timeout = Some(Duration::from_secs(30));
+        let mut idle = IdleDetector::new(start);
+
+        assert!(!idle.check(start + Duration::from_secs(29), timeout));
+        assert!(idle.check(start + Duration::from_secs(30), timeout));
+        assert!(!idle.check(start + Duration::from_secs(40), timeout));
+        assert!(idle.activity(start + Duration::from_secs(41)));
+        assert!(!idle.activity(start + Duration::from_secs(42)));
+        assert!(!idle.check(start + Duration::from_secs(100), None));
+    }
+
     #[test]
     fn test_event_matching() {
         use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
@@ -0,0 +1,383 @@
+//! Slot-based inventories with stacking items.
+//!
+//! An [`ItemCatalog`] describes every kind of item a game has: its name,
+//! description, glyph and how many fit on one stack. An [`Inventory`] is a
+//! fixed number of slots, each empty or holding a [`Stack`] of one kind of
+//! item. Adding items tops up existing stacks before filling empty slots,
+//! and moving a stack onto another merges them when they hold the same item
+//! and swaps them otherwise.
+//!
+//! Inventories save as one line per filled slot, `slot item count`, and are
+//! shown with the [`InventoryGrid`](crate::nodes::InventoryGrid) node.
+//!
+//! ```
+//! use coil_engine::inventory::{Inventory, ItemCatalog, ItemInfo};
+//! use std::rc::Rc;
+//!
+//! let catalog = ItemCatalog::new().with(ItemInfo::new("arrow", "Arrow", '↑').with_max_stack(20));
+//! let mut quiver = Inventory::new(Rc::new(catalog), 2);
+//! assert_eq!(quiver.add("arrow", 50).unwrap(), 10);
+//! assert_eq!(quiver.count("arrow"), 40);
+//! ```
+use crate::autosave::Saveable;
+use crate::errors::EngineError;
+use crate::style::Style;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+
+/// An inventory shared between the game and the nodes showing it.
+pub type SharedInventory = Rc<RefCell<Inventory>>;
+
+/// What a kind of item is and how it stacks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemInfo {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub glyph: char,
+    pub style: Style,
+    /// Most items of this kind one slot holds
+    pub max_stack: u32,
+}
+
+impl ItemInfo {
+    /// Describes an item that does not stack.
+    pub fn new(id: impl Into<String>, name: impl Into<String>, glyph: char) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            description: String::new(),
+            glyph,
+            style: Style::new(),
+            max_stack: 1,
+        }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_max_stack(mut self, max_stack: u32) -> Self {
+        self.max_stack = max_stack.max(1);
+        self
+    }
+}
+
+/// Every kind of item in a game, by id.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemCatalog {
+    items: BTreeMap<String, ItemInfo>,
+}
+
+impl ItemCatalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a kind of item and returns `self`, so you can chain.
+    pub fn with(mut self, item: ItemInfo) -> Self {
+        self.insert(item);
+        self
+    }
+
+    /// Adds a kind of item, replacing one with the same id.
+    pub fn insert(&mut self, item: ItemInfo) {
+        self.items.insert(item.id.clone(), item);
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ItemInfo> {
+        self.items.get(id)
+    }
+
+    fn require(&self, id: &str) -> Result<&ItemInfo, EngineError> {
+        self.get(id)
+            .ok_or_else(|| EngineError::game(format!("Unknown item {:?}", id)))
+    }
+}
+
+/// Some items of one kind in a slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stack {
+    pub item: String,
+    pub count: u32,
+}
+
+impl Stack {
+    pub fn new(item: impl Into<String>, count: u32) -> Self {
+        Self {
+            item: item.into(),
+            count,
+        }
+    }
+}
+
+/// A fixed number of slots holding stacks of items.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inventory {
+    catalog: Rc<ItemCatalog>,
+    slots: Vec<Option<Stack>>,
+}
+
+impl Inventory {
+    /// Creates an empty inventory with `size` slots.
+    pub fn new(catalog: Rc<ItemCatalog>, size: usize) -> Self {
+        Self {
+            catalog,
+            slots: vec![None; size],
+        }
+    }
+
+    /// Restores an inventory saved in the text format.
+    pub fn parse(catalog: Rc<ItemCatalog>, size: usize, text: &str) -> Result<Self, EngineError> {
+        let mut inventory = Self::new(catalog, size);
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid =
+                || EngineError::game(format!("Invalid inventory line {}: {:?}", number + 1, line));
+            let mut parts = line.split_whitespace();
+            let (Some(slot), Some(item), Some(count), None) =
+                (parts.next(), parts.next(), parts.next(), parts.next())
+            else {
+                return Err(invalid());
+            };
+            let slot: usize = slot.parse().map_err(|_| invalid())?;
+            let count: u32 = count.parse().map_err(|_| invalid())?;
+            if slot >= size || inventory.slots[slot].is_some() {
+                return Err(invalid());
+            }
+            let max = inventory.catalog.require(item)?.max_stack;
+            inventory.slots[slot] = Some(Stack::new(item, count.min(max)));
+        }
+        Ok(inventory)
+    }
+
+    pub fn catalog(&self) -> &ItemCatalog {
+        &self.catalog
+    }
+
+    /// Returns the number of slots.
+    pub fn size(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the stack in a slot.
+    pub fn slot(&self, slot: usize) -> Option<&Stack> {
+        self.slots.get(slot)?.as_ref()
+    }
+
+    /// Returns what the item in a slot is.
+    pub fn info(&self, slot: usize) -> Option<&ItemInfo> {
+        self.catalog.get(&self.slot(slot)?.item)
+    }
+
+    /// Returns how many of an item the inventory holds in total.
+    pub fn count(&self, item: &str) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    /// Adds items, topping up stacks before filling empty slots, and returns
+    /// how many did not fit.
+    pub fn add(&mut self, item: &str, count: u32) -> Result<u32, EngineError> {
+        let max = self.catalog.require(item)?.max_stack;
+        let mut left = count;
+        for stack in self.slots.iter_mut().flatten() {
+            if stack.item == item && left > 0 {
+                let added = left.min(max.saturating_sub(stack.count));
+                stack.count += added;
+                left -= added;
+            }
+        }
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if left == 0 {
+                break;
+            }
+            let added = left.min(max);
+            *slot = Some(Stack::new(item, added));
+            left -= added;
+        }
+        Ok(left)
+    }
+
+    /// Removes up to `count` of an item, emptying the last slots first, and
+    /// returns how many were removed.
+    pub fn remove(&mut self, item: &str, count: u32) -> u32 {
+        let mut removed = 0;
+        for slot in self.slots.iter_mut().rev() {
+            let Some(stack) = slot.as_mut().filter(|stack| stack.item == item) else {
+                continue;
+            };
+            let taken = stack.count.min(count - removed);
+            stack.count -= taken;
+            removed += taken;
+            if stack.count == 0 {
+                *slot = None;
+            }
+        }
+        removed
+    }
+
+    /// Empties a slot, returning its stack.
+    pub fn take(&mut self, slot: usize) -> Option<Stack> {
+        self.slots.get_mut(slot)?.take()
+    }
+
+    /// Puts a stack into a slot and returns what does not fit there: the
+    /// rest of the stack if the slot was empty or held the same item, or the
+    /// stack that was in the slot if it held another item and they swapped.
+    pub fn put(&mut self, slot: usize, mut stack: Stack) -> Result<Option<Stack>, EngineError> {
+        let max = self.catalog.require(&stack.item)?.max_stack;
+        let Some(target) = self.slots.get_mut(slot) else {
+            return Ok(Some(stack));
+        };
+        match target {
+            Some(existing) if existing.item == stack.item => {
+                let added = stack.count.min(max.saturating_sub(existing.count));
+                existing.count += added;
+                stack.count -= added;
+                Ok((stack.count > 0).then_some(stack))
+            }
+            Some(_) if stack.count > max => Ok(Some(stack)),
+            Some(_) => Ok(target.replace(stack)),
+            None => {
+                let rest = stack.count.saturating_sub(max);
+                stack.count -= rest;
+                let item = stack.item.clone();
+                *target = Some(stack);
+                Ok((rest > 0).then(|| Stack::new(item, rest)))
+            }
+        }
+    }
+
+    /// Moves the stack in `from` onto `to`, merging or swapping.
+    pub fn move_stack(&mut self, from: usize, to: usize) -> Result<(), EngineError> {
+        if from == to {
+            return Ok(());
+        }
+        let Some(stack) = self.take(from) else {
+            return Ok(());
+        };
+        if let Some(rest) = self.put(to, stack)? {
+            self.slots[from] = Some(rest);
+        }
+        Ok(())
+    }
+
+    /// Moves the stack in slot `from` of `source` onto slot `to` of
+    /// `target`, merging or swapping like [`Inventory::move_stack`].
+    pub fn transfer(
+        source: &mut Inventory,
+        from: usize,
+        target: &mut Inventory,
+        to: usize,
+    ) -> Result<(), EngineError> {
+        let Some(stack) = source.take(from) else {
+            return Ok(());
+        };
+        match target.put(to, stack.clone()) {
+            Ok(Some(rest)) => {
+                source.slots[from] = Some(rest);
+                Ok(())
+            }
+            Ok(None) => Ok(()),
+            Err(error) => {
+                source.slots[from] = Some(stack);
+                Err(error)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Inventory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (slot, stack) in self.slots.iter().enumerate() {
+            if let Some(stack) = stack {
+                writeln!(f, "{} {} {}", slot, stack.item, stack.count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Saveable for Inventory {
+    fn save(&self) -> Result<String, EngineError> {
+        Ok(self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> Rc<ItemCatalog> {
+        Rc::new(
+            ItemCatalog::new()
+                .with(ItemInfo::new("coin", "Coin", '$').with_max_stack(10))
+                .with(ItemInfo::new("sword", "Sword", '/')),
+        )
+    }
+
+    #[test]
+    fn test_add_stacks_then_fills_slots() {
+        let mut bag = Inventory::new(catalog(), 3);
+        assert_eq!(bag.add("coin", 4).unwrap(), 0);
+        assert_eq!(bag.add("sword", 1).unwrap(), 0);
+        assert_eq!(bag.add("coin", 10).unwrap(), 0);
+        assert_eq!(bag.slot(0), Some(&Stack::new("coin", 10)));
+        assert_eq!(bag.slot(2), Some(&Stack::new("coin", 4)));
+        assert_eq!(bag.add("coin", 20).unwrap(), 14);
+        assert!(bag.add("potion", 1).is_err());
+
+        assert_eq!(bag.remove("coin", 12), 12);
+        assert_eq!(bag.count("coin"), 8);
+        assert_eq!(bag.slot(2), None);
+        assert_eq!(bag.info(1).map(|info| info.glyph), Some('/'));
+    }
+
+    #[test]
+    fn test_move_merges_or_swaps() {
+        let mut bag = Inventory::new(catalog(), 3);
+        bag.put(0, Stack::new("coin", 7)).unwrap();
+        bag.put(1, Stack::new("coin", 6)).unwrap();
+        bag.put(2, Stack::new("sword", 1)).unwrap();
+
+        bag.move_stack(0, 1).unwrap();
+        assert_eq!(bag.slot(0), Some(&Stack::new("coin", 3)));
+        assert_eq!(bag.slot(1), Some(&Stack::new("coin", 10)));
+        bag.move_stack(2, 0).unwrap();
+        assert_eq!(bag.slot(0), Some(&Stack::new("sword", 1)));
+        assert_eq!(bag.slot(2), Some(&Stack::new("coin", 3)));
+
+        let mut chest = Inventory::new(catalog(), 1);
+        Inventory::transfer(&mut bag, 2, &mut chest, 0).unwrap();
+        assert_eq!(chest.slot(0), Some(&Stack::new("coin", 3)));
+        assert_eq!(bag.slot(2), None);
+    }
+
+    #[test]
+    fn test_save_and_parse() {
+        let mut bag = Inventory::new(catalog(), 4);
+        bag.put(1, Stack::new("coin", 5)).unwrap();
+        bag.put(3, Stack::new("sword", 1)).unwrap();
+        let text = bag.save().unwrap();
+        assert_eq!(text, "1 coin 5\n3 sword 1\n");
+        assert_eq!(Inventory::parse(catalog(), 4, &text).unwrap(), bag);
+        assert!(Inventory::parse(catalog(), 2, &text).is_err());
+        assert!(Inventory::parse(catalog(), 4, "0 coin").is_err());
+    }
+}
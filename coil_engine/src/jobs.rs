@@ -0,0 +1,239 @@
+//! Worker threads for heavy work that should not stall the game loop.
+//!
+//! Map generation, batches of path queries or image conversion can take
+//! longer than a frame. Hand them to [`Context::spawn_job`](crate::context::Context::spawn_job)
+//! instead: the job runs on a small pool of worker threads, and once it is
+//! done the engine sends [`EngineEvent::JobFinished`](crate::context::EngineEvent::JobFinished)
+//! with its id at the start of a frame. The node that spawned it then takes
+//! the result with [`Context::take_job`](crate::context::Context::take_job).
+//!
+//! Jobs must own their data (`Send + 'static`). A job that panics finishes
+//! with an error rather than taking a worker down. If no worker thread could
+//! be started, jobs run when spawned and finish with the next poll.
+use crate::errors::EngineError;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Output = Result<Box<dyn Any + Send>, String>;
+type Task = Box<dyn FnOnce() -> Box<dyn Any + Send> + Send>;
+type TaskSender = Sender<(JobId, Task)>;
+type ResultSender = Sender<(JobId, Output)>;
+type ResultReceiver = Receiver<(JobId, Output)>;
+
+/// Identifies a job spawned on a [`Jobs`] pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JobId(u64);
+
+/// A pool of worker threads running jobs and collecting their results.
+///
+/// The threads are started with the first job.
+pub struct Jobs {
+    workers: usize,
+    /// The task queue, and where jobs run without a worker send their result
+    tasks: Option<(TaskSender, ResultSender)>,
+    results: Option<ResultReceiver>,
+    finished: HashMap<JobId, Output>,
+    next: u64,
+    /// Jobs spawned whose result has not come back yet
+    running: usize,
+}
+
+impl Jobs {
+    /// Creates a pool of `workers` threads, at least one.
+    pub fn new(workers: usize) -> Self {
+        Self {
+            workers: workers.max(1),
+            tasks: None,
+            results: None,
+            finished: HashMap::new(),
+            next: 0,
+            running: 0,
+        }
+    }
+
+    /// Creates a pool leaving one core to the game loop.
+    pub fn with_available_parallelism() -> Self {
+        let cores = thread::available_parallelism().map_or(2, |n| n.get());
+        Self::new(cores - 1)
+    }
+
+    /// Runs `job` on a worker thread, or right away when there is none.
+    pub fn spawn<T: Send + 'static>(&mut self, job: impl FnOnce() -> T + Send + 'static) -> JobId {
+        let id = JobId(self.next);
+        self.next += 1;
+        let task: Task = Box::new(move || Box::new(job()));
+        let (sender, results) = self.tasks.get_or_insert_with(|| {
+            let (tasks, results, outputs) = start(self.workers);
+            self.results = Some(outputs);
+            (tasks, results)
+        });
+        // Every worker failed to start, which dropped the queue
+        if let Err(mpsc::SendError((id, task))) = sender.send((id, task)) {
+            // The pool holds the receiver, so the result always arrives
+            results.send((id, run(task))).ok();
+        }
+        self.running += 1;
+        id
+    }
+
+    /// Collects the results of jobs finished since the last poll and
+    /// returns their ids, in the order they were spawned.
+    pub fn poll(&mut self) -> Vec<JobId> {
+        let Some(results) = &self.results else {
+            return Vec::new();
+        };
+        let mut done: Vec<JobId> = Vec::new();
+        for (id, output) in results.try_iter() {
+            self.finished.insert(id, output);
+            done.push(id);
+        }
+        self.running -= done.len();
+        done.sort();
+        done
+    }
+
+    /// Takes the result of a finished job; `None` if it has not finished
+    /// or its result was already taken.
+    ///
+    /// Fails if the job panicked or returned something other than a `T`.
+    pub fn take<T: Any>(&mut self, id: JobId) -> Option<Result<T, EngineError>> {
+        let output = self.finished.remove(&id)?;
+        Some(match output {
+            Ok(value) => value
+                .downcast::<T>()
+                .map(|value| *value)
+                .map_err(|_| EngineError::game(format!("job {} returned another type", id.0))),
+            Err(message) => Err(EngineError::game(format!(
+                "job {} panicked: {}",
+                id.0, message
+            ))),
+        })
+    }
+
+    /// Returns the number of jobs spawned whose result has not been polled yet.
+    pub fn running(&self) -> usize {
+        self.running
+    }
+}
+
+impl Default for Jobs {
+    fn default() -> Self {
+        Self::with_available_parallelism()
+    }
+}
+
+impl fmt::Debug for Jobs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Jobs")
+            .field("workers", &self.workers)
+            .field("running", &self.running)
+            .field("finished", &self.finished.len())
+            .finish()
+    }
+}
+
+/// Runs `task`, turning a panic into an error message.
+fn run(task: Task) -> Output {
+    panic::catch_unwind(AssertUnwindSafe(task)).map_err(|panic| {
+        panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string())
+    })
+}
+
+/// Starts `workers` threads sharing one task queue.
+fn start(workers: usize) -> (TaskSender, ResultSender, ResultReceiver) {
+    let (tasks, queue) = mpsc::channel::<(JobId, Task)>();
+    let (results, outputs) = mpsc::channel();
+    let queue = Arc::new(Mutex::new(queue));
+    for index in 0..workers {
+        let (queue, results) = (Arc::clone(&queue), results.clone());
+        let worker = thread::Builder::new().name(format!("coil-job-{}", index));
+        let spawned = worker.spawn(move || {
+            loop {
+                // Hold the lock only while waiting for the next task
+                let next = queue
+                    .lock()
+                    .map_err(drop)
+                    .and_then(|q| q.recv().map_err(drop));
+                let Ok((id, task)) = next else {
+                    return;
+                };
+                if results.send((id, run(task))).is_err() {
+                    return;
+                }
+            }
+        });
+        if let Err(e) = spawned {
+            log::error!("Could not start job worker: {}", e);
+        }
+    }
+    (tasks, results, outputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn wait(jobs: &mut Jobs, count: usize) -> Vec<JobId> {
+        let start = Instant::now();
+        let mut done = Vec::new();
+        while done.len() < count {
+            assert!(start.elapsed() < Duration::from_secs(2), "jobs timed out");
+            done.extend(jobs.poll());
+            thread::sleep(Duration::from_millis(1));
+        }
+        done
+    }
+
+    #[test]
+    fn test_jobs_run_off_thread() {
+        let mut jobs = Jobs::new(2);
+        let sum = jobs.spawn(|| (1..=100u32).sum::<u32>());
+        let name = jobs.spawn(|| thread::current().name().map(str::to_string));
+        assert_eq!(jobs.running(), 2);
+
+        let mut done = wait(&mut jobs, 2);
+        done.sort();
+        assert_eq!(done, [sum, name]);
+        assert_eq!(jobs.running(), 0);
+        assert_eq!(jobs.take::<u32>(sum).unwrap().unwrap(), 5050);
+        assert!(jobs.take::<u32>(sum).is_none());
+        let worker = jobs.take::<Option<String>>(name).unwrap().unwrap().unwrap();
+        assert!(worker.starts_with("coil-job-"));
+    }
+
+    #[test]
+    fn test_panicking_job_fails() {
+        let mut jobs = Jobs::new(1);
+        let broken = jobs.spawn(|| -> u8 { panic!("out of tiles") });
+        let fine = jobs.spawn(|| 7u8);
+        wait(&mut jobs, 2);
+        let error = jobs.take::<u8>(broken).unwrap().unwrap_err();
+        assert!(error.to_string().contains("out of tiles"));
+        assert!(jobs.take::<String>(fine).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_jobs_run_inline_without_workers() {
+        // As if no worker had started: the task queue has no receiver
+        let mut jobs = Jobs::new(1);
+        let (results, outputs) = mpsc::channel();
+        jobs.tasks = Some((mpsc::channel().0, results));
+        jobs.results = Some(outputs);
+
+        let job = jobs.spawn(|| thread::current().name().map(str::to_string));
+        assert_eq!(jobs.running(), 1);
+        assert_eq!(jobs.poll(), [job]);
+        let name = jobs.take::<Option<String>>(job).unwrap().unwrap();
+        assert_eq!(name, thread::current().name().map(str::to_string));
+    }
+}
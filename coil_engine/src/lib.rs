@@ -1,9 +1,76 @@
+// Lets `#[derive(Node)]` expand to `::coil_engine` paths inside this crate too
+extern crate self as coil_engine;
+
+pub mod accessibility;
+pub mod achievements;
+pub mod actions;
+pub mod audio;
+pub mod automaton;
+pub mod autosave;
+pub mod big_text;
+pub mod board;
+pub mod camera;
+pub mod charts;
+#[cfg(feature = "chat")]
+pub mod chat;
+pub mod chunks;
+pub mod color;
 pub mod config;
+pub mod context;
+#[cfg(feature = "control")]
+pub mod control;
 pub mod core;
+pub mod day_cycle;
+#[cfg(feature = "db")]
+pub mod db;
+pub mod drag;
 pub mod errors;
 pub mod event_loop;
+pub mod fade;
+pub mod fiction;
+pub mod fixed;
+pub mod frame;
+pub mod geometry;
+pub mod glyphs;
+pub mod hex;
+pub mod hit;
 pub mod input;
+pub mod inventory;
+pub mod jobs;
+pub mod lighting;
+pub mod macros;
+pub mod mods;
+pub mod motion;
+pub mod navigation;
 pub mod nodes;
+pub mod notify;
+pub mod particles;
+pub mod paths;
+pub mod pool;
+pub mod random;
 pub mod renderer;
+pub mod replay;
+pub mod resource_bar;
+pub mod rewind;
+pub mod scene;
+pub mod shapes;
+pub mod spectate;
+pub mod speech;
+pub mod stats;
+pub mod steering;
+pub mod style;
+pub mod terminal;
+pub mod text;
+pub mod text_effects;
+pub mod tilemap;
+pub mod tmux;
+pub mod tooltip;
+pub mod undo;
+pub mod wfc;
 
-pub use core::Game;
+pub use context::Context;
+pub use core::{Game, GameBuilder};
+pub use crossterm;
+pub use errors::EngineError;
+pub use frame::Frame;
+pub use nodes::Node;
@@ -0,0 +1,323 @@
+//! Light levels and fog of war for grid worlds.
+//!
+//! A [`Lighting`] layer covers a map cell for cell. Each frame the game
+//! recomputes it from its [`Light`] sources, whose light falls off with
+//! distance and is blocked by opaque cells, and reveals what the player can
+//! see with [`Lighting::reveal`]. Cells that were seen before but are out of
+//! view are remembered and drawn dimmed in gray; cells never seen are not
+//! drawn at all. [`TileMap::draw_lit`](crate::tilemap::TileMap::draw_lit)
+//! draws a map through the layer, and [`Lighting::apply`] lights any other
+//! cell, e.g. of an entity.
+use crate::color::{self, Rgb};
+use crate::renderer::Cell;
+
+/// Returns which cells within `radius` of `origin` can be seen from it, row
+/// by row, for a `width` x `height` grid.
+///
+/// A cell is visible when the straight line to it passes no opaque cell
+/// before reaching it, so walls are seen but not seen through.
+pub fn field_of_view(
+    origin: (u16, u16),
+    radius: u16,
+    (width, height): (u16, u16),
+    opaque: impl Fn(u16, u16) -> bool,
+) -> Vec<bool> {
+    let mut visible = vec![false; width as usize * height as usize];
+    let (ox, oy) = (origin.0 as i32, origin.1 as i32);
+    let radius = radius as i32;
+    for y in (oy - radius).max(0)..(oy + radius + 1).min(height as i32) {
+        for x in (ox - radius).max(0)..(ox + radius + 1).min(width as i32) {
+            let (dx, dy) = (x - ox, y - oy);
+            if dx * dx + dy * dy > radius * radius {
+                continue;
+            }
+            let clear = line(ox, oy, x, y)
+                .skip(1)
+                .take_while(|&cell| cell != (x, y))
+                .all(|(cx, cy)| !opaque(cx as u16, cy as u16));
+            visible[y as usize * width as usize + x as usize] = clear;
+        }
+    }
+    visible
+}
+
+/// Iterates over the cells of a Bresenham line, both ends included.
+fn line(x0: i32, y0: i32, x1: i32, y1: i32) -> impl Iterator<Item = (i32, i32)> {
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+    let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+    let mut done = false;
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        let cell = (x, y);
+        done = cell == (x1, y1);
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += sx;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += sy;
+        }
+        Some(cell)
+    })
+}
+
+/// A light source on the grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    pub x: u16,
+    pub y: u16,
+    /// Distance at which the light has faded out
+    pub radius: u16,
+    pub color: Rgb,
+    /// Brightness at the source, where 1.0 shows colors unchanged
+    pub intensity: f32,
+}
+
+impl Light {
+    /// Creates a white light of full intensity.
+    pub fn new(x: u16, y: u16, radius: u16) -> Self {
+        Self {
+            x,
+            y,
+            radius,
+            color: Rgb::new(255, 255, 255),
+            intensity: 1.0,
+        }
+    }
+
+    pub fn with_color(mut self, color: Rgb) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity.max(0.0);
+        self
+    }
+}
+
+/// Whether the player can see a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// Never seen.
+    Hidden,
+    /// Seen before, but not now.
+    Explored,
+    /// In view.
+    Visible,
+}
+
+/// Light levels and explored cells of a grid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lighting {
+    width: u16,
+    height: u16,
+    ambient: Rgb,
+    /// Accumulated light per cell as RGB, where 1.0 is full brightness
+    levels: Vec<[f32; 3]>,
+    fog: bool,
+    visible: Vec<bool>,
+    explored: Vec<bool>,
+    /// Brightness of remembered cells
+    memory: f32,
+}
+
+impl Lighting {
+    /// Creates a fully lit layer without fog of war.
+    pub fn new(width: u16, height: u16) -> Self {
+        let cells = width as usize * height as usize;
+        Self {
+            width,
+            height,
+            ambient: Rgb::new(255, 255, 255),
+            levels: vec![[1.0; 3]; cells],
+            fog: false,
+            visible: vec![true; cells],
+            explored: vec![true; cells],
+            memory: 0.4,
+        }
+    }
+
+    /// Sets the light every cell gets without any source, e.g. dark blue at night.
+    pub fn with_ambient(mut self, ambient: Rgb) -> Self {
+        self.ambient = ambient;
+        self
+    }
+
+    /// Hides cells until they are revealed, and dims those out of view.
+    pub fn with_fog(mut self) -> Self {
+        self.fog = true;
+        self.visible.fill(false);
+        self.explored.fill(false);
+        self
+    }
+
+    /// Sets the brightness of explored cells that are out of view.
+    pub fn with_memory(mut self, brightness: f32) -> Self {
+        self.memory = brightness.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn ambient(&self) -> Rgb {
+        self.ambient
+    }
+
+    /// Changes the ambient light, e.g. as the day passes.
+    pub fn set_ambient(&mut self, ambient: Rgb) {
+        self.ambient = ambient;
+    }
+
+    /// Recomputes light levels from the ambient light and `lights`.
+    pub fn compute(&mut self, lights: &[Light], opaque: impl Fn(u16, u16) -> bool) {
+        let ambient = [self.ambient.r, self.ambient.g, self.ambient.b].map(|c| c as f32 / 255.0);
+        self.levels.fill(ambient);
+        let size = (self.width, self.height);
+        for light in lights {
+            let lit = field_of_view((light.x, light.y), light.radius, size, &opaque);
+            let color = [light.color.r, light.color.g, light.color.b].map(|c| c as f32 / 255.0);
+            let radius = light.radius.max(1) as f32 + 1.0;
+            for (index, level) in self.levels.iter_mut().enumerate() {
+                if !lit[index] {
+                    continue;
+                }
+                let dx = (index % self.width as usize) as f32 - light.x as f32;
+                let dy = (index / self.width as usize) as f32 - light.y as f32;
+                // Quadratic falloff, reaching zero just past the radius
+                let falloff = (1.0 - (dx * dx + dy * dy).sqrt() / radius).max(0.0).powi(2);
+                for (channel, color) in level.iter_mut().zip(color) {
+                    *channel += color * light.intensity * falloff;
+                }
+            }
+        }
+    }
+
+    /// Marks what a viewer at `origin` sees within `radius` as visible and
+    /// explored; everything else goes out of view.
+    pub fn reveal(&mut self, origin: (u16, u16), radius: u16, opaque: impl Fn(u16, u16) -> bool) {
+        self.visible = field_of_view(origin, radius, (self.width, self.height), opaque);
+        for (explored, visible) in self.explored.iter_mut().zip(&self.visible) {
+            *explored |= *visible;
+        }
+    }
+
+    /// Forgets every explored cell, e.g. when entering a new level.
+    pub fn forget(&mut self) {
+        if self.fog {
+            self.visible.fill(false);
+            self.explored.fill(false);
+        }
+    }
+
+    pub fn visibility(&self, x: u16, y: u16) -> Visibility {
+        let Some(index) = self.index(x, y) else {
+            return Visibility::Hidden;
+        };
+        if self.visible[index] {
+            Visibility::Visible
+        } else if self.explored[index] {
+            Visibility::Explored
+        } else {
+            Visibility::Hidden
+        }
+    }
+
+    /// Returns the light falling on a cell, saturated to white.
+    pub fn light(&self, x: u16, y: u16) -> Rgb {
+        let level = self
+            .index(x, y)
+            .map_or([0.0; 3], |index| self.levels[index]);
+        let [r, g, b] = level.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        Rgb::new(r, g, b)
+    }
+
+    /// Lights a cell drawn at (x,y) of the grid: visible cells are filtered
+    /// through their light, explored cells dimmed in gray and hidden cells blanked.
+    pub fn apply(&self, x: u16, y: u16, cell: Cell) -> Cell {
+        match self.visibility(x, y) {
+            Visibility::Hidden => Cell::BLANK,
+            Visibility::Explored => {
+                let gray = (self.memory * 255.0).round() as u8;
+                let dim = |color| {
+                    Rgb::from_color(color).map_or(color, |rgb: Rgb| {
+                        rgb.grayscale().multiply(Rgb::new(gray, gray, gray)).into()
+                    })
+                };
+                Cell {
+                    fg: dim(cell.fg),
+                    bg: dim(cell.bg),
+                    ..cell
+                }
+            }
+            Visibility::Visible => {
+                let light = self.light(x, y);
+                Cell {
+                    fg: color::multiply(cell.fg, light),
+                    bg: color::multiply(cell.bg, light),
+                    ..cell
+                }
+            }
+        }
+    }
+
+    fn index(&self, x: u16, y: u16) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| y as usize * self.width as usize + x as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::style::Color;
+
+    #[test]
+    fn test_walls_block_sight_and_light() {
+        // A wall column at x = 3
+        let wall = |x: u16, _: u16| x == 3;
+        let seen = field_of_view((1, 2), 5, (8, 5), wall);
+        let at = |x: usize, y: usize| seen[y * 8 + x];
+        assert!(at(1, 2) && at(2, 0) && at(3, 2));
+        assert!(!at(4, 2) && !at(6, 1));
+
+        let mut lighting = Lighting::new(8, 5).with_ambient(Rgb::new(0, 0, 0));
+        lighting.compute(&[Light::new(1, 2, 4).with_color(Rgb::new(255, 0, 0))], wall);
+        assert_eq!(lighting.light(1, 2), Rgb::new(255, 0, 0));
+        let near = lighting.light(2, 2).r;
+        assert!(near > 0 && near < 255);
+        assert_eq!(lighting.light(5, 2), Rgb::new(0, 0, 0));
+    }
+
+    #[test]
+    fn test_fog_remembers_explored_cells() {
+        let mut lighting = Lighting::new(10, 1).with_fog().with_memory(0.5);
+        let open = |_, _| false;
+        lighting.reveal((0, 0), 2, open);
+        assert_eq!(lighting.visibility(2, 0), Visibility::Visible);
+        assert_eq!(lighting.visibility(3, 0), Visibility::Hidden);
+        lighting.reveal((6, 0), 2, open);
+        assert_eq!(lighting.visibility(2, 0), Visibility::Explored);
+
+        let cell = Cell::new('#');
+        let red = Cell {
+            fg: Color::Rgb { r: 255, g: 0, b: 0 },
+            ..cell
+        };
+        assert_eq!(lighting.apply(3, 0, red), Cell::BLANK);
+        assert_eq!(lighting.apply(6, 0, red), red);
+        // Red is remembered as a dim gray
+        assert_eq!(
+            lighting.apply(0, 0, red).fg,
+            Color::Rgb {
+                r: 38,
+                g: 38,
+                b: 38
+            }
+        );
+        lighting.forget();
+        assert_eq!(lighting.visibility(6, 0), Visibility::Hidden);
+    }
+}
@@ -0,0 +1,235 @@
+//! Key macros recorded by the player.
+//!
+//! A [`Macros`] node lets players record a sequence of actions while
+//! playing and bind it to a key, which then performs the same actions
+//! again. Macros are stored with the key bindings in the [`ActionMap`], so
+//! saving the map keeps them across runs.
+//!
+//! To record, press the record key (F9 by default), play the actions, press
+//! the record key again and then the key to bind the macro to; Esc cancels.
+//!
+//! Macros replay actions rather than raw keys: each step presses the
+//! action's current first key, sent with [`Context::send_event`] one step
+//! per update. Steps naming an action that no longer exists are skipped.
+//! Macros cannot be bound to keys of actions, steps whose key plays a macro
+//! are skipped, and a playing macro can neither record nor start other
+//! macros, so a macro can never trigger itself.
+use crate::actions::{ActionMap, KeyBinding};
+use crate::context::Context;
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::nodes::Node;
+use crate::notify::Level;
+use crate::replay;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Most actions a macro records
+const MAX_STEPS: usize = 64;
+/// How long recording messages are shown
+const MESSAGE_TIME: Duration = Duration::from_secs(2);
+
+enum State {
+    Idle,
+    Recording(Vec<String>),
+    /// Waiting for the key to bind the recorded actions to
+    Binding(Vec<String>),
+}
+
+/// A node recording and playing the player's macros.
+///
+/// Add it as the last child of the root, so it sees keys before the game.
+/// Keys are passed on to the game while recording; keys playing a macro
+/// and the record key are not.
+pub struct Macros {
+    actions: ActionMap,
+    record_key: KeyBinding,
+    state: State,
+    /// Keys of the macro being played that are still to be pressed
+    playing: VecDeque<KeyBinding>,
+}
+
+impl Macros {
+    /// Records and plays macros of the actions in `actions`.
+    pub fn new(actions: ActionMap) -> Self {
+        Self {
+            actions,
+            record_key: KeyCode::F(9).into(),
+            state: State::Idle,
+            playing: VecDeque::new(),
+        }
+    }
+
+    /// Replaces the key starting and stopping a recording.
+    pub fn with_record_key(mut self, key: impl Into<KeyBinding>) -> Self {
+        self.record_key = key.into();
+        self
+    }
+
+    /// Returns the key bindings, including the macros recorded so far.
+    pub fn actions(&self) -> &ActionMap {
+        &self.actions
+    }
+
+    pub fn actions_mut(&mut self) -> &mut ActionMap {
+        &mut self.actions
+    }
+
+    pub fn is_recording(&self) -> bool {
+        !matches!(self.state, State::Idle)
+    }
+
+    pub fn is_playing(&self) -> bool {
+        !self.playing.is_empty()
+    }
+
+    /// Starts playing the actions of a macro.
+    fn play(&mut self, steps: &[String]) {
+        self.playing = steps
+            .iter()
+            .filter_map(|action| self.actions.bindings(action).first().copied())
+            .filter(|&key| {
+                let event = KeyEvent::new(key.code, key.modifiers);
+                self.actions.macro_for(&event).is_none()
+            })
+            .collect();
+    }
+}
+
+impl Node for Macros {
+    fn update(&mut self, ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+        if let Some(key) = self.playing.pop_front() {
+            ctx.send_event(replay::key_event(key));
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        let Event::Key(key) = ev else {
+            return Ok(false);
+        };
+        if key.kind == KeyEventKind::Release || self.is_playing() {
+            return Ok(false);
+        }
+        match &mut self.state {
+            State::Idle if self.record_key.matches(key) => {
+                self.state = State::Recording(Vec::new());
+                ctx.notify("Recording macro", Level::Info, MESSAGE_TIME);
+                Ok(true)
+            }
+            State::Idle => match self.actions.macro_for(key) {
+                Some(steps) => {
+                    let steps = steps.to_vec();
+                    self.play(&steps);
+                    Ok(true)
+                }
+                None => Ok(false),
+            },
+            State::Recording(steps) if self.record_key.matches(key) => {
+                let steps = std::mem::take(steps);
+                if steps.is_empty() {
+                    self.state = State::Idle;
+                    ctx.notify("Macro cancelled", Level::Info, MESSAGE_TIME);
+                } else {
+                    self.state = State::Binding(steps);
+                    ctx.notify("Press a key for the macro", Level::Info, MESSAGE_TIME);
+                }
+                Ok(true)
+            }
+            State::Recording(steps) => {
+                if let Some(action) = self.actions.action_for(key)
+                    && steps.len() < MAX_STEPS
+                {
+                    steps.push(action.to_string());
+                }
+                Ok(false)
+            }
+            State::Binding(steps) => {
+                let steps = std::mem::take(steps);
+                self.state = State::Idle;
+                let trigger = KeyBinding::new(key.code, key.modifiers);
+                if key.code == KeyCode::Esc || trigger == self.record_key {
+                    ctx.notify("Macro cancelled", Level::Info, MESSAGE_TIME);
+                } else if let Some(action) = self.actions.action_for(key) {
+                    let message = format!("{} is already bound to {}", trigger, action);
+                    ctx.notify(message, Level::Warning, MESSAGE_TIME);
+                } else {
+                    ctx.notify(
+                        format!("Macro bound to {}", trigger),
+                        Level::Success,
+                        MESSAGE_TIME,
+                    );
+                    self.actions.bind_macro(trigger, steps);
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crossterm::event::{KeyEvent, KeyModifiers};
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn test_record_bind_and_play() {
+        let actions = ActionMap::new()
+            .with_binding("left", KeyCode::Left)
+            .with_binding("jump", KeyCode::Char(' '));
+        let mut macros = Macros::new(actions);
+        let mut ctx = Context::new(GameConfig::new());
+
+        let presses = [
+            (KeyCode::F(9), true),
+            (KeyCode::Left, false),
+            (KeyCode::Char('x'), false),
+            (KeyCode::Char(' '), false),
+            (KeyCode::F(9), true),
+            (KeyCode::Left, true),
+        ];
+        for (code, consumed) in presses {
+            assert_eq!(macros.on_event(&mut ctx, &key(code)).unwrap(), consumed);
+        }
+        assert!(!macros.is_recording());
+        assert_eq!(macros.actions().macros().count(), 0);
+
+        // Left plays an action, so the recording is bound to F1 instead
+        for code in [
+            KeyCode::F(9),
+            KeyCode::Left,
+            KeyCode::Char(' '),
+            KeyCode::F(9),
+        ] {
+            macros.on_event(&mut ctx, &key(code)).unwrap();
+        }
+        assert!(macros.on_event(&mut ctx, &key(KeyCode::F(1))).unwrap());
+        assert_eq!(
+            macros.actions().to_string(),
+            "left = ['Left']\njump = ['Space']\nmacro 'F1' = ['left', 'jump']\n"
+        );
+
+        // Rebinding an action changes the key the macro presses
+        macros
+            .actions_mut()
+            .rebind("left", vec![KeyCode::Char('a').into()]);
+        assert!(macros.on_event(&mut ctx, &key(KeyCode::F(1))).unwrap());
+        for _ in 0..3 {
+            macros.update(&mut ctx, 0.1).unwrap();
+        }
+        assert_eq!(
+            ctx.take_sent_events(),
+            [key(KeyCode::Char('a')), key(KeyCode::Char(' '))]
+        );
+    }
+}
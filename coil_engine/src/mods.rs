@@ -0,0 +1,387 @@
+//! Content packs that extend a game without recompiling it.
+//!
+//! A game ships its content in a base directory, with assets, scenes,
+//! scripts and localization in subdirectories of its choice. Players drop
+//! mods into a mods directory, one directory per mod, mirroring the same
+//! layout; [`Mods::discover`] overlays them over the base:
+//!
+//! ```text
+//! content/                  mods/
+//!   scenes/forest.txt         more-trees/
+//!   localization/en.txt         mod.txt
+//!                               scenes/forest.txt
+//!                               localization/en.txt
+//! ```
+//!
+//! A mod can describe itself in a `mod.txt` file of `key = value` lines:
+//!
+//! ```text
+//! name = More trees
+//! priority = 10
+//! enabled = true
+//! ```
+//!
+//! Without one, a mod is named after its directory and has priority 0.
+//!
+//! Mods are layered over the base from the lowest priority to the highest,
+//! mods of equal priority by name, and each file comes from the last layer
+//! that has it. Files two mods both provide are [conflicts](Mods::conflicts),
+//! which games can show to players so they can reorder their mods; mods
+//! replacing base files are what mods are for, so those are not reported.
+//! Localization files are the exception: [`Mods::localization`] merges them
+//! string by string, so a mod only needs the strings it adds or changes.
+//!
+//! ```no_run
+//! use coil_engine::mods::Mods;
+//!
+//! let mods = Mods::discover("content", "mods").unwrap();
+//! for conflict in mods.conflicts() {
+//!     log::warn!("{}", conflict);
+//! }
+//! let forest = mods.read_to_string("scenes/forest.txt").unwrap();
+//! ```
+use crate::errors::EngineError;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// File a mod describes itself in
+pub const MANIFEST: &str = "mod.txt";
+
+/// A content pack found in the mods directory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mod {
+    pub name: String,
+    pub priority: i32,
+    pub enabled: bool,
+    /// Directory holding the mod's files
+    pub dir: PathBuf,
+}
+
+impl Mod {
+    /// Reads the mod in `dir`, using its manifest if it has one.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, EngineError> {
+        let dir = dir.as_ref();
+        let mut found = Self {
+            name: dir
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            priority: 0,
+            enabled: true,
+            dir: dir.to_path_buf(),
+        };
+        let contents = match fs::read_to_string(dir.join(MANIFEST)) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(found),
+            Err(e) => return Err(e.into()),
+        };
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || {
+                EngineError::Config(format!(
+                    "Invalid line {} in {}: {:?}",
+                    number + 1,
+                    dir.join(MANIFEST).display(),
+                    line
+                ))
+            };
+            let (key, value) = line.split_once('=').ok_or_else(invalid)?;
+            let value = value.trim().trim_matches('"');
+            match key.trim() {
+                "name" => found.name = value.to_string(),
+                "priority" => found.priority = value.parse().map_err(|_| invalid())?,
+                "enabled" => found.enabled = value.parse().map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(found)
+    }
+}
+
+/// A file provided by more than one mod.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    /// Path of the file, relative to the content root
+    pub path: PathBuf,
+    /// Mod the file is used from
+    pub winner: String,
+    /// Mods whose file is hidden, from the highest precedence down
+    pub overridden: Vec<String>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} from {} overrides {}",
+            self.path.display(),
+            self.winner,
+            self.overridden.join(", ")
+        )
+    }
+}
+
+/// The base content with the enabled mods overlaid on it.
+#[derive(Debug)]
+pub struct Mods {
+    base: PathBuf,
+    /// Enabled mods, from the lowest precedence to the highest
+    mods: Vec<Mod>,
+    /// Mods that were found but are disabled
+    disabled: Vec<Mod>,
+    /// Each relative path with the layers providing it, lowest first; layer
+    /// 0 is the base and layer `i` the mod `i - 1`
+    files: BTreeMap<PathBuf, Vec<usize>>,
+}
+
+impl Mods {
+    /// Overlays the mods in `mods_dir` over the content in `base`.
+    ///
+    /// A missing mods directory means there are no mods.
+    pub fn discover(
+        base: impl AsRef<Path>,
+        mods_dir: impl AsRef<Path>,
+    ) -> Result<Self, EngineError> {
+        let mut found = Vec::new();
+        match fs::read_dir(mods_dir) {
+            Ok(entries) => {
+                for entry in entries {
+                    let entry = entry?;
+                    if entry.file_type()?.is_dir() {
+                        found.push(Mod::load(entry.path())?);
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        Self::with_mods(base, found)
+    }
+
+    /// Overlays `mods` over the content in `base`.
+    pub fn with_mods(base: impl AsRef<Path>, mods: Vec<Mod>) -> Result<Self, EngineError> {
+        let (mut mods, disabled): (Vec<_>, Vec<_>) = mods.into_iter().partition(|m| m.enabled);
+        mods.sort_by(|a, b| {
+            a.priority
+                .cmp(&b.priority)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        let mut overlay = Self {
+            base: base.as_ref().to_path_buf(),
+            mods,
+            disabled,
+            files: BTreeMap::new(),
+        };
+        let roots: Vec<_> = std::iter::once(overlay.base.clone())
+            .chain(overlay.mods.iter().map(|m| m.dir.clone()))
+            .collect();
+        for (layer, root) in roots.iter().enumerate() {
+            let mut paths = Vec::new();
+            if root.is_dir() {
+                list_files(root, Path::new(""), &mut paths)?;
+            }
+            for path in paths {
+                if layer > 0 && path == Path::new(MANIFEST) {
+                    continue;
+                }
+                overlay.files.entry(path).or_default().push(layer);
+            }
+        }
+        Ok(overlay)
+    }
+
+    /// Returns the enabled mods, from the lowest precedence to the highest.
+    pub fn mods(&self) -> &[Mod] {
+        &self.mods
+    }
+
+    /// Returns the mods that were found but are disabled.
+    pub fn disabled(&self) -> &[Mod] {
+        &self.disabled
+    }
+
+    /// Returns where the file at `path`, relative to the content root, is
+    /// read from.
+    pub fn resolve(&self, path: impl AsRef<Path>) -> Option<PathBuf> {
+        let path = path.as_ref();
+        let &layer = self.files.get(path)?.last()?;
+        Some(self.root(layer).join(path))
+    }
+
+    /// Returns the name of the mod the file at `path` comes from, or `None`
+    /// for base files and files that do not exist.
+    pub fn source(&self, path: impl AsRef<Path>) -> Option<&str> {
+        let &layer = self.files.get(path.as_ref())?.last()?;
+        layer.checked_sub(1).map(|i| self.mods[i].name.as_str())
+    }
+
+    pub fn read(&self, path: impl AsRef<Path>) -> Result<Vec<u8>, EngineError> {
+        Ok(fs::read(self.existing(path.as_ref())?)?)
+    }
+
+    pub fn read_to_string(&self, path: impl AsRef<Path>) -> Result<String, EngineError> {
+        Ok(fs::read_to_string(self.existing(path.as_ref())?)?)
+    }
+
+    /// Returns the paths of the files under the directory `dir`, relative to
+    /// the content root, such as every scene in `scenes`.
+    pub fn files_in(&self, dir: impl AsRef<Path>) -> impl Iterator<Item = &Path> {
+        let dir = dir.as_ref().to_path_buf();
+        self.files
+            .keys()
+            .filter(move |path| path.starts_with(&dir))
+            .map(PathBuf::as_path)
+    }
+
+    /// Returns the files provided by more than one mod.
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        self.files
+            .iter()
+            .filter_map(|(path, layers)| {
+                let mut names = layers
+                    .iter()
+                    .rev()
+                    .filter_map(|layer| layer.checked_sub(1))
+                    .map(|i| self.mods[i].name.clone());
+                let winner = names.next()?;
+                let overridden: Vec<_> = names.collect();
+                (!overridden.is_empty()).then(|| Conflict {
+                    path: path.clone(),
+                    winner,
+                    overridden,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the strings of the localization file at `path`, merged from
+    /// every layer that has it; each string comes from the last of them.
+    ///
+    /// Localization files hold `key = text` lines; lines starting with `#`
+    /// are comments.
+    pub fn localization(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<HashMap<String, String>, EngineError> {
+        let path = path.as_ref();
+        let mut strings = HashMap::new();
+        for &layer in self.files.get(path).into_iter().flatten() {
+            let file = self.root(layer).join(path);
+            for (number, line) in fs::read_to_string(&file)?.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let (key, text) = line.split_once('=').ok_or_else(|| {
+                    EngineError::Config(format!(
+                        "Expected `key = text` on line {} of {}",
+                        number + 1,
+                        file.display()
+                    ))
+                })?;
+                strings.insert(key.trim().to_string(), text.trim().to_string());
+            }
+        }
+        Ok(strings)
+    }
+
+    fn root(&self, layer: usize) -> &Path {
+        match layer.checked_sub(1) {
+            Some(i) => &self.mods[i].dir,
+            None => &self.base,
+        }
+    }
+
+    fn existing(&self, path: &Path) -> Result<PathBuf, EngineError> {
+        self.resolve(path).ok_or_else(|| {
+            EngineError::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no content file {}", path.display()),
+            ))
+        })
+    }
+}
+
+/// Adds the files under `root.join(dir)` to `paths`, relative to `root`.
+fn list_files(root: &Path, dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), EngineError> {
+    for entry in fs::read_dir(root.join(dir))? {
+        let entry = entry?;
+        let path = dir.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            list_files(root, &path, paths)?;
+        } else {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_mods_overlay_base_content() {
+        let root = std::env::temp_dir().join(format!("coil-mods-{}", std::process::id()));
+        let (base, mods) = (root.join("content"), root.join("mods"));
+        write(&base.join("scenes/forest.txt"), "base forest");
+        write(&base.join("scenes/cave.txt"), "base cave");
+        write(
+            &base.join("localization/en.txt"),
+            "hello = Hello\nbye = Bye",
+        );
+        write(&mods.join("trees/scenes/forest.txt"), "more trees");
+        write(&mods.join("trees/localization/en.txt"), "bye = Farewell");
+        write(
+            &mods.join("dark/mod.txt"),
+            "name = Dark forest\npriority = 5",
+        );
+        write(&mods.join("dark/scenes/forest.txt"), "dark forest");
+        write(&mods.join("dark/scenes/swamp.txt"), "swamp");
+        write(&mods.join("off/mod.txt"), "enabled = false");
+        write(&mods.join("off/scenes/cave.txt"), "disabled cave");
+
+        let overlay = Mods::discover(&base, &mods).unwrap();
+        let names: Vec<_> = overlay.mods().iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["trees", "Dark forest"]);
+        assert_eq!(overlay.disabled()[0].name, "off");
+
+        assert_eq!(
+            overlay.read_to_string("scenes/forest.txt").unwrap(),
+            "dark forest"
+        );
+        assert_eq!(
+            overlay.read_to_string("scenes/cave.txt").unwrap(),
+            "base cave"
+        );
+        assert_eq!(overlay.source("scenes/swamp.txt"), Some("Dark forest"));
+        assert_eq!(overlay.source("scenes/cave.txt"), None);
+        assert!(overlay.read("scenes/beach.txt").is_err());
+        let scenes: Vec<_> = overlay.files_in("scenes").collect();
+        assert_eq!(scenes.len(), 3);
+
+        assert_eq!(
+            overlay.conflicts(),
+            [Conflict {
+                path: PathBuf::from("scenes/forest.txt"),
+                winner: "Dark forest".to_string(),
+                overridden: vec!["trees".to_string()],
+            }]
+        );
+        let strings = overlay.localization("localization/en.txt").unwrap();
+        assert_eq!(strings["hello"], "Hello");
+        assert_eq!(strings["bye"], "Farewell");
+        fs::remove_dir_all(root).unwrap();
+    }
+}
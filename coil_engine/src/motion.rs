@@ -0,0 +1,150 @@
+//! Helpers for entities that move by fractions of a cell.
+//!
+//! Positions are kept as floats and interpolated with the frame's alpha, so
+//! motion looks smooth even when updates run slower than rendering. Drawing
+//! can use half blocks (`▌`/`▐`) to show horizontal positions at half-cell
+//! precision.
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::renderer::Cell;
+use crate::style::Style;
+
+/// Rounds a fractional position to the cell it falls in, or `None` if that
+/// cell is off the top or left of the screen.
+pub fn to_cell(x: f32, y: f32) -> Option<(u16, u16)> {
+    let (x, y) = (x.round(), y.round());
+    let range = 0.0..=u16::MAX as f32;
+    if range.contains(&x) && range.contains(&y) {
+        Some((x as u16, y as u16))
+    } else {
+        None
+    }
+}
+
+/// A fractional position that remembers where it was before the last update.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SmoothPosition {
+    previous: (f32, f32),
+    current: (f32, f32),
+}
+
+impl SmoothPosition {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            previous: (x, y),
+            current: (x, y),
+        }
+    }
+
+    /// Returns the position after the last update.
+    pub fn position(&self) -> (f32, f32) {
+        self.current
+    }
+
+    /// Moves to (x,y), interpolating from the current position.
+    ///
+    /// Call it at most once per update; use [`SmoothPosition::teleport`] for
+    /// jumps that should not be animated.
+    pub fn move_to(&mut self, x: f32, y: f32) {
+        self.previous = self.current;
+        self.current = (x, y);
+    }
+
+    /// Moves by (dx,dy), interpolating from the current position.
+    pub fn translate(&mut self, dx: f32, dy: f32) {
+        self.move_to(self.current.0 + dx, self.current.1 + dy);
+    }
+
+    /// Jumps to (x,y) without interpolating.
+    pub fn teleport(&mut self, x: f32, y: f32) {
+        *self = Self::new(x, y);
+    }
+
+    /// Returns the position to draw at for the frame's interpolation alpha.
+    pub fn interpolated(&self, alpha: f32) -> (f32, f32) {
+        let alpha = alpha.clamp(0.0, 1.0);
+        (
+            self.previous.0 + (self.current.0 - self.previous.0) * alpha,
+            self.previous.1 + (self.current.1 - self.previous.1) * alpha,
+        )
+    }
+
+    /// Returns the cell to draw at for the frame's interpolation alpha.
+    pub fn cell(&self, alpha: f32) -> Option<(u16, u16)> {
+        let (x, y) = self.interpolated(alpha);
+        to_cell(x, y)
+    }
+}
+
+/// Draws a horizontal bar `width` cells long at a fractional position.
+///
+/// The position is rounded to the nearest half cell. When it lands halfway
+/// between two cells, the bar starts with `▐` and ends with `▌` so it
+/// appears shifted by half a cell. The bar is drawn in `style.fg` over
+/// `style.bg`; parts off the frame are clipped.
+pub fn draw_half_block_bar(
+    frame: &mut Frame<'_>,
+    x: f32,
+    y: f32,
+    width: u16,
+    style: Style,
+) -> Result<(), EngineError> {
+    let row = y.round();
+    if width == 0 || !(0.0..=u16::MAX as f32).contains(&row) {
+        return Ok(());
+    }
+    let halves = (x * 2.0).round() as i64;
+    let start = halves.div_euclid(2);
+    let shifted = halves.rem_euclid(2) == 1;
+
+    let mut cells = Vec::with_capacity(width as usize + 1);
+    if shifted {
+        cells.push('▐');
+        cells.extend(std::iter::repeat_n('█', width as usize - 1));
+        cells.push('▌');
+    } else {
+        cells.extend(std::iter::repeat_n('█', width as usize));
+    }
+    for (offset, ch) in cells.into_iter().enumerate() {
+        if let Ok(column) = u16::try_from(start + offset as i64) {
+            frame.draw_cell(column, row as u16, Cell::styled(ch, style))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Rect;
+    use crate::renderer::TestRenderer;
+
+    #[test]
+    fn test_interpolation() {
+        let mut position = SmoothPosition::new(1.0, 2.0);
+        position.translate(2.0, 0.0);
+        assert_eq!(position.position(), (3.0, 2.0));
+        assert_eq!(position.interpolated(0.0), (1.0, 2.0));
+        assert_eq!(position.interpolated(0.25), (1.5, 2.0));
+        assert_eq!(position.cell(0.5), Some((2, 2)));
+
+        position.teleport(-4.0, 0.0);
+        assert_eq!(position.interpolated(0.5), (-4.0, 0.0));
+        assert_eq!(position.cell(0.5), None);
+    }
+
+    #[test]
+    fn test_half_block_bar() {
+        let mut renderer = TestRenderer::new(6, 3);
+        {
+            let mut frame = Frame::new(&mut renderer, Rect::new(0, 0, 6, 3), 0.0);
+            draw_half_block_bar(&mut frame, 1.1, 0.0, 2, Style::new()).unwrap();
+            draw_half_block_bar(&mut frame, 1.4, 1.0, 2, Style::new()).unwrap();
+            draw_half_block_bar(&mut frame, -0.5, 2.2, 1, Style::new()).unwrap();
+        }
+        assert_eq!(renderer.row(0), " ██");
+        assert_eq!(renderer.row(1), " ▐█▌");
+        assert_eq!(renderer.row(2), "▌");
+    }
+}
@@ -0,0 +1,405 @@
+//! Path finding on tile maps, shared by many agents.
+//!
+//! A [`NavGrid`] records which cells of a [`TileMap`] can be walked on, plus
+//! obstacles such as closed doors or other units that come and go at
+//! runtime. It keeps the walkable cells labelled by connected region and
+//! repairs only the regions an obstacle touches, so asking for a path to an
+//! unreachable cell fails at once instead of searching the whole map.
+//!
+//! Agents can call [`NavGrid::find_path`] directly, or queue requests with
+//! [`NavGrid::request`] and let [`NavGrid::process`] answer a few of them
+//! each update, so a crowd asking for paths at once does not stall a frame.
+//! Paths are lists of cells, ready for
+//! [`PathFollower`](crate::steering::PathFollower).
+use crate::tilemap::{Tile, TileMap};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+/// A handle to a queued path request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PathRequest(u64);
+
+/// The answer to a queued path request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathStatus {
+    /// Still waiting in the queue.
+    Pending,
+    /// A shortest path, from the start cell to the goal cell.
+    Found(Vec<(u16, u16)>),
+    /// The goal cannot be reached.
+    NoPath,
+}
+
+/// The start and goal cells of a path query.
+type Route = ((u16, u16), (u16, u16));
+
+/// Walkable cells of a map with dynamic obstacles and queued path queries.
+#[derive(Debug, Clone)]
+pub struct NavGrid {
+    width: u16,
+    height: u16,
+    /// Whether the map itself can be walked on, cell by cell
+    walkable: Vec<bool>,
+    /// Number of obstacles on each cell
+    obstacles: Vec<u16>,
+    /// Connected region of each cell, 0 for blocked cells
+    regions: Vec<u32>,
+    next_region: u32,
+    diagonals: bool,
+    queue: VecDeque<(PathRequest, Route)>,
+    results: HashMap<PathRequest, PathStatus>,
+    next_request: u64,
+}
+
+impl NavGrid {
+    /// Creates a grid where `walkable` tells which cells can be walked on.
+    pub fn new(width: u16, height: u16, walkable: impl Fn(u16, u16) -> bool) -> Self {
+        let walkable = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| walkable(x, y))
+            .collect();
+        let cells = width as usize * height as usize;
+        let mut grid = Self {
+            width,
+            height,
+            walkable,
+            obstacles: vec![0; cells],
+            regions: vec![0; cells],
+            next_region: 1,
+            diagonals: false,
+            queue: VecDeque::new(),
+            results: HashMap::new(),
+            next_request: 0,
+        };
+        for index in 0..cells {
+            if grid.regions[index] == 0 && grid.is_open(index) {
+                grid.flood(index);
+            }
+        }
+        grid
+    }
+
+    /// Creates a grid covering a map, walkable where `walkable` accepts the tile.
+    pub fn from_tilemap(map: &TileMap, walkable: impl Fn(Tile) -> bool) -> Self {
+        Self::new(map.width(), map.height(), |x, y| {
+            map.tile(x, y).is_some_and(&walkable)
+        })
+    }
+
+    /// Lets paths move diagonally, without cutting past blocked corners.
+    pub fn with_diagonals(mut self) -> Self {
+        self.diagonals = true;
+        self
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Returns whether an agent can stand on (x,y) now.
+    pub fn is_walkable(&self, x: u16, y: u16) -> bool {
+        self.index(x, y).is_some_and(|index| self.is_open(index))
+    }
+
+    /// Returns whether `to` can be reached from `from` at all.
+    pub fn connected(&self, from: (u16, u16), to: (u16, u16)) -> bool {
+        match (self.index(from.0, from.1), self.index(to.0, to.1)) {
+            (Some(from), Some(to)) => {
+                self.regions[from] != 0 && self.regions[from] == self.regions[to]
+            }
+            _ => false,
+        }
+    }
+
+    /// Changes whether the map at (x,y) can be walked on, e.g. after a tile
+    /// was dug out or built.
+    pub fn set_walkable(&mut self, x: u16, y: u16, walkable: bool) {
+        if let Some(index) = self.index(x, y) {
+            let was_open = self.is_open(index);
+            self.walkable[index] = walkable;
+            self.repair(index, was_open);
+        }
+    }
+
+    /// Blocks (x,y) with an obstacle. Obstacles stack, so a cell opens again
+    /// once every obstacle placed on it was removed.
+    pub fn add_obstacle(&mut self, x: u16, y: u16) {
+        if let Some(index) = self.index(x, y) {
+            let was_open = self.is_open(index);
+            self.obstacles[index] = self.obstacles[index].saturating_add(1);
+            self.repair(index, was_open);
+        }
+    }
+
+    /// Removes an obstacle placed on (x,y).
+    pub fn remove_obstacle(&mut self, x: u16, y: u16) {
+        if let Some(index) = self.index(x, y) {
+            let was_open = self.is_open(index);
+            self.obstacles[index] = self.obstacles[index].saturating_sub(1);
+            self.repair(index, was_open);
+        }
+    }
+
+    /// Returns whether a path found earlier now runs into a blocked cell.
+    pub fn is_blocked(&self, path: &[(u16, u16)]) -> bool {
+        path.iter().any(|&(x, y)| !self.is_walkable(x, y))
+    }
+
+    /// Finds a shortest path from `from` to `to`, both included.
+    pub fn find_path(&self, from: (u16, u16), to: (u16, u16)) -> Option<Vec<(u16, u16)>> {
+        if !self.connected(from, to) {
+            return None;
+        }
+        let start = self.index(from.0, from.1)?;
+        let goal = self.index(to.0, to.1)?;
+        // Costs are in tenths of a step, so diagonals can cost 14
+        let heuristic = |index: usize| {
+            let (x, y) = self.position(index);
+            let (dx, dy) = (x.abs_diff(to.0) as u32, y.abs_diff(to.1) as u32);
+            if self.diagonals {
+                10 * dx.max(dy) + 4 * dx.min(dy)
+            } else {
+                10 * (dx + dy)
+            }
+        };
+        let mut costs = HashMap::from([(start, 0)]);
+        let mut came_from = HashMap::new();
+        let mut open = BinaryHeap::from([Reverse((heuristic(start), start))]);
+        while let Some(Reverse((_, current))) = open.pop() {
+            if current == goal {
+                let mut path = vec![self.position(goal)];
+                let mut cell = goal;
+                while let Some(&previous) = came_from.get(&cell) {
+                    path.push(self.position(previous));
+                    cell = previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            let cost = costs[&current];
+            for (next, step) in self.steps(current) {
+                let next_cost = cost + step;
+                if costs.get(&next).is_none_or(|&known| next_cost < known) {
+                    costs.insert(next, next_cost);
+                    came_from.insert(next, current);
+                    open.push(Reverse((next_cost + heuristic(next), next)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Queues a path query to be answered by [`NavGrid::process`].
+    pub fn request(&mut self, from: (u16, u16), to: (u16, u16)) -> PathRequest {
+        let request = PathRequest(self.next_request);
+        self.next_request += 1;
+        self.queue.push_back((request, (from, to)));
+        self.results.insert(request, PathStatus::Pending);
+        request
+    }
+
+    /// Returns the number of queries waiting to be answered.
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Answers up to `budget` queued queries, oldest first; call it from
+    /// `update`. Queries between the same cells share one search.
+    pub fn process(&mut self, budget: usize) {
+        let mut answered: HashMap<Route, PathStatus> = HashMap::new();
+        for _ in 0..budget {
+            let Some((request, (from, to))) = self.queue.pop_front() else {
+                break;
+            };
+            let status = answered
+                .entry((from, to))
+                .or_insert_with(|| match self.find_path(from, to) {
+                    Some(path) => PathStatus::Found(path),
+                    None => PathStatus::NoPath,
+                })
+                .clone();
+            self.results.insert(request, status);
+        }
+    }
+
+    /// Returns the answer to a request, without consuming it.
+    pub fn status(&self, request: PathRequest) -> Option<&PathStatus> {
+        self.results.get(&request)
+    }
+
+    /// Takes the answer to a request once it is no longer pending.
+    pub fn take(&mut self, request: PathRequest) -> Option<PathStatus> {
+        match self.results.get(&request)? {
+            PathStatus::Pending => None,
+            _ => self.results.remove(&request),
+        }
+    }
+
+    /// Drops a request, whether it was answered or not.
+    pub fn cancel(&mut self, request: PathRequest) {
+        self.queue.retain(|(queued, _)| *queued != request);
+        self.results.remove(&request);
+    }
+
+    fn index(&self, x: u16, y: u16) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| y as usize * self.width as usize + x as usize)
+    }
+
+    fn position(&self, index: usize) -> (u16, u16) {
+        let width = self.width as usize;
+        ((index % width) as u16, (index / width) as u16)
+    }
+
+    fn is_open(&self, index: usize) -> bool {
+        self.walkable[index] && self.obstacles[index] == 0
+    }
+
+    /// Returns the open cells one step from `index` and the cost of the step.
+    fn steps(&self, index: usize) -> impl Iterator<Item = (usize, u32)> + '_ {
+        let (x, y) = self.position(index);
+        let open = move |dx: i32, dy: i32| {
+            let (x, y) = (x as i32 + dx, y as i32 + dy);
+            let x = u16::try_from(x).ok()?;
+            let y = u16::try_from(y).ok()?;
+            self.index(x, y).filter(|&next| self.is_open(next))
+        };
+        let straight = [(1, 0), (0, 1), (-1, 0), (0, -1)]
+            .into_iter()
+            .filter_map(move |(dx, dy)| open(dx, dy).map(|next| (next, 10)));
+        let diagonal = [(1, 1), (-1, 1), (-1, -1), (1, -1)]
+            .into_iter()
+            .filter(move |_| self.diagonals)
+            // Only step diagonally when both sides are open
+            .filter(move |&(dx, dy)| open(dx, 0).is_some() && open(0, dy).is_some())
+            .filter_map(move |(dx, dy)| open(dx, dy).map(|next| (next, 14)));
+        straight.chain(diagonal)
+    }
+
+    /// Relabels the regions around a cell whose openness may have changed.
+    fn repair(&mut self, index: usize, was_open: bool) {
+        let open = self.is_open(index);
+        if open == was_open {
+            return;
+        }
+        if open {
+            // The cell joins, and may merge, the regions around it
+            self.flood(index);
+        } else {
+            // The cell's region may split; relabel each side separately
+            self.regions[index] = 0;
+            let sides: Vec<usize> = self.steps(index).map(|(next, _)| next).collect();
+            let mut relabelled = Vec::new();
+            for side in sides {
+                if !relabelled.contains(&self.regions[side]) {
+                    relabelled.push(self.flood(side));
+                }
+            }
+        }
+    }
+
+    /// Labels the open cells connected to `index` with a new region.
+    fn flood(&mut self, index: usize) -> u32 {
+        let region = self.next_region;
+        self.next_region += 1;
+        self.regions[index] = region;
+        let mut stack = vec![index];
+        while let Some(cell) = stack.pop() {
+            let next: Vec<usize> = self.steps(cell).map(|(next, _)| next).collect();
+            for next in next {
+                if self.regions[next] != region {
+                    self.regions[next] = region;
+                    stack.push(next);
+                }
+            }
+        }
+        region
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::Style;
+
+    fn grid(rows: &[&str]) -> NavGrid {
+        let palette = vec![Tile::new('.', Style::new()), Tile::new('#', Style::new())];
+        let map = TileMap::parse(&rows.join("\n"), palette).unwrap();
+        NavGrid::from_tilemap(&map, |tile| tile.ch == '.')
+    }
+
+    #[test]
+    fn test_find_path_around_walls() {
+        let nav = grid(&[
+            ".....", //
+            ".###.", //
+            "...#.", //
+        ]);
+        let path = nav.find_path((0, 2), (4, 2)).unwrap();
+        assert_eq!(path.first(), Some(&(0, 2)));
+        assert_eq!(path.last(), Some(&(4, 2)));
+        assert_eq!(path.len(), 9);
+        assert!(!nav.is_blocked(&path));
+        assert_eq!(nav.find_path((0, 0), (1, 1)), None);
+
+        // Diagonal steps may not cut the corners of walls
+        let diagonal = nav.clone().with_diagonals();
+        assert_eq!(diagonal.find_path((0, 2), (4, 2)).unwrap().len(), 9);
+        let open = grid(&["...", "...", "..."]).with_diagonals();
+        assert_eq!(
+            open.find_path((0, 0), (2, 2)).unwrap(),
+            [(0, 0), (1, 1), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn test_obstacles_split_and_merge_regions() {
+        let mut nav = grid(&[
+            "..#..", //
+            ".....", //
+            "..#..", //
+        ]);
+        assert!(nav.connected((0, 0), (4, 2)));
+        nav.add_obstacle(2, 1);
+        assert!(!nav.connected((0, 0), (4, 2)));
+        assert!(nav.connected((0, 0), (1, 2)));
+        assert_eq!(nav.find_path((0, 1), (4, 1)), None);
+
+        // Stacked obstacles keep the cell closed until both are gone
+        nav.add_obstacle(2, 1);
+        nav.remove_obstacle(2, 1);
+        assert!(!nav.is_walkable(2, 1));
+        nav.remove_obstacle(2, 1);
+        assert!(nav.connected((0, 0), (4, 2)));
+        assert_eq!(nav.find_path((0, 1), (4, 1)).unwrap().len(), 5);
+
+        nav.set_walkable(2, 0, true);
+        nav.set_walkable(2, 1, false);
+        assert!(nav.connected((0, 1), (4, 1)));
+    }
+
+    #[test]
+    fn test_queued_requests() {
+        let mut nav = grid(&["....", "##.#", "...."]);
+        let first = nav.request((0, 0), (0, 2));
+        let second = nav.request((0, 0), (0, 2));
+        let blocked = nav.request((0, 0), (0, 1));
+        assert_eq!(nav.take(first), None);
+
+        nav.process(2);
+        assert_eq!(nav.pending(), 1);
+        assert_eq!(nav.status(blocked), Some(&PathStatus::Pending));
+        let Some(PathStatus::Found(path)) = nav.take(first) else {
+            panic!("path not found");
+        };
+        assert_eq!(path.len(), 7);
+        assert_eq!(nav.take(second), Some(PathStatus::Found(path)));
+        assert_eq!(nav.take(first), None);
+
+        nav.process(10);
+        assert_eq!(nav.take(blocked), Some(PathStatus::NoPath));
+    }
+}
@@ -1,16 +1,496 @@
-use crate::renderer::Renderer;
+use crate::autosave::SaveData;
+use crate::camera::Bounds;
+use crate::config::ErrorPolicy;
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
 use crossterm::event::Event;
 
+mod attract;
 mod container;
+mod context_menu;
+mod credits;
+mod file_picker;
+mod floating_text;
+mod hosted;
+mod inventory_grid;
+mod launcher;
+mod layers;
+mod level_editor;
+mod loading_screen;
+mod node_tree;
+mod panes;
+mod parallel_group;
+mod pause_overlay;
+mod replay_inspector;
+mod retained;
+mod schedule;
+mod settings_menu;
+mod setup_wizard;
+mod story_player;
+mod tabs;
+mod title_screen;
+mod toasts;
+mod touch_controls;
+mod weather;
+pub use attract::Attract;
 pub use container::Container;
+pub use context_menu::{ContextMenu, MenuItem};
+pub use credits::Credits;
+pub use file_picker::FilePicker;
+pub use floating_text::{FloatingText, Popup};
+pub use inventory_grid::{InventoryDrag, InventoryGrid};
+pub use launcher::Launcher;
+pub use layers::Layers;
+pub use level_editor::{EditorTool, LevelEditor};
+pub use loading_screen::{Assets, LoadingScreen};
+pub use node_tree::{NodeId, NodeTree, Siblings};
+pub use panes::Panes;
+pub use parallel_group::{ParallelGroup, ParallelUpdate};
+pub use pause_overlay::PauseOverlay;
+pub use replay_inspector::ReplayInspector;
+pub use retained::Retained;
+pub use schedule::UpdateSchedule;
+pub use settings_menu::SettingsMenu;
+pub use setup_wizard::SetupWizard;
+pub use story_player::StoryPlayer;
+pub use tabs::Tabs;
+pub use title_screen::TitleScreen;
+pub use toasts::{ToastPlacement, Toasts};
+pub use touch_controls::TouchControls;
+pub use weather::{Weather, WeatherKind};
 
+/// Derives [`Node`] by delegating to child fields; see `coil_derive` for the attributes.
+#[cfg(feature = "derive")]
+pub use coil_derive::Node;
+
+/// A part of the game tree driven by the event loop.
+///
+/// Recoverable errors returned from `update`, `on_event` and `render`, such
+/// as [`EngineError::recoverable`] ones, are handed to [`Node::on_error`]
+/// and the configured [`ErrorPolicy`]; others stop the game.
 pub trait Node {
     /// Called once per fixed‐timestep tick
-    fn update(&mut self, dt: f32);
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError>;
 
     /// Called for each input event; return `true` to consume it
-    fn on_event(&mut self, ev: Event) -> bool;
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError>;
+
+    /// Returns how many ticks pass between updates of this node, e.g. 10
+    /// for background ambience or 3 for AI far from the player.
+    ///
+    /// The engine's containers skip their children's updates in between and
+    /// pass the time since the last one as `dt`; so does the event loop for
+    /// the root node. Your own containers can use an [`UpdateSchedule`]. Asked every
+    /// tick, so it can change with the level of detail; 1 updates every tick.
+    fn update_interval(&self) -> u32 {
+        1
+    }
+
+    /// Returns the area of the world the node draws into, so the world
+    /// layer of [`Layers`] can skip it while the camera does not show it.
+    ///
+    /// `None`, the default, opts out: the node is always drawn.
+    fn world_bounds(&self) -> Option<Bounds> {
+        None
+    }
+
+    /// Called for each notification emitted by the engine itself.
+    fn on_engine_event(&mut self, _ctx: &mut Context, _ev: &EngineEvent) {}
+
+    /// Called when the game is paused, after which fixed updates stop.
+    fn on_pause(&mut self, _ctx: &mut Context) {}
+
+    /// Called when the game is resumed.
+    fn on_resume(&mut self, _ctx: &mut Context) {}
+
+    /// Called when the viewport is resized, with its new size; the viewport
+    /// and the renderer's buffers already have it.
+    fn on_resize(&mut self, _ctx: &mut Context, _width: u16, _height: u16) {}
+
+    /// Called when the game is autosaved; write the state to keep into `save`.
+    fn on_save(&self, _save: &mut SaveData) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    /// Called for each recoverable error raised while running the tree.
+    ///
+    /// Return `Some` to override the configured [`ErrorPolicy`] for this error.
+    fn on_error(&mut self, _ctx: &mut Context, _err: &EngineError) -> Option<ErrorPolicy> {
+        None
+    }
+
+    /// Returns whether the node would draw something different than the
+    /// last time it was drawn, so [`Retained`] cannot reuse that drawing.
+    ///
+    /// Nodes that do not keep track are always dirty.
+    fn is_dirty(&self) -> bool {
+        true
+    }
+
+    /// Called by [`Retained`] after it kept what the node drew; the node is
+    /// clean until it changes again.
+    fn mark_clean(&mut self) {}
+
+    /// Draw yourself into the frame assigned to this node.  Children drawn automatically.
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError>;
+}
+
+/// A [`Node`] whose hooks never fail.
+///
+/// Implement it instead of [`Node`] for nodes that have no errors to
+/// report: its hooks are those of [`Node`] with the same parameters and
+/// defaults, only without the `Result` return types. Every hook is forwarded
+/// to the [`Node`] implementation.
+pub trait InfallibleNode {
+    /// Called once per fixed‐timestep tick
+    fn update(&mut self, ctx: &mut Context, dt: f32);
+
+    /// Called for each input event; return `true` to consume it
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> bool;
+
+    /// See [`Node::update_interval`].
+    fn update_interval(&self) -> u32 {
+        1
+    }
+
+    /// See [`Node::world_bounds`].
+    fn world_bounds(&self) -> Option<Bounds> {
+        None
+    }
+
+    /// Called for each notification emitted by the engine itself.
+    fn on_engine_event(&mut self, _ctx: &mut Context, _ev: &EngineEvent) {}
+
+    /// Called when the game is paused, after which fixed updates stop.
+    fn on_pause(&mut self, _ctx: &mut Context) {}
+
+    /// Called when the game is resumed.
+    fn on_resume(&mut self, _ctx: &mut Context) {}
+
+    /// Called when the viewport is resized, with its new size.
+    fn on_resize(&mut self, _ctx: &mut Context, _width: u16, _height: u16) {}
+
+    /// Called when the game is autosaved; write the state to keep into `save`.
+    fn on_save(&self, _save: &mut SaveData) {}
+
+    /// See [`Node::on_error`].
+    fn on_error(&mut self, _ctx: &mut Context, _err: &EngineError) -> Option<ErrorPolicy> {
+        None
+    }
+
+    /// See [`Node::is_dirty`].
+    fn is_dirty(&self) -> bool {
+        true
+    }
+
+    /// See [`Node::mark_clean`].
+    fn mark_clean(&mut self) {}
+
+    /// Draw yourself into the frame assigned to this node.
+    fn render(&self, frame: &mut Frame<'_>);
+}
+
+impl<T: InfallibleNode> Node for T {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        InfallibleNode::update(self, ctx, dt);
+        Ok(())
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        Ok(InfallibleNode::on_event(self, ctx, ev))
+    }
+
+    fn update_interval(&self) -> u32 {
+        InfallibleNode::update_interval(self)
+    }
+
+    fn world_bounds(&self) -> Option<Bounds> {
+        InfallibleNode::world_bounds(self)
+    }
+
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        InfallibleNode::on_engine_event(self, ctx, ev);
+    }
+
+    fn on_pause(&mut self, ctx: &mut Context) {
+        InfallibleNode::on_pause(self, ctx);
+    }
+
+    fn on_resume(&mut self, ctx: &mut Context) {
+        InfallibleNode::on_resume(self, ctx);
+    }
+
+    fn on_resize(&mut self, ctx: &mut Context, width: u16, height: u16) {
+        InfallibleNode::on_resize(self, ctx, width, height);
+    }
+
+    fn on_save(&self, save: &mut SaveData) -> Result<(), EngineError> {
+        InfallibleNode::on_save(self, save);
+        Ok(())
+    }
+
+    fn on_error(&mut self, ctx: &mut Context, err: &EngineError) -> Option<ErrorPolicy> {
+        InfallibleNode::on_error(self, ctx, err)
+    }
+
+    fn is_dirty(&self) -> bool {
+        InfallibleNode::is_dirty(self)
+    }
+
+    fn mark_clean(&mut self) {
+        InfallibleNode::mark_clean(self);
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        InfallibleNode::render(self, frame);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    struct Counter {
+        ticks: u32,
+    }
+
+    impl InfallibleNode for Counter {
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) {
+            self.ticks += 1;
+        }
+
+        fn on_event(&mut self, _ctx: &mut Context, ev: &Event) -> bool {
+            matches!(ev, Event::Key(key) if key.code == KeyCode::Esc)
+        }
+
+        fn update_interval(&self) -> u32 {
+            4
+        }
+
+        fn on_save(&self, save: &mut SaveData) {
+            save.insert("counter", self.ticks.to_string());
+        }
+
+        fn render(&self, _frame: &mut Frame<'_>) {}
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derive_node_delegates_to_children() {
+        use std::cell::RefCell;
+
+        thread_local! {
+            static LOG: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+        }
+
+        struct Probe(&'static str, bool);
+
+        impl Node for Probe {
+            fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+                LOG.with(|log| log.borrow_mut().push(self.0));
+                Ok(())
+            }
+
+            fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+                LOG.with(|log| log.borrow_mut().push(self.0));
+                Ok(self.1)
+            }
+
+            fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+                LOG.with(|log| log.borrow_mut().push(self.0));
+                Ok(())
+            }
+        }
+
+        #[derive(Node)]
+        #[node(update = Self::tick, render = Self::draw)]
+        struct Composite {
+            #[node(child, render_order = 1)]
+            front: Probe,
+            #[node(child, event_priority = 5)]
+            back: Probe,
+            #[allow(dead_code)]
+            plain: u32,
+        }
+
+        impl Composite {
+            fn tick(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+                LOG.with(|log| log.borrow_mut().push("self"));
+                Ok(())
+            }
+
+            fn draw(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+                LOG.with(|log| log.borrow_mut().push("self"));
+                Ok(())
+            }
+        }
+
+        let take = || LOG.with(|log| std::mem::take(&mut *log.borrow_mut()));
+        let mut ctx = Context::new(GameConfig::new());
+        let mut node = Composite {
+            front: Probe("front", false),
+            back: Probe("back", true),
+            plain: 0,
+        };
+
+        node.update(&mut ctx, 0.1).unwrap();
+        assert_eq!(take(), ["front", "back", "self"]);
+
+        let esc = Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(node.on_event(&mut ctx, &esc).unwrap());
+        assert_eq!(take(), ["back"]);
+
+        let mut renderer = crate::renderer::BasicRenderer::new(4, 4).unwrap();
+        let area = crate::geometry::Rect::new(0, 0, 4, 4);
+        node.render(&mut Frame::new(&mut renderer, area, 0.0))
+            .unwrap();
+        assert_eq!(take(), ["self", "back", "front"]);
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derive_node_honors_update_intervals() {
+        struct Slow {
+            interval: u32,
+            updates: u32,
+        }
+
+        impl Node for Slow {
+            fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+                self.updates += 1;
+                Ok(())
+            }
+
+            fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+                Ok(false)
+            }
+
+            fn update_interval(&self) -> u32 {
+                self.interval
+            }
+
+            fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+                Ok(())
+            }
+        }
+
+        #[derive(Node)]
+        struct Scheduled {
+            #[node(child)]
+            fast: Slow,
+            #[node(child)]
+            slow: Slow,
+            #[node(schedules)]
+            schedules: Vec<UpdateSchedule>,
+        }
+
+        let mut ctx = Context::new(GameConfig::new());
+        let mut node = Scheduled {
+            fast: Slow {
+                interval: 1,
+                updates: 0,
+            },
+            slow: Slow {
+                interval: 3,
+                updates: 0,
+            },
+            schedules: Vec::new(),
+        };
+        for _ in 0..6 {
+            node.update(&mut ctx, 0.1).unwrap();
+        }
+        assert_eq!((node.fast.updates, node.slow.updates), (6, 2));
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derive_node_forwards_dirty_tracking() {
+        struct Still(Bounds, bool);
+
+        impl Node for Still {
+            fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+                Ok(())
+            }
+
+            fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+                Ok(false)
+            }
+
+            fn is_dirty(&self) -> bool {
+                self.1
+            }
+
+            fn mark_clean(&mut self) {
+                self.1 = false;
+            }
+
+            fn world_bounds(&self) -> Option<Bounds> {
+                Some(self.0)
+            }
+
+            fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+                Ok(())
+            }
+        }
+
+        #[derive(Node)]
+        struct Pair {
+            #[node(child)]
+            left: Still,
+            #[node(child)]
+            right: Still,
+        }
+
+        #[derive(Node)]
+        #[node(render = Self::draw)]
+        struct Drawn {
+            #[node(child)]
+            inner: Still,
+        }
+
+        impl Drawn {
+            fn draw(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+                Ok(())
+            }
+        }
+
+        let mut pair = Pair {
+            left: Still(Bounds::new(0, 0, 2, 1), false),
+            right: Still(Bounds::new(4, 2, 1, 1), true),
+        };
+        assert!(pair.is_dirty());
+        pair.mark_clean();
+        assert!(!pair.is_dirty());
+        assert_eq!(pair.world_bounds(), Some(Bounds::new(0, 0, 5, 3)));
+
+        // Its own drawing is not tracked, so it always counts as changed
+        let drawn = Drawn {
+            inner: Still(Bounds::new(0, 0, 1, 1), false),
+        };
+        assert!(drawn.is_dirty());
+        assert_eq!(drawn.world_bounds(), None);
+    }
+
+    #[test]
+    fn test_infallible_node_shim() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut node: Box<dyn Node> = Box::new(Counter { ticks: 0 });
+
+        assert!(node.update(&mut ctx, 0.1).is_ok());
+        let esc = Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(node.on_event(&mut ctx, &esc).unwrap());
+        let other = Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(!node.on_event(&mut ctx, &other).unwrap());
 
-    /// Draw yourself into the given renderer.  Children drawn automatically.
-    fn render(&self, r: &mut dyn Renderer);
+        // The other hooks are forwarded too
+        assert_eq!(node.update_interval(), 4);
+        assert!(node.is_dirty());
+        let mut save = SaveData::new();
+        node.on_save(&mut save).unwrap();
+        assert_eq!(save.get("counter"), Some("1"));
+    }
 }
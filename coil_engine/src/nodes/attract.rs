@@ -0,0 +1,212 @@
+use crate::autosave::SaveData;
+use crate::config::{ErrorPolicy, GameConfig};
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::nodes::Node;
+use crate::nodes::hosted::Hosted;
+use crate::replay::{self, Replay};
+use crossterm::event::{Event, KeyEventKind};
+
+type Factory = Box<dyn Fn() -> Box<dyn Node>>;
+
+/// A recording being played by a fresh instance of the game.
+struct Demo {
+    game: Hosted,
+    tick: u64,
+}
+
+/// A title menu that plays a recorded demo behind itself while the player
+/// is idle, as arcade machines do in attract mode.
+///
+/// The demo starts on [`EngineEvent::Idle`], so set the `idle_timeout`
+/// setting, or with [`Attract::start`]. It runs a new instance of the game
+/// fed with the keys of a [`Replay`] and starts over when the replay ends.
+/// Any key press or mouse event stops it and is not passed on, so the menu
+/// comes back without acting on the key that woke it.
+///
+/// The menu is updated and drawn all along, over the demo, and gets input
+/// while no demo is playing.
+pub struct Attract {
+    menu: Box<dyn Node>,
+    create: Factory,
+    config: GameConfig,
+    replay: Replay,
+    demo: Option<Demo>,
+}
+
+impl Attract {
+    /// Creates a menu whose demo plays `replay` in instances made by `create`.
+    pub fn new<M, N, F>(menu: M, replay: Replay, create: F) -> Self
+    where
+        M: Node + 'static,
+        N: Node + 'static,
+        F: Fn() -> N + 'static,
+    {
+        Self {
+            menu: Box::new(menu),
+            create: Box::new(move || Box::new(create())),
+            config: GameConfig::new(),
+            replay,
+            demo: None,
+        }
+    }
+
+    /// Runs the demo with `config`, e.g. with sound turned off.
+    pub fn with_config(mut self, config: GameConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn menu(&self) -> &dyn Node {
+        self.menu.as_ref()
+    }
+
+    pub fn menu_mut(&mut self) -> &mut (dyn Node + 'static) {
+        self.menu.as_mut()
+    }
+
+    /// Returns whether the demo is playing.
+    pub fn is_playing(&self) -> bool {
+        self.demo.is_some()
+    }
+
+    /// Plays the demo from the start.
+    pub fn start(&mut self, ctx: &mut Context) {
+        self.demo = Some(Demo {
+            game: Hosted::new((self.create)(), self.config.clone(), ctx.screen_size()),
+            tick: 0,
+        });
+    }
+
+    /// Stops the demo, going back to the menu alone.
+    pub fn stop(&mut self) {
+        self.demo = None;
+    }
+}
+
+impl Node for Attract {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        if let Some(demo) = &mut self.demo {
+            demo.game.resize(ctx.screen_size());
+            for key in self.replay.keys_at(demo.tick) {
+                demo.game
+                    .node
+                    .on_event(&mut demo.game.ctx, &replay::key_event(key))?;
+            }
+            demo.game.update(dt)?;
+            demo.game.settle();
+            demo.tick += 1;
+            if demo.tick >= self.replay.length() || !demo.game.is_running() {
+                self.start(ctx);
+            }
+        }
+        self.menu.update(ctx, dt)
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        if self.demo.is_some() {
+            match ev {
+                Event::Key(key) if key.kind != KeyEventKind::Release => {
+                    self.stop();
+                    return Ok(true);
+                }
+                Event::Mouse(_) => {
+                    self.stop();
+                    return Ok(true);
+                }
+                _ => {}
+            }
+        }
+        self.menu.on_event(ctx, ev)
+    }
+
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        if let EngineEvent::Idle = ev
+            && self.demo.is_none()
+        {
+            self.start(ctx);
+        }
+        self.menu.on_engine_event(ctx, ev);
+    }
+
+    fn on_pause(&mut self, ctx: &mut Context) {
+        self.menu.on_pause(ctx);
+    }
+
+    fn on_resume(&mut self, ctx: &mut Context) {
+        self.menu.on_resume(ctx);
+    }
+
+    fn on_resize(&mut self, ctx: &mut Context, width: u16, height: u16) {
+        self.menu.on_resize(ctx, width, height);
+    }
+
+    fn on_save(&self, save: &mut SaveData) -> Result<(), EngineError> {
+        self.menu.on_save(save)
+    }
+
+    fn on_error(&mut self, ctx: &mut Context, err: &EngineError) -> Option<ErrorPolicy> {
+        self.menu.on_error(ctx, err)
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        if let Some(demo) = &self.demo {
+            demo.game.node.render(frame)?;
+        }
+        self.menu.render(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Logs the keys it gets.
+    struct Keys(Rc<RefCell<String>>);
+
+    impl Node for Keys {
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+            Ok(())
+        }
+
+        fn on_event(&mut self, _ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+            if let Event::Key(key) = ev {
+                self.0.borrow_mut().push_str(&key.code.to_string());
+            }
+            Ok(true)
+        }
+
+        fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_demo_plays_until_input() {
+        let (menu_log, demo_log) = (Rc::default(), Rc::new(RefCell::new(String::new())));
+        let log = Rc::clone(&demo_log);
+        let replay = Replay::parse("0 a\n2 b\n3 end").unwrap();
+        let mut attract = Attract::new(Keys(Rc::clone(&menu_log)), replay, move || {
+            Keys(Rc::clone(&log))
+        });
+        let mut ctx = Context::new(GameConfig::new());
+        attract.on_engine_event(&mut ctx, &EngineEvent::Idle);
+        assert!(attract.is_playing());
+        for _ in 0..4 {
+            attract.update(&mut ctx, 0.1).unwrap();
+        }
+        // The replay ends after three ticks and starts over
+        assert_eq!(demo_log.borrow().as_str(), "aba");
+
+        let key = Event::Key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE));
+        assert!(attract.on_event(&mut ctx, &key).unwrap());
+        assert!(!attract.is_playing());
+        assert_eq!(menu_log.borrow().as_str(), "");
+        attract.on_event(&mut ctx, &key).unwrap();
+        assert_eq!(menu_log.borrow().as_str(), "x");
+    }
+}
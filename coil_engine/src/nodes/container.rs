@@ -1,35 +1,88 @@
-use crate::nodes::Node;
-use crate::renderer::Renderer;
+use crate::autosave::SaveData;
+use crate::config::ErrorPolicy;
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::nodes::{Node, UpdateSchedule};
 use crossterm::event::Event;
 
 /// A container node that can hold multiple child nodes.
+///
+/// Children are updated at their [`update_interval`](Node::update_interval).
 pub struct Container {
     pub x: u16,
     pub y: u16,
-    pub children: Vec<Box<dyn Node>>,
+    /// Children with their update schedules, which move along with them
+    children: Vec<(Box<dyn Node>, UpdateSchedule)>,
 }
 
 impl Node for Container {
-    fn update(&mut self, dt: f32) {
-        for c in &mut self.children {
-            c.update(dt);
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        for (c, schedule) in &mut self.children {
+            schedule.tick(c.as_mut(), ctx, dt)?;
         }
+        Ok(())
     }
-    fn on_event(&mut self, ev: Event) -> bool {
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
         // first give children a chance
-        for c in self.children.iter_mut().rev() {
-            if c.on_event(ev.clone()) {
-                return true;
+        for c in self.children_mut().rev() {
+            if c.on_event(ctx, ev)? {
+                return Ok(true);
             }
         }
-        false
+        Ok(false)
     }
-    fn render(&self, r: &mut dyn Renderer) {
-        // push a translation, if you want…
-        for c in &self.children {
-            c.render(r);
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        for c in self.children_mut() {
+            c.on_engine_event(ctx, ev);
         }
     }
+    fn on_pause(&mut self, ctx: &mut Context) {
+        for c in self.children_mut() {
+            c.on_pause(ctx);
+        }
+    }
+    fn on_resume(&mut self, ctx: &mut Context) {
+        for c in self.children_mut() {
+            c.on_resume(ctx);
+        }
+    }
+    fn on_resize(&mut self, ctx: &mut Context, width: u16, height: u16) {
+        for c in self.children_mut() {
+            c.on_resize(ctx, width, height);
+        }
+    }
+    fn on_save(&self, save: &mut SaveData) -> Result<(), EngineError> {
+        for c in self.children() {
+            c.on_save(save)?;
+        }
+        Ok(())
+    }
+    fn on_error(&mut self, ctx: &mut Context, err: &EngineError) -> Option<ErrorPolicy> {
+        // the first child with an opinion decides
+        self.children_mut().find_map(|c| c.on_error(ctx, err))
+    }
+    fn is_dirty(&self) -> bool {
+        self.children().any(|c| c.is_dirty())
+    }
+    fn mark_clean(&mut self) {
+        self.children_mut().for_each(|c| c.mark_clean());
+    }
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        // children draw relative to the container's offset
+        let (width, height) = frame.size();
+        let area = Rect::new(
+            self.x,
+            self.y,
+            width.saturating_sub(self.x),
+            height.saturating_sub(self.y),
+        );
+        for c in self.children() {
+            c.render(&mut frame.child(area))?;
+        }
+        Ok(())
+    }
 }
 
 impl Container {
@@ -43,7 +96,100 @@ impl Container {
 
     /// Pushes a child and returns `self`, so you can chain.
     pub fn with_child<N: Node + 'static>(mut self, child: N) -> Self {
-        self.children.push(Box::new(child));
+        self.push(Box::new(child));
         self
     }
+
+    /// Adds a child, drawn over the others.
+    pub fn push(&mut self, child: Box<dyn Node>) {
+        self.children.push((child, UpdateSchedule::default()));
+    }
+
+    /// Adds a child at `index`, shifting the ones after it.
+    pub fn insert(&mut self, index: usize, child: Box<dyn Node>) {
+        self.children
+            .insert(index, (child, UpdateSchedule::default()));
+    }
+
+    /// Removes and returns the child at `index`.
+    pub fn remove(&mut self, index: usize) -> Box<dyn Node> {
+        self.children.remove(index).0
+    }
+
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Returns the children, from the bottom one up.
+    pub fn children(&self) -> impl DoubleEndedIterator<Item = &dyn Node> {
+        self.children.iter().map(|(c, _)| c.as_ref())
+    }
+
+    /// Returns the children mutably, from the bottom one up.
+    pub fn children_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut Box<dyn Node>> {
+        self.children.iter_mut().map(|(c, _)| c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Counts its updates, which are due every `interval` ticks.
+    struct Counter {
+        interval: u32,
+        updates: Rc<Cell<u32>>,
+    }
+
+    impl Node for Counter {
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+            self.updates.set(self.updates.get() + 1);
+            Ok(())
+        }
+
+        fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+            Ok(false)
+        }
+
+        fn update_interval(&self) -> u32 {
+            self.interval
+        }
+
+        fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_schedules_follow_children() {
+        let mut ctx = Context::new(GameConfig::new());
+        let slow = Rc::new(Cell::new(0));
+        let mut container = Container::new(0, 0)
+            .with_child(Counter {
+                interval: 1,
+                updates: Rc::default(),
+            })
+            .with_child(Counter {
+                interval: 3,
+                updates: slow.clone(),
+            });
+        container.update(&mut ctx, 0.1).unwrap();
+        assert_eq!(slow.get(), 1);
+
+        // The slow child keeps its own schedule after moving down
+        container.remove(0);
+        assert_eq!(container.len(), 1);
+        container.update(&mut ctx, 0.1).unwrap();
+        container.update(&mut ctx, 0.1).unwrap();
+        assert_eq!(slow.get(), 1);
+        container.update(&mut ctx, 0.1).unwrap();
+        assert_eq!(slow.get(), 2);
+    }
 }
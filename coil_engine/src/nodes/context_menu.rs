@@ -0,0 +1,500 @@
+use crate::actions::KeyBinding;
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::hit::HitId;
+use crate::nodes::Node;
+use crate::style::Style;
+use crossterm::event::{Event, KeyCode, KeyEventKind, MouseButton, MouseEventKind};
+
+/// An entry of a [`ContextMenu`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuItem {
+    /// Resolves to `action` when chosen.
+    Action {
+        label: String,
+        action: String,
+        enabled: bool,
+    },
+    /// Opens a nested menu.
+    Submenu { label: String, items: Vec<MenuItem> },
+    /// A line between groups of entries.
+    Separator,
+}
+
+impl MenuItem {
+    pub fn action(label: impl Into<String>, action: impl Into<String>) -> Self {
+        Self::Action {
+            label: label.into(),
+            action: action.into(),
+            enabled: true,
+        }
+    }
+
+    pub fn submenu(label: impl Into<String>, items: Vec<MenuItem>) -> Self {
+        Self::Submenu {
+            label: label.into(),
+            items,
+        }
+    }
+
+    /// Greys out an action so it shows but cannot be chosen.
+    pub fn disabled(mut self) -> Self {
+        if let Self::Action { enabled, .. } = &mut self {
+            *enabled = false;
+        }
+        self
+    }
+
+    fn label(&self) -> &str {
+        match self {
+            Self::Action { label, .. } | Self::Submenu { label, .. } => label,
+            Self::Separator => "",
+        }
+    }
+
+    fn selectable(&self) -> bool {
+        match self {
+            Self::Action { enabled, .. } => *enabled,
+            Self::Submenu { .. } => true,
+            Self::Separator => false,
+        }
+    }
+}
+
+/// An open menu level: the root menu or a submenu.
+#[derive(Debug, Clone, Copy)]
+struct Level {
+    selected: usize,
+    id: HitId,
+}
+
+/// A popup menu of actions, opened with a right click or a key.
+///
+/// Add it on top of the [`Layers::hud`](crate::nodes::Layers) so it sees
+/// input first and positions match the screen. Right clicks over a region
+/// given to [`ContextMenu::with_target`] open it at the cursor, the key of
+/// [`ContextMenu::with_key`] opens it at the anchor set with
+/// [`ContextMenu::set_anchor`], e.g. the selected tile, and
+/// [`ContextMenu::open`] opens it anywhere. Choosing an action closes the
+/// menu; poll the chosen action with [`ContextMenu::take_action`].
+///
+/// * Up/Down: select an entry
+/// * Right or Enter: open a submenu; Enter also chooses an action
+/// * Left or Esc: close the innermost submenu; Esc on the root menu closes it
+/// * Mouse: hover to select and open submenus, click to choose; clicking
+///   outside the menu closes it
+///
+/// While open the menu consumes all key and mouse events.
+pub struct ContextMenu {
+    items: Vec<MenuItem>,
+    /// Open levels, the root menu first; empty while closed
+    levels: Vec<Level>,
+    /// Where the root menu opens, in screen coordinates
+    position: (u16, u16),
+    anchor: (u16, u16),
+    target: Option<HitId>,
+    key: Option<KeyBinding>,
+    /// Whether the last mouse event was over the menu
+    pointer_inside: bool,
+    chosen: Option<String>,
+    style: Style,
+    selected_style: Style,
+    disabled_style: Style,
+}
+
+impl ContextMenu {
+    pub fn new(items: Vec<MenuItem>) -> Self {
+        Self {
+            items,
+            levels: Vec::new(),
+            position: (0, 0),
+            anchor: (0, 0),
+            target: None,
+            key: None,
+            pointer_inside: false,
+            chosen: None,
+            style: crate::style!(white on dark_blue),
+            selected_style: crate::style!(black on white),
+            disabled_style: crate::style!(dark_grey on dark_blue),
+        }
+    }
+
+    /// Opens the menu on right clicks over the hit region `target`.
+    pub fn with_target(mut self, target: HitId) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Opens the menu at the anchor when `key` is pressed.
+    pub fn with_key(mut self, key: impl Into<KeyBinding>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Replaces the entries, e.g. to match what was right-clicked; only
+    /// takes effect while the menu is closed.
+    pub fn set_items(&mut self, items: Vec<MenuItem>) {
+        if !self.is_open() {
+            self.items = items;
+        }
+    }
+
+    /// Sets where the key opens the menu, in screen coordinates.
+    pub fn set_anchor(&mut self, x: u16, y: u16) {
+        self.anchor = (x, y);
+    }
+
+    /// Opens the root menu with its top-left corner at (x,y), moved as
+    /// needed to stay on screen.
+    pub fn open(&mut self, ctx: &mut Context, x: u16, y: u16) {
+        self.position = (x, y);
+        self.pointer_inside = false;
+        self.levels.clear();
+        self.push_level();
+        ctx.announce(format!("Menu. {}", self.focused()));
+    }
+
+    pub fn close(&mut self) {
+        self.levels.clear();
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.levels.is_empty()
+    }
+
+    /// Returns the action chosen since the last call, if any.
+    pub fn take_action(&mut self) -> Option<String> {
+        self.chosen.take()
+    }
+
+    /// Returns the entries of the level at `depth`.
+    fn items(&self, depth: usize) -> &[MenuItem] {
+        let mut items = self.items.as_slice();
+        for level in &self.levels[..depth] {
+            match &items[level.selected] {
+                MenuItem::Submenu { items: nested, .. } => items = nested,
+                _ => unreachable!("only submenus open a level"),
+            }
+        }
+        items
+    }
+
+    /// Opens the submenu selected in the innermost level, or the root menu
+    /// if none is open, selecting its first selectable entry.
+    fn push_level(&mut self) {
+        let depth = self.levels.len();
+        self.levels.push(Level {
+            selected: 0,
+            id: HitId::new(),
+        });
+        if let Some(first) = self.items(depth).iter().position(MenuItem::selectable) {
+            self.levels[depth].selected = first;
+        }
+    }
+
+    fn selected_item(&self) -> Option<&MenuItem> {
+        let depth = self.levels.len().checked_sub(1)?;
+        self.items(depth).get(self.levels[depth].selected)
+    }
+
+    /// Returns the label of the selected entry.
+    fn focused(&self) -> String {
+        self.selected_item()
+            .map(|item| item.label().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Moves the selection of the innermost level by `step`, skipping
+    /// entries that cannot be selected.
+    fn select(&mut self, step: isize) {
+        let depth = self.levels.len() - 1;
+        let items = self.items(depth);
+        let count = items.len() as isize;
+        let mut index = self.levels[depth].selected as isize;
+        for _ in 0..count {
+            index = (index + step).rem_euclid(count);
+            if items[index as usize].selectable() {
+                self.levels[depth].selected = index as usize;
+                return;
+            }
+        }
+    }
+
+    /// Chooses the selected entry: opens a submenu or resolves an action.
+    fn activate(&mut self) {
+        match self.selected_item() {
+            Some(MenuItem::Submenu { .. }) => self.push_level(),
+            Some(MenuItem::Action {
+                action,
+                enabled: true,
+                ..
+            }) => {
+                self.chosen = Some(action.clone());
+                self.close();
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the area of every open level on a screen of `size`.
+    fn boxes(&self, (width, height): (u16, u16)) -> Vec<Rect> {
+        let mut boxes: Vec<Rect> = Vec::new();
+        for depth in 0..self.levels.len() {
+            let items = self.items(depth);
+            let inner = items
+                .iter()
+                .map(|item| item.label().chars().count())
+                .max()
+                .unwrap_or(0) as u16;
+            // A space on each side and room for the submenu marker
+            let box_width = (inner + 4).min(width);
+            let box_height = (items.len() as u16).min(height);
+            let (x, y) = match boxes.last() {
+                None => self.position,
+                Some(parent) => {
+                    let row = parent.y + self.levels[depth - 1].selected as u16;
+                    // Right of the parent, or left of it if there is no room
+                    if parent.right() + box_width <= width {
+                        (parent.right(), row)
+                    } else {
+                        (parent.x.saturating_sub(box_width), row)
+                    }
+                }
+            };
+            boxes.push(Rect::new(
+                x.min(width - box_width),
+                y.min(height - box_height),
+                box_width,
+                box_height,
+            ));
+        }
+        boxes
+    }
+
+    fn handle_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Up => self.select(-1),
+            KeyCode::Down => self.select(1),
+            KeyCode::Right if matches!(self.selected_item(), Some(MenuItem::Submenu { .. })) => {
+                self.push_level()
+            }
+            KeyCode::Enter => self.activate(),
+            KeyCode::Left if self.levels.len() > 1 => {
+                self.levels.pop();
+            }
+            KeyCode::Esc => {
+                self.levels.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Node for ContextMenu {
+    fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        match ev {
+            Event::Key(key) if key.kind == KeyEventKind::Release => Ok(self.is_open()),
+            Event::Key(key) if self.is_open() => {
+                let before = (self.levels.len(), self.focused());
+                self.handle_key(key.code);
+                if !self.is_open() {
+                    ctx.announce("Menu closed");
+                } else if (self.levels.len(), self.focused()) != before {
+                    ctx.announce(self.focused());
+                }
+                Ok(true)
+            }
+            Event::Key(key) if self.key.is_some_and(|binding| binding.matches(key)) => {
+                let (x, y) = self.anchor;
+                self.open(ctx, x, y);
+                Ok(true)
+            }
+            Event::Mouse(mouse) if self.is_open() => {
+                let inside = std::mem::take(&mut self.pointer_inside);
+                if !inside && matches!(mouse.kind, MouseEventKind::Down(_)) {
+                    self.close();
+                    ctx.announce("Menu closed");
+                }
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        let EngineEvent::Hit(hit) = ev else {
+            return;
+        };
+        if let Some(depth) = self.levels.iter().position(|level| level.id == hit.id) {
+            self.pointer_inside = true;
+            let row = hit.y as usize;
+            if !self.items(depth).get(row).is_some_and(MenuItem::selectable) {
+                return;
+            }
+            let opened = self.levels.len() > depth + 1 && self.levels[depth].selected == row;
+            if !opened {
+                self.levels.truncate(depth + 1);
+                self.levels[depth].selected = row;
+                if matches!(self.items(depth)[row], MenuItem::Submenu { .. }) {
+                    self.push_level();
+                }
+                ctx.announce(self.items(depth)[row].label().to_string());
+            }
+            if matches!(hit.event.kind, MouseEventKind::Down(MouseButton::Left)) {
+                self.levels.truncate(depth + 1);
+                self.activate();
+            }
+        } else if !self.is_open()
+            && Some(hit.id) == self.target
+            && hit.event.kind == MouseEventKind::Down(MouseButton::Right)
+        {
+            self.open(ctx, hit.event.column, hit.event.row);
+            // The click that opened the menu is not an outside click
+            self.pointer_inside = true;
+        }
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        for (depth, area) in self.boxes(frame.size()).into_iter().enumerate() {
+            let level = self.levels[depth];
+            frame.hit_region(level.id, area);
+            let inner = area.width.saturating_sub(4) as usize;
+            for (row, item) in self.items(depth).iter().enumerate() {
+                let y = area.y + row as u16;
+                if y >= area.bottom() {
+                    break;
+                }
+                let style = match item {
+                    _ if row == level.selected => self.selected_style,
+                    MenuItem::Action { enabled: false, .. } => self.disabled_style,
+                    _ => self.style,
+                };
+                let text = match item {
+                    MenuItem::Separator => "─".repeat(area.width as usize),
+                    MenuItem::Submenu { label, .. } => format!(" {:<1$} ▸ ", label, inner),
+                    MenuItem::Action { label, .. } => format!(" {:<1$}   ", label, inner),
+                };
+                let text: String = text.chars().take(area.width as usize).collect();
+                frame.draw_styled(area.x, y, &text, style)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::hit::{Hit, HitRegions};
+    use crate::renderer::TestRenderer;
+    use crossterm::event::{KeyEvent, KeyModifiers, MouseEvent};
+
+    fn menu() -> ContextMenu {
+        ContextMenu::new(vec![
+            MenuItem::action("Cut", "cut").disabled(),
+            MenuItem::action("Copy", "copy"),
+            MenuItem::Separator,
+            MenuItem::submenu(
+                "Paint",
+                vec![
+                    MenuItem::action("Wall", "paint_wall"),
+                    MenuItem::action("Water", "paint_water"),
+                ],
+            ),
+        ])
+    }
+
+    fn press(menu: &mut ContextMenu, ctx: &mut Context, code: KeyCode) -> bool {
+        menu.on_event(ctx, &Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+            .unwrap()
+    }
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    fn render(menu: &ContextMenu, regions: &mut HitRegions) -> TestRenderer {
+        let mut renderer = TestRenderer::new(20, 6);
+        regions.clear();
+        menu.render(
+            &mut Frame::new(&mut renderer, Rect::new(0, 0, 20, 6), 0.0).with_hit_regions(regions),
+        )
+        .unwrap();
+        renderer
+    }
+
+    #[test]
+    fn test_keyboard_navigates_submenus() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut menu = menu().with_key(KeyCode::Char('m'));
+        menu.set_anchor(1, 1);
+        assert!(press(&mut menu, &mut ctx, KeyCode::Char('m')));
+
+        // Disabled entries and separators are skipped
+        assert_eq!(menu.focused(), "Copy");
+        press(&mut menu, &mut ctx, KeyCode::Down);
+        assert_eq!(menu.focused(), "Paint");
+        press(&mut menu, &mut ctx, KeyCode::Down);
+        assert_eq!(menu.focused(), "Copy");
+        press(&mut menu, &mut ctx, KeyCode::Up);
+        press(&mut menu, &mut ctx, KeyCode::Right);
+        press(&mut menu, &mut ctx, KeyCode::Down);
+
+        let renderer = render(&menu, &mut HitRegions::new());
+        assert_eq!(renderer.row(1), "  Cut");
+        assert_eq!(renderer.row(3), " ─────────");
+        // The submenu opens beside Paint
+        assert_eq!(renderer.row(4), "  Paint ▸  Wall");
+        assert_eq!(renderer.row(5), "           Water");
+
+        press(&mut menu, &mut ctx, KeyCode::Left);
+        assert_eq!(menu.focused(), "Paint");
+        press(&mut menu, &mut ctx, KeyCode::Enter);
+        press(&mut menu, &mut ctx, KeyCode::Enter);
+        assert!(!menu.is_open());
+        assert_eq!(menu.take_action().as_deref(), Some("paint_wall"));
+        assert_eq!(menu.take_action(), None);
+    }
+
+    #[test]
+    fn test_mouse_opens_chooses_and_dismisses() {
+        let mut ctx = Context::new(GameConfig::new());
+        let board = HitId::new();
+        let mut menu = menu().with_target(board);
+        let mut regions = HitRegions::new();
+
+        let right = mouse(MouseEventKind::Down(MouseButton::Right), 2, 0);
+        let hit = |id, x, y, event| EngineEvent::Hit(Hit { id, x, y, event });
+        menu.on_engine_event(&mut ctx, &hit(board, 2, 0, right));
+        assert!(menu.on_event(&mut ctx, &Event::Mouse(right)).unwrap());
+        assert!(menu.is_open());
+
+        // Hovering Paint opens its submenu, clicking Water chooses it
+        render(&menu, &mut regions);
+        let moved = mouse(MouseEventKind::Moved, 4, 3);
+        menu.on_engine_event(&mut ctx, &EngineEvent::Hit(regions.hit(moved).unwrap()));
+        render(&menu, &mut regions);
+        let click = mouse(MouseEventKind::Down(MouseButton::Left), 14, 4);
+        menu.on_engine_event(&mut ctx, &EngineEvent::Hit(regions.hit(click).unwrap()));
+        assert_eq!(menu.take_action().as_deref(), Some("paint_water"));
+
+        // Clicking outside closes the menu without an action
+        menu.open(&mut ctx, 0, 0);
+        let outside = mouse(MouseEventKind::Down(MouseButton::Left), 18, 5);
+        assert!(menu.on_event(&mut ctx, &Event::Mouse(outside)).unwrap());
+        assert!(!menu.is_open());
+        assert_eq!(menu.take_action(), None);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::color;
+use crate::context::Context;
+use crate::errors::EngineError;
+use crate::fade::ColorFade;
+use crate::frame::Frame;
+use crate::nodes::Node;
+use crate::style::Style;
+use crate::text;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use crossterm::style::Color;
+use std::path::Path;
+use std::time::Duration;
+
+/// Scroll speeds the player can pick, in lines per second
+const MIN_SPEED: f32 = 0.5;
+const MAX_SPEED: f32 = 16.0;
+/// Factor each Up/Down press changes the speed by
+const SPEED_STEP: f32 = 2.0;
+/// Rows at the top and bottom over which lines fade in and out
+const EDGE_ROWS: f32 = 3.0;
+const FADE_OUT: Duration = Duration::from_millis(800);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Title,
+    Heading,
+    Text,
+}
+
+/// Scrolling credits, as shown at the end of a game.
+///
+/// Credits are plain text, one centered line each: lines starting with `# `
+/// are titles, lines starting with `## ` headings such as roles, and other
+/// lines names or text. Blank lines are kept as spacing.
+///
+/// ```text
+/// # Snake
+///
+/// ## Design
+/// Ada Lovelace
+/// ```
+///
+/// The text scrolls up smoothly between updates, fading in at the bottom
+/// and out at the top. Down speeds it up and Up slows it down; Esc or Enter
+/// skips the rest. Once the last line has left the screen, or after a skip,
+/// the credits fade out and [`Credits::is_finished`] turns true, at which
+/// point the game moves on, or exits with [`Credits::with_exit`].
+pub struct Credits {
+    lines: Vec<(Kind, String)>,
+    speed: f32,
+    /// Rows scrolled before and after the last update
+    previous: f32,
+    scrolled: f32,
+    /// Fade out after the end; each line is dimmed by its progress, as
+    /// lines have styles of their own
+    fade: Option<ColorFade>,
+    exit_code: Option<i32>,
+    title_style: Style,
+    heading_style: Style,
+    text_style: Style,
+}
+
+impl Credits {
+    /// Creates credits from text in the credits format.
+    pub fn new(credits: &str) -> Self {
+        let lines = credits
+            .lines()
+            .map(|line| {
+                let line = line.trim();
+                if let Some(title) = line.strip_prefix("# ") {
+                    (Kind::Title, title.trim().to_string())
+                } else if let Some(heading) = line.strip_prefix("## ") {
+                    (Kind::Heading, heading.trim().to_string())
+                } else {
+                    (Kind::Text, line.to_string())
+                }
+            })
+            .collect();
+        Self {
+            lines,
+            speed: 2.0,
+            previous: 0.0,
+            scrolled: 0.0,
+            fade: None,
+            exit_code: None,
+            title_style: crate::style!(yellow on black),
+            heading_style: crate::style!(grey on black),
+            text_style: crate::style!(white on black),
+        }
+    }
+
+    /// Loads credits from a file in the credits format.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, EngineError> {
+        Ok(Self::new(&std::fs::read_to_string(path)?))
+    }
+
+    /// Sets the starting speed in lines per second.
+    pub fn with_speed(mut self, lines_per_second: f32) -> Self {
+        self.speed = lines_per_second.clamp(MIN_SPEED, MAX_SPEED);
+        self
+    }
+
+    /// Exits the game with `code` once the credits are over.
+    pub fn with_exit(mut self, code: i32) -> Self {
+        self.exit_code = Some(code);
+        self
+    }
+
+    /// Replaces the styles of titles, headings and other lines.
+    pub fn with_styles(mut self, title: Style, heading: Style, text: Style) -> Self {
+        self.title_style = title;
+        self.heading_style = heading;
+        self.text_style = text;
+        self
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Skips to the end, fading the credits out.
+    pub fn skip(&mut self) {
+        if self.fade.is_none() {
+            let visible = Style::new();
+            self.fade = Some(ColorFade::new(visible, visible, FADE_OUT));
+        }
+    }
+
+    /// Returns whether the credits have scrolled or been skipped to the end
+    /// and faded out.
+    pub fn is_finished(&self) -> bool {
+        self.fade.as_ref().is_some_and(ColorFade::is_finished)
+    }
+
+    fn style(&self, kind: Kind) -> Style {
+        match kind {
+            Kind::Title => self.title_style,
+            Kind::Heading => self.heading_style,
+            Kind::Text => self.text_style,
+        }
+    }
+}
+
+impl Node for Credits {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        self.previous = self.scrolled;
+        if let Some(fade) = &mut self.fade {
+            let finished = fade.is_finished();
+            fade.update(dt);
+            if !finished
+                && fade.is_finished()
+                && let Some(code) = self.exit_code
+            {
+                ctx.request_exit(code);
+            }
+            return Ok(());
+        }
+        // Rows to scroll until the last line has left the screen
+        let length = (ctx.viewport().height as usize + self.lines.len()) as f32;
+        self.scrolled += self.speed * dt;
+        if self.scrolled >= length {
+            self.skip();
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, _ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        let Event::Key(key) = ev else {
+            return Ok(false);
+        };
+        if key.kind == KeyEventKind::Release {
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::Down => self.speed = (self.speed * SPEED_STEP).min(MAX_SPEED),
+            KeyCode::Up => self.speed = (self.speed / SPEED_STEP).max(MIN_SPEED),
+            KeyCode::Esc | KeyCode::Enter => self.skip(),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        let (width, height) = frame.size();
+        let blank = " ".repeat(width as usize);
+        for y in 0..height {
+            frame.draw_styled(0, y, &blank, self.text_style)?;
+        }
+        let alpha = frame.alpha();
+        let scrolled = self.previous + (self.scrolled - self.previous) * alpha;
+        let fade_out = self.fade.as_ref().map_or(0.0, |fade| fade.progress(alpha));
+        for (index, (kind, line)) in self.lines.iter().enumerate() {
+            let y = height as f32 - scrolled + index as f32;
+            if y < 0.0 || y >= height as f32 || line.is_empty() {
+                continue;
+            }
+            // Dim lines towards the background near the edges
+            let edge = (y + 1.0).min(height as f32 - y) / EDGE_ROWS;
+            let dim = (1.0 - edge).clamp(0.0, 1.0).max(fade_out);
+            let style = self.style(*kind);
+            let background = match style.bg {
+                Color::Reset => Color::Black,
+                bg => bg,
+            };
+            let style = style.fg(color::mix(style.fg, background, dim));
+            let x = width.saturating_sub(text::text_width(line)) / 2;
+            frame.draw_styled(x, y as u16, line, style)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::geometry::Rect;
+    use crate::renderer::TestRenderer;
+    use crossterm::event::{KeyEvent, KeyModifiers};
+
+    #[test]
+    fn test_credits_scroll_and_finish() {
+        let mut credits = Credits::new("# Snake\n\n## Design\nAda").with_exit(0);
+        let mut ctx = Context::new(GameConfig::new());
+        ctx.set_viewport(Rect::new(0, 0, 12, 6));
+
+        // Two lines per second: after a second the title is two rows up
+        credits.update(&mut ctx, 1.0).unwrap();
+        let mut renderer = TestRenderer::new(12, 6);
+        credits
+            .render(&mut Frame::new(&mut renderer, Rect::new(0, 0, 12, 6), 1.0))
+            .unwrap();
+        assert_eq!(renderer.row(4), "   Snake");
+        assert_eq!(renderer.row(5), "");
+
+        let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+        assert!(credits.on_event(&mut ctx, &key(KeyCode::Down)).unwrap());
+        assert_eq!(credits.speed(), 4.0);
+        // 8 more rows at 4 lines per second, then the fade out
+        for _ in 0..3 {
+            credits.update(&mut ctx, 1.0).unwrap();
+        }
+        assert!(!credits.is_finished());
+        credits.update(&mut ctx, 1.0).unwrap();
+        assert!(credits.is_finished());
+        assert_eq!(ctx.exit_requested(), Some(0));
+    }
+}
@@ -0,0 +1,396 @@
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::hit::HitId;
+use crate::nodes::Node;
+use crate::style::Style;
+use crossterm::event::{Event, KeyCode, KeyEventKind, MouseButton, MouseEventKind};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const WIDTH: u16 = 40;
+const HEIGHT: u16 = 14;
+
+/// A row of the listing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Entry {
+    Parent,
+    Directory(String),
+    File(String),
+}
+
+impl Entry {
+    fn label(&self) -> String {
+        match self {
+            Entry::Parent => "../".to_string(),
+            Entry::Directory(name) => format!("{}/", name),
+            Entry::File(name) => name.clone(),
+        }
+    }
+}
+
+/// A modal dialog for choosing a file, such as a save, map or replay.
+///
+/// The picker browses one or more root directories and never leaves them.
+/// It lists subdirectories first, then the files with one of the accepted
+/// extensions (all files if none are set). Choosing a file closes the
+/// dialog; poll it with [`FilePicker::take_chosen`].
+///
+/// * Up/Down, PageUp/PageDown: select an entry
+/// * Enter: open the selected directory or choose the selected file
+/// * Backspace: go to the parent directory
+/// * Tab: switch to the next root
+/// * Esc: close without choosing
+/// * Mouse: click an entry to select it, click it again to open it
+///
+/// While open the picker consumes all key and mouse events, so add it on
+/// top of the [`Layers::hud`](crate::nodes::Layers).
+pub struct FilePicker {
+    title: String,
+    /// Root directories with their display names
+    roots: Vec<(String, PathBuf)>,
+    root: usize,
+    /// Current directory, relative to the root
+    directory: PathBuf,
+    extensions: Vec<String>,
+    entries: Vec<Entry>,
+    selected: usize,
+    /// Why the current directory could not be listed
+    error: Option<String>,
+    open: bool,
+    chosen: Option<PathBuf>,
+    list_id: HitId,
+    style: Style,
+    selected_style: Style,
+    error_style: Style,
+}
+
+impl FilePicker {
+    /// Creates a picker with the heading `title`, e.g. "Load game".
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            roots: Vec::new(),
+            root: 0,
+            directory: PathBuf::new(),
+            extensions: Vec::new(),
+            entries: Vec::new(),
+            selected: 0,
+            error: None,
+            open: false,
+            chosen: None,
+            list_id: HitId::new(),
+            style: crate::style!(white on dark_blue),
+            selected_style: crate::style!(black on white),
+            error_style: crate::style!(red on dark_blue),
+        }
+    }
+
+    /// Adds a root directory shown as `name`; the first one is browsed first.
+    pub fn with_root(mut self, name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        self.roots.push((name.into(), path.into()));
+        self
+    }
+
+    /// Only lists files with one of `extensions`, given without the dot.
+    pub fn with_extensions(mut self, extensions: &[&str]) -> Self {
+        self.extensions = extensions.iter().map(|e| e.to_ascii_lowercase()).collect();
+        self
+    }
+
+    /// Opens the dialog at the top of the current root.
+    pub fn open(&mut self, ctx: &mut Context) {
+        self.open = true;
+        self.directory = PathBuf::new();
+        self.refresh();
+        ctx.announce(format!("{}. {}", self.title, self.focused()));
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Returns the file chosen since the last call, if any.
+    pub fn take_chosen(&mut self) -> Option<PathBuf> {
+        self.chosen.take()
+    }
+
+    /// Returns the directory being listed.
+    pub fn directory(&self) -> Option<PathBuf> {
+        let (_, root) = self.roots.get(self.root)?;
+        Some(root.join(&self.directory))
+    }
+
+    fn accepts(&self, path: &Path) -> bool {
+        self.extensions.is_empty()
+            || path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| self.extensions.contains(&e.to_ascii_lowercase()))
+    }
+
+    /// Lists the current directory again.
+    fn refresh(&mut self) {
+        self.selected = 0;
+        self.entries.clear();
+        self.error = None;
+        let Some(path) = self.directory() else {
+            self.error = Some("No folders to browse".to_string());
+            return;
+        };
+        if self.directory.parent().is_some() {
+            self.entries.push(Entry::Parent);
+        }
+        let listing = match fs::read_dir(&path) {
+            Ok(listing) => listing,
+            Err(e) => {
+                self.error = Some(format!("Cannot read {}: {}", path.display(), e));
+                return;
+            }
+        };
+        let (mut directories, mut files) = (Vec::new(), Vec::new());
+        for entry in listing.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            match entry.file_type() {
+                Ok(kind) if kind.is_dir() => directories.push(Entry::Directory(name)),
+                Ok(_) if self.accepts(&entry.path()) => files.push(Entry::File(name)),
+                _ => {}
+            }
+        }
+        directories.sort_by_key(Entry::label);
+        files.sort_by_key(Entry::label);
+        self.entries.extend(directories);
+        self.entries.extend(files);
+    }
+
+    /// Returns the label of the selected entry.
+    fn focused(&self) -> String {
+        match (&self.error, self.entries.get(self.selected)) {
+            (Some(error), _) => error.clone(),
+            (None, Some(entry)) => entry.label(),
+            (None, None) => "Empty folder".to_string(),
+        }
+    }
+
+    fn activate(&mut self) {
+        match self.entries.get(self.selected).cloned() {
+            Some(Entry::Parent) => self.up(),
+            Some(Entry::Directory(name)) => {
+                self.directory.push(name);
+                self.refresh();
+            }
+            Some(Entry::File(name)) => {
+                self.chosen = self.directory().map(|directory| directory.join(name));
+                self.open = false;
+            }
+            None => {}
+        }
+    }
+
+    fn up(&mut self) {
+        if self.directory.pop() {
+            self.refresh();
+        }
+    }
+
+    /// Returns the number of entries shown at once.
+    fn rows(&self) -> usize {
+        HEIGHT as usize - 3
+    }
+
+    /// Returns the index of the first entry shown.
+    fn scroll(&self) -> usize {
+        self.selected.saturating_sub(self.rows() - 1)
+    }
+
+    fn handle_key(&mut self, code: KeyCode) {
+        let last = self.entries.len().saturating_sub(1);
+        match code {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(last),
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(self.rows()),
+            KeyCode::PageDown => self.selected = (self.selected + self.rows()).min(last),
+            KeyCode::Enter => self.activate(),
+            KeyCode::Backspace => self.up(),
+            KeyCode::Tab if self.roots.len() > 1 => {
+                self.root = (self.root + 1) % self.roots.len();
+                self.directory = PathBuf::new();
+                self.refresh();
+            }
+            KeyCode::Esc => self.close(),
+            _ => {}
+        }
+    }
+}
+
+impl Node for FilePicker {
+    fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        if !self.open {
+            return Ok(false);
+        }
+        if let Event::Key(key) = ev
+            && key.kind != KeyEventKind::Release
+        {
+            let before = (self.directory.clone(), self.focused());
+            self.handle_key(key.code);
+            if !self.open {
+                ctx.announce(format!("{} closed", self.title));
+            } else if (self.directory.clone(), self.focused()) != before {
+                ctx.announce(self.focused());
+            }
+        }
+        Ok(true)
+    }
+
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        if let EngineEvent::Hit(hit) = ev
+            && hit.id == self.list_id
+            && hit.event.kind == MouseEventKind::Down(MouseButton::Left)
+        {
+            let index = self.scroll() + hit.y as usize;
+            if index >= self.entries.len() {
+                return;
+            }
+            if index == self.selected {
+                self.activate();
+            } else {
+                self.selected = index;
+            }
+            ctx.announce(self.focused());
+        }
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        if !self.open {
+            return Ok(());
+        }
+        let (width, height) = frame.size();
+        let (box_width, box_height) = (WIDTH.min(width), HEIGHT.min(height));
+        let area = Rect::new(
+            width.saturating_sub(box_width) / 2,
+            height.saturating_sub(box_height) / 2,
+            box_width,
+            box_height,
+        );
+        let mut frame = frame.child(area);
+        let blank = " ".repeat(box_width as usize);
+        for row in 0..box_height {
+            frame.draw_styled(0, row, &blank, self.style)?;
+        }
+        let location = match self.roots.get(self.root) {
+            Some((name, _)) => Path::new(name).join(&self.directory),
+            None => PathBuf::new(),
+        };
+        frame.draw_styled(
+            1,
+            0,
+            &format!("{}: {}", self.title, location.display()),
+            self.style,
+        )?;
+
+        let inner = box_width.saturating_sub(2) as usize;
+        if let Some(error) = &self.error {
+            frame.draw_styled(1, 2, error, self.error_style)?;
+        }
+        frame.hit_region(self.list_id, Rect::new(0, 2, box_width, self.rows() as u16));
+        let scroll = self.scroll();
+        for (row, entry) in self
+            .entries
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(self.rows())
+        {
+            let style = if row == self.selected {
+                self.selected_style
+            } else {
+                self.style
+            };
+            let label: String = format!("{:<1$}", entry.label(), inner)
+                .chars()
+                .take(inner)
+                .collect();
+            frame.draw_styled(1, 2 + (row - scroll) as u16, &label, style)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crossterm::event::{KeyEvent, KeyModifiers};
+
+    fn press(picker: &mut FilePicker, ctx: &mut Context, code: KeyCode) {
+        assert!(
+            picker
+                .on_event(ctx, &Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+                .unwrap()
+        );
+    }
+
+    fn labels(picker: &FilePicker) -> Vec<String> {
+        picker.entries.iter().map(Entry::label).collect()
+    }
+
+    #[test]
+    fn test_browse_and_choose() {
+        let dir = std::env::temp_dir().join(format!("coil-picker-{}", std::process::id()));
+        fs::create_dir_all(dir.join("maps/caves")).unwrap();
+        for file in [
+            "notes.txt",
+            "maps/b.map",
+            "maps/a.MAP",
+            "maps/caves/deep.map",
+        ] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        let mut ctx = Context::new(GameConfig::new());
+        let mut picker = FilePicker::new("Open map")
+            .with_root("levels", &dir)
+            .with_extensions(&["map"]);
+        picker.open(&mut ctx);
+        assert_eq!(labels(&picker), ["maps/"]);
+
+        press(&mut picker, &mut ctx, KeyCode::Enter);
+        assert_eq!(labels(&picker), ["../", "caves/", "a.MAP", "b.map"]);
+        press(&mut picker, &mut ctx, KeyCode::Down);
+        press(&mut picker, &mut ctx, KeyCode::Enter);
+        assert_eq!(picker.directory(), Some(dir.join("maps/caves")));
+
+        // The root is as far up as it goes
+        press(&mut picker, &mut ctx, KeyCode::Backspace);
+        press(&mut picker, &mut ctx, KeyCode::Backspace);
+        press(&mut picker, &mut ctx, KeyCode::Backspace);
+        assert_eq!(picker.directory(), Some(dir.clone()));
+
+        press(&mut picker, &mut ctx, KeyCode::Enter);
+        press(&mut picker, &mut ctx, KeyCode::PageDown);
+        press(&mut picker, &mut ctx, KeyCode::Enter);
+        assert!(!picker.is_open());
+        assert_eq!(picker.take_chosen(), Some(dir.join("maps/b.map")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unreadable_directory_shows_error() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut picker = FilePicker::new("Load").with_root("saves", "/nonexistent/coil/saves");
+        picker.open(&mut ctx);
+        assert!(picker.focused().starts_with("Cannot read"));
+        press(&mut picker, &mut ctx, KeyCode::Enter);
+        press(&mut picker, &mut ctx, KeyCode::Esc);
+        assert!(!picker.is_open());
+        assert_eq!(picker.take_chosen(), None);
+    }
+}
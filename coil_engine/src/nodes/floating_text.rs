@@ -0,0 +1,210 @@
+use crate::color;
+use crate::context::Context;
+use crate::errors::EngineError;
+use crate::fade::ColorFade;
+use crate::frame::Frame;
+use crate::motion::SmoothPosition;
+use crate::nodes::Node;
+use crate::pool::{Pool, PoolStats};
+use crate::style::Style;
+use crossterm::event::Event;
+use std::time::Duration;
+
+/// A short text to pop up at a world position, e.g. a damage number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Popup {
+    pub x: f32,
+    pub y: f32,
+    pub text: String,
+    pub style: Style,
+}
+
+/// A popup on its way up.
+struct Floating {
+    text: String,
+    origin: (f32, f32),
+    position: SmoothPosition,
+    fade: ColorFade,
+    /// Seconds since the popup appeared
+    age: f32,
+}
+
+/// Shows popups that rise and fade out, such as damage numbers or pickups.
+///
+/// Add it to the world of [`Layers`](crate::nodes::Layers) so popups stay
+/// on the spot they were spawned at while the camera moves. Spawn popups
+/// from anywhere in the tree with [`Context::popup`], or directly with
+/// [`FloatingText::spawn`]. Texts are centered on their position and
+/// recycled through a [`Pool`]. With `reduced_motion` set they fade in place.
+pub struct FloatingText {
+    popups: Pool<Floating>,
+    /// Seconds a popup stays on screen
+    duration: f32,
+    /// Rows a popup rises over its lifetime
+    rise: f32,
+    still: bool,
+}
+
+impl FloatingText {
+    pub fn new() -> Self {
+        Self {
+            popups: Pool::new(|| Floating {
+                text: String::new(),
+                origin: (0.0, 0.0),
+                position: SmoothPosition::default(),
+                fade: ColorFade::new(Style::new(), Style::new(), Duration::from_secs(1)),
+                age: 0.0,
+            })
+            .with_capacity(256),
+            duration: 1.0,
+            rise: 2.0,
+            still: false,
+        }
+    }
+
+    /// Sets how long popups stay on screen.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration.as_secs_f32().max(f32::EPSILON);
+        self
+    }
+
+    /// Sets how many rows popups rise before disappearing.
+    pub fn with_rise(mut self, rows: f32) -> Self {
+        self.rise = rows;
+        self
+    }
+
+    /// Shows a popup; it is dropped if too many are on screen already.
+    pub fn spawn(&mut self, popup: Popup) {
+        let duration = Duration::from_secs_f32(self.duration);
+        // Fade the text out towards a dark shade of its own color
+        let faded = Style {
+            fg: color::darken(popup.style.fg, 0.8),
+            ..popup.style
+        };
+        self.popups.spawn(|floating| {
+            floating.text.clear();
+            floating.text.push_str(&popup.text);
+            floating.origin = (popup.x, popup.y);
+            floating.position.teleport(popup.x, popup.y);
+            floating.fade = ColorFade::new(popup.style, faded, duration);
+            floating.age = 0.0;
+        });
+    }
+
+    /// Returns the number of popups on screen.
+    pub fn len(&self) -> usize {
+        self.popups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.popups.is_empty()
+    }
+
+    /// Returns the usage of the popup pool.
+    pub fn stats(&self) -> PoolStats {
+        self.popups.stats()
+    }
+}
+
+impl Default for FloatingText {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Node for FloatingText {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        self.still = ctx.config().reduced_motion;
+        for popup in ctx.take_popups() {
+            self.spawn(popup);
+        }
+        let (duration, rise) = (self.duration, if self.still { 0.0 } else { self.rise });
+        self.popups.retain(|floating| {
+            floating.age += dt;
+            floating.fade.update(dt);
+            // Ease out: quick at first, slowing down near the top
+            let t = (floating.age / duration).min(1.0);
+            let height = rise * (1.0 - (1.0 - t) * (1.0 - t));
+            let (x, y) = floating.origin;
+            floating.position.move_to(x, y - height);
+            floating.age < duration
+        });
+        Ok(())
+    }
+
+    fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+        Ok(false)
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        let alpha = frame.alpha();
+        for floating in self.popups.iter() {
+            let (x, y) = floating.position.interpolated(alpha);
+            let width = floating.text.chars().count() as f32;
+            let left = (x - (width - 1.0) / 2.0).round();
+            let top = y.round();
+            if left < 0.0 || top < 0.0 {
+                continue;
+            }
+            frame.draw_styled(
+                left as u16,
+                top as u16,
+                &floating.text,
+                floating.fade.style(alpha),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::geometry::Rect;
+    use crate::renderer::TestRenderer;
+
+    #[test]
+    fn test_popups_rise_and_expire() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut texts = FloatingText::new().with_rise(2.0);
+        ctx.popup(3.0, 4.0, "-12", crate::style!(red));
+        texts.update(&mut ctx, 0.5).unwrap();
+        assert_eq!(texts.len(), 1);
+
+        let mut renderer = TestRenderer::new(7, 5);
+        texts
+            .render(&mut Frame::new(&mut renderer, Rect::new(0, 0, 7, 5), 1.0))
+            .unwrap();
+        // Half way through it has eased up 1.5 of its 2 rows, centered on x = 3
+        assert_eq!(renderer.row(4), "");
+        assert_eq!(renderer.row(3), "  -12");
+
+        texts.update(&mut ctx, 0.5).unwrap();
+        assert!(texts.is_empty());
+        texts.spawn(Popup {
+            x: 1.0,
+            y: 1.0,
+            text: "+1".into(),
+            style: Style::new(),
+        });
+        assert_eq!(texts.stats().reused, 1);
+    }
+
+    #[test]
+    fn test_reduced_motion_fades_in_place() {
+        let mut ctx = Context::new(GameConfig {
+            reduced_motion: true,
+            ..GameConfig::new()
+        });
+        let mut texts = FloatingText::new();
+        ctx.popup(2.0, 1.0, "ok", Style::new());
+        texts.update(&mut ctx, 0.5).unwrap();
+        let mut renderer = TestRenderer::new(4, 2);
+        texts
+            .render(&mut Frame::new(&mut renderer, Rect::new(0, 0, 4, 2), 1.0))
+            .unwrap();
+        assert_eq!(renderer.row(1), "  ok");
+    }
+}
@@ -0,0 +1,69 @@
+use crate::config::GameConfig;
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::geometry::Rect;
+use crate::nodes::Node;
+
+/// A game run inside another node, with a [`Context`] of its own.
+///
+/// The host drives the node through `ctx` and calls [`Hosted::settle`]
+/// afterwards, so what the game queued on its context takes effect without
+/// reaching the host's.
+pub(crate) struct Hosted {
+    pub(crate) node: Box<dyn Node>,
+    pub(crate) ctx: Context,
+    /// Exit code requested by the game, after which it stops running
+    pub(crate) exited: Option<i32>,
+}
+
+impl Hosted {
+    pub(crate) fn new(node: Box<dyn Node>, config: GameConfig, size: (u16, u16)) -> Self {
+        let mut ctx = Context::new(config);
+        ctx.set_viewport(Rect::new(0, 0, size.0, size.1));
+        Self {
+            node,
+            ctx,
+            exited: None,
+        }
+    }
+
+    /// Sets the size of the screen the game sees, telling the game if it
+    /// changed.
+    pub(crate) fn resize(&mut self, (width, height): (u16, u16)) {
+        if self.ctx.screen_size() != (width, height) {
+            self.ctx.set_viewport(Rect::new(0, 0, width, height));
+            self.node.on_resize(&mut self.ctx, width, height);
+        }
+    }
+
+    /// Updates the game, then drops what it queued that no node took.
+    pub(crate) fn update(&mut self, dt: f32) -> Result<(), EngineError> {
+        let result = self.node.update(&mut self.ctx, dt);
+        self.ctx.finish_update();
+        result
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        self.exited.is_none()
+    }
+
+    /// Applies what the game queued on its context while it ran.
+    pub(crate) fn settle(&mut self) {
+        match self.ctx.apply_pause() {
+            Some(true) => self.node.on_pause(&mut self.ctx),
+            Some(false) => self.node.on_resume(&mut self.ctx),
+            None => {}
+        }
+        for change in self.ctx.apply_pending() {
+            self.node
+                .on_engine_event(&mut self.ctx, &EngineEvent::ConfigChanged(change));
+        }
+        for job in self.ctx.poll_jobs() {
+            self.node
+                .on_engine_event(&mut self.ctx, &EngineEvent::JobFinished(job));
+        }
+        if self.exited.is_none() {
+            self.exited = self.ctx.exit_requested();
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use crate::autosave::SaveData;
+use crate::context::{Context, EngineEvent};
+use crate::drag::{Drag, DragEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::hit::HitId;
+use crate::inventory::{Inventory, SharedInventory};
+use crate::nodes::Node;
+use crate::style::Style;
+use crossterm::event::{Event, KeyCode, KeyEventKind, MouseEventKind};
+use log::warn;
+use std::rc::Rc;
+
+/// Columns a slot takes, not counting the gap after it.
+const SLOT_WIDTH: u16 = 4;
+
+/// The payload of a drag started from an [`InventoryGrid`].
+#[derive(Debug, Clone)]
+pub struct InventoryDrag {
+    pub inventory: SharedInventory,
+    pub slot: usize,
+}
+
+/// Shows an [`Inventory`] as a grid of slots, each with the item's glyph
+/// and count, and a tooltip naming the item under the cursor or selection.
+///
+/// * Arrows: move the selection
+/// * Enter: pick up the selected stack, then Enter again to put it down;
+///   `Esc` puts it back
+/// * Mouse: drag a stack onto another slot, of this grid or another one
+///
+/// Stacks dropped on a slot merge with the same item and swap with others.
+/// The inventory is shared, so the game keeps a handle to it as well.
+pub struct InventoryGrid {
+    inventory: SharedInventory,
+    columns: usize,
+    selected: usize,
+    /// Slot picked up with the keyboard
+    held: Option<usize>,
+    /// Slot under the mouse
+    hovered: Option<usize>,
+    grid_id: HitId,
+    save_name: Option<String>,
+    style: Style,
+    selected_style: Style,
+    held_style: Style,
+}
+
+impl InventoryGrid {
+    /// Shows `inventory` in rows of `columns` slots.
+    pub fn new(inventory: SharedInventory, columns: usize) -> Self {
+        Self {
+            inventory,
+            columns: columns.max(1),
+            selected: 0,
+            held: None,
+            hovered: None,
+            grid_id: HitId::new(),
+            save_name: None,
+            style: crate::style!(white on dark_grey),
+            selected_style: crate::style!(black on cyan),
+            held_style: crate::style!(black on yellow),
+        }
+    }
+
+    /// Writes the inventory into the autosave section `name`; restore it
+    /// with [`Inventory::parse`].
+    pub fn with_save_name(mut self, name: impl Into<String>) -> Self {
+        self.save_name = Some(name.into());
+        self
+    }
+
+    pub fn inventory(&self) -> &SharedInventory {
+        &self.inventory
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Returns the size of the grid in cells, without the tooltip.
+    fn grid_size(&self) -> (u16, u16) {
+        let slots = self.inventory.borrow().size();
+        let rows = slots.div_ceil(self.columns) as u16;
+        let columns = self.columns.min(slots) as u16;
+        ((SLOT_WIDTH + 1) * columns, rows)
+    }
+
+    /// Returns the slot at (x,y) relative to the grid, if any.
+    fn slot_at(&self, x: u16, y: u16) -> Option<usize> {
+        if x % (SLOT_WIDTH + 1) == SLOT_WIDTH {
+            return None;
+        }
+        let column = (x / (SLOT_WIDTH + 1)) as usize;
+        let slot = y as usize * self.columns + column;
+        (column < self.columns && slot < self.inventory.borrow().size()).then_some(slot)
+    }
+
+    fn select(&mut self, dx: isize, dy: isize) {
+        let size = self.inventory.borrow().size() as isize;
+        let target = self.selected as isize + dx + dy * self.columns as isize;
+        if (0..size).contains(&target) {
+            self.selected = target as usize;
+        }
+    }
+
+    fn drop_on(&mut self, slot: usize, drag: &InventoryDrag) {
+        let result = if Rc::ptr_eq(&drag.inventory, &self.inventory) {
+            self.inventory.borrow_mut().move_stack(drag.slot, slot)
+        } else {
+            let mut source = drag.inventory.borrow_mut();
+            Inventory::transfer(
+                &mut source,
+                drag.slot,
+                &mut self.inventory.borrow_mut(),
+                slot,
+            )
+        };
+        if let Err(e) = result {
+            warn!("Could not move items: {}", e);
+        }
+        self.selected = slot;
+    }
+}
+
+impl Node for InventoryGrid {
+    fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    fn on_event(&mut self, _ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        let Event::Key(key) = ev else {
+            return Ok(false);
+        };
+        if key.kind == KeyEventKind::Release {
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::Left => self.select(-1, 0),
+            KeyCode::Right => self.select(1, 0),
+            KeyCode::Up => self.select(0, -1),
+            KeyCode::Down => self.select(0, 1),
+            KeyCode::Enter => match self.held.take() {
+                Some(from) => self
+                    .inventory
+                    .borrow_mut()
+                    .move_stack(from, self.selected)?,
+                None if self.inventory.borrow().slot(self.selected).is_some() => {
+                    self.held = Some(self.selected)
+                }
+                None => return Ok(false),
+            },
+            KeyCode::Esc if self.held.is_some() => self.held = None,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        match ev {
+            EngineEvent::Hit(hit) if hit.id == self.grid_id => {
+                self.hovered = self.slot_at(hit.x, hit.y);
+                let Some(slot) = self.hovered else {
+                    return;
+                };
+                if !matches!(hit.event.kind, MouseEventKind::Down(_)) {
+                    return;
+                }
+                self.selected = slot;
+                let inventory = self.inventory.borrow();
+                let Some(info) = inventory.info(slot) else {
+                    return;
+                };
+                let payload = InventoryDrag {
+                    inventory: Rc::clone(&self.inventory),
+                    slot,
+                };
+                let drag = Drag::new(self.grid_id, payload).with_ghost(info.glyph, info.style);
+                drop(inventory);
+                ctx.start_drag(drag);
+            }
+            EngineEvent::Hit(_) => self.hovered = None,
+            EngineEvent::Drag(DragEvent::Drop { target, x, y, drag })
+                if *target == self.grid_id =>
+            {
+                if let (Some(slot), Some(payload)) = (
+                    self.slot_at(*x, *y),
+                    drag.payload.downcast_ref::<InventoryDrag>(),
+                ) {
+                    self.drop_on(slot, payload);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_save(&self, save: &mut SaveData) -> Result<(), EngineError> {
+        if let Some(name) = &self.save_name {
+            save.write(name, &*self.inventory.borrow())?;
+        }
+        Ok(())
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        let inventory = self.inventory.borrow();
+        let (width, height) = self.grid_size();
+        frame.hit_region(self.grid_id, Rect::new(0, 0, width, height));
+        for slot in 0..inventory.size() {
+            let x = (slot % self.columns) as u16 * (SLOT_WIDTH + 1);
+            let y = (slot / self.columns) as u16;
+            let style = if self.held == Some(slot) {
+                self.held_style
+            } else if slot == self.selected {
+                self.selected_style
+            } else {
+                self.style
+            };
+            let label = match (inventory.slot(slot), inventory.info(slot)) {
+                (Some(stack), Some(info)) if info.max_stack > 1 => {
+                    format!("{}{:>3}", info.glyph, stack.count.min(999))
+                }
+                (Some(_), Some(info)) => format!("{}   ", info.glyph),
+                _ => "    ".to_string(),
+            };
+            frame.draw_styled(x, y, &label, style)?;
+            // Color the glyph in the item's own style over the slot background
+            if let Some(info) = inventory.info(slot) {
+                let glyph_style = Style {
+                    bg: style.bg,
+                    ..info.style
+                };
+                let mut glyph = [0; 4];
+                if info.style != Style::new() {
+                    frame.draw_styled(x, y, info.glyph.encode_utf8(&mut glyph), glyph_style)?;
+                }
+            }
+        }
+
+        let tooltip = self.hovered.unwrap_or(self.selected);
+        if let (Some(stack), Some(info)) = (inventory.slot(tooltip), inventory.info(tooltip)) {
+            let title = if stack.count > 1 {
+                format!("{} ({})", info.name, stack.count)
+            } else {
+                info.name.clone()
+            };
+            frame.draw_styled(0, height + 1, &title, crate::style!(yellow))?;
+            frame.draw_styled(0, height + 2, &info.description, Style::new())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::hit::Hit;
+    use crate::inventory::{ItemCatalog, ItemInfo, Stack};
+    use crate::renderer::TestRenderer;
+    use crossterm::event::{KeyEvent, KeyModifiers, MouseButton, MouseEvent};
+    use std::cell::RefCell;
+
+    fn inventory(slots: usize) -> SharedInventory {
+        let catalog = ItemCatalog::new()
+            .with(ItemInfo::new("coin", "Coin", '$').with_max_stack(99))
+            .with(ItemInfo::new("key", "Key", 'k').with_description("Opens the gate"));
+        Rc::new(RefCell::new(Inventory::new(Rc::new(catalog), slots)))
+    }
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn test_keyboard_moves_stacks_and_shows_tooltip() {
+        let bag = inventory(4);
+        bag.borrow_mut().add("coin", 12).unwrap();
+        bag.borrow_mut().add("key", 1).unwrap();
+        let mut ctx = Context::new(GameConfig::new());
+        let mut grid = InventoryGrid::new(Rc::clone(&bag), 2);
+
+        // Carry the coins from slot 0 to slot 3
+        grid.on_event(&mut ctx, &key(KeyCode::Enter)).unwrap();
+        grid.on_event(&mut ctx, &key(KeyCode::Down)).unwrap();
+        grid.on_event(&mut ctx, &key(KeyCode::Right)).unwrap();
+        grid.on_event(&mut ctx, &key(KeyCode::Enter)).unwrap();
+        assert_eq!(bag.borrow().slot(3), Some(&Stack::new("coin", 12)));
+        assert_eq!(grid.selected(), 3);
+
+        let mut renderer = TestRenderer::new(12, 5);
+        grid.render(&mut Frame::new(&mut renderer, Rect::new(0, 0, 12, 5), 1.0))
+            .unwrap();
+        assert_eq!(renderer.row(0), "     k");
+        assert_eq!(renderer.row(1), "     $ 12");
+        assert_eq!(renderer.row(3), "Coin (12)");
+    }
+
+    #[test]
+    fn test_drag_between_grids() {
+        let bag = inventory(2);
+        let chest = inventory(2);
+        bag.borrow_mut().add("key", 1).unwrap();
+        chest.borrow_mut().put(1, Stack::new("coin", 5)).unwrap();
+        let mut ctx = Context::new(GameConfig::new());
+        let mut from = InventoryGrid::new(Rc::clone(&bag), 2);
+        let mut to = InventoryGrid::new(Rc::clone(&chest), 2);
+
+        let press = MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 1,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        };
+        from.on_engine_event(
+            &mut ctx,
+            &EngineEvent::Hit(Hit {
+                id: from.grid_id,
+                x: 1,
+                y: 0,
+                event: press,
+            }),
+        );
+        let drag = ctx.drag().cloned().unwrap();
+        assert_eq!(drag.ghost, "k");
+        assert!(drag.payload.is::<InventoryDrag>());
+
+        // Dropping the key on the coins swaps them
+        let drop = EngineEvent::Drag(DragEvent::Drop {
+            target: to.grid_id,
+            x: 6,
+            y: 0,
+            drag,
+        });
+        from.on_engine_event(&mut ctx, &drop);
+        to.on_engine_event(&mut ctx, &drop);
+        assert_eq!(chest.borrow().slot(1), Some(&Stack::new("key", 1)));
+        assert_eq!(bag.borrow().slot(0), Some(&Stack::new("coin", 5)));
+    }
+}
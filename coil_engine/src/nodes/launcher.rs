@@ -0,0 +1,363 @@
+use crate::autosave::SaveData;
+use crate::config::{ErrorPolicy, GameConfig};
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::hit::HitId;
+use crate::nodes::Node;
+use crate::nodes::hosted::Hosted;
+use crate::style::Style;
+use crossterm::event::{Event, KeyCode, KeyEventKind, MouseButton, MouseEventKind};
+
+/// Width of the thumbnail, in cells
+const THUMBNAIL_WIDTH: u16 = 32;
+/// Height of the thumbnail, in cells
+const THUMBNAIL_HEIGHT: u16 = 10;
+
+type Factory = Box<dyn Fn() -> Box<dyn Node>>;
+
+struct Entry {
+    name: String,
+    description: String,
+    config: GameConfig,
+    create: Factory,
+}
+
+/// A menu listing games or examples and running the one the player picks.
+///
+/// Each entry shows its description and a thumbnail: the first frame of a
+/// fresh instance of the game, cropped to a small box. Launching an entry
+/// creates another instance and runs it on the whole screen with a
+/// [`Context`] of its own, so when the game requests an exit the launcher
+/// shows its menu again instead of ending.
+///
+/// * Up/Down: select an entry
+/// * Enter: launch the selected entry
+/// * Esc: exit the launcher
+/// * Mouse: click an entry to select it, click it again to launch it
+pub struct Launcher {
+    title: String,
+    entries: Vec<Entry>,
+    selected: usize,
+    /// Instance of the selected entry that is drawn but never updated
+    preview: Option<Box<dyn Node>>,
+    running: Option<Hosted>,
+    list_id: HitId,
+    style: Style,
+    selected_style: Style,
+    title_style: Style,
+}
+
+impl Launcher {
+    /// Creates a launcher with the heading `title`.
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            entries: Vec::new(),
+            selected: 0,
+            preview: None,
+            running: None,
+            list_id: HitId::new(),
+            style: Style::new(),
+            selected_style: crate::style!(black on white),
+            title_style: crate::style!(yellow),
+        }
+    }
+
+    /// Registers a game run with the default configuration; `create` is
+    /// called each time a new instance is needed.
+    pub fn with_game<N, F>(
+        self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        create: F,
+    ) -> Self
+    where
+        N: Node + 'static,
+        F: Fn() -> N + 'static,
+    {
+        self.with_configured_game(name, description, GameConfig::new(), create)
+    }
+
+    /// Registers a game run with `config`.
+    pub fn with_configured_game<N, F>(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        config: GameConfig,
+        create: F,
+    ) -> Self
+    where
+        N: Node + 'static,
+        F: Fn() -> N + 'static,
+    {
+        self.entries.push(Entry {
+            name: name.into(),
+            description: description.into(),
+            config,
+            create: Box::new(move || Box::new(create())),
+        });
+        if self.entries.len() == 1 {
+            self.preview = Some((self.entries[0].create)());
+        }
+        self
+    }
+
+    /// Returns the index of the selected entry.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Selects entry `index`, announcing its name; out of range indices are ignored.
+    pub fn select(&mut self, ctx: &mut Context, index: usize) {
+        if let Some(entry) = self.entries.get(index)
+            && index != self.selected
+        {
+            self.selected = index;
+            self.preview = Some((entry.create)());
+            ctx.announce(format!("{}. {}", entry.name, entry.description));
+        }
+    }
+
+    /// Returns whether a game is running instead of the menu.
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Runs a new instance of the selected entry.
+    pub fn launch(&mut self, ctx: &mut Context) {
+        let Some(entry) = self.entries.get(self.selected) else {
+            return;
+        };
+        ctx.announce(format!("Starting {}", entry.name));
+        self.running = Some(Hosted::new(
+            (entry.create)(),
+            entry.config.clone(),
+            ctx.screen_size(),
+        ));
+    }
+
+    /// Goes back to the menu once the running game has asked to exit.
+    fn settle(&mut self, ctx: &mut Context) {
+        let Some(game) = &mut self.running else {
+            return;
+        };
+        game.settle();
+        if !game.is_running() {
+            self.running = None;
+            ctx.announce(format!(
+                "{}. {}",
+                self.title, self.entries[self.selected].name
+            ));
+        }
+    }
+
+    fn list_width(&self) -> u16 {
+        let longest = self.entries.iter().map(|entry| entry.name.chars().count());
+        longest.max().unwrap_or(0) as u16 + 2
+    }
+}
+
+impl Node for Launcher {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        if let Some(game) = &mut self.running {
+            game.resize(ctx.screen_size());
+            if !game.ctx.is_paused() {
+                game.update(dt)?;
+            }
+            self.settle(ctx);
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        if let Some(game) = &mut self.running {
+            if let Event::Resize(width, height) = ev {
+                game.resize((*width, *height));
+            }
+            let consumed = game.node.on_event(&mut game.ctx, ev)?;
+            self.settle(ctx);
+            return Ok(consumed);
+        }
+        let Event::Key(key) = ev else {
+            return Ok(false);
+        };
+        if key.kind == KeyEventKind::Release {
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::Up => self.select(ctx, self.selected.saturating_sub(1)),
+            KeyCode::Down => self.select(ctx, self.selected + 1),
+            KeyCode::Enter => self.launch(ctx),
+            KeyCode::Esc => ctx.request_exit(0),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        if let Some(game) = &mut self.running {
+            if let EngineEvent::Idle | EngineEvent::ActivityResumed = ev {
+                game.ctx.set_idle(matches!(ev, EngineEvent::Idle));
+            }
+            if let EngineEvent::Hit(_) | EngineEvent::Idle | EngineEvent::ActivityResumed = ev {
+                game.node.on_engine_event(&mut game.ctx, ev);
+            }
+            return;
+        }
+        if let EngineEvent::Hit(hit) = ev
+            && hit.id == self.list_id
+            && let MouseEventKind::Down(MouseButton::Left) = hit.event.kind
+        {
+            let index = hit.y as usize;
+            if index == self.selected {
+                self.launch(ctx);
+            } else {
+                self.select(ctx, index);
+            }
+        }
+    }
+
+    fn on_pause(&mut self, _ctx: &mut Context) {
+        if let Some(game) = &mut self.running {
+            game.node.on_pause(&mut game.ctx);
+        }
+    }
+
+    fn on_resume(&mut self, _ctx: &mut Context) {
+        if let Some(game) = &mut self.running {
+            game.node.on_resume(&mut game.ctx);
+        }
+    }
+
+    fn on_save(&self, save: &mut SaveData) -> Result<(), EngineError> {
+        match &self.running {
+            Some(game) => game.node.on_save(save),
+            None => Ok(()),
+        }
+    }
+
+    fn on_error(&mut self, _ctx: &mut Context, err: &EngineError) -> Option<ErrorPolicy> {
+        let game = self.running.as_mut()?;
+        game.node.on_error(&mut game.ctx, err)
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        if let Some(game) = &self.running {
+            return game.node.render(frame);
+        }
+        frame.draw_styled(1, 0, &self.title, self.title_style)?;
+        let width = self.list_width();
+        frame.hit_region(
+            self.list_id,
+            Rect::new(0, 2, width, self.entries.len() as u16),
+        );
+        for (index, entry) in self.entries.iter().enumerate() {
+            let style = if index == self.selected {
+                self.selected_style
+            } else {
+                self.style
+            };
+            let label = format!(" {:<1$} ", entry.name, width as usize - 2);
+            frame.draw_styled(0, 2 + index as u16, &label, style)?;
+        }
+
+        let x = width + 2;
+        if let Some(preview) = &self.preview {
+            let mut thumbnail = frame.child(Rect::new(x, 2, THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT));
+            thumbnail.clear()?;
+            preview.render(&mut thumbnail)?;
+        }
+        if let Some(entry) = self.entries.get(self.selected) {
+            frame.draw_text(x, 3 + THUMBNAIL_HEIGHT, &entry.description, self.style)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hit::HitRegions;
+    use crate::renderer::TestRenderer;
+    use crossterm::event::{KeyEvent, KeyModifiers};
+
+    /// Shows its letter and quits on `q`.
+    struct Letter(char);
+
+    impl Node for Letter {
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+            Ok(())
+        }
+
+        fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+            if let Event::Key(key) = ev
+                && key.code == KeyCode::Char('q')
+            {
+                ctx.request_exit(1);
+                return Ok(true);
+            }
+            Ok(false)
+        }
+
+        fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+            frame.draw_styled(0, 0, &self.0.to_string(), Style::new())?;
+            Ok(())
+        }
+    }
+
+    fn draw(launcher: &Launcher, regions: &mut HitRegions) -> TestRenderer {
+        let mut renderer = TestRenderer::new(40, 16);
+        regions.clear();
+        launcher
+            .render(
+                &mut Frame::new(&mut renderer, Rect::new(0, 0, 40, 16), 0.0)
+                    .with_hit_regions(regions),
+            )
+            .unwrap();
+        renderer
+    }
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn test_launching_returns_to_menu_on_exit() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut launcher = Launcher::new("Examples")
+            .with_game("Snake", "Eat and grow", || Letter('s'))
+            .with_game("Life", "Cells", || Letter('l'));
+        let mut regions = HitRegions::new();
+        let screen = draw(&launcher, &mut regions);
+        assert_eq!(screen.rows()[2..4], [" Snake   s", " Life"]);
+
+        // Clicking the second entry selects it, clicking again launches it
+        let click = crossterm::event::MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 1,
+            row: 3,
+            modifiers: KeyModifiers::NONE,
+        };
+        let hit = EngineEvent::Hit(regions.hit(click).unwrap());
+        launcher.on_engine_event(&mut ctx, &hit);
+        assert_eq!(launcher.selected(), 1);
+        let screen = draw(&launcher, &mut regions);
+        assert_eq!(screen.row(2), " Snake   l");
+        assert_eq!(screen.row(13), "         Cells");
+        launcher.on_engine_event(&mut ctx, &hit);
+        assert!(launcher.is_running());
+        assert_eq!(draw(&launcher, &mut regions).row(0), "l");
+
+        // The game's exit only ends the game
+        launcher
+            .on_event(&mut ctx, &key(KeyCode::Char('q')))
+            .unwrap();
+        assert!(!launcher.is_running());
+        assert_eq!(ctx.exit_requested(), None);
+        launcher.on_event(&mut ctx, &key(KeyCode::Up)).unwrap();
+        launcher.on_event(&mut ctx, &key(KeyCode::Enter)).unwrap();
+        assert_eq!(draw(&launcher, &mut regions).row(0), "s");
+    }
+}
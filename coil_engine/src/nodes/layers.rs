@@ -0,0 +1,421 @@
+use crate::autosave::SaveData;
+use crate::camera::{Bounds, Camera};
+use crate::color::{self, Rgb};
+use crate::config::ErrorPolicy;
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::nodes::{Node, UpdateSchedule};
+use crate::renderer::Cell;
+use crossterm::event::Event;
+
+/// A root node splitting the tree into a scrolling world and a fixed HUD.
+///
+/// World children are drawn through the context's [`Camera`], while HUD
+/// children are drawn in screen space on top of them and see input first.
+/// Mouse events reach world children in screen coordinates; convert them
+/// with [`Camera::screen_to_world`]. The context's world tint, if set,
+/// colors the world layer only.
+///
+/// World children that report their [`Node::world_bounds`] are not drawn
+/// while they are off-screen, and with [`Layers::with_culled_updates`] not
+/// updated either. Children of both layers are updated at their
+/// [`update_interval`](Node::update_interval).
+pub struct Layers {
+    /// Children with their update schedules, which move along with them
+    world: Vec<(Box<dyn Node>, UpdateSchedule)>,
+    hud: Vec<(Box<dyn Node>, UpdateSchedule)>,
+    /// Camera and tint captured after the last update, used while rendering
+    camera: Camera,
+    tint: Option<Rgb>,
+    /// Camera and tint when the layers were last marked clean
+    clean_view: Option<(Camera, Option<Rgb>)>,
+    cull_updates: bool,
+}
+
+impl Layers {
+    pub fn new() -> Self {
+        Layers {
+            world: Vec::new(),
+            hud: Vec::new(),
+            camera: Camera::default(),
+            tint: None,
+            clean_view: None,
+            cull_updates: false,
+        }
+    }
+
+    /// Also skips the updates of off-screen world children, which then stand
+    /// still until they are shown again.
+    pub fn with_culled_updates(mut self, cull: bool) -> Self {
+        self.cull_updates = cull;
+        self
+    }
+
+    /// Pushes a world child and returns `self`, so you can chain.
+    pub fn with_world<N: Node + 'static>(mut self, child: N) -> Self {
+        self.push_world(Box::new(child));
+        self
+    }
+
+    /// Pushes a HUD child and returns `self`, so you can chain.
+    pub fn with_hud<N: Node + 'static>(mut self, child: N) -> Self {
+        self.push_hud(Box::new(child));
+        self
+    }
+
+    /// Adds a world child, drawn over the other world children.
+    pub fn push_world(&mut self, child: Box<dyn Node>) {
+        self.world.push((child, UpdateSchedule::default()));
+    }
+
+    /// Adds a HUD child, drawn over the other HUD children.
+    pub fn push_hud(&mut self, child: Box<dyn Node>) {
+        self.hud.push((child, UpdateSchedule::default()));
+    }
+
+    /// Returns the world children, bottom first.
+    pub fn world(&self) -> impl DoubleEndedIterator<Item = &dyn Node> {
+        self.world.iter().map(|(c, _)| c.as_ref())
+    }
+
+    /// Returns the HUD children, bottom first.
+    pub fn hud(&self) -> impl DoubleEndedIterator<Item = &dyn Node> {
+        self.hud.iter().map(|(c, _)| c.as_ref())
+    }
+
+    /// Returns the world children mutably, bottom first.
+    pub fn world_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut Box<dyn Node>> {
+        self.world.iter_mut().map(|(c, _)| c)
+    }
+
+    /// Returns the HUD children mutably, bottom first.
+    pub fn hud_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut Box<dyn Node>> {
+        self.hud.iter_mut().map(|(c, _)| c)
+    }
+
+    fn children(&self) -> impl Iterator<Item = &dyn Node> {
+        self.world().chain(self.hud())
+    }
+
+    fn children_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Node>> {
+        self.world
+            .iter_mut()
+            .chain(self.hud.iter_mut())
+            .map(|(c, _)| c)
+    }
+}
+
+impl Default for Layers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Node for Layers {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        let (camera, view) = (ctx.camera(), ctx.screen_size());
+        for (c, schedule) in &mut self.world {
+            if !self.cull_updates || is_shown(c.world_bounds(), camera, view) {
+                schedule.tick(c.as_mut(), ctx, dt)?;
+            }
+        }
+        for (c, schedule) in &mut self.hud {
+            schedule.tick(c.as_mut(), ctx, dt)?;
+        }
+        self.camera = ctx.camera();
+        self.tint = ctx.world_tint();
+        Ok(())
+    }
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        // the HUD sits on top, so it gets the first chance
+        let (world, hud) = (self.world.iter_mut(), self.hud.iter_mut());
+        for (c, _) in hud.rev().chain(world.rev()) {
+            if c.on_event(ctx, ev)? {
+                return Ok(true);
+            }
+        }
+        self.camera = ctx.camera();
+        Ok(false)
+    }
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        for c in self.children_mut() {
+            c.on_engine_event(ctx, ev);
+        }
+    }
+    fn on_pause(&mut self, ctx: &mut Context) {
+        for c in self.children_mut() {
+            c.on_pause(ctx);
+        }
+    }
+    fn on_resume(&mut self, ctx: &mut Context) {
+        for c in self.children_mut() {
+            c.on_resume(ctx);
+        }
+    }
+    fn on_resize(&mut self, ctx: &mut Context, width: u16, height: u16) {
+        for c in self.children_mut() {
+            c.on_resize(ctx, width, height);
+        }
+    }
+    fn on_save(&self, save: &mut SaveData) -> Result<(), EngineError> {
+        for c in self.children() {
+            c.on_save(save)?;
+        }
+        Ok(())
+    }
+    fn on_error(&mut self, ctx: &mut Context, err: &EngineError) -> Option<ErrorPolicy> {
+        self.children_mut().find_map(|c| c.on_error(ctx, err))
+    }
+    fn is_dirty(&self) -> bool {
+        self.clean_view != Some((self.camera, self.tint)) || self.children().any(|c| c.is_dirty())
+    }
+    fn mark_clean(&mut self) {
+        self.children_mut().for_each(|c| c.mark_clean());
+        self.clean_view = Some((self.camera, self.tint));
+    }
+    fn world_bounds(&self) -> Option<Bounds> {
+        let world = self.world().map(|c| {
+            let bounds = c.world_bounds()?;
+            let (x, y) = self.camera.world_to_screen(bounds.x, bounds.y);
+            Some(Bounds { x, y, ..bounds })
+        });
+        Bounds::enclosing(world.chain(self.hud().map(|c| c.world_bounds())))
+    }
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        {
+            let view = frame.size();
+            let mut world = frame.translated(-self.camera.x, -self.camera.y);
+            let draw_world = |world: &mut Frame<'_>| -> Result<(), EngineError> {
+                for c in self.world() {
+                    if is_shown(c.world_bounds(), self.camera, view) {
+                        c.render(world)?;
+                    }
+                }
+                Ok(())
+            };
+            match self.tint {
+                Some(tint) => {
+                    let filter = |cell: Cell| Cell {
+                        fg: color::multiply(cell.fg, tint),
+                        bg: color::multiply(cell.bg, tint),
+                        ..cell
+                    };
+                    world.filtered(&filter, draw_world)?
+                }
+                None => draw_world(&mut world)?,
+            }
+        }
+        for c in self.hud() {
+            c.render(frame)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether a node with the given bounds, if any, is in view.
+fn is_shown(bounds: Option<Bounds>, camera: Camera, view: (u16, u16)) -> bool {
+    bounds.is_none_or(|bounds| camera.is_visible(bounds, view))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::geometry::Rect;
+    use crate::renderer::{BasicRenderer, Cell};
+    use std::cell::Cell as Counter;
+    use std::rc::Rc;
+
+    struct Marker(char);
+
+    impl Node for Marker {
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+            Ok(())
+        }
+
+        fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+            Ok(false)
+        }
+
+        fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+            frame.draw_cell(5, 1, Cell::new(self.0))
+        }
+    }
+
+    #[test]
+    fn test_hud_ignores_camera() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut layers = Layers::new().with_world(Marker('w')).with_hud(Marker('h'));
+        ctx.set_camera(Camera::new(2, 1));
+        layers.update(&mut ctx, 0.1).unwrap();
+
+        let mut renderer = BasicRenderer::new(8, 3).unwrap();
+        layers
+            .render(&mut Frame::new(&mut renderer, Rect::new(0, 0, 8, 3), 0.0))
+            .unwrap();
+        assert_eq!(renderer.cell(3, 0).unwrap().ch, 'w');
+        assert_eq!(renderer.cell(5, 1).unwrap().ch, 'h');
+    }
+
+    /// Occupies one world cell, counting its updates and draws.
+    struct Sprite {
+        x: i32,
+        updates: Rc<Counter<u32>>,
+        draws: Rc<Counter<u32>>,
+    }
+
+    impl Node for Sprite {
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+            self.updates.set(self.updates.get() + 1);
+            Ok(())
+        }
+
+        fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+            Ok(false)
+        }
+
+        fn world_bounds(&self) -> Option<Bounds> {
+            Some(Bounds::new(self.x, 0, 1, 1))
+        }
+
+        fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+            self.draws.set(self.draws.get() + 1);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_off_screen_world_nodes_are_culled() {
+        let sprite = |x| {
+            let (updates, draws) = (Rc::new(Counter::new(0)), Rc::new(Counter::new(0)));
+            let sprite = Sprite {
+                x,
+                updates: Rc::clone(&updates),
+                draws: Rc::clone(&draws),
+            };
+            (sprite, updates, draws)
+        };
+        let (near, near_updates, near_draws) = sprite(2);
+        let (far, far_updates, far_draws) = sprite(20);
+        let mut layers = Layers::new()
+            .with_world(near)
+            .with_world(far)
+            .with_culled_updates(true);
+        let mut ctx = Context::new(GameConfig::new());
+        ctx.set_viewport(Rect::new(0, 0, 8, 3));
+        let mut renderer = BasicRenderer::new(8, 3).unwrap();
+
+        for camera in [Camera::new(0, 0), Camera::new(15, 0)] {
+            ctx.set_camera(camera);
+            layers.update(&mut ctx, 0.1).unwrap();
+            layers
+                .render(&mut Frame::new(&mut renderer, Rect::new(0, 0, 8, 3), 0.0))
+                .unwrap();
+        }
+        assert_eq!((near_updates.get(), near_draws.get()), (1, 1));
+        assert_eq!((far_updates.get(), far_draws.get()), (1, 1));
+    }
+
+    #[test]
+    fn test_children_update_at_their_interval() {
+        struct Slow(Rc<Counter<u32>>);
+        impl Node for Slow {
+            fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+                self.0.set(self.0.get() + 1);
+                Ok(())
+            }
+            fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+                Ok(false)
+            }
+            fn update_interval(&self) -> u32 {
+                2
+            }
+            fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+                Ok(())
+            }
+        }
+
+        let (world, hud) = (Rc::new(Counter::new(0)), Rc::new(Counter::new(0)));
+        let mut layers = Layers::new()
+            .with_world(Slow(Rc::clone(&world)))
+            .with_hud(Slow(Rc::clone(&hud)));
+        let mut ctx = Context::new(GameConfig::new());
+        for _ in 0..4 {
+            layers.update(&mut ctx, 0.1).unwrap();
+        }
+        assert_eq!((world.get(), hud.get()), (2, 2));
+    }
+
+    #[test]
+    fn test_moving_the_camera_makes_the_layers_dirty() {
+        /// Draws the same thing every time into one world cell.
+        struct Clean;
+        impl Node for Clean {
+            fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+                Ok(())
+            }
+            fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+                Ok(false)
+            }
+            fn is_dirty(&self) -> bool {
+                false
+            }
+            fn world_bounds(&self) -> Option<Bounds> {
+                Some(Bounds::new(2, 0, 1, 1))
+            }
+            fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+                Ok(())
+            }
+        }
+
+        let mut layers = Layers::new().with_world(Clean);
+        let mut ctx = Context::new(GameConfig::new());
+        layers.update(&mut ctx, 0.1).unwrap();
+        assert!(layers.is_dirty());
+        layers.mark_clean();
+        assert!(!layers.is_dirty());
+
+        ctx.set_camera(Camera::new(1, 0));
+        layers.update(&mut ctx, 0.1).unwrap();
+        assert!(layers.is_dirty());
+        // World bounds are reported where they show on screen
+        assert_eq!(layers.world_bounds(), Some(Bounds::new(1, 0, 1, 1)));
+    }
+
+    #[test]
+    fn test_world_tint_spares_hud() {
+        struct Red;
+        impl Node for Red {
+            fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+                Ok(())
+            }
+            fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+                Ok(false)
+            }
+            fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+                frame.draw_cell(0, 0, Cell::styled('r', crate::style!(white on red)))
+            }
+        }
+
+        let mut ctx = Context::new(GameConfig::new());
+        let mut layers = Layers::new().with_world(Red).with_hud(Marker('h'));
+        ctx.set_world_tint(Some(Rgb::new(128, 128, 128)));
+        layers.update(&mut ctx, 0.1).unwrap();
+
+        let mut renderer = BasicRenderer::new(8, 3).unwrap();
+        layers
+            .render(&mut Frame::new(&mut renderer, Rect::new(0, 0, 8, 3), 0.0))
+            .unwrap();
+        let world = renderer.cell(0, 0).unwrap();
+        assert_eq!(world.ch, 'r');
+        assert_eq!(
+            world.bg,
+            crossterm::style::Color::Rgb { r: 128, g: 0, b: 0 }
+        );
+        assert_eq!(
+            renderer.cell(5, 1).unwrap().fg,
+            crossterm::style::Color::Reset
+        );
+    }
+}
@@ -0,0 +1,521 @@
+use crate::actions::KeyBinding;
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::hit::{Hit, HitId};
+use crate::nodes::Node;
+use crate::notify::Level;
+use crate::style::Style;
+use crate::tilemap::TileMap;
+use crate::undo::{Command, History};
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind,
+};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const PALETTE_LABEL: &str = "Tiles ";
+
+/// How mouse presses on the map edit it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorTool {
+    /// Paint the selected tile under the cursor while the button is held.
+    Paint,
+    /// Fill the rectangle spanned by a press and its release.
+    Rectangle,
+}
+
+/// A tile change, kept so it can be undone.
+#[derive(Debug, Clone, Copy)]
+struct Change {
+    x: u16,
+    y: u16,
+    before: usize,
+    after: usize,
+}
+
+impl Command<TileMap> for Change {
+    fn execute(&mut self, map: &mut TileMap) {
+        map.set(self.x, self.y, self.after);
+    }
+
+    fn undo(&mut self, map: &mut TileMap) {
+        map.set(self.x, self.y, self.before);
+    }
+}
+
+/// An in-game editor for a [`TileMap`].
+///
+/// The editor owns the map and always draws it at the top-left of its frame;
+/// games read it back through [`LevelEditor::map`]. The toggle key (F2 by
+/// default) opens the editor in debug builds, which then consumes all input:
+///
+/// * Left mouse button: paint, or span a rectangle with the rectangle tool
+/// * Right mouse button: pick the tile under the cursor
+/// * `1`-`9`, `[` and `]`: select a tile; clicking the palette works too
+/// * `p`/`r`: paint or rectangle tool
+/// * Ctrl+Z/Ctrl+Y: undo/redo
+/// * Ctrl+S: save to the map file; Ctrl+R: reload it
+/// * Esc or the toggle key: close the editor
+pub struct LevelEditor {
+    map: TileMap,
+    path: Option<PathBuf>,
+    enabled: bool,
+    active: bool,
+    toggle_key: KeyBinding,
+    tool: EditorTool,
+    selected: usize,
+    /// Whether a stroke is being painted
+    painting: bool,
+    /// Anchor and opposite corner of the rectangle being spanned
+    rectangle: Option<((u16, u16), (u16, u16))>,
+    history: History<TileMap>,
+    map_id: HitId,
+    palette_id: HitId,
+    bar_style: Style,
+    selected_style: Style,
+}
+
+impl LevelEditor {
+    pub fn new(map: TileMap) -> Self {
+        Self {
+            map,
+            path: None,
+            enabled: cfg!(debug_assertions),
+            active: false,
+            toggle_key: KeyCode::F(2).into(),
+            tool: EditorTool::Paint,
+            selected: 0,
+            painting: false,
+            rectangle: None,
+            history: History::new(),
+            map_id: HitId::new(),
+            palette_id: HitId::new(),
+            bar_style: crate::style!(white on dark_grey),
+            selected_style: crate::style!(black on yellow),
+        }
+    }
+
+    /// Sets the file Ctrl+S saves the map to and Ctrl+R reloads it from.
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Allows or forbids opening the editor; by default only debug builds allow it.
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Replaces the key that opens and closes the editor.
+    pub fn with_toggle_key(mut self, key: impl Into<KeyBinding>) -> Self {
+        self.toggle_key = key.into();
+        self
+    }
+
+    pub fn map(&self) -> &TileMap {
+        &self.map
+    }
+
+    /// Gives mutable access to the map; changes made here cannot be undone.
+    pub fn map_mut(&mut self) -> &mut TileMap {
+        &mut self.map
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn tool(&self) -> EditorTool {
+        self.tool
+    }
+
+    /// Returns the palette index of the tile being placed.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Opens or closes the editor, if it is enabled.
+    pub fn toggle(&mut self) {
+        self.active = self.enabled && !self.active;
+        // Keep what was painted so far, but drop an unfinished rectangle
+        self.rectangle = None;
+        self.finish_gesture();
+    }
+
+    /// Reverts the last edit.
+    pub fn undo(&mut self) -> bool {
+        self.history.undo(&mut self.map)
+    }
+
+    /// Re-applies the last undone edit.
+    pub fn redo(&mut self) -> bool {
+        self.history.redo(&mut self.map)
+    }
+
+    fn select(&mut self, tile: usize) {
+        self.selected = tile.min(self.map.palette().len() - 1);
+    }
+
+    /// Places the selected tile, recording the change if the tile differed.
+    fn place(&mut self, x: u16, y: u16) {
+        if let Some(before) = self.map.set(x, y, self.selected)
+            && before != self.selected
+        {
+            self.history.push(Change {
+                x,
+                y,
+                before,
+                after: self.selected,
+            });
+        }
+    }
+
+    /// Ends the stroke or rectangle in progress as one undo step.
+    fn finish_gesture(&mut self) {
+        self.painting = false;
+        if let Some(((ax, ay), (bx, by))) = self.rectangle.take() {
+            self.history.begin_group();
+            for y in ay.min(by)..=ay.max(by) {
+                for x in ax.min(bx)..=ax.max(bx) {
+                    self.place(x, y);
+                }
+            }
+        }
+        self.history.end_group();
+    }
+
+    fn on_map(&mut self, hit: &Hit) {
+        let (x, y) = (hit.x, hit.y);
+        match hit.event.kind {
+            MouseEventKind::Down(MouseButton::Right) => {
+                if let Some(tile) = self.map.get(x, y) {
+                    self.selected = tile;
+                }
+            }
+            MouseEventKind::Down(MouseButton::Left) => match self.tool {
+                EditorTool::Paint => {
+                    self.painting = true;
+                    self.history.begin_group();
+                    self.place(x, y);
+                }
+                EditorTool::Rectangle => self.rectangle = Some(((x, y), (x, y))),
+            },
+            MouseEventKind::Drag(MouseButton::Left) => {
+                if self.painting {
+                    self.place(x, y);
+                }
+                if let Some((_, corner)) = &mut self.rectangle {
+                    *corner = (x, y);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_key(&mut self, ctx: &mut Context, key: KeyEvent) {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => self.toggle(),
+            KeyCode::Char('z') if control => {
+                self.undo();
+            }
+            KeyCode::Char('y') if control => {
+                self.redo();
+            }
+            KeyCode::Char('s') if control => match self.save() {
+                Ok(()) => ctx.notify("Map saved", Level::Success, Duration::from_secs(2)),
+                Err(e) => ctx.notify(
+                    format!("Could not save map: {}", e),
+                    Level::Error,
+                    Duration::from_secs(4),
+                ),
+            },
+            KeyCode::Char('r') if control => match self.reload() {
+                Ok(()) => ctx.notify("Map reloaded", Level::Info, Duration::from_secs(2)),
+                Err(e) => ctx.notify(
+                    format!("Could not load map: {}", e),
+                    Level::Error,
+                    Duration::from_secs(4),
+                ),
+            },
+            KeyCode::Char(digit @ '1'..='9') => self.select(digit as usize - '1' as usize),
+            KeyCode::Char('[') => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Char(']') => self.select(self.selected + 1),
+            KeyCode::Char('p') => self.tool = EditorTool::Paint,
+            KeyCode::Char('r') => self.tool = EditorTool::Rectangle,
+            _ => {}
+        }
+    }
+
+    fn save(&self) -> Result<(), EngineError> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| EngineError::game("no map file set"))?;
+        self.map.save(path)
+    }
+
+    /// Replaces the map with the file's contents, keeping the palette.
+    fn reload(&mut self) -> Result<(), EngineError> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| EngineError::game("no map file set"))?;
+        self.map = TileMap::load(path, self.map.palette().to_vec())?;
+        self.history.clear();
+        Ok(())
+    }
+}
+
+impl Node for LevelEditor {
+    fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        if let Event::Key(key) = &ev
+            && self.enabled
+            && self.toggle_key.matches(key)
+        {
+            self.toggle();
+            return Ok(true);
+        }
+        if !self.active {
+            return Ok(false);
+        }
+        match ev {
+            Event::Key(key) if key.kind != KeyEventKind::Release => self.handle_key(ctx, *key),
+            // The button may be released anywhere, not only over the map
+            Event::Mouse(mouse) if matches!(mouse.kind, MouseEventKind::Up(_)) => {
+                self.finish_gesture()
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn on_engine_event(&mut self, _ctx: &mut Context, ev: &EngineEvent) {
+        let EngineEvent::Hit(hit) = ev else {
+            return;
+        };
+        if !self.active {
+            return;
+        }
+        if hit.id == self.map_id {
+            self.on_map(hit);
+        } else if hit.id == self.palette_id
+            && matches!(hit.event.kind, MouseEventKind::Down(_))
+            && hit.x % 2 == 0
+        {
+            self.select(hit.x as usize / 2);
+        }
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        self.map.draw(frame)?;
+        if !self.active {
+            return Ok(());
+        }
+        frame.hit_region(self.map_id, self.map.bounds());
+
+        if let Some(((ax, ay), (bx, by))) = self.rectangle {
+            let tile = self.map.palette()[self.selected];
+            let mut glyph = [0; 4];
+            for y in ay.min(by)..=ay.max(by) {
+                for x in ax.min(bx)..=ax.max(bx) {
+                    frame.draw_styled(x, y, tile.ch.encode_utf8(&mut glyph), tile.style)?;
+                }
+            }
+        }
+
+        // Palette bar on the last row: the label, then each tile followed by a space
+        let (width, height) = frame.size();
+        let y = height.saturating_sub(1);
+        frame.draw_styled(0, y, &" ".repeat(width as usize), self.bar_style)?;
+        frame.draw_styled(0, y, PALETTE_LABEL, self.bar_style)?;
+        let x = PALETTE_LABEL.len() as u16;
+        let palette = self.map.palette();
+        let mut glyph = [0; 4];
+        for (index, tile) in palette.iter().enumerate() {
+            let style = if index == self.selected {
+                self.selected_style
+            } else {
+                tile.style
+            };
+            frame.draw_styled(
+                x + index as u16 * 2,
+                y,
+                tile.ch.encode_utf8(&mut glyph),
+                style,
+            )?;
+        }
+        frame.hit_region(
+            self.palette_id,
+            Rect::new(x, y, palette.len() as u16 * 2, 1),
+        );
+        let tool = match self.tool {
+            EditorTool::Paint => "paint",
+            EditorTool::Rectangle => "rectangle",
+        };
+        let status = format!("{}  [p]aint [r]ect ^Z ^Y ^S", tool);
+        frame.draw_styled(x + palette.len() as u16 * 2 + 1, y, &status, self.bar_style)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::tilemap::Tile;
+    use crossterm::event::MouseEvent;
+
+    fn editor() -> LevelEditor {
+        let palette = vec![
+            Tile::new('.', Style::new()),
+            Tile::new('#', Style::new()),
+            Tile::new('~', Style::new()),
+        ];
+        LevelEditor::new(TileMap::new(4, 3, palette)).with_enabled(true)
+    }
+
+    fn key(editor: &mut LevelEditor, ctx: &mut Context, code: KeyCode, modifiers: KeyModifiers) {
+        editor
+            .on_event(ctx, &Event::Key(KeyEvent::new(code, modifiers)))
+            .unwrap();
+    }
+
+    fn mouse(editor: &mut LevelEditor, ctx: &mut Context, kind: MouseEventKind, x: u16, y: u16) {
+        let event = MouseEvent {
+            kind,
+            column: x,
+            row: y,
+            modifiers: KeyModifiers::NONE,
+        };
+        if let Some(id) = (x < 4 && y < 3).then_some(editor.map_id) {
+            let hit = Hit { id, x, y, event };
+            editor.on_engine_event(ctx, &EngineEvent::Hit(hit));
+        }
+        editor.on_event(ctx, &Event::Mouse(event)).unwrap();
+    }
+
+    #[test]
+    fn test_paint_fill_undo_redo() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut editor = editor();
+        let left = MouseButton::Left;
+
+        key(&mut editor, &mut ctx, KeyCode::F(2), KeyModifiers::NONE);
+        assert!(editor.is_active());
+        key(
+            &mut editor,
+            &mut ctx,
+            KeyCode::Char('2'),
+            KeyModifiers::NONE,
+        );
+        mouse(&mut editor, &mut ctx, MouseEventKind::Down(left), 0, 0);
+        mouse(&mut editor, &mut ctx, MouseEventKind::Drag(left), 1, 0);
+        mouse(&mut editor, &mut ctx, MouseEventKind::Drag(left), 9, 9);
+        mouse(&mut editor, &mut ctx, MouseEventKind::Up(left), 9, 9);
+        assert_eq!(editor.map().to_string(), "##..\n....\n....\n");
+
+        key(
+            &mut editor,
+            &mut ctx,
+            KeyCode::Char(']'),
+            KeyModifiers::NONE,
+        );
+        key(
+            &mut editor,
+            &mut ctx,
+            KeyCode::Char('r'),
+            KeyModifiers::NONE,
+        );
+        mouse(&mut editor, &mut ctx, MouseEventKind::Down(left), 3, 2);
+        mouse(&mut editor, &mut ctx, MouseEventKind::Drag(left), 1, 1);
+        mouse(&mut editor, &mut ctx, MouseEventKind::Up(left), 1, 1);
+        assert_eq!(editor.map().to_string(), "##..\n.~~~\n.~~~\n");
+
+        key(
+            &mut editor,
+            &mut ctx,
+            KeyCode::Char('z'),
+            KeyModifiers::CONTROL,
+        );
+        assert_eq!(editor.map().to_string(), "##..\n....\n....\n");
+        key(
+            &mut editor,
+            &mut ctx,
+            KeyCode::Char('z'),
+            KeyModifiers::CONTROL,
+        );
+        assert_eq!(editor.map().to_string(), "....\n....\n....\n");
+        key(
+            &mut editor,
+            &mut ctx,
+            KeyCode::Char('y'),
+            KeyModifiers::CONTROL,
+        );
+        assert_eq!(editor.map().to_string(), "##..\n....\n....\n");
+
+        mouse(
+            &mut editor,
+            &mut ctx,
+            MouseEventKind::Down(MouseButton::Right),
+            0,
+            0,
+        );
+        assert_eq!(editor.selected(), 1);
+        key(&mut editor, &mut ctx, KeyCode::Esc, KeyModifiers::NONE);
+        assert!(!editor.is_active());
+    }
+
+    #[test]
+    fn test_save_and_reload() {
+        let path = std::env::temp_dir().join(format!("coil-level-{}.txt", std::process::id()));
+        let mut ctx = Context::new(GameConfig::new());
+        let mut editor = editor().with_path(&path);
+        editor.toggle();
+        key(
+            &mut editor,
+            &mut ctx,
+            KeyCode::Char('3'),
+            KeyModifiers::NONE,
+        );
+        let left = MouseButton::Left;
+        mouse(&mut editor, &mut ctx, MouseEventKind::Down(left), 2, 1);
+        mouse(&mut editor, &mut ctx, MouseEventKind::Up(left), 2, 1);
+        key(
+            &mut editor,
+            &mut ctx,
+            KeyCode::Char('s'),
+            KeyModifiers::CONTROL,
+        );
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "....\n..~.\n....\n"
+        );
+
+        editor.map_mut().set(0, 0, 1);
+        key(
+            &mut editor,
+            &mut ctx,
+            KeyCode::Char('r'),
+            KeyModifiers::CONTROL,
+        );
+        assert_eq!(editor.map().to_string(), "....\n..~.\n....\n");
+        assert!(!editor.undo());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_disabled_editor_ignores_toggle() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut editor = editor().with_enabled(false);
+        key(&mut editor, &mut ctx, KeyCode::F(2), KeyModifiers::NONE);
+        assert!(!editor.is_active());
+    }
+}
@@ -0,0 +1,378 @@
+use crate::autosave::SaveData;
+use crate::config::ErrorPolicy;
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::nodes::Node;
+use crate::resource_bar::ResourceBar;
+use crate::style::Style;
+use crossterm::event::{Event, KeyCode};
+use log::{error, warn};
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+type Asset = Box<dyn Any + Send>;
+type Load = Box<dyn FnOnce() -> Result<Asset, EngineError> + Send>;
+type Build = Box<dyn FnOnce(Assets) -> Result<Box<dyn Node>, EngineError>>;
+
+const BAR_WIDTH: u16 = 30;
+
+/// The assets loaded by a [`LoadingScreen`], by name.
+#[derive(Default)]
+pub struct Assets {
+    assets: HashMap<String, Asset>,
+}
+
+impl Assets {
+    /// Takes the asset `name` out, if it was loaded as a `T`.
+    pub fn take<T: Any>(&mut self, name: &str) -> Result<T, EngineError> {
+        let asset = self
+            .assets
+            .remove(name)
+            .ok_or_else(|| EngineError::game(format!("no asset named {}", name)))?;
+        asset
+            .downcast::<T>()
+            .map(|asset| *asset)
+            .map_err(|_| EngineError::game(format!("asset {} has another type", name)))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.assets.contains_key(name)
+    }
+}
+
+/// Progress reported by the loading thread.
+enum Message {
+    Started(usize),
+    Loaded(usize, Asset),
+    Failed(usize, EngineError),
+}
+
+enum State {
+    /// Waiting for the first update to start loading
+    Idle(Vec<Load>),
+    Loading(Receiver<Message>),
+    /// Every asset is in; the game is built on the next update
+    Loaded,
+    /// Loading stopped; holds what went wrong
+    Failed(String),
+    Ready(Box<dyn Node>),
+}
+
+/// Loads assets on a worker thread while showing a progress bar, then
+/// swaps itself for the game built from them.
+///
+/// Assets are loaded one after the other, in the order they were added.
+/// Once all of them are in, the build function turns them into the game's
+/// node, and from then on the loading screen passes everything through to
+/// it. If an asset fails to load, loading stops and the screen shows the
+/// error; Esc or Enter then exits the game with code 1.
+///
+/// ```no_run
+/// use coil_engine::fiction::Story;
+/// use coil_engine::nodes::{LoadingScreen, StoryPlayer};
+///
+/// let screen = LoadingScreen::new(|mut assets| {
+///     let story: Story = assets.take("story")?;
+///     Ok(Box::new(StoryPlayer::new(story)?))
+/// })
+/// .with_asset("story", || Story::load("story.twee"));
+/// ```
+pub struct LoadingScreen {
+    names: Vec<String>,
+    assets: Assets,
+    /// Index of the asset being loaded
+    current: usize,
+    build: Option<Build>,
+    state: State,
+    bar: ResourceBar,
+    style: Style,
+    error_style: Style,
+}
+
+impl LoadingScreen {
+    /// Creates a loading screen that hands the loaded assets to `build`.
+    pub fn new(build: impl FnOnce(Assets) -> Result<Box<dyn Node>, EngineError> + 'static) -> Self {
+        Self {
+            names: Vec::new(),
+            assets: Assets::default(),
+            current: 0,
+            build: Some(Box::new(build)),
+            state: State::Idle(Vec::new()),
+            bar: ResourceBar::new(1.0, BAR_WIDTH)
+                .with_thresholds(Vec::new())
+                .with_speed(f32::INFINITY),
+            style: Style::new(),
+            error_style: crate::style!(red),
+        }
+    }
+
+    /// Adds an asset, loaded on the worker thread by `load`.
+    pub fn with_asset<T: Any + Send>(
+        mut self,
+        name: impl Into<String>,
+        load: impl FnOnce() -> Result<T, EngineError> + Send + 'static,
+    ) -> Self {
+        if let State::Idle(loads) = &mut self.state {
+            self.names.push(name.into());
+            loads.push(Box::new(move || {
+                load().map(|asset| Box::new(asset) as Asset)
+            }));
+        }
+        self
+    }
+
+    /// Returns the fraction of assets loaded, from 0.0 to 1.0.
+    pub fn progress(&self) -> f32 {
+        match self.state {
+            State::Loaded | State::Ready(_) => 1.0,
+            _ if self.names.is_empty() => 0.0,
+            _ => self.assets.assets.len() as f32 / self.names.len() as f32,
+        }
+    }
+
+    /// Returns the game once loading has finished.
+    pub fn game(&self) -> Option<&dyn Node> {
+        match &self.state {
+            State::Ready(game) => Some(game.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// Returns the error that stopped loading, if any.
+    pub fn error(&self) -> Option<&str> {
+        match &self.state {
+            State::Failed(message) => Some(message),
+            _ => None,
+        }
+    }
+
+    fn start(&mut self, loads: Vec<Load>) {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for (index, load) in loads.into_iter().enumerate() {
+                let _ = sender.send(Message::Started(index));
+                let message = match load() {
+                    Ok(asset) => Message::Loaded(index, asset),
+                    Err(e) => Message::Failed(index, e),
+                };
+                let failed = matches!(message, Message::Failed(..));
+                if sender.send(message).is_err() || failed {
+                    return;
+                }
+            }
+        });
+        self.state = State::Loading(receiver);
+    }
+
+    /// Takes in what the loading thread reported since the last update.
+    fn receive(&mut self) {
+        let State::Loading(receiver) = &self.state else {
+            return;
+        };
+        let next = loop {
+            match receiver.try_recv() {
+                Ok(Message::Started(index)) => self.current = index,
+                Ok(Message::Loaded(index, asset)) => {
+                    self.assets.assets.insert(self.names[index].clone(), asset);
+                }
+                Ok(Message::Failed(index, e)) => {
+                    break Some(State::Failed(format!(
+                        "Could not load {}: {}",
+                        self.names[index], e
+                    )));
+                }
+                Err(TryRecvError::Empty) => break None,
+                Err(TryRecvError::Disconnected) if self.assets.assets.len() == self.names.len() => {
+                    break Some(State::Loaded);
+                }
+                Err(TryRecvError::Disconnected) => {
+                    break Some(State::Failed("The loading thread stopped".to_string()));
+                }
+            }
+        };
+        if let Some(next) = next {
+            if let State::Failed(message) = &next {
+                error!("{}", message);
+            }
+            self.state = next;
+        }
+    }
+
+    fn finish(&mut self) {
+        let Some(build) = self.build.take() else {
+            return;
+        };
+        self.state = match build(std::mem::take(&mut self.assets)) {
+            Ok(game) => State::Ready(game),
+            Err(e) => {
+                let message = format!("Could not start the game: {}", e);
+                error!("{}", message);
+                State::Failed(message)
+            }
+        };
+    }
+}
+
+impl Node for LoadingScreen {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        if let State::Idle(loads) = &mut self.state {
+            let loads = std::mem::take(loads);
+            self.start(loads);
+        }
+        self.receive();
+        if let State::Loaded = self.state {
+            self.finish();
+        }
+        self.bar.set(self.progress());
+        self.bar.update(dt);
+        match &mut self.state {
+            State::Ready(game) => game.update(ctx, dt),
+            _ => Ok(()),
+        }
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        match &mut self.state {
+            State::Ready(game) => game.on_event(ctx, ev),
+            State::Failed(_) => match ev {
+                Event::Key(key) if matches!(key.code, KeyCode::Esc | KeyCode::Enter) => {
+                    ctx.request_exit(1);
+                    Ok(true)
+                }
+                _ => Ok(false),
+            },
+            _ => Ok(false),
+        }
+    }
+
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        if let State::Ready(game) = &mut self.state {
+            game.on_engine_event(ctx, ev);
+        }
+    }
+
+    fn on_pause(&mut self, ctx: &mut Context) {
+        if let State::Ready(game) = &mut self.state {
+            game.on_pause(ctx);
+        }
+    }
+
+    fn on_resume(&mut self, ctx: &mut Context) {
+        if let State::Ready(game) = &mut self.state {
+            game.on_resume(ctx);
+        }
+    }
+
+    fn on_resize(&mut self, ctx: &mut Context, width: u16, height: u16) {
+        if let State::Ready(game) = &mut self.state {
+            game.on_resize(ctx, width, height);
+        }
+    }
+
+    fn on_save(&self, save: &mut SaveData) -> Result<(), EngineError> {
+        match &self.state {
+            State::Ready(game) => game.on_save(save),
+            _ => Ok(()),
+        }
+    }
+
+    fn on_error(&mut self, ctx: &mut Context, err: &EngineError) -> Option<ErrorPolicy> {
+        match &mut self.state {
+            State::Ready(game) => game.on_error(ctx, err),
+            _ => {
+                warn!("Error while loading: {}", err);
+                None
+            }
+        }
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        let (width, height) = frame.size();
+        let (x, y) = (width / 2, height / 2);
+        let centered = |text: &str| x.saturating_sub(text.chars().count() as u16 / 2);
+        match &self.state {
+            State::Ready(game) => game.render(frame),
+            State::Failed(message) => {
+                frame.draw_styled(centered(message), y, message, self.error_style)?;
+                let hint = "Press Esc to quit";
+                frame.draw_styled(centered(hint), y + 2, hint, self.style)?;
+                Ok(())
+            }
+            _ => {
+                let title = match self.names.get(self.current) {
+                    Some(name) => format!("Loading {}...", name),
+                    None => "Loading...".to_string(),
+                };
+                frame.draw_styled(centered(&title), y.saturating_sub(1), &title, self.style)?;
+                // The bar is drawn above the given row
+                self.bar.draw(frame, x as f32, y as f32 + 2.0)?;
+                let count = format!("{}/{}", self.assets.assets.len(), self.names.len());
+                frame.draw_styled(centered(&count), y + 2, &count, self.style)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::nodes::Container;
+    use std::time::{Duration, Instant};
+
+    /// Updates the screen until `done` holds, failing after a second.
+    fn update_until(
+        screen: &mut LoadingScreen,
+        ctx: &mut Context,
+        done: impl Fn(&LoadingScreen) -> bool,
+    ) {
+        let start = Instant::now();
+        while !done(screen) {
+            assert!(
+                start.elapsed() < Duration::from_secs(1),
+                "loading timed out"
+            );
+            screen.update(ctx, 0.01).unwrap();
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_loads_assets_then_builds_the_game() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut screen = LoadingScreen::new(|mut assets| {
+            let (map, size): (String, u32) = (assets.take("map")?, assets.take("size")?);
+            assert!(assets.take::<u32>("map").is_err());
+            Ok(Box::new(Container::new(size as u16, map.len() as u16)))
+        })
+        .with_asset("map", || Ok("#..#".to_string()))
+        .with_asset("size", || Ok(3u32));
+        assert_eq!(screen.progress(), 0.0);
+
+        update_until(&mut screen, &mut ctx, |screen| screen.game().is_some());
+        assert_eq!(screen.progress(), 1.0);
+    }
+
+    #[test]
+    fn test_failed_asset_is_reported() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut screen = LoadingScreen::new(|_| Ok(Box::new(Container::new(0, 0))))
+            .with_asset("music", || {
+                Err::<(), _>(EngineError::game("file not found"))
+            });
+        update_until(&mut screen, &mut ctx, |screen| screen.error().is_some());
+        assert_eq!(
+            screen.error(),
+            Some("Could not load music: game error: file not found")
+        );
+        assert!(screen.game().is_none());
+
+        let esc = Event::Key(KeyCode::Esc.into());
+        assert!(screen.on_event(&mut ctx, &esc).unwrap());
+        assert_eq!(ctx.exit_requested(), Some(1));
+    }
+}
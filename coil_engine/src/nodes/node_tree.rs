@@ -0,0 +1,467 @@
+use crate::autosave::SaveData;
+use crate::config::ErrorPolicy;
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::nodes::{Node, UpdateSchedule};
+use crossterm::event::Event;
+
+/// A handle to a node stored in a [`NodeTree`].
+///
+/// Handles stay valid while the node is in the tree. Once it is removed its
+/// slot may be reused, but the old handle will not resolve to the new node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: u32,
+    generation: u32,
+}
+
+struct Entry {
+    node: Box<dyn Node>,
+    schedule: UpdateSchedule,
+    parent: Option<NodeId>,
+    /// Links to the children, and to the siblings, in the order they were added
+    first_child: Option<NodeId>,
+    last_child: Option<NodeId>,
+    prev: Option<NodeId>,
+    next: Option<NodeId>,
+}
+
+struct Slot {
+    generation: u32,
+    entry: Option<Entry>,
+}
+
+/// A tree of nodes stored flat in an arena and addressed by [`NodeId`].
+///
+/// Unlike nesting [`Container`](crate::nodes::Container)s, a node can be
+/// reached, added or removed through its id without walking the tree, and
+/// its id can be kept around to find it later.
+///
+/// Nodes are updated and rendered parents first, in the order they were
+/// added. Input goes the other way: the last drawn node sees it first, and
+/// children before their parent.
+///
+/// Nodes are updated at their [`update_interval`](Node::update_interval).
+#[derive(Default)]
+pub struct NodeTree {
+    slots: Vec<Slot>,
+    /// Indices of empty slots, reused before the arena grows
+    free: Vec<u32>,
+    first_root: Option<NodeId>,
+    last_root: Option<NodeId>,
+    len: usize,
+}
+
+/// An iterator over the children of a node or the top level nodes of a
+/// [`NodeTree`], in the order they were added.
+pub struct Siblings<'a> {
+    tree: &'a NodeTree,
+    next: Option<NodeId>,
+}
+
+impl Iterator for Siblings<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.next?;
+        self.next = self.tree.entry(id).and_then(|entry| entry.next);
+        Some(id)
+    }
+}
+
+impl NodeTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a node at the top level of the tree.
+    pub fn insert<N: Node + 'static>(&mut self, node: N) -> NodeId {
+        self.allocate(Box::new(node), None)
+    }
+
+    /// Adds a node under `parent`, after its other children.
+    pub fn insert_child<N: Node + 'static>(
+        &mut self,
+        parent: NodeId,
+        node: N,
+    ) -> Result<NodeId, EngineError> {
+        if !self.contains(parent) {
+            return Err(EngineError::game("parent node is not in the tree"));
+        }
+        Ok(self.allocate(Box::new(node), Some(parent)))
+    }
+
+    /// Removes a node along with its children and returns it.
+    pub fn remove(&mut self, id: NodeId) -> Option<Box<dyn Node>> {
+        let entry = self.release(id)?;
+        match entry.prev.and_then(|prev| self.entry_mut(prev)) {
+            Some(prev) => prev.next = entry.next,
+            None => *self.ends_mut(entry.parent).0 = entry.next,
+        }
+        match entry.next.and_then(|next| self.entry_mut(next)) {
+            Some(next) => next.prev = entry.prev,
+            None => *self.ends_mut(entry.parent).1 = entry.prev,
+        }
+        let mut orphans: Vec<NodeId> = entry.first_child.into_iter().collect();
+        while let Some(orphan) = orphans.pop() {
+            if let Some(removed) = self.release(orphan) {
+                orphans.extend(removed.next);
+                orphans.extend(removed.first_child);
+            }
+        }
+        Some(entry.node)
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.entry(id).is_some()
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&dyn Node> {
+        self.entry(id).map(|entry| entry.node.as_ref())
+    }
+
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut (dyn Node + 'static)> {
+        self.entry_mut(id).map(|entry| entry.node.as_mut())
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.entry(id)?.parent
+    }
+
+    /// Returns the children of a node, in the order they were added.
+    pub fn children(&self, id: NodeId) -> Siblings<'_> {
+        Siblings {
+            tree: self,
+            next: self.entry(id).and_then(|entry| entry.first_child),
+        }
+    }
+
+    /// Returns the top level nodes, in the order they were added.
+    pub fn roots(&self) -> Siblings<'_> {
+        Siblings {
+            tree: self,
+            next: self.first_root,
+        }
+    }
+
+    /// Returns the number of nodes in the tree.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn entry(&self, id: NodeId) -> Option<&Entry> {
+        let slot = self.slots.get(id.index as usize)?;
+        slot.entry
+            .as_ref()
+            .filter(|_| slot.generation == id.generation)
+    }
+
+    fn entry_mut(&mut self, id: NodeId) -> Option<&mut Entry> {
+        let slot = self.slots.get_mut(id.index as usize)?;
+        slot.entry
+            .as_mut()
+            .filter(|_| slot.generation == id.generation)
+    }
+
+    /// Returns the first and last child of `parent`, or the first and last
+    /// top level node for `None`.
+    fn ends_mut(&mut self, parent: Option<NodeId>) -> (&mut Option<NodeId>, &mut Option<NodeId>) {
+        match parent.and_then(|parent| self.slots[parent.index as usize].entry.as_mut()) {
+            Some(entry) => (&mut entry.first_child, &mut entry.last_child),
+            None => (&mut self.first_root, &mut self.last_root),
+        }
+    }
+
+    /// Stores a node after the other children of `parent`, which must be in
+    /// the tree.
+    fn allocate(&mut self, node: Box<dyn Node>, parent: Option<NodeId>) -> NodeId {
+        let entry = Some(Entry {
+            node,
+            schedule: UpdateSchedule::default(),
+            parent,
+            first_child: None,
+            last_child: None,
+            prev: *self.ends_mut(parent).1,
+            next: None,
+        });
+        self.len += 1;
+        let id = match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.entry = entry;
+                NodeId {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    entry,
+                });
+                NodeId {
+                    index: self.slots.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        };
+        let (first, last) = self.ends_mut(parent);
+        let prev = last.replace(id);
+        first.get_or_insert(id);
+        if let Some(prev) = prev.and_then(|prev| self.entry_mut(prev)) {
+            prev.next = Some(id);
+        }
+        id
+    }
+
+    /// Empties the slot of `id`, leaving the links to it as they are.
+    fn release(&mut self, id: NodeId) -> Option<Entry> {
+        self.entry(id)?;
+        let slot = &mut self.slots[id.index as usize];
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(id.index);
+        self.len -= 1;
+        slot.entry.take()
+    }
+
+    /// Returns the node after `id` in drawing order, where parents come
+    /// before their children.
+    fn next_in_order(&self, id: NodeId) -> Option<NodeId> {
+        let mut entry = self.entry(id)?;
+        if entry.first_child.is_some() {
+            return entry.first_child;
+        }
+        loop {
+            if entry.next.is_some() {
+                return entry.next;
+            }
+            entry = self.entry(entry.parent?)?;
+        }
+    }
+
+    /// Returns the node before `id` in drawing order.
+    fn prev_in_order(&self, id: NodeId) -> Option<NodeId> {
+        let entry = self.entry(id)?;
+        match entry.prev {
+            Some(prev) => Some(self.last_descendant(prev)),
+            None => entry.parent,
+        }
+    }
+
+    /// Returns the node drawn last among `id` and its descendants.
+    fn last_descendant(&self, mut id: NodeId) -> NodeId {
+        while let Some(last) = self.entry(id).and_then(|entry| entry.last_child) {
+            id = last;
+        }
+        id
+    }
+
+    /// Returns every node in drawing order.
+    fn ordered(&self) -> impl Iterator<Item = &Entry> {
+        std::iter::successors(self.first_root, |&id| self.next_in_order(id))
+            .filter_map(|id| self.entry(id))
+    }
+
+    /// Calls `visit` with every node in drawing order.
+    fn for_each_mut(&mut self, mut visit: impl FnMut(&mut dyn Node)) {
+        let mut cursor = self.first_root;
+        while let Some(id) = cursor {
+            if let Some(node) = self.get_mut(id) {
+                visit(node);
+            }
+            cursor = self.next_in_order(id);
+        }
+    }
+}
+
+impl Node for NodeTree {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        let mut cursor = self.first_root;
+        while let Some(id) = cursor {
+            if let Some(entry) = self.entry_mut(id) {
+                entry.schedule.tick(entry.node.as_mut(), ctx, dt)?;
+            }
+            cursor = self.next_in_order(id);
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        let mut cursor = self.last_root.map(|root| self.last_descendant(root));
+        while let Some(id) = cursor {
+            if let Some(node) = self.get_mut(id)
+                && node.on_event(ctx, ev)?
+            {
+                return Ok(true);
+            }
+            cursor = self.prev_in_order(id);
+        }
+        Ok(false)
+    }
+
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        self.for_each_mut(|node| node.on_engine_event(ctx, ev));
+    }
+
+    fn on_pause(&mut self, ctx: &mut Context) {
+        self.for_each_mut(|node| node.on_pause(ctx));
+    }
+
+    fn on_resume(&mut self, ctx: &mut Context) {
+        self.for_each_mut(|node| node.on_resume(ctx));
+    }
+
+    fn on_resize(&mut self, ctx: &mut Context, width: u16, height: u16) {
+        self.for_each_mut(|node| node.on_resize(ctx, width, height));
+    }
+
+    fn on_save(&self, save: &mut SaveData) -> Result<(), EngineError> {
+        for entry in self.ordered() {
+            entry.node.on_save(save)?;
+        }
+        Ok(())
+    }
+
+    fn on_error(&mut self, ctx: &mut Context, err: &EngineError) -> Option<ErrorPolicy> {
+        let mut cursor = self.first_root;
+        while let Some(id) = cursor {
+            if let Some(policy) = self.get_mut(id).and_then(|node| node.on_error(ctx, err)) {
+                return Some(policy);
+            }
+            cursor = self.next_in_order(id);
+        }
+        None
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.slots
+            .iter()
+            .filter_map(|slot| slot.entry.as_ref())
+            .any(|entry| entry.node.is_dirty())
+    }
+
+    fn mark_clean(&mut self) {
+        for entry in self.slots.iter_mut().filter_map(|slot| slot.entry.as_mut()) {
+            entry.node.mark_clean();
+        }
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        for entry in self.ordered() {
+            entry.node.render(frame)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::geometry::Rect;
+    use crate::renderer::BasicRenderer;
+    use crossterm::event::KeyCode;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Log = Rc<RefCell<String>>;
+
+    /// Writes its letter to the log when drawn and takes its own key.
+    struct Letter(char, Log);
+
+    impl Node for Letter {
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+            Ok(())
+        }
+
+        fn on_event(&mut self, _ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+            Ok(matches!(ev, Event::Key(key) if key.code == KeyCode::Char(self.0)))
+        }
+
+        fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+            self.1.borrow_mut().push(self.0);
+            Ok(())
+        }
+    }
+
+    impl Letter {
+        fn new(log: &Log, ch: char) -> Self {
+            Self(ch, Rc::clone(log))
+        }
+    }
+
+    fn drawn(tree: &NodeTree, log: &Log) -> String {
+        let mut renderer = BasicRenderer::new(8, 1).unwrap();
+        let mut frame = Frame::new(&mut renderer, Rect::new(0, 0, 8, 1), 1.0);
+        tree.render(&mut frame).unwrap();
+        log.take()
+    }
+
+    #[test]
+    fn test_nodes_render_parents_first() {
+        let log = Log::default();
+        let mut tree = NodeTree::new();
+        let a = tree.insert(Letter::new(&log, 'a'));
+        let d = tree.insert(Letter::new(&log, 'd'));
+        let b = tree.insert_child(a, Letter::new(&log, 'b')).unwrap();
+        tree.insert_child(b, Letter::new(&log, 'c')).unwrap();
+        tree.insert_child(d, Letter::new(&log, 'e')).unwrap();
+        assert_eq!(tree.len(), 5);
+        assert_eq!(tree.parent(b), Some(a));
+        assert_eq!(drawn(&tree, &log), "abcde");
+
+        let mut ctx = Context::new(GameConfig::new());
+        let key = Event::Key(KeyCode::Char('b').into());
+        assert!(tree.on_event(&mut ctx, &key).unwrap());
+        tree.remove(b);
+        assert!(!tree.on_event(&mut ctx, &key).unwrap());
+        assert_eq!(drawn(&tree, &log), "ade");
+    }
+
+    #[test]
+    fn test_removing_a_sibling_keeps_the_order() {
+        let log = Log::default();
+        let mut tree = NodeTree::new();
+        let a = tree.insert(Letter::new(&log, 'a'));
+        let [b, c, d] =
+            ['b', 'c', 'd'].map(|ch| tree.insert_child(a, Letter::new(&log, ch)).unwrap());
+        tree.insert_child(c, Letter::new(&log, 'x')).unwrap();
+        let e = tree.insert(Letter::new(&log, 'e'));
+        assert_eq!(drawn(&tree, &log), "abcxde");
+
+        tree.remove(c);
+        assert_eq!(tree.children(a).collect::<Vec<_>>(), [b, d]);
+        tree.remove(d);
+        tree.insert_child(a, Letter::new(&log, 'f')).unwrap();
+        assert_eq!(drawn(&tree, &log), "abfe");
+        tree.remove(a);
+        assert_eq!(tree.roots().collect::<Vec<_>>(), [e]);
+        assert_eq!(tree.len(), 1);
+    }
+
+    #[test]
+    fn test_removed_ids_do_not_resolve() {
+        let log = Log::default();
+        let mut tree = NodeTree::new();
+        let a = tree.insert(Letter::new(&log, 'a'));
+        let b = tree.insert_child(a, Letter::new(&log, 'b')).unwrap();
+        let c = tree.insert(Letter::new(&log, 'c'));
+        assert!(tree.remove(a).is_some());
+        assert_eq!(tree.len(), 1);
+        assert!(!tree.contains(b));
+        assert!(tree.insert_child(b, Letter::new(&log, 'x')).is_err());
+
+        // The freed slots are reused under new generations
+        let d = tree.insert(Letter::new(&log, 'd'));
+        assert_ne!(d, a);
+        assert_ne!(d, b);
+        assert!(tree.get(a).is_none());
+        assert_eq!(tree.roots().collect::<Vec<_>>(), [c, d]);
+        assert_eq!(drawn(&tree, &log), "cd");
+    }
+}
@@ -0,0 +1,392 @@
+use crate::actions::KeyBinding;
+use crate::autosave::SaveData;
+use crate::config::ErrorPolicy;
+use crate::context::{Context, EngineEvent};
+use crate::core::Game;
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::nodes::Node;
+use crate::nodes::hosted::Hosted;
+use crate::style::Style;
+use crossterm::event::{Event, KeyCode, KeyModifiers, MouseEvent, MouseEventKind};
+
+struct Pane {
+    title: String,
+    /// Area of the pane, title row included, in the host's coordinates
+    area: Rect,
+    /// The pane's game, with its own context so games cannot see or change
+    /// each other's state
+    game: Hosted,
+}
+
+impl Pane {
+    /// Returns the area the game draws into, below the title row.
+    fn content(&self) -> Rect {
+        Rect::new(
+            self.area.x,
+            self.area.y.saturating_add(1),
+            self.area.width,
+            self.area.height.saturating_sub(1),
+        )
+    }
+}
+
+/// Runs several games side by side in one terminal, each in its own pane.
+///
+/// Every pane has its own [`Context`], with a viewport the size of the pane,
+/// so a game sees its pane as the whole screen and its pauses, exit requests
+/// and configuration changes stay within it. Panes are placed by the caller,
+/// e.g. a launcher with mini-games in a grid or a small demo pictured in a
+/// corner; later panes are drawn over earlier ones.
+///
+/// * F6: focus the next running pane
+/// * Mouse: events go to the pane under the pointer, in its coordinates,
+///   and a click focuses it
+///
+/// Key events go to the focused pane only. A pane whose game requests an
+/// exit stops and shows its exit code; once all of them have, the host
+/// exits too. Notifications, popups and terminal commands of a pane's
+/// game are not shown.
+pub struct Panes {
+    panes: Vec<Pane>,
+    focused: usize,
+    focus_key: KeyBinding,
+    style: Style,
+    focused_style: Style,
+}
+
+impl Panes {
+    pub fn new() -> Self {
+        Self {
+            panes: Vec::new(),
+            focused: 0,
+            focus_key: KeyBinding::new(KeyCode::F(6), KeyModifiers::NONE),
+            style: crate::style!(white on dark_grey),
+            focused_style: crate::style!(black on white),
+        }
+    }
+
+    /// Adds a pane running `node` with the default configuration.
+    ///
+    /// `area` includes the pane's title row and is relative to the host.
+    pub fn with_pane<N: Node + 'static>(
+        self,
+        title: impl Into<String>,
+        area: Rect,
+        node: N,
+    ) -> Self {
+        self.with_game(title, area, Game::new(node))
+    }
+
+    /// Adds a pane running `game` with its own configuration.
+    pub fn with_game<N: Node + 'static>(
+        mut self,
+        title: impl Into<String>,
+        area: Rect,
+        game: Game<N>,
+    ) -> Self {
+        let mut pane = Pane {
+            title: title.into(),
+            area,
+            game: Hosted::new(Box::new(game.node), game.config, (0, 0)),
+        };
+        pane.game.resize(pane.content().size());
+        self.panes.push(pane);
+        self
+    }
+
+    /// Replaces the key moving the focus to the next pane.
+    pub fn with_focus_key(mut self, key: impl Into<KeyBinding>) -> Self {
+        self.focus_key = key.into();
+        self
+    }
+
+    /// Returns the index of the pane receiving key events.
+    pub fn focused(&self) -> usize {
+        self.focused
+    }
+
+    /// Focuses pane `index`, announcing its title; out of range indices are ignored.
+    pub fn focus(&mut self, ctx: &mut Context, index: usize) {
+        if index < self.panes.len() && index != self.focused {
+            self.focused = index;
+            ctx.announce(format!("{} pane", self.panes[index].title));
+        }
+    }
+
+    /// Returns the number of panes.
+    pub fn len(&self) -> usize {
+        self.panes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.panes.is_empty()
+    }
+
+    pub fn pane(&self, index: usize) -> Option<&dyn Node> {
+        self.panes.get(index).map(|pane| pane.game.node.as_ref())
+    }
+
+    pub fn pane_mut(&mut self, index: usize) -> Option<&mut (dyn Node + 'static)> {
+        self.panes
+            .get_mut(index)
+            .map(|pane| pane.game.node.as_mut())
+    }
+
+    /// Returns the context of pane `index`.
+    pub fn context(&self, index: usize) -> Option<&Context> {
+        self.panes.get(index).map(|pane| &pane.game.ctx)
+    }
+
+    /// Returns the exit code of pane `index` if its game has ended.
+    pub fn exit_code(&self, index: usize) -> Option<i32> {
+        self.panes.get(index)?.game.exited
+    }
+
+    /// Returns the topmost running pane under a point and the point in its
+    /// content's coordinates.
+    fn under(&self, column: u16, row: u16) -> Option<(usize, Option<(u16, u16)>)> {
+        let index = self
+            .panes
+            .iter()
+            .rposition(|pane| pane.game.is_running() && pane.area.contains(column, row))?;
+        let content = self.panes[index].content();
+        let local = content
+            .contains(column, row)
+            .then(|| (column - content.x, row - content.y));
+        Some((index, local))
+    }
+
+    fn focus_next(&mut self, ctx: &mut Context) {
+        let count = self.panes.len();
+        let next = (1..count)
+            .map(|step| (self.focused + step) % count)
+            .find(|&index| self.panes[index].game.is_running());
+        if let Some(index) = next {
+            self.focus(ctx, index);
+        }
+    }
+}
+
+impl Default for Panes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Node for Panes {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        for pane in &mut self.panes {
+            if pane.game.is_running() && !pane.game.ctx.is_paused() {
+                pane.game.update(dt)?;
+            }
+            pane.game.settle();
+        }
+        if !self.panes.is_empty() && self.panes.iter().all(|pane| !pane.game.is_running()) {
+            ctx.request_exit(0);
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        match ev {
+            Event::Key(key) if self.focus_key.matches(key) => {
+                self.focus_next(ctx);
+                Ok(true)
+            }
+            Event::Mouse(mouse) => {
+                let Some((index, local)) = self.under(mouse.column, mouse.row) else {
+                    return Ok(false);
+                };
+                if let MouseEventKind::Down(_) = mouse.kind {
+                    self.focus(ctx, index);
+                }
+                let Some((column, row)) = local else {
+                    // Clicks on the title row only focus the pane
+                    return Ok(true);
+                };
+                let pane = &mut self.panes[index];
+                let local = Event::Mouse(MouseEvent {
+                    column,
+                    row,
+                    ..*mouse
+                });
+                let consumed = pane.game.node.on_event(&mut pane.game.ctx, &local)?;
+                pane.game.settle();
+                Ok(consumed)
+            }
+            Event::Resize(..) => {
+                for pane in &mut self.panes {
+                    pane.game.node.on_event(&mut pane.game.ctx, ev)?;
+                }
+                Ok(false)
+            }
+            _ => match self.panes.get_mut(self.focused) {
+                Some(pane) if pane.game.is_running() => {
+                    let consumed = pane.game.node.on_event(&mut pane.game.ctx, ev)?;
+                    pane.game.settle();
+                    Ok(consumed)
+                }
+                _ => Ok(false),
+            },
+        }
+    }
+
+    fn on_engine_event(&mut self, _ctx: &mut Context, ev: &EngineEvent) {
+        match ev {
+            // Regions drawn by a pane are registered with the host, so hits
+            // go to the pane under the pointer
+            EngineEvent::Hit(hit) => {
+                if let Some((index, _)) = self.under(hit.event.column, hit.event.row) {
+                    let pane = &mut self.panes[index];
+                    pane.game.node.on_engine_event(&mut pane.game.ctx, ev);
+                }
+            }
+            EngineEvent::Idle | EngineEvent::ActivityResumed => {
+                for pane in &mut self.panes {
+                    pane.game.ctx.set_idle(matches!(ev, EngineEvent::Idle));
+                    pane.game.node.on_engine_event(&mut pane.game.ctx, ev);
+                }
+            }
+            // The rest concern the host's own context
+            _ => {}
+        }
+    }
+
+    fn on_pause(&mut self, _ctx: &mut Context) {
+        for pane in &mut self.panes {
+            pane.game.node.on_pause(&mut pane.game.ctx);
+        }
+    }
+
+    fn on_resume(&mut self, _ctx: &mut Context) {
+        for pane in &mut self.panes {
+            pane.game.node.on_resume(&mut pane.game.ctx);
+        }
+    }
+
+    fn on_save(&self, save: &mut SaveData) -> Result<(), EngineError> {
+        for pane in &self.panes {
+            pane.game.node.on_save(save)?;
+        }
+        Ok(())
+    }
+
+    fn on_error(&mut self, _ctx: &mut Context, err: &EngineError) -> Option<ErrorPolicy> {
+        let pane = self.panes.get_mut(self.focused)?;
+        pane.game.node.on_error(&mut pane.game.ctx, err)
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        for (index, pane) in self.panes.iter().enumerate() {
+            let style = if index == self.focused {
+                self.focused_style
+            } else {
+                self.style
+            };
+            let mut title = frame.child(Rect::new(pane.area.x, pane.area.y, pane.area.width, 1));
+            title.draw_styled(0, 0, &" ".repeat(pane.area.width as usize), style)?;
+            title.draw_styled(1, 0, &pane.title, style)?;
+
+            let mut content = frame.child(pane.content());
+            content.clear()?;
+            match pane.game.exited {
+                Some(code) => {
+                    content.draw_styled(0, 0, &format!("Exited with code {code}"), Style::new())?;
+                }
+                None => pane.game.node.render(&mut content)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::renderer::TestRenderer;
+    use crossterm::event::{KeyEvent, MouseButton};
+
+    /// Shows the last key or click it got and quits on `q`.
+    #[derive(Default)]
+    struct Echo(String);
+
+    impl Node for Echo {
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+            Ok(())
+        }
+
+        fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+            match ev {
+                Event::Key(key) if key.code == KeyCode::Char('q') => ctx.request_exit(3),
+                Event::Key(key) => self.0 = key.code.to_string(),
+                Event::Mouse(mouse) => self.0 = format!("{},{}", mouse.column, mouse.row),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        }
+
+        fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+            frame.draw_styled(0, 0, &self.0, Style::new())?;
+            Ok(())
+        }
+    }
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn test_input_goes_to_its_pane() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut panes = Panes::new()
+            .with_pane("A", Rect::new(0, 0, 8, 3), Echo::default())
+            .with_pane("B", Rect::new(8, 0, 8, 3), Echo::default());
+        assert_eq!(panes.context(1).unwrap().screen_size(), (8, 2));
+        let mut renderer = TestRenderer::new(16, 3);
+
+        panes.on_event(&mut ctx, &key(KeyCode::Char('x'))).unwrap();
+        let click = Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 11,
+            row: 2,
+            modifiers: KeyModifiers::NONE,
+        });
+        assert!(panes.on_event(&mut ctx, &click).unwrap());
+        assert_eq!(panes.focused(), 1);
+        renderer.render(&panes).unwrap();
+        assert_eq!(renderer.row(1), "x       3,1");
+
+        panes.on_event(&mut ctx, &key(KeyCode::Char('y'))).unwrap();
+        renderer.render(&panes).unwrap();
+        assert_eq!(renderer.row(1), "x       y");
+        panes.on_event(&mut ctx, &key(KeyCode::F(6))).unwrap();
+        assert_eq!(panes.focused(), 0);
+    }
+
+    #[test]
+    fn test_host_exits_after_every_pane() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut panes = Panes::new()
+            .with_pane("A", Rect::new(0, 0, 16, 2), Echo::default())
+            .with_pane("B", Rect::new(0, 2, 16, 1), Echo::default());
+        panes.on_event(&mut ctx, &key(KeyCode::Char('q'))).unwrap();
+        panes.update(&mut ctx, 0.1).unwrap();
+        assert_eq!(panes.exit_code(0), Some(3));
+        let mut renderer = TestRenderer::new(16, 3);
+        renderer.render(&panes).unwrap();
+        assert_eq!(renderer.row(1), "Exited with code");
+        assert_eq!(ctx.exit_requested(), None);
+
+        // The exited pane is skipped when moving the focus
+        panes.on_event(&mut ctx, &key(KeyCode::F(6))).unwrap();
+        assert_eq!(panes.focused(), 1);
+        panes.on_event(&mut ctx, &key(KeyCode::F(6))).unwrap();
+        assert_eq!(panes.focused(), 1);
+        panes.on_event(&mut ctx, &key(KeyCode::Char('q'))).unwrap();
+        panes.update(&mut ctx, 0.1).unwrap();
+        assert_eq!(ctx.exit_requested(), Some(0));
+    }
+}
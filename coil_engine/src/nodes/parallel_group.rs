@@ -0,0 +1,201 @@
+use crate::autosave::SaveData;
+use crate::config::ErrorPolicy;
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::nodes::Node;
+use crossterm::event::Event;
+
+/// The part of a node's update that can run on any thread.
+///
+/// [`Node`] methods take the engine [`Context`], which is tied to the main
+/// thread, so they cannot run in parallel. A node that does heavy work each
+/// tick, such as simulating a chunk of the world, splits its update in two:
+/// [`ParallelUpdate::par_update`] does the work on its own data, without
+/// the context, and [`ParallelUpdate::after_update`] then runs on the main
+/// thread to act on the result, e.g. by spawning popups or notifications.
+///
+/// Implementors must be `Send`: they may not hold `Rc`s, `RefCell`s or
+/// anything else shared with other nodes without synchronization.
+pub trait ParallelUpdate: Send {
+    /// Advances the node by `dt` seconds, possibly on a worker thread.
+    fn par_update(&mut self, dt: f32) -> Result<(), EngineError>;
+
+    /// Called on the main thread after every node of the group was updated.
+    fn after_update(&mut self, _ctx: &mut Context) -> Result<(), EngineError> {
+        Ok(())
+    }
+}
+
+/// A container whose children are updated in parallel.
+///
+/// With the `parallel` feature the children's [`ParallelUpdate::par_update`]
+/// runs on rayon's thread pool; without it they run one after the other.
+/// Event handling and rendering stay on the main thread and go through the
+/// children's [`Node`] methods, like in a [`Container`](crate::nodes::Container);
+/// their [`Node::update`] is not called.
+pub struct ParallelGroup<T> {
+    pub children: Vec<T>,
+}
+
+impl<T: Node + ParallelUpdate> ParallelGroup<T> {
+    pub fn new() -> Self {
+        Self {
+            children: Vec::new(),
+        }
+    }
+
+    /// Pushes a child and returns `self`, so you can chain.
+    pub fn with_child(mut self, child: T) -> Self {
+        self.children.push(child);
+        self
+    }
+}
+
+impl<T: Node + ParallelUpdate> Default for ParallelGroup<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Node + ParallelUpdate> Node for ParallelGroup<T> {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            self.children
+                .par_iter_mut()
+                .try_for_each(|c| c.par_update(dt))?;
+        }
+        #[cfg(not(feature = "parallel"))]
+        for c in &mut self.children {
+            c.par_update(dt)?;
+        }
+        for c in &mut self.children {
+            c.after_update(ctx)?;
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        for c in self.children.iter_mut().rev() {
+            if c.on_event(ctx, ev)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        for c in &mut self.children {
+            c.on_engine_event(ctx, ev);
+        }
+    }
+
+    fn on_pause(&mut self, ctx: &mut Context) {
+        for c in &mut self.children {
+            c.on_pause(ctx);
+        }
+    }
+
+    fn on_resume(&mut self, ctx: &mut Context) {
+        for c in &mut self.children {
+            c.on_resume(ctx);
+        }
+    }
+
+    fn on_resize(&mut self, ctx: &mut Context, width: u16, height: u16) {
+        for c in &mut self.children {
+            c.on_resize(ctx, width, height);
+        }
+    }
+
+    fn on_save(&self, save: &mut SaveData) -> Result<(), EngineError> {
+        for c in &self.children {
+            c.on_save(save)?;
+        }
+        Ok(())
+    }
+
+    fn on_error(&mut self, ctx: &mut Context, err: &EngineError) -> Option<ErrorPolicy> {
+        self.children.iter_mut().find_map(|c| c.on_error(ctx, err))
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.children.iter().any(|c| c.is_dirty())
+    }
+
+    fn mark_clean(&mut self) {
+        self.children.iter_mut().for_each(|c| c.mark_clean());
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        for c in &self.children {
+            c.render(frame)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::notify::Level;
+    use std::time::Duration;
+
+    /// Sums a range of numbers each tick and reports when it reaches a goal.
+    struct Summer {
+        total: u64,
+        goal: u64,
+        reported: bool,
+    }
+
+    impl ParallelUpdate for Summer {
+        fn par_update(&mut self, _dt: f32) -> Result<(), EngineError> {
+            self.total += (1..=1000u64).sum::<u64>();
+            Ok(())
+        }
+
+        fn after_update(&mut self, ctx: &mut Context) -> Result<(), EngineError> {
+            if self.total >= self.goal && !self.reported {
+                self.reported = true;
+                ctx.notify("Goal reached", Level::Info, Duration::from_secs(1));
+            }
+            Ok(())
+        }
+    }
+
+    impl Node for Summer {
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+            unreachable!("updated through ParallelUpdate")
+        }
+
+        fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+            Ok(false)
+        }
+
+        fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_children_update_then_apply_on_main_thread() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut group = ParallelGroup::new();
+        for goal in [500_500, 1_001_000, u64::MAX] {
+            group = group.with_child(Summer {
+                total: 0,
+                goal,
+                reported: false,
+            });
+        }
+        group.update(&mut ctx, 0.1).unwrap();
+        group.update(&mut ctx, 0.1).unwrap();
+        let totals: Vec<u64> = group.children.iter().map(|c| c.total).collect();
+        assert_eq!(totals, [1_001_000; 3]);
+        let reported: Vec<bool> = group.children.iter().map(|c| c.reported).collect();
+        assert_eq!(reported, [true, true, false]);
+    }
+}
@@ -0,0 +1,133 @@
+use crate::config::Config;
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::nodes::Node;
+use crate::style::{Style, Theme};
+use crate::text::measure_text;
+use crossterm::event::Event;
+
+/// Default pause screen: a message centered in its frame while the game is paused.
+///
+/// Add it as the last child of the root so it is drawn over the game. The
+/// message is drawn in the overlay style of the game's [`Theme`] unless
+/// given one with [`PauseOverlay::with_style`].
+pub struct PauseOverlay {
+    text: String,
+    style: Option<Style>,
+    theme: Theme,
+    paused: bool,
+}
+
+impl PauseOverlay {
+    pub fn new() -> Self {
+        Self {
+            text: "Paused".to_string(),
+            style: None,
+            theme: Theme::default(),
+            paused: false,
+        }
+    }
+
+    /// Replaces the message shown while paused.
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.text = text.into();
+        self
+    }
+
+    /// Replaces the style the message is drawn in.
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = Some(style);
+        self
+    }
+}
+
+impl Default for PauseOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Node for PauseOverlay {
+    fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+        Ok(false)
+    }
+
+    fn on_pause(&mut self, ctx: &mut Context) {
+        self.theme = ctx.config().theme;
+        self.paused = true;
+    }
+
+    fn on_resume(&mut self, _ctx: &mut Context) {
+        self.paused = false;
+    }
+
+    fn on_engine_event(&mut self, _ctx: &mut Context, ev: &EngineEvent) {
+        if let EngineEvent::ConfigChanged(Config::Theme(theme)) = ev {
+            self.theme = *theme;
+        }
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        if self.paused {
+            let (width, height) = frame.size();
+            let (text_width, text_height) = measure_text(&self.text, width);
+            let x = width.saturating_sub(text_width) / 2;
+            let y = height.saturating_sub(text_height) / 2;
+            let style = self.style.unwrap_or(self.theme.overlay);
+            frame.draw_text(x, y, &self.text, style)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::geometry::Rect;
+    use crate::renderer::BasicRenderer;
+
+    #[test]
+    fn test_overlay_follows_pause_hooks() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut overlay = PauseOverlay::new().with_text("Zzz");
+        let mut renderer = BasicRenderer::new(7, 3).unwrap();
+        let area = Rect::new(0, 0, 7, 3);
+
+        overlay
+            .render(&mut Frame::new(&mut renderer, area, 0.0))
+            .unwrap();
+        assert_eq!(renderer.cell(2, 1).unwrap().ch, ' ');
+
+        overlay.on_pause(&mut ctx);
+        overlay
+            .render(&mut Frame::new(&mut renderer, area, 0.0))
+            .unwrap();
+        assert_eq!(renderer.cell(2, 1).unwrap().ch, 'Z');
+        assert_eq!(
+            renderer.cell(2, 1).unwrap().style(),
+            crate::style!(black on white)
+        );
+
+        let theme = Theme {
+            overlay: crate::style!(yellow on black),
+            ..Theme::default()
+        };
+        ctx.set_theme(theme);
+        for change in ctx.apply_pending() {
+            overlay.on_engine_event(&mut ctx, &EngineEvent::ConfigChanged(change));
+        }
+        overlay
+            .render(&mut Frame::new(&mut renderer, area, 0.0))
+            .unwrap();
+        assert_eq!(
+            renderer.cell(2, 1).unwrap().style(),
+            crate::style!(yellow on black)
+        );
+    }
+}
@@ -0,0 +1,316 @@
+use crate::autosave::SaveData;
+use crate::config::GameConfig;
+use crate::context::Context;
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::nodes::Node;
+use crate::nodes::hosted::Hosted;
+use crate::replay::{self, Replay};
+use crate::style::Style;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+
+type Factory = Box<dyn Fn() -> Box<dyn Node>>;
+
+/// Rows of the input timeline at the bottom
+const TIMELINE_ROWS: usize = 5;
+
+/// Plays a [`Replay`] back with controls for inspecting it, e.g. to find
+/// the tick where a run stops matching a recording.
+///
+/// With a game from [`ReplayInspector::with_game`], a fresh instance of it
+/// is fed the replay's keys, one tick per update, and a hash of the state
+/// it saves through [`Node::on_save`] is shown after each tick; two runs
+/// that keep the same hashes saved the same state. Without one, only the
+/// replay's inputs are shown.
+///
+/// Space pauses and resumes, Left/Right step a tick back or forward,
+/// Page Up/Page Down seek a second, Home/End jump to either end, and Esc or
+/// `q` exits. Ticks always last the frame duration of the game's
+/// configuration, so seeking back, which plays the game again from the
+/// start, reaches the same state.
+pub struct ReplayInspector {
+    replay: Replay,
+    create: Option<Factory>,
+    config: GameConfig,
+    game: Option<Hosted>,
+    /// Ticks played so far
+    tick: u64,
+    playing: bool,
+    style: Style,
+}
+
+impl ReplayInspector {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            create: None,
+            config: GameConfig::new(),
+            game: None,
+            tick: 0,
+            playing: false,
+            style: Style::new().reverse(),
+        }
+    }
+
+    /// Plays the replay in instances of the game made by `create`.
+    pub fn with_game<N, F>(mut self, create: F) -> Self
+    where
+        N: Node + 'static,
+        F: Fn() -> N + 'static,
+    {
+        self.create = Some(Box::new(move || Box::new(create())));
+        self
+    }
+
+    /// Runs the game with `config`, whose frame duration also sets the
+    /// length of a tick.
+    pub fn with_config(mut self, config: GameConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Returns the number of ticks played.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Returns whether ticks are played on each update.
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Returns a hash of the state the game saves, or `None` without a game.
+    ///
+    /// The hash is FNV-1a over the saved text, so it is the same across
+    /// runs and builds.
+    pub fn state_hash(&self) -> Option<u64> {
+        let game = self.game.as_ref()?;
+        let mut save = SaveData::new();
+        game.node.on_save(&mut save).ok()?;
+        let hash = save
+            .to_string()
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+            });
+        Some(hash)
+    }
+
+    /// Plays the replay up to `tick`, from the start when going back.
+    pub fn seek(&mut self, ctx: &mut Context, tick: u64) -> Result<(), EngineError> {
+        let tick = tick.min(self.replay.length());
+        if tick < self.tick || self.game.is_none() {
+            self.restart(ctx);
+        }
+        while self.tick < tick {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    fn restart(&mut self, ctx: &Context) {
+        self.tick = 0;
+        self.game = self
+            .create
+            .as_ref()
+            .map(|create| Hosted::new(create(), self.config.clone(), ctx.screen_size()));
+    }
+
+    /// Plays one tick, returning whether there was one left to play.
+    fn step(&mut self) -> Result<bool, EngineError> {
+        if self.tick >= self.replay.length() {
+            return Ok(false);
+        }
+        if let Some(game) = self.game.as_mut().filter(|game| game.is_running()) {
+            for key in self.replay.keys_at(self.tick) {
+                game.node.on_event(&mut game.ctx, &replay::key_event(key))?;
+            }
+            let dt = self.config.frame_duration().as_secs_f32();
+            game.update(dt)?;
+            game.settle();
+        }
+        self.tick += 1;
+        Ok(true)
+    }
+
+    fn status(&self) -> String {
+        let state = if self.playing { "playing" } else { "paused" };
+        let hash = match self.state_hash() {
+            Some(hash) => format!("state {:016x}", hash),
+            None => "inputs only".to_string(),
+        };
+        let exited = match self.game.as_ref().and_then(|game| game.exited) {
+            Some(code) => format!("  exited with {}", code),
+            None => String::new(),
+        };
+        format!(
+            " tick {}/{}  {}  {}{}",
+            self.tick,
+            self.replay.length(),
+            state,
+            hash,
+            exited
+        )
+    }
+
+    /// Returns the timeline rows: the ticks with input around the current
+    /// one, each with its keys, the last one played marked.
+    fn timeline(&self) -> Vec<String> {
+        let mut ticks: Vec<u64> = self.replay.inputs().map(|(tick, _)| tick).collect();
+        ticks.dedup();
+        let next = ticks.partition_point(|&tick| tick < self.tick);
+        let start = next.saturating_sub(TIMELINE_ROWS / 2 + 1);
+        ticks[start..]
+            .iter()
+            .take(TIMELINE_ROWS)
+            .map(|&tick| {
+                let marker = if tick + 1 == self.tick { '>' } else { ' ' };
+                let keys: Vec<String> = self.replay.keys_at(tick).map(|k| k.to_string()).collect();
+                format!("{} {:>6}  {}", marker, tick, keys.join(" "))
+            })
+            .collect()
+    }
+}
+
+impl Node for ReplayInspector {
+    fn update(&mut self, ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+        if self.game.is_none() && self.create.is_some() {
+            self.restart(ctx);
+        }
+        if let Some(game) = &mut self.game {
+            game.resize(ctx.screen_size());
+        }
+        if self.playing && !self.step()? {
+            self.playing = false;
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        let Event::Key(key) = ev else {
+            return Ok(false);
+        };
+        if key.kind == KeyEventKind::Release {
+            return Ok(false);
+        }
+        let second = self.config.target_fps as u64;
+        match key.code {
+            KeyCode::Char(' ') => self.playing = !self.playing,
+            KeyCode::Right => {
+                self.playing = false;
+                self.seek(ctx, self.tick + 1)?;
+            }
+            KeyCode::Left => {
+                self.playing = false;
+                self.seek(ctx, self.tick.saturating_sub(1))?;
+            }
+            KeyCode::PageDown => self.seek(ctx, self.tick + second)?,
+            KeyCode::PageUp => self.seek(ctx, self.tick.saturating_sub(second))?,
+            KeyCode::Home => self.seek(ctx, 0)?,
+            KeyCode::End => self.seek(ctx, self.replay.length())?,
+            KeyCode::Char('q') | KeyCode::Esc => ctx.request_exit(0),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        if let Some(game) = &self.game {
+            game.node.render(frame)?;
+        }
+        let (width, height) = frame.size();
+        let width = width as usize;
+        frame.draw_styled(0, 0, &format!("{:1$}", self.status(), width), self.style)?;
+
+        let timeline = self.timeline();
+        let help = " Space pause  Left/Right step  PgUp/PgDn seek  Home/End  q quit";
+        let top = height.saturating_sub(timeline.len() as u16 + 1);
+        for (row, line) in timeline.iter().enumerate() {
+            let line = format!("{:1$}", line, width);
+            frame.draw_styled(0, top + row as u16, &line, Style::new())?;
+        }
+        frame.draw_styled(
+            0,
+            height.saturating_sub(1),
+            &format!("{:1$}", help, width),
+            self.style,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::TestRenderer;
+    use crossterm::event::{KeyEvent, KeyModifiers};
+
+    /// Counts the keys it gets and saves the count.
+    struct Counter(u32);
+
+    impl Node for Counter {
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+            Ok(())
+        }
+
+        fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+            self.0 += 1;
+            Ok(true)
+        }
+
+        fn on_save(&self, save: &mut SaveData) -> Result<(), EngineError> {
+            save.insert("counter", self.0.to_string());
+            Ok(())
+        }
+
+        fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+            frame.draw_styled(0, 1, &self.0.to_string(), Style::new())?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_inspector_steps_and_seeks() {
+        let replay = Replay::parse("0 a\n2 b\n2 c\n5 end").unwrap();
+        let mut inspector = ReplayInspector::new(replay).with_game(|| Counter(0));
+        let mut ctx = Context::new(GameConfig::new());
+        let press = |inspector: &mut ReplayInspector, ctx: &mut Context, code| {
+            let ev = Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+            inspector.on_event(ctx, &ev).unwrap();
+        };
+
+        inspector.update(&mut ctx, 0.1).unwrap();
+        assert_eq!(inspector.tick(), 0);
+        let start = inspector.state_hash();
+        press(&mut inspector, &mut ctx, KeyCode::Char(' '));
+        for _ in 0..3 {
+            inspector.update(&mut ctx, 0.1).unwrap();
+        }
+        assert_eq!(inspector.tick(), 3);
+
+        let mut renderer = TestRenderer::new(60, 8);
+        renderer.render(&inspector).unwrap();
+        let expected = format!(
+            " tick 3/5  playing  state {:016x}",
+            inspector.state_hash().unwrap()
+        );
+        assert_eq!(renderer.row(0), expected);
+        assert_eq!(renderer.row(1), "3");
+        assert_eq!(renderer.row(5), "       0  a");
+        assert_eq!(renderer.row(6), ">      2  b c");
+
+        // Stepping back plays the game again from the start
+        press(&mut inspector, &mut ctx, KeyCode::Left);
+        press(&mut inspector, &mut ctx, KeyCode::Left);
+        assert!(!inspector.is_playing());
+        assert_eq!(inspector.tick(), 1);
+        press(&mut inspector, &mut ctx, KeyCode::Home);
+        assert_eq!(inspector.state_hash(), start);
+        press(&mut inspector, &mut ctx, KeyCode::End);
+        assert_eq!(inspector.tick(), 5);
+        assert_ne!(inspector.state_hash(), start);
+
+        press(&mut inspector, &mut ctx, KeyCode::Char('q'));
+        assert_eq!(ctx.exit_requested(), Some(0));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::autosave::SaveData;
+use crate::camera::Bounds;
+use crate::config::ErrorPolicy;
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::{Frame, Recording};
+use crate::nodes::Node;
+use crossterm::event::Event;
+use std::cell::{Cell, RefCell};
+
+/// Keeps what a node drew and draws it again while the node is clean,
+/// instead of rendering it every frame.
+///
+/// Wrap the parts of the tree that rarely change, such as a static HUD or
+/// an idle menu, and implement [`Node::is_dirty`] and [`Node::mark_clean`]
+/// for their nodes. A clean frame then only copies the kept cells, hit
+/// regions and tooltips to the renderer; the node's `render` is not called.
+/// The node is drawn again whenever it is dirty or the frame it is drawn
+/// in moves or changes size.
+pub struct Retained<N> {
+    node: N,
+    recording: RefCell<Recording>,
+    /// Set when the node was rendered, until it is marked clean
+    rendered: Cell<bool>,
+}
+
+impl<N: Node> Retained<N> {
+    pub fn new(node: N) -> Self {
+        Self {
+            node,
+            recording: RefCell::new(Recording::new()),
+            rendered: Cell::new(false),
+        }
+    }
+
+    pub fn node(&self) -> &N {
+        &self.node
+    }
+
+    pub fn node_mut(&mut self) -> &mut N {
+        self.clean();
+        &mut self.node
+    }
+
+    /// Forgets the kept drawing, so the node is rendered on the next frame.
+    pub fn invalidate(&mut self) {
+        self.recording.get_mut().clear();
+    }
+
+    /// Marks the node clean if it was drawn since it was last changed.
+    ///
+    /// Called before anything that may change the node, so a change made
+    /// right after a render is not forgotten.
+    fn clean(&mut self) {
+        if self.rendered.replace(false) {
+            self.node.mark_clean();
+        }
+    }
+}
+
+impl<N: Node> Node for Retained<N> {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        self.clean();
+        self.node.update(ctx, dt)
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        self.clean();
+        self.node.on_event(ctx, ev)
+    }
+
+    fn update_interval(&self) -> u32 {
+        self.node.update_interval()
+    }
+
+    fn world_bounds(&self) -> Option<Bounds> {
+        self.node.world_bounds()
+    }
+
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        self.clean();
+        self.node.on_engine_event(ctx, ev);
+    }
+
+    fn on_pause(&mut self, ctx: &mut Context) {
+        self.clean();
+        self.node.on_pause(ctx);
+    }
+
+    fn on_resume(&mut self, ctx: &mut Context) {
+        self.clean();
+        self.node.on_resume(ctx);
+    }
+
+    fn on_resize(&mut self, ctx: &mut Context, width: u16, height: u16) {
+        self.clean();
+        self.node.on_resize(ctx, width, height);
+    }
+
+    fn on_save(&self, save: &mut SaveData) -> Result<(), EngineError> {
+        self.node.on_save(save)
+    }
+
+    fn on_error(&mut self, ctx: &mut Context, err: &EngineError) -> Option<ErrorPolicy> {
+        self.clean();
+        self.node.on_error(ctx, err)
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.node.is_dirty()
+    }
+
+    fn mark_clean(&mut self) {
+        self.clean();
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        let mut recording = self.recording.borrow_mut();
+        if !self.node.is_dirty() && frame.replay(&recording)? {
+            return Ok(());
+        }
+        frame.record(&mut recording, |frame| self.node.render(frame))?;
+        self.rendered.set(true);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::geometry::Rect;
+    use crate::hit::{HitId, HitRegions};
+    use crate::renderer::TestRenderer;
+
+    /// A score display that counts how often it was drawn.
+    struct Score {
+        score: u32,
+        dirty: bool,
+        renders: Cell<u32>,
+        region: HitId,
+    }
+
+    impl Node for Score {
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+            Ok(())
+        }
+
+        fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+            self.score += 10;
+            self.dirty = true;
+            Ok(false)
+        }
+
+        fn is_dirty(&self) -> bool {
+            self.dirty
+        }
+
+        fn mark_clean(&mut self) {
+            self.dirty = false;
+        }
+
+        fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+            self.renders.set(self.renders.get() + 1);
+            let text = format!("Score {}", self.score);
+            frame.draw_styled(0, 0, &text, Default::default())?;
+            frame.hit_region(self.region, Rect::new(0, 0, 5, 1));
+            Ok(())
+        }
+    }
+
+    fn draw(node: &Retained<Score>, area: Rect) -> (TestRenderer, HitRegions) {
+        let mut renderer = TestRenderer::new(10, 2);
+        let mut hits = HitRegions::new();
+        let mut frame = Frame::new(&mut renderer, area, 1.0).with_hit_regions(&mut hits);
+        node.render(&mut frame).unwrap();
+        (renderer, hits)
+    }
+
+    #[test]
+    fn test_clean_node_is_replayed() {
+        let mut ctx = Context::new(GameConfig::new());
+        let region = HitId::new();
+        let mut score = Retained::new(Score {
+            score: 0,
+            dirty: true,
+            renders: Cell::new(0),
+            region,
+        });
+        let area = Rect::new(1, 1, 9, 1);
+        assert_eq!(draw(&score, area).0.row(1), " Score 0");
+        score.update(&mut ctx, 0.1).unwrap();
+        let (renderer, hits) = draw(&score, area);
+        assert_eq!(renderer.row(1), " Score 0");
+        assert_eq!(hits.hit_test(2, 1).map(|(id, ..)| id), Some(region));
+        assert_eq!(score.node().renders.get(), 1);
+
+        // Changes, and a different area, draw the node again
+        score.on_event(&mut ctx, &Event::FocusGained).unwrap();
+        assert_eq!(draw(&score, area).0.row(1), " Score 10");
+        score.update(&mut ctx, 0.1).unwrap();
+        assert_eq!(draw(&score, Rect::new(0, 1, 10, 1)).0.row(1), "Score 10");
+        assert_eq!(score.node().renders.get(), 3);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::context::Context;
+use crate::errors::EngineError;
+use crate::nodes::Node;
+
+/// Tracks when a child with an [`update_interval`](Node::update_interval)
+/// above one is next updated, for the containers that honor it.
+///
+/// Keep one next to each child of your own containers, or mark a
+/// `Vec<UpdateSchedule>` field `#[node(schedules)]` in a derived node.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UpdateSchedule {
+    /// Ticks left before the next update
+    due: u32,
+    /// Time passed since the last update
+    elapsed: f32,
+}
+
+impl UpdateSchedule {
+    /// Advances the schedule by one tick and updates `node` if it is due,
+    /// with the time passed since its last update.
+    ///
+    /// A node is updated on its first tick, then every `update_interval`
+    /// ticks; a shorter interval takes effect at once.
+    pub fn tick(
+        &mut self,
+        node: &mut dyn Node,
+        ctx: &mut Context,
+        dt: f32,
+    ) -> Result<(), EngineError> {
+        let interval = node.update_interval().max(1);
+        self.elapsed += dt;
+        self.due = self.due.min(interval - 1);
+        if self.due > 0 {
+            self.due -= 1;
+            return Ok(());
+        }
+        self.due = interval - 1;
+        node.update(ctx, std::mem::take(&mut self.elapsed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::frame::Frame;
+    use crossterm::event::Event;
+
+    /// Updates every `interval` ticks and records the time it was given.
+    struct Slow {
+        interval: u32,
+        updates: Vec<f32>,
+    }
+
+    impl Node for Slow {
+        fn update(&mut self, _ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+            self.updates.push(dt);
+            Ok(())
+        }
+
+        fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+            Ok(false)
+        }
+
+        fn update_interval(&self) -> u32 {
+            self.interval
+        }
+
+        fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_updates_every_interval_with_the_elapsed_time() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut node = Slow {
+            interval: 3,
+            updates: Vec::new(),
+        };
+        let mut schedule = UpdateSchedule::default();
+        for _ in 0..6 {
+            schedule.tick(&mut node, &mut ctx, 0.5).unwrap();
+        }
+        assert_eq!(node.updates, [0.5, 1.5]);
+
+        // Switching to every tick applies on the next one
+        node.interval = 1;
+        schedule.tick(&mut node, &mut ctx, 0.5).unwrap();
+        assert_eq!(node.updates, [0.5, 1.5, 1.5]);
+    }
+}
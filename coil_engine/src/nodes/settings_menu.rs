@@ -0,0 +1,370 @@
+use crate::actions::{ActionMap, KeyBinding};
+use crate::config::{Config, GameConfig};
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::nodes::Node;
+use crate::notify::Level;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const FPS_STEP: u32 = 5;
+const MAX_FPS: u32 = 240;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Row {
+    TargetFps,
+    Vsync,
+    MouseCapture,
+    TouchControls,
+    HighContrast,
+    ReducedMotion,
+    Binding(String),
+    Save,
+    Close,
+}
+
+/// A ready-made settings screen for the engine configuration and key bindings.
+///
+/// Changes apply live through the [`Context`]; *Save* writes them to the
+/// configured files. While open the menu consumes all key events, so add it
+/// as a child that sees input before the game (e.g. on [`Layers::hud`](crate::nodes::Layers)).
+///
+/// The focused row is announced through [`Context::announce`] whenever it
+/// or its value changes, so the menu can be used with a screen reader.
+///
+/// Keys: Up/Down select, Left/Right adjust, Enter toggles, rebinds the
+/// selected action (the next key pressed becomes its binding) or activates
+/// *Save*/*Close*, and Esc cancels a rebind or closes the menu.
+///
+/// The menu is drawn in the [`Theme`](crate::style::Theme) of the game.
+pub struct SettingsMenu {
+    open: bool,
+    selected: usize,
+    config: GameConfig,
+    actions: ActionMap,
+    config_path: Option<PathBuf>,
+    actions_path: Option<PathBuf>,
+    rebinding: Option<String>,
+}
+
+impl SettingsMenu {
+    pub fn new(actions: ActionMap) -> Self {
+        Self {
+            open: false,
+            selected: 0,
+            config: GameConfig::new(),
+            actions,
+            config_path: None,
+            actions_path: None,
+            rebinding: None,
+        }
+    }
+
+    /// Sets the file *Save* writes the engine configuration to.
+    pub fn with_config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
+    /// Sets the file *Save* writes the key bindings to.
+    pub fn with_actions_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.actions_path = Some(path.into());
+        self
+    }
+
+    /// Opens the menu, showing the configuration currently in effect.
+    pub fn open(&mut self, ctx: &mut Context) {
+        self.config = ctx.config().clone();
+        self.selected = 0;
+        self.rebinding = None;
+        self.open = true;
+        ctx.announce(format!("Settings. {}", self.focused()));
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        self.rebinding = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Returns the key bindings, including any rebinds made in the menu.
+    pub fn actions(&self) -> &ActionMap {
+        &self.actions
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = vec![
+            Row::TargetFps,
+            Row::Vsync,
+            Row::MouseCapture,
+            Row::TouchControls,
+            Row::HighContrast,
+            Row::ReducedMotion,
+        ];
+        rows.extend(self.actions.actions().map(|a| Row::Binding(a.to_string())));
+        rows.extend([Row::Save, Row::Close]);
+        rows
+    }
+
+    /// Returns the label of the selected row.
+    fn focused(&self) -> String {
+        let rows = self.rows();
+        self.label(&rows[self.selected.min(rows.len() - 1)])
+    }
+
+    fn label(&self, row: &Row) -> String {
+        let on_off = |value: bool| if value { "on" } else { "off" };
+        match row {
+            Row::TargetFps => format!("Target FPS      < {} >", self.config.target_fps),
+            Row::Vsync => format!("Vsync           < {} >", on_off(self.config.vsync)),
+            Row::MouseCapture => {
+                format!("Mouse capture   < {} >", on_off(self.config.mouse_capture))
+            }
+            Row::TouchControls => {
+                format!("Touch controls  < {} >", on_off(self.config.touch_controls))
+            }
+            Row::HighContrast => {
+                format!("High contrast   < {} >", on_off(self.config.high_contrast))
+            }
+            Row::ReducedMotion => {
+                format!("Reduced motion  < {} >", on_off(self.config.reduced_motion))
+            }
+            Row::Binding(action) if self.rebinding.as_ref() == Some(action) => {
+                format!("{:<15} press a key...", action)
+            }
+            Row::Binding(action) => {
+                let keys: Vec<String> = self
+                    .actions
+                    .bindings(action)
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect();
+                format!("{:<15} {}", action, keys.join(", "))
+            }
+            Row::Save => "Save".to_string(),
+            Row::Close => "Close".to_string(),
+        }
+    }
+
+    /// Applies a change live and mirrors it in the displayed configuration.
+    fn apply(&mut self, ctx: &mut Context, change: Config) {
+        self.config = self.config.clone().add_config(change.clone());
+        ctx.set_config(change);
+    }
+
+    fn adjust(&mut self, ctx: &mut Context, row: &Row, increase: bool) {
+        match row {
+            Row::TargetFps => {
+                let fps = self.config.target_fps;
+                let fps = if increase {
+                    (fps + FPS_STEP).min(MAX_FPS)
+                } else {
+                    fps.saturating_sub(FPS_STEP).max(FPS_STEP)
+                };
+                self.apply(ctx, Config::TargetFps(fps));
+            }
+            Row::Vsync => self.apply(ctx, Config::Vsync(!self.config.vsync)),
+            Row::MouseCapture => self.apply(ctx, Config::MouseCapture(!self.config.mouse_capture)),
+            Row::TouchControls => {
+                self.apply(ctx, Config::TouchControls(!self.config.touch_controls))
+            }
+            Row::HighContrast => self.apply(ctx, Config::HighContrast(!self.config.high_contrast)),
+            Row::ReducedMotion => {
+                self.apply(ctx, Config::ReducedMotion(!self.config.reduced_motion))
+            }
+            _ => {}
+        }
+    }
+
+    fn save(&self) -> Result<(), EngineError> {
+        if let Some(path) = &self.config_path {
+            self.config.save(path)?;
+        }
+        if let Some(path) = &self.actions_path {
+            self.actions.save(path)?;
+        }
+        Ok(())
+    }
+
+    fn handle_key(&mut self, ctx: &mut Context, key: KeyEvent) {
+        if let Some(action) = self.rebinding.take() {
+            if key.code != KeyCode::Esc {
+                self.actions
+                    .rebind(&action, vec![KeyBinding::new(key.code, key.modifiers)]);
+            }
+            return;
+        }
+        let rows = self.rows();
+        let row = rows[self.selected.min(rows.len() - 1)].clone();
+        match key.code {
+            KeyCode::Up => self.selected = self.selected.checked_sub(1).unwrap_or(rows.len() - 1),
+            KeyCode::Down => self.selected = (self.selected + 1) % rows.len(),
+            KeyCode::Left => self.adjust(ctx, &row, false),
+            KeyCode::Right => self.adjust(ctx, &row, true),
+            KeyCode::Esc => self.close(),
+            KeyCode::Enter => match row {
+                Row::TargetFps => {}
+                Row::Vsync
+                | Row::MouseCapture
+                | Row::TouchControls
+                | Row::HighContrast
+                | Row::ReducedMotion => self.adjust(ctx, &row, true),
+                Row::Binding(action) => self.rebinding = Some(action),
+                Row::Save => match self.save() {
+                    Ok(()) => ctx.notify("Settings saved", Level::Success, Duration::from_secs(2)),
+                    Err(e) => ctx.notify(
+                        format!("Could not save settings: {}", e),
+                        Level::Error,
+                        Duration::from_secs(4),
+                    ),
+                },
+                Row::Close => self.close(),
+            },
+            _ => {}
+        }
+    }
+}
+
+impl Node for SettingsMenu {
+    fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        if !self.open {
+            return Ok(false);
+        }
+        if let Event::Key(key) = ev
+            && key.kind != KeyEventKind::Release
+        {
+            let before = (self.selected, self.focused());
+            self.handle_key(ctx, *key);
+            if !self.open {
+                ctx.announce("Settings closed");
+            } else if (self.selected, self.focused()) != before {
+                ctx.announce(self.focused());
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn on_engine_event(&mut self, _ctx: &mut Context, ev: &EngineEvent) {
+        if let EngineEvent::ConfigChanged(change) = ev {
+            self.config = self.config.clone().add_config(change.clone());
+        }
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        if !self.open {
+            return Ok(());
+        }
+        let rows = self.rows();
+        let labels: Vec<String> = rows.iter().map(|row| self.label(row)).collect();
+        let (width, height) = frame.size();
+        let inner = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0) as u16 + 2;
+        let box_width = inner.max(20).min(width);
+        let box_height = (rows.len() as u16 + 2).min(height);
+        let x = width.saturating_sub(box_width) / 2;
+        let y = height.saturating_sub(box_height) / 2;
+
+        let theme = self.config.theme;
+        let blank = " ".repeat(box_width as usize);
+        for row in 0..box_height {
+            frame.draw_styled(x, y + row, &blank, theme.menu)?;
+        }
+        frame.draw_styled(x + 1, y, "Settings", theme.menu)?;
+        for (index, label) in labels.iter().enumerate() {
+            let style = if index == self.selected {
+                theme.selected
+            } else {
+                theme.menu
+            };
+            frame.draw_styled(x + 1, y + 2 + index as u16, label, style)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn press(menu: &mut SettingsMenu, ctx: &mut Context, code: KeyCode) -> bool {
+        menu.on_event(ctx, &Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_settings_apply_live_and_rebind() {
+        let mut ctx = Context::new(GameConfig::new());
+        let actions = ActionMap::new().with_binding("jump", KeyCode::Char(' '));
+        let mut menu = SettingsMenu::new(actions);
+
+        assert!(!press(&mut menu, &mut ctx, KeyCode::Right));
+        menu.open(&mut ctx);
+        assert!(press(&mut menu, &mut ctx, KeyCode::Right));
+        ctx.apply_pending();
+        assert_eq!(ctx.config().target_fps, 65);
+
+        // down to the "jump" binding, then rebind it to 'w'
+        for _ in 0..6 {
+            press(&mut menu, &mut ctx, KeyCode::Down);
+        }
+        press(&mut menu, &mut ctx, KeyCode::Enter);
+        press(&mut menu, &mut ctx, KeyCode::Char('w'));
+        assert_eq!(menu.actions().bindings("jump"), [KeyCode::Char('w').into()]);
+
+        press(&mut menu, &mut ctx, KeyCode::Esc);
+        assert!(!menu.is_open());
+    }
+
+    #[test]
+    fn test_focus_changes_are_announced() {
+        let config = GameConfig::new().add_config(Config::SpeechOutput(Some("speech.txt".into())));
+        let mut ctx = Context::new(config);
+        let mut menu = SettingsMenu::new(ActionMap::new());
+
+        menu.open(&mut ctx);
+        press(&mut menu, &mut ctx, KeyCode::Down);
+        press(&mut menu, &mut ctx, KeyCode::Right);
+        press(&mut menu, &mut ctx, KeyCode::Esc);
+        assert_eq!(
+            ctx.take_announcements(),
+            [
+                "Settings. Target FPS      < 60 >",
+                "Vsync           < on >",
+                "Vsync           < off >",
+                "Settings closed",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_save_writes_both_files() {
+        let dir = std::env::temp_dir().join(format!("coil-settings-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut ctx = Context::new(GameConfig::new());
+        let mut menu = SettingsMenu::new(ActionMap::new().with_binding("fire", KeyCode::Enter))
+            .with_config_path(dir.join("game.conf"))
+            .with_actions_path(dir.join("keys.conf"));
+        menu.open(&mut ctx);
+        press(&mut menu, &mut ctx, KeyCode::Left);
+        // Save is the second to last row
+        press(&mut menu, &mut ctx, KeyCode::Up);
+        press(&mut menu, &mut ctx, KeyCode::Up);
+        press(&mut menu, &mut ctx, KeyCode::Enter);
+
+        let saved = GameConfig::new().merge_file(dir.join("game.conf")).unwrap();
+        assert_eq!(saved.target_fps, 55);
+        let keys = ActionMap::load(dir.join("keys.conf")).unwrap();
+        assert_eq!(keys.bindings("fire"), [KeyCode::Enter.into()]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
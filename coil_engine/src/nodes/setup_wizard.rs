@@ -0,0 +1,437 @@
+use crate::actions::ActionMap;
+use crate::color::{self, ColorDepth};
+use crate::config::{Config, GameConfig};
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::glyphs::GlyphSupport;
+use crate::nodes::Node;
+use crate::notify::Level;
+use crate::style::Style;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::Color;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Width of the color samples on the color check step
+const SAMPLE_WIDTH: u16 = 24;
+/// Color depths offered on the color check step, most colors first
+const DEPTHS: [(ColorDepth, &str); 3] = [
+    (ColorDepth::TrueColor, "Millions of colors"),
+    (ColorDepth::Ansi256, "256 colors"),
+    (ColorDepth::Ansi16, "16 colors"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+    Controls,
+    Colors,
+    Accessibility,
+}
+
+/// A first-launch setup flow: the player picks a key binding preset,
+/// checks which colors the terminal shows and turns on accessibility
+/// options, and the choices are saved for the next runs.
+///
+/// The wizard opens on its first update when the config file does not exist
+/// yet, i.e. on the first launch, and can be reopened with
+/// [`SetupWizard::open`]. The files default to `config.txt` and `keys.txt`
+/// in the [config directory](Context::paths) when the `app_name` setting is
+/// set; without files the wizard only opens when asked to and its choices
+/// last until the game exits. Games read the files back at startup with
+/// [`GameConfig::merge_file`] and [`ActionMap::load`].
+///
+/// Choices apply live through the [`Context`] like in the
+/// [`SettingsMenu`](crate::nodes::SettingsMenu), and the focused row is
+/// announced for screen readers. While open the wizard consumes all key
+/// events, so add it where it sees input before the game.
+///
+/// Keys: Up/Down select, Left/Right toggle options, Enter picks the
+/// selected row and moves on, and Esc goes back a step.
+pub struct SetupWizard {
+    open: bool,
+    /// Whether the first update, which decides whether to open, happened
+    started: bool,
+    step: Step,
+    selected: usize,
+    presets: Vec<(String, ActionMap)>,
+    preset: usize,
+    config: GameConfig,
+    config_path: Option<PathBuf>,
+    actions_path: Option<PathBuf>,
+    style: Style,
+    selected_style: Style,
+}
+
+impl SetupWizard {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            started: false,
+            step: Step::Controls,
+            selected: 0,
+            presets: Vec::new(),
+            preset: 0,
+            config: GameConfig::new(),
+            config_path: None,
+            actions_path: None,
+            style: crate::style!(white on dark_blue),
+            selected_style: crate::style!(black on white),
+        }
+    }
+
+    /// Offers the key bindings `actions` as a preset named `name`; the
+    /// first preset is the default. Without presets the controls step is
+    /// skipped.
+    pub fn with_preset(mut self, name: impl Into<String>, actions: ActionMap) -> Self {
+        self.presets.push((name.into(), actions));
+        self
+    }
+
+    /// Sets the file the engine configuration is saved to, whose absence
+    /// means this is the first launch.
+    pub fn with_config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
+    /// Sets the file the chosen key bindings are saved to.
+    pub fn with_actions_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.actions_path = Some(path.into());
+        self
+    }
+
+    /// Opens the wizard at its first step.
+    pub fn open(&mut self, ctx: &mut Context) {
+        self.config = ctx.config().clone();
+        self.open = true;
+        let first = if self.presets.is_empty() {
+            Step::Colors
+        } else {
+            Step::Controls
+        };
+        self.enter(ctx, first);
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Returns the key bindings of the chosen preset.
+    pub fn actions(&self) -> Option<&ActionMap> {
+        self.presets.get(self.preset).map(|(_, actions)| actions)
+    }
+
+    fn enter(&mut self, ctx: &mut Context, step: Step) {
+        self.step = step;
+        self.selected = match step {
+            Step::Controls => self.preset,
+            _ => 0,
+        };
+        if step == Step::Colors {
+            // Show the samples unreduced, so the player sees what the
+            // terminal really does with them
+            self.apply(ctx, Config::ColorDepth(ColorDepth::TrueColor));
+        }
+        ctx.announce(format!("{}. {}", self.title(), self.focused()));
+    }
+
+    fn title(&self) -> String {
+        let steps = if self.presets.is_empty() { 2 } else { 3 };
+        let (number, name) = match self.step {
+            Step::Controls => (1, "Controls"),
+            Step::Colors => (steps - 1, "Which samples look smooth?"),
+            Step::Accessibility => (steps, "Accessibility"),
+        };
+        format!("Setup {}/{}: {}", number, steps, name)
+    }
+
+    fn rows(&self) -> Vec<String> {
+        let on_off = |value: bool| if value { "on" } else { "off" };
+        match self.step {
+            Step::Controls => self.presets.iter().map(|(name, _)| name.clone()).collect(),
+            Step::Colors => DEPTHS.iter().map(|(_, label)| label.to_string()).collect(),
+            Step::Accessibility => vec![
+                format!(
+                    "High contrast      < {} >",
+                    on_off(self.config.high_contrast)
+                ),
+                format!(
+                    "Reduced motion     < {} >",
+                    on_off(self.config.reduced_motion)
+                ),
+                format!(
+                    "Simple characters  < {} >",
+                    on_off(self.config.glyphs == GlyphSupport::Ascii)
+                ),
+                "Finish".to_string(),
+            ],
+        }
+    }
+
+    fn focused(&self) -> String {
+        self.rows().swap_remove(self.selected)
+    }
+
+    /// Applies a change live and mirrors it in the configuration to save.
+    fn apply(&mut self, ctx: &mut Context, change: Config) {
+        self.config = self.config.clone().add_config(change.clone());
+        ctx.set_config(change);
+    }
+
+    fn toggle(&mut self, ctx: &mut Context) {
+        let change = match self.selected {
+            0 => Config::HighContrast(!self.config.high_contrast),
+            1 => Config::ReducedMotion(!self.config.reduced_motion),
+            2 => Config::Glyphs(match self.config.glyphs {
+                GlyphSupport::Ascii => GlyphSupport::Auto,
+                _ => GlyphSupport::Ascii,
+            }),
+            _ => return,
+        };
+        self.apply(ctx, change);
+    }
+
+    /// Returns the files to save to, falling back to the config directory.
+    fn paths(&self, ctx: &Context) -> (Option<PathBuf>, Option<PathBuf>) {
+        let paths = ctx.paths();
+        let config = self
+            .config_path
+            .clone()
+            .or_else(|| paths.as_ref().map(|paths| paths.config_file("config.txt")));
+        let actions = self
+            .actions_path
+            .clone()
+            .or_else(|| paths.map(|paths| paths.config_file("keys.txt")));
+        (config, actions)
+    }
+
+    fn save(&self, ctx: &Context) -> Result<(), EngineError> {
+        let (config_path, actions_path) = self.paths(ctx);
+        if let Some(path) = config_path {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            self.config.save(path)?;
+        }
+        if let (Some(path), Some(actions)) = (actions_path, self.actions()) {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            actions.save(path)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, ctx: &mut Context) {
+        self.open = false;
+        match self.save(ctx) {
+            Ok(()) => ctx.notify("Setup complete", Level::Success, Duration::from_secs(2)),
+            Err(e) => ctx.notify(
+                format!("Could not save settings: {}", e),
+                Level::Error,
+                Duration::from_secs(4),
+            ),
+        }
+    }
+
+    fn handle_key(&mut self, ctx: &mut Context, key: KeyEvent) {
+        let count = self.rows().len();
+        match key.code {
+            KeyCode::Up => self.selected = self.selected.checked_sub(1).unwrap_or(count - 1),
+            KeyCode::Down => self.selected = (self.selected + 1) % count,
+            KeyCode::Left | KeyCode::Right if self.step == Step::Accessibility => self.toggle(ctx),
+            KeyCode::Esc => match self.step {
+                Step::Accessibility => self.enter(ctx, Step::Colors),
+                Step::Colors if !self.presets.is_empty() => self.enter(ctx, Step::Controls),
+                _ => {}
+            },
+            KeyCode::Enter => match self.step {
+                Step::Controls => {
+                    self.preset = self.selected;
+                    self.enter(ctx, Step::Colors);
+                }
+                Step::Colors => {
+                    self.apply(ctx, Config::ColorDepth(DEPTHS[self.selected].0));
+                    self.enter(ctx, Step::Accessibility);
+                }
+                Step::Accessibility if self.selected + 1 == count => self.finish(ctx),
+                Step::Accessibility => self.toggle(ctx),
+            },
+            _ => {}
+        }
+    }
+
+    /// Draws a hue gradient reduced to `depth`.
+    fn render_sample(
+        frame: &mut Frame<'_>,
+        x: u16,
+        y: u16,
+        depth: ColorDepth,
+    ) -> Result<(), EngineError> {
+        for column in 0..SAMPLE_WIDTH {
+            let hue = column as f32 * 360.0 / SAMPLE_WIDTH as f32;
+            let color = Color::from(color::Rgb::from_hsl(hue, 0.8, 0.5));
+            let color = match depth {
+                ColorDepth::Ansi256 => color::to_ansi256(color),
+                ColorDepth::Ansi16 => color::to_ansi16(color),
+                _ => color,
+            };
+            frame.draw_styled(x + column, y, " ", Style::new().bg(color))?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for SetupWizard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Node for SetupWizard {
+    fn update(&mut self, ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+        if !self.started {
+            self.started = true;
+            if let (Some(path), _) = self.paths(ctx)
+                && !path.exists()
+            {
+                self.open(ctx);
+            }
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        if !self.open {
+            return Ok(false);
+        }
+        if let Event::Key(key) = ev
+            && key.kind != KeyEventKind::Release
+        {
+            let before = (self.step, self.selected, self.focused());
+            self.handle_key(ctx, *key);
+            if !self.open {
+                ctx.announce("Setup complete");
+            } else if (self.step, self.selected, self.focused()) != before {
+                ctx.announce(self.focused());
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn on_engine_event(&mut self, _ctx: &mut Context, ev: &EngineEvent) {
+        if let EngineEvent::ConfigChanged(change) = ev {
+            self.config = self.config.clone().add_config(change.clone());
+        }
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        if !self.open {
+            return Ok(());
+        }
+        let title = self.title();
+        let rows = self.rows();
+        let samples = if self.step == Step::Colors {
+            SAMPLE_WIDTH + 1
+        } else {
+            0
+        };
+        let (width, height) = frame.size();
+        let widest = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0) as u16;
+        let inner = (widest + samples).max(title.chars().count() as u16) + 2;
+        let box_width = inner.max(20).min(width);
+        let box_height = (rows.len() as u16 + 2).min(height);
+        let x = width.saturating_sub(box_width) / 2;
+        let y = height.saturating_sub(box_height) / 2;
+
+        let blank = " ".repeat(box_width as usize);
+        for row in 0..box_height {
+            frame.draw_styled(x, y + row, &blank, self.style)?;
+        }
+        frame.draw_styled(x + 1, y, &title, self.style)?;
+        for (index, label) in rows.iter().enumerate() {
+            let style = if index == self.selected {
+                self.selected_style
+            } else {
+                self.style
+            };
+            let row_y = y + 2 + index as u16;
+            if self.step == Step::Colors {
+                Self::render_sample(frame, x + 1, row_y, DEPTHS[index].0)?;
+            }
+            frame.draw_styled(x + 1 + samples, row_y, label, style)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn press(wizard: &mut SetupWizard, ctx: &mut Context, code: KeyCode) {
+        wizard
+            .on_event(ctx, &Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+            .unwrap();
+        ctx.apply_pending();
+    }
+
+    #[test]
+    fn test_first_run_choices_are_saved() {
+        let dir = std::env::temp_dir().join(format!("coil-setup-{}", std::process::id()));
+        let (config_path, keys_path) = (dir.join("config.txt"), dir.join("keys.txt"));
+        let mut wizard = SetupWizard::new()
+            .with_preset("Arrows", ActionMap::new().with_binding("up", KeyCode::Up))
+            .with_preset(
+                "WASD",
+                ActionMap::new().with_binding("up", KeyCode::Char('w')),
+            )
+            .with_config_path(&config_path)
+            .with_actions_path(&keys_path);
+        let mut ctx = Context::new(GameConfig::new());
+        wizard.update(&mut ctx, 0.1).unwrap();
+        assert!(wizard.is_open());
+
+        // WASD, then 256 colors, then reduced motion and finish
+        for code in [
+            KeyCode::Down,
+            KeyCode::Enter,
+            KeyCode::Down,
+            KeyCode::Enter,
+            KeyCode::Down,
+            KeyCode::Right,
+            KeyCode::Up,
+            KeyCode::Esc,
+            KeyCode::Down,
+            KeyCode::Enter,
+            KeyCode::Up,
+            KeyCode::Enter,
+        ] {
+            press(&mut wizard, &mut ctx, code);
+        }
+        assert!(!wizard.is_open());
+        assert!(ctx.config().reduced_motion);
+        assert_eq!(ctx.config().color_depth, ColorDepth::Ansi256);
+
+        let saved = GameConfig::new().merge_file(&config_path).unwrap();
+        assert!(saved.reduced_motion);
+        assert_eq!(saved.color_depth, ColorDepth::Ansi256);
+        assert_eq!(
+            ActionMap::load(&keys_path).unwrap().bindings("up"),
+            [KeyCode::Char('w').into()]
+        );
+
+        // Not the first run any more
+        let mut again = SetupWizard::new().with_config_path(&config_path);
+        again.update(&mut ctx, 0.1).unwrap();
+        assert!(!again.is_open());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
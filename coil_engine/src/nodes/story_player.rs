@@ -0,0 +1,212 @@
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::fiction::{Page, Story, StoryState};
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::hit::HitId;
+use crate::nodes::Node;
+use crate::style::Style;
+use crate::text_effects::Typewriter;
+use crossterm::event::{Event, KeyCode, KeyEventKind, MouseEventKind};
+
+/// Plays an interactive [`Story`] in its frame.
+///
+/// Each passage is typed out with a [`Typewriter`], then its choices are
+/// listed below the text:
+///
+/// * Any key or click while typing: show the whole passage
+/// * Up/Down and Enter, `1`-`9` or a click: follow a choice
+pub struct StoryPlayer {
+    story: Story,
+    state: StoryState,
+    page: Page,
+    typewriter: Typewriter,
+    selected: usize,
+    /// Choice clicked since the last update
+    clicked: Option<usize>,
+    choices_id: HitId,
+    style: Style,
+    choice_style: Style,
+    selected_style: Style,
+}
+
+impl StoryPlayer {
+    /// Starts playing `story` from its start passage.
+    pub fn new(story: Story) -> Result<Self, EngineError> {
+        let mut state = StoryState::default();
+        let page = story.begin(&mut state)?;
+        Ok(Self {
+            typewriter: Typewriter::new(page.text.clone(), 40.0),
+            story,
+            state,
+            page,
+            selected: 0,
+            clicked: None,
+            choices_id: HitId::new(),
+            style: Style::new(),
+            choice_style: crate::style!(cyan),
+            selected_style: crate::style!(black on cyan),
+        })
+    }
+
+    /// Sets how many characters per second passages are typed at.
+    pub fn with_speed(mut self, chars_per_second: f32) -> Self {
+        self.typewriter = Typewriter::new(self.page.text.clone(), chars_per_second);
+        self
+    }
+
+    pub fn story(&self) -> &Story {
+        &self.story
+    }
+
+    /// Returns the variables and visited passages of the playthrough.
+    pub fn state(&self) -> &StoryState {
+        &self.state
+    }
+
+    pub fn page(&self) -> &Page {
+        &self.page
+    }
+
+    /// Returns whether the current passage offers no more choices.
+    pub fn is_ended(&self) -> bool {
+        self.page.choices.is_empty()
+    }
+
+    /// Follows the choice at `index` of the current passage.
+    pub fn choose(&mut self, index: usize) -> Result<(), EngineError> {
+        let page = self.story.choose(&mut self.state, &self.page, index)?;
+        self.show(page);
+        Ok(())
+    }
+
+    /// Starts the story over with fresh variables.
+    pub fn restart(&mut self) -> Result<(), EngineError> {
+        let page = self.story.begin(&mut self.state)?;
+        self.show(page);
+        Ok(())
+    }
+
+    fn show(&mut self, page: Page) {
+        self.typewriter.set_text(page.text.clone());
+        self.page = page;
+        self.selected = 0;
+    }
+}
+
+impl Node for StoryPlayer {
+    fn update(&mut self, _ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        if let Some(index) = self.clicked.take() {
+            self.choose(index)?;
+        }
+        self.typewriter.update(dt);
+        Ok(())
+    }
+
+    fn on_event(&mut self, _ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        if self.typewriter.on_event(ev) {
+            return Ok(true);
+        }
+        let Event::Key(key) = ev else {
+            return Ok(false);
+        };
+        let count = self.page.choices.len();
+        if key.kind == KeyEventKind::Release || count == 0 {
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::Up => self.selected = (self.selected + count - 1) % count,
+            KeyCode::Down => self.selected = (self.selected + 1) % count,
+            KeyCode::Enter => self.choose(self.selected)?,
+            KeyCode::Char(c @ '1'..='9') if (c as usize - '1' as usize) < count => {
+                self.choose(c as usize - '1' as usize)?
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn on_engine_event(&mut self, _ctx: &mut Context, ev: &EngineEvent) {
+        if let EngineEvent::Hit(hit) = ev
+            && hit.id == self.choices_id
+            && matches!(hit.event.kind, MouseEventKind::Down(_))
+            && self.typewriter.is_done()
+        {
+            // Choices are listed one per row from the top of their region
+            self.clicked = Some(hit.y as usize);
+        }
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        let text = self.typewriter.draw(frame, 0, 0, self.style)?;
+        if !self.typewriter.is_done() {
+            return Ok(());
+        }
+        let top = text.height + 1;
+        let mut width = 0;
+        for (index, choice) in self.page.choices.iter().enumerate() {
+            let style = if index == self.selected {
+                self.selected_style
+            } else {
+                self.choice_style
+            };
+            let label = format!("{}. {}", index + 1, choice.text);
+            let drawn = frame.draw_styled(0, top + index as u16, &label, style)?;
+            width = width.max(drawn.width);
+        }
+        frame.hit_region(
+            self.choices_id,
+            Rect::new(0, top, width, self.page.choices.len() as u16),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::hit::Hit;
+    use crossterm::event::{KeyEvent, KeyModifiers, MouseButton, MouseEvent};
+
+    #[test]
+    fn test_player_follows_keys_and_clicks() {
+        let story = Story::parse(
+            ":: Start\nA fork.\n[[Left]]\n[[Right]]\n:: Left\nA wall.\n[[Start]]\n:: Right\nThe end.\n",
+        )
+        .unwrap();
+        let mut ctx = Context::new(GameConfig::new());
+        let mut player = StoryPlayer::new(story).unwrap();
+        let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+
+        // The first key press only finishes typing
+        assert!(player.on_event(&mut ctx, &key(KeyCode::Down)).unwrap());
+        assert!(player.on_event(&mut ctx, &key(KeyCode::Down)).unwrap());
+        player.on_event(&mut ctx, &key(KeyCode::Enter)).unwrap();
+        assert_eq!(player.page().passage, "Right");
+        assert!(player.is_ended());
+
+        player.restart().unwrap();
+        player.on_event(&mut ctx, &key(KeyCode::Enter)).unwrap();
+        let event = MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 0,
+            row: 2,
+            modifiers: KeyModifiers::NONE,
+        };
+        let id = player.choices_id;
+        player.on_engine_event(
+            &mut ctx,
+            &EngineEvent::Hit(Hit {
+                id,
+                x: 0,
+                y: 0,
+                event,
+            }),
+        );
+        player.update(&mut ctx, 0.0).unwrap();
+        assert_eq!(player.page().passage, "Left");
+        // Restarting forgot the earlier playthrough
+        assert!(!player.state().visited.contains("Right"));
+    }
+}
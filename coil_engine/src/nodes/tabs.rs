@@ -0,0 +1,363 @@
+use crate::actions::KeyBinding;
+use crate::autosave::SaveData;
+use crate::camera::Bounds;
+use crate::config::ErrorPolicy;
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::hit::HitId;
+use crate::nodes::{Node, UpdateSchedule};
+use crate::style::Style;
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers, MouseEventKind};
+
+struct Page {
+    title: String,
+    node: Box<dyn Node>,
+    schedule: UpdateSchedule,
+    /// Region of the page's header
+    id: HitId,
+}
+
+/// A container showing one of several pages, with a row of headers to
+/// switch between them.
+///
+/// Only the selected page is updated, at its
+/// [`update_interval`](Node::update_interval), sees input and is drawn, in
+/// the area below the headers. The other pages are kept as they are, so switching
+/// back finds them in the same state; they still receive engine events and
+/// take part in saves, pauses and resumes.
+///
+/// * Ctrl+Right / Ctrl+Left: next or previous page, wrapping around
+/// * Alt+1 to Alt+9: the page with that number
+/// * Mouse: click a header
+///
+/// The selected page sees key events after the page shortcuts.
+pub struct Tabs {
+    pages: Vec<Page>,
+    selected: usize,
+    /// Page selected when the tabs were last marked clean
+    clean_selected: Option<usize>,
+    next_key: KeyBinding,
+    previous_key: KeyBinding,
+    style: Style,
+    selected_style: Style,
+}
+
+impl Tabs {
+    pub fn new() -> Self {
+        Self {
+            pages: Vec::new(),
+            selected: 0,
+            clean_selected: None,
+            next_key: KeyBinding::new(KeyCode::Right, KeyModifiers::CONTROL),
+            previous_key: KeyBinding::new(KeyCode::Left, KeyModifiers::CONTROL),
+            style: crate::style!(white on dark_grey),
+            selected_style: crate::style!(black on white),
+        }
+    }
+
+    /// Adds a page with the header `title` and returns `self`, so you can chain.
+    pub fn with_page<N: Node + 'static>(mut self, title: impl Into<String>, page: N) -> Self {
+        self.pages.push(Page {
+            title: title.into(),
+            node: Box::new(page),
+            schedule: UpdateSchedule::default(),
+            id: HitId::new(),
+        });
+        self
+    }
+
+    /// Replaces the keys switching to the next and previous page.
+    pub fn with_keys(
+        mut self,
+        next: impl Into<KeyBinding>,
+        previous: impl Into<KeyBinding>,
+    ) -> Self {
+        self.next_key = next.into();
+        self.previous_key = previous.into();
+        self
+    }
+
+    /// Returns the index of the selected page.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Selects page `index`, announcing its title; out of range indices are ignored.
+    pub fn select(&mut self, ctx: &mut Context, index: usize) {
+        if index < self.pages.len() && index != self.selected {
+            self.selected = index;
+            ctx.announce(format!("{} tab", self.pages[index].title));
+        }
+    }
+
+    /// Returns the number of pages.
+    pub fn len(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+
+    pub fn page(&self, index: usize) -> Option<&dyn Node> {
+        self.pages.get(index).map(|page| page.node.as_ref())
+    }
+
+    pub fn page_mut(&mut self, index: usize) -> Option<&mut (dyn Node + 'static)> {
+        self.pages.get_mut(index).map(|page| page.node.as_mut())
+    }
+
+    /// Returns each header's title with its column and width on the header row.
+    fn headers(&self) -> impl Iterator<Item = (&Page, u16, u16)> {
+        self.pages.iter().scan(0u16, |x, page| {
+            let width = page.title.chars().count() as u16 + 2;
+            let column = *x;
+            *x = x.saturating_add(width + 1);
+            Some((page, column, width))
+        })
+    }
+}
+
+impl Default for Tabs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Node for Tabs {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        match self.pages.get_mut(self.selected) {
+            Some(page) => page.schedule.tick(page.node.as_mut(), ctx, dt),
+            None => Ok(()),
+        }
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        if self.pages.is_empty() {
+            return Ok(false);
+        }
+        if let Event::Key(key) = &ev {
+            let count = self.pages.len();
+            if self.next_key.matches(key) {
+                self.select(ctx, (self.selected + 1) % count);
+                return Ok(true);
+            }
+            if self.previous_key.matches(key) {
+                self.select(ctx, (self.selected + count - 1) % count);
+                return Ok(true);
+            }
+            if let KeyCode::Char(digit @ '1'..='9') = key.code
+                && key.modifiers.contains(KeyModifiers::ALT)
+                && key.kind != KeyEventKind::Release
+            {
+                self.select(ctx, digit as usize - '1' as usize);
+                return Ok(true);
+            }
+        }
+        self.pages[self.selected].node.on_event(ctx, ev)
+    }
+
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        if let EngineEvent::Hit(hit) = ev
+            && let MouseEventKind::Down(_) = hit.event.kind
+            && let Some(index) = self.pages.iter().position(|page| page.id == hit.id)
+        {
+            self.select(ctx, index);
+        }
+        for page in &mut self.pages {
+            page.node.on_engine_event(ctx, ev);
+        }
+    }
+
+    fn on_pause(&mut self, ctx: &mut Context) {
+        for page in &mut self.pages {
+            page.node.on_pause(ctx);
+        }
+    }
+
+    fn on_resume(&mut self, ctx: &mut Context) {
+        for page in &mut self.pages {
+            page.node.on_resume(ctx);
+        }
+    }
+
+    fn on_resize(&mut self, ctx: &mut Context, width: u16, height: u16) {
+        for page in &mut self.pages {
+            page.node.on_resize(ctx, width, height);
+        }
+    }
+
+    fn on_save(&self, save: &mut SaveData) -> Result<(), EngineError> {
+        for page in &self.pages {
+            page.node.on_save(save)?;
+        }
+        Ok(())
+    }
+
+    fn on_error(&mut self, ctx: &mut Context, err: &EngineError) -> Option<ErrorPolicy> {
+        let page = self.pages.get_mut(self.selected)?;
+        page.node.on_error(ctx, err)
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.clean_selected != Some(self.selected)
+            || self
+                .pages
+                .get(self.selected)
+                .is_some_and(|page| page.node.is_dirty())
+    }
+
+    fn mark_clean(&mut self) {
+        if let Some(page) = self.pages.get_mut(self.selected) {
+            page.node.mark_clean();
+        }
+        self.clean_selected = Some(self.selected);
+    }
+
+    fn world_bounds(&self) -> Option<Bounds> {
+        let width = self.headers().last().map_or(0, |(_, x, width)| x + width);
+        let headers = Bounds::new(0, 0, width, 1);
+        let page = match self.pages.get(self.selected) {
+            Some(page) => page.node.world_bounds().map(|b| Bounds { y: b.y + 1, ..b }),
+            None => Some(headers),
+        };
+        Bounds::enclosing([Some(headers), page])
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        let (width, height) = frame.size();
+        for (index, (page, x, header)) in self.headers().enumerate() {
+            let style = if index == self.selected {
+                self.selected_style
+            } else {
+                self.style
+            };
+            frame.hit_region(page.id, Rect::new(x, 0, header, 1));
+            frame.draw_styled(x, 0, &format!(" {} ", page.title), style)?;
+        }
+        match self.pages.get(self.selected) {
+            Some(page) => {
+                page.node
+                    .render(&mut frame.child(Rect::new(0, 1, width, height.saturating_sub(1))))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::hit::HitRegions;
+    use crate::renderer::TestRenderer;
+    use crossterm::event::{KeyEvent, MouseButton, MouseEvent};
+
+    /// Counts its updates and shows the count.
+    #[derive(Default)]
+    struct Counter(u32);
+
+    impl Node for Counter {
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+            self.0 += 1;
+            Ok(())
+        }
+
+        fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+            Ok(false)
+        }
+
+        fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+            frame.draw_styled(0, 0, &self.0.to_string(), Style::new())?;
+            Ok(())
+        }
+    }
+
+    fn render(tabs: &Tabs, regions: &mut HitRegions) -> TestRenderer {
+        let mut renderer = TestRenderer::new(16, 2);
+        regions.clear();
+        tabs.render(
+            &mut Frame::new(&mut renderer, Rect::new(0, 0, 16, 2), 0.0).with_hit_regions(regions),
+        )
+        .unwrap();
+        renderer
+    }
+
+    #[test]
+    fn test_switching_pages_keeps_their_state() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut tabs = Tabs::new()
+            .with_page("Video", Counter::default())
+            .with_page("Keys", Counter::default())
+            .with_page("Debug", Counter::default());
+        tabs.update(&mut ctx, 0.1).unwrap();
+        tabs.update(&mut ctx, 0.1).unwrap();
+
+        let key = |code, modifiers| Event::Key(KeyEvent::new(code, modifiers));
+        assert!(
+            tabs.on_event(&mut ctx, &key(KeyCode::Left, KeyModifiers::CONTROL))
+                .unwrap()
+        );
+        assert_eq!(tabs.selected(), 2);
+        tabs.on_event(&mut ctx, &key(KeyCode::Char('2'), KeyModifiers::ALT))
+            .unwrap();
+        assert_eq!(tabs.selected(), 1);
+        tabs.update(&mut ctx, 0.1).unwrap();
+
+        let mut regions = HitRegions::new();
+        let renderer = render(&tabs, &mut regions);
+        assert_eq!(renderer.row(0), " Video   Keys");
+        assert_eq!(renderer.row(1), "1");
+
+        // Clicking the first header goes back to a page updated twice
+        let click = MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 2,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        };
+        tabs.on_engine_event(&mut ctx, &EngineEvent::Hit(regions.hit(click).unwrap()));
+        assert_eq!(tabs.selected(), 0);
+        assert_eq!(render(&tabs, &mut regions).row(1), "2");
+    }
+
+    #[test]
+    fn test_switching_pages_makes_the_tabs_dirty() {
+        /// Draws the same thing every time into a 2x2 area.
+        struct Still;
+
+        impl Node for Still {
+            fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+                Ok(())
+            }
+
+            fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+                Ok(false)
+            }
+
+            fn is_dirty(&self) -> bool {
+                false
+            }
+
+            fn world_bounds(&self) -> Option<Bounds> {
+                Some(Bounds::new(0, 0, 2, 2))
+            }
+
+            fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+                Ok(())
+            }
+        }
+
+        let mut ctx = Context::new(GameConfig::new());
+        let mut tabs = Tabs::new().with_page("A", Still).with_page("B", Still);
+        assert!(tabs.is_dirty());
+        tabs.mark_clean();
+        assert!(!tabs.is_dirty());
+        tabs.select(&mut ctx, 1);
+        assert!(tabs.is_dirty());
+        // The headers and the page below them
+        assert_eq!(tabs.world_bounds(), Some(Bounds::new(0, 0, 7, 3)));
+    }
+}
@@ -0,0 +1,291 @@
+use crate::big_text;
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::hit::HitId;
+use crate::nodes::Node;
+use crate::scene::Scene;
+use crate::style::Style;
+use crossterm::event::{Event, KeyCode, KeyEventKind, MouseButton, MouseEventKind};
+
+type Action = Box<dyn FnMut(&mut Context)>;
+
+/// A ready-made title screen: the game's name in block letters over an
+/// optional animated background, a menu and a version string.
+///
+/// Menu entries push scenes onto the [`SceneStack`](crate::scene::SceneStack)
+/// or run any action; a new project can start from it:
+///
+/// ```no_run
+/// use coil_engine::nodes::{TitleScreen, Weather, WeatherKind};
+/// use coil_engine::scene::{Scene, SceneStack};
+/// use coil_engine::{Context, EngineError, Frame, Game, Node};
+/// # use coil_engine::crossterm::event::Event;
+///
+/// struct Level;
+/// # impl Node for Level {
+/// #     fn update(&mut self, _: &mut Context, _: f32) -> Result<(), EngineError> { Ok(()) }
+/// #     fn on_event(&mut self, _: &mut Context, _: &Event) -> Result<bool, EngineError> { Ok(false) }
+/// #     fn render(&self, _: &mut Frame<'_>) -> Result<(), EngineError> { Ok(()) }
+/// # }
+/// impl Scene for Level {}
+///
+/// let title = TitleScreen::new("Snake")
+///     .with_background(Weather::new(WeatherKind::Snow))
+///     .with_scene("Play", || Level)
+///     .with_quit("Quit")
+///     .with_version(env!("CARGO_PKG_VERSION"));
+/// Game::new(SceneStack::new(title)).start();
+/// ```
+///
+/// Up/Down select an entry and Enter picks it, as does clicking it; Esc
+/// exits the game. The selected entry is announced for screen readers. The
+/// background is updated and drawn with the title screen, so like the rest
+/// of it, it stands still while another scene covers the title.
+pub struct TitleScreen {
+    title: String,
+    items: Vec<(String, Action)>,
+    selected: usize,
+    background: Option<Box<dyn Node>>,
+    version: Option<String>,
+    menu_id: HitId,
+    title_style: Style,
+    style: Style,
+    selected_style: Style,
+    version_style: Style,
+}
+
+impl TitleScreen {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            items: Vec::new(),
+            selected: 0,
+            background: None,
+            version: None,
+            menu_id: HitId::new(),
+            title_style: crate::style!(yellow),
+            style: Style::new(),
+            selected_style: crate::style!(black on white),
+            version_style: crate::style!(dark_grey),
+        }
+    }
+
+    /// Adds an entry running `action` when picked.
+    pub fn with_item(
+        mut self,
+        label: impl Into<String>,
+        action: impl FnMut(&mut Context) + 'static,
+    ) -> Self {
+        self.items.push((label.into(), Box::new(action)));
+        self
+    }
+
+    /// Adds an entry pushing a new scene made by `create` when picked.
+    pub fn with_scene<S, F>(self, label: impl Into<String>, create: F) -> Self
+    where
+        S: Scene + 'static,
+        F: Fn() -> S + 'static,
+    {
+        self.with_item(label, move |ctx| ctx.push_scene(create()))
+    }
+
+    /// Adds an entry exiting the game when picked.
+    pub fn with_quit(self, label: impl Into<String>) -> Self {
+        self.with_item(label, |ctx| ctx.request_exit(0))
+    }
+
+    /// Draws `background`, e.g. [`Weather`](crate::nodes::Weather) or
+    /// particles, behind the title.
+    pub fn with_background(mut self, background: impl Node + 'static) -> Self {
+        self.background = Some(Box::new(background));
+        self
+    }
+
+    /// Shows `version` in the bottom-right corner.
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Replaces the styles of the title, the entries and the selected entry.
+    pub fn with_styles(mut self, title: Style, item: Style, selected: Style) -> Self {
+        self.title_style = title;
+        self.style = item;
+        self.selected_style = selected;
+        self
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    fn select(&mut self, ctx: &mut Context, index: usize) {
+        if let Some((label, _)) = self.items.get(index)
+            && index != self.selected
+        {
+            self.selected = index;
+            ctx.announce(label.clone());
+        }
+    }
+
+    fn pick(&mut self, ctx: &mut Context) {
+        if let Some((_, action)) = self.items.get_mut(self.selected) {
+            action(ctx);
+        }
+    }
+
+    fn menu_width(&self) -> u16 {
+        let longest = self.items.iter().map(|(label, _)| label.chars().count());
+        longest.max().unwrap_or(0) as u16 + 4
+    }
+
+    /// Returns the area of the menu in a frame of `size`.
+    fn menu_area(&self, (width, height): (u16, u16)) -> Rect {
+        let menu_width = self.menu_width();
+        let menu_height = self.items.len() as u16;
+        // Below the title, or centered when there is no room for it
+        let top = if height >= big_text::HEIGHT + 2 + menu_height {
+            let free = height - big_text::HEIGHT - 2 - menu_height;
+            free / 2 + big_text::HEIGHT + 2
+        } else {
+            height.saturating_sub(menu_height) / 2
+        };
+        Rect::new(
+            width.saturating_sub(menu_width) / 2,
+            top,
+            menu_width,
+            menu_height,
+        )
+    }
+}
+
+impl Node for TitleScreen {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        match &mut self.background {
+            Some(background) => background.update(ctx, dt),
+            None => Ok(()),
+        }
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        let Event::Key(key) = ev else {
+            return Ok(false);
+        };
+        if key.kind == KeyEventKind::Release {
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::Up => self.select(ctx, self.selected.saturating_sub(1)),
+            KeyCode::Down => self.select(ctx, self.selected + 1),
+            KeyCode::Enter => self.pick(ctx),
+            KeyCode::Esc => ctx.request_exit(0),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        if let EngineEvent::Hit(hit) = ev
+            && hit.id == self.menu_id
+            && let MouseEventKind::Down(MouseButton::Left) = hit.event.kind
+        {
+            self.select(ctx, hit.y as usize);
+            self.pick(ctx);
+        }
+        if let Some(background) = &mut self.background {
+            background.on_engine_event(ctx, ev);
+        }
+    }
+
+    fn on_resize(&mut self, ctx: &mut Context, width: u16, height: u16) {
+        if let Some(background) = &mut self.background {
+            background.on_resize(ctx, width, height);
+        }
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        if let Some(background) = &self.background {
+            background.render(frame)?;
+        }
+        let size = frame.size();
+        let menu = self.menu_area(size);
+        if menu.y >= big_text::HEIGHT + 2 && big_text::width(&self.title) <= size.0 {
+            let x = size.0.saturating_sub(big_text::width(&self.title)) / 2;
+            let y = menu.y - big_text::HEIGHT - 2;
+            big_text::draw(frame, x, y, &self.title, self.title_style)?;
+        } else {
+            let x = size.0.saturating_sub(self.title.chars().count() as u16) / 2;
+            frame.draw_styled(x, menu.y.saturating_sub(2), &self.title, self.title_style)?;
+        }
+
+        frame.hit_region(self.menu_id, menu);
+        for (index, (label, _)) in self.items.iter().enumerate() {
+            let style = if index == self.selected {
+                self.selected_style
+            } else {
+                self.style
+            };
+            let label = format!("{:^1$}", label, menu.width as usize);
+            frame.draw_styled(menu.x, menu.y + index as u16, &label, style)?;
+        }
+
+        if let Some(version) = &self.version {
+            let x = size.0.saturating_sub(version.chars().count() as u16 + 1);
+            frame.draw_styled(x, size.1.saturating_sub(1), version, self.version_style)?;
+        }
+        Ok(())
+    }
+}
+
+impl Scene for TitleScreen {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::hit::HitRegions;
+    use crate::renderer::TestRenderer;
+    use crate::scene::SceneStack;
+    use crossterm::event::{KeyEvent, KeyModifiers, MouseEvent};
+
+    #[test]
+    fn test_title_menu_pushes_scenes() {
+        let title = TitleScreen::new("Go")
+            .with_scene("Play", || TitleScreen::new("Level"))
+            .with_quit("Quit")
+            .with_version("1.0");
+        let mut stack = SceneStack::new(title);
+        let mut ctx = Context::new(GameConfig::new());
+        let mut regions = HitRegions::new();
+        let mut renderer = TestRenderer::new(20, 12);
+        stack
+            .render(
+                &mut Frame::new(&mut renderer, Rect::new(0, 0, 20, 12), 0.0)
+                    .with_hit_regions(&mut regions),
+            )
+            .unwrap();
+        let screen = renderer.rows();
+        assert_eq!(screen[1], "      ███ ███");
+        assert_eq!(screen[2], "      █   █ █");
+        assert_eq!(screen[8], "        Play");
+        assert_eq!(screen[9], "        Quit");
+        assert_eq!(screen[11], "                1.0");
+
+        let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+        stack.on_event(&mut ctx, &key(KeyCode::Enter)).unwrap();
+        assert_eq!(stack.len(), 2);
+        stack.pop(&mut ctx);
+
+        let click = MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 8,
+            row: 9,
+            modifiers: KeyModifiers::NONE,
+        };
+        let hit = regions.hit(click).unwrap();
+        stack.on_engine_event(&mut ctx, &EngineEvent::Hit(hit));
+        assert_eq!(ctx.exit_requested(), Some(0));
+    }
+}
@@ -0,0 +1,162 @@
+use crate::config::Config;
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::nodes::Node;
+use crate::notify::{Level, Notification};
+use crate::style::{Style, Theme};
+use crate::text::text_width;
+use crossterm::event::Event;
+
+/// Where [`Toasts`] draws notifications within its frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToastPlacement {
+    /// Stacked in the top-right corner, oldest at the top.
+    #[default]
+    TopRight,
+    /// The newest notification across the bottom row.
+    StatusLine,
+}
+
+/// Shows the notifications posted with [`Context::notify`].
+///
+/// Add it to the HUD (e.g. [`Layers::hud`](crate::nodes::Layers)) so it stays
+/// on screen while the world scrolls. Notifications are drawn in the
+/// [`Theme`] of the game unless given a style with [`Toasts::with_style`].
+pub struct Toasts {
+    placement: ToastPlacement,
+    styles: [Option<Style>; 4],
+    theme: Theme,
+    visible: Vec<Notification>,
+}
+
+impl Toasts {
+    pub fn new() -> Self {
+        Self {
+            placement: ToastPlacement::default(),
+            styles: [None; 4],
+            theme: Theme::default(),
+            visible: Vec::new(),
+        }
+    }
+
+    /// Changes where notifications are drawn.
+    pub fn with_placement(mut self, placement: ToastPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Changes the style notifications of the given level are drawn in.
+    pub fn with_style(mut self, level: Level, style: Style) -> Self {
+        self.styles[level as usize] = Some(style);
+        self
+    }
+
+    fn style(&self, level: Level) -> Style {
+        self.styles[level as usize].unwrap_or_else(|| self.theme.notification(level))
+    }
+}
+
+impl Default for Toasts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Node for Toasts {
+    fn update(&mut self, ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+        self.theme = ctx.config().theme;
+        Ok(())
+    }
+
+    fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+        Ok(false)
+    }
+
+    fn on_engine_event(&mut self, _ctx: &mut Context, ev: &EngineEvent) {
+        match ev {
+            EngineEvent::Notifications(visible) => self.visible = visible.clone(),
+            EngineEvent::ConfigChanged(Config::Theme(theme)) => self.theme = *theme,
+            _ => {}
+        }
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        let (width, height) = frame.size();
+        match self.placement {
+            ToastPlacement::TopRight => {
+                for (row, notification) in self.visible.iter().enumerate() {
+                    let text = format!(" {} ", notification.message);
+                    let x = width.saturating_sub(text_width(&text));
+                    let style = self.style(notification.level);
+                    frame.draw_styled(x, row as u16, &text, style)?;
+                }
+            }
+            ToastPlacement::StatusLine => {
+                if let Some(notification) = self.visible.last() {
+                    let text = format!(" {}", notification.message);
+                    let padding = (width as usize).saturating_sub(text_width(&text) as usize);
+                    let line = format!("{}{}", text, " ".repeat(padding));
+                    let style = self.style(notification.level);
+                    frame.draw_styled(0, height.saturating_sub(1), &line, style)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::geometry::Rect;
+    use crate::renderer::TestRenderer;
+    use std::time::Duration;
+
+    #[test]
+    fn test_toasts_follow_engine_events() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut toasts = Toasts::new();
+        let mut status = Toasts::new().with_placement(ToastPlacement::StatusLine);
+        ctx.notify("Saved!", Level::Success, Duration::from_secs(2));
+        ctx.notify("Low HP", Level::Warning, Duration::from_secs(2));
+        let visible = ctx.advance_notifications(Duration::ZERO).unwrap();
+        toasts.on_engine_event(&mut ctx, &EngineEvent::Notifications(visible.clone()));
+        status.on_engine_event(&mut ctx, &EngineEvent::Notifications(visible));
+
+        let mut renderer = TestRenderer::new(12, 3);
+        let area = Rect::new(0, 0, 12, 3);
+        toasts
+            .render(&mut Frame::new(&mut renderer, area, 0.0))
+            .unwrap();
+        status
+            .render(&mut Frame::new(&mut renderer, area, 0.0))
+            .unwrap();
+        assert_eq!(renderer.row(0), "     Saved!");
+        assert_eq!(renderer.row(1), "     Low HP");
+        assert_eq!(renderer.row(2), " Low HP");
+        assert_eq!(
+            renderer.cell_at(5, 0).unwrap().style(),
+            crate::style!(black on green)
+        );
+    }
+
+    #[test]
+    fn test_toasts_follow_the_theme() {
+        let mut ctx = Context::new(GameConfig::new());
+        let mut toasts = Toasts::new();
+        let mut custom = Toasts::new().with_style(Level::Info, crate::style!(red on black));
+        let mut theme = Theme::default();
+        theme.notifications[Level::Info as usize] = crate::style!(white on blue);
+        ctx.set_theme(theme);
+        for change in ctx.apply_pending() {
+            let event = EngineEvent::ConfigChanged(change);
+            toasts.on_engine_event(&mut ctx, &event);
+            custom.on_engine_event(&mut ctx, &event);
+        }
+        assert_eq!(toasts.style(Level::Info), crate::style!(white on blue));
+        assert_eq!(toasts.style(Level::Error), crate::style!(white on dark_red));
+        assert_eq!(custom.style(Level::Info), crate::style!(red on black));
+    }
+}
@@ -0,0 +1,234 @@
+use crate::actions::{ActionMap, KeyBinding};
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::hit::HitId;
+use crate::nodes::Node;
+use crate::replay;
+use crate::style::Style;
+use crossterm::event::{Event, KeyCode, MouseButton, MouseEventKind};
+
+/// How long a pressed button stays highlighted, in seconds
+const PRESS_FLASH: f32 = 0.15;
+
+struct Button {
+    label: String,
+    key: KeyBinding,
+    id: HitId,
+}
+
+/// On-screen buttons that press keys when clicked, for players whose
+/// terminal has a mouse or touch screen but no handy keyboard, such as SSH
+/// apps on tablets.
+///
+/// Arrow buttons sit in the bottom-left corner and the other buttons along
+/// the bottom-right. A click sends the button's key through
+/// [`Context::send_event`], so the game handles it like a key pressed by
+/// the player. The buttons are only shown while the `touch_controls`
+/// setting is on; add the node as the last child of the root so they are
+/// drawn, and clicked, over the game.
+pub struct TouchControls {
+    arrows: Vec<Button>,
+    buttons: Vec<Button>,
+    enabled: bool,
+    /// Button last pressed and how much longer it stays highlighted
+    pressed: Option<(HitId, f32)>,
+    style: Style,
+    pressed_style: Style,
+}
+
+impl TouchControls {
+    pub fn new() -> Self {
+        Self {
+            arrows: Vec::new(),
+            buttons: Vec::new(),
+            enabled: false,
+            pressed: None,
+            style: crate::style!(black on grey),
+            pressed_style: crate::style!(black on white),
+        }
+    }
+
+    /// Adds a pad of arrow buttons pressing the arrow keys.
+    pub fn with_arrows(mut self) -> Self {
+        self.arrows = [
+            ("^", KeyCode::Up),
+            ("<", KeyCode::Left),
+            ("v", KeyCode::Down),
+            (">", KeyCode::Right),
+        ]
+        .into_iter()
+        .map(|(label, code)| Button {
+            label: label.to_string(),
+            key: code.into(),
+            id: HitId::new(),
+        })
+        .collect();
+        self
+    }
+
+    /// Adds a button labeled `label` pressing `key`.
+    pub fn with_button(mut self, label: impl Into<String>, key: impl Into<KeyBinding>) -> Self {
+        self.buttons.push(Button {
+            label: label.into(),
+            key: key.into(),
+            id: HitId::new(),
+        });
+        self
+    }
+
+    /// Adds a button for each action of `actions`, pressing its first key.
+    pub fn with_actions(mut self, actions: &ActionMap) -> Self {
+        for action in actions.actions() {
+            if let Some(&key) = actions.bindings(action).first() {
+                self = self.with_button(action, key);
+            }
+        }
+        self
+    }
+
+    /// Replaces the styles of the buttons and of a button just pressed.
+    pub fn with_style(mut self, style: Style, pressed: Style) -> Self {
+        self.style = style;
+        self.pressed_style = pressed;
+        self
+    }
+
+    /// Returns whether the buttons are shown.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns each button with its area in a frame of `size`.
+    fn layout(&self, (width, height): (u16, u16)) -> Vec<(&Button, Rect)> {
+        // Up above down, with left and right beside it
+        let pad = [(4, 2), (0, 1), (4, 1), (8, 1)];
+        let mut layout: Vec<_> = self
+            .arrows
+            .iter()
+            .zip(pad)
+            .map(|(button, (x, up))| (button, Rect::new(x, height.saturating_sub(up), 3, 1)))
+            .collect();
+        let widths: Vec<u16> = self
+            .buttons
+            .iter()
+            .map(|button| button.label.chars().count() as u16 + 2)
+            .collect();
+        let total = widths.iter().map(|width| width + 1).sum::<u16>();
+        let mut x = width.saturating_sub(total.saturating_sub(1));
+        for (button, width) in self.buttons.iter().zip(widths) {
+            layout.push((button, Rect::new(x, height.saturating_sub(1), width, 1)));
+            x = x.saturating_add(width + 1);
+        }
+        layout
+    }
+}
+
+impl Default for TouchControls {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Node for TouchControls {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        self.enabled = ctx.config().touch_controls;
+        if let Some((_, remaining)) = &mut self.pressed {
+            *remaining -= dt;
+            if *remaining <= 0.0 {
+                self.pressed = None;
+            }
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+        Ok(false)
+    }
+
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        if let EngineEvent::Hit(hit) = ev
+            && self.enabled
+            && let MouseEventKind::Down(MouseButton::Left) = hit.event.kind
+            && let Some(button) = self
+                .arrows
+                .iter()
+                .chain(&self.buttons)
+                .find(|button| button.id == hit.id)
+        {
+            ctx.send_event(replay::key_event(button.key));
+            self.pressed = Some((button.id, PRESS_FLASH));
+        }
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        if !self.enabled {
+            return Ok(());
+        }
+        for (button, area) in self.layout(frame.size()) {
+            let style = match self.pressed {
+                Some((id, _)) if id == button.id => self.pressed_style,
+                _ => self.style,
+            };
+            frame.hit_region(button.id, area);
+            frame.draw_styled(area.x, area.y, &format!(" {} ", button.label), style)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, GameConfig};
+    use crate::hit::HitRegions;
+    use crate::renderer::TestRenderer;
+    use crossterm::event::{KeyEvent, KeyModifiers, MouseEvent};
+
+    fn render(controls: &TouchControls, regions: &mut HitRegions) -> TestRenderer {
+        let mut renderer = TestRenderer::new(20, 3);
+        regions.clear();
+        controls
+            .render(
+                &mut Frame::new(&mut renderer, Rect::new(0, 0, 20, 3), 0.0)
+                    .with_hit_regions(regions),
+            )
+            .unwrap();
+        renderer
+    }
+
+    #[test]
+    fn test_buttons_press_keys_when_enabled() {
+        let actions = ActionMap::new().with_binding("jump", KeyCode::Char(' '));
+        let mut controls = TouchControls::new().with_arrows().with_actions(&actions);
+        let mut ctx = Context::new(GameConfig::new());
+        let mut regions = HitRegions::new();
+        controls.update(&mut ctx, 0.1).unwrap();
+        assert_eq!(render(&controls, &mut regions).row(2), "");
+
+        ctx.set_config(Config::TouchControls(true));
+        ctx.apply_pending();
+        controls.update(&mut ctx, 0.1).unwrap();
+        let renderer = render(&controls, &mut regions);
+        assert_eq!(renderer.row(1), "     ^");
+        assert_eq!(renderer.row(2), " <   v   >     jump");
+
+        let click = |column, row| MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        };
+        for (x, y) in [(9, 2), (17, 2)] {
+            let hit = regions.hit(click(x, y)).unwrap();
+            controls.on_engine_event(&mut ctx, &EngineEvent::Hit(hit));
+        }
+        let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+        assert_eq!(
+            ctx.take_sent_events(),
+            [key(KeyCode::Right), key(KeyCode::Char(' '))]
+        );
+        assert!(regions.hit(click(2, 1)).is_none());
+    }
+}
@@ -0,0 +1,245 @@
+use crate::context::Context;
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::nodes::Node;
+use crate::particles::{Particle, Particles};
+use crate::random::Rng;
+use crate::style::Style;
+use crossterm::event::Event;
+
+/// The kind of weather a [`Weather`] node shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeatherKind {
+    Rain,
+    Snow,
+    /// Patches of mist drifting with the wind.
+    Fog,
+}
+
+/// A screen-space weather effect drawn over whatever is below it.
+///
+/// Put it in the HUD of [`Layers`](crate::nodes::Layers) so it stays in
+/// place while the camera moves. Rain and snow are [`Particles`] that fall
+/// across the frame, drifting with the wind; fog is a slowly moving pattern.
+/// Like every node it stops moving while the game is paused, and it shows
+/// nothing moving when `reduced_motion` is set.
+pub struct Weather {
+    kind: WeatherKind,
+    /// How much of the sky is covered, from 0.0 to 1.0
+    density: f32,
+    /// Horizontal drift in columns per second
+    wind: f32,
+    style: Style,
+    particles: Particles,
+    rng: Rng,
+    /// Fractional particles owed by previous updates
+    pending: f32,
+    /// Distance the fog has drifted before and after the last update
+    fog_offset: (f32, f32),
+    size: (u16, u16),
+}
+
+impl Weather {
+    pub fn new(kind: WeatherKind) -> Self {
+        let style = match kind {
+            WeatherKind::Rain => crate::style!(blue),
+            WeatherKind::Snow => crate::style!(white),
+            WeatherKind::Fog => crate::style!(grey),
+        };
+        Self {
+            kind,
+            density: 0.5,
+            wind: 0.0,
+            style,
+            particles: Particles::new(4096),
+            rng: Rng::from_time(),
+            pending: 0.0,
+            fog_offset: (0.0, 0.0),
+            size: (0, 0),
+        }
+    }
+
+    pub fn with_density(mut self, density: f32) -> Self {
+        self.set_density(density);
+        self
+    }
+
+    pub fn with_wind(mut self, wind: f32) -> Self {
+        self.wind = wind;
+        self
+    }
+
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Makes the effect repeatable, e.g. for tests or replays.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    pub fn kind(&self) -> WeatherKind {
+        self.kind
+    }
+
+    /// Sets how heavy the weather is, from 0.0 (none) to 1.0.
+    pub fn set_density(&mut self, density: f32) {
+        self.density = density.clamp(0.0, 1.0);
+    }
+
+    pub fn set_wind(&mut self, wind: f32) {
+        self.wind = wind;
+    }
+
+    /// Returns the number of raindrops or snowflakes on screen.
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Emits the drops or flakes due after `dt` seconds at the top of the frame.
+    fn emit(&mut self, dt: f32) {
+        let (width, height) = (self.size.0 as f32, self.size.1 as f32);
+        let (rate, speed) = match self.kind {
+            WeatherKind::Rain => (3.0, 24.0),
+            WeatherKind::Snow => (0.6, 3.0),
+            WeatherKind::Fog => return,
+        };
+        // Wind carries particles sideways while they fall, so spawn them upwind too
+        let fall_time = (height + 1.0) / speed;
+        let drift = -self.wind * fall_time;
+        let (left, right) = (drift.min(0.0), width + drift.max(0.0));
+
+        self.pending += self.density * width * rate * dt;
+        while self.pending >= 1.0 {
+            self.pending -= 1.0;
+            let x = left + self.rng.next_f32() * (right - left);
+            let vy = speed * (0.8 + self.rng.next_f32() * 0.4);
+            let (ch, vx) = match self.kind {
+                WeatherKind::Rain if self.wind > speed * 0.2 => ('\\', self.wind),
+                WeatherKind::Rain if self.wind < -speed * 0.2 => ('/', self.wind),
+                WeatherKind::Rain => ('|', self.wind),
+                _ => {
+                    let sway = self.rng.next_f32() * 2.0 - 1.0;
+                    let ch = if self.rng.chance(0.5) { '*' } else { '.' };
+                    (ch, self.wind + sway)
+                }
+            };
+            let particle = Particle::new(x, 0.0, ch, self.style)
+                .with_velocity(vx, vy)
+                .with_life((height + 1.0) / vy);
+            self.particles.emit(particle);
+        }
+    }
+
+    /// Returns whether fog covers the cell at (x,y) after drifting by `offset`.
+    fn foggy(&self, x: u16, y: u16, offset: f32) -> bool {
+        let (x, y) = (x as f32 - offset, y as f32);
+        // Overlapping waves give irregular patches without a noise texture
+        let wave = (x * 0.21 + y * 0.5).sin()
+            + (x * 0.083 - y * 0.31 + 1.7).sin()
+            + (x * 0.047 + y * 0.13 + 4.1).sin();
+        (wave + 3.0) / 6.0 < self.density
+    }
+}
+
+impl Node for Weather {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        self.size = ctx.screen_size();
+        if ctx.config().reduced_motion {
+            self.particles.clear();
+            return Ok(());
+        }
+        // Over the CPU budget, the particles already falling are left to run out
+        if !ctx.is_degraded() {
+            self.emit(dt);
+        }
+        self.particles.update(dt);
+        let drift = if self.wind == 0.0 { 0.5 } else { self.wind };
+        self.fog_offset = (self.fog_offset.1, self.fog_offset.1 + drift * dt);
+        Ok(())
+    }
+
+    fn on_event(&mut self, _ctx: &mut Context, _ev: &Event) -> Result<bool, EngineError> {
+        Ok(false)
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        if self.kind != WeatherKind::Fog {
+            return self.particles.draw(frame);
+        }
+        let (previous, current) = self.fog_offset;
+        let offset = previous + (current - previous) * frame.alpha();
+        let (width, height) = frame.size();
+        for y in 0..height {
+            for x in 0..width {
+                if self.foggy(x, y, offset) {
+                    frame.draw_styled(x, y, "░", self.style)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::geometry::Rect;
+    use crate::renderer::TestRenderer;
+
+    fn context(reduced_motion: bool) -> Context {
+        let mut ctx = Context::new(GameConfig {
+            reduced_motion,
+            ..GameConfig::new()
+        });
+        ctx.set_viewport(Rect::new(0, 0, 20, 10));
+        ctx
+    }
+
+    #[test]
+    fn test_rain_falls_with_the_wind() {
+        let mut ctx = context(false);
+        let mut rain = Weather::new(WeatherKind::Rain)
+            .with_density(1.0)
+            .with_wind(10.0)
+            .with_seed(1);
+        for _ in 0..10 {
+            rain.update(&mut ctx, 0.05).unwrap();
+        }
+        // 20 columns at full density emit 60 drops a second
+        assert!(rain.particle_count() > 20);
+        assert!(
+            rain.particles
+                .iter()
+                .all(|drop| drop.ch == '\\' && drop.vx == 10.0)
+        );
+
+        let mut renderer = TestRenderer::new(20, 10);
+        rain.render(&mut Frame::new(&mut renderer, Rect::new(0, 0, 20, 10), 1.0))
+            .unwrap();
+        let drawn = renderer.to_string().matches('\\').count();
+        assert!(drawn > 0);
+    }
+
+    #[test]
+    fn test_reduced_motion_stills_weather() {
+        let mut ctx = context(true);
+        let mut snow = Weather::new(WeatherKind::Snow)
+            .with_density(1.0)
+            .with_seed(1);
+        snow.update(&mut ctx, 1.0).unwrap();
+        assert_eq!(snow.particle_count(), 0);
+
+        let mut fog = Weather::new(WeatherKind::Fog).with_density(0.5);
+        fog.update(&mut ctx, 1.0).unwrap();
+        assert_eq!(fog.fog_offset, (0.0, 0.0));
+        let mut renderer = TestRenderer::new(20, 10);
+        fog.render(&mut Frame::new(&mut renderer, Rect::new(0, 0, 20, 10), 1.0))
+            .unwrap();
+        let covered = renderer.to_string().matches('░').count();
+        assert!(covered > 20 && covered < 180, "{}", covered);
+    }
+}
@@ -0,0 +1,28 @@
+//! Transient notifications posted through [`Context::notify`](crate::context::Context::notify).
+//!
+//! The context queues notifications and counts down the ones on display;
+//! nodes such as [`Toasts`](crate::nodes::Toasts) show them.
+use std::time::Duration;
+
+/// How many notifications are on display at once; later ones wait their turn.
+pub const MAX_VISIBLE: usize = 3;
+
+/// Severity of a notification, which decides how it is styled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Level {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// A message shown to the player for a limited time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub message: String,
+    pub level: Level,
+    /// Display time left; only counts down while the notification is visible.
+    pub remaining: Duration,
+}
@@ -0,0 +1,179 @@
+//! Simple particles: glyphs that move, fall and expire.
+//!
+//! A [`Particles`] pool moves its particles in the fixed update and draws
+//! them at interpolated positions, so they glide smoothly at any frame rate.
+//! Effects such as [`nodes::Weather`](crate::nodes::Weather) emit particles
+//! into a pool; games can use one directly for sparks, debris or smoke.
+//! Effects should not emit anything while
+//! [`GameConfig::reduced_motion`](crate::config::GameConfig::reduced_motion) is set.
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::style::Style;
+
+/// A glyph moving across the frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particle {
+    pub x: f32,
+    pub y: f32,
+    /// Velocity in cells per second
+    pub vx: f32,
+    pub vy: f32,
+    /// Seconds left before the particle disappears
+    pub life: f32,
+    pub ch: char,
+    pub style: Style,
+    /// Position before the last update, for interpolation
+    previous: (f32, f32),
+}
+
+impl Particle {
+    /// Creates a still particle that lives for one second.
+    pub fn new(x: f32, y: f32, ch: char, style: Style) -> Self {
+        Self {
+            x,
+            y,
+            vx: 0.0,
+            vy: 0.0,
+            life: 1.0,
+            ch,
+            style,
+            previous: (x, y),
+        }
+    }
+
+    pub fn with_velocity(mut self, vx: f32, vy: f32) -> Self {
+        self.vx = vx;
+        self.vy = vy;
+        self
+    }
+
+    pub fn with_life(mut self, seconds: f32) -> Self {
+        self.life = seconds;
+        self
+    }
+
+    /// Returns the position at the frame's interpolation alpha.
+    pub fn position(&self, alpha: f32) -> (f32, f32) {
+        let alpha = alpha.clamp(0.0, 1.0);
+        (
+            self.previous.0 + (self.x - self.previous.0) * alpha,
+            self.previous.1 + (self.y - self.previous.1) * alpha,
+        )
+    }
+}
+
+/// A bounded pool of particles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Particles {
+    particles: Vec<Particle>,
+    capacity: usize,
+    /// Acceleration applied to every particle, in cells per second squared
+    gravity: (f32, f32),
+}
+
+impl Particles {
+    /// Creates a pool holding at most `capacity` particles.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            particles: Vec::with_capacity(capacity),
+            capacity,
+            gravity: (0.0, 0.0),
+        }
+    }
+
+    pub fn with_gravity(mut self, x: f32, y: f32) -> Self {
+        self.gravity = (x, y);
+        self
+    }
+
+    /// Adds a particle, returning `false` if the pool is full.
+    pub fn emit(&mut self, particle: Particle) -> bool {
+        if self.particles.len() >= self.capacity {
+            return false;
+        }
+        self.particles.push(particle);
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Particle> {
+        self.particles.iter()
+    }
+
+    /// Removes the particles for which `keep` returns `false`.
+    pub fn retain(&mut self, keep: impl FnMut(&Particle) -> bool) {
+        self.particles.retain(keep);
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    /// Moves every particle by `dt` seconds and removes expired ones.
+    pub fn update(&mut self, dt: f32) {
+        let (gx, gy) = self.gravity;
+        for particle in &mut self.particles {
+            particle.previous = (particle.x, particle.y);
+            particle.vx += gx * dt;
+            particle.vy += gy * dt;
+            particle.x += particle.vx * dt;
+            particle.y += particle.vy * dt;
+            particle.life -= dt;
+        }
+        self.particles.retain(|particle| particle.life > 0.0);
+    }
+
+    /// Draws the particles at their interpolated positions; those outside
+    /// the frame are clipped.
+    pub fn draw(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        let mut glyph = [0; 4];
+        for particle in &self.particles {
+            let (x, y) = particle.position(frame.alpha());
+            if x < 0.0 || y < 0.0 {
+                continue;
+            }
+            frame.draw_styled(
+                x as u16,
+                y as u16,
+                particle.ch.encode_utf8(&mut glyph),
+                particle.style,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Rect;
+    use crate::renderer::BasicRenderer;
+
+    #[test]
+    fn test_particles_move_fall_and_expire() {
+        let mut particles = Particles::new(2).with_gravity(0.0, 2.0);
+        let spark = Particle::new(1.0, 0.0, '*', Style::new()).with_velocity(2.0, 0.0);
+        assert!(particles.emit(spark));
+        assert!(particles.emit(spark.with_life(0.2)));
+        assert!(!particles.emit(spark));
+
+        particles.update(0.5);
+        assert_eq!(particles.len(), 1);
+        let moved = particles.iter().next().unwrap();
+        assert_eq!((moved.x, moved.y), (2.0, 0.5));
+        assert_eq!(moved.position(0.5), (1.5, 0.25));
+
+        let mut renderer = BasicRenderer::new(4, 2).unwrap();
+        particles
+            .draw(&mut Frame::new(&mut renderer, Rect::new(0, 0, 4, 2), 1.0))
+            .unwrap();
+        assert_eq!(renderer.cell(2, 0).unwrap().ch, '*');
+    }
+}
@@ -0,0 +1,219 @@
+//! Where a game keeps its files on each platform.
+//!
+//! Games should not write settings, saves or logs into whatever directory
+//! they were started from. [`Paths`] resolves one directory for each kind
+//! of file, following the conventions of the platform:
+//!
+//! | Directory | Linux and BSD            | macOS                                 | Windows                      |
+//! |-----------|--------------------------|---------------------------------------|------------------------------|
+//! | config    | `$XDG_CONFIG_HOME/<app>` | `~/Library/Preferences/<app>`         | `%APPDATA%\<app>\config`     |
+//! | saves     | `$XDG_DATA_HOME/<app>`   | `~/Library/Application Support/<app>` | `%APPDATA%\<app>\saves`      |
+//! | cache     | `$XDG_CACHE_HOME/<app>`  | `~/Library/Caches/<app>`              | `%LOCALAPPDATA%\<app>\cache` |
+//! | logs      | `$XDG_STATE_HOME/<app>`  | `~/Library/Logs/<app>`                | `%LOCALAPPDATA%\<app>\logs`  |
+//!
+//! The XDG variables default to `~/.config`, `~/.local/share`, `~/.cache`
+//! and `~/.local/state`. Replays and screenshots go into subdirectories of
+//! the save directory.
+//!
+//! Setting `app_name` in the game configuration makes the engine use these
+//! directories: a relative `autosave` path is then a file in the save
+//! directory, and [`Context::paths`](crate::context::Context::paths) gives
+//! nodes the same directories for their own files.
+//!
+//! ```no_run
+//! use coil_engine::actions::ActionMap;
+//! use coil_engine::paths::Paths;
+//!
+//! let paths = Paths::new("snake");
+//! let keys = ActionMap::load(paths.config_file("keys.txt")).unwrap();
+//! ```
+use crate::errors::EngineError;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The directories a game keeps its files in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Paths {
+    config: PathBuf,
+    saves: PathBuf,
+    cache: PathBuf,
+    logs: PathBuf,
+}
+
+impl Paths {
+    /// Resolves the directories of the game `app` for the current platform
+    /// and user.
+    ///
+    /// Without a home directory, the directories are created under `.<app>`
+    /// in the current directory.
+    pub fn new(app: &str) -> Self {
+        Self::resolve(app, env::consts::OS, |name| {
+            env::var_os(name).map(PathBuf::from)
+        })
+    }
+
+    /// Keeps every kind of file in a subdirectory of `root`, e.g. for
+    /// portable installs that carry their data along.
+    pub fn local(root: impl AsRef<Path>) -> Self {
+        let root = root.as_ref();
+        Self {
+            config: root.join("config"),
+            saves: root.join("saves"),
+            cache: root.join("cache"),
+            logs: root.join("logs"),
+        }
+    }
+
+    /// Resolves the directories for the platform `os`, as named by
+    /// [`std::env::consts::OS`], reading environment variables with `var`.
+    fn resolve(app: &str, os: &str, var: impl Fn(&str) -> Option<PathBuf>) -> Self {
+        // Relative values are ignored, as the XDG specification asks
+        let var = |name: &str| var(name).filter(|path| path.is_absolute());
+        let home = var("HOME").or_else(|| var("USERPROFILE"));
+        match (os, home) {
+            ("windows", home) => {
+                let roaming = var("APPDATA")
+                    .or_else(|| home.as_ref().map(|home| home.join("AppData\\Roaming")));
+                let local = var("LOCALAPPDATA")
+                    .or_else(|| home.as_ref().map(|home| home.join("AppData\\Local")));
+                match (roaming, local) {
+                    (Some(roaming), Some(local)) => Self {
+                        config: roaming.join(app).join("config"),
+                        saves: roaming.join(app).join("saves"),
+                        cache: local.join(app).join("cache"),
+                        logs: local.join(app).join("logs"),
+                    },
+                    _ => Self::local(format!(".{}", app)),
+                }
+            }
+            ("macos", Some(home)) => {
+                let library = home.join("Library");
+                Self {
+                    config: library.join("Preferences").join(app),
+                    saves: library.join("Application Support").join(app),
+                    cache: library.join("Caches").join(app),
+                    logs: library.join("Logs").join(app),
+                }
+            }
+            (_, Some(home)) => {
+                let xdg = |name, default| var(name).unwrap_or_else(|| home.join(default)).join(app);
+                Self {
+                    config: xdg("XDG_CONFIG_HOME", ".config"),
+                    saves: xdg("XDG_DATA_HOME", ".local/share"),
+                    cache: xdg("XDG_CACHE_HOME", ".cache"),
+                    logs: xdg("XDG_STATE_HOME", ".local/state"),
+                }
+            }
+            (_, None) => Self::local(format!(".{}", app)),
+        }
+    }
+
+    /// Directory for settings and key bindings.
+    pub fn config_dir(&self) -> &Path {
+        &self.config
+    }
+
+    /// Directory for saves and other progress the player would miss.
+    pub fn save_dir(&self) -> &Path {
+        &self.saves
+    }
+
+    /// Directory for files the game can recreate, such as generated chunks.
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache
+    }
+
+    pub fn log_dir(&self) -> &Path {
+        &self.logs
+    }
+
+    pub fn replay_dir(&self) -> PathBuf {
+        self.saves.join("replays")
+    }
+
+    pub fn screenshot_dir(&self) -> PathBuf {
+        self.saves.join("screenshots")
+    }
+
+    /// Returns the path of the file `name` in the config directory.
+    ///
+    /// Like the other file methods, absolute names are returned unchanged,
+    /// so paths given by the player are used as they are.
+    pub fn config_file(&self, name: impl AsRef<Path>) -> PathBuf {
+        self.config.join(name)
+    }
+
+    pub fn save_file(&self, name: impl AsRef<Path>) -> PathBuf {
+        self.saves.join(name)
+    }
+
+    pub fn cache_file(&self, name: impl AsRef<Path>) -> PathBuf {
+        self.cache.join(name)
+    }
+
+    pub fn log_file(&self, name: impl AsRef<Path>) -> PathBuf {
+        self.logs.join(name)
+    }
+
+    pub fn replay_file(&self, name: impl AsRef<Path>) -> PathBuf {
+        self.replay_dir().join(name)
+    }
+
+    pub fn screenshot_file(&self, name: impl AsRef<Path>) -> PathBuf {
+        self.screenshot_dir().join(name)
+    }
+
+    /// Creates every directory that does not exist yet.
+    pub fn create_all(&self) -> Result<(), EngineError> {
+        for dir in [&self.config, &self.cache, &self.logs] {
+            fs::create_dir_all(dir)?;
+        }
+        fs::create_dir_all(self.replay_dir())?;
+        fs::create_dir_all(self.screenshot_dir())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(os: &str, vars: &[(&str, &str)]) -> Paths {
+        Paths::resolve("snake", os, |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| PathBuf::from(value))
+        })
+    }
+
+    #[test]
+    fn test_platform_directories() {
+        let linux = resolve(
+            "linux",
+            &[("HOME", "/home/ada"), ("XDG_CACHE_HOME", "/tmp/cache")],
+        );
+        assert_eq!(linux.config_dir(), Path::new("/home/ada/.config/snake"));
+        assert_eq!(linux.save_dir(), Path::new("/home/ada/.local/share/snake"));
+        assert_eq!(linux.cache_dir(), Path::new("/tmp/cache/snake"));
+        assert_eq!(linux.log_dir(), Path::new("/home/ada/.local/state/snake"));
+        assert_eq!(
+            linux.replay_file("best.txt"),
+            Path::new("/home/ada/.local/share/snake/replays/best.txt")
+        );
+        // Absolute names are kept
+        assert_eq!(linux.save_file("/srv/save.txt"), Path::new("/srv/save.txt"));
+
+        let macos = resolve("macos", &[("HOME", "/Users/ada")]);
+        assert_eq!(
+            macos.save_dir(),
+            Path::new("/Users/ada/Library/Application Support/snake")
+        );
+        assert_eq!(macos.log_dir(), Path::new("/Users/ada/Library/Logs/snake"));
+
+        // Relative XDG values and a missing home fall back
+        let bare = resolve("linux", &[("XDG_CONFIG_HOME", "config")]);
+        assert_eq!(bare, Paths::local(".snake"));
+        assert_eq!(bare.config_dir(), Path::new(".snake/config"));
+    }
+}
@@ -0,0 +1,283 @@
+//! Object pools for things spawned and despawned all the time.
+//!
+//! Bullets, sparks and damage numbers live for a moment but are created by
+//! the dozen every second. A [`Pool`] keeps despawned items and hands them
+//! out again on the next spawn, so their allocations (strings, buffers) are
+//! reused instead of freed and made anew. New items are built from the
+//! pool's prefab, a function returning a fresh item, and every spawn then
+//! sets up the item it gets, new or recycled.
+//!
+//! ```
+//! use coil_engine::pool::Pool;
+//!
+//! let mut bullets = Pool::new(|| (0.0f32, 0.0f32));
+//! let bullet = bullets.spawn(|b| *b = (1.0, 2.0)).unwrap();
+//! bullets.despawn(bullet);
+//! bullets.spawn(|b| *b = (3.0, 4.0)).unwrap();
+//! assert_eq!(bullets.stats().reused, 1);
+//! ```
+use std::fmt;
+
+/// A reference to an item spawned from a [`Pool`].
+///
+/// Handles of despawned items stay invalid even once their slot is reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PoolHandle {
+    index: u32,
+    generation: u32,
+}
+
+/// Usage counters of a pool, e.g. for a debug overlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PoolStats {
+    /// Items currently spawned
+    pub active: usize,
+    /// Items waiting to be reused
+    pub free: usize,
+    /// Most items spawned at once
+    pub peak: usize,
+    /// Spawns that recycled an item
+    pub reused: u64,
+    /// Spawns refused because the pool was full
+    pub refused: u64,
+}
+
+impl fmt::Display for PoolStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} active, {} free, peak {}, {} reused",
+            self.active, self.free, self.peak, self.reused
+        )?;
+        if self.refused > 0 {
+            write!(f, ", {} refused", self.refused)?;
+        }
+        Ok(())
+    }
+}
+
+struct Slot<T> {
+    item: T,
+    generation: u32,
+    active: bool,
+}
+
+/// A set of reusable items built from a prefab.
+pub struct Pool<T> {
+    prefab: Box<dyn Fn() -> T>,
+    slots: Vec<Slot<T>>,
+    /// Indices of inactive slots
+    free: Vec<u32>,
+    capacity: Option<usize>,
+    stats: PoolStats,
+}
+
+impl<T> Pool<T> {
+    /// Creates an empty pool that builds new items with `prefab`.
+    pub fn new(prefab: impl Fn() -> T + 'static) -> Self {
+        Self {
+            prefab: Box::new(prefab),
+            slots: Vec::new(),
+            free: Vec::new(),
+            capacity: None,
+            stats: PoolStats::default(),
+        }
+    }
+
+    /// Limits the pool to `capacity` items; spawns beyond it are refused.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Builds `count` items up front, so the first spawns do not allocate.
+    pub fn prewarm(mut self, count: usize) -> Self {
+        let count = self.capacity.map_or(count, |capacity| count.min(capacity));
+        while self.slots.len() < count {
+            self.free.push(self.slots.len() as u32);
+            self.slots.push(Slot {
+                item: (self.prefab)(),
+                generation: 0,
+                active: false,
+            });
+        }
+        self.stats.free = self.free.len();
+        self
+    }
+
+    /// Spawns an item, reusing a despawned one if possible, and sets it up
+    /// with `setup`. Returns `None` if the pool is full.
+    pub fn spawn(&mut self, setup: impl FnOnce(&mut T)) -> Option<PoolHandle> {
+        let index = match self.free.pop() {
+            Some(index) => {
+                if self.slots[index as usize].generation > 0 {
+                    self.stats.reused += 1;
+                }
+                index
+            }
+            None if self
+                .capacity
+                .is_some_and(|capacity| self.slots.len() >= capacity) =>
+            {
+                self.stats.refused += 1;
+                return None;
+            }
+            None => {
+                self.slots.push(Slot {
+                    item: (self.prefab)(),
+                    generation: 0,
+                    active: false,
+                });
+                self.slots.len() as u32 - 1
+            }
+        };
+        let slot = &mut self.slots[index as usize];
+        slot.active = true;
+        setup(&mut slot.item);
+        self.stats.active += 1;
+        self.stats.free = self.free.len();
+        self.stats.peak = self.stats.peak.max(self.stats.active);
+        Some(PoolHandle {
+            index,
+            generation: slot.generation,
+        })
+    }
+
+    /// Returns an item to the pool, returning `false` if it was already despawned.
+    pub fn despawn(&mut self, handle: PoolHandle) -> bool {
+        if self.get(handle).is_none() {
+            return false;
+        }
+        self.release(handle.index);
+        true
+    }
+
+    pub fn get(&self, handle: PoolHandle) -> Option<&T> {
+        self.slots
+            .get(handle.index as usize)
+            .filter(|slot| slot.active && slot.generation == handle.generation)
+            .map(|slot| &slot.item)
+    }
+
+    pub fn get_mut(&mut self, handle: PoolHandle) -> Option<&mut T> {
+        self.slots
+            .get_mut(handle.index as usize)
+            .filter(|slot| slot.active && slot.generation == handle.generation)
+            .map(|slot| &mut slot.item)
+    }
+
+    /// Iterates over the spawned items.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.slots
+            .iter()
+            .filter(|slot| slot.active)
+            .map(|slot| &slot.item)
+    }
+
+    /// Iterates mutably over the spawned items.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots
+            .iter_mut()
+            .filter(|slot| slot.active)
+            .map(|slot| &mut slot.item)
+    }
+
+    /// Despawns the items for which `keep` returns `false`, e.g. expired ones.
+    pub fn retain(&mut self, mut keep: impl FnMut(&mut T) -> bool) {
+        for index in 0..self.slots.len() {
+            let slot = &mut self.slots[index];
+            if slot.active && !keep(&mut slot.item) {
+                self.release(index as u32);
+            }
+        }
+    }
+
+    /// Despawns every item.
+    pub fn clear(&mut self) {
+        self.retain(|_| false);
+    }
+
+    /// Returns the number of spawned items.
+    pub fn len(&self) -> usize {
+        self.stats.active
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stats.active == 0
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        self.stats
+    }
+
+    fn release(&mut self, index: u32) {
+        let slot = &mut self.slots[index as usize];
+        slot.active = false;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(index);
+        self.stats.active -= 1;
+        self.stats.free = self.free.len();
+    }
+}
+
+impl<T> fmt::Debug for Pool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pool").field("stats", &self.stats).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawn_reuses_despawned_items() {
+        let mut texts = Pool::new(String::new).with_capacity(2);
+        let first = texts
+            .spawn(|text| text.push_str("a long line of text"))
+            .unwrap();
+        let second = texts.spawn(|text| text.push('b')).unwrap();
+        assert_eq!(texts.spawn(|_| {}), None);
+
+        let capacity = texts.get(first).unwrap().capacity();
+        assert!(texts.despawn(first));
+        assert!(!texts.despawn(first));
+        let third = texts
+            .spawn(|text| {
+                text.clear();
+                text.push('c');
+            })
+            .unwrap();
+        // The recycled string keeps its allocation, and the old handle stays dead
+        assert_eq!(texts.get(third).unwrap().capacity(), capacity);
+        assert_eq!(texts.get(first), None);
+        assert_eq!(texts.get(second).map(String::as_str), Some("b"));
+
+        texts.retain(|text| text != "b");
+        assert_eq!(texts.iter().collect::<Vec<_>>(), ["c"]);
+        let stats = texts.stats();
+        assert_eq!(
+            (
+                stats.active,
+                stats.free,
+                stats.peak,
+                stats.reused,
+                stats.refused
+            ),
+            (1, 1, 2, 1, 1)
+        );
+        assert_eq!(
+            stats.to_string(),
+            "1 active, 1 free, peak 2, 1 reused, 1 refused"
+        );
+    }
+
+    #[test]
+    fn test_prewarm() {
+        let mut pool = Pool::new(|| 0).with_capacity(3).prewarm(5);
+        assert_eq!(pool.stats().free, 3);
+        let handle = pool.spawn(|n| *n = 7).unwrap();
+        assert_eq!(pool.get(handle), Some(&7));
+        assert_eq!(pool.stats().reused, 0);
+    }
+}
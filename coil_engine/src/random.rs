@@ -0,0 +1,86 @@
+//! A small seedable random number generator.
+//!
+//! Procedural generators take a seed so the same seed always produces the
+//! same result, e.g. to share a map or replay a run. [`Rng`] is SplitMix64:
+//! fast and good enough for games, but not for anything security related.
+
+/// A seedable pseudo-random number generator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Seeds the generator from the system clock.
+    pub fn from_time() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        Self::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..bound`, or 0 if `bound` is 0.
+    pub fn below(&mut self, bound: usize) -> usize {
+        if bound == 0 {
+            return 0;
+        }
+        ((self.next_u64() as u128 * bound as u128) >> 64) as usize
+    }
+
+    /// Returns a number in `0.0..1.0`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Returns `true` with probability `chance`.
+    pub fn chance(&mut self, chance: f32) -> bool {
+        self.next_f32() < chance
+    }
+
+    /// Returns an index into `weights`, each picked in proportion to its
+    /// weight, or `None` if no weight is positive.
+    pub fn weighted(&mut self, weights: &[f32]) -> Option<usize> {
+        let total: f32 = weights.iter().filter(|w| **w > 0.0).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut pick = self.next_f32() * total;
+        let mut last = None;
+        for (index, weight) in weights.iter().enumerate().filter(|(_, w)| **w > 0.0) {
+            if pick < *weight {
+                return Some(index);
+            }
+            pick -= weight;
+            last = Some(index);
+        }
+        // Rounding may leave a sliver past the last weight
+        last
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_numbers() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        assert!((0..10).all(|_| a.next_u64() == b.next_u64()));
+        assert!((0..1000).all(|_| a.below(6) < 6 && (0.0..1.0).contains(&a.next_f32())));
+        assert_eq!(a.weighted(&[0.0, 2.0, 0.0]), Some(1));
+        assert_eq!(a.weighted(&[0.0, -1.0]), None);
+    }
+}
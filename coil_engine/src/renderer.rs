@@ -2,20 +2,64 @@
 //!
 //! Defines a cell-based API and a Crossterm-backed implementation.
 use crate::errors::EngineError;
-use crossterm::cursor;
-use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
-use crossterm::execute;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::nodes::Node;
+use crate::style::{Attributes, Style};
+use crate::text::char_width;
 use crossterm::style::Color;
-use crossterm::terminal::{EnterAlternateScreen, LeaveAlternateScreen};
-use log::warn;
-use std::io::{Write, stdout};
+use crossterm::terminal::{Clear, ClearType};
+use crossterm::{execute, queue};
+use std::fmt;
+use std::io::{Write, sink, stdout};
+use std::num::NonZeroU16;
 
-/// A single character cell with foreground and background colors.
+/// Identifies a hyperlink URI registered with a renderer through [`Renderer::set_link`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinkId(NonZeroU16);
+
+/// A single character cell with foreground and background colors,
+/// attributes such as bold, and optionally a hyperlink.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cell {
     pub ch: char,
     pub fg: Color,
     pub bg: Color,
+    pub attrs: Attributes,
+    pub link: Option<LinkId>,
+}
+
+impl Cell {
+    /// A space drawn in the terminal's default colors.
+    pub const BLANK: Cell = Cell::new(' ');
+
+    /// Character marking the column covered by the right half of a wide glyph.
+    ///
+    /// Such cells are never printed themselves; the glyph to their left fills them.
+    pub const WIDE_TAIL: char = '\0';
+
+    /// Creates a cell in the terminal's default colors.
+    pub const fn new(ch: char) -> Self {
+        Self::styled(ch, Style::new())
+    }
+
+    /// Creates a cell with the given style.
+    pub const fn styled(ch: char, style: Style) -> Self {
+        Self {
+            ch,
+            fg: style.fg,
+            bg: style.bg,
+            attrs: style.attrs,
+            link: None,
+        }
+    }
+
+    /// Returns the style of this cell.
+    pub const fn style(&self) -> Style {
+        Style::new().fg(self.fg).bg(self.bg).attrs(self.attrs)
+    }
 }
 
 /// Abstract renderer API for games.
@@ -24,9 +68,13 @@ pub trait Renderer {
     fn clear(&mut self) -> Result<(), EngineError>;
 
     /// Draw one cell at (x,y).
+    ///
+    /// Cells outside the drawing area are clipped rather than failing the
+    /// draw; implementations may report them through other means.
     fn draw_cell(&mut self, x: u16, y: u16, cell: Cell) -> Result<(), EngineError>;
 
     /// Draw a string starting at (x,y).
+    #[deprecated(since = "0.1.0", note = "use `draw_styled` with a `Style`")]
     fn draw_str(
         &mut self,
         x: u16,
@@ -34,49 +82,218 @@ pub trait Renderer {
         text: &str,
         fg: Color,
         bg: Color,
-    ) -> Result<(), EngineError>;
+    ) -> Result<(), EngineError> {
+        self.draw_styled(x, y, text, Style::new().fg(fg).bg(bg))
+    }
+
+    /// Draw a string starting at (x,y) with the given style.
+    ///
+    /// Wide glyphs take two columns and zero-width characters are skipped.
+    fn draw_styled(&mut self, x: u16, y: u16, text: &str, style: Style) -> Result<(), EngineError>;
+
+    /// Attaches a hyperlink to the cells drawn by subsequent `draw_styled`
+    /// calls, or stops attaching one with `None`.
+    ///
+    /// Renderers that cannot show links ignore it.
+    fn set_link(&mut self, _uri: Option<&str>) {}
 
     /// Flush all pending draws to the terminal.
     fn flush(&mut self) -> Result<(), EngineError>;
 }
 
 pub struct BasicRenderer {
+    origin: (u16, u16),
     width: u16,
     height: u16,
     back_buffer: Vec<Cell>,
     front_buffer: Vec<Cell>,
+    /// The back buffer as of the last flush, which may differ from what is
+    /// on screen while redraws are shown
+    previous: Vec<Cell>,
+    /// Whether cells that changed since the last flush are flashed
+    show_redraws: bool,
+    /// Cells that changed in the flushes since the last `take_changed_cells`
+    changed_cells: u64,
+    /// Recoverable errors (such as clipped draws) since the last `take_errors`.
+    errors: Vec<EngineError>,
+    /// Mappings applied, in order, to every cell as it is written to the terminal.
+    filters: Vec<fn(Cell) -> Cell>,
+    /// Number of frames dumped so far, when writing frames as plain text.
+    dumped_frames: Option<u64>,
+    /// Where escape sequences are written; the terminal unless set otherwise.
+    out: CountingWriter,
+    /// URIs of the hyperlinks drawn so far, indexed by `LinkId - 1`.
+    links: Vec<String>,
+    /// Link attached to cells drawn by `draw_styled`.
+    pen_link: Option<LinkId>,
 }
 
 impl BasicRenderer {
+    /// Creates a renderer for a `width` x `height` area.
+    ///
+    /// The terminal itself is set up by a [`TerminalSession`](crate::terminal::TerminalSession).
     pub fn new(width: u16, height: u16) -> Result<Self, EngineError> {
-        execute!(
-            stdout(),
-            EnterAlternateScreen,
-            EnableMouseCapture,
-            cursor::Hide
-        )
-        .map_err(|e| EngineError::Input(e.to_string()))?;
-        let back_buffer = vec![
-            Cell {
-                ch: ' ',
-                fg: Color::Reset,
-                bg: Color::Reset,
-            };
-            (width * height) as usize
-        ];
+        let back_buffer = vec![Cell::BLANK; width as usize * height as usize];
         let front_buffer = back_buffer.clone();
         Ok(Self {
+            origin: (0, 0),
             width,
             height,
+            previous: back_buffer.clone(),
             back_buffer,
             front_buffer,
+            show_redraws: false,
+            changed_cells: 0,
+            errors: Vec::new(),
+            filters: Vec::new(),
+            dumped_frames: None,
+            out: CountingWriter::new(Box::new(stdout())),
+            links: Vec::new(),
+            pen_link: None,
         })
     }
 
+    /// Returns the URI of a link registered with [`Renderer::set_link`].
+    pub fn link_uri(&self, link: LinkId) -> Option<&str> {
+        self.links
+            .get(link.0.get() as usize - 1)
+            .map(String::as_str)
+    }
+
+    /// Creates a renderer that draws to `out` instead of stdout, such as the
+    /// tty of another terminal or [`tmux` pane](crate::tmux::TmuxPane).
+    pub fn with_output(
+        width: u16,
+        height: u16,
+        out: impl Write + 'static,
+    ) -> Result<Self, EngineError> {
+        let mut renderer = Self::new(width, height)?;
+        renderer.out = CountingWriter::new(Box::new(out));
+        Ok(renderer)
+    }
+
+    /// Creates a renderer that writes each changed frame to stdout as plain
+    /// text after a `--- frame N ---` marker, for output that is not a terminal.
+    ///
+    /// Colors are dropped and trailing spaces trimmed, so the output can be
+    /// compared against golden files.
+    pub fn frame_dump(width: u16, height: u16) -> Result<Self, EngineError> {
+        let mut renderer = Self::new(width, height)?;
+        renderer.dumped_frames = Some(0);
+        Ok(renderer)
+    }
+
+    /// Returns the back buffer as text, one trimmed line per row.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for row in self.back_buffer.chunks(self.width.max(1) as usize) {
+            let line: String = row
+                .iter()
+                .map(|cell| self.filters.iter().fold(*cell, |cell, filter| filter(cell)))
+                .filter(|cell| cell.ch != Cell::WIDE_TAIL)
+                .map(|cell| cell.ch)
+                .collect();
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Sets the mappings applied, in order, to every cell when it is flushed,
+    /// such as [`accessibility::high_contrast`](crate::accessibility::high_contrast)
+    /// or [`glyphs::ascii`](crate::glyphs::ascii).
+    ///
+    /// The back buffer keeps the cells as drawn; the next flush redraws every cell.
+    pub fn set_filters(&mut self, filters: Vec<fn(Cell) -> Cell>) {
+        self.filters = filters;
+        self.invalidate();
+    }
+
+    /// Forgets what is on screen so the next flush redraws every cell,
+    /// e.g. after returning from [`TerminalSession::suspend`](crate::terminal::TerminalSession::suspend).
+    pub fn invalidate(&mut self) {
+        self.front_buffer.fill(Cell::new('\0'));
+    }
+
     pub fn size(&self) -> (u16, u16) {
         (self.width, self.height)
     }
 
+    /// Moves and resizes the drawing area within the terminal.
+    ///
+    /// The buffers are resized in place: what was drawn in the part of the
+    /// old area that is still inside the new one is kept, so a frame that
+    /// is not redrawn right away still shows it. The terminal is cleared
+    /// and the next flush redraws every cell.
+    pub fn set_viewport(&mut self, viewport: Rect) -> Result<(), EngineError> {
+        let old = (self.width, self.height);
+        let new = (viewport.width, viewport.height);
+        self.origin = (viewport.x, viewport.y);
+        self.width = viewport.width;
+        self.height = viewport.height;
+        resize_cells(&mut self.back_buffer, old, new);
+        // The terminal is cleared below, so nothing is on screen any more
+        self.front_buffer.clear();
+        self.front_buffer
+            .resize(self.back_buffer.len(), Cell::BLANK);
+        // Cells kept through the resize have not changed
+        self.previous.clone_from(&self.back_buffer);
+        if self.dumped_frames.is_some() {
+            return Ok(());
+        }
+        execute!(self.out, Clear(ClearType::All))?;
+        Ok(())
+    }
+
+    /// Returns the number of bytes written to the terminal since the last
+    /// call, and starts counting again.
+    pub fn take_bytes_written(&mut self) -> u64 {
+        std::mem::take(&mut self.out.written)
+    }
+
+    /// Flashes the cells that changed since the previous flush in reverse
+    /// video until the next one, or stops doing so.
+    ///
+    /// Cells a node draws the same as before are not written to the
+    /// terminal, so what flashes on a screen that looks still is redrawn
+    /// for nothing, and is a likely cause of flicker.
+    pub fn set_show_redraws(&mut self, enabled: bool) {
+        self.show_redraws = enabled;
+        self.invalidate();
+    }
+
+    /// Returns the number of cells that changed in the flushes since the
+    /// last call, and starts counting again.
+    pub fn take_changed_cells(&mut self) -> u64 {
+        std::mem::take(&mut self.changed_cells)
+    }
+
+    /// Keeps a copy of everything written to the terminal from now on, to
+    /// collect with [`BasicRenderer::take_mirrored`], or stops doing so.
+    pub fn set_mirrored(&mut self, enabled: bool) {
+        self.out.mirror = enabled.then(Vec::new);
+    }
+
+    /// Returns the bytes written to the terminal since the last call, if
+    /// enabled with [`BasicRenderer::set_mirrored`].
+    pub fn take_mirrored(&mut self) -> Vec<u8> {
+        self.out
+            .mirror
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Returns and clears the recoverable errors collected while drawing.
+    pub fn take_errors(&mut self) -> Vec<EngineError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Returns the cell drawn at (x,y) since the last clear.
+    pub fn cell(&self, x: u16, y: u16) -> Result<Cell, EngineError> {
+        Ok(self.back_buffer[self.index(x, y)?])
+    }
+
     /// Return the index of the cell at (x,y) in the back buffer.
     pub fn index(&self, x: u16, y: u16) -> Result<usize, EngineError> {
         if x >= self.width || y >= self.height {
@@ -88,6 +305,38 @@ impl BasicRenderer {
         Ok((y as usize * self.width as usize) + x as usize)
     }
 
+    /// Writes a cell at (x,y), followed by the tail cells of a wide glyph.
+    ///
+    /// A wide glyph partly overwritten is blanked whole, so neither its
+    /// head nor an orphaned tail is left behind on screen.
+    fn put(&mut self, x: u16, y: u16, cell: Cell) -> Result<(), EngineError> {
+        let width = char_width(cell.ch).max(1) as usize;
+        let index = self.index(x, y)?;
+        self.index(x.saturating_add(width as u16 - 1), y)?;
+        let row = y as usize * self.width as usize..(y as usize + 1) * self.width as usize;
+        let broken = |cell: &mut Cell| *cell = Cell { ch: ' ', ..*cell };
+        // The head and tails of a glyph covered from its right...
+        let mut left = index;
+        while left > row.start && self.back_buffer[left].ch == Cell::WIDE_TAIL {
+            left -= 1;
+            broken(&mut self.back_buffer[left]);
+        }
+        // ...and the tails of one covered from its left
+        let mut right = index + width;
+        while right < row.end && self.back_buffer[right].ch == Cell::WIDE_TAIL {
+            broken(&mut self.back_buffer[right]);
+            right += 1;
+        }
+        self.back_buffer[index] = cell;
+        for tail in &mut self.back_buffer[index + 1..index + width] {
+            *tail = Cell {
+                ch: Cell::WIDE_TAIL,
+                ..cell
+            };
+        }
+        Ok(())
+    }
+
     pub fn coordinates(&self, index: usize) -> Result<(u16, u16), EngineError> {
         if index >= self.back_buffer.len() {
             return Err(EngineError::Render(format!(
@@ -101,74 +350,509 @@ impl BasicRenderer {
     }
 }
 
+/// Passes writes on to the terminal, counting the bytes that went through
+/// and keeping a copy of them if asked to.
+struct CountingWriter {
+    inner: Box<dyn Write>,
+    written: u64,
+    mirror: Option<Vec<u8>>,
+}
+
+impl CountingWriter {
+    fn new(inner: Box<dyn Write>) -> Self {
+        Self {
+            inner,
+            written: 0,
+            mirror: None,
+        }
+    }
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.written += written as u64;
+        if let Some(mirror) = &mut self.mirror {
+            mirror.extend_from_slice(&buf[..written]);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Resizes a grid of `old` (width, height) cells to `new` in place, keeping
+/// the cells of the top-left part both sizes share and blanking the rest.
+///
+/// The vector keeps its allocation when shrinking, so dragging a window
+/// edge back and forth does not allocate again.
+fn resize_cells(cells: &mut Vec<Cell>, old: (u16, u16), new: (u16, u16)) {
+    let (old_width, new_width) = (old.0 as usize, new.0 as usize);
+    let rows = old.1.min(new.1) as usize;
+    let kept = old_width.min(new_width);
+    let len = new_width * new.1 as usize;
+    if new_width <= old_width {
+        for y in 0..rows {
+            cells.copy_within(y * old_width..y * old_width + kept, y * new_width);
+            // A wide glyph cut in half at the new edge cannot be shown
+            let last = y * new_width + kept;
+            if kept > 0 && char_width(cells[last - 1].ch) > 1 {
+                cells[last - 1] = Cell::BLANK;
+            }
+        }
+        cells.truncate(rows * new_width);
+        cells.resize(len, Cell::BLANK);
+    } else {
+        // Rows move towards the end, so go backwards to not overwrite them
+        cells.resize(len, Cell::BLANK);
+        for y in (0..rows).rev() {
+            cells.copy_within(y * old_width..y * old_width + kept, y * new_width);
+            cells[y * new_width + kept..(y + 1) * new_width].fill(Cell::BLANK);
+        }
+        cells[rows * new_width..].fill(Cell::BLANK);
+    }
+}
+
 impl Renderer for BasicRenderer {
     fn clear(&mut self) -> Result<(), EngineError> {
-        self.back_buffer.fill(Cell {
-            ch: ' ',
-            fg: Color::Reset,
-            bg: Color::Reset,
-        });
+        self.back_buffer.fill(Cell::BLANK);
         Ok(())
     }
 
     fn draw_cell(&mut self, x: u16, y: u16, cell: Cell) -> Result<(), EngineError> {
-        let index = self.index(x, y)?;
-        self.back_buffer[index] = cell;
+        if let Err(e) = self.put(x, y, cell) {
+            self.errors.push(e);
+        }
         Ok(())
     }
 
-    fn draw_str(
-        &mut self,
-        x: u16,
-        y: u16,
-        text: &str,
-        fg: Color,
-        bg: Color,
-    ) -> Result<(), EngineError> {
-        for (i, ch) in text.chars().enumerate() {
-            match self.draw_cell(x + i as u16, y, Cell { ch, fg, bg }) {
-                Ok(_) => {}
-                Err(EngineError::Render(e)) => {
-                    warn!("Failed to draw string at ({}, {}): {}", x + i as u16, y, e);
-                }
-                Err(e) => return Err(e),
+    fn draw_styled(&mut self, x: u16, y: u16, text: &str, style: Style) -> Result<(), EngineError> {
+        let mut clipped = false;
+        let mut column = x;
+        for ch in text.chars() {
+            let width = char_width(ch);
+            if width == 0 {
+                continue;
             }
-            self.draw_cell(x + i as u16, y, Cell { ch, fg, bg })?;
+            let cell = Cell {
+                link: self.pen_link,
+                ..Cell::styled(ch, style)
+            };
+            clipped |= self.put(column, y, cell).is_err();
+            column = column.saturating_add(width);
+        }
+        if clipped {
+            self.errors.push(EngineError::Render(format!(
+                "String at ({}, {}) clipped: {:?}",
+                x, y, text
+            )));
         }
         Ok(())
     }
 
+    fn set_link(&mut self, uri: Option<&str>) {
+        self.pen_link = uri.and_then(|uri| {
+            let index = match self.links.iter().position(|link| link == uri) {
+                Some(index) => index,
+                None => {
+                    self.links.push(uri.to_string());
+                    self.links.len() - 1
+                }
+            };
+            u16::try_from(index + 1)
+                .ok()
+                .and_then(NonZeroU16::new)
+                .map(LinkId)
+        });
+    }
+
     fn flush(&mut self) -> Result<(), EngineError> {
-        let mut out = stdout();
+        if let Some(frames) = self.dumped_frames {
+            if self.back_buffer == self.front_buffer {
+                return Ok(());
+            }
+            self.dumped_frames = Some(frames + 1);
+            let text = format!("--- frame {} ---\n{}", frames + 1, self.to_text());
+            self.front_buffer.copy_from_slice(&self.back_buffer);
+            self.out.write_all(text.as_bytes())?;
+            self.out.flush()?;
+            return Ok(());
+        }
+        let width = self.width as usize;
+        // The terminal has no attributes set between flushes
+        let mut attrs = Attributes::empty();
         for (i, back_cell) in self.back_buffer.iter().enumerate() {
-            let front_cell = self.front_buffer[i];
-            let (x, y) = self.coordinates(i)?;
-            if back_cell != &front_cell {
-                execute!(
-                    out,
-                    crossterm::cursor::MoveTo(x, y),
-                    crossterm::style::SetForegroundColor(back_cell.fg),
-                    crossterm::style::SetBackgroundColor(back_cell.bg),
-                    crossterm::style::Print(back_cell.ch)
-                )
-                .map_err(|e| EngineError::Render(e.to_string()))?;
-                self.front_buffer[i] = *back_cell; // Update front buffer
+            let changed = back_cell != &self.previous[i];
+            self.changed_cells += changed as u64;
+            let back_cell = &if changed && self.show_redraws {
+                highlight(*back_cell)
+            } else {
+                *back_cell
+            };
+            if back_cell == &self.front_buffer[i] {
+                continue;
             }
+            // The wide glyph to the left already covers this column
+            if back_cell.ch != Cell::WIDE_TAIL {
+                let (x, y) = ((i % width) as u16, (i / width) as u16);
+                let shown = self
+                    .filters
+                    .iter()
+                    .fold(*back_cell, |cell, filter| filter(cell));
+                queue!(
+                    self.out,
+                    crossterm::cursor::MoveTo(self.origin.0 + x, self.origin.1 + y)
+                )?;
+                if shown.attrs != attrs {
+                    // Attributes can only be turned off all at once, colors included
+                    if !attrs.is_empty() {
+                        queue!(
+                            self.out,
+                            crossterm::style::SetAttribute(crossterm::style::Attribute::Reset)
+                        )?;
+                    }
+                    queue!(
+                        self.out,
+                        crossterm::style::SetAttributes(shown.attrs.into())
+                    )?;
+                    attrs = shown.attrs;
+                }
+                queue!(
+                    self.out,
+                    crossterm::style::SetForegroundColor(shown.fg),
+                    crossterm::style::SetBackgroundColor(shown.bg),
+                )?;
+                // OSC 8; the id lets terminals highlight all cells of a link together
+                let link = shown.link.and_then(|link| {
+                    let uri = self.links.get(link.0.get() as usize - 1)?;
+                    Some((link, uri))
+                });
+                match link {
+                    Some((link, uri)) => queue!(
+                        self.out,
+                        crossterm::style::Print(format_args!(
+                            "\x1b]8;id=coil{};{}\x1b\\{}\x1b]8;;\x1b\\",
+                            link.0, uri, shown.ch
+                        ))
+                    ),
+                    None => queue!(self.out, crossterm::style::Print(shown.ch)),
+                }?;
+            }
+            self.front_buffer[i] = *back_cell; // Update front buffer
+        }
+        self.previous.copy_from_slice(&self.back_buffer);
+        if !attrs.is_empty() {
+            queue!(
+                self.out,
+                crossterm::style::SetAttribute(crossterm::style::Attribute::Reset)
+            )?;
         }
-        out.flush()
-            .map_err(|e| EngineError::Render(e.to_string()))?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Returns how a cell that changed is shown while redraws are shown.
+fn highlight(cell: Cell) -> Cell {
+    let attrs = if cell.attrs.contains(Attributes::REVERSE) {
+        cell.attrs.difference(Attributes::REVERSE)
+    } else {
+        cell.attrs | Attributes::REVERSE
+    };
+    Cell { attrs, ..cell }
+}
+
+/// A renderer that only draws into memory, for testing what nodes draw
+/// without a terminal.
+///
+/// It draws exactly like [`BasicRenderer`], and flushing writes nothing:
+///
+/// ```
+/// use coil_engine::renderer::{Renderer, TestRenderer};
+/// use coil_engine::style::Style;
+///
+/// let mut renderer = TestRenderer::new(8, 2);
+/// renderer.draw_styled(1, 1, "hi", Style::new().bold())?;
+/// assert_eq!(renderer.cell_at(1, 1).map(|cell| cell.ch), Some('h'));
+/// assert_eq!(renderer.to_string(), "\n hi\n");
+/// # Ok::<(), coil_engine::EngineError>(())
+/// ```
+pub struct TestRenderer {
+    inner: BasicRenderer,
+}
+
+impl TestRenderer {
+    pub fn new(width: u16, height: u16) -> Self {
+        let inner = BasicRenderer::with_output(width, height, sink())
+            .expect("creating an in-memory renderer cannot fail");
+        Self { inner }
+    }
+
+    pub fn size(&self) -> (u16, u16) {
+        self.inner.size()
+    }
+
+    /// Clears the screen and draws `node` on all of it.
+    pub fn render(&mut self, node: &dyn Node) -> Result<(), EngineError> {
+        let (width, height) = self.size();
+        self.inner.clear()?;
+        node.render(&mut Frame::new(self, Rect::new(0, 0, width, height), 1.0))
+    }
+
+    /// Returns the cell drawn at (x,y), or `None` outside the screen.
+    pub fn cell_at(&self, x: u16, y: u16) -> Option<Cell> {
+        self.inner.cell(x, y).ok()
+    }
+
+    /// Returns the characters of row `y` with trailing spaces trimmed.
+    pub fn row(&self, y: u16) -> String {
+        let (width, _) = self.size();
+        let row: String = (0..width)
+            .filter_map(|x| self.cell_at(x, y))
+            .filter(|cell| cell.ch != Cell::WIDE_TAIL)
+            .map(|cell| cell.ch)
+            .collect();
+        row.trim_end().to_string()
+    }
+
+    /// Returns every row as [`TestRenderer::row`] does.
+    pub fn rows(&self) -> Vec<String> {
+        (0..self.size().1).map(|y| self.row(y)).collect()
+    }
+
+    /// Returns and clears the draws clipped at the edges so far.
+    pub fn take_errors(&mut self) -> Vec<EngineError> {
+        self.inner.take_errors()
+    }
+}
+
+impl fmt::Display for TestRenderer {
+    /// Writes the screen as text, one trimmed line per row.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.inner.to_text())
+    }
+}
+
+impl Renderer for TestRenderer {
+    fn clear(&mut self) -> Result<(), EngineError> {
+        self.inner.clear()
+    }
+
+    fn draw_cell(&mut self, x: u16, y: u16, cell: Cell) -> Result<(), EngineError> {
+        self.inner.draw_cell(x, y, cell)
+    }
+
+    fn draw_styled(&mut self, x: u16, y: u16, text: &str, style: Style) -> Result<(), EngineError> {
+        self.inner.draw_styled(x, y, text, style)
+    }
+
+    fn set_link(&mut self, uri: Option<&str>) {
+        self.inner.set_link(uri);
+    }
+
+    fn flush(&mut self) -> Result<(), EngineError> {
         Ok(())
     }
 }
 
-impl Drop for BasicRenderer {
-    fn drop(&mut self) {
-        // Leave alternate screen and show cursor
-        let _ = execute!(
-            stdout(),
-            LeaveAlternateScreen,
-            DisableMouseCapture,
-            cursor::Show
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_text() {
+        let mut renderer = BasicRenderer::frame_dump(6, 2).unwrap();
+        renderer
+            .draw_styled(1, 0, "日x", Style::new().fg(Color::Red))
+            .unwrap();
+        renderer.draw_cell(0, 1, Cell::new('┼')).unwrap();
+        assert_eq!(renderer.to_text(), " 日x\n┼\n");
+
+        renderer.set_filters(vec![crate::glyphs::ascii]);
+        assert_eq!(renderer.to_text(), " 日x\n+\n");
+    }
+
+    #[test]
+    fn test_test_renderer_draws_nodes() {
+        struct Banner;
+        impl Node for Banner {
+            fn update(&mut self, _: &mut crate::Context, _: f32) -> Result<(), EngineError> {
+                Ok(())
+            }
+            fn on_event(
+                &mut self,
+                _: &mut crate::Context,
+                _: &crossterm::event::Event,
+            ) -> Result<bool, EngineError> {
+                Ok(false)
+            }
+            fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+                frame.draw_styled(0, 0, "日本", crate::style!(red))?;
+                frame.draw_cell(2, 1, Cell::new('x'))?;
+                Ok(())
+            }
+        }
+
+        let mut renderer = TestRenderer::new(5, 2);
+        renderer.draw_styled(0, 1, "old", Style::new()).unwrap();
+        renderer.render(&Banner).unwrap();
+        assert_eq!(renderer.rows(), ["日本", "  x"]);
+        assert_eq!(renderer.to_string(), "日本\n  x\n");
+        assert_eq!(renderer.cell_at(0, 0).unwrap().fg, Color::Red);
+        assert_eq!(renderer.cell_at(5, 0), None);
+        assert!(renderer.take_errors().is_empty());
+
+        renderer.draw_styled(4, 0, "cut", Style::new()).unwrap();
+        assert_eq!(renderer.take_errors().len(), 1);
+        assert!(renderer.flush().is_ok());
+    }
+
+    #[test]
+    fn test_resize_keeps_overlapping_cells() {
+        let mut renderer = BasicRenderer::frame_dump(4, 2).unwrap();
+        let rows = |renderer: &BasicRenderer| -> Vec<String> {
+            let (width, height) = renderer.size();
+            (0..height)
+                .map(|y| {
+                    (0..width)
+                        .map(|x| renderer.cell(x, y).unwrap().ch)
+                        .collect()
+                })
+                .collect()
+        };
+        renderer.draw_styled(0, 0, "ab日", Style::new()).unwrap();
+        renderer.draw_styled(0, 1, "cdef", Style::new()).unwrap();
+
+        renderer.set_viewport(Rect::new(0, 0, 3, 3)).unwrap();
+        assert_eq!(rows(&renderer), ["ab ", "cde", "   "]);
+        renderer.set_viewport(Rect::new(0, 0, 5, 2)).unwrap();
+        assert_eq!(rows(&renderer), ["ab   ", "cde  "]);
+        renderer.set_viewport(Rect::new(0, 0, 2, 1)).unwrap();
+        assert_eq!(rows(&renderer), ["ab"]);
+    }
+
+    /// Output kept by the test after the renderer takes it.
+    #[derive(Clone, Default)]
+    struct Shared(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_links_are_encoded_as_osc8() {
+        let out = Shared::default();
+        let mut renderer = BasicRenderer::with_output(4, 1, out.clone()).unwrap();
+        {
+            let mut frame = crate::frame::Frame::new(&mut renderer, Rect::new(0, 0, 4, 1), 0.0);
+            frame
+                .draw_link(0, 0, "ab", Style::new(), "https://example.com")
+                .unwrap();
+            frame.draw_styled(2, 0, "c", Style::new()).unwrap();
+        }
+        let link = renderer.cell(0, 0).unwrap().link.unwrap();
+        assert_eq!(renderer.cell(1, 0).unwrap().link, Some(link));
+        assert_eq!(renderer.cell(2, 0).unwrap().link, None);
+        assert_eq!(renderer.link_uri(link), Some("https://example.com"));
+
+        renderer.flush().unwrap();
+        let written = String::from_utf8(out.0.borrow().clone()).unwrap();
+        assert_eq!(
+            written
+                .matches("\x1b]8;id=coil1;https://example.com\x1b\\")
+                .count(),
+            2
         );
+        assert!(written.contains("c"));
+        assert_eq!(renderer.take_bytes_written(), written.len() as u64);
+        assert_eq!(renderer.take_bytes_written(), 0);
+    }
+
+    #[test]
+    fn test_attributes_are_flushed() {
+        let out = Shared::default();
+        let mut renderer = BasicRenderer::with_output(4, 1, out.clone()).unwrap();
+        let bold = Style::new().bold();
+        renderer.draw_styled(0, 0, "ab", bold).unwrap();
+        renderer.draw_styled(2, 0, "c", Style::new()).unwrap();
+        assert_eq!(renderer.cell(1, 0).unwrap().attrs, Attributes::BOLD);
+        assert_eq!(renderer.cell(2, 0).unwrap().style(), Style::new());
+
+        // Bold is set once for both cells and reset before the plain one
+        renderer.flush().unwrap();
+        let written = String::from_utf8(out.0.borrow().clone()).unwrap();
+        assert_eq!(written.matches("\x1b[1m").count(), 1);
+        assert_eq!(written.matches("\x1b[0m").count(), 1);
+        assert!(written.find("\x1b[0m") < written.find('c'));
+
+        // Frames without attributes never touch them
+        out.0.borrow_mut().clear();
+        renderer.draw_styled(0, 0, "ab", Style::new()).unwrap();
+        renderer.flush().unwrap();
+        let written = String::from_utf8(out.0.borrow().clone()).unwrap();
+        assert!(!written.contains("\x1b[0m") && !written.contains("\x1b[1m"));
+    }
+
+    #[test]
+    fn test_overwriting_part_of_a_wide_glyph_blanks_the_rest() {
+        let mut renderer = BasicRenderer::new(4, 1).unwrap();
+        let chars = |renderer: &BasicRenderer| -> Vec<char> {
+            (0..4).map(|x| renderer.cell(x, 0).unwrap().ch).collect()
+        };
+        renderer.draw_cell(0, 0, Cell::new('中')).unwrap();
+        assert_eq!(chars(&renderer), ['中', Cell::WIDE_TAIL, ' ', ' ']);
+
+        // Over the head, the tail is blanked
+        renderer.draw_styled(0, 0, "a", Style::new()).unwrap();
+        assert_eq!(chars(&renderer), ['a', ' ', ' ', ' ']);
+        // Over the tail, the head is blanked
+        renderer.draw_styled(1, 0, "中", Style::new()).unwrap();
+        renderer.draw_cell(2, 0, Cell::new('b')).unwrap();
+        assert_eq!(chars(&renderer), ['a', ' ', 'b', ' ']);
+        // Over the tail of one glyph and the head of the next
+        renderer.draw_styled(0, 0, "中中", Style::new()).unwrap();
+        renderer.draw_styled(1, 0, "文", Style::new()).unwrap();
+        assert_eq!(chars(&renderer), [' ', '文', Cell::WIDE_TAIL, ' ']);
+
+        // A wide glyph that does not fit is clipped whole
+        renderer.take_errors();
+        renderer.draw_cell(3, 0, Cell::new('中')).unwrap();
+        assert_eq!(renderer.take_errors().len(), 1);
+        assert_eq!(renderer.cell(3, 0).unwrap().ch, ' ');
+    }
+
+    #[test]
+    fn test_show_redraws_flashes_changed_cells() {
+        let out = Shared::default();
+        let mut renderer = BasicRenderer::with_output(4, 1, out.clone()).unwrap();
+        renderer.set_show_redraws(true);
+        let flush = |renderer: &mut BasicRenderer, text: &str| {
+            out.0.borrow_mut().clear();
+            renderer.draw_styled(0, 0, text, Style::new()).unwrap();
+            renderer.flush().unwrap();
+            String::from_utf8(out.0.borrow().clone()).unwrap()
+        };
+
+        let written = flush(&mut renderer, "ab");
+        assert_eq!(written.matches("\x1b[7m").count(), 1);
+        assert!(written.find("\x1b[7m") < written.find('a'));
+        assert_eq!(renderer.take_changed_cells(), 2);
+        assert_eq!(renderer.cell(0, 0).unwrap().attrs, Attributes::empty());
+
+        // The flash lasts one frame, then only what changes flashes
+        let written = flush(&mut renderer, "ac");
+        assert_eq!(renderer.take_changed_cells(), 1);
+        assert_eq!(written.matches("\x1b[7m").count(), 1);
+        assert!(written.find('a') < written.find("\x1b[7m"));
+        let written = flush(&mut renderer, "ac");
+        assert_eq!(renderer.take_changed_cells(), 0);
+        assert!(!written.contains('a') && !written.contains("\x1b[7m"));
     }
 }
@@ -0,0 +1,241 @@
+//! Recorded key input for playing a run again.
+//!
+//! A [`Replay`] holds the keys pressed during a run, by fixed-update tick.
+//! Feeding the same keys at the same ticks to a fresh instance of a
+//! deterministic game plays the run back, e.g. as a demo behind the title
+//! menu with [`Attract`](crate::nodes::Attract). Record one by wrapping the
+//! game in a [`Recorder`].
+//!
+//! Replays are stored as text, one key per line after the tick it was
+//! pressed on, using the key names of [`actions`](crate::actions):
+//!
+//! ```text
+//! # Demo of the first level
+//! 12 Right
+//! 40 Space
+//! 300 end
+//! ```
+//!
+//! The optional `end` line sets the length of the replay; without it the
+//! replay ends on the tick of its last key. Bundle a replay with the game
+//! with `Replay::parse(include_str!("demo.replay"))`.
+use crate::actions::KeyBinding;
+use crate::autosave::SaveData;
+use crate::config::ErrorPolicy;
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::nodes::Node;
+use crossterm::event::{Event, KeyEvent, KeyEventKind};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Keys pressed during a run, by tick.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Replay {
+    /// Keys in the order they were pressed, so ticks never decrease
+    inputs: Vec<(u64, KeyBinding)>,
+    length: u64,
+}
+
+impl Replay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a key pressed on `tick`, extending the replay if needed.
+    ///
+    /// Keys must be added in order; one before the last key is moved to
+    /// the last key's tick.
+    pub fn push(&mut self, tick: u64, key: impl Into<KeyBinding>) {
+        let tick = tick.max(self.inputs.last().map_or(0, |&(last, _)| last));
+        self.inputs.push((tick, key.into()));
+        self.length = self.length.max(tick);
+    }
+
+    /// Sets the number of ticks the replay lasts; it is never shorter than
+    /// the tick of its last key.
+    pub fn set_length(&mut self, ticks: u64) {
+        self.length = ticks.max(self.inputs.last().map_or(0, |&(tick, _)| tick));
+    }
+
+    /// Returns the number of ticks the replay lasts.
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// Returns the keys pressed on `tick`, in order.
+    pub fn keys_at(&self, tick: u64) -> impl Iterator<Item = KeyBinding> + '_ {
+        let start = self.inputs.partition_point(|&(pressed, _)| pressed < tick);
+        self.inputs[start..]
+            .iter()
+            .take_while(move |&&(pressed, _)| pressed == tick)
+            .map(|&(_, key)| key)
+    }
+
+    /// Returns every key with the tick it was pressed on, in order.
+    pub fn inputs(&self) -> impl Iterator<Item = (u64, KeyBinding)> + '_ {
+        self.inputs.iter().copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// Parses a replay; blank lines and lines starting with `#` are skipped.
+    pub fn parse(contents: &str) -> Result<Self, EngineError> {
+        let mut replay = Self::new();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid =
+                || EngineError::Config(format!("Invalid replay line {}: {:?}", number + 1, line));
+            let (tick, key) = line.split_once(' ').ok_or_else(invalid)?;
+            let tick: u64 = tick.parse().map_err(|_| invalid())?;
+            match key.trim() {
+                "end" => replay.set_length(tick),
+                key => replay.push(tick, key.parse::<KeyBinding>()?),
+            }
+        }
+        Ok(replay)
+    }
+
+    /// Loads a replay file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, EngineError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Writes the replay to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), EngineError> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (tick, key) in &self.inputs {
+            writeln!(f, "{} {}", tick, key)?;
+        }
+        writeln!(f, "{} end", self.length)
+    }
+}
+
+/// Wraps a node and records the keys it is sent into a [`Replay`].
+///
+/// Keys are stamped with the number of updates the node has had, so
+/// playing them back before the update of the same tick repeats the run.
+pub struct Recorder<N> {
+    node: N,
+    replay: Replay,
+    tick: u64,
+}
+
+impl<N: Node> Recorder<N> {
+    pub fn new(node: N) -> Self {
+        Self {
+            node,
+            replay: Replay::new(),
+            tick: 0,
+        }
+    }
+
+    pub fn node(&self) -> &N {
+        &self.node
+    }
+
+    pub fn node_mut(&mut self) -> &mut N {
+        &mut self.node
+    }
+
+    /// Returns what was recorded so far, lasting until the current tick.
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+}
+
+impl<N: Node> Node for Recorder<N> {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        self.node.update(ctx, dt)?;
+        self.tick += 1;
+        self.replay.set_length(self.tick);
+        Ok(())
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        if let Event::Key(key) = ev
+            && key.kind != KeyEventKind::Release
+        {
+            self.replay
+                .push(self.tick, KeyBinding::new(key.code, key.modifiers));
+        }
+        self.node.on_event(ctx, ev)
+    }
+
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        self.node.on_engine_event(ctx, ev);
+    }
+
+    fn on_pause(&mut self, ctx: &mut Context) {
+        self.node.on_pause(ctx);
+    }
+
+    fn on_resume(&mut self, ctx: &mut Context) {
+        self.node.on_resume(ctx);
+    }
+
+    fn on_resize(&mut self, ctx: &mut Context, width: u16, height: u16) {
+        self.node.on_resize(ctx, width, height);
+    }
+
+    fn on_save(&self, save: &mut SaveData) -> Result<(), EngineError> {
+        self.node.on_save(save)
+    }
+
+    fn on_error(&mut self, ctx: &mut Context, err: &EngineError) -> Option<ErrorPolicy> {
+        self.node.on_error(ctx, err)
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.node.is_dirty()
+    }
+
+    fn mark_clean(&mut self) {
+        self.node.mark_clean();
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        self.node.render(frame)
+    }
+}
+
+/// Returns the key event a replay sends for `key`.
+pub(crate) fn key_event(key: KeyBinding) -> Event {
+    Event::Key(KeyEvent::new(key.code, key.modifiers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    #[test]
+    fn test_parse_roundtrip() {
+        let replay = Replay::parse("# demo\n3 Right\n3 Ctrl+s\n\n10 q\n20 end\n").unwrap();
+        assert_eq!(replay.length(), 20);
+        let keys: Vec<_> = replay.keys_at(3).collect();
+        assert_eq!(
+            keys,
+            [
+                KeyBinding::from(KeyCode::Right),
+                KeyBinding::new(KeyCode::Char('s'), KeyModifiers::CONTROL)
+            ]
+        );
+        assert_eq!(replay.keys_at(4).count(), 0);
+        assert_eq!(Replay::parse(&replay.to_string()).unwrap(), replay);
+        assert!(Replay::parse("x Right").is_err());
+    }
+}
@@ -0,0 +1,245 @@
+//! Health, mana or stamina bars that follow an entity.
+//!
+//! A [`ResourceBar`] is owned by an entity and drawn just above or below
+//! it. When the value drops, the bar empties smoothly and the lost part
+//! lingers in a trail color for a moment, so big hits read at a glance.
+//! Thresholds change the bar's color as it empties, e.g. from green to red.
+//!
+//! Draw bars from a node in the world of [`Layers`](crate::nodes::Layers)
+//! at the entity's world position; the layer applies the camera.
+//!
+//! ```
+//! use coil_engine::resource_bar::ResourceBar;
+//!
+//! let mut health = ResourceBar::new(100.0, 6);
+//! health.set(40.0);
+//! health.update(0.1);
+//! assert_eq!(health.value(), 40.0);
+//! assert!(health.shown(1.0) > 40.0);
+//! ```
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::renderer::Cell;
+use crate::style::Style;
+
+/// Partially filled cells, by eighths.
+const EIGHTHS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Where a bar is drawn relative to its entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BarPlacement {
+    /// On the row above the entity.
+    #[default]
+    Above,
+    /// On the row below the entity.
+    Below,
+}
+
+/// A bar showing how much of a resource is left.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceBar {
+    value: f32,
+    max: f32,
+    /// Value the bar shows before and after the last update
+    shown: (f32, f32),
+    /// How much of the maximum the shown value catches up by per second
+    speed: f32,
+    width: u16,
+    placement: BarPlacement,
+    /// Styles by the fraction they apply at or below, highest first
+    thresholds: Vec<(f32, Style)>,
+    style: Style,
+    trail: Style,
+    empty: Style,
+}
+
+impl ResourceBar {
+    /// Creates a full bar `width` cells wide.
+    pub fn new(max: f32, width: u16) -> Self {
+        let max = max.max(f32::EPSILON);
+        Self {
+            value: max,
+            max,
+            shown: (max, max),
+            speed: 1.0,
+            width,
+            placement: BarPlacement::Above,
+            thresholds: vec![(0.5, crate::style!(yellow)), (0.25, crate::style!(red))],
+            style: crate::style!(green),
+            trail: crate::style!(white),
+            empty: crate::style!(dark_grey),
+        }
+    }
+
+    pub fn with_placement(mut self, placement: BarPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Sets the style of the filled part above every threshold.
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Sets the styles of the trail left when the value drops and of the empty part.
+    pub fn with_trail(mut self, trail: Style, empty: Style) -> Self {
+        self.trail = trail;
+        self.empty = empty;
+        self
+    }
+
+    /// Replaces the thresholds: the filled part takes the style of the lowest
+    /// threshold the fraction left is at or below.
+    pub fn with_thresholds(mut self, mut thresholds: Vec<(f32, Style)>) -> Self {
+        thresholds.sort_by(|a, b| b.0.total_cmp(&a.0));
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// Sets how fast the bar empties, as a fraction of the maximum per
+    /// second; `f32::INFINITY` shows changes at once.
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed.max(0.0);
+        self
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+
+    /// Returns the fraction of the maximum left, from 0.0 to 1.0.
+    pub fn fraction(&self) -> f32 {
+        self.value / self.max
+    }
+
+    /// Sets the value, clamped to the maximum. Gains show at once, losses drain.
+    pub fn set(&mut self, value: f32) {
+        self.value = value.clamp(0.0, self.max);
+        if self.value > self.shown.1 {
+            self.shown = (self.value, self.value);
+        }
+    }
+
+    /// Changes the maximum, keeping the value within it.
+    pub fn set_max(&mut self, max: f32) {
+        self.max = max.max(f32::EPSILON);
+        self.set(self.value);
+        self.shown.0 = self.shown.0.min(self.max);
+        self.shown.1 = self.shown.1.min(self.max);
+    }
+
+    /// Drains the shown value towards the value; call it from `update`.
+    pub fn update(&mut self, dt: f32) {
+        let step = self.speed * self.max * dt;
+        self.shown = (self.shown.1, (self.shown.1 - step).max(self.value));
+    }
+
+    /// Returns the value the bar shows at the frame's interpolation alpha.
+    pub fn shown(&self, alpha: f32) -> f32 {
+        let (previous, current) = self.shown;
+        previous + (current - previous) * alpha.clamp(0.0, 1.0)
+    }
+
+    /// Returns the style of the filled part for the current value.
+    pub fn fill_style(&self) -> Style {
+        let fraction = self.fraction();
+        self.thresholds
+            .iter()
+            .rev()
+            .find(|(threshold, _)| fraction <= *threshold)
+            .map_or(self.style, |(_, style)| *style)
+    }
+
+    /// Draws the bar centered over or under an entity at (x,y).
+    pub fn draw(&self, frame: &mut Frame<'_>, x: f32, y: f32) -> Result<(), EngineError> {
+        let row = match self.placement {
+            BarPlacement::Above => y.round() - 1.0,
+            BarPlacement::Below => y.round() + 1.0,
+        };
+        let left = (x - (self.width as f32 - 1.0) / 2.0).round();
+        let width = self.width as f32;
+        // Fill in eighths of a cell
+        let filled = (self.value / self.max * width * 8.0).round() as i64;
+        let trail = (self.shown(frame.alpha()) / self.max * width * 8.0).round() as i64;
+        let fill = self.fill_style();
+        for column in 0..self.width as i64 {
+            let (Ok(cx), Ok(cy)) = (
+                u16::try_from(left as i64 + column),
+                u16::try_from(row as i64),
+            ) else {
+                continue;
+            };
+            let eighths = (filled - column * 8).clamp(0, 8);
+            let trailing = (trail - column * 8).clamp(0, 8);
+            let behind = if trailing > eighths {
+                self.trail
+            } else {
+                self.empty
+            };
+            let cell = match eighths {
+                8 => Cell::styled('█', fill),
+                // A partial cell shows the fill over what lies behind it
+                1..=7 => Cell::styled(EIGHTHS[eighths as usize - 1], fill.bg(behind.fg)),
+                _ => Cell::styled('█', behind),
+            };
+            frame.draw_cell(cx, cy, cell)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Rect;
+    use crate::renderer::TestRenderer;
+
+    #[test]
+    fn test_bar_drains_and_changes_color() {
+        let mut bar = ResourceBar::new(10.0, 4).with_speed(0.5);
+        bar.set(5.5);
+        assert_eq!(bar.fill_style(), crate::style!(green));
+        bar.set(2.0);
+        assert_eq!(bar.fill_style(), crate::style!(red));
+
+        bar.update(1.0);
+        assert_eq!(bar.shown(1.0), 5.0);
+        assert_eq!(bar.shown(0.5), 7.5);
+        bar.update(1.0);
+        assert_eq!(bar.shown(1.0), 2.0);
+        bar.set(20.0);
+        assert_eq!((bar.value(), bar.shown(0.0)), (10.0, 10.0));
+    }
+
+    #[test]
+    fn test_draw_above_and_below() {
+        let mut bar = ResourceBar::new(4.0, 4).with_speed(0.25);
+        bar.set(1.5);
+        bar.update(1.0);
+        let mut renderer = TestRenderer::new(6, 5);
+        {
+            let mut frame = Frame::new(&mut renderer, Rect::new(0, 0, 6, 5), 1.0);
+            bar.draw(&mut frame, 2.5, 1.0).unwrap();
+            bar.clone()
+                .with_placement(BarPlacement::Below)
+                .draw(&mut frame, 2.5, 3.0)
+                .unwrap();
+        }
+        assert_eq!(renderer.row(0), " █▌██");
+        assert_eq!(renderer.row(4), " █▌██");
+        // The drained part trails behind what is left
+        let yellow = crate::style!(yellow);
+        assert_eq!(renderer.cell_at(1, 0).unwrap().fg, yellow.fg);
+        assert_eq!(renderer.cell_at(2, 0).unwrap().bg, crate::style!(white).fg);
+        assert_eq!(renderer.cell_at(3, 0).unwrap().fg, crate::style!(white).fg);
+        assert_eq!(
+            renderer.cell_at(4, 0).unwrap().fg,
+            crate::style!(dark_grey).fg
+        );
+    }
+}
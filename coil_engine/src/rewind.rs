@@ -0,0 +1,113 @@
+//! Rewinding game state.
+//!
+//! A [`Rewind`] recorder keeps a ring buffer of state snapshots taken every
+//! few ticks. Games call [`Rewind::record`] from `update` and
+//! [`Rewind::rewind`] to go back in time, e.g. while a rewind key is held.
+//! Snapshots are clones of the state; games with large states can record a
+//! compact form instead, such as a serialized `Vec<u8>`.
+use std::collections::VecDeque;
+
+/// A ring buffer of snapshots of a `T`, indexed by tick.
+#[derive(Debug, Clone)]
+pub struct Rewind<T> {
+    snapshots: VecDeque<(u64, T)>,
+    capacity: usize,
+    interval: u64,
+    tick: u64,
+}
+
+impl<T: Clone> Rewind<T> {
+    /// Keeps up to `capacity` snapshots, one every `interval` ticks, so the
+    /// game can rewind about `capacity * interval` ticks.
+    pub fn new(capacity: usize, interval: u64) -> Self {
+        Self {
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            interval: interval.max(1),
+            tick: 0,
+        }
+    }
+
+    /// Advances one tick, snapshotting `state` when the interval has elapsed.
+    pub fn record(&mut self, state: &T) {
+        if self.tick.is_multiple_of(self.interval) {
+            if self.snapshots.len() == self.capacity {
+                self.snapshots.pop_front();
+            }
+            self.snapshots.push_back((self.tick, state.clone()));
+        }
+        self.tick += 1;
+    }
+
+    /// Returns the number of ticks recorded so far.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Returns the range of ticks that can still be rewound to.
+    pub fn available(&self) -> Option<(u64, u64)> {
+        Some((self.snapshots.front()?.0, self.snapshots.back()?.0))
+    }
+
+    /// Returns the latest snapshot taken at or before `tick`, without rewinding.
+    pub fn snapshot_at(&self, tick: u64) -> Option<(u64, &T)> {
+        self.snapshots
+            .iter()
+            .rev()
+            .find(|(taken, _)| *taken <= tick)
+            .map(|(taken, state)| (*taken, state))
+    }
+
+    /// Goes back `ticks` ticks and returns the state to resume from.
+    ///
+    /// The state is the latest snapshot at or before the target tick, or the
+    /// oldest one if the target is no longer recorded. Snapshots after it are
+    /// discarded and recording continues from its tick.
+    pub fn rewind(&mut self, ticks: u64) -> Option<T> {
+        let target = self.tick.saturating_sub(ticks);
+        let keep = match self
+            .snapshots
+            .iter()
+            .rposition(|(taken, _)| *taken <= target)
+        {
+            Some(index) => index + 1,
+            None => 1.min(self.snapshots.len()),
+        };
+        self.snapshots.truncate(keep);
+        let (taken, state) = self.snapshots.pop_back()?;
+        // The restored state is snapshotted again by the next record
+        self.tick = taken;
+        Some(state)
+    }
+
+    /// Forgets every snapshot and restarts counting ticks.
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.tick = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewind_restores_earlier_snapshots() {
+        let mut rewind = Rewind::new(3, 2);
+        for position in 0..7 {
+            rewind.record(&position);
+        }
+        // Snapshots at ticks 2, 4 and 6; tick 0 fell out of the buffer
+        assert_eq!(rewind.available(), Some((2, 6)));
+        assert_eq!(rewind.snapshot_at(5), Some((4, &4)));
+
+        assert_eq!(rewind.rewind(2), Some(4));
+        assert_eq!(rewind.tick(), 4);
+        rewind.record(&40);
+        rewind.record(&41);
+        assert_eq!(rewind.available(), Some((2, 4)));
+
+        assert_eq!(rewind.rewind(100), Some(2));
+        assert_eq!(rewind.rewind(1), None);
+    }
+}
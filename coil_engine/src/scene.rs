@@ -0,0 +1,445 @@
+//! Pushdown scene management.
+//!
+//! Games move between screens: the title screen starts the game, the game
+//! opens a pause menu on top of itself, and quitting goes back to the title.
+//! A [`SceneStack`] holds these screens as [`Scene`]s, of which only the top
+//! one runs: it alone is updated and gets input, while the scenes below
+//! wait, paused, until it is popped.
+//!
+//! Scenes change the stack through the [`Context`], from any of their
+//! hooks; changes apply as soon as the hook returns:
+//!
+//! ```no_run
+//! use coil_engine::crossterm::event::{Event, KeyCode};
+//! use coil_engine::scene::{Scene, SceneStack};
+//! use coil_engine::{Context, EngineError, Frame, Game, Node, style};
+//!
+//! struct Title;
+//! struct Level;
+//!
+//! impl Node for Title {
+//!     fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+//!         Ok(())
+//!     }
+//!     fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+//!         if let Event::Key(key) = ev && key.code == KeyCode::Enter {
+//!             ctx.push_scene(Level);
+//!         }
+//!         Ok(true)
+//!     }
+//!     fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+//!         frame.draw_styled(0, 0, "Press Enter", style!(yellow))?;
+//!         Ok(())
+//!     }
+//! }
+//! impl Scene for Title {}
+//! # impl Node for Level {
+//! #     fn update(&mut self, _: &mut Context, _: f32) -> Result<(), EngineError> { Ok(()) }
+//! #     fn on_event(&mut self, _: &mut Context, _: &Event) -> Result<bool, EngineError> { Ok(false) }
+//! #     fn render(&self, _: &mut Frame<'_>) -> Result<(), EngineError> { Ok(()) }
+//! # }
+//! # impl Scene for Level {}
+//!
+//! Game::new(SceneStack::new(Title)).start();
+//! ```
+use crate::autosave::SaveData;
+use crate::config::ErrorPolicy;
+use crate::context::{Context, EngineEvent};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::nodes::Node;
+use crossterm::event::Event;
+use std::fmt;
+
+/// A screen of the game managed by a [`SceneStack`].
+///
+/// Besides the hooks below, a scene covered by another one gets
+/// [`Node::on_pause`] and stops being updated until it is uncovered, when
+/// it gets [`Node::on_resume`]; pausing the game pauses the top scene the
+/// same way, and each scene sees pauses and resumes in pairs.
+pub trait Scene: Node {
+    /// Called when the scene is pushed, before its first update.
+    fn on_enter(&mut self, _ctx: &mut Context) {}
+
+    /// Called when the scene is popped or replaced.
+    fn on_exit(&mut self, _ctx: &mut Context) {}
+
+    /// Returns whether the scene below stays drawn underneath this one, as
+    /// for a pause menu over the game.
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+/// A change to the scene stack requested through the [`Context`].
+pub(crate) enum SceneCommand {
+    Push(Box<dyn Scene>),
+    Pop,
+    Replace(Box<dyn Scene>),
+}
+
+impl fmt::Debug for SceneCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SceneCommand::Push(_) => "Push",
+            SceneCommand::Pop => "Pop",
+            SceneCommand::Replace(_) => "Replace",
+        })
+    }
+}
+
+/// A stack of scenes, of which the top one runs; use it as the game's root
+/// node.
+///
+/// Input goes to the top scene only, and engine events and saves to every
+/// scene. The top scene is drawn over the scenes below it that it keeps
+/// visible with [`Scene::is_overlay`]. Popping the last scene exits the
+/// game with code 0.
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+    /// Whether the first scene has been entered
+    started: bool,
+    /// Whether the game is paused, so the top scene already got `on_pause`
+    paused: bool,
+}
+
+impl SceneStack {
+    /// Creates a stack starting with `first`, which is entered on the first
+    /// update or event.
+    pub fn new(first: impl Scene + 'static) -> Self {
+        Self {
+            scenes: vec![Box::new(first)],
+            started: false,
+            paused: false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    /// Pushes `scene` on top of the current one.
+    pub fn push(&mut self, ctx: &mut Context, scene: impl Scene + 'static) {
+        self.start(ctx);
+        self.apply(ctx, SceneCommand::Push(Box::new(scene)));
+        self.apply_pending(ctx);
+    }
+
+    /// Pops the top scene, going back to the one below.
+    pub fn pop(&mut self, ctx: &mut Context) {
+        self.start(ctx);
+        self.apply(ctx, SceneCommand::Pop);
+        self.apply_pending(ctx);
+    }
+
+    fn start(&mut self, ctx: &mut Context) {
+        if !self.started {
+            self.started = true;
+            if let Some(first) = self.scenes.last_mut() {
+                first.on_enter(ctx);
+            }
+            self.apply_pending(ctx);
+        }
+    }
+
+    /// Applies the changes scenes requested, including those requested
+    /// while entering or exiting.
+    fn apply_pending(&mut self, ctx: &mut Context) {
+        loop {
+            let commands = ctx.take_scene_commands();
+            if commands.is_empty() {
+                break;
+            }
+            for command in commands {
+                self.apply(ctx, command);
+            }
+        }
+    }
+
+    fn apply(&mut self, ctx: &mut Context, command: SceneCommand) {
+        match command {
+            SceneCommand::Push(mut scene) => {
+                if let Some(top) = self.scenes.last_mut()
+                    && !self.paused
+                {
+                    top.on_pause(ctx);
+                }
+                scene.on_enter(ctx);
+                if self.paused {
+                    scene.on_pause(ctx);
+                }
+                self.scenes.push(scene);
+            }
+            SceneCommand::Pop => {
+                if let Some(mut top) = self.scenes.pop() {
+                    top.on_exit(ctx);
+                }
+                match self.scenes.last_mut() {
+                    Some(top) if !self.paused => top.on_resume(ctx),
+                    Some(_) => {}
+                    None => ctx.request_exit(0),
+                }
+            }
+            SceneCommand::Replace(mut scene) => {
+                if let Some(mut top) = self.scenes.pop() {
+                    top.on_exit(ctx);
+                }
+                scene.on_enter(ctx);
+                if self.paused {
+                    scene.on_pause(ctx);
+                }
+                self.scenes.push(scene);
+            }
+        }
+    }
+
+    /// Returns the index of the lowest scene that is drawn.
+    fn first_visible(&self) -> usize {
+        let mut first = self.scenes.len().saturating_sub(1);
+        while first > 0 && self.scenes[first].is_overlay() {
+            first -= 1;
+        }
+        first
+    }
+}
+
+impl Node for SceneStack {
+    fn update(&mut self, ctx: &mut Context, dt: f32) -> Result<(), EngineError> {
+        self.start(ctx);
+        let result = match self.scenes.last_mut() {
+            Some(top) => top.update(ctx, dt),
+            None => Ok(()),
+        };
+        self.apply_pending(ctx);
+        result
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+        self.start(ctx);
+        let result = match self.scenes.last_mut() {
+            Some(top) => top.on_event(ctx, ev),
+            None => Ok(false),
+        };
+        self.apply_pending(ctx);
+        result
+    }
+
+    fn update_interval(&self) -> u32 {
+        self.scenes.last().map_or(1, |top| top.update_interval())
+    }
+
+    fn on_engine_event(&mut self, ctx: &mut Context, ev: &EngineEvent) {
+        for scene in &mut self.scenes {
+            scene.on_engine_event(ctx, ev);
+        }
+        self.apply_pending(ctx);
+    }
+
+    fn on_pause(&mut self, ctx: &mut Context) {
+        self.paused = true;
+        if let Some(top) = self.scenes.last_mut() {
+            top.on_pause(ctx);
+        }
+    }
+
+    fn on_resume(&mut self, ctx: &mut Context) {
+        self.paused = false;
+        if let Some(top) = self.scenes.last_mut() {
+            top.on_resume(ctx);
+        }
+    }
+
+    fn on_resize(&mut self, ctx: &mut Context, width: u16, height: u16) {
+        // Covered scenes are laid out again too, ready for when they return
+        for scene in &mut self.scenes {
+            scene.on_resize(ctx, width, height);
+        }
+    }
+
+    fn on_save(&self, save: &mut SaveData) -> Result<(), EngineError> {
+        for scene in &self.scenes {
+            scene.on_save(save)?;
+        }
+        Ok(())
+    }
+
+    fn on_error(&mut self, ctx: &mut Context, err: &EngineError) -> Option<ErrorPolicy> {
+        self.scenes
+            .iter_mut()
+            .rev()
+            .find_map(|scene| scene.on_error(ctx, err))
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.scenes[self.first_visible()..]
+            .iter()
+            .any(|scene| scene.is_dirty())
+    }
+
+    fn mark_clean(&mut self) {
+        self.scenes.iter_mut().for_each(|scene| scene.mark_clean());
+    }
+
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        for scene in &self.scenes[self.first_visible()..] {
+            scene.render(frame)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    struct Named {
+        name: &'static str,
+        overlay: bool,
+        log: Log,
+    }
+
+    impl Named {
+        fn record(&self, hook: &str) {
+            self.log
+                .borrow_mut()
+                .push(format!("{} {}", self.name, hook));
+        }
+    }
+
+    impl Node for Named {
+        fn update(&mut self, _ctx: &mut Context, _dt: f32) -> Result<(), EngineError> {
+            self.record("update");
+            Ok(())
+        }
+
+        fn on_event(&mut self, ctx: &mut Context, ev: &Event) -> Result<bool, EngineError> {
+            let Event::Key(key) = ev else {
+                return Ok(false);
+            };
+            let log = self.log.clone();
+            match key.code {
+                KeyCode::Enter => ctx.push_scene(Named {
+                    name: "game",
+                    overlay: false,
+                    log,
+                }),
+                KeyCode::Char('p') => ctx.push_scene(Named {
+                    name: "pause",
+                    overlay: true,
+                    log,
+                }),
+                KeyCode::Char('q') => ctx.replace_scene(Named {
+                    name: "title",
+                    overlay: false,
+                    log,
+                }),
+                KeyCode::Esc => ctx.pop_scene(),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        }
+
+        fn on_pause(&mut self, _ctx: &mut Context) {
+            self.record("pause");
+        }
+
+        fn on_resume(&mut self, _ctx: &mut Context) {
+            self.record("resume");
+        }
+
+        fn on_resize(&mut self, _ctx: &mut Context, width: u16, height: u16) {
+            self.record(&format!("resize {}x{}", width, height));
+        }
+
+        fn render(&self, _frame: &mut Frame<'_>) -> Result<(), EngineError> {
+            self.record("render");
+            Ok(())
+        }
+    }
+
+    impl Scene for Named {
+        fn on_enter(&mut self, _ctx: &mut Context) {
+            self.record("enter");
+        }
+
+        fn on_exit(&mut self, _ctx: &mut Context) {
+            self.record("exit");
+        }
+
+        fn is_overlay(&self) -> bool {
+            self.overlay
+        }
+    }
+
+    #[test]
+    fn test_scenes_push_pop_and_overlay() {
+        let log = Log::default();
+        let mut stack = SceneStack::new(Named {
+            name: "title",
+            overlay: false,
+            log: log.clone(),
+        });
+        let mut ctx = Context::new(GameConfig::new());
+        let press = |stack: &mut SceneStack, ctx: &mut Context, code| {
+            let ev = Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+            stack.on_event(ctx, &ev).unwrap();
+        };
+        let mut renderer = crate::renderer::BasicRenderer::new(4, 2).unwrap();
+        let mut render = |stack: &SceneStack| {
+            let area = crate::geometry::Rect::new(0, 0, 4, 2);
+            stack
+                .render(&mut Frame::new(&mut renderer, area, 0.0))
+                .unwrap();
+        };
+        let take = || std::mem::take(&mut *log.borrow_mut());
+
+        press(&mut stack, &mut ctx, KeyCode::Enter);
+        press(&mut stack, &mut ctx, KeyCode::Char('p'));
+        stack.update(&mut ctx, 0.1).unwrap();
+        render(&stack);
+        assert_eq!(
+            take(),
+            [
+                "title enter",
+                "title pause",
+                "game enter",
+                "game pause",
+                "pause enter",
+                "pause update",
+                "game render",
+                "pause render",
+            ]
+        );
+
+        // Pausing the game only pauses the running scene
+        stack.on_pause(&mut ctx);
+        press(&mut stack, &mut ctx, KeyCode::Esc);
+        stack.on_resume(&mut ctx);
+        assert_eq!(take(), ["pause pause", "pause exit", "game resume"]);
+
+        // Covered scenes are resized too
+        stack.on_resize(&mut ctx, 100, 30);
+        assert_eq!(take(), ["title resize 100x30", "game resize 100x30"]);
+
+        press(&mut stack, &mut ctx, KeyCode::Char('q'));
+        press(&mut stack, &mut ctx, KeyCode::Esc);
+        assert_eq!(
+            take(),
+            ["game exit", "title enter", "title exit", "title resume"]
+        );
+        assert_eq!(stack.len(), 1);
+        press(&mut stack, &mut ctx, KeyCode::Esc);
+        assert!(stack.is_empty());
+        assert_eq!(ctx.exit_requested(), Some(0));
+    }
+}
@@ -0,0 +1,131 @@
+//! Characters for drawing boxes and borders.
+//!
+//! The shapes themselves are drawn through [`Frame`](crate::frame::Frame):
+//! [`draw_hline`](crate::frame::Frame::draw_hline),
+//! [`draw_vline`](crate::frame::Frame::draw_vline),
+//! [`draw_line`](crate::frame::Frame::draw_line),
+//! [`draw_rect`](crate::frame::Frame::draw_rect),
+//! [`fill_rect`](crate::frame::Frame::fill_rect) and
+//! [`draw_border`](crate::frame::Frame::draw_border), which takes one of the
+//! [`BorderSet`]s here. Box-drawing borders fall back to ASCII on terminals
+//! without Unicode support, like any other glyph.
+use crate::errors::EngineError;
+use std::str::FromStr;
+
+/// The characters of a rectangular border.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BorderSet {
+    pub top_left: char,
+    pub top_right: char,
+    pub bottom_left: char,
+    pub bottom_right: char,
+    pub horizontal: char,
+    pub vertical: char,
+}
+
+impl BorderSet {
+    /// `┌─┐` thin lines.
+    pub const SINGLE: BorderSet = BorderSet::new(['┌', '┐', '└', '┘', '─', '│']);
+    /// `╔═╗` double lines.
+    pub const DOUBLE: BorderSet = BorderSet::new(['╔', '╗', '╚', '╝', '═', '║']);
+    /// `╭─╮` thin lines with rounded corners.
+    pub const ROUNDED: BorderSet = BorderSet::new(['╭', '╮', '╰', '╯', '─', '│']);
+    /// `+-+` plain ASCII.
+    pub const ASCII: BorderSet = BorderSet::new(['+', '+', '+', '+', '-', '|']);
+
+    /// Creates a border from its top-left, top-right, bottom-left and
+    /// bottom-right corners, then its horizontal and vertical edges.
+    pub const fn new(chars: [char; 6]) -> Self {
+        let [
+            top_left,
+            top_right,
+            bottom_left,
+            bottom_right,
+            horizontal,
+            vertical,
+        ] = chars;
+        Self {
+            top_left,
+            top_right,
+            bottom_left,
+            bottom_right,
+            horizontal,
+            vertical,
+        }
+    }
+
+    /// Returns a border drawn entirely with `ch`.
+    pub const fn uniform(ch: char) -> Self {
+        Self::new([ch; 6])
+    }
+}
+
+impl Default for BorderSet {
+    fn default() -> Self {
+        Self::SINGLE
+    }
+}
+
+impl FromStr for BorderSet {
+    type Err = EngineError;
+
+    /// Parses `single`, `double`, `rounded` or `ascii`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "single" => Ok(Self::SINGLE),
+            "double" => Ok(Self::DOUBLE),
+            "rounded" => Ok(Self::ROUNDED),
+            "ascii" => Ok(Self::ASCII),
+            _ => Err(EngineError::Config(format!("Invalid border: {:?}", s))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::Frame;
+    use crate::geometry::Rect;
+    use crate::renderer::{Cell, TestRenderer};
+    use crate::style::Style;
+
+    fn draw(width: u16, height: u16, draw: impl FnOnce(&mut Frame<'_>)) -> Vec<String> {
+        let mut renderer = TestRenderer::new(width, height);
+        draw(&mut Frame::new(
+            &mut renderer,
+            Rect::new(0, 0, width, height),
+            1.0,
+        ));
+        renderer.rows()
+    }
+
+    #[test]
+    fn test_borders_and_fills() {
+        assert_eq!("Rounded".parse::<BorderSet>().unwrap(), BorderSet::ROUNDED);
+        assert!("wavy".parse::<BorderSet>().is_err());
+
+        let rows = draw(7, 4, |frame| {
+            frame
+                .draw_border(Rect::new(0, 0, 5, 4), BorderSet::DOUBLE, Style::new())
+                .unwrap();
+            frame
+                .fill_rect(Rect::new(1, 1, 3, 2), Cell::new('.'))
+                .unwrap();
+            // Cut off by the right edge of the frame
+            frame
+                .draw_rect(Rect::new(5, 1, 4, 3), Cell::new('#'))
+                .unwrap();
+        });
+        assert_eq!(rows, ["╔═══╗", "║...║##", "║...║#", "╚═══╝##"]);
+    }
+
+    #[test]
+    fn test_lines() {
+        let rows = draw(6, 4, |frame| {
+            frame.draw_hline(1, 0, 9, Cell::new('-')).unwrap();
+            frame.draw_vline(0, 1, 3, Cell::new('|')).unwrap();
+            frame.draw_line((1, 1), (5, 3), Cell::new('*')).unwrap();
+        });
+        assert_eq!(rows, [" -----", "|*", "| **", "|   **"]);
+    }
+}
@@ -0,0 +1,221 @@
+//! Read-only spectators watching a game from another terminal.
+//!
+//! When the `spectate` setting is set, the engine copies everything it
+//! writes to the terminal, i.e. the changes of each frame, to spectators:
+//!
+//! * an address such as `127.0.0.1:7878` is listened on, and any number of
+//!   spectators attach with e.g. `nc 127.0.0.1 7878`
+//! * anything else is the path of a FIFO (`mkfifo watch`), which one
+//!   spectator at a time attaches to with `cat watch`
+//!
+//! A spectator that attaches gets the whole screen on the next frame, then
+//! only the changes. Spectators cannot send input, and their terminal
+//! should be at least as large as the player's. One that reads too slowly
+//! is dropped rather than slowing the game down.
+use crate::errors::EngineError;
+use log::{debug, info, warn};
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::net::{SocketAddr, TcpListener};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TrySendError};
+use std::thread;
+
+/// Frames queued for a spectator before it counts as too slow
+const QUEUED_FRAMES: usize = 30;
+/// Clears the spectator's screen and hides its cursor
+const RESET: &[u8] = b"\x1b[2J\x1b[H\x1b[?25l";
+
+/// Where spectators attach.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpectateTarget {
+    /// Listen for TCP connections on an address.
+    Tcp(SocketAddr),
+    /// Write to a named pipe, reopening it whenever its reader goes away.
+    Fifo(PathBuf),
+}
+
+impl std::str::FromStr for SpectateTarget {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(EngineError::Config("Empty spectate target".to_string()));
+        }
+        Ok(match s.parse() {
+            Ok(address) => SpectateTarget::Tcp(address),
+            Err(_) => SpectateTarget::Fifo(PathBuf::from(s)),
+        })
+    }
+}
+
+impl fmt::Display for SpectateTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpectateTarget::Tcp(address) => write!(f, "{}", address),
+            SpectateTarget::Fifo(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// The spectators attached to the game, fed from the event loop.
+///
+/// Each spectator is written to by a thread of its own through a bounded
+/// queue, so a slow or stuck one never blocks a frame.
+pub(crate) struct Spectators {
+    /// Queues of spectators that attached since the last `accept`
+    joined: Option<Receiver<SyncSender<Vec<u8>>>>,
+    watching: Vec<SyncSender<Vec<u8>>>,
+}
+
+impl Spectators {
+    /// Starts waiting for spectators at `target`, or disables spectating when `None`.
+    pub fn open(target: Option<&SpectateTarget>) -> Result<Self, EngineError> {
+        let joined = match target {
+            None => None,
+            Some(SpectateTarget::Tcp(address)) => {
+                let listener = TcpListener::bind(address)?;
+                info!("Spectators can attach to {}", listener.local_addr()?);
+                let (sender, receiver) = mpsc::channel();
+                thread::spawn(move || {
+                    for stream in listener.incoming() {
+                        match stream {
+                            Ok(stream) => {
+                                let sender = sender.clone();
+                                thread::spawn(move || serve(stream, &sender));
+                            }
+                            Err(e) => warn!("Failed to accept a spectator: {}", e),
+                        }
+                    }
+                });
+                Some(receiver)
+            }
+            Some(SpectateTarget::Fifo(path)) => {
+                let path = path.clone();
+                info!("Spectators can attach to {}", path.display());
+                let (sender, receiver) = mpsc::channel();
+                // Opening a FIFO for writing waits for a reader
+                thread::spawn(move || {
+                    loop {
+                        let file = match OpenOptions::new().write(true).open(&path) {
+                            Ok(file) => file,
+                            Err(e) => {
+                                warn!("Failed to open {}: {}", path.display(), e);
+                                return;
+                            }
+                        };
+                        if !serve(file, &sender) {
+                            return;
+                        }
+                    }
+                });
+                Some(receiver)
+            }
+        };
+        Ok(Self {
+            joined,
+            watching: Vec::new(),
+        })
+    }
+
+    /// Returns whether spectating is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.joined.is_some()
+    }
+
+    /// Takes in the spectators that attached since the last call and
+    /// returns whether there were any, in which case the next frame has to
+    /// be drawn in full.
+    pub fn accept(&mut self) -> bool {
+        let Some(joined) = &self.joined else {
+            return false;
+        };
+        let mut any = false;
+        for spectator in joined.try_iter() {
+            if spectator.try_send(RESET.to_vec()).is_ok() {
+                debug!("Spectator attached");
+                self.watching.push(spectator);
+                any = true;
+            }
+        }
+        any
+    }
+
+    /// Sends what was written to the terminal to every spectator.
+    pub fn send(&mut self, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        self.watching
+            .retain(|spectator| match spectator.try_send(bytes.to_vec()) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    info!("Dropping a spectator that fell behind");
+                    false
+                }
+                Err(TrySendError::Disconnected(_)) => {
+                    debug!("Spectator detached");
+                    false
+                }
+            });
+    }
+}
+
+/// Registers a spectator writing to `out` and writes its frames until it
+/// goes away or is dropped.
+///
+/// Returns `false` if the game is no longer taking spectators.
+fn serve(mut out: impl Write, joined: &Sender<SyncSender<Vec<u8>>>) -> bool {
+    let (sender, frames) = mpsc::sync_channel::<Vec<u8>>(QUEUED_FRAMES);
+    if joined.send(sender).is_err() {
+        return false;
+    }
+    for bytes in frames {
+        if out.write_all(&bytes).and_then(|()| out.flush()).is_err() {
+            break;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpStream;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            "127.0.0.1:7878".parse::<SpectateTarget>().unwrap(),
+            SpectateTarget::Tcp(SocketAddr::from(([127, 0, 0, 1], 7878)))
+        );
+        let fifo: SpectateTarget = "/tmp/watch".parse().unwrap();
+        assert_eq!(fifo, SpectateTarget::Fifo(PathBuf::from("/tmp/watch")));
+        assert_eq!(fifo.to_string(), "/tmp/watch");
+    }
+
+    #[test]
+    fn test_tcp_spectator_receives_frames() {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let mut spectators = Spectators::open(Some(&SpectateTarget::Tcp(port))).unwrap();
+        let mut stream = TcpStream::connect(port).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !spectators.accept() {
+            assert!(Instant::now() < deadline, "spectator never attached");
+            thread::sleep(Duration::from_millis(5));
+        }
+        spectators.send(b"frame");
+
+        let mut received = vec![0; RESET.len() + 5];
+        stream.read_exact(&mut received).unwrap();
+        assert_eq!(&received[RESET.len()..], b"frame");
+    }
+}
@@ -0,0 +1,64 @@
+//! Plain-text output for players using a screen reader.
+//!
+//! When `speech_output` names a file, every [`Context::announce`](crate::context::Context::announce)
+//! and notification is appended to it as one line. The player follows that
+//! file with their screen reader (e.g. `tail -f` in a second terminal) while
+//! the game keeps drawing cells as usual.
+use crate::errors::EngineError;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// The stream announcements are written to, if any.
+pub(crate) struct SpeechOutput {
+    file: Option<File>,
+}
+
+impl SpeechOutput {
+    /// Opens `path` for appending, or disables output when `None`.
+    pub fn open(path: Option<&Path>) -> Result<Self, EngineError> {
+        let file = match path {
+            Some(path) => Some(OpenOptions::new().create(true).append(true).open(path)?),
+            None => None,
+        };
+        Ok(Self { file })
+    }
+
+    /// Writes each announcement on its own line.
+    pub fn write(&mut self, announcements: &[String]) -> Result<(), EngineError> {
+        let Some(file) = &mut self.file else {
+            return Ok(());
+        };
+        if announcements.is_empty() {
+            return Ok(());
+        }
+        for announcement in announcements {
+            // Keep one announcement per line whatever the message contains
+            writeln!(file, "{}", announcement.replace('\n', " "))?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_announcements_are_appended() {
+        let path = std::env::temp_dir().join(format!("coil-speech-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut speech = SpeechOutput::open(Some(&path)).unwrap();
+        speech.write(&["Settings".to_string()]).unwrap();
+        speech.write(&["Vsync < on >\nnow".to_string()]).unwrap();
+        SpeechOutput::open(None)
+            .unwrap()
+            .write(&["ignored".to_string()])
+            .unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(written, "Settings\nVsync < on > now\n");
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,263 @@
+//! Measurements of the frames the engine draws.
+//!
+//! The event loop records how many bytes each frame wrote to the terminal
+//! and how long writing them took in the context's [`FrameStats`]. On slow
+//! terminals, such as over SSH or on Windows consoles, flushing can take
+//! longer than updating and drawing together; the numbers show when that
+//! happens, and whether fewer changed cells, e.g. through
+//! [`Retained`](crate::nodes::Retained) nodes, would help.
+//!
+//! It also records how long each frame spent working, i.e. updating,
+//! drawing and flushing, leaving out the time spent waiting for input. With
+//! a `cpu_budget` configured, the engine degrades while the frames of the
+//! last half second took longer than that on average: it draws every other
+//! frame only, and nodes that check [`Context::is_degraded`](crate::context::Context::is_degraded)
+//! cut back on effects, e.g. [`Weather`](crate::nodes::Weather) stops
+//! spawning particles. It recovers once the average falls well below the
+//! budget, so games run on shared servers do not keep a core busy.
+//!
+//! With `debug_mode` on, the latest numbers and a histogram of flush times
+//! are drawn in the top-right corner of the screen. `show_redraws` draws them
+//! too, and flashes the cells that changed since the previous frame, to find
+//! nodes that redraw static content and make the screen flicker.
+use crate::charts::{BarChart, Series};
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use std::time::Duration;
+
+/// Number of frames the statistics look back on.
+pub const HISTORY: usize = 120;
+
+/// Width of a flush time histogram bucket.
+const BUCKET: Duration = Duration::from_millis(2);
+const BUCKETS: usize = 8;
+const OVERLAY_WIDTH: u16 = 2 * BUCKETS as u16 + 6;
+/// Frames averaged when comparing work times to the CPU budget
+const BUDGET_WINDOW: usize = 30;
+/// Fraction of the budget the average must fall below to recover
+const RECOVERY: f32 = 0.75;
+
+/// Statistics of the latest frames.
+#[derive(Debug, Clone)]
+pub struct FrameStats {
+    frames: u64,
+    /// Bytes written to the terminal by each frame
+    bytes_written: Series,
+    /// Cells that changed since the previous frame, for each frame
+    changed_cells: Series,
+    /// Time spent flushing each frame, in milliseconds
+    flush_times: Series,
+    /// Time spent updating, drawing and flushing each frame, in milliseconds
+    work_times: Series,
+    degraded: bool,
+    /// Frames recorded since the engine last degraded or recovered
+    settled: usize,
+    skipped_renders: u64,
+    frame_skips: u64,
+}
+
+impl FrameStats {
+    pub fn new() -> Self {
+        Self {
+            frames: 0,
+            bytes_written: Series::new(HISTORY),
+            changed_cells: Series::new(HISTORY),
+            flush_times: Series::new(HISTORY),
+            work_times: Series::new(HISTORY),
+            degraded: false,
+            settled: 0,
+            skipped_renders: 0,
+            frame_skips: 0,
+        }
+    }
+
+    /// Records a flushed frame.
+    pub(crate) fn record_flush(&mut self, bytes: u64, cells: u64, time: Duration) {
+        self.frames += 1;
+        self.bytes_written.push(bytes as f32);
+        self.changed_cells.push(cells as f32);
+        self.flush_times.push(time.as_secs_f32() * 1000.0);
+    }
+
+    /// Records the time a frame spent working and checks it against the
+    /// budget; returns whether the engine degraded or recovered.
+    pub(crate) fn record_work(&mut self, time: Duration, budget: Option<Duration>) -> Option<bool> {
+        self.work_times.push(time.as_secs_f32() * 1000.0);
+        self.settled += 1;
+        let Some(budget) = budget else {
+            return self.set_degraded(false);
+        };
+        if self.settled < BUDGET_WINDOW {
+            return None;
+        }
+        let average = self.work_times.latest(BUDGET_WINDOW).sum::<f32>() / BUDGET_WINDOW as f32;
+        let budget = budget.as_secs_f32() * 1000.0;
+        if average > budget {
+            self.set_degraded(true)
+        } else if average < budget * RECOVERY {
+            self.set_degraded(false)
+        } else {
+            None
+        }
+    }
+
+    fn set_degraded(&mut self, degraded: bool) -> Option<bool> {
+        if self.degraded == degraded {
+            return None;
+        }
+        self.degraded = degraded;
+        self.settled = 0;
+        Some(degraded)
+    }
+
+    /// Records a frame that was not drawn to save CPU.
+    pub(crate) fn record_skipped_render(&mut self) {
+        self.skipped_renders += 1;
+    }
+
+    /// Records a frame that was not drawn so updates could catch up.
+    pub(crate) fn record_frame_skip(&mut self) {
+        self.frame_skips += 1;
+    }
+
+    /// Returns the number of frames drawn so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Returns the bytes written to the terminal by each of the latest frames.
+    pub fn bytes_written(&self) -> &Series {
+        &self.bytes_written
+    }
+
+    /// Returns the number of cells that changed in each of the latest frames.
+    pub fn changed_cells(&self) -> &Series {
+        &self.changed_cells
+    }
+
+    /// Returns how long flushing each of the latest frames took, in milliseconds.
+    pub fn flush_times(&self) -> &Series {
+        &self.flush_times
+    }
+
+    /// Returns how long each of the latest frames spent updating, drawing
+    /// and flushing, in milliseconds.
+    pub fn work_times(&self) -> &Series {
+        &self.work_times
+    }
+
+    /// Returns whether the frames are over the CPU budget, so the engine
+    /// draws less often and effects should be cut back.
+    pub fn is_degraded(&self) -> bool {
+        self.degraded
+    }
+
+    /// Returns the number of frames not drawn to stay within the CPU budget.
+    pub fn skipped_renders(&self) -> u64 {
+        self.skipped_renders
+    }
+
+    /// Returns the number of frames not drawn so updates could catch up,
+    /// up to `max_frame_skip` in a row.
+    pub fn frame_skips(&self) -> u64 {
+        self.frame_skips
+    }
+
+    /// Counts the latest frames by flush time, in `buckets` buckets of
+    /// `width`; the last bucket also counts every slower frame.
+    pub fn flush_histogram(&self, width: Duration, buckets: usize) -> Vec<usize> {
+        let mut counts = vec![0; buckets];
+        let width = width.as_secs_f32() * 1000.0;
+        for ms in self.flush_times.iter() {
+            let bucket = (ms / width) as usize;
+            if let Some(count) = counts.get_mut(bucket.min(buckets.saturating_sub(1))) {
+                *count += 1;
+            }
+        }
+        counts
+    }
+
+    /// Draws the latest numbers and the flush time histogram in the
+    /// top-right corner of the frame.
+    pub(crate) fn draw_overlay(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        let (width, _) = frame.size();
+        let area = Rect::new(width.saturating_sub(OVERLAY_WIDTH), 0, OVERLAY_WIDTH, 6);
+        let style = crate::style!(white on black);
+        let mut overlay = frame.child(area);
+        overlay.clear()?;
+        let flush = self.flush_times.last().unwrap_or_default();
+        let bytes = self.bytes_written.last().unwrap_or_default();
+        overlay.draw_styled(1, 0, &format!("flush {:.1} ms", flush), style)?;
+        let cells = self.changed_cells.last().unwrap_or_default();
+        overlay.draw_styled(1, 1, &format!("{} B, {} cells", bytes, cells), style)?;
+        let work = self.work_times.last().unwrap_or_default();
+        let work = if self.degraded {
+            format!("work {:.1} ms, degraded", work)
+        } else {
+            format!("work {:.1} ms", work)
+        };
+        overlay.draw_styled(1, 2, &work, style)?;
+
+        let mut histogram = Series::new(BUCKETS);
+        for count in self.flush_histogram(BUCKET, BUCKETS) {
+            histogram.push(count as f32);
+        }
+        BarChart::new()
+            .with_range(0.0, self.flush_times.len().max(1) as f32)
+            .with_bars(2, 0)
+            .with_style(style)
+            .draw(
+                &mut overlay,
+                Rect::new(1, 3, 2 * BUCKETS as u16, 2),
+                &histogram,
+            )?;
+        let label = format!("0-{}ms", BUCKET.as_millis() * BUCKETS as u128);
+        overlay.draw_styled(1, 5, &label, style)?;
+        Ok(())
+    }
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flush_histogram() {
+        let mut stats = FrameStats::new();
+        for ms in [0, 1, 3, 3, 40] {
+            stats.record_flush(100, 12, Duration::from_millis(ms));
+        }
+        assert_eq!(stats.frames(), 5);
+        assert_eq!(stats.bytes_written().last(), Some(100.0));
+        assert_eq!(stats.changed_cells().last(), Some(12.0));
+        assert_eq!(
+            stats.flush_histogram(Duration::from_millis(2), 4),
+            [2, 2, 0, 1]
+        );
+    }
+
+    #[test]
+    fn test_degrades_over_budget() {
+        let mut stats = FrameStats::new();
+        let budget = Some(Duration::from_millis(10));
+        let frames = |stats: &mut FrameStats, ms: u64| {
+            (0..BUDGET_WINDOW)
+                .filter_map(|_| stats.record_work(Duration::from_millis(ms), budget))
+                .collect::<Vec<bool>>()
+        };
+        assert!(frames(&mut stats, 9).is_empty());
+        assert_eq!(frames(&mut stats, 14), [true]);
+        assert!(stats.is_degraded());
+        // Just under the budget is not enough to recover
+        assert!(frames(&mut stats, 9).is_empty());
+        assert_eq!(frames(&mut stats, 2), [false]);
+        assert_eq!(stats.record_work(Duration::from_millis(50), None), None);
+    }
+}
@@ -0,0 +1,445 @@
+//! Steering behaviors for entities that move on their own.
+//!
+//! A [`Body`] has a position, a velocity and limits on its speed and on how
+//! sharply it can turn. Each behavior returns a steering force for a body;
+//! games add up the forces they want, e.g. seeking the player while keeping
+//! apart from the flock, and pass the sum to [`Body::apply`] in `update`.
+//!
+//! ```
+//! use coil_engine::steering::{self, Body};
+//!
+//! let mut enemy = Body::new(0.0, 0.0).with_max_speed(4.0);
+//! for _ in 0..10 {
+//!     let force = steering::seek(&enemy, (10.0, 0.0));
+//!     enemy.apply(force, 0.1);
+//! }
+//! assert!(enemy.position().0 > 0.0);
+//! ```
+use crate::motion;
+use crate::random::Rng;
+
+/// The number type of the steering math: [`Fixed`](crate::fixed::Fixed)
+/// with the `fixed` feature, for results that are the same on every
+/// machine, and `f32` otherwise.
+#[cfg(feature = "fixed")]
+pub type Scalar = crate::fixed::Fixed;
+#[cfg(not(feature = "fixed"))]
+pub type Scalar = f32;
+
+type Vector = (Scalar, Scalar);
+
+// Without the `fixed` feature the conversions do nothing
+#[allow(clippy::useless_conversion)]
+fn scalar(value: f32) -> Scalar {
+    Scalar::from(value)
+}
+
+#[allow(clippy::useless_conversion)]
+fn to_f32(value: Scalar) -> f32 {
+    f32::from(value)
+}
+
+fn vector((x, y): (f32, f32)) -> Vector {
+    (scalar(x), scalar(y))
+}
+
+fn float((x, y): Vector) -> (f32, f32) {
+    (to_f32(x), to_f32(y))
+}
+
+/// A moving entity with limited speed and acceleration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Body {
+    /// Position before the last move, for interpolating
+    previous: Vector,
+    position: Vector,
+    velocity: Vector,
+    /// Highest speed in cells per second
+    max_speed: Scalar,
+    /// Highest steering force in cells per second squared
+    max_force: Scalar,
+}
+
+impl Body {
+    /// Creates a still body with a speed of 5 cells per second.
+    pub fn new(x: f32, y: f32) -> Self {
+        let position = vector((x, y));
+        Self {
+            previous: position,
+            position,
+            velocity: vector((0.0, 0.0)),
+            max_speed: scalar(5.0),
+            max_force: scalar(20.0),
+        }
+    }
+
+    pub fn with_max_speed(mut self, max_speed: f32) -> Self {
+        self.max_speed = scalar(max_speed.max(0.0));
+        self
+    }
+
+    pub fn with_max_force(mut self, max_force: f32) -> Self {
+        self.max_force = scalar(max_force.max(0.0));
+        self
+    }
+
+    pub fn with_velocity(mut self, x: f32, y: f32) -> Self {
+        self.velocity = limit(vector((x, y)), self.max_speed);
+        self
+    }
+
+    pub fn position(&self) -> (f32, f32) {
+        float(self.position)
+    }
+
+    /// Returns the position to draw at for the frame's interpolation alpha.
+    pub fn interpolated(&self, alpha: f32) -> (f32, f32) {
+        let alpha = alpha.clamp(0.0, 1.0);
+        let (previous, current) = (float(self.previous), float(self.position));
+        (
+            previous.0 + (current.0 - previous.0) * alpha,
+            previous.1 + (current.1 - previous.1) * alpha,
+        )
+    }
+
+    /// Returns the cell to draw at for the frame's interpolation alpha.
+    pub fn cell(&self, alpha: f32) -> Option<(u16, u16)> {
+        let (x, y) = self.interpolated(alpha);
+        motion::to_cell(x, y)
+    }
+
+    pub fn velocity(&self) -> (f32, f32) {
+        float(self.velocity)
+    }
+
+    pub fn max_speed(&self) -> f32 {
+        to_f32(self.max_speed)
+    }
+
+    /// Jumps to (x,y) without interpolating, keeping the velocity.
+    pub fn teleport(&mut self, x: f32, y: f32) {
+        self.position = vector((x, y));
+        self.previous = self.position;
+    }
+
+    /// Accelerates by a steering force, limited to the maximum force and
+    /// speed, and moves for `dt` seconds. Call it once per update.
+    pub fn apply(&mut self, force: (f32, f32), dt: f32) {
+        let dt = scalar(dt);
+        let force = limit(vector(force), self.max_force);
+        let velocity = (
+            self.velocity.0 + force.0 * dt,
+            self.velocity.1 + force.1 * dt,
+        );
+        self.velocity = limit(velocity, self.max_speed);
+        self.previous = self.position;
+        self.position = (
+            self.position.0 + self.velocity.0 * dt,
+            self.position.1 + self.velocity.1 * dt,
+        );
+    }
+}
+
+fn length((x, y): Vector) -> Scalar {
+    (x * x + y * y).sqrt()
+}
+
+fn offset(from: Vector, to: Vector) -> Vector {
+    (to.0 - from.0, to.1 - from.1)
+}
+
+/// Scales a vector down to at most `max` long.
+fn limit(vector: Vector, max: Scalar) -> Vector {
+    let len = length(vector);
+    if len > max && len > scalar(0.0) {
+        (vector.0 * max / len, vector.1 * max / len)
+    } else {
+        vector
+    }
+}
+
+/// Returns the force turning the body's velocity into `desired`.
+fn steer_towards(body: &Body, desired: Vector) -> (f32, f32) {
+    float(offset(body.velocity, desired))
+}
+
+/// Returns a vector `speed` long pointing from `from` to `to`.
+fn towards(from: Vector, to: Vector, speed: Scalar) -> Vector {
+    let offset = offset(from, to);
+    let len = length(offset);
+    if len <= Scalar::EPSILON {
+        return vector((0.0, 0.0));
+    }
+    (offset.0 * speed / len, offset.1 * speed / len)
+}
+
+/// Steers straight at `target` at full speed.
+pub fn seek(body: &Body, target: (f32, f32)) -> (f32, f32) {
+    seek_vector(body, vector(target))
+}
+
+fn seek_vector(body: &Body, target: Vector) -> (f32, f32) {
+    steer_towards(body, towards(body.position, target, body.max_speed))
+}
+
+/// Steers straight away from `threat` at full speed.
+pub fn flee(body: &Body, threat: (f32, f32)) -> (f32, f32) {
+    steer_towards(body, towards(vector(threat), body.position, body.max_speed))
+}
+
+/// Steers at `target`, slowing down within `slowing_radius` to stop on it.
+pub fn arrive(body: &Body, target: (f32, f32), slowing_radius: f32) -> (f32, f32) {
+    arrive_vector(body, vector(target), scalar(slowing_radius))
+}
+
+fn arrive_vector(body: &Body, target: Vector, slowing_radius: Scalar) -> (f32, f32) {
+    let distance = length(offset(body.position, target));
+    let speed = if distance < slowing_radius {
+        body.max_speed * distance / slowing_radius
+    } else {
+        body.max_speed
+    };
+    steer_towards(body, towards(body.position, target, speed))
+}
+
+/// Random but smooth wandering, for idle creatures.
+#[derive(Debug, Clone)]
+pub struct Wander {
+    /// Direction the body is heading for, in radians
+    angle: Scalar,
+    /// Largest change of direction per second, in radians
+    jitter: Scalar,
+    rng: Rng,
+}
+
+impl Wander {
+    pub fn new(rng: Rng) -> Self {
+        Self {
+            angle: scalar(0.0),
+            jitter: scalar(3.0),
+            rng,
+        }
+    }
+
+    pub fn with_jitter(mut self, jitter: f32) -> Self {
+        self.jitter = scalar(jitter.max(0.0));
+        self
+    }
+
+    /// Turns the heading a little at random and steers along it.
+    pub fn steer(&mut self, body: &Body, dt: f32) -> (f32, f32) {
+        let turn = scalar(self.rng.next_f32() * 2.0 - 1.0);
+        self.angle += turn * self.jitter * scalar(dt);
+        let desired = (
+            self.angle.cos() * body.max_speed,
+            self.angle.sin() * body.max_speed,
+        );
+        steer_towards(body, desired)
+    }
+}
+
+/// Follows a path of cells, e.g. one found by a path finder, waypoint by waypoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathFollower {
+    waypoints: Vec<Vector>,
+    next: usize,
+    /// Distance at which a waypoint counts as reached
+    radius: Scalar,
+    looped: bool,
+}
+
+impl PathFollower {
+    pub fn new(path: &[(u16, u16)]) -> Self {
+        Self {
+            waypoints: path
+                .iter()
+                .map(|&(x, y)| vector((x as f32, y as f32)))
+                .collect(),
+            next: 0,
+            radius: scalar(0.5),
+            looped: false,
+        }
+    }
+
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = scalar(radius.max(0.0));
+        self
+    }
+
+    /// Starts over from the first waypoint after the last, e.g. for patrols.
+    pub fn looped(mut self) -> Self {
+        self.looped = true;
+        self
+    }
+
+    /// Returns the waypoint the body is heading for.
+    pub fn target(&self) -> Option<(f32, f32)> {
+        self.waypoints.get(self.next).copied().map(float)
+    }
+
+    /// Returns whether the body reached the end of a path that is not looped.
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.waypoints.len()
+    }
+
+    /// Steers to the next waypoint, arriving at the last one.
+    pub fn steer(&mut self, body: &Body) -> (f32, f32) {
+        while let Some(&target) = self.waypoints.get(self.next) {
+            let last = self.next + 1 == self.waypoints.len();
+            if length(offset(body.position, target)) > self.radius || (last && !self.looped) {
+                break;
+            }
+            self.next = if last { 0 } else { self.next + 1 };
+        }
+        match self.waypoints.get(self.next).copied() {
+            Some(target) if self.next + 1 == self.waypoints.len() && !self.looped => {
+                let distance = length(offset(body.position, target));
+                if distance <= self.radius && length(body.velocity) < scalar(0.1) {
+                    self.next = self.waypoints.len();
+                }
+                arrive_vector(body, target, scalar(2.0))
+            }
+            Some(target) => seek_vector(body, target),
+            None => steer_towards(body, vector((0.0, 0.0))),
+        }
+    }
+}
+
+/// Weights and range of the flocking rules.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Flocking {
+    /// Distance within which other bodies count as neighbors
+    pub radius: f32,
+    /// Weight of keeping apart from neighbors
+    pub separation: f32,
+    /// Weight of matching the neighbors' velocity
+    pub alignment: f32,
+    /// Weight of moving towards the neighbors' center
+    pub cohesion: f32,
+}
+
+impl Default for Flocking {
+    fn default() -> Self {
+        Self {
+            radius: 4.0,
+            separation: 1.5,
+            alignment: 1.0,
+            cohesion: 1.0,
+        }
+    }
+}
+
+impl Flocking {
+    /// Steers a body to keep apart from, move along with and stay near the
+    /// other bodies of its flock. `flock` may include the body itself.
+    pub fn steer(&self, body: &Body, flock: &[Body]) -> (f32, f32) {
+        let zero = scalar(0.0);
+        let radius = scalar(self.radius);
+        let mut separation = (zero, zero);
+        let mut velocity = (zero, zero);
+        let mut center = (zero, zero);
+        let mut neighbors = 0;
+        for other in flock {
+            let offset = offset(other.position, body.position);
+            let distance = length(offset);
+            if distance <= Scalar::EPSILON || distance > radius {
+                continue;
+            }
+            // Push away harder from closer neighbors
+            separation.0 += offset.0 / (distance * distance);
+            separation.1 += offset.1 / (distance * distance);
+            velocity.0 += other.velocity.0;
+            velocity.1 += other.velocity.1;
+            center.0 += other.position.0;
+            center.1 += other.position.1;
+            neighbors += 1;
+        }
+        if neighbors == 0 {
+            return (0.0, 0.0);
+        }
+        let count = scalar(neighbors as f32);
+        let separate = vector(steer_towards(body, limit_to(separation, body.max_speed)));
+        let align = vector(steer_towards(
+            body,
+            limit_to((velocity.0 / count, velocity.1 / count), body.max_speed),
+        ));
+        let cohere = vector(seek_vector(body, (center.0 / count, center.1 / count)));
+        let (separation, alignment, cohesion) = (
+            scalar(self.separation),
+            scalar(self.alignment),
+            scalar(self.cohesion),
+        );
+        float((
+            separate.0 * separation + align.0 * alignment + cohere.0 * cohesion,
+            separate.1 * separation + align.1 * alignment + cohere.1 * cohesion,
+        ))
+    }
+}
+
+/// Scales a non-zero vector to exactly `len` long.
+fn limit_to(vector: Vector, len: Scalar) -> Vector {
+    towards((scalar(0.0), scalar(0.0)), vector, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(body: &mut Body, steps: usize, mut force: impl FnMut(&Body) -> (f32, f32)) {
+        for _ in 0..steps {
+            let force = force(body);
+            body.apply(force, 0.1);
+        }
+    }
+
+    #[test]
+    fn test_seek_flee_and_arrive() {
+        let mut hunter = Body::new(0.0, 0.0).with_max_speed(2.0);
+        run(&mut hunter, 10, |body| seek(body, (10.0, 0.0)));
+        assert!(hunter.position().0 > 0.5);
+        assert!(length(vector(hunter.velocity())) <= scalar(2.0 + 1e-4));
+
+        let mut prey = Body::new(0.0, 0.0);
+        run(&mut prey, 10, |body| flee(body, (1.0, 0.0)));
+        assert!(prey.position().0 < 0.0);
+
+        let mut visitor = Body::new(0.0, 0.0).with_max_force(50.0);
+        run(&mut visitor, 100, |body| arrive(body, (5.0, 3.0), 3.0));
+        let (x, y) = visitor.position();
+        assert!((x - 5.0).abs() < 0.1 && (y - 3.0).abs() < 0.1, "{x},{y}");
+        assert!(length(vector(visitor.velocity())) < scalar(0.1));
+    }
+
+    #[test]
+    fn test_follow_path() {
+        let mut walker = Body::new(0.0, 0.0).with_max_force(50.0);
+        let mut path = PathFollower::new(&[(0, 0), (4, 0), (4, 4)]);
+        run(&mut walker, 200, |body| path.steer(body));
+        assert!(path.is_finished());
+        let (x, y) = walker.position();
+        assert!((x - 4.0).abs() < 0.5 && (y - 4.0).abs() < 0.5, "{x},{y}");
+
+        let mut patrol = PathFollower::new(&[(0, 0), (2, 0)]).looped();
+        let mut guard = Body::new(0.0, 0.0);
+        run(&mut guard, 100, |body| patrol.steer(body));
+        assert!(!patrol.is_finished());
+    }
+
+    #[test]
+    fn test_flock_and_wander() {
+        let flock = [
+            Body::new(0.0, 0.0),
+            Body::new(1.0, 0.0).with_velocity(0.0, 2.0),
+            Body::new(20.0, 0.0).with_velocity(0.0, -2.0),
+        ];
+        let force = Flocking::default().steer(&flock[0], &flock);
+        // Pushed away from and aligned with the close neighbor; the far one is ignored
+        assert!(force.1 > 0.0);
+        assert!(force.0 < 0.0);
+
+        let mut wander = Wander::new(Rng::new(7));
+        let mut idle = Body::new(0.0, 0.0);
+        run(&mut idle, 20, |body| wander.steer(body, 0.1));
+        assert!(length(vector(idle.velocity())) > scalar(0.0));
+    }
+}
@@ -0,0 +1,424 @@
+//! Cell styling helpers.
+//!
+//! [`Style`] bundles the colors and [`Attributes`] of a cell or string so
+//! call sites read `Style::new().fg(Color::Green).bg(Color::Black).bold()`
+//! instead of passing positional colors. The [`cell!`](crate::cell) and
+//! [`style!`](crate::style) macros build the same values from color names:
+//!
+//! ```
+//! use coil_engine::{cell, style};
+//! use coil_engine::renderer::Cell;
+//!
+//! const WALL: Cell = cell!('█', dark_grey on black);
+//! let title = style!(yellow).bold();
+//! let highlight = style!(on dark_blue).underline();
+//! ```
+use crate::color;
+use crate::errors::EngineError;
+use crate::notify::Level;
+use crossterm::style::{Attribute, Color};
+use std::ops::{BitOr, BitOrAssign};
+
+/// A set of text attributes such as bold or underlined.
+///
+/// Terminals lacking an attribute ignore it, so attributes should add to
+/// what colors already say rather than carry meaning of their own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Attributes(u8);
+
+impl Attributes {
+    pub const BOLD: Attributes = Attributes(1);
+    /// Faint text, e.g. for disabled items
+    pub const DIM: Attributes = Attributes(1 << 1);
+    pub const ITALIC: Attributes = Attributes(1 << 2);
+    pub const UNDERLINE: Attributes = Attributes(1 << 3);
+    /// Swapped foreground and background colors
+    pub const REVERSE: Attributes = Attributes(1 << 4);
+
+    /// Names of each attribute in text, in the order above
+    const NAMES: [(Attributes, &'static str); 5] = [
+        (Self::BOLD, "bold"),
+        (Self::DIM, "dim"),
+        (Self::ITALIC, "italic"),
+        (Self::UNDERLINE, "underline"),
+        (Self::REVERSE, "reverse"),
+    ];
+
+    /// Terminal attributes of each attribute, in the order above
+    const TERMINAL: [(Attributes, Attribute); 5] = [
+        (Self::BOLD, Attribute::Bold),
+        (Self::DIM, Attribute::Dim),
+        (Self::ITALIC, Attribute::Italic),
+        (Self::UNDERLINE, Attribute::Underlined),
+        (Self::REVERSE, Attribute::Reverse),
+    ];
+
+    /// Returns the empty set.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns whether every attribute of `other` is set.
+    pub const fn contains(self, other: Attributes) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the attributes set in either.
+    pub const fn union(self, other: Attributes) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Returns these attributes without those of `other`.
+    pub const fn difference(self, other: Attributes) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl BitOr for Attributes {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        self.union(other)
+    }
+}
+
+impl BitOrAssign for Attributes {
+    fn bitor_assign(&mut self, other: Self) {
+        *self = self.union(other);
+    }
+}
+
+impl From<Attributes> for crossterm::style::Attributes {
+    fn from(attributes: Attributes) -> Self {
+        Attributes::TERMINAL
+            .iter()
+            .filter(|(attribute, _)| attributes.contains(*attribute))
+            .fold(Self::none(), |set, (_, terminal)| set.with(*terminal))
+    }
+}
+
+/// Foreground and background colors and attributes applied to drawn cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub attrs: Attributes,
+}
+
+impl Style {
+    /// Creates a style using the terminal's default colors.
+    pub const fn new() -> Self {
+        Self {
+            fg: Color::Reset,
+            bg: Color::Reset,
+            attrs: Attributes::empty(),
+        }
+    }
+
+    /// Sets the foreground color.
+    pub const fn fg(mut self, color: Color) -> Self {
+        self.fg = color;
+        self
+    }
+
+    /// Sets the background color.
+    pub const fn bg(mut self, color: Color) -> Self {
+        self.bg = color;
+        self
+    }
+
+    /// Adds the given attributes.
+    pub const fn attrs(mut self, attrs: Attributes) -> Self {
+        self.attrs = self.attrs.union(attrs);
+        self
+    }
+
+    pub const fn bold(self) -> Self {
+        self.attrs(Attributes::BOLD)
+    }
+
+    pub const fn dim(self) -> Self {
+        self.attrs(Attributes::DIM)
+    }
+
+    pub const fn italic(self) -> Self {
+        self.attrs(Attributes::ITALIC)
+    }
+
+    pub const fn underline(self) -> Self {
+        self.attrs(Attributes::UNDERLINE)
+    }
+
+    pub const fn reverse(self) -> Self {
+        self.attrs(Attributes::REVERSE)
+    }
+}
+
+/// Parses a style from text, as found in palettes and theme files.
+///
+/// The text is an optional foreground color, then `on` and a background
+/// color, then attribute names, e.g. `yellow on dark_blue bold underline`
+/// or `on black`. Colors take any form [`color::parse`] accepts.
+pub fn parse(text: &str) -> Result<Style, EngineError> {
+    let mut style = Style::new();
+    let mut colors = text.trim();
+    // Attributes come last, so strip them off the end
+    loop {
+        let (rest, word) = colors
+            .rsplit_once(char::is_whitespace)
+            .unwrap_or(("", colors));
+        let mut names = Attributes::NAMES.iter();
+        let Some((attrs, _)) = names.find(|(_, name)| word.eq_ignore_ascii_case(name)) else {
+            break;
+        };
+        style = style.attrs(*attrs);
+        colors = rest.trim_end();
+    }
+    let (fg, bg) = match colors.split_once(" on ") {
+        Some((fg, bg)) => (fg, Some(bg)),
+        None => match colors.strip_prefix("on ") {
+            Some(bg) => ("", Some(bg)),
+            None => (colors, None),
+        },
+    };
+    if !fg.trim().is_empty() {
+        style = style.fg(color::parse(fg)?);
+    }
+    if let Some(bg) = bg {
+        style = style.bg(color::parse(bg)?);
+    }
+    Ok(style)
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Styles of the engine's built-in interface nodes, such as
+/// [`SettingsMenu`](crate::nodes::SettingsMenu),
+/// [`PauseOverlay`](crate::nodes::PauseOverlay) and
+/// [`Toasts`](crate::nodes::Toasts).
+///
+/// Set with [`Context::set_theme`](crate::context::Context::set_theme), it
+/// takes effect from the next frame on. Nodes given a style of their own
+/// keep it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Theme {
+    /// Menu panels
+    pub menu: Style,
+    /// The selected entry of a menu
+    pub selected: Style,
+    /// Messages drawn over the game, such as the pause message
+    pub overlay: Style,
+    /// Notifications, by [`Level`]
+    pub notifications: [Style; 4],
+}
+
+impl Theme {
+    /// Returns the style of notifications of the given level.
+    pub fn notification(&self, level: Level) -> Style {
+        self.notifications[level as usize]
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            menu: crate::style!(white on dark_blue),
+            selected: crate::style!(black on white),
+            overlay: crate::style!(black on white),
+            notifications: [
+                crate::style!(black on white),
+                crate::style!(black on green),
+                crate::style!(black on yellow),
+                crate::style!(white on dark_red),
+            ],
+        }
+    }
+}
+
+/// Expands a lowercase color name (`green`, `dark_blue`, `reset`, ...) to a
+/// crossterm [`Color`].
+#[macro_export]
+macro_rules! named_color {
+    (reset) => {
+        $crate::crossterm::style::Color::Reset
+    };
+    (black) => {
+        $crate::crossterm::style::Color::Black
+    };
+    (dark_grey) => {
+        $crate::crossterm::style::Color::DarkGrey
+    };
+    (red) => {
+        $crate::crossterm::style::Color::Red
+    };
+    (dark_red) => {
+        $crate::crossterm::style::Color::DarkRed
+    };
+    (green) => {
+        $crate::crossterm::style::Color::Green
+    };
+    (dark_green) => {
+        $crate::crossterm::style::Color::DarkGreen
+    };
+    (yellow) => {
+        $crate::crossterm::style::Color::Yellow
+    };
+    (dark_yellow) => {
+        $crate::crossterm::style::Color::DarkYellow
+    };
+    (blue) => {
+        $crate::crossterm::style::Color::Blue
+    };
+    (dark_blue) => {
+        $crate::crossterm::style::Color::DarkBlue
+    };
+    (magenta) => {
+        $crate::crossterm::style::Color::Magenta
+    };
+    (dark_magenta) => {
+        $crate::crossterm::style::Color::DarkMagenta
+    };
+    (cyan) => {
+        $crate::crossterm::style::Color::Cyan
+    };
+    (dark_cyan) => {
+        $crate::crossterm::style::Color::DarkCyan
+    };
+    (white) => {
+        $crate::crossterm::style::Color::White
+    };
+    (grey) => {
+        $crate::crossterm::style::Color::Grey
+    };
+}
+
+/// Builds a [`Style`] from color names: `style!(green)`, `style!(on black)`
+/// or `style!(green on black)`, optionally followed by attributes:
+/// `style!(green on black, bold | underline)`.
+#[macro_export]
+macro_rules! style {
+    () => {
+        $crate::style::Style::new()
+    };
+    (on $bg:ident, $($attr:ident)|+) => {
+        $crate::style!(on $bg)$(.$attr())+
+    };
+    ($fg:ident on $bg:ident, $($attr:ident)|+) => {
+        $crate::style!($fg on $bg)$(.$attr())+
+    };
+    ($fg:ident, $($attr:ident)|+) => {
+        $crate::style!($fg)$(.$attr())+
+    };
+    (on $bg:ident) => {
+        $crate::style::Style::new().bg($crate::named_color!($bg))
+    };
+    ($fg:ident on $bg:ident) => {
+        $crate::style::Style::new()
+            .fg($crate::named_color!($fg))
+            .bg($crate::named_color!($bg))
+    };
+    ($fg:ident) => {
+        $crate::style::Style::new().fg($crate::named_color!($fg))
+    };
+}
+
+/// Builds a [`Cell`](crate::renderer::Cell) from a character and an optional
+/// style in [`style!`](crate::style) syntax: `cell!('█', green on black, bold)`.
+#[macro_export]
+macro_rules! cell {
+    ($ch:expr) => {
+        $crate::renderer::Cell::new($ch)
+    };
+    ($ch:expr, $($style:tt)+) => {
+        $crate::renderer::Cell::styled($ch, $crate::style!($($style)+))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::Cell;
+
+    #[test]
+    fn test_style_builder() {
+        let style = Style::new().fg(Color::Green).bg(Color::Black);
+        assert_eq!(style.fg, Color::Green);
+        assert_eq!(style.bg, Color::Black);
+        assert_eq!(Style::default(), Style::new());
+
+        let title = style.bold().underline();
+        assert!(
+            title
+                .attrs
+                .contains(Attributes::BOLD | Attributes::UNDERLINE)
+        );
+        assert!(!title.attrs.contains(Attributes::DIM));
+        assert_eq!(
+            title.attrs.difference(Attributes::BOLD),
+            Attributes::UNDERLINE
+        );
+        let terminal = crossterm::style::Attributes::from(title.attrs);
+        assert!(terminal.has(Attribute::Underlined) && !terminal.has(Attribute::Italic));
+    }
+
+    #[test]
+    fn test_style_parse() {
+        assert_eq!(parse("").unwrap(), Style::new());
+        assert_eq!(parse("green").unwrap(), style!(green));
+        assert_eq!(parse("on dark_blue").unwrap(), style!(on dark_blue));
+        assert_eq!(
+            parse("rgb(255, 0, 0) on black bold UNDERLINE").unwrap(),
+            Style::new()
+                .fg(Color::Rgb { r: 255, g: 0, b: 0 })
+                .bg(Color::Black)
+                .bold()
+                .underline()
+        );
+        assert_eq!(parse("dim").unwrap(), Style::new().dim());
+        assert!(parse("green on").is_err());
+        assert!(parse("sparkly").is_err());
+    }
+
+    #[test]
+    fn test_style_macro() {
+        assert_eq!(style!(), Style::new());
+        assert_eq!(style!(red), Style::new().fg(Color::Red));
+        assert_eq!(style!(on dark_blue), Style::new().bg(Color::DarkBlue));
+        assert_eq!(
+            style!(white on dark_grey),
+            Style::new().fg(Color::White).bg(Color::DarkGrey)
+        );
+        assert_eq!(style!(red, bold), Style::new().fg(Color::Red).bold());
+        assert_eq!(
+            style!(on black, dim | italic),
+            Style::new().bg(Color::Black).dim().italic()
+        );
+        assert_eq!(
+            style!(white on blue, bold | underline).attrs,
+            Attributes::BOLD | Attributes::UNDERLINE
+        );
+    }
+
+    #[test]
+    fn test_cell_macro() {
+        const ALIVE: Cell = cell!('█', green on black);
+        assert_eq!(ALIVE.ch, '█');
+        assert_eq!(ALIVE.fg, Color::Green);
+        assert_eq!(ALIVE.bg, Color::Black);
+        assert_eq!(cell!(' '), Cell::new(' '));
+        assert_eq!(cell!('x', yellow).style(), style!(yellow));
+        const TITLE: Cell = cell!('█', green on black, bold);
+        assert_eq!(TITLE.style(), style!(green on black).bold());
+    }
+}
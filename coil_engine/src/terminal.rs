@@ -0,0 +1,376 @@
+//! Terminal setup and teardown.
+//!
+//! [`TerminalSession`] owns the raw mode, alternate screen, hidden cursor and
+//! mouse capture state for as long as it lives, restoring the terminal when
+//! dropped. The event loop uses one internally; it is public so that custom
+//! loops can reuse the same setup.
+//!
+//! Older Windows consoles do not understand ANSI escape sequences; crossterm
+//! drives them through the console API instead, which only knows 16 colors
+//! and reports the screen buffer rather than the window size on resize.
+//! [`ConsoleMode`] selects the code paths that cope with that.
+//!
+//! When stdout is not a terminal at all (CI, piping to a file) the session is
+//! headless: the terminal is left alone and frames are written as plain text,
+//! see [`OutputMode`].
+use crate::color;
+use crate::errors::EngineError;
+use crate::renderer::Cell;
+use crate::style::Attributes;
+use crossterm::cursor::{self, SetCursorStyle};
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::execute;
+use crossterm::terminal::{
+    self, EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+};
+use std::fmt;
+use std::io::{IsTerminal, Write, stdout};
+
+/// Terminal operations requested by nodes through the
+/// [`Context`](crate::context::Context), applied after the next flush.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TerminalCommand {
+    SetTitle(String),
+    Bell,
+    CursorStyle(SetCursorStyle),
+    /// Shows the cursor at a position within the viewport, or hides it.
+    Cursor(Option<(u16, u16)>),
+}
+
+/// Where frames are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutputMode {
+    /// Draw to the terminal, or dump frames when stdout is not a terminal.
+    #[default]
+    Auto,
+    /// Always draw to the terminal.
+    Terminal,
+    /// Write each changed frame to stdout as plain text after a
+    /// `--- frame N ---` marker, without touching the terminal or reading input.
+    FrameDump,
+}
+
+impl OutputMode {
+    /// Returns whether frames should be dumped as text.
+    pub fn is_frame_dump(self) -> bool {
+        match self {
+            OutputMode::Auto => !stdout().is_terminal(),
+            OutputMode::Terminal => false,
+            OutputMode::FrameDump => true,
+        }
+    }
+}
+
+impl std::str::FromStr for OutputMode {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "auto" => Ok(OutputMode::Auto),
+            "terminal" => Ok(OutputMode::Terminal),
+            "frame_dump" => Ok(OutputMode::FrameDump),
+            _ => Err(EngineError::Config(format!("Unknown output mode: {:?}", s))),
+        }
+    }
+}
+
+impl fmt::Display for OutputMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OutputMode::Auto => "auto",
+            OutputMode::Terminal => "terminal",
+            OutputMode::FrameDump => "frame_dump",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Saves the window title on the terminal's title stack (XTWINOPS).
+const PUSH_TITLE: &str = "\x1b[22;0t";
+/// Restores the window title saved with [`PUSH_TITLE`].
+const POP_TITLE: &str = "\x1b[23;0t";
+
+/// Which kind of console the game is running in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConsoleMode {
+    /// Detect a legacy Windows console at runtime.
+    #[default]
+    Auto,
+    /// A terminal that understands ANSI escape sequences.
+    Ansi,
+    /// A console without ANSI support, such as `conhost` before Windows 10.
+    Legacy,
+}
+
+impl ConsoleMode {
+    /// Returns whether the legacy console code paths should be used.
+    pub fn is_legacy(self) -> bool {
+        match self {
+            ConsoleMode::Auto => legacy_console_detected(),
+            ConsoleMode::Ansi => false,
+            ConsoleMode::Legacy => true,
+        }
+    }
+}
+
+impl std::str::FromStr for ConsoleMode {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(ConsoleMode::Auto),
+            "ansi" => Ok(ConsoleMode::Ansi),
+            "legacy" => Ok(ConsoleMode::Legacy),
+            _ => Err(EngineError::Config(format!(
+                "Unknown console mode: {:?}",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ConsoleMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ConsoleMode::Auto => "auto",
+            ConsoleMode::Ansi => "ansi",
+            ConsoleMode::Legacy => "legacy",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(windows)]
+fn legacy_console_detected() -> bool {
+    !crossterm::ansi_support::supports_ansi()
+}
+
+#[cfg(not(windows))]
+fn legacy_console_detected() -> bool {
+    false
+}
+
+/// Renderer filter mapping every color to the 16 a legacy console can show
+/// and dropping attributes, which it cannot show, and hyperlinks, which it
+/// would print as garbage.
+pub fn legacy_colors(cell: Cell) -> Cell {
+    Cell {
+        ch: cell.ch,
+        fg: color::to_ansi16(cell.fg),
+        bg: color::to_ansi16(cell.bg),
+        attrs: Attributes::empty(),
+        link: None,
+    }
+}
+
+/// RAII guard that puts the terminal into game mode.
+///
+/// ```no_run
+/// use coil_engine::terminal::TerminalSession;
+///
+/// let mut session = TerminalSession::new(true)?;
+/// // ... run a custom loop ...
+/// session.suspend(|| {
+///     std::process::Command::new("vi").status()
+/// })??;
+/// // back in game mode here, and restored when `session` drops
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct TerminalSession {
+    mouse_capture: bool,
+    active: bool,
+    /// Size reported by a headless session, which never touches the terminal.
+    headless: Option<(u16, u16)>,
+    title: Option<String>,
+    cursor_style: Option<SetCursorStyle>,
+    /// Where the cursor is shown, in terminal coordinates; hidden if `None`.
+    cursor: Option<(u16, u16)>,
+}
+
+impl TerminalSession {
+    /// Enables raw mode, enters the alternate screen and hides the cursor,
+    /// optionally capturing mouse events.
+    pub fn new(mouse_capture: bool) -> Result<Self, EngineError> {
+        let mut session = Self {
+            mouse_capture,
+            active: false,
+            headless: None,
+            title: None,
+            cursor_style: None,
+            cursor: None,
+        };
+        session.enter()?;
+        Ok(session)
+    }
+
+    /// Creates a session that leaves the terminal alone and reports a fixed
+    /// size, for output that is not a terminal.
+    pub fn headless(width: u16, height: u16) -> Self {
+        Self {
+            mouse_capture: false,
+            active: false,
+            headless: Some((width, height)),
+            title: None,
+            cursor_style: None,
+            cursor: None,
+        }
+    }
+
+    /// Returns whether this session leaves the terminal alone.
+    pub fn is_headless(&self) -> bool {
+        self.headless.is_some()
+    }
+
+    /// Returns the size of the terminal, or the fixed size of a headless session.
+    pub fn size(&self) -> Result<(u16, u16), EngineError> {
+        match self.headless {
+            Some(size) => Ok(size),
+            None => Ok(terminal::size()?),
+        }
+    }
+
+    /// Returns whether the terminal is currently in game mode.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Returns whether mouse events are being captured.
+    pub fn mouse_capture(&self) -> bool {
+        self.mouse_capture
+    }
+
+    /// Enables or disables mouse capture.
+    pub fn set_mouse_capture(&mut self, enabled: bool) -> Result<(), EngineError> {
+        if self.active && enabled != self.mouse_capture {
+            if enabled {
+                execute!(stdout(), EnableMouseCapture)
+            } else {
+                execute!(stdout(), DisableMouseCapture)
+            }?;
+        }
+        self.mouse_capture = enabled;
+        Ok(())
+    }
+
+    /// Sets the terminal window title; the previous title is restored when
+    /// the session ends.
+    pub fn set_title(&mut self, title: &str) -> Result<(), EngineError> {
+        if self.active {
+            if self.title.is_none() {
+                write!(stdout(), "{}", PUSH_TITLE)?;
+            }
+            execute!(stdout(), crossterm::terminal::SetTitle(title))?;
+        }
+        self.title = Some(title.to_string());
+        Ok(())
+    }
+
+    /// Rings the terminal bell.
+    pub fn bell(&mut self) -> Result<(), EngineError> {
+        if self.active {
+            let mut out = stdout();
+            out.write_all(b"\x07")?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Changes the cursor's shape; the user's shape is restored when the session ends.
+    pub fn set_cursor_style(&mut self, style: SetCursorStyle) -> Result<(), EngineError> {
+        if self.active {
+            execute!(stdout(), style)?;
+        }
+        self.cursor_style = Some(style);
+        Ok(())
+    }
+
+    /// Shows the cursor at a terminal position, or hides it with `None`.
+    ///
+    /// Drawing moves the cursor, so call [`TerminalSession::place_cursor`]
+    /// after every flush.
+    pub fn set_cursor(&mut self, position: Option<(u16, u16)>) -> Result<(), EngineError> {
+        if self.active && position.is_none() && self.cursor.is_some() {
+            execute!(stdout(), cursor::Hide)?;
+        }
+        self.cursor = position;
+        self.place_cursor()
+    }
+
+    /// Moves the cursor back to where [`TerminalSession::set_cursor`] put it, if shown.
+    pub fn place_cursor(&mut self) -> Result<(), EngineError> {
+        if let (true, Some((x, y))) = (self.active, self.cursor) {
+            execute!(stdout(), cursor::MoveTo(x, y), cursor::Show)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn apply(&mut self, command: TerminalCommand) -> Result<(), EngineError> {
+        match command {
+            TerminalCommand::SetTitle(title) => self.set_title(&title),
+            TerminalCommand::Bell => self.bell(),
+            TerminalCommand::CursorStyle(style) => self.set_cursor_style(style),
+            TerminalCommand::Cursor(position) => self.set_cursor(position),
+        }
+    }
+
+    /// Restores the normal terminal while `f` runs, then re-enters game mode.
+    ///
+    /// Useful for temporarily dropping to a shell or launching an editor.
+    /// Anything drawn before suspending must be redrawn in full afterwards.
+    pub fn suspend<T>(&mut self, f: impl FnOnce() -> T) -> Result<T, EngineError> {
+        self.leave()?;
+        let result = f();
+        self.enter()?;
+        Ok(result)
+    }
+
+    fn enter(&mut self) -> Result<(), EngineError> {
+        if self.is_headless() {
+            return Ok(());
+        }
+        enable_raw_mode().map_err(|e| EngineError::Input(e.to_string()))?;
+        self.active = true;
+        execute!(stdout(), EnterAlternateScreen, cursor::Hide)
+            .map_err(|e| EngineError::Input(e.to_string()))?;
+        if self.mouse_capture {
+            execute!(stdout(), EnableMouseCapture)
+                .map_err(|e| EngineError::Input(e.to_string()))?;
+        }
+        if let Some(title) = self.title.take() {
+            self.set_title(&title)?;
+        }
+        if let Some(style) = self.cursor_style {
+            execute!(stdout(), style)?;
+        }
+        self.place_cursor()
+    }
+
+    fn leave(&mut self) -> Result<(), EngineError> {
+        if !self.active {
+            return Ok(());
+        }
+        self.active = false;
+        if self.mouse_capture {
+            execute!(stdout(), DisableMouseCapture)?;
+        }
+        if self.title.is_some() {
+            write!(stdout(), "{}", POP_TITLE)?;
+        }
+        if self.cursor_style.is_some() {
+            execute!(stdout(), SetCursorStyle::DefaultUserShape)?;
+        }
+        execute!(stdout(), LeaveAlternateScreen, cursor::Show)?;
+        disable_raw_mode()?;
+        Ok(())
+    }
+}
+
+impl Drop for TerminalSession {
+    fn drop(&mut self) {
+        self.leave().unwrap_or_else(|e| {
+            eprintln!("Failed to restore terminal: {}", e);
+        });
+    }
+}
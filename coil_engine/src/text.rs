@@ -0,0 +1,197 @@
+//! Text measurement and wrapping in terminal columns.
+//!
+//! Widths are display widths: wide glyphs such as CJK characters take two
+//! columns, and zero-width characters (combining marks, control characters)
+//! take none and are skipped when drawing.
+use unicode_width::UnicodeWidthChar;
+
+/// Returns the number of columns a character occupies.
+pub fn char_width(ch: char) -> u16 {
+    ch.width().unwrap_or(0) as u16
+}
+
+/// Returns the number of columns a single line of text occupies.
+pub fn text_width(text: &str) -> u16 {
+    text.chars()
+        .fold(0u16, |width, ch| width.saturating_add(char_width(ch)))
+}
+
+/// Returns the `(width, height)` text occupies once wrapped to `max_width` columns.
+pub fn measure_text(text: &str, max_width: u16) -> (u16, u16) {
+    let lines = wrap(text, max_width);
+    let width = lines.iter().map(|line| text_width(line)).max().unwrap_or(0);
+    (width, lines.len() as u16)
+}
+
+/// Splits text into lines of at most `max_width` columns.
+///
+/// Lines break at `\n` and between words; words longer than a line are
+/// split wherever they reach the edge. A glyph wider than `max_width` gets a
+/// line of its own. Every line is a slice of `text`, including empty ones.
+pub fn wrap(text: &str, max_width: u16) -> Vec<&str> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        wrap_paragraph(paragraph, max_width, &mut lines);
+    }
+    lines
+}
+
+fn wrap_paragraph<'a>(paragraph: &'a str, max_width: u16, lines: &mut Vec<&'a str>) {
+    // Byte range of the line being built
+    let mut line: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    for word in paragraph.split(' ') {
+        let word_start = offset;
+        offset += word.len() + 1;
+        if word.is_empty() {
+            continue;
+        }
+        let word_end = word_start + word.len();
+        if let Some((start, _)) = line {
+            if text_width(&paragraph[start..word_end]) <= max_width {
+                line = Some((start, word_end));
+                continue;
+            }
+            let (start, end) = line.take().unwrap_or_default();
+            lines.push(&paragraph[start..end]);
+        }
+
+        let mut rest_start = word_start;
+        while text_width(&paragraph[rest_start..word_end]) > max_width {
+            let rest = &paragraph[rest_start..word_end];
+            // Always keep one character so wrapping makes progress
+            let first = rest.chars().next().map_or(0, char::len_utf8);
+            let split = rest_start + fit_width(rest, max_width).max(first);
+            if split == word_end {
+                break;
+            }
+            lines.push(&paragraph[rest_start..split]);
+            rest_start = split;
+        }
+        line = Some((rest_start, word_end));
+    }
+    match line {
+        Some((start, end)) => lines.push(&paragraph[start..end]),
+        None => lines.push(&paragraph[..0]),
+    }
+}
+
+/// How [`Frame::draw_text_layout`](crate::frame::Frame::draw_text_layout)
+/// arranges a block of text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextLayout {
+    /// Number of columns the text flows through, left to right
+    pub columns: u16,
+    /// Blank columns between two text columns
+    pub gap: u16,
+    /// Whether lines are stretched to the column width, except the last
+    /// line of each paragraph
+    pub justify: bool,
+}
+
+impl TextLayout {
+    /// A single left-aligned column.
+    pub const fn new() -> Self {
+        Self {
+            columns: 1,
+            gap: 2,
+            justify: false,
+        }
+    }
+
+    pub const fn with_columns(mut self, columns: u16, gap: u16) -> Self {
+        self.columns = columns;
+        self.gap = gap;
+        self
+    }
+
+    pub const fn justified(mut self) -> Self {
+        self.justify = true;
+        self
+    }
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Widens the spaces between the words of `line` so it fills `width` columns.
+///
+/// Extra spaces go to the leftmost gaps first. Lines with a single word, or
+/// already as wide as `width`, are returned with single spaces.
+pub fn justify(line: &str, width: u16) -> String {
+    let words: Vec<&str> = line.split(' ').filter(|word| !word.is_empty()).collect();
+    let gaps = words.len().saturating_sub(1);
+    let used: u16 = words.iter().map(|word| text_width(word)).sum();
+    if gaps == 0 {
+        return words.concat();
+    }
+    let spaces = (width.saturating_sub(used) as usize).max(gaps);
+    let mut justified = String::with_capacity(line.len() + spaces);
+    for (index, word) in words.iter().enumerate() {
+        if index > 0 {
+            let extra = usize::from(index - 1 < spaces % gaps);
+            justified.extend(std::iter::repeat_n(' ', spaces / gaps + extra));
+        }
+        justified.push_str(word);
+    }
+    justified
+}
+
+/// Returns the byte index where `text` first exceeds `max_width` columns.
+pub(crate) fn fit_width(text: &str, max_width: u16) -> usize {
+    let mut width = 0u16;
+    for (index, ch) in text.char_indices() {
+        width = width.saturating_add(char_width(ch));
+        if width > max_width {
+            return index;
+        }
+    }
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_widths() {
+        assert_eq!(text_width("abc"), 3);
+        assert_eq!(text_width("日本"), 4);
+        assert_eq!(text_width("e\u{301}"), 1);
+        assert_eq!(char_width('\t'), 0);
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("the quick brown fox", 10), ["the quick", "brown fox"]);
+        assert_eq!(wrap("one\n\ntwo", 10), ["one", "", "two"]);
+        assert_eq!(wrap("abcdefgh", 3), ["abc", "def", "gh"]);
+        assert_eq!(wrap("日本語", 5), ["日本", "語"]);
+        assert_eq!(wrap("日", 1), ["日"]);
+        assert_eq!(wrap("", 4), [""]);
+    }
+
+    #[test]
+    fn test_justify() {
+        assert_eq!(justify("a bb c", 10), "a   bb   c");
+        assert_eq!(justify("a b c d", 9), "a  b  c d");
+        assert_eq!(justify("lonely", 10), "lonely");
+        assert_eq!(justify("too  wide", 3), "too wide");
+    }
+
+    #[test]
+    fn test_measure_text() {
+        assert_eq!(
+            measure_text("Game Paused. Press Space to Resume.", 80),
+            (35, 1)
+        );
+        assert_eq!(
+            measure_text("Game Paused. Press Space to Resume.", 20),
+            (18, 2)
+        );
+        assert_eq!(measure_text("a\nbcd", 10), (3, 2));
+    }
+}
@@ -0,0 +1,279 @@
+//! Animated text for labels and dialogue.
+//!
+//! [`Typewriter`] reveals text a few characters at a time, [`Marquee`]
+//! scrolls a line that does not fit its width, and [`Blink`] toggles
+//! visibility. All of them advance in the fixed update (`update(dt)`) and
+//! draw with the frame's interpolation alpha.
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::style::Style;
+use crate::text;
+use crossterm::event::{Event, KeyEventKind, MouseEventKind};
+
+/// Reveals text at a fixed number of characters per second.
+///
+/// The text is wrapped once for its full length, so words do not jump to
+/// the next line while they are being typed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Typewriter {
+    text: String,
+    chars_per_second: f32,
+    /// Characters revealed before and after the last update
+    previous: f32,
+    revealed: f32,
+    length: usize,
+}
+
+impl Typewriter {
+    pub fn new(text: impl Into<String>, chars_per_second: f32) -> Self {
+        let text = text.into();
+        Self {
+            length: text.chars().count(),
+            text,
+            chars_per_second: chars_per_second.max(0.0),
+            previous: 0.0,
+            revealed: 0.0,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Starts typing a new text from the beginning.
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        *self = Self::new(text, self.chars_per_second);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.previous = self.revealed;
+        self.revealed = (self.revealed + dt * self.chars_per_second).min(self.length as f32);
+    }
+
+    /// Reveals the whole text at once.
+    pub fn skip(&mut self) {
+        self.previous = self.length as f32;
+        self.revealed = self.length as f32;
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.previous >= self.length as f32
+    }
+
+    /// Skips to the end on a key press or mouse click, returning whether the
+    /// event was used; once the text is complete events pass through.
+    pub fn on_event(&mut self, ev: &Event) -> bool {
+        let skips = match ev {
+            Event::Key(key) => key.kind != KeyEventKind::Release,
+            Event::Mouse(mouse) => matches!(mouse.kind, MouseEventKind::Down(_)),
+            _ => false,
+        };
+        if skips && !self.is_done() {
+            self.skip();
+            return true;
+        }
+        false
+    }
+
+    /// Returns the number of characters shown at the interpolation alpha.
+    pub fn visible_chars(&self, alpha: f32) -> usize {
+        let shown = self.previous + (self.revealed - self.previous) * alpha.clamp(0.0, 1.0);
+        shown as usize
+    }
+
+    /// Draws the revealed text at local (x,y), wrapped to the right edge of the frame.
+    pub fn draw(
+        &self,
+        frame: &mut Frame<'_>,
+        x: u16,
+        y: u16,
+        style: Style,
+    ) -> Result<Rect, EngineError> {
+        let (width, height) = frame.size();
+        let end = self
+            .text
+            .char_indices()
+            .nth(self.visible_chars(frame.alpha()))
+            .map_or(self.text.len(), |(index, _)| index);
+        let mut bounds = Rect::new(x, y, 0, 0);
+        for (row, line) in text::wrap(&self.text, width.saturating_sub(x))
+            .into_iter()
+            .enumerate()
+        {
+            // Wrapped lines borrow from the text, so their offset tells how much is revealed
+            let start = line.as_ptr() as usize - self.text.as_ptr() as usize;
+            let line_y = y.saturating_add(row as u16);
+            if start >= end || line_y >= height {
+                break;
+            }
+            let shown = &line[..(end - start).min(line.len())];
+            let drawn = frame.draw_styled(x, line_y, shown, style)?;
+            bounds.width = bounds.width.max(drawn.width);
+            bounds.height = row as u16 + 1;
+        }
+        Ok(bounds)
+    }
+}
+
+/// Scrolls a single line horizontally when it is wider than its space.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marquee {
+    text: String,
+    /// Scrolling speed in columns per second
+    speed: f32,
+    /// Blank columns between the end of the text and its next repetition
+    gap: u16,
+    previous: f32,
+    offset: f32,
+}
+
+impl Marquee {
+    pub fn new(text: impl Into<String>, speed: f32) -> Self {
+        Self {
+            text: text.into(),
+            speed,
+            gap: 3,
+            previous: 0.0,
+            offset: 0.0,
+        }
+    }
+
+    /// Sets the blank columns between repetitions of the text.
+    pub fn with_gap(mut self, gap: u16) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let cycle = (text::text_width(&self.text) + self.gap).max(1) as f32;
+        self.previous = self.offset;
+        self.offset += dt * self.speed;
+        // Keep both offsets within one cycle, preserving their distance
+        let wraps = (self.previous / cycle).floor() * cycle;
+        self.previous -= wraps;
+        self.offset -= wraps;
+    }
+
+    /// Draws the line at local (x,y) in `width` columns, scrolling only if it does not fit.
+    pub fn draw(
+        &self,
+        frame: &mut Frame<'_>,
+        x: u16,
+        y: u16,
+        width: u16,
+        style: Style,
+    ) -> Result<(), EngineError> {
+        let text_width = text::text_width(&self.text);
+        if text_width <= width {
+            frame.draw_styled(x, y, &self.text, style)?;
+            return Ok(());
+        }
+        let alpha = frame.alpha().clamp(0.0, 1.0);
+        let offset = (self.previous + (self.offset - self.previous) * alpha) as u16;
+        let cycle = text_width + self.gap;
+        let mut window = frame.child(Rect::new(x, y, width, 1));
+        // Draw the text at each position it overlaps the window; the child clips it
+        let mut start = -((offset % cycle) as i32);
+        while start < width as i32 {
+            let mut shifted = window.translated(start, 0);
+            shifted.draw_styled(0, 0, &self.text, style)?;
+            start += cycle as i32;
+        }
+        Ok(())
+    }
+}
+
+/// Toggles between visible and hidden at a fixed period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blink {
+    /// Seconds for one visible and one hidden phase
+    period: f32,
+    elapsed: f32,
+}
+
+impl Blink {
+    pub fn new(period: std::time::Duration) -> Self {
+        Self {
+            period: period.as_secs_f32().max(f32::EPSILON),
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed = (self.elapsed + dt) % self.period;
+    }
+
+    /// Shows the text again from the start of a visible phase, e.g. after input.
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+
+    /// Returns whether the text is in its visible phase.
+    pub fn visible(&self) -> bool {
+        self.elapsed < self.period / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::{Renderer, TestRenderer};
+    use crossterm::event::{KeyCode, KeyEvent};
+    use std::time::Duration;
+
+    #[test]
+    fn test_typewriter_reveals_wrapped_text() {
+        let mut typewriter = Typewriter::new("Hello there world", 10.0);
+        typewriter.update(0.8);
+        let mut renderer = TestRenderer::new(10, 3);
+        typewriter
+            .draw(
+                &mut Frame::new(&mut renderer, Rect::new(0, 0, 10, 3), 1.0),
+                0,
+                0,
+                Style::new(),
+            )
+            .unwrap();
+        // "there" is already on the second line while it is typed
+        assert_eq!(renderer.row(0), "Hello");
+        assert_eq!(renderer.row(1), "th");
+
+        let key = Event::Key(KeyEvent::from(KeyCode::Enter));
+        assert!(typewriter.on_event(&key));
+        assert!(typewriter.is_done());
+        assert!(!typewriter.on_event(&key));
+        assert_eq!(typewriter.visible_chars(0.0), 17);
+    }
+
+    #[test]
+    fn test_marquee_scrolls_overflowing_text() {
+        let mut marquee = Marquee::new("abcdef", 2.0).with_gap(1);
+        marquee.update(1.0);
+        let mut renderer = TestRenderer::new(6, 1);
+        let draw = |marquee: &Marquee, renderer: &mut TestRenderer| {
+            renderer.clear().unwrap();
+            marquee
+                .draw(
+                    &mut Frame::new(renderer, Rect::new(0, 0, 6, 1), 1.0),
+                    1,
+                    0,
+                    4,
+                    Style::new(),
+                )
+                .unwrap();
+        };
+        draw(&marquee, &mut renderer);
+        assert_eq!(renderer.row(0), " cdef");
+        marquee.update(2.0);
+        draw(&marquee, &mut renderer);
+        assert_eq!(renderer.row(0), "  abc");
+
+        let mut blink = Blink::new(Duration::from_secs(1));
+        assert!(blink.visible());
+        blink.update(0.6);
+        assert!(!blink.visible());
+        blink.update(0.5);
+        assert!(blink.visible());
+    }
+}
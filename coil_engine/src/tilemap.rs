@@ -0,0 +1,293 @@
+//! Tile maps: grids of tiles chosen from a palette.
+//!
+//! Maps are stored as plain text, one line per row and one character per
+//! tile, using each tile's glyph:
+//!
+//! ```text
+//! ########
+//! #..~~..#
+//! ########
+//! ```
+//!
+//! Palettes can be stored as text too, one tile per line: its glyph, `=`
+//! and its style as [`style::parse`] reads it. Other lines that are blank or
+//! start with `#` are comments.
+//!
+//! ```text
+//! # Dungeon tiles
+//! # = dark_grey on black
+//! . = grey on black dim
+//! ~ = blue on dark_blue
+//! ```
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::lighting::Lighting;
+use crate::renderer::Cell;
+use crate::style::{self, Style};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// A kind of tile: its glyph, which also identifies it in map files, and style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub ch: char,
+    pub style: Style,
+}
+
+impl Tile {
+    pub const fn new(ch: char, style: Style) -> Self {
+        Self { ch, style }
+    }
+}
+
+/// Parses a palette in the text format, keeping the order of its tiles.
+pub fn parse_palette(text: &str) -> Result<Vec<Tile>, EngineError> {
+    let mut palette = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let mut chars = line.chars();
+        let entry = chars.next().and_then(|ch| {
+            let style = chars.as_str().trim_start().strip_prefix('=')?;
+            Some((ch, style))
+        });
+        match entry {
+            Some((ch, style)) => {
+                let style = style::parse(style)
+                    .map_err(|e| EngineError::game(format!("{} at line {}", e, number + 1)))?;
+                palette.push(Tile::new(ch, style));
+            }
+            None if line.trim().is_empty() || line.starts_with('#') => {}
+            None => {
+                return Err(EngineError::game(format!(
+                    "Expected `glyph = style` at line {}",
+                    number + 1
+                )));
+            }
+        }
+    }
+    Ok(palette)
+}
+
+/// Reads a palette file.
+pub fn load_palette(path: impl AsRef<Path>) -> Result<Vec<Tile>, EngineError> {
+    parse_palette(&fs::read_to_string(path)?)
+}
+
+/// A grid of tiles, each an index into the map's palette.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileMap {
+    width: u16,
+    height: u16,
+    palette: Vec<Tile>,
+    tiles: Vec<usize>,
+}
+
+impl TileMap {
+    /// Creates a map filled with the first tile of `palette`.
+    ///
+    /// # Panics
+    /// If `palette` is empty.
+    pub fn new(width: u16, height: u16, palette: Vec<Tile>) -> Self {
+        assert!(!palette.is_empty(), "a tile map needs at least one tile");
+        Self {
+            width,
+            height,
+            palette,
+            tiles: vec![0; width as usize * height as usize],
+        }
+    }
+
+    /// Parses a map in the text format; rows shorter than the longest are
+    /// padded with the first tile.
+    pub fn parse(text: &str, palette: Vec<Tile>) -> Result<Self, EngineError> {
+        let rows: Vec<&str> = text.lines().collect();
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut map = Self::new(width as u16, rows.len() as u16, palette);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, ch) in row.chars().enumerate() {
+                let tile = map.tile_index(ch).ok_or_else(|| {
+                    EngineError::game(format!(
+                        "Unknown tile {:?} at line {}, column {}",
+                        ch,
+                        y + 1,
+                        x + 1
+                    ))
+                })?;
+                map.set(x as u16, y as u16, tile);
+            }
+        }
+        Ok(map)
+    }
+
+    /// Reads a map file.
+    pub fn load(path: impl AsRef<Path>, palette: Vec<Tile>) -> Result<Self, EngineError> {
+        Self::parse(&fs::read_to_string(path)?, palette)
+    }
+
+    /// Writes the map in the text format.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), EngineError> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn palette(&self) -> &[Tile] {
+        &self.palette
+    }
+
+    /// Returns the palette index of the tile with glyph `ch`.
+    pub fn tile_index(&self, ch: char) -> Option<usize> {
+        self.palette.iter().position(|tile| tile.ch == ch)
+    }
+
+    /// Returns the palette index of the tile at (x,y).
+    pub fn get(&self, x: u16, y: u16) -> Option<usize> {
+        self.index(x, y).map(|i| self.tiles[i])
+    }
+
+    /// Returns the tile at (x,y).
+    pub fn tile(&self, x: u16, y: u16) -> Option<Tile> {
+        self.get(x, y).map(|tile| self.palette[tile])
+    }
+
+    /// Places palette tile `tile` at (x,y), returning the tile it replaced.
+    ///
+    /// Positions outside the map and indices outside the palette are ignored.
+    pub fn set(&mut self, x: u16, y: u16, tile: usize) -> Option<usize> {
+        if tile >= self.palette.len() {
+            return None;
+        }
+        let i = self.index(x, y)?;
+        Some(std::mem::replace(&mut self.tiles[i], tile))
+    }
+
+    /// Draws the map with its top-left tile at local (0,0).
+    pub fn draw(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        let mut glyph = [0; 4];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let tile = self.palette[self.tiles[y as usize * self.width as usize + x as usize]];
+                frame.draw_styled(x, y, tile.ch.encode_utf8(&mut glyph), tile.style)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws the map like [`TileMap::draw`], lighting each tile through `lighting`.
+    pub fn draw_lit(&self, frame: &mut Frame<'_>, lighting: &Lighting) -> Result<(), EngineError> {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let tile = self.palette[self.tiles[y as usize * self.width as usize + x as usize]];
+                frame.draw_cell(
+                    x,
+                    y,
+                    lighting.apply(x, y, Cell::styled(tile.ch, tile.style)),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws the map as a hex grid in "odd-r" offset layout, each tile two
+    /// columns wide and odd rows shifted by one column; see [`crate::hex`].
+    pub fn draw_hex(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        let mut glyph = [0; 4];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let tile = self.palette[self.tiles[y as usize * self.width as usize + x as usize]];
+                let column = x * 2 + (y & 1);
+                frame.draw_styled(column, y, tile.ch.encode_utf8(&mut glyph), tile.style)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the tile drawn at local (x,y) by [`TileMap::draw_hex`].
+    pub fn hex_at(&self, x: u16, y: u16) -> Option<(u16, u16)> {
+        let column = x.checked_sub(y & 1)? / 2;
+        self.index(column, y).map(|_| (column, y))
+    }
+
+    /// Returns the area the map covers in its own coordinates.
+    pub fn bounds(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+
+    fn index(&self, x: u16, y: u16) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| y as usize * self.width as usize + x as usize)
+    }
+}
+
+impl fmt::Display for TileMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.tiles.chunks(self.width.max(1) as usize) {
+            let line: String = row.iter().map(|&tile| self.palette[tile].ch).collect();
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_round_trips_through_text() {
+        let palette = vec![
+            Tile::new('.', Style::new()),
+            Tile::new('#', crate::style!(white)),
+            Tile::new('~', crate::style!(blue)),
+        ];
+        let map = TileMap::parse("###\n#~\n", palette.clone()).unwrap();
+        assert_eq!((map.width(), map.height()), (3, 2));
+        assert_eq!(map.tile(1, 1).map(|tile| tile.ch), Some('~'));
+        assert_eq!(map.to_string(), "###\n#~.\n");
+
+        let mut edited = map.clone();
+        assert_eq!(edited.set(2, 1, 1), Some(0));
+        assert_eq!(edited.set(3, 1, 1), None);
+        assert_eq!(edited.to_string(), "###\n#~#\n");
+
+        assert!(TileMap::parse("#?", palette).is_err());
+    }
+
+    #[test]
+    fn test_palette_text_format() {
+        let palette = parse_palette("# Tiles\n\n# = white\n  = on blue\n~ = blue bold\n").unwrap();
+        assert_eq!(
+            palette,
+            [
+                Tile::new('#', crate::style!(white)),
+                Tile::new(' ', crate::style!(on blue)),
+                Tile::new('~', crate::style!(blue).bold()),
+            ]
+        );
+        assert!(parse_palette("#\nwall").is_err());
+        assert!(parse_palette("# = shiny").is_err());
+    }
+
+    #[test]
+    fn test_hex_layout_interleaves_rows() {
+        let palette = vec![Tile::new('.', Style::new()), Tile::new('#', Style::new())];
+        let map = TileMap::parse("#..\n.#.\n", palette).unwrap();
+        let mut renderer = crate::renderer::TestRenderer::new(7, 2);
+        map.draw_hex(&mut Frame::new(&mut renderer, Rect::new(0, 0, 7, 2), 1.0))
+            .unwrap();
+        assert_eq!(renderer.rows(), ["# . .", " . # ."]);
+        assert_eq!(map.hex_at(4, 1), Some((1, 1)));
+        assert_eq!(map.hex_at(0, 1), None);
+    }
+}
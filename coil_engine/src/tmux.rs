@@ -0,0 +1,154 @@
+//! Auxiliary views in tmux panes.
+//!
+//! When the game runs inside tmux, extra views such as a map, a message log
+//! or a debug console can be shown in panes next to the game. Each
+//! [`TmuxPane`] splits a new pane, draws into its tty with its own
+//! [`BasicRenderer`] and closes the pane when dropped.
+//!
+//! ```no_run
+//! use coil_engine::style::Style;
+//! use coil_engine::tmux::{Split, TmuxPane};
+//!
+//! if coil_engine::tmux::available() {
+//!     let mut log = TmuxPane::split(Split::Right, 30)?;
+//!     log.draw(|frame| {
+//!         frame.draw_styled(0, 0, "Log", Style::new())?;
+//!         Ok(())
+//!     })?;
+//! }
+//! # Ok::<(), coil_engine::EngineError>(())
+//! ```
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::renderer::{BasicRenderer, Renderer};
+use crossterm::execute;
+use crossterm::terminal::{Clear, ClearType};
+use log::warn;
+use std::fs::OpenOptions;
+use std::process::Command;
+
+/// Where a new pane is placed relative to the game's pane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    Right,
+    Below,
+}
+
+/// Returns whether the game is running inside tmux.
+pub fn available() -> bool {
+    std::env::var_os("TMUX").is_some()
+}
+
+/// A tmux pane the game draws an auxiliary view into.
+pub struct TmuxPane {
+    id: String,
+    renderer: BasicRenderer,
+}
+
+impl TmuxPane {
+    /// Splits the game's pane, giving the new pane `percent` of its size.
+    ///
+    /// The pane runs a process that never reads input, so the game owns
+    /// everything drawn on it. Focus stays on the game's pane.
+    pub fn split(split: Split, percent: u8) -> Result<Self, EngineError> {
+        let direction = match split {
+            Split::Right => "-h",
+            Split::Below => "-v",
+        };
+        let info = tmux(&[
+            "split-window",
+            "-d",
+            direction,
+            "-l",
+            &format!("{}%", percent.clamp(1, 99)),
+            "-P",
+            "-F",
+            "#{pane_id} #{pane_width} #{pane_height} #{pane_tty}",
+            "tail -f /dev/null",
+        ])?;
+        let (id, width, height, tty) = parse_pane_info(&info)?;
+        let mut out = OpenOptions::new().write(true).open(tty)?;
+        execute!(out, Clear(ClearType::All), crossterm::cursor::Hide)?;
+        Ok(Self {
+            id,
+            renderer: BasicRenderer::with_output(width, height, out)?,
+        })
+    }
+
+    /// Returns the tmux id of the pane, e.g. `%3`.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the size of the pane when it was created.
+    pub fn size(&self) -> (u16, u16) {
+        self.renderer.size()
+    }
+
+    /// Redraws the pane: clears it, lets `f` draw into a frame covering it,
+    /// and flushes the changed cells.
+    pub fn draw(
+        &mut self,
+        f: impl FnOnce(&mut Frame<'_>) -> Result<(), EngineError>,
+    ) -> Result<(), EngineError> {
+        let (width, height) = self.renderer.size();
+        self.renderer.clear()?;
+        f(&mut Frame::new(
+            &mut self.renderer,
+            Rect::new(0, 0, width, height),
+            0.0,
+        ))?;
+        self.renderer.take_errors();
+        self.renderer.flush()
+    }
+}
+
+impl Drop for TmuxPane {
+    fn drop(&mut self) {
+        if let Err(e) = tmux(&["kill-pane", "-t", &self.id]) {
+            warn!("Failed to close tmux pane {}: {}", self.id, e);
+        }
+    }
+}
+
+/// Runs a tmux command, returning its output.
+fn tmux(args: &[&str]) -> Result<String, EngineError> {
+    let output = Command::new("tmux").args(args).output()?;
+    if !output.status.success() {
+        return Err(EngineError::Render(format!(
+            "tmux {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parses `#{pane_id} #{pane_width} #{pane_height} #{pane_tty}`.
+fn parse_pane_info(info: &str) -> Result<(String, u16, u16, String), EngineError> {
+    let invalid = || EngineError::Render(format!("Unexpected tmux pane info: {:?}", info));
+    let mut fields = info.split_whitespace();
+    let mut next = || fields.next().ok_or_else(invalid);
+    let id = next()?.to_string();
+    let width = next()?.parse().map_err(|_| invalid())?;
+    let height = next()?.parse().map_err(|_| invalid())?;
+    let tty = next()?.to_string();
+    Ok((id, width, height, tty))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pane_info() {
+        let (id, width, height, tty) = parse_pane_info("%3 40 24 /dev/pts/5\n").unwrap();
+        assert_eq!(
+            (id.as_str(), width, height, tty.as_str()),
+            ("%3", 40, 24, "/dev/pts/5")
+        );
+        assert!(parse_pane_info("%3 forty 24 /dev/pts/5").is_err());
+        assert!(parse_pane_info("").is_err());
+    }
+}
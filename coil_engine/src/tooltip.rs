@@ -0,0 +1,259 @@
+//! Tooltips for hovered and focused widgets.
+//!
+//! Nodes provide tooltip content while rendering: [`Frame::tooltip`]
+//! attaches text to one of their hit regions, shown when the mouse rests
+//! over it, and [`Frame::focus_tooltip`] describes the widget that has
+//! keyboard focus. Once the same content has been wanted for the tooltip
+//! delay, the engine draws it in a small box next to the cursor or below
+//! the focused widget, moved as needed to stay on screen.
+//!
+//! Hover tooltips win over focus tooltips. Pressing a mouse button hides a
+//! tooltip until the cursor moves to another region, and none are shown
+//! while dragging.
+//!
+//! [`Frame::tooltip`]: crate::frame::Frame::tooltip
+//! [`Frame::focus_tooltip`]: crate::frame::Frame::focus_tooltip
+use crate::errors::EngineError;
+use crate::frame::Frame;
+use crate::geometry::Rect;
+use crate::hit::{Hit, HitId, HitRegions};
+use crate::style::Style;
+use crossterm::event::MouseEventKind;
+use std::time::Duration;
+
+/// How long content has to be hovered or focused before its tooltip shows.
+pub const TOOLTIP_DELAY: Duration = Duration::from_millis(500);
+
+/// A tooltip ready to be drawn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tooltip {
+    /// The content, one line per row
+    pub text: String,
+    /// What the tooltip is about in renderer coordinates: a single cell for
+    /// the cursor, or the focused widget's area
+    pub anchor: Rect,
+}
+
+impl Tooltip {
+    /// Returns the box the tooltip takes on a screen of `size`.
+    ///
+    /// The box goes below the anchor, or above it if there is no room
+    /// below, and is pushed left to fit the screen; it is cut down if the
+    /// screen is smaller than the text.
+    pub fn placement(&self, (width, height): (u16, u16)) -> Rect {
+        let lines = self.text.lines().count().max(1) as u16;
+        let longest = self
+            .text
+            .lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0) as u16;
+        let (box_width, box_height) = ((longest + 2).min(width), lines.min(height));
+        let below = self.anchor.bottom();
+        let y = if below + box_height <= height {
+            below
+        } else if self.anchor.y >= box_height {
+            self.anchor.y - box_height
+        } else {
+            height - box_height
+        };
+        // Beside the cursor, not under it
+        let x = if self.anchor.width == 1 && self.anchor.height == 1 {
+            self.anchor.x + 1
+        } else {
+            self.anchor.x
+        };
+        Rect::new(x.min(width - box_width), y, box_width, box_height)
+    }
+
+    /// Draws the tooltip in `style`, padded by a space on each side.
+    pub fn draw(&self, frame: &mut Frame<'_>, style: Style) -> Result<(), EngineError> {
+        let area = self.placement(frame.size());
+        let mut frame = frame.child(area);
+        for (y, line) in self.text.lines().take(area.height as usize).enumerate() {
+            let text = format!(" {:<1$} ", line, area.width.saturating_sub(2) as usize);
+            frame.draw_styled(0, y as u16, &text, style)?;
+        }
+        Ok(())
+    }
+}
+
+/// What a tooltip is wanted for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Subject {
+    Hover(HitId),
+    Focus(Rect, String),
+}
+
+/// Follows what is hovered and focused and decides when a tooltip shows.
+#[derive(Debug)]
+pub(crate) struct TooltipState {
+    delay: Duration,
+    /// Region under the cursor and the cursor position in renderer coordinates
+    hovered: Option<(HitId, (u16, u16))>,
+    /// Region whose tooltip was dismissed by a mouse press
+    dismissed: Option<HitId>,
+    subject: Option<Subject>,
+    /// How long the subject has been wanted
+    waited: Duration,
+}
+
+impl Default for TooltipState {
+    fn default() -> Self {
+        Self {
+            delay: TOOLTIP_DELAY,
+            hovered: None,
+            dismissed: None,
+            subject: None,
+            waited: Duration::ZERO,
+        }
+    }
+}
+
+impl TooltipState {
+    pub fn set_delay(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+
+    /// Follows a mouse event at `position` in renderer coordinates, with the
+    /// region under it.
+    pub fn mouse(&mut self, kind: MouseEventKind, position: (u16, u16), hit: Option<&Hit>) {
+        let id = hit.map(|hit| hit.id);
+        if self.hovered.map(|(hovered, _)| hovered) != id {
+            self.dismissed = None;
+        }
+        if let MouseEventKind::Down(_) | MouseEventKind::Drag(_) = kind {
+            self.dismissed = id;
+        }
+        self.hovered = id.map(|id| (id, position));
+    }
+
+    /// Advances the delay by `elapsed`, given the regions and tooltips of the
+    /// last frame.
+    pub fn advance(&mut self, elapsed: Duration, regions: &HitRegions) {
+        let hover = self
+            .hovered
+            .map(|(id, _)| id)
+            .filter(|id| Some(*id) != self.dismissed && regions.tooltip(*id).is_some());
+        let subject = match hover {
+            Some(id) => Some(Subject::Hover(id)),
+            None => regions
+                .focus_tooltip()
+                .map(|(area, text)| Subject::Focus(area, text.to_string())),
+        };
+        if subject == self.subject {
+            self.waited = self.waited.saturating_add(elapsed);
+        } else {
+            // The new subject appeared during the elapsed time
+            self.subject = subject;
+            self.waited = elapsed;
+        }
+    }
+
+    /// Returns the tooltip to show over the frame with `regions`, if any.
+    pub fn visible(&self, regions: &HitRegions) -> Option<Tooltip> {
+        if self.waited < self.delay {
+            return None;
+        }
+        match self.subject.as_ref()? {
+            Subject::Hover(id) => {
+                let (_, (x, y)) = self.hovered?;
+                Some(Tooltip {
+                    text: regions.tooltip(*id)?.to_string(),
+                    anchor: Rect::new(x, y, 1, 1),
+                })
+            }
+            Subject::Focus(area, text) => Some(Tooltip {
+                text: text.clone(),
+                anchor: *area,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::TestRenderer;
+    use crossterm::event::{KeyModifiers, MouseButton, MouseEvent};
+
+    fn hit(id: HitId, kind: MouseEventKind) -> Hit {
+        Hit {
+            id,
+            x: 0,
+            y: 0,
+            event: MouseEvent {
+                kind,
+                column: 0,
+                row: 0,
+                modifiers: KeyModifiers::NONE,
+            },
+        }
+    }
+
+    #[test]
+    fn test_hover_shows_after_delay_until_pressed() {
+        let (button, focused) = (HitId::new(), Rect::new(0, 0, 6, 1));
+        let mut regions = HitRegions::new();
+        let mut renderer = TestRenderer::new(20, 5);
+        {
+            let mut frame = Frame::new(&mut renderer, Rect::new(0, 0, 20, 5), 0.0)
+                .with_hit_regions(&mut regions);
+            frame.hit_region(button, Rect::new(10, 2, 4, 1));
+            frame.tooltip(button, "Fire");
+            frame.focus_tooltip(focused, "Name");
+        }
+        let mut state = TooltipState::default();
+        let half = TOOLTIP_DELAY / 2;
+
+        // The focused widget's tooltip waits for the delay
+        state.advance(half, &regions);
+        assert_eq!(state.visible(&regions), None);
+        state.advance(half, &regions);
+        assert_eq!(state.visible(&regions).unwrap().text, "Name");
+
+        // Hovering takes over and restarts the delay
+        let moved = hit(button, MouseEventKind::Moved);
+        state.mouse(MouseEventKind::Moved, (11, 2), Some(&moved));
+        state.advance(half, &regions);
+        assert_eq!(state.visible(&regions), None);
+        state.advance(half, &regions);
+        let tooltip = state.visible(&regions).unwrap();
+        assert_eq!(
+            (tooltip.text.as_str(), tooltip.anchor),
+            ("Fire", Rect::new(11, 2, 1, 1))
+        );
+
+        let press = MouseEventKind::Down(MouseButton::Left);
+        state.mouse(press, (11, 2), Some(&hit(button, press)));
+        state.advance(TOOLTIP_DELAY, &regions);
+        assert_eq!(state.visible(&regions).unwrap().text, "Name");
+    }
+
+    #[test]
+    fn test_placement_stays_on_screen() {
+        let tooltip = |x, y, width| Tooltip {
+            text: "Opens\nthe gate".to_string(),
+            anchor: Rect::new(x, y, width, 1),
+        };
+        // Below and beside the cursor
+        assert_eq!(tooltip(2, 1, 1).placement((20, 6)), Rect::new(3, 2, 10, 2));
+        // Pushed left at the right edge and above at the bottom
+        assert_eq!(
+            tooltip(18, 5, 1).placement((20, 6)),
+            Rect::new(10, 3, 10, 2)
+        );
+        // Cut down on a tiny screen
+        assert_eq!(tooltip(0, 0, 4).placement((6, 1)), Rect::new(0, 0, 6, 1));
+
+        let mut renderer = TestRenderer::new(12, 3);
+        tooltip(0, 0, 4)
+            .draw(
+                &mut Frame::new(&mut renderer, Rect::new(0, 0, 12, 3), 0.0),
+                Style::new(),
+            )
+            .unwrap();
+        assert_eq!(renderer.row(1), " Opens");
+        assert_eq!(renderer.row(2), " the gate");
+    }
+}
@@ -0,0 +1,207 @@
+//! Undo and redo through reversible commands.
+//!
+//! Each edit is a [`Command`] that knows how to apply itself to a target and
+//! how to revert it. A [`History`] runs commands and keeps them so they can
+//! be undone and redone; commands issued between [`History::begin_group`] and
+//! [`History::end_group`] (a paint stroke, a multi-tile move) undo as one.
+//!
+//! ```
+//! use coil_engine::undo::{Command, History};
+//!
+//! struct Add(i32);
+//!
+//! impl Command<i32> for Add {
+//!     fn execute(&mut self, total: &mut i32) {
+//!         *total += self.0;
+//!     }
+//!
+//!     fn undo(&mut self, total: &mut i32) {
+//!         *total -= self.0;
+//!     }
+//! }
+//!
+//! let mut total = 0;
+//! let mut history = History::new();
+//! history.execute(Add(2), &mut total);
+//! history.execute(Add(3), &mut total);
+//! history.undo(&mut total);
+//! assert_eq!(total, 2);
+//! history.redo(&mut total);
+//! assert_eq!(total, 5);
+//! ```
+
+/// A reversible change to a `T`.
+pub trait Command<T> {
+    /// Applies the change; called again on redo.
+    fn execute(&mut self, target: &mut T);
+
+    /// Reverts the change made by the last `execute`.
+    fn undo(&mut self, target: &mut T);
+}
+
+/// Commands undone and redone together, in execution order.
+type Entry<T> = Vec<Box<dyn Command<T>>>;
+
+/// The commands applied to a `T`, most recent last, and those undone since.
+pub struct History<T> {
+    done: Vec<Entry<T>>,
+    undone: Vec<Entry<T>>,
+    /// The group being recorded, if any
+    group: Option<Entry<T>>,
+    limit: Option<usize>,
+}
+
+impl<T> History<T> {
+    /// Creates a history without a limit.
+    pub fn new() -> Self {
+        Self {
+            done: Vec::new(),
+            undone: Vec::new(),
+            group: None,
+            limit: None,
+        }
+    }
+
+    /// Keeps at most `limit` undo steps, forgetting the oldest first.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self.trim();
+        self
+    }
+
+    /// Executes `command` on `target` and records it, dropping the redo steps.
+    pub fn execute(&mut self, mut command: impl Command<T> + 'static, target: &mut T) {
+        command.execute(target);
+        self.push(command);
+    }
+
+    /// Records a command whose change has already been applied.
+    pub fn push(&mut self, command: impl Command<T> + 'static) {
+        self.undone.clear();
+        match &mut self.group {
+            Some(group) => group.push(Box::new(command)),
+            None => {
+                self.done.push(vec![Box::new(command)]);
+                self.trim();
+            }
+        }
+    }
+
+    /// Starts recording the following commands as a single undo step.
+    ///
+    /// Ends the group already being recorded, if any.
+    pub fn begin_group(&mut self) {
+        self.end_group();
+        self.group = Some(Vec::new());
+    }
+
+    /// Stops grouping commands; an empty group records nothing.
+    pub fn end_group(&mut self) {
+        if let Some(group) = self.group.take()
+            && !group.is_empty()
+        {
+            self.done.push(group);
+            self.trim();
+        }
+    }
+
+    /// Reverts the last step, returning whether there was one.
+    ///
+    /// A group still being recorded is ended first.
+    pub fn undo(&mut self, target: &mut T) -> bool {
+        self.end_group();
+        let Some(mut entry) = self.done.pop() else {
+            return false;
+        };
+        for command in entry.iter_mut().rev() {
+            command.undo(target);
+        }
+        self.undone.push(entry);
+        true
+    }
+
+    /// Re-applies the last undone step, returning whether there was one.
+    pub fn redo(&mut self, target: &mut T) -> bool {
+        self.end_group();
+        let Some(mut entry) = self.undone.pop() else {
+            return false;
+        };
+        for command in entry.iter_mut() {
+            command.execute(target);
+        }
+        self.done.push(entry);
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty() || self.group.as_ref().is_some_and(|group| !group.is_empty())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Forgets every step, e.g. after loading a new document.
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+        self.group = None;
+    }
+
+    fn trim(&mut self) {
+        if let Some(limit) = self.limit
+            && self.done.len() > limit
+        {
+            self.done.drain(..self.done.len() - limit);
+        }
+    }
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Push(char);
+
+    impl Command<String> for Push {
+        fn execute(&mut self, text: &mut String) {
+            text.push(self.0);
+        }
+
+        fn undo(&mut self, text: &mut String) {
+            text.pop();
+        }
+    }
+
+    #[test]
+    fn test_groups_and_limit() {
+        let mut text = String::new();
+        let mut history = History::new().with_limit(2);
+        history.execute(Push('a'), &mut text);
+        history.begin_group();
+        history.execute(Push('b'), &mut text);
+        history.execute(Push('c'), &mut text);
+        history.end_group();
+        history.execute(Push('d'), &mut text);
+        assert_eq!(text, "abcd");
+
+        assert!(history.undo(&mut text));
+        assert!(history.undo(&mut text));
+        assert_eq!(text, "a");
+        // 'a' fell off the history
+        assert!(!history.undo(&mut text));
+
+        assert!(history.redo(&mut text));
+        assert_eq!(text, "abc");
+        history.execute(Push('e'), &mut text);
+        assert!(!history.can_redo());
+        assert!(!history.redo(&mut text));
+        assert_eq!(text, "abce");
+    }
+}
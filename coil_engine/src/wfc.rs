@@ -0,0 +1,339 @@
+//! Wave function collapse map generation.
+//!
+//! [`Rules`] say which tiles may sit next to each other, either learned from
+//! a sample [`TileMap`] or declared by hand. A [`Wfc`] generator then fills a
+//! larger map: it repeatedly collapses the cell with the fewest remaining
+//! options to one tile and propagates the constraints to its neighbors,
+//! starting over when it runs into a contradiction.
+//!
+//! Generation is incremental, so large maps do not stall a frame: call
+//! [`Wfc::step`] from `update` with a budget of cells, show
+//! [`Wfc::progress`] meanwhile, and drop the generator to cancel it.
+//!
+//! ```
+//! use coil_engine::style::Style;
+//! use coil_engine::tilemap::{Tile, TileMap};
+//! use coil_engine::wfc::{Rules, Wfc, WfcStatus};
+//!
+//! let palette = vec![Tile::new('.', Style::new()), Tile::new('#', Style::new())];
+//! let sample = TileMap::parse("..#\n.##\n..#\n", palette.clone()).unwrap();
+//! let mut wfc = Wfc::new(Rules::from_sample(&sample), 20, 10, 42);
+//! while wfc.step(50) == WfcStatus::Running {}
+//! let map = wfc.to_tilemap(palette).unwrap();
+//! ```
+use crate::random::Rng;
+use crate::tilemap::{Tile, TileMap};
+
+/// Neighbor offsets, paired so that `OFFSETS[(d + 2) % 4]` is opposite `OFFSETS[d]`.
+const OFFSETS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+/// Which tiles may be placed next to each other, and how often each is used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rules {
+    weights: Vec<f32>,
+    /// `allowed[d][a * tiles + b]`: tile `b` may sit in direction `d` of tile `a`
+    allowed: [Vec<bool>; 4],
+}
+
+impl Rules {
+    /// Creates rules for `tiles` tiles of equal weight that may not touch anything.
+    pub fn new(tiles: usize) -> Self {
+        Self {
+            weights: vec![1.0; tiles],
+            allowed: std::array::from_fn(|_| vec![false; tiles * tiles]),
+        }
+    }
+
+    /// Learns adjacencies and tile frequencies from a sample map.
+    pub fn from_sample(sample: &TileMap) -> Self {
+        let mut rules = Self::new(sample.palette().len());
+        rules.weights.fill(0.0);
+        for y in 0..sample.height() {
+            for x in 0..sample.width() {
+                let Some(tile) = sample.get(x, y) else {
+                    continue;
+                };
+                rules.weights[tile] += 1.0;
+                if let Some(right) = sample.get(x + 1, y) {
+                    rules.allow_right(tile, right);
+                }
+                if let Some(below) = sample.get(x, y + 1) {
+                    rules.allow_below(tile, below);
+                }
+            }
+        }
+        rules
+    }
+
+    pub fn tiles(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Lets tile `b` be placed to the right of tile `a`.
+    pub fn allow_right(&mut self, a: usize, b: usize) {
+        self.allow(0, a, b);
+    }
+
+    /// Lets tile `b` be placed below tile `a`.
+    pub fn allow_below(&mut self, a: usize, b: usize) {
+        self.allow(1, a, b);
+    }
+
+    /// Sets how often a tile is picked relative to the others; 0 never picks it.
+    pub fn set_weight(&mut self, tile: usize, weight: f32) {
+        if let Some(w) = self.weights.get_mut(tile) {
+            *w = weight.max(0.0);
+        }
+    }
+
+    fn allow(&mut self, direction: usize, a: usize, b: usize) {
+        let tiles = self.tiles();
+        if a < tiles && b < tiles {
+            self.allowed[direction][a * tiles + b] = true;
+            self.allowed[(direction + 2) % 4][b * tiles + a] = true;
+        }
+    }
+}
+
+/// Where a generator stands after a step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WfcStatus {
+    /// More steps are needed.
+    Running,
+    /// Every cell holds a tile.
+    Done,
+    /// Every attempt ran into a contradiction.
+    Failed,
+}
+
+/// An incremental wave function collapse generator.
+#[derive(Debug, Clone)]
+pub struct Wfc {
+    rules: Rules,
+    width: u16,
+    height: u16,
+    /// Which tiles each cell can still become
+    wave: Vec<Vec<bool>>,
+    collapsed: usize,
+    rng: Rng,
+    attempts: u32,
+    status: WfcStatus,
+}
+
+impl Wfc {
+    /// Prepares a `width` x `height` map; the same seed always generates the same map.
+    pub fn new(rules: Rules, width: u16, height: u16, seed: u64) -> Self {
+        let mut wfc = Self {
+            wave: Vec::new(),
+            collapsed: 0,
+            rules,
+            width,
+            height,
+            rng: Rng::new(seed),
+            attempts: 10,
+            status: WfcStatus::Running,
+        };
+        wfc.reset();
+        wfc
+    }
+
+    /// Sets how many times generation starts over after a contradiction.
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    pub fn status(&self) -> WfcStatus {
+        self.status
+    }
+
+    /// Returns the share of cells collapsed in the current attempt, from 0.0 to 1.0.
+    pub fn progress(&self) -> f32 {
+        self.collapsed as f32 / self.wave.len().max(1) as f32
+    }
+
+    /// Collapses up to `budget` cells.
+    pub fn step(&mut self, budget: usize) -> WfcStatus {
+        for _ in 0..budget {
+            if self.status != WfcStatus::Running {
+                break;
+            }
+            let Some(cell) = self.lowest_entropy() else {
+                self.status = WfcStatus::Done;
+                break;
+            };
+            if !self.collapse(cell) || !self.propagate(cell) {
+                self.attempts -= 1;
+                if self.attempts == 0 {
+                    self.status = WfcStatus::Failed;
+                } else {
+                    self.reset();
+                }
+            } else if self.collapsed == self.wave.len() {
+                self.status = WfcStatus::Done;
+            }
+        }
+        self.status
+    }
+
+    /// Returns the generated tiles, row by row, once generation is done.
+    pub fn tiles(&self) -> Option<Vec<usize>> {
+        if self.status != WfcStatus::Done {
+            return None;
+        }
+        Some(
+            self.wave
+                .iter()
+                .map(|options| options.iter().position(|o| *o).unwrap_or_default())
+                .collect(),
+        )
+    }
+
+    /// Returns the generated map drawn with `palette`, once generation is done.
+    pub fn to_tilemap(&self, palette: Vec<Tile>) -> Option<TileMap> {
+        let tiles = self.tiles()?;
+        let mut map = TileMap::new(self.width, self.height, palette);
+        for (index, tile) in tiles.into_iter().enumerate() {
+            let x = (index % self.width as usize) as u16;
+            let y = (index / self.width as usize) as u16;
+            map.set(x, y, tile);
+        }
+        Some(map)
+    }
+
+    fn reset(&mut self) {
+        let options: Vec<bool> = self.rules.weights.iter().map(|w| *w > 0.0).collect();
+        self.wave = vec![options; self.width as usize * self.height as usize];
+        self.collapsed = 0;
+    }
+
+    /// Picks an undecided cell with the fewest options, breaking ties at random.
+    fn lowest_entropy(&mut self) -> Option<usize> {
+        let mut best: Option<(f32, usize)> = None;
+        for (index, options) in self.wave.iter().enumerate() {
+            let count = options.iter().filter(|o| **o).count();
+            if count <= 1 {
+                continue;
+            }
+            let entropy = count as f32 + self.rng.next_f32() * 0.5;
+            if best.is_none_or(|(lowest, _)| entropy < lowest) {
+                best = Some((entropy, index));
+            }
+        }
+        best.map(|(_, index)| index)
+    }
+
+    /// Settles a cell on one of its options, returning `false` if it has none.
+    fn collapse(&mut self, cell: usize) -> bool {
+        let weights: Vec<f32> = self.wave[cell]
+            .iter()
+            .zip(&self.rules.weights)
+            .map(|(possible, weight)| if *possible { *weight } else { 0.0 })
+            .collect();
+        let Some(tile) = self.rng.weighted(&weights) else {
+            return false;
+        };
+        for (index, option) in self.wave[cell].iter_mut().enumerate() {
+            *option = index == tile;
+        }
+        true
+    }
+
+    /// Removes options that no longer fit their neighbors, returning `false`
+    /// if a cell runs out of options.
+    fn propagate(&mut self, start: usize) -> bool {
+        let tiles = self.rules.tiles();
+        let width = self.width as i32;
+        let mut stack = vec![start];
+        while let Some(cell) = stack.pop() {
+            let (x, y) = (cell as i32 % width, cell as i32 / width);
+            for (direction, (dx, dy)) in OFFSETS.iter().enumerate() {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= width || ny >= self.height as i32 {
+                    continue;
+                }
+                let neighbor = (ny * width + nx) as usize;
+                let allowed = &self.rules.allowed[direction];
+                let mut changed = false;
+                for b in 0..tiles {
+                    if !self.wave[neighbor][b] {
+                        continue;
+                    }
+                    let supported =
+                        (0..tiles).any(|a| self.wave[cell][a] && allowed[a * tiles + b]);
+                    if !supported {
+                        self.wave[neighbor][b] = false;
+                        changed = true;
+                    }
+                }
+                if changed {
+                    if !self.wave[neighbor].contains(&true) {
+                        return false;
+                    }
+                    stack.push(neighbor);
+                }
+            }
+        }
+        self.collapsed = self
+            .wave
+            .iter()
+            .filter(|options| options.iter().filter(|o| **o).count() == 1)
+            .count();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::Style;
+
+    fn palette() -> Vec<Tile> {
+        vec![
+            Tile::new('~', Style::new()),
+            Tile::new('.', Style::new()),
+            Tile::new('^', Style::new()),
+        ]
+    }
+
+    #[test]
+    fn test_generated_maps_follow_sample_adjacencies() {
+        // Water never touches mountains, and sand only follows water
+        let sample = TileMap::parse("~~..^^\n~~..^^\n~...^^\n", palette()).unwrap();
+        let rules = Rules::from_sample(&sample);
+        let generate = |seed| {
+            let mut wfc = Wfc::new(rules.clone(), 16, 8, seed);
+            let mut steps = 0;
+            while wfc.step(1) == WfcStatus::Running {
+                steps += 1;
+                assert!(wfc.progress() < 1.0);
+            }
+            assert_eq!(wfc.status(), WfcStatus::Done);
+            assert!(steps > 1, "generation should span several steps");
+            wfc.to_tilemap(palette()).unwrap()
+        };
+        let map = generate(3);
+        assert_eq!(map.to_string(), generate(3).to_string());
+        for y in 0..8 {
+            for x in 0..16 {
+                let tile = map.get(x, y).unwrap();
+                for (nx, ny) in [(x + 1, y), (x, y + 1)] {
+                    if let Some(neighbor) = map.get(nx, ny) {
+                        assert!(neighbor == tile || neighbor == tile + 1, "{}", map);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_impossible_rules_fail() {
+        // Tile 0 may only sit below tile 1 and vice versa, but nothing fits sideways
+        let mut rules = Rules::new(2);
+        rules.allow_below(0, 1);
+        rules.allow_below(1, 0);
+        let mut wfc = Wfc::new(rules, 2, 2, 1).with_attempts(3);
+        assert_eq!(wfc.step(100), WfcStatus::Failed);
+        assert!(wfc.tiles().is_none());
+    }
+}
@@ -1,5 +1,9 @@
-use coil_engine::{Game, config::Config, nodes::Node, renderer::Renderer};
-use crossterm::event::Event;
+use coil_engine::actions::KeyBinding;
+use coil_engine::{
+    Game, config::GameConfig, context::Context, errors::EngineError, frame::Frame, nodes::Node,
+    style,
+};
+use crossterm::event::{Event, KeyCode, KeyModifiers};
 
 struct MyGame {
     // Example state - you could have a state machine here
@@ -13,49 +17,41 @@ impl MyGame {
 }
 
 impl Node for MyGame {
-    fn update(&mut self, _delta_time: f32) {
+    fn update(&mut self, _ctx: &mut Context, _delta_time: f32) -> Result<(), EngineError> {
         self.frame_count += 1;
+        Ok(())
     }
 
-    fn on_event(&mut self, event: Event) -> bool {
-        // Handle input events
-        match event {
-            Event::Key(key_event) => {
-                use crossterm::event::{KeyCode, KeyModifiers};
-                match (key_event.code, key_event.modifiers) {
-                    (KeyCode::Char('c'), KeyModifiers::CONTROL) => true, // Exit on Ctrl+C
-                    (KeyCode::Esc, _) => true,                           // Exit on Escape
-                    _ => false,
-                }
-            }
-            _ => false,
-        }
+    fn on_event(&mut self, _ctx: &mut Context, _event: &Event) -> Result<bool, EngineError> {
+        // Esc and Ctrl+C are handled by the engine through the quit keys
+        Ok(false)
     }
 
-    fn render(&self, renderer: &mut dyn Renderer) {
-        renderer
-            .draw_str(
-                0,
-                0,
-                &format!("Frame: {}", self.frame_count),
-                crossterm::style::Color::Black,
-                crossterm::style::Color::White,
-            )
-            .unwrap();
-        renderer
-            .draw_str(
-                0,
-                1,
-                "Press Esc or Ctrl+C to exit",
-                crossterm::style::Color::Black,
-                crossterm::style::Color::White,
-            )
-            .unwrap();
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        let text_style = style!(black on white);
+        frame.draw_styled(0, 0, &format!("Frame: {}", self.frame_count), text_style)?;
+        frame.draw_styled(0, 1, "Press Esc or Ctrl+C to exit", text_style)?;
+        Ok(())
     }
 }
 
 fn main() {
-    Game::new(MyGame::new())
-        .add_config(Config::TargetFps(60))
+    let config = GameConfig::from_args().unwrap_or_else(|e| {
+        eprintln!("Invalid configuration: {}", e);
+        std::process::exit(2);
+    });
+
+    Game::builder()
+        .config(config)
+        .node(MyGame::new())
+        .quit_keys([
+            KeyBinding::from(KeyCode::Esc),
+            KeyBinding::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+        ])
+        .build()
+        .unwrap_or_else(|e| {
+            eprintln!("Invalid configuration: {}", e);
+            std::process::exit(2);
+        })
         .start();
 }
@@ -1,6 +1,8 @@
-use coil_engine::{Game, config::Config, nodes::Node, renderer::Renderer};
+use coil_engine::{
+    Game, config::GameConfig, context::Context, errors::EngineError, frame::Frame, nodes::Node,
+    style,
+};
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
-use crossterm::style::Color;
 
 struct EchoGame {
     message: String,
@@ -15,10 +17,12 @@ impl EchoGame {
 }
 
 impl Node for EchoGame {
-    fn update(&mut self, _delta_time: f32) {}
+    fn update(&mut self, _ctx: &mut Context, _delta_time: f32) -> Result<(), EngineError> {
+        Ok(())
+    }
 
-    fn on_event(&mut self, event: Event) -> bool {
-        match event {
+    fn on_event(&mut self, _ctx: &mut Context, event: &Event) -> Result<bool, EngineError> {
+        let exit = match event {
             Event::Key(KeyEvent {
                 code: KeyCode::Esc, ..
             }) => true,
@@ -32,20 +36,23 @@ impl Node for EchoGame {
                 false
             }
             _ => false, // Ignore other events
-        }
+        };
+        Ok(exit)
     }
 
-    fn render(&self, renderer: &mut dyn Renderer) {
-        renderer
-            .draw_str(0, 0, &self.message, Color::Black, Color::White)
-            .unwrap();
+    fn render(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        frame.draw_styled(0, 0, &self.message, style!(black on white))?;
+        Ok(())
     }
 }
 
 fn main() {
     env_logger::init();
 
-    Game::new(EchoGame::new())
-        .add_config(Config::TargetFps(60))
-        .start();
+    let config = GameConfig::from_args().unwrap_or_else(|e| {
+        eprintln!("Invalid configuration: {}", e);
+        std::process::exit(2);
+    });
+
+    Game::with_config(EchoGame::new(), config).start();
 }
@@ -1,166 +1,86 @@
 use coil_engine::{
     Game,
+    automaton::{CellularAutomaton, Edges, Neighborhood},
+    cell,
     config::GameConfig,
-    nodes::Node,
-    renderer::{Cell, Renderer},
+    context::{Context, EngineEvent},
+    errors::EngineError,
+    frame::Frame,
+    geometry::Rect,
+    hit::{Hit, HitId},
+    nodes::{Node, PauseOverlay},
+    renderer::Cell,
+    style,
 };
-use crossterm::event::{Event, KeyCode, KeyEvent, MouseEvent, MouseEventKind};
-use crossterm::style::Color;
+use crossterm::event::{Event, KeyCode, KeyEvent, MouseEventKind};
 use rand::Rng;
 
-const ALIVE_CELL: Cell = Cell {
-    ch: '█',
-    fg: Color::Green,
-    bg: Color::Reset,
-};
-
-const DEAD_CELL: Cell = Cell {
-    ch: ' ',
-    fg: Color::Reset,
-    bg: Color::Reset,
-};
-
-struct Grid {
-    width: u16,
-    height: u16,
-    cells: Vec<bool>,
-}
-
-impl Grid {
-    fn new(width: u16, height: u16) -> Self {
-        let mut rng = rand::rng();
-        let mut cells = vec![false; (width * height) as usize];
-        for cell in cells.iter_mut() {
-            *cell = rng.random_bool(0.1);
-        }
-        Grid {
-            width,
-            height,
-            cells,
-        }
-    }
-
-    fn get(&self, x: u16, y: u16) -> bool {
-        if x < self.width && y < self.height {
-            self.cells[(y * self.width + x) as usize]
-        } else {
-            false
-        }
-    }
-
-    fn coordinates(&self, index: usize) -> Option<(u16, u16)> {
-        if index < self.cells.len() {
-            Some((index as u16 % self.width, index as u16 / self.width))
-        } else {
-            None
-        }
-    }
-
-    fn get_neighbors(&self, x: u16, y: u16) -> usize {
-        let mut count = 0;
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                if (dx != 0 || dy != 0)
-                    && self.get((x as isize + dx) as u16, (y as isize + dy) as u16)
-                {
-                    count += 1;
-                }
-            }
-        }
-        count
-    }
-
-    fn set(&mut self, x: u16, y: u16, value: bool) {
-        if x < self.width && y < self.height {
-            self.cells[(y * self.width + x) as usize] = value;
-        }
-    }
-}
-
-struct PauseMenu {
-    width: u16,
-    height: u16,
-    paused: bool,
-}
-
-impl PauseMenu {
-    fn new(width: u16, height: u16) -> Self {
-        PauseMenu {
-            width,
-            height,
-            paused: false,
-        }
-    }
+const ALIVE_CELL: Cell = cell!('█', green);
 
-    fn is_paused(&self) -> bool {
-        self.paused
-    }
-}
-
-impl Node for PauseMenu {
-    fn update(&mut self, _delta_time: f32) {}
-
-    fn on_event(&mut self, event: Event) -> bool {
-        match event {
-            Event::Key(KeyEvent {
-                code: KeyCode::Char(' '),
-                ..
-            }) => {
-                self.paused = !self.paused; // Toggle pause state
-                false // Do not exit
-            }
-            _ => false, // Ignore other events
-        }
-    }
-
-    fn render(&self, renderer: &mut dyn Renderer) {
-        if self.paused {
-            let pause_text = "Game Paused. Press Space to Resume.";
-            let x = (self.width / 2) - (pause_text.len() as u16 / 2);
-            let y = self.height / 2;
+const DEAD_CELL: Cell = Cell::BLANK;
 
-            renderer
-                .draw_str(x, y, pause_text, Color::Reset, Color::DarkBlue)
-                .unwrap();
-        }
-    }
+/// Creates a grid where about one cell in ten is alive, dead beyond its edges.
+fn random_grid(width: u16, height: u16) -> CellularAutomaton<bool> {
+    let mut rng = rand::rng();
+    CellularAutomaton::from_fn(width, height, |_, _| rng.random_bool(0.1))
+        .with_edges(Edges::Value(false))
 }
 
+#[derive(Node)]
+#[node(
+    update = Self::step,
+    on_event = Self::handle_event,
+    on_engine_event = Self::handle_engine_event,
+    on_resize = Self::resize,
+    render = Self::draw
+)]
 struct GameOfLife {
-    pub grid: Grid,
-    pause_menu: PauseMenu,
+    pub grid: CellularAutomaton<bool>,
+    grid_id: HitId,
+    #[node(child)]
+    pause_overlay: PauseOverlay,
 }
 
 impl GameOfLife {
     fn new(width: u16, height: u16) -> Self {
         GameOfLife {
-            grid: Grid::new(width, height),
-            pause_menu: PauseMenu::new(width, height),
+            grid: random_grid(width, height),
+            grid_id: HitId::new(),
+            pause_overlay: PauseOverlay::new()
+                .with_text("Game Paused. Press Space to Resume.")
+                .with_style(style!(on dark_blue)),
         }
     }
-}
 
-impl Node for GameOfLife {
-    fn update(&mut self, _delta_time: f32) {
-        if self.pause_menu.is_paused() {
-            return; // Skip update if paused
-        }
-        for idx in 0..self.grid.cells.len() {
-            let (x, y) = self.grid.coordinates(idx).unwrap();
-            let neighbors = self.grid.get_neighbors(x, y);
-            let alive = self.grid.get(x, y);
-            let new_state = match (alive, neighbors) {
+    fn step(&mut self, _ctx: &mut Context, _delta_time: f32) -> Result<(), EngineError> {
+        self.grid.step(|cell: &Neighborhood<'_, bool>| {
+            let neighbors = cell.moore().filter(|alive| **alive).count();
+            match (*cell.get(), neighbors) {
                 (true, 2) | (true, 3) => true, // Stay alive
                 (false, 3) => true,            // Become alive
                 _ => false,                    // Die or stay dead
-            };
-            self.grid.set(x, y, new_state);
-        }
+            }
+        });
+        Ok(())
+    }
+
+    /// Fits the grid to the new screen, keeping the cells still on it and
+    /// seeding the new area.
+    fn resize(&mut self, _ctx: &mut Context, width: u16, height: u16) {
+        let old = &self.grid;
+        let mut rng = rand::rng();
+        self.grid = CellularAutomaton::from_fn(width, height, |x, y| {
+            if x < old.width() && y < old.height() {
+                *old.get(x, y)
+            } else {
+                rng.random_bool(0.1)
+            }
+        })
+        .with_edges(Edges::Value(false));
     }
 
-    fn on_event(&mut self, event: Event) -> bool {
-        self.pause_menu.on_event(event.clone());
-        match event {
+    fn handle_event(&mut self, ctx: &mut Context, event: &Event) -> Result<bool, EngineError> {
+        let exit = match event {
             Event::Key(KeyEvent {
                 code: KeyCode::Esc, ..
             }) => true, // Exit on Esc key
@@ -169,42 +89,70 @@ impl Node for GameOfLife {
                 modifiers: crossterm::event::KeyModifiers::CONTROL,
                 ..
             }) => true, // Exit on Ctrl+C
-            Event::Mouse(MouseEvent {
-                kind: MouseEventKind::Down(_),
-                column,
-                row,
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('+'),
                 ..
             }) => {
-                let (x, y) = (column, row);
-                if x < self.grid.width && y < self.grid.height {
-                    let current_state = self.grid.get(x, y);
-                    self.grid.set(x, y, !current_state);
-                }
+                let fps = ctx.config().target_fps;
+                ctx.set_target_fps((fps + 5).min(60)); // Speed up
+                false
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('-'),
+                ..
+            }) => {
+                let fps = ctx.config().target_fps;
+                ctx.set_target_fps(fps.saturating_sub(5).max(5)); // Slow down
                 false
             }
             _ => false, // Ignore other events
+        };
+        Ok(exit)
+    }
+
+    fn handle_engine_event(&mut self, _ctx: &mut Context, event: &EngineEvent) {
+        // Clicks on the grid arrive with grid coordinates
+        if let EngineEvent::Hit(Hit { id, x, y, event }) = *event
+            && id == self.grid_id
+            && matches!(event.kind, MouseEventKind::Down(_))
+        {
+            let current_state = *self.grid.get(x, y);
+            self.grid.set(x, y, !current_state);
         }
     }
 
-    fn render(&self, renderer: &mut dyn Renderer) {
-        for y in 0..self.grid.height {
-            for x in 0..self.grid.width {
-                if self.grid.get(x, y) {
-                    renderer.draw_cell(x, y, ALIVE_CELL).unwrap();
+    fn draw(&self, frame: &mut Frame<'_>) -> Result<(), EngineError> {
+        frame.hit_region(
+            self.grid_id,
+            Rect::new(0, 0, self.grid.width(), self.grid.height()),
+        );
+        for y in 0..self.grid.height() {
+            for x in 0..self.grid.width() {
+                if *self.grid.get(x, y) {
+                    frame.draw_cell(x, y, ALIVE_CELL)?;
                 } else {
-                    renderer.draw_cell(x, y, DEAD_CELL).unwrap();
+                    frame.draw_cell(x, y, DEAD_CELL)?;
                 }
             }
         }
-        self.pause_menu.render(renderer);
+        Ok(())
     }
 }
 
 fn main() {
     let config = GameConfig {
         target_fps: 10,
+        pause_key: Some(KeyCode::Char(' ').into()),
         ..Default::default()
-    };
-    let (width, height) = config.screen_size;
+    }
+    .with_overrides()
+    .unwrap_or_else(|e| {
+        eprintln!("Invalid configuration: {}", e);
+        std::process::exit(2);
+    });
+    let (width, height) = config.viewport().map(|v| v.size()).unwrap_or_else(|e| {
+        eprintln!("Error running game: {}", e);
+        std::process::exit(1);
+    });
     Game::with_config(GameOfLife::new(width, height), config).start();
 }